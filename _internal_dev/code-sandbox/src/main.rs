@@ -1,15 +1,13 @@
-use std::process::{Command, Stdio};
-use std::io::{Write, Read};
-use std::time::{Instant, Duration};
-use std::sync::mpsc;
-use std::thread;
+use std::collections::HashMap;
+use std::time::Duration;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::env;
 
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Reply, Rejection};
-use anyhow::{Result, Context};
-use tracing::{info, error};
+use anyhow::Result;
+use tracing::info;
+use synthesis::runtime::{artifact_document, ArtifactOptions, SandboxLimits, SandboxedEngine, Value};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ExecutionRequest {
@@ -24,7 +22,8 @@ struct ExecutionResponse {
     error: Option<String>,
     execution_time: u128,
     memory_used: usize,
-    visualization: Option<String>,
+    /// Frames, audio and metrics in the `synthesis-artifacts` JSON format
+    artifacts: Option<serde_json::Value>,
 }
 
 fn limit_code_complexity(code: &str) -> Result<()> {
@@ -32,116 +31,61 @@ fn limit_code_complexity(code: &str) -> Result<()> {
     anyhow::ensure!(code.len() <= 10000, "Code too long");
     anyhow::ensure!(code.lines().count() <= 500, "Too many lines");
     
-    // Prevent potentially dangerous operations
-    let dangerous_patterns = [
-        "unsafe", 
-        "std::fs", 
-        "std::env", 
-        "std::net", 
-        "std::process",
-        "system(",
-        "exec(",
-        "shell(",
-    ];
-    
-    for pattern in &dangerous_patterns {
-        anyhow::ensure!(!code.contains(pattern), "Potentially unsafe code detected");
-    }
+    // Network, camera, microphone and file access are refused by the
+    // interpreter itself (`--sandbox`), so no source pattern checks here
     
     Ok(())
 }
 
-fn execute_synthesis_code(request: ExecutionRequest) -> Result<ExecutionResponse> {
-    // Validate code complexity and safety
+fn sandbox_limits(config: &SandboxConfig) -> SandboxLimits {
+    SandboxLimits {
+        deadline: Duration::from_secs(config.max_execution_time),
+        memory_quota: config.max_memory * 1024 * 1024,
+        ..SandboxLimits::default()
+    }
+}
+
+fn execute_synthesis_code(request: ExecutionRequest, limits: SandboxLimits) -> Result<ExecutionResponse> {
+    // Validate code complexity
     limit_code_complexity(&request.code)?;
     
-    // Create a temporary file for code execution
-    let temp_file = tempfile::NamedTempFile::new()?;
-    let file_path = temp_file.path().to_str().context("Invalid file path")?;
-    
-    // Write code to temporary file
-    std::fs::write(file_path, &request.code)?;
-    
-    // Set up timeout and output capture
-    let (tx, rx) = mpsc::channel();
-    
-    let start_time = Instant::now();
-    
-    let handler = thread::spawn(move || {
-        let output = Command::new("synthesis")
-            .arg("run")
-            .arg(file_path)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .expect("Failed to execute code");
-        
-        tx.send(output).expect("Could not send output");
-    });
-    
-    // Wait with timeout
-    let result = match rx.recv_timeout(Duration::from_secs(5)) {
-        Ok(output) => {
-            let execution_time = start_time.elapsed().as_millis();
-            
-            // Process output
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            
-            // Determine response based on exit status
-            if output.status.success() {
-                Ok(ExecutionResponse {
-                    output: stdout,
-                    error: if !stderr.is_empty() { Some(stderr) } else { None },
-                    execution_time,
-                    memory_used: get_memory_usage(),
-                    visualization: extract_visualization(&stdout),
-                })
-            } else {
-                Err(anyhow::anyhow!(
-                    "Execution failed: {}{}",
-                    stdout,
-                    stderr
-                ))
-            }
-        },
-        Err(_) => {
-            // Timeout occurred
-            Err(anyhow::anyhow!("Code execution timed out"))
-        }
-    };
+    // Runs in-process: instruction, time and memory limits, no devices or files
+    let report = SandboxedEngine::new(limits).run(&request.code);
     
-    // Ensure thread is joined
-    handler.join().expect("Thread panicked");
+    if let Some(error) = &report.error {
+        anyhow::bail!("Execution failed: {}", error);
+    }
     
-    result
+    Ok(ExecutionResponse {
+        output: format_variables(&report.variables),
+        error: None,
+        execution_time: report.elapsed.as_millis(),
+        memory_used: report.peak_memory / 1024,
+        artifacts: Some(artifact_document(&report, &ArtifactOptions::default())),
+    })
 }
 
-fn get_memory_usage() -> usize {
-    // Placeholder for actual memory usage tracking
-    // In a real implementation, use system-specific memory tracking
-    42 // KB
-}
-
-fn extract_visualization(output: &str) -> Option<String> {
-    // Extract visualization URL or data from output
-    // This is a placeholder - actual implementation would parse output
-    if output.contains("VISUALIZATION:") {
-        Some(output.split("VISUALIZATION:").nth(1)?.trim().to_string())
-    } else {
-        None
-    }
+fn format_variables(variables: &HashMap<String, Value>) -> String {
+    let mut names: Vec<_> = variables.keys().collect();
+    names.sort();
+    names.iter()
+        .map(|name| format!("{} = {}\n", name, variables[*name]))
+        .collect()
 }
 
 async fn handle_execution(request: ExecutionRequest) -> Result<impl Reply, Rejection> {
-    match execute_synthesis_code(request) {
+    let limits = sandbox_limits(&get_config());
+    let result = tokio::task::spawn_blocking(move || execute_synthesis_code(request, limits))
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!("Execution worker failed: {}", e)));
+    match result {
         Ok(response) => Ok(warp::reply::json(&response)),
         Err(e) => Ok(warp::reply::json(&ExecutionResponse {
             output: String::new(),
             error: Some(e.to_string()),
             execution_time: 0,
             memory_used: 0,
-            visualization: None,
+            artifacts: None,
         }))
     }
 }
@@ -233,24 +177,38 @@ mod tests {
     fn test_code_complexity_limits() {
         let long_code = "x".repeat(20000);
         assert!(limit_code_complexity(&long_code).is_err());
+    }
+    
+    #[test]
+    fn test_device_access_is_refused() {
+        let result = execute_synthesis_code(ExecutionRequest {
+            code: "audio = Audio.mic_input()".to_string(),
+            module: "test".to_string(),
+            example: "microphone".to_string(),
+        }, SandboxLimits::default());
         
-        let unsafe_code = "import std::fs;";
-        assert!(limit_code_complexity(unsafe_code).is_err());
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("microphone"));
     }
     
     #[test]
     fn test_execution_timeout() {
         let infinite_loop = r#"
-        loop {
-            // Intentional infinite loop
+        while true {
+            x = 1
         }
         "#.to_string();
         
+        let limits = SandboxLimits {
+            deadline: Duration::from_millis(200),
+            max_instructions: u64::MAX,
+            ..SandboxLimits::default()
+        };
         let result = execute_synthesis_code(ExecutionRequest {
             code: infinite_loop,
             module: "test".to_string(),
             example: "timeout".to_string(),
-        });
+        }, limits);
         
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("timeout"));
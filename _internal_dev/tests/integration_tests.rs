@@ -55,7 +55,7 @@ fn test_audio_visualizer_workflow() {
         Graphics.clear(Graphics.black)
         
         // Create visualizations based on frequency data
-        Graphics.plasma(speed: bass, intensity: mid, color: treble)
+        Graphics.plasma(speed: bass, intensity: mid + treble)
     "#;
     
     let (_, tokens) = tokenize(input).unwrap();
@@ -168,7 +168,7 @@ fn test_array_operations() {
         energy = spectrum[0] + spectrum[1] + spectrum[2]
         
         // Test array access in function calls
-        Graphics.plasma(bass: spectrum[0], mid: spectrum[15], treble: spectrum[31])
+        Graphics.plasma(speed: spectrum[0], intensity: spectrum[15] + spectrum[31])
     "#;
     
     let (_, tokens) = tokenize(input).unwrap();
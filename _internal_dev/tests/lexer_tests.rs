@@ -116,4 +116,90 @@ fn test_function_call_with_named_args() {
     assert_eq!(tokens[9], Token::Colon);
     assert_eq!(tokens[10], Token::Identifier("red".to_string()));
     assert_eq!(tokens[11], Token::RightParen);
-}
\ No newline at end of file
+}
+#[test]
+fn test_string_escapes() {
+    use synthesis::parser::ast::{Expression, StringPart};
+
+    let input = r#""line one\nline two\t\"quoted\" \\ \u{1F3B5} \${not} $5" "${name}\u{e9}""#;
+    let (rest, tokens) = tokenize(input).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(tokens, vec![
+        Token::String("line one\nline two\t\"quoted\" \\ 🎵 ${not} $5".to_string()),
+        Token::InterpolatedString(vec![
+            StringPart::Interpolation(Expression::Identifier("name".to_string())),
+            StringPart::Text("é".to_string()),
+        ]),
+    ]);
+
+    assert!(tokenize(r#""bad \q escape""#).is_err());
+    assert!(tokenize(r#""\u{110000}""#).is_err());
+}
+#[test]
+fn test_raw_and_multiline_strings() {
+    use synthesis::parser::ast::{Expression, StringPart};
+
+    let input = "r\"C:\\shaders\\${glow}.frag\" r#\"say \"hi\"\"# shader = \"\"\"\n    void main() {\n        gl_FragColor = vec4(\"${hue}\", 1.0);\n    }\n    \"\"\"";
+    let (rest, tokens) = tokenize(input).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(tokens[0], Token::String("C:\\shaders\\${glow}.frag".to_string()));
+    assert_eq!(tokens[1], Token::String("say \"hi\"".to_string()));
+    assert_eq!(tokens[4], Token::InterpolatedString(vec![
+        StringPart::Text("void main() {\n    gl_FragColor = vec4(\"".to_string()),
+        StringPart::Interpolation(Expression::Identifier("hue".to_string())),
+        StringPart::Text("\", 1.0);\n}".to_string()),
+    ]));
+
+    // Raw multiline strings are dedented too, and `r` alone is still a name
+    let (_, tokens) = tokenize("r\"\"\"\n  /light/1\n  /light/\\d\n  \"\"\" r").unwrap();
+    assert_eq!(tokens, vec![Token::String("/light/1\n/light/\\d".to_string()), Token::Identifier("r".to_string())]);
+
+    assert!(tokenize("\"\"\"never closed").is_err());
+    assert!(tokenize("r#\"never closed\"").is_err());
+}
+
+#[test]
+fn test_overflowing_hex_and_binary_literals() {
+    use synthesis::parser::lexer::{literal_error, tokenize_with_spans};
+
+    // The largest ones still fit
+    let (_, tokens) = tokenize("0x7FFFFFFFFFFFFFFF 0b111").unwrap();
+    assert_eq!(tokens, vec![Token::BasedInteger(i64::MAX, 16), Token::BasedInteger(7, 2)]);
+
+    let binary = format!("0b1{}", "0".repeat(64));
+    for (source, literal, column) in [
+        ("x = 0xFFFFFFFFFFFFFFFFFF".to_string(), "0xFFFFFFFFFFFFFFFFFF", 5),
+        (format!("y = 1\nz = {} + 1", binary), binary.as_str(), 5),
+    ] {
+        // Not a 0 followed by a name: the whole number is refused
+        let error = tokenize_with_spans(&source).unwrap_err();
+        assert!(matches!(error, nom::Err::Failure(_)), "{}: {:?}", source, error);
+        let error = literal_error(&source, &error, "song.syn").expect("an overflowing literal should get its own error");
+        assert!(error.message.contains(&format!("{} is too large", literal)), "{}", error.message);
+        let location = error.location.unwrap();
+        assert_eq!((location.line, location.column), (source.lines().count(), column));
+    }
+}
+
+#[test]
+fn test_colours_only_where_a_value_is_expected() {
+    // A comment that happens to spell hex is still a comment at the start of a line or after a value
+    let (_, tokens) = tokenize("#facade comment\nx = #c0ffee #deadbeef\ny = 1 #c0ffee").unwrap();
+    assert_eq!(tokens, vec![
+        Token::Identifier("x".to_string()),
+        Token::Assignment,
+        Token::Color([0xc0, 0xff, 0xee, 0xff]),
+        Token::Identifier("y".to_string()),
+        Token::Assignment,
+        Token::Integer(1),
+    ]);
+
+    // After `(`, `,`, `:` and `[` it is a colour, and so is a `match` arm's pattern
+    let (_, tokens) = tokenize("circle(1, color: #ff0000) [#00ff0080]\nmatch c {\n    #0000ff => { }\n}").unwrap();
+    let colors: Vec<_> = tokens.iter().filter(|token| matches!(token, Token::Color(_))).collect();
+    assert_eq!(colors, vec![
+        &Token::Color([0xff, 0, 0, 0xff]),
+        &Token::Color([0, 0xff, 0, 0x80]),
+        &Token::Color([0, 0, 0xff, 0xff]),
+    ]);
+}
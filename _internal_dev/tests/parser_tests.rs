@@ -43,7 +43,7 @@ fn test_function_call() {
     
     assert_eq!(program.items.len(), 1);
     match &program.items[0] {
-        Item::Statement(Statement::Expression(Expression::FunctionCall { module, name, args })) => {
+        Item::Statement(Statement::Expression(Expression::FunctionCall { module, name, args, .. })) => {
             assert_eq!(module, &Some("Audio".to_string()));
             assert_eq!(name, "mic_input");
             assert_eq!(args.len(), 0);
@@ -61,7 +61,7 @@ fn test_function_call_with_args() {
     
     assert_eq!(program.items.len(), 1);
     match &program.items[0] {
-        Item::Statement(Statement::Expression(Expression::FunctionCall { module, name, args })) => {
+        Item::Statement(Statement::Expression(Expression::FunctionCall { module, name, args, .. })) => {
             assert_eq!(module, &Some("Audio".to_string()));
             assert_eq!(name, "analyze_fft");
             assert_eq!(args.len(), 2);
@@ -98,19 +98,14 @@ fn test_named_arguments() {
     
     assert_eq!(program.items.len(), 1);
     match &program.items[0] {
-        Item::Statement(Statement::Expression(Expression::FunctionCall { module, name, args })) => {
+        Item::Statement(Statement::Expression(Expression::FunctionCall { module, name, args, named_args })) => {
             assert_eq!(module, &Some("Graphics".to_string()));
             assert_eq!(name, "plasma");
-            assert_eq!(args.len(), 1);
+            assert!(args.is_empty());
             
-            // Named argument should be parsed as a block
-            match &args[0] {
-                Expression::Block { fields } => {
-                    assert_eq!(fields.len(), 1);
-                    assert!(fields.contains_key("speed"));
-                }
-                _ => panic!("Expected block expression for named argument"),
-            }
+            // Named arguments are kept apart from the positional ones
+            assert_eq!(named_args.len(), 1);
+            assert!(named_args.contains_key("speed"));
         }
         _ => panic!("Expected function call expression"),
     }
@@ -187,4 +182,606 @@ fn test_binary_operations() {
         }
         _ => panic!("Expected assignment statement"),
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_unary_operators() {
+    let input = "x = -3\ny = !a && not b\nz = a - -b";
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    
+    let values: Vec<&Expression> = program.items.iter()
+        .map(|item| match item {
+            Item::Statement(Statement::Assignment { value, .. }) => value,
+            _ => panic!("Expected assignment statement"),
+        })
+        .collect();
+    assert_eq!(*values[0], Expression::Literal(Literal::Integer(-3)));
+    let not = |name: &str| Expression::UnaryOp {
+        op: UnaryOperator::Not,
+        operand: Box::new(Expression::Identifier(name.to_string())),
+    };
+    assert_eq!(*values[1], Expression::BinaryOp {
+        left: Box::new(not("a")),
+        op: BinaryOperator::LogicalAnd,
+        right: Box::new(not("b")),
+    });
+    assert_eq!(*values[2], Expression::BinaryOp {
+        left: Box::new(Expression::Identifier("a".to_string())),
+        op: BinaryOperator::Subtract,
+        right: Box::new(Expression::UnaryOp {
+            op: UnaryOperator::Negate,
+            operand: Box::new(Expression::Identifier("b".to_string())),
+        }),
+    });
+}
+
+#[test]
+fn test_optional_chaining_and_null_coalescing() {
+    let input = "tempo = beat?.bpm ?? 120\nready = a || b ?? false";
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    
+    let values: Vec<&Expression> = program.items.iter()
+        .map(|item| match item {
+            Item::Statement(Statement::Assignment { value, .. }) => value,
+            _ => panic!("Expected assignment statement"),
+        })
+        .collect();
+    let identifier = |name: &str| Box::new(Expression::Identifier(name.to_string()));
+    assert_eq!(*values[0], Expression::BinaryOp {
+        left: Box::new(Expression::MethodCall {
+            object: identifier("beat"),
+            method: "bpm".to_string(),
+            args: Vec::new(),
+            named_args: std::collections::HashMap::new(),
+            optional: true,
+        }),
+        op: BinaryOperator::NullCoalesce,
+        right: Box::new(Expression::Literal(Literal::Integer(120))),
+    });
+    assert_eq!(*values[1], Expression::BinaryOp {
+        left: Box::new(Expression::BinaryOp {
+            left: identifier("a"),
+            op: BinaryOperator::LogicalOr,
+            right: identifier("b"),
+        }),
+        op: BinaryOperator::NullCoalesce,
+        right: Box::new(Expression::Literal(Literal::Boolean(false))),
+    });
+    
+    let (_, tokens) = tokenize("x = beat?.").unwrap();
+    assert!(Parser::new(&tokens).parse().is_err());
+}
+
+#[test]
+fn test_let_destructuring() {
+    let input = "let [a, _, c] = bands\nlet {left, right: r} = Audio.split(stereo)";
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    
+    let patterns: Vec<&Destructure> = program.items.iter()
+        .map(|item| match item {
+            Item::Statement(Statement::LetDestructure { pattern, .. }) => pattern,
+            _ => panic!("Expected destructuring let"),
+        })
+        .collect();
+    assert_eq!(*patterns[0], Destructure::Array(vec![Some("a".to_string()), None, Some("c".to_string())]));
+    assert_eq!(*patterns[1], Destructure::Object(vec![
+        ("left".to_string(), "left".to_string()),
+        ("right".to_string(), "r".to_string()),
+    ]));
+    
+    let (_, tokens) = tokenize("let [a, b]").unwrap();
+    assert!(Parser::new(&tokens).parse().is_err());
+}
+
+#[test]
+fn test_spread_in_lists_and_calls() {
+    let input = "all = [...bass_notes, 72]\nGraphics.rect(...params)";
+    let (_, tokens) = tokenize(input).unwrap();
+    assert!(tokens.contains(&synthesis::parser::lexer::Token::Spread));
+    let program = Parser::new(&tokens).parse().unwrap();
+    
+    let spread = |name: &str| Expression::Spread(Box::new(Expression::Identifier(name.to_string())));
+    match &program.items[0] {
+        Item::Statement(Statement::Assignment { value, .. }) => assert_eq!(*value, Expression::ArrayLiteral(vec![
+            spread("bass_notes"),
+            Expression::Literal(Literal::Integer(72)),
+        ])),
+        other => panic!("Expected assignment, got {:?}", other),
+    }
+    match &program.items[1] {
+        Item::Statement(Statement::Expression(Expression::FunctionCall { args, .. })) => assert_eq!(*args, vec![spread("params")]),
+        other => panic!("Expected function call, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_match_patterns_and_guards() {
+    let input = r#"
+match event {
+    [low, _, ...] if low > 0.5 => { }
+    { kind: "note", velocity } => { }
+    -1..=1 => { }
+}
+"#;
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    
+    let Item::Statement(Statement::Match { arms, .. }) = &program.items[0] else { panic!("Expected match statement") };
+    assert_eq!(arms[0].pattern, Pattern::Array {
+        items: vec![Pattern::Binding("low".to_string()), Pattern::Wildcard],
+        rest: true,
+    });
+    assert!(matches!(arms[0].guard, Some(Expression::BinaryOp { op: BinaryOperator::GreaterThan, .. })));
+    assert_eq!(arms[1].pattern, Pattern::Object(vec![
+        ("kind".to_string(), Pattern::Literal(Literal::String("note".to_string()))),
+        ("velocity".to_string(), Pattern::Binding("velocity".to_string())),
+    ]));
+    assert_eq!(arms[2].pattern, Pattern::Range { start: Literal::Integer(-1), end: Literal::Integer(1), inclusive: true });
+    assert_eq!(arms[2].guard, None);
+}
+
+#[test]
+fn test_const_declarations() {
+    use synthesis::parser::lexer::{tokenize_with_spans, Span};
+    
+    let (_, (tokens, spans)) = tokenize_with_spans("x = 1\nconst SAMPLE_RATE = 48000\nconstant = 1").unwrap();
+    let program = Parser::with_spans(&tokens, &spans, "consts.syn").parse().unwrap();
+    assert_eq!(program.items[1], Item::Statement(Statement::Const {
+        name: "SAMPLE_RATE".to_string(),
+        value: Expression::Literal(Literal::Integer(48000)),
+        span: Span { line: 2, column: 1 },
+    }));
+    assert!(matches!(&program.items[2], Item::Statement(Statement::Assignment { name, .. }) if name == "constant"));
+    
+    let (_, tokens) = tokenize("const SAMPLE_RATE").unwrap();
+    assert!(Parser::new(&tokens).parse().is_err());
+}
+
+#[test]
+fn test_type_annotations_are_checked() {
+    use synthesis::parser::lexer::tokenize_with_spans;
+    use synthesis::parser::{check_types, TypeChecker};
+    use synthesis::errors::ErrorKind;
+
+    let input = "struct Point { x: Float }\nfunc half(x) -> Float { return x / 2 }\nlet a: Number = 3\nlet b: Float = 2\nlet c: Text = \"hi\" + \"!\"\nlet d: List = [1, 2]\nlet p: Point = Point { x: 1.0 }\nlet e: Number = half(a)\nlet f: Frequency = Audio.pitch()\nlet g: Integer = 7 / 2\nloop {\n    let h: Text = a * 2\n}";
+    let (_, (tokens, spans)) = tokenize_with_spans(input).unwrap();
+    let program = Parser::with_spans(&tokens, &spans, "song.syn").parse().unwrap();
+    let mismatches = TypeChecker::with_spans(&tokens, &spans, "song.syn").check(&program);
+
+    assert_eq!(mismatches.len(), 2, "{:?}", mismatches);
+    assert!(mismatches.iter().all(|error| matches!(error.kind, ErrorKind::TypeMismatch)));
+    assert!(mismatches[0].message.contains("'g' is declared as Integer, but it's given Float"));
+    let location = mismatches[1].location.as_ref().expect("mismatches should point at their let");
+    assert_eq!((location.line, location.column, location.filename.as_str()), (12, 5, "song.syn"));
+    assert!(mismatches[1].message.contains("given Number"));
+
+    // Without spans the mistakes are still found, just without a place to point at
+    let mismatches = check_types(&program);
+    assert_eq!(mismatches.len(), 2);
+    assert!(mismatches[0].location.is_none());
+
+    let (_, tokens) = tokenize("let names: List = [\"kick\", 3]\nlet wrong: Boolean = 440.Hz").unwrap();
+    let mismatches = check_types(&Parser::new(&tokens).parse().unwrap());
+    assert_eq!(mismatches.len(), 1);
+    assert!(mismatches[0].message.contains("'wrong'"));
+}
+
+#[test]
+fn test_types_are_inferred_across_variables_functions_and_pipes() {
+    use synthesis::parser::{infer_types, InferredType};
+    use synthesis::runtime::{CreativeType, CreativeTypeSystem, FrequencyType};
+    use synthesis::runtime::Value;
+
+    let input = "func tone(pitch, length = 1) {\n    return Signal.sine(pitch, length)\n}\nmic = Audio.mic_input()\nbands = mic |> Audio.analyze_fft(16)\nbright = Audio.spectral_centroid(mic)\nwet = mic |> Audio.apply_reverb(room_size: 80%)\nroot = \"A4\"\nlow = tone(root)\nbeep = tone(880)\nmood = \"calm\"\nmood = 0.5\nbroken = Audio.bandpass(bands, 200, 2000)";
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    let inference = infer_types(&program);
+
+    assert_eq!(inference.variables["mic"], InferredType::Audio);
+    assert_eq!(inference.variables["bands"], InferredType::List(Box::new(InferredType::Number)));
+    assert_eq!(inference.variables["bright"], InferredType::Frequency);
+    assert_eq!(inference.variables["wet"], InferredType::Audio);
+    assert_eq!(inference.variables["root"], InferredType::Note);
+    // A variable given two kinds of value isn't tracked
+    assert_eq!(inference.variables["mood"], InferredType::Unknown);
+    // The parameter that reaches Signal.sine() is a frequency
+    assert_eq!(inference.functions["tone"].to_string(), "(Frequency, Number) -> List<Number>");
+
+    let rules: Vec<(&str, &str)> = inference.coercions.iter().map(|c| (c.site.as_str(), c.rule.as_str())).collect();
+    assert_eq!(rules, vec![("Audio.apply_reverb(room_size:)", "percentage_to_normalized"), ("tone() argument 1", "note_to_frequency")]);
+    assert_eq!(inference.conflicts.len(), 1, "{}", inference.report());
+    assert!(inference.conflicts[0].message.contains("Audio.bandpass() input expects Audio stream, but it's given List<Number>"));
+    assert!(inference.report().contains("bright: Frequency"));
+
+    // The type system coerces by what inference found, not by guessing from the value
+    let mut system = CreativeTypeSystem::new();
+    system.learn(&inference);
+    assert_eq!(system.variable_types.get("bright"), Some(&CreativeType::Frequency(FrequencyType::Hertz)));
+    let hertz = system.coerce_variable("root", &Value::String("A4".to_string()), &CreativeType::Frequency(FrequencyType::Hertz), None).unwrap();
+    assert!((hertz.as_number().unwrap() - 440.0).abs() < 0.01);
+}
+
+#[test]
+fn test_generic_functions_take_type_parameters() {
+    use synthesis::parser::{check_types, infer_types, InferredType};
+
+    let input = "func first<T>(items: List<T>) -> T {\n    return items[0]\n}\nfunc pair<T>(a: T, b: T) -> List<T> {\n    return [a, b]\n}\nfunc smooth<S>(signal: S, amount: Number) -> S {\n    return signal\n}\nlet a: Integer = first([1, 2])\nlet b: Text = first([1, 2])\nlet c: List<Float> = pair(1, 2.5)\nd = pair(1, \"x\")\nmic = Audio.mic_input()\nheard = smooth(mic, 0.5)\nbright = smooth(Audio.spectral_centroid(mic), 0.9)";
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+
+    let Item::Function(first) = &program.items[0] else { panic!("expected a function") };
+    assert_eq!(first.type_params, vec!["T".to_string()]);
+    assert_eq!(first.parameters[0].type_annotation, Some(TypeAnnotation::Generic {
+        base: "List".to_string(),
+        params: vec![TypeAnnotation::Simple("T".to_string())],
+    }));
+    assert_eq!(first.return_type, Some(TypeAnnotation::Simple("T".to_string())));
+
+    // Each call works out T from its own arguments
+    let errors = check_types(&program);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].message.contains("'b' is declared as Text, but it's given Integer"), "{}", errors[0].message);
+    assert!(errors[1].message.contains("pair() needs the same type everywhere it uses T, but it's given Integer and Text"), "{}", errors[1].message);
+
+    // The same smoothing function serves audio and control values
+    let inference = infer_types(&program);
+    assert_eq!(inference.variables["heard"], InferredType::Audio);
+    assert_eq!(inference.variables["bright"], InferredType::Frequency);
+
+    for bad in ["func pick<T, T>(a: T) { return a }", "func pick<>(a) { return a }", "let x: List<Number = 3"] {
+        let (_, tokens) = tokenize(bad).unwrap();
+        assert!(Parser::new(&tokens).parse().is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn test_parse_try_catch() {
+    for input in [
+        "try { x = 1 } catch (err) { x = 0 }",
+        "try { x = 1 } catch err { x = 0 }",
+        "try { x = 1 } catch { x = 0 }",
+    ] {
+        let (_, tokens) = tokenize(input).unwrap();
+        let program = Parser::new(&tokens).parse().unwrap();
+        match &program.items[0] {
+            Item::Statement(Statement::Try { body, error, handler }) => {
+                assert_eq!(body.len(), 1);
+                assert_eq!(handler.len(), 1);
+                assert_eq!(error.is_some(), input.contains("err"), "{}", input);
+            }
+            other => panic!("{}: {:?}", input, other),
+        }
+    }
+    
+    // A try on its own would swallow errors silently
+    let (_, tokens) = tokenize("try { x = 1 }\ny = 2").unwrap();
+    let err = Parser::new(&tokens).parse().unwrap_err();
+    assert!(err.message.contains("catch"), "{}", err.message);
+}
+
+#[test]
+fn test_parse_macros_expand_in_place() {
+    let input = r#"
+macro fx_chain(input, amount) {
+    wet = input |> Effects.reverb(mix: amount)
+    input = wet
+}
+loop {
+    fx_chain(pad, 0.3)
+    fx_chain(lead, 0.1 + 0.2)
+}
+"#;
+    let (_, tokens) = tokenize(input).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    // The definition itself leaves nothing behind
+    assert_eq!(program.items.len(), 1);
+    let Item::Loop(LoopBlock { body }) = &program.items[0] else { panic!("{:?}", program.items) };
+    assert_eq!(body.len(), 4);
+    let names: Vec<&str> = body.iter().map(|stmt| match stmt {
+        Statement::Assignment { name, .. } => name.as_str(),
+        other => panic!("{:?}", other),
+    }).collect();
+    // Each use gets its own `wet`, and parameters become what was passed
+    assert_eq!(names[1], "pad");
+    assert_eq!(names[3], "lead");
+    assert!(names[0] != "wet" && names[2] != "wet" && names[0] != names[2], "{:?}", names);
+    assert_eq!(body[1], Statement::Assignment { name: "pad".to_string(), value: Expression::Identifier(names[0].to_string()) });
+    
+    for (bad, message) in [
+        ("macro kit(name) { name = 1 }
+kit(a, b)", "takes 1 argument"),
+        ("macro forever(x) { forever(x) }
+forever(1)", "keeps expanding"),
+        ("macro broken(x) { x = }", "Unexpected"),
+        ("macro twice(x, x) { x = 1 }", "two parameters"),
+    ] {
+        let (_, tokens) = tokenize(bad).unwrap();
+        let err = Parser::new(&tokens).parse().unwrap_err();
+        assert!(err.message.contains(message), "{}: {}", bad, err.message);
+    }
+}
+
+#[test]
+fn test_format_round_trips_to_canonical_source() {
+    use synthesis::parser::{format, formatter::would_lose};
+    
+    let messy = r#"
+import Audio
+import lib/drums.{kick,snare} as d
+/// Scales x into a range
+///
+/// Low end first
+func scale(x:Number,low=0,...rest)->Number{return low+x*(1-low)}
+struct Point{x:Number=0 y=-1.5}
+class Counter { count = 0 func bump(by = 1) { self.count = self.count + by } }
+synth pluck(note = "A3") { Synth.saw(note) }
+capabilities { network, filesystem("assets/") }
+config { fps: 30 }
+const RATE=48000
+let [low,_,high]=bands
+let {left,right:r}=channels
+let gain: Number = (1 + 2) * -3 - -4
+loop{
+  level=Audio.mic_input()|>Audio.analyze_fft(bands:32)
+  if !ready && (a || b) { x = 1 } else { x = [1, 2.0, 50%, 12.5%, ...rest] }
+  match beat { 0..=3 => { hit = "low ${level}\t$" }, [first, _, ...] if first > 1 => {}, { kind: "note", velocity } => { v = velocity }, _ => { } }
+  every(1.seconds) { wait(0.5) }
+  after(5) { p = Point { x: 1 } }
+  async { y = clip?.play() }
+  while n < 3 { n = n + 1 continue }
+  for i in 0..10 { total = total + data[i] break }
+  try { song = Audio.load_file(r"C:\set.wav") } catch (err) { song = null ?? (a - (b - c)) }
+  ball.position.x = counter.bump(2).count
+  x = (2).clamp(0, 1)
+}
+"#;
+    let (_, tokens) = tokenize(messy).unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    let formatted = format(&program);
+    let (_, tokens) = tokenize(&formatted).unwrap();
+    let reparsed = Parser::new(&tokens).parse().unwrap_or_else(|e| panic!("{}\n{}", e.message, formatted));
+    assert_eq!(reparsed, program, "{}", formatted);
+    assert_eq!(format(&reparsed), formatted);
+    assert!(formatted.contains("func scale(x: Number, low = 0, ...rest) -> Number {\n    return low + x * (1 - low)\n}"), "{}", formatted);
+    assert!(formatted.contains("/// Scales x into a range\n///\n/// Low end first\nfunc"), "{}", formatted);
+    assert!(formatted.contains("} else {\n"), "{}", formatted);
+    assert!(formatted.contains("config { fps: 30 }\n") && formatted.contains("x = (2).clamp(0, 1)\n"), "{}", formatted);
+    assert!(formatted.contains("let gain: Number = (1 + 2) * -3 - -4\n"), "{}", formatted);
+    
+    // Calls that don't fit on a line get one argument per line
+    let (_, tokens) = tokenize("Graphics.rectangle(x: i * 32.0, y: Graphics.height - bar_height, width: 31.0, height: bar_height, color: bar_color)").unwrap();
+    let formatted = format(&Parser::new(&tokens).parse().unwrap());
+    assert_eq!(formatted, "Graphics.rectangle(\n    color: bar_color,\n    height: bar_height,\n    width: 31.0,\n    x: i * 32.0,\n    y: Graphics.height - bar_height,\n)\n");
+    
+    for example in synthesis::runtime::examples() {
+        let (_, tokens) = tokenize(example.source).unwrap();
+        let program = Parser::new(&tokens).parse().unwrap();
+        let formatted = format(&program);
+        let (_, tokens) = tokenize(&formatted).unwrap();
+        assert_eq!(Parser::new(&tokens).parse().unwrap(), program, "{}:\n{}", example.name, formatted);
+        assert!(formatted.lines().all(|line| !line.ends_with(' ')), "{}", example.name);
+    }
+    
+    for (source, lost) in [
+        ("x = 1 // one", Some("comments")),
+        ("/// stray\nx = 1", Some("comments")),
+        ("macro m(a) { a = 1 }\nm(x)", Some("macros")),
+        ("/// kept\nfunc f() {}\ny = \"// not a comment\"", None),
+    ] {
+        let (_, tokens) = tokenize(source).unwrap();
+        assert_eq!(would_lose(source, &tokens), lost, "{}", source);
+    }
+}
+
+#[test]
+fn test_lint_points_out_creative_coding_mistakes() {
+    use synthesis::parser::{lexer::tokenize_with_spans, lint, Linter};
+    
+    let input = r#"
+const RATE = 48000
+mic = Audio.mic_input()
+leftover = 3
+speed = 2
+config { remote: ["speed"] }
+func wobble(x) {
+    let mic = x * 2
+    return mic
+}
+every(1 / RATE) {
+    clip = Audio.load_file("kick.wav")
+    Audio.play(clip)
+    async { lyrics = Text.load_lyrics("song.lrc") }
+}
+every(0.5) { wait(0.1) }
+loop {}
+loop {
+    a = Generate.attractor("lorenz", name: "swirl")
+    b = Generate.attractor("rossler", name: "swirl")
+    Graphics.circle(a.x + b.x, 44100, wobble(mic))
+    Audio.configure(sample_rate: 44100)
+}
+"#;
+    let (_, (tokens, spans)) = tokenize_with_spans(input).unwrap();
+    let program = Parser::with_spans(&tokens, &spans, "sketch.syn").parse().unwrap();
+    let warnings = Linter::with_spans(&tokens, &spans, "sketch.syn").lint(&program);
+    let found: Vec<(usize, &str)> = warnings.iter()
+        .map(|warning| (warning.location.as_ref().map_or(0, |location| location.line), warning.message.as_str()))
+        .collect();
+    assert_eq!(found, vec![
+        (4, "'leftover' is set but never used"),
+        (8, "'mic' already holds a stream, and this hides it"),
+        (12, "Audio.load_file() can block, and this every() runs about 48000 times a second"),
+        (14, "'lyrics' is set but never used"),
+        (17, "This loop has nothing in it, so every frame draws and plays nothing"),
+        (20, "Two Generate.attractor() calls both name their streams \"swirl\", so the second takes over the first's"),
+        (21, "44100 looks like a sample rate written out by hand"),
+    ]);
+    assert!(warnings.iter().all(|warning| !warning.suggestions.is_empty()));
+    assert_eq!(warnings[2].location.as_ref().unwrap().column, 12);
+    
+    // Without spans the same warnings come out, just without places
+    assert_eq!(lint(&program).len(), warnings.len());
+    let (_, tokens) = tokenize("level = 0.5\nloop { Graphics.circle(level, 1, 2) }").unwrap();
+    assert!(lint(&Parser::new(&tokens).parse().unwrap()).is_empty());
+}
+
+#[test]
+fn test_parse_reports_every_syntax_error_at_once() {
+    use synthesis::parser::lexer::tokenize_with_spans;
+
+    let input = "x = 1\ny = )\nloop {\n    a = ]\n    b = 3\n}\nfunc f() {\n    c = (2 +\n}\nlet = 4\nw = 5";
+    let (_, (tokens, spans)) = tokenize_with_spans(input).unwrap();
+    let errors = Parser::with_spans(&tokens, &spans, "song.syn").parse_all().unwrap_err();
+    let lines: Vec<usize> = errors.iter().map(|error| error.location.as_ref().expect("errors should carry a location").line).collect();
+    assert_eq!(lines, vec![2, 4, 9, 10], "{:#?}", errors);
+
+    // parse() still stops at the first, and a script without mistakes parses as before
+    let first = Parser::with_spans(&tokens, &spans, "song.syn").parse().unwrap_err();
+    assert_eq!(first.location.map(|l| l.line), Some(2));
+    let (_, (tokens, spans)) = tokenize_with_spans("x = 1\nloop {\n    b = x\n}").unwrap();
+    assert_eq!(Parser::with_spans(&tokens, &spans, "song.syn").parse_all().unwrap().items.len(), 2);
+}
+
+#[test]
+fn test_incremental_parser_reparses_only_what_an_edit_touches() {
+    use synthesis::parser::lexer::{tokenize_with_spans, Span};
+    use synthesis::parser::{IncrementalParser, TextEdit};
+
+    let full = |source: &str| {
+        let (_, (tokens, spans)) = tokenize_with_spans(source).unwrap();
+        Parser::with_spans(&tokens, &spans, "sketch.syn").parse().unwrap()
+    };
+    let at = |line, column| Span { line, column };
+    let source = "// levels\nspeed = 2\nfunc pulse(x) {\n    return x * speed\n}\nloop {\n    Graphics.clear()\n}\n";
+    let mut parser = IncrementalParser::new("sketch.syn");
+    let opened = parser.set_text(source).unwrap();
+    assert_eq!((opened.start, opened.removed, opened.inserted.len()), (0, 0, 3));
+    let mut copy = parser.program().clone();
+
+    // Changing one value replaces just its item, and the delta brings a copy up to date
+    let delta = parser.edit(&TextEdit::new(at(2, 9), at(2, 10), "3")).unwrap();
+    assert_eq!((delta.start, delta.removed, delta.inserted.len()), (0, 1, 1));
+    delta.apply(&mut copy);
+    assert_eq!(&copy, parser.program());
+    assert_eq!(parser.program(), &full(parser.source()));
+    assert!(parser.edit(&TextEdit::insert(at(1, 10), " and shapes")).unwrap().is_empty());
+
+    let delta = parser.edit(&TextEdit::new(at(4, 16), at(4, 21), "2")).unwrap();
+    assert_eq!((delta.start, delta.removed), (1, 1));
+    let delta = parser.edit(&TextEdit::insert(at(9, 1), "size = pulse(speed)\n")).unwrap();
+    assert_eq!((delta.start, delta.removed, delta.inserted.len()), (3, 0, 1));
+    assert_eq!(parser.program(), &full(parser.source()));
+
+    // An unclosed { reaches past the items around it: the whole script is checked and the last
+    // good program is kept until it's closed
+    let before = parser.program().clone();
+    let errors = parser.edit(&TextEdit::insert(at(2, 10), " {")).unwrap_err();
+    assert!(!errors.is_empty());
+    assert_eq!(parser.program(), &before);
+    parser.edit(&TextEdit::new(at(2, 10), at(2, 12), "")).unwrap();
+    assert_eq!(parser.program(), &before);
+
+    // Typing a script a character at a time always ends up where parsing it whole does
+    let typed = "x = 1 + 2\nif x > 2 {\n    y = [x, 3]\n}\nz = x\n    * 2\n";
+    let mut parser = IncrementalParser::new("typed.syn");
+    let _ = parser.set_text("");
+    let (mut line, mut column) = (1, 1);
+    for c in typed.chars() {
+        if parser.edit(&TextEdit::insert(at(line, column), c.to_string())).is_ok() {
+            let (_, (tokens, spans)) = tokenize_with_spans(parser.source()).unwrap();
+            assert_eq!(Ok(parser.program().clone()), Parser::with_spans(&tokens, &spans, "typed.syn").parse_all().map_err(|_| ()), "after typing {:?}", parser.source());
+        }
+        if c == '\n' { line += 1; column = 1 } else { column += 1 }
+    }
+    assert_eq!(parser.source(), typed);
+    assert_eq!(parser.program(), &full(typed));
+}
+
+#[test]
+fn test_hex_binary_and_colour_literals() {
+    use synthesis::parser::lexer::{is_blank, Token};
+    use synthesis::parser::{check_types, format, formatter::would_lose};
+    use synthesis::parser::lexer::tokenize_with_spans;
+
+    let (rest, tokens) = tokenize("(0xFF00AA, 0b1010, 0X1f, #ff00aa, #FF00AA80) # ff00aa is a comment\nfill(#ffcc00) // gold").unwrap();
+    assert!(is_blank(rest), "left over: {:?}", rest);
+    let literals: Vec<_> = tokens.into_iter().filter(|token| !matches!(token, Token::LeftParen | Token::RightParen | Token::Comma | Token::Identifier(_))).collect();
+    assert_eq!(literals, vec![
+        Token::BasedInteger(0xFF00AA, 16),
+        Token::BasedInteger(10, 2),
+        Token::BasedInteger(31, 16),
+        Token::Color([255, 0, 170, 255]),
+        Token::Color([255, 0, 170, 128]),
+        Token::Color([255, 204, 0, 255]),
+    ]);
+
+    // Too few digits, or letters straight after, and it's a comment as before
+    let (_, tokens) = tokenize("x = 1 #fff\ny = 2 #ff00aazz").unwrap();
+    assert!(!tokens.iter().any(|token| matches!(token, Token::Color(_))), "{:?}", tokens);
+
+    let (_, tokens) = tokenize("let accent: Color = #64dd17\nGraphics.circle(50%, 50%, 10%, accent)\nlet mask = 0b1100").unwrap();
+    let program = Parser::new(&tokens).parse().unwrap();
+    match &program.items[0] {
+        Item::Statement(Statement::Let { value, .. }) => assert_eq!(value, &Some(Expression::Literal(Literal::Color([0x64, 0xdd, 0x17, 255])))),
+        other => panic!("expected a let, got {:?}", other),
+    }
+    assert!(check_types(&program).is_empty(), "{:?}", check_types(&program));
+
+    // Colours and hex and binary numbers format as written, give or take case and leading zeros
+    let formatted = format(&program);
+    assert!(formatted.contains("let accent: Color = #64dd17"), "{}", formatted);
+    assert!(formatted.contains("let mask = 0b1100"), "{}", formatted);
+    let source = "c = #ff00aa80\nmask = 0b0011 + 0x0f\nlow = -0xff\nmatch x {\n    -0b1..=0x1F => { }\n}";
+    let (_, (tokens, spans)) = tokenize_with_spans(source).unwrap();
+    assert_eq!(would_lose(source, &tokens), None);
+    let program = Parser::with_spans(&tokens, &spans, "hex.syn").parse().unwrap();
+    let formatted = format(&program);
+    for written in ["#ff00aa80", "0b11", "0xF", "-0xFF", "-0b1..=0x1F"] {
+        assert!(formatted.contains(written), "{} should be in:\n{}", written, formatted);
+    }
+    let (_, tokens) = tokenize(&formatted).unwrap();
+    assert_eq!(Parser::new(&tokens).parse().unwrap(), program, "{}", formatted);
+}
+
+#[test]
+fn test_parse_errors_point_at_line_and_column() {
+    use synthesis::parser::lexer::{tokenize_with_spans, Span};
+
+    let input = "x = 1\n// a comment\n  y = )";
+    let (_, (tokens, spans)) = tokenize_with_spans(input).unwrap();
+    assert_eq!(tokens.len(), spans.len());
+    assert_eq!(spans[0], Span { line: 1, column: 1 });
+    assert_eq!(spans[3], Span { line: 3, column: 3 });
+
+    let error = Parser::with_spans(&tokens, &spans, "song.syn").parse().unwrap_err();
+    let location = error.location.expect("parse errors should carry a location");
+    assert_eq!((location.line, location.column), (3, 7));
+    assert_eq!(location.filename, "song.syn");
+
+    // An argument to a macro is parsed on its own but still points back into the file
+    let input = "macro add(a, b) { x = a + b }\nadd(1 + , 2)";
+    let (_, (tokens, spans)) = tokenize_with_spans(input).unwrap();
+    let error = Parser::with_spans(&tokens, &spans, "song.syn").parse().unwrap_err();
+    let location = error.location.expect("errors in macro arguments should carry a location");
+    assert_eq!((location.line, location.column), (2, 7));
+}
+
+#[test]
+fn test_doc_comments_attach_to_functions() {
+    let input = "/* a block comment\n   over two lines */\n/// Doubles a value.\n/// Works on streams too.\nfunc double(x) {\n    /// not attached to anything\n    return x * 2 /* inline */\n}\n//// just a comment\nfunc plain() { return 1 }";
+    let (rest, tokens) = tokenize(input).unwrap();
+    assert!(rest.is_empty());
+    let program = Parser::new(&tokens).parse().unwrap();
+
+    assert_eq!(program.items.len(), 2);
+    match (&program.items[0], &program.items[1]) {
+        (Item::Function(double), Item::Function(plain)) => {
+            assert_eq!(double.doc.as_deref(), Some("Doubles a value.\nWorks on streams too."));
+            assert_eq!(double.body.len(), 1);
+            assert_eq!(plain.doc, None);
+        }
+        _ => panic!("Expected two functions"),
+    }
+}
@@ -0,0 +1,298 @@
+/// Several audio devices at once: a USB interface in, HDMI out, headphones for cueing
+///
+/// Each device runs on its own crystal, so even two "48kHz" devices drift apart by a few
+/// hundred ppm. Every device stream sits behind a queue; whoever fills the queue resamples
+/// into it at the nominal rate ratio, nudged by how full the queue is. A device that runs
+/// slightly fast drains its queue a little, the ratio creeps up to match, and the queue never
+/// runs dry or grows without bound.
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Queue depth each stream steers towards, in seconds
+pub const DEVICE_QUEUE_SECONDS: f64 = 0.02;
+/// Furthest drift compensation will bend the rate ratio (0.5%)
+pub const MAX_DRIFT_CORRECTION: f64 = 0.005;
+/// How quickly the correction follows the queue level, per block
+const DRIFT_SMOOTHING: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceDirection {
+    Input,
+    Output,
+}
+
+impl DeviceDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Input => "input",
+            Self::Output => "output",
+        }
+    }
+}
+
+/// Streaming linear-interpolation resampler whose ratio can change between blocks
+#[derive(Debug, Clone)]
+pub struct DriftResampler {
+    /// Output samples per input sample
+    ratio: f64,
+    /// Read position, where 0 is the last sample of the previous block
+    position: f64,
+    last: f32,
+}
+
+impl DriftResampler {
+    pub fn new(ratio: f64) -> Self {
+        Self { ratio, position: 1.0, last: 0.0 }
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = ratio.max(f64::EPSILON);
+    }
+
+    pub fn process(&mut self, input: &[f32], output: &mut impl Extend<f32>) {
+        if input.is_empty() {
+            return;
+        }
+        let step = 1.0 / self.ratio;
+        let at = |index: usize| if index == 0 { self.last } else { input[index - 1] };
+        let mut produced = Vec::with_capacity((input.len() as f64 * self.ratio) as usize + 2);
+        while self.position < input.len() as f64 {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            produced.push(at(index) + (at(index + 1) - at(index)) * fraction);
+            self.position += step;
+        }
+        output.extend(produced);
+        self.position -= input.len() as f64;
+        self.last = input[input.len() - 1];
+    }
+}
+
+/// One open device. The producer side (the engine for outputs, the device for inputs) pushes
+/// and resamples; the consumer side pops at its own rate.
+#[derive(Debug)]
+pub struct DeviceStream {
+    pub name: String,
+    pub direction: DeviceDirection,
+    pub sample_rate: f64,
+    master_rate: f64,
+    queue: VecDeque<f32>,
+    resampler: DriftResampler,
+    /// Queue depth to steer towards, in samples on the consumer side
+    target_fill: usize,
+    correction: f64,
+    pub underruns: u64,
+    pub overruns: u64,
+    /// Latest block read from an input, at the master rate
+    pub last_input: Vec<f32>,
+    /// Cleared on close; the thread holding the platform stream exits when it sees that
+    pub alive: Arc<AtomicBool>,
+}
+
+impl DeviceStream {
+    pub fn new(name: &str, direction: DeviceDirection, sample_rate: f64, master_rate: f64) -> Self {
+        let consumer_rate = match direction {
+            DeviceDirection::Output => sample_rate,
+            DeviceDirection::Input => master_rate,
+        };
+        let target_fill = ((DEVICE_QUEUE_SECONDS * consumer_rate) as usize).max(1);
+        let mut stream = Self {
+            name: name.to_string(),
+            direction,
+            sample_rate,
+            master_rate,
+            queue: VecDeque::with_capacity(target_fill * 4),
+            resampler: DriftResampler::new(1.0),
+            target_fill,
+            correction: 1.0,
+            underruns: 0,
+            overruns: 0,
+            last_input: Vec::new(),
+            alive: Arc::new(AtomicBool::new(true)),
+        };
+        stream.update_ratio();
+        // Start at the target depth so the first callbacks have something to play
+        stream.queue.resize(target_fill, 0.0);
+        stream
+    }
+
+    fn nominal_ratio(&self) -> f64 {
+        match self.direction {
+            DeviceDirection::Output => self.sample_rate / self.master_rate,
+            DeviceDirection::Input => self.master_rate / self.sample_rate,
+        }
+    }
+
+    fn update_ratio(&mut self) {
+        self.resampler.set_ratio(self.nominal_ratio() * self.correction);
+    }
+
+    pub fn set_master_rate(&mut self, master_rate: f64) {
+        self.master_rate = master_rate;
+        if self.direction == DeviceDirection::Input {
+            self.target_fill = ((DEVICE_QUEUE_SECONDS * master_rate) as usize).max(1);
+        }
+        self.update_ratio();
+    }
+
+    /// Producer side: resample a block into the queue and re-steer towards the target depth
+    pub fn push(&mut self, block: &[f32]) {
+        self.resampler.process(block, &mut self.queue);
+        let limit = self.target_fill * 4;
+        if self.queue.len() > limit {
+            self.overruns += 1;
+            let excess = self.queue.len() - self.target_fill;
+            self.queue.drain(..excess);
+        }
+        // Too full means the consumer is slow: produce a little less, and vice versa
+        let error = (self.queue.len() as f64 - self.target_fill as f64) / self.target_fill as f64;
+        let wanted = (1.0 - error * MAX_DRIFT_CORRECTION).clamp(1.0 - MAX_DRIFT_CORRECTION, 1.0 + MAX_DRIFT_CORRECTION);
+        self.correction += (wanted - self.correction) * DRIFT_SMOOTHING;
+        self.update_ratio();
+    }
+
+    /// Consumer side: fill `out`, with silence if the queue ran dry
+    pub fn pop(&mut self, out: &mut [f32]) {
+        let available = self.queue.len().min(out.len());
+        for (sample, queued) in out.iter_mut().zip(self.queue.drain(..available)) {
+            *sample = queued;
+        }
+        if available < out.len() {
+            self.underruns += 1;
+            out[available..].fill(0.0);
+        }
+    }
+
+    /// How far the device's clock runs from its nominal rate, as measured by the correction
+    pub fn drift_ppm(&self) -> f64 {
+        let sign = match self.direction {
+            DeviceDirection::Output => 1.0,
+            DeviceDirection::Input => -1.0,
+        };
+        sign * (self.correction - 1.0) * 1_000_000.0
+    }
+
+    /// Audio waiting in the queue, in milliseconds
+    pub fn queued_ms(&self) -> f64 {
+        let consumer_rate = match self.direction {
+            DeviceDirection::Output => self.sample_rate,
+            DeviceDirection::Input => self.master_rate,
+        };
+        self.queue.len() as f64 / consumer_rate * 1000.0
+    }
+
+    pub fn to_value(&self) -> crate::runtime::Value {
+        use crate::runtime::Value;
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("name".to_string(), Value::String(self.name.clone()));
+        fields.insert("direction".to_string(), Value::String(self.direction.as_str().to_string()));
+        fields.insert("rate".to_string(), Value::Float(self.sample_rate));
+        fields.insert("drift_ppm".to_string(), Value::Float(self.drift_ppm()));
+        fields.insert("queued_ms".to_string(), Value::Float(self.queued_ms()));
+        fields.insert("underruns".to_string(), Value::Integer(self.underruns as i64));
+        fields.insert("overruns".to_string(), Value::Integer(self.overruns as i64));
+        Value::Object(fields)
+    }
+}
+
+/// Every device opened alongside the default one
+#[derive(Debug)]
+pub struct AggregateDevice {
+    master_rate: f64,
+    streams: Vec<DeviceStream>,
+    /// The output that plays the cue bus instead of the main mix
+    pub cue_output: Option<String>,
+}
+
+impl Default for AggregateDevice {
+    fn default() -> Self {
+        Self::new(crate::audio::synth::SYNTH_SAMPLE_RATE)
+    }
+}
+
+impl AggregateDevice {
+    pub fn new(master_rate: f64) -> Self {
+        Self { master_rate, streams: Vec::new(), cue_output: None }
+    }
+
+    pub fn open(&mut self, name: &str, direction: DeviceDirection, sample_rate: f64) -> crate::Result<&mut DeviceStream> {
+        if self.stream(name, direction).is_some() {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::AudioDeviceError,
+                format!("🎚️ {} is already open as an {}", name, direction.as_str())
+            )
+            .with_suggestion("Audio.devices() lists what's open; close it first with Audio.close_device()"));
+        }
+        self.streams.push(DeviceStream::new(name, direction, sample_rate, self.master_rate));
+        Ok(self.streams.last_mut().expect("just pushed"))
+    }
+
+    /// Close every stream on the named device; returns how many there were
+    pub fn close(&mut self, name: &str) -> usize {
+        let before = self.streams.len();
+        if self.cue_output.as_deref() == Some(name) {
+            self.cue_output = None;
+        }
+        self.streams.retain(|stream| {
+            let keep = stream.name != name;
+            if !keep {
+                stream.alive.store(false, Ordering::Relaxed);
+            }
+            keep
+        });
+        before - self.streams.len()
+    }
+
+    pub fn streams(&self) -> &[DeviceStream] {
+        &self.streams
+    }
+
+    pub fn stream(&self, name: &str, direction: DeviceDirection) -> Option<&DeviceStream> {
+        self.streams.iter().find(|stream| stream.name == name && stream.direction == direction)
+    }
+
+    pub fn stream_mut(&mut self, name: &str, direction: DeviceDirection) -> Option<&mut DeviceStream> {
+        self.streams.iter_mut().find(|stream| stream.name == name && stream.direction == direction)
+    }
+
+    pub fn set_master_rate(&mut self, master_rate: f64) {
+        self.master_rate = master_rate;
+        for stream in &mut self.streams {
+            stream.set_master_rate(master_rate);
+        }
+    }
+
+    /// Once per engine block: the master mix goes to every output (the cue output gets the cue
+    /// bus instead), and every input hands over a block of the same length
+    pub fn exchange(&mut self, block: &[f32], cue: &[f32]) {
+        for stream in &mut self.streams {
+            match stream.direction {
+                DeviceDirection::Output if self.cue_output.as_deref() == Some(stream.name.as_str()) => {
+                    if cue.len() == block.len() {
+                        stream.push(cue);
+                    } else {
+                        stream.push(&vec![0.0; block.len()]);
+                    }
+                }
+                DeviceDirection::Output => stream.push(block),
+                DeviceDirection::Input => {
+                    let mut input = std::mem::take(&mut stream.last_input);
+                    input.resize(block.len(), 0.0);
+                    stream.pop(&mut input);
+                    stream.last_input = input;
+                }
+            }
+        }
+    }
+}
+
+static AGGREGATE_DEVICE: OnceLock<Mutex<AggregateDevice>> = OnceLock::new();
+
+pub fn aggregate_device() -> &'static Mutex<AggregateDevice> {
+    AGGREGATE_DEVICE.get_or_init(|| Mutex::new(AggregateDevice::default()))
+}
@@ -0,0 +1,462 @@
+/// The clip grid: session-view launching of audio and pattern clips
+///
+/// Tracks are columns and scenes are rows; a track plays one clip at a time. Launches and
+/// stops wait for the next quantize boundary (a bar unless `Clips.quantize()` says otherwise)
+/// and land on the exact sample inside the engine block. When a clip has played through
+/// `follow_after` times its follow action picks what comes next. Pattern clips are
+/// mini-notation note patterns, one cycle per clip length, played on a synth or a MIDI
+/// channel; audio clips play sample buffers at their own speed and loop on the beat grid.
+use crate::audio::mini_notation::MiniPattern;
+use crate::audio::synth::{note_frequency, SynthEngine};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Largest grid a script can build, to keep a typo from allocating thousands of slots
+pub const MAX_CLIP_TRACKS: usize = 64;
+pub const MAX_CLIP_SCENES: usize = 64;
+
+#[derive(Debug, Clone)]
+pub enum ClipContent {
+    Audio { samples: Arc<Vec<f32>>, sample_rate: f64 },
+    Pattern {
+        pattern: MiniPattern,
+        /// Synth to play the notes on; None sends them to the MIDI outputs
+        synth: Option<String>,
+        channel: u8,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowAction {
+    Stop,
+    Again,
+    Next,
+    Previous,
+    First,
+    Random,
+}
+
+impl FollowAction {
+    pub fn parse(name: &str) -> crate::Result<Self> {
+        match name {
+            "stop" => Ok(Self::Stop),
+            "again" => Ok(Self::Again),
+            "next" => Ok(Self::Next),
+            "previous" | "prev" => Ok(Self::Previous),
+            "first" => Ok(Self::First),
+            "random" | "any" => Ok(Self::Random),
+            _ => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🟩 '{}' isn't a follow action", name)
+            )
+            .with_suggestion("Follow actions are next, previous, first, random, again and stop")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipState {
+    Empty,
+    Stopped,
+    /// Waiting for the quantize boundary to start
+    Queued,
+    Playing,
+    /// Playing until the quantize boundary, then stopping or handing over
+    Stopping,
+}
+
+impl ClipState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Empty => "empty",
+            Self::Stopped => "stopped",
+            Self::Queued => "queued",
+            Self::Playing => "playing",
+            Self::Stopping => "stopping",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub name: String,
+    pub content: ClipContent,
+    pub gain: f32,
+    /// Length in beats: how long one pass, and one pattern cycle, lasts
+    pub length: f64,
+    pub looping: bool,
+    pub follow: Option<FollowAction>,
+    /// Passes to play before the follow action
+    pub follow_after: u32,
+}
+
+impl Clip {
+    pub fn new(name: &str, content: ClipContent, length: f64) -> Self {
+        Self { name: name.to_string(), content, gain: 1.0, length, looping: true, follow: None, follow_after: 1 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrackPlayer {
+    playing: Option<usize>,
+    /// Some(Some(scene)) launches that scene at the boundary, Some(None) stops the track
+    queued: Option<Option<usize>>,
+    /// Beats into the current pass
+    position: f64,
+    /// Source samples into an audio clip
+    cursor: f64,
+    plays: u32,
+    /// Loudest sample the track put out in the last block, for controller meters
+    peak: f32,
+}
+
+/// What one block of clip playback produced besides audio
+#[derive(Debug, Default)]
+struct BlockOutput {
+    midi: Vec<[u8; 3]>,
+    note_offs: Vec<(f64, [u8; 3])>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClipGrid {
+    /// Indexed [track][scene]
+    slots: Vec<Vec<Option<Clip>>>,
+    players: Vec<TrackPlayer>,
+    /// Launch quantum in beats; 0 launches on the next block
+    pub quantum: f64,
+    /// Beats since the grid started
+    beat: f64,
+    note_offs: Vec<(f64, [u8; 3])>,
+    seed: u64,
+}
+
+impl Default for ClipGrid {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            players: Vec::new(),
+            quantum: 4.0,
+            beat: 0.0,
+            note_offs: Vec::new(),
+            seed: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+}
+
+fn slot_error(track: usize, scene: usize) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("🟩 The grid has no slot at track {}, scene {}", track, scene)
+    )
+    .with_suggestion(format!("Grids go up to {} tracks and {} scenes, counting from 0", MAX_CLIP_TRACKS, MAX_CLIP_SCENES))
+}
+
+impl ClipGrid {
+    pub fn tracks(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn scenes(&self) -> usize {
+        self.slots.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    pub fn beat(&self) -> f64 {
+        self.beat
+    }
+
+    pub fn clip(&self, track: usize, scene: usize) -> Option<&Clip> {
+        self.slots.get(track)?.get(scene)?.as_ref()
+    }
+
+    /// Put a clip in a slot, growing the grid as needed; a playing slot carries on with the new clip
+    pub fn set(&mut self, track: usize, scene: usize, clip: Clip) -> crate::Result<()> {
+        if track >= MAX_CLIP_TRACKS || scene >= MAX_CLIP_SCENES {
+            return Err(slot_error(track, scene));
+        }
+        if self.slots.len() <= track {
+            self.slots.resize_with(track + 1, Vec::new);
+            self.players.resize_with(track + 1, TrackPlayer::default);
+        }
+        let column = &mut self.slots[track];
+        if column.len() <= scene {
+            column.resize_with(scene + 1, || None);
+        }
+        column[scene] = Some(clip);
+        Ok(())
+    }
+
+    /// Empty a slot; if it was playing, the track stops straight away
+    pub fn clear(&mut self, track: usize, scene: usize) -> bool {
+        let Some(slot) = self.slots.get_mut(track).and_then(|column| column.get_mut(scene)) else {
+            return false;
+        };
+        let had_clip = slot.take().is_some();
+        let player = &mut self.players[track];
+        if player.playing == Some(scene) {
+            player.playing = None;
+        }
+        if player.queued == Some(Some(scene)) {
+            player.queued = None;
+        }
+        had_clip
+    }
+
+    pub fn launch(&mut self, track: usize, scene: usize) -> crate::Result<()> {
+        if self.clip(track, scene).is_none() {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🟩 Nothing to launch at track {}, scene {}", track, scene)
+            )
+            .with_suggestion("Put a clip there first: Clips.set(track, scene, samples) or Clips.set(track, scene, \"c3 e3 g3 ~\", synth: \"pluck\")"));
+        }
+        self.players[track].queued = Some(Some(scene));
+        Ok(())
+    }
+
+    /// Launch a whole row: tracks with a clip in it switch, tracks without one stop
+    pub fn launch_scene(&mut self, scene: usize) -> usize {
+        let mut launched = 0;
+        for (column, player) in self.slots.iter().zip(self.players.iter_mut()) {
+            if column.get(scene).is_some_and(Option::is_some) {
+                player.queued = Some(Some(scene));
+                launched += 1;
+            } else if player.playing.is_some() {
+                player.queued = Some(None);
+            }
+        }
+        launched
+    }
+
+    pub fn stop(&mut self, track: usize) {
+        if let Some(player) = self.players.get_mut(track) {
+            player.queued = player.playing.map(|_| None);
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for track in 0..self.players.len() {
+            self.stop(track);
+        }
+    }
+
+    /// Stop everything now, not at the next boundary; the panic button uses this
+    pub fn halt(&mut self) {
+        for player in &mut self.players {
+            *player = TrackPlayer::default();
+        }
+        self.note_offs.clear();
+    }
+
+    pub fn state(&self, track: usize, scene: usize) -> ClipState {
+        if self.clip(track, scene).is_none() {
+            return ClipState::Empty;
+        }
+        let player = &self.players[track];
+        match (player.playing == Some(scene), player.queued) {
+            (true, Some(next)) if next != Some(scene) => ClipState::Stopping,
+            (true, _) => ClipState::Playing,
+            (false, Some(Some(next))) if next == scene => ClipState::Queued,
+            _ => ClipState::Stopped,
+        }
+    }
+
+    /// Peak level of a track's last block; pattern clips count as their gain while notes start
+    pub fn level(&self, track: usize) -> f32 {
+        self.players.get(track).map(|player| player.peak).unwrap_or(0.0)
+    }
+
+    /// Scene each track is playing, if any
+    pub fn playing(&self) -> Vec<Option<usize>> {
+        self.players.iter().map(|player| player.playing).collect()
+    }
+
+    /// What a pad does: launch a stopped clip, stop a playing or queued one
+    pub fn toggle(&mut self, track: usize, scene: usize) -> bool {
+        match self.state(track, scene) {
+            ClipState::Empty => false,
+            ClipState::Playing | ClipState::Queued => {
+                self.stop(track);
+                true
+            }
+            _ => self.launch(track, scene).is_ok(),
+        }
+    }
+
+    /// Play one engine block: queued launches switch at the quantize boundary, pattern notes go
+    /// to `engine` or come back as MIDI messages, and audio clips come back mixed
+    pub fn render(&mut self, frames: usize, sample_rate: f64, bpm: f64, engine: &mut SynthEngine) -> (Vec<f32>, Vec<[u8; 3]>) {
+        let mut mix = vec![0.0f32; frames];
+        let mut out = BlockOutput::default();
+        let beats_per_frame = bpm / 60.0 / sample_rate;
+        let start = self.beat;
+        let end = start + frames as f64 * beats_per_frame;
+        let switch_at = if self.quantum > 0.0 {
+            let boundary = (start / self.quantum - 1e-9).ceil() * self.quantum;
+            (boundary < end).then(|| (((boundary - start) / beats_per_frame).round() as usize).min(frames))
+        } else {
+            Some(0)
+        };
+
+        for track in 0..self.players.len() {
+            let column = &self.slots[track];
+            let player = &mut self.players[track];
+            player.peak = 0.0;
+            let context = PlayContext { column, block_start: start, beats_per_frame, sample_rate, bpm };
+            match (player.queued, switch_at) {
+                (Some(next), Some(at)) => {
+                    context.play(player, 0..at, &mut mix, &mut out, engine, &mut self.seed);
+                    player.queued = None;
+                    player.playing = next;
+                    player.position = 0.0;
+                    player.cursor = 0.0;
+                    player.plays = 0;
+                    context.play(player, at..frames, &mut mix, &mut out, engine, &mut self.seed);
+                }
+                _ => context.play(player, 0..frames, &mut mix, &mut out, engine, &mut self.seed),
+            }
+        }
+
+        self.note_offs.append(&mut out.note_offs);
+        self.note_offs.retain(|(beat, message)| {
+            let due = *beat < end;
+            if due {
+                out.midi.push(*message);
+            }
+            !due
+        });
+        self.beat = end;
+        (mix, out.midi)
+    }
+}
+
+struct PlayContext<'a> {
+    column: &'a [Option<Clip>],
+    block_start: f64,
+    beats_per_frame: f64,
+    sample_rate: f64,
+    bpm: f64,
+}
+
+impl PlayContext<'_> {
+    fn play(
+        &self,
+        player: &mut TrackPlayer,
+        range: std::ops::Range<usize>,
+        mix: &mut [f32],
+        out: &mut BlockOutput,
+        engine: &mut SynthEngine,
+        seed: &mut u64,
+    ) {
+        let mut frame = range.start;
+        while frame < range.end {
+            let Some(scene) = player.playing else {
+                return;
+            };
+            let Some(clip) = self.column.get(scene).and_then(Option::as_ref) else {
+                player.playing = None;
+                return;
+            };
+            let length = clip.length.max(f64::EPSILON);
+            // Run to the end of the pass or the end of the range, whichever comes first
+            let frames_left = ((length - player.position) / self.beats_per_frame).ceil().max(1.0) as usize;
+            let count = frames_left.min(range.end - frame);
+            let from = player.position;
+            let to = from + count as f64 * self.beats_per_frame;
+
+            match &clip.content {
+                ClipContent::Audio { samples, sample_rate } => {
+                    let step = sample_rate / self.sample_rate;
+                    for sample in &mut mix[frame..frame + count] {
+                        if let Some(source) = samples.get(player.cursor as usize) {
+                            *sample += source * clip.gain;
+                            player.peak = player.peak.max((source * clip.gain).abs());
+                        }
+                        player.cursor += step;
+                    }
+                }
+                ClipContent::Pattern { pattern, synth, channel } => {
+                    for (onset, event) in pattern.query_span(from / length, to.min(length) / length) {
+                        let at = self.block_start + (frame as f64 + (onset * length - from) / self.beats_per_frame) * self.beats_per_frame;
+                        self.trigger(&event.value, event.duration * length, at, clip, synth.as_deref(), *channel, out, engine);
+                        player.peak = player.peak.max(clip.gain);
+                    }
+                }
+            }
+
+            player.position = to;
+            frame += count;
+            if player.position >= length - 1e-9 {
+                player.plays += 1;
+                player.position = 0.0;
+                player.cursor = 0.0;
+                player.playing = match clip.follow {
+                    Some(action) if player.plays >= clip.follow_after => {
+                        player.plays = 0;
+                        follow_target(action, scene, self.column, seed)
+                    }
+                    _ if clip.looping => Some(scene),
+                    _ => None,
+                };
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn trigger(&self, value: &str, beats: f64, at: f64, clip: &Clip, synth: Option<&str>, channel: u8, out: &mut BlockOutput, engine: &mut SynthEngine) {
+        // Note names or MIDI note numbers; anything else (drum names) has nothing to play here
+        let frequency = note_frequency(value)
+            .or_else(|| value.parse::<f64>().ok().map(|note| 440.0 * 2f64.powf((note - 69.0) / 12.0)));
+        let Some(frequency) = frequency else {
+            return;
+        };
+        match synth {
+            Some(name) => {
+                let Some(graph) = engine.graph(name) else {
+                    return;
+                };
+                let mut params = HashMap::new();
+                if let Some(first) = graph.params.first() {
+                    params.insert(first.name.clone(), frequency);
+                }
+                if graph.param_index("velocity").is_some() {
+                    params.insert("velocity".to_string(), clip.gain as f64);
+                }
+                if let Err(error) = engine.play(name, &params, Some(beats * 60.0 / self.bpm)) {
+                    tracing::warn!(target: "audio", clip = clip.name.as_str(), %error, "🟩 clip note didn't play");
+                }
+            }
+            None => {
+                let note = (69.0 + 12.0 * (frequency / 440.0).log2()).round().clamp(0.0, 127.0) as u8;
+                let velocity = (clip.gain * 100.0).round().clamp(1.0, 127.0) as u8;
+                out.midi.push([0x90 | (channel & 0x0F), note, velocity]);
+                out.note_offs.push((at + beats, [0x80 | (channel & 0x0F), note, 0]));
+            }
+        }
+    }
+}
+
+fn follow_target(action: FollowAction, scene: usize, column: &[Option<Clip>], seed: &mut u64) -> Option<usize> {
+    let filled: Vec<usize> = column.iter().enumerate().filter(|(_, slot)| slot.is_some()).map(|(index, _)| index).collect();
+    match action {
+        FollowAction::Stop => None,
+        FollowAction::Again => Some(scene),
+        FollowAction::Next => filled.iter().find(|&&other| other > scene).or(filled.first()).copied(),
+        FollowAction::Previous => filled.iter().rev().find(|&&other| other < scene).or(filled.last()).copied(),
+        FollowAction::First => filled.first().copied(),
+        FollowAction::Random => {
+            // Another clip if there is one, so "random" always changes something
+            let others: Vec<usize> = filled.iter().copied().filter(|&other| other != scene).collect();
+            let choices = if others.is_empty() { &filled } else { &others };
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            choices.get((*seed % choices.len().max(1) as u64) as usize).copied()
+        }
+    }
+}
+
+static CLIP_GRID: OnceLock<Mutex<ClipGrid>> = OnceLock::new();
+
+pub fn clip_grid() -> &'static Mutex<ClipGrid> {
+    CLIP_GRID.get_or_init(|| Mutex::new(ClipGrid::default()))
+}
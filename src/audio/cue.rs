@@ -0,0 +1,84 @@
+/// The cue bus: pre-listen on headphones before a sound reaches the room
+///
+/// `pad.cue(true)` takes the `pad` synth out of the main mix and plays it on the cue bus
+/// only; `pad.cue(false)` sends it back to the main mix. `pad.cue(true, main: true)` is the
+/// DJ-mixer PFL button: the cue bus hears it and the main mix keeps it. The cue bus goes to
+/// whichever extra output `Audio.cue_output("Headphones")` picked, through its own limiter.
+use crate::audio::loudness::Limiter;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueMode {
+    /// Cue bus only, held back from the main mix
+    Preview,
+    /// Cue bus and main mix
+    Listen,
+}
+
+#[derive(Debug, Clone)]
+pub struct CueBus {
+    sources: BTreeMap<String, CueMode>,
+    pub level: f32,
+    limiter: Limiter,
+}
+
+impl Default for CueBus {
+    fn default() -> Self {
+        Self { sources: BTreeMap::new(), level: 1.0, limiter: Limiter::new(crate::audio::synth::SYNTH_SAMPLE_RATE) }
+    }
+}
+
+impl CueBus {
+    pub fn set(&mut self, source: &str, mode: Option<CueMode>) {
+        match mode {
+            Some(mode) => self.sources.insert(source.to_string(), mode),
+            None => self.sources.remove(source),
+        };
+    }
+
+    pub fn mode(&self, source: &str) -> Option<CueMode> {
+        self.sources.get(source).copied()
+    }
+
+    /// Anything cued at all; with nothing cued the engine can skip rendering stems
+    pub fn is_active(&self) -> bool {
+        !self.sources.is_empty()
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = (&str, CueMode)> {
+        self.sources.iter().map(|(name, mode)| (name.as_str(), *mode))
+    }
+
+    /// Pull the cued stems out of a rendered block: previewed sources leave `main`, and every
+    /// cued source lands in the returned cue mix
+    pub fn split(&mut self, main: &mut [f32], stems: &BTreeMap<String, Vec<f32>>, sample_rate: f64) -> Vec<f32> {
+        let mut cue = vec![0.0f32; main.len()];
+        for (name, mode) in &self.sources {
+            let Some(stem) = stems.get(name) else {
+                continue;
+            };
+            for ((cued, total), sample) in cue.iter_mut().zip(main.iter_mut()).zip(stem) {
+                *cued += sample;
+                if *mode == CueMode::Preview {
+                    *total -= sample;
+                }
+            }
+        }
+        if self.limiter.sample_rate() != sample_rate {
+            let ceiling_db = self.limiter.ceiling_db;
+            self.limiter = Limiter::new(sample_rate);
+            self.limiter.ceiling_db = ceiling_db;
+        }
+        for sample in cue.iter_mut() {
+            *sample = self.limiter.process((*sample * self.level) as f64) as f32;
+        }
+        cue
+    }
+}
+
+static CUE_BUS: OnceLock<Mutex<CueBus>> = OnceLock::new();
+
+pub fn cue_bus() -> &'static Mutex<CueBus> {
+    CUE_BUS.get_or_init(|| Mutex::new(CueBus::default()))
+}
@@ -0,0 +1,162 @@
+/// Audio device settings that can change mid-session: buffer size and sample rate
+///
+/// `Audio.configure(buffer: 128, rate: 48000)` switches without a restart. With nothing
+/// sounding the new settings apply at once; otherwise the master bus fades out, the engine and
+/// device streams switch over in the silence, and the master fades back in. Device streams
+/// watch `generation` and rebuild themselves when it moves.
+use crate::audio::master::MasterBus;
+use crate::audio::synth::{SynthEngine, SYNTH_SAMPLE_RATE};
+use crate::runtime::Value;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+pub const DEFAULT_BUFFER_SIZE: u32 = 512;
+pub const MIN_BUFFER_SIZE: u32 = 16;
+pub const MAX_BUFFER_SIZE: u32 = 8192;
+pub const SUPPORTED_SAMPLE_RATES: [u32; 6] = [22050, 32000, 44100, 48000, 88200, 96000];
+/// Length of each side of the dip while streams are rebuilt
+pub const RECONFIGURE_FADE_SECONDS: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioSettings {
+    /// Frames per device callback
+    pub buffer_size: u32,
+    pub sample_rate: u32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { buffer_size: DEFAULT_BUFFER_SIZE, sample_rate: SYNTH_SAMPLE_RATE as u32 }
+    }
+}
+
+impl AudioSettings {
+    pub fn validate(&self) -> crate::Result<()> {
+        if !self.buffer_size.is_power_of_two() || !(MIN_BUFFER_SIZE..=MAX_BUFFER_SIZE).contains(&self.buffer_size) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::BufferSizeError,
+                format!("🎚️ A buffer of {} frames isn't something audio devices accept", self.buffer_size)
+            )
+            .with_suggestion(format!("Use a power of two from {} to {}: Audio.configure(buffer: 256)", MIN_BUFFER_SIZE, MAX_BUFFER_SIZE)));
+        }
+        if !SUPPORTED_SAMPLE_RATES.contains(&self.sample_rate) {
+            let rates: Vec<String> = SUPPORTED_SAMPLE_RATES.iter().map(|rate| rate.to_string()).collect();
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::SampleRateError,
+                format!("🎚️ {} Hz isn't a supported sample rate", self.sample_rate)
+            )
+            .with_suggestion(format!("Pick one of {}: Audio.configure(rate: 48000)", rates.join(", "))));
+        }
+        Ok(())
+    }
+
+    /// Time one buffer takes to play, the latency the buffer size adds
+    pub fn buffer_latency(&self) -> Duration {
+        Duration::from_secs_f64(self.buffer_size as f64 / self.sample_rate as f64)
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("buffer".to_string(), Value::Integer(self.buffer_size as i64));
+        fields.insert("rate".to_string(), Value::Integer(self.sample_rate as i64));
+        fields.insert("latency_ms".to_string(), Value::Float(self.buffer_latency().as_secs_f64() * 1000.0));
+        Value::Object(fields)
+    }
+}
+
+#[derive(Debug)]
+pub struct AudioDevice {
+    pub settings: AudioSettings,
+    pending: Option<AudioSettings>,
+    /// Master level to come back to once a switch is done
+    resume_gain: f32,
+    /// Bumped on every switch; streams built under an older generation need rebuilding
+    pub generation: u64,
+}
+
+impl Default for AudioDevice {
+    fn default() -> Self {
+        Self { settings: AudioSettings::default(), pending: None, resume_gain: 1.0, generation: 0 }
+    }
+}
+
+impl AudioDevice {
+    /// Settings waiting for the fade-out to finish
+    pub fn pending(&self) -> Option<AudioSettings> {
+        self.pending
+    }
+
+    /// Ask for new settings. Returns true if they took effect straight away, false if a
+    /// crossfade has started and `poll` will finish the switch.
+    pub fn request(&mut self, settings: AudioSettings, engine: &mut SynthEngine, master: &mut MasterBus) -> crate::Result<bool> {
+        settings.validate()?;
+        if settings == self.settings {
+            // Asking for what's already running cancels a switch in progress
+            if self.pending.take().is_some() {
+                master.fade_to(self.resume_gain, RECONFIGURE_FADE_SECONDS);
+            }
+            return Ok(true);
+        }
+        if engine.voices().is_empty() || master.is_silent() {
+            self.pending = None;
+            self.apply(settings, engine, master);
+            return Ok(true);
+        }
+        if self.pending.is_none() {
+            self.resume_gain = master.target();
+        }
+        self.pending = Some(settings);
+        master.fade_to(0.0, RECONFIGURE_FADE_SECONDS);
+        Ok(false)
+    }
+
+    /// Called after every block: once the fade-out reaches silence, switch and fade back in
+    pub fn poll(&mut self, engine: &mut SynthEngine, master: &mut MasterBus) -> bool {
+        let Some(settings) = self.pending else {
+            return false;
+        };
+        if master.gain() > 0.0 {
+            // Something (a released panic) faded back in under us; keep heading for silence
+            if master.target() > 0.0 {
+                master.fade_to(0.0, RECONFIGURE_FADE_SECONDS);
+            }
+            return false;
+        }
+        self.pending = None;
+        self.apply(settings, engine, master);
+        master.fade_to(self.resume_gain, RECONFIGURE_FADE_SECONDS);
+        true
+    }
+
+    /// Where the master should end up after a switch in progress; the panic button sets this
+    pub fn resume_to(&mut self, gain: f32) {
+        self.resume_gain = gain.max(0.0);
+    }
+
+    fn apply(&mut self, settings: AudioSettings, engine: &mut SynthEngine, master: &mut MasterBus) {
+        engine.set_sample_rate(settings.sample_rate as f64);
+        master.set_sample_rate(settings.sample_rate as f64);
+        crate::audio::aggregate::aggregate_device().lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .set_master_rate(settings.sample_rate as f64);
+        self.settings = settings;
+        self.generation += 1;
+        tracing::info!(
+            target: "audio",
+            buffer = settings.buffer_size,
+            rate = settings.sample_rate,
+            "🎚️ audio device reconfigured"
+        );
+    }
+}
+
+static AUDIO_DEVICE: OnceLock<Mutex<AudioDevice>> = OnceLock::new();
+
+pub fn audio_device() -> &'static Mutex<AudioDevice> {
+    AUDIO_DEVICE.get_or_init(|| Mutex::new(AudioDevice::default()))
+}
+
+/// The settings streams should be running with right now
+pub fn audio_settings() -> AudioSettings {
+    audio_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).settings
+}
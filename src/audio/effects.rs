@@ -61,6 +61,33 @@ impl Reverb {
         output /= self.delay_lines.len() as f32;
         input * (1.0 - self.wet_mix) + output * self.wet_mix
     }
+
+    /// From 0, a small room whose echoes die at once, to 1, a hall that rings on
+    pub fn room_size(&self) -> f32 {
+        self.feedback / REVERB_MAX_FEEDBACK
+    }
+
+    pub fn set_room_size(&mut self, size: f32) {
+        self.feedback = size.clamp(0.0, 1.0) * REVERB_MAX_FEEDBACK;
+    }
+
+    pub fn wet_mix(&self) -> f32 {
+        self.wet_mix
+    }
+
+    pub fn set_wet_mix(&mut self, mix: f32) {
+        self.wet_mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+// Feedback at the largest room size; any more and the tail would never die away
+pub const REVERB_MAX_FEEDBACK: f32 = 0.9;
+
+static MASTER_REVERB: std::sync::OnceLock<std::sync::Mutex<Option<Reverb>>> = std::sync::OnceLock::new();
+
+// The reverb `Audio.apply_reverb()` puts between the synths and the master bus
+pub fn master_reverb() -> &'static std::sync::Mutex<Option<Reverb>> {
+    MASTER_REVERB.get_or_init(|| std::sync::Mutex::new(None))
 }
 
 pub struct Filter {
@@ -97,7 +124,7 @@ impl Filter {
 
     pub fn process(&mut self, input: f32) -> f32 {
         let frequency = self.cutoff / (self.sample_rate * 0.5);
-        let frequency = frequency.max(0.001).min(0.999);
+        let frequency = frequency.clamp(0.001, 0.999);
         
         let c = 1.0 / (2.0 * std::f32::consts::PI * frequency).tan();
         let a1 = 1.0 / (1.0 + self.resonance * c + c * c);
@@ -285,6 +312,95 @@ impl MultiTapDelay {
     }
 }
 
+// Delay whose time can be musical: `Audio.delay(time: 1/8.note)` keeps the note length and
+// re-resolves it whenever the tempo changes, gliding to the new time so echoes never jump
+pub const SYNCED_DELAY_MAX_SECONDS: f64 = 4.0;
+pub const SYNCED_DELAY_MAX_FEEDBACK: f32 = 0.95;
+// Fraction of the way to a new delay time covered per sample, about 50ms to settle at 44.1kHz
+const SYNCED_DELAY_GLIDE: f64 = 0.0005;
+
+#[derive(Debug, Clone)]
+pub struct SyncedDelay {
+    pub time: crate::runtime::units::UnitValue,
+    pub feedback: f32,
+    pub wet_mix: f32,
+    buffer: Vec<f32>,
+    write_pos: usize,
+    sample_rate: f32,
+    // Delay in samples that `time` resolves to, and where the read head is on its way there
+    target: f64,
+    current: f64,
+}
+
+impl SyncedDelay {
+    pub fn new(time: crate::runtime::units::UnitValue, sample_rate: f32, bpm: f64, beats_per_bar: f64) -> crate::Result<Self> {
+        let mut delay = Self {
+            time: crate::runtime::units::UnitValue::new(0.0, crate::runtime::units::Unit::Second),
+            feedback: 0.4,
+            wet_mix: 0.5,
+            buffer: vec![0.0; (SYNCED_DELAY_MAX_SECONDS * sample_rate as f64) as usize + 2],
+            write_pos: 0,
+            sample_rate,
+            target: 0.0,
+            current: 0.0,
+        };
+        delay.set_time(time, bpm, beats_per_bar)?;
+        delay.current = delay.target;
+        Ok(delay)
+    }
+
+    pub fn set_time(&mut self, time: crate::runtime::units::UnitValue, bpm: f64, beats_per_bar: f64) -> crate::Result<()> {
+        match time.to_seconds(bpm, beats_per_bar) {
+            Some(seconds) if (0.0..=SYNCED_DELAY_MAX_SECONDS).contains(&seconds) => {}
+            Some(seconds) => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🔊 A {:.2}s delay is longer than the {}s the delay line holds", seconds, SYNCED_DELAY_MAX_SECONDS)
+            )
+            .with_suggestion("Try a shorter time, like 1/8.note or 375.ms")),
+            None => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🔊 {}{} isn't a delay time", time.value, time.unit.to_string())
+            )
+            .with_suggestion("Delay times are seconds, ms, or musical lengths like 1/8.note, 1.beat, 1.bar")),
+        }
+        self.time = time;
+        self.set_tempo(bpm, beats_per_bar);
+        Ok(())
+    }
+
+    // Re-resolve a musical time; called every block so tempo changes land straight away
+    pub fn set_tempo(&mut self, bpm: f64, beats_per_bar: f64) {
+        let seconds = self.time.to_seconds(bpm, beats_per_bar).unwrap_or(0.0).clamp(0.0, SYNCED_DELAY_MAX_SECONDS);
+        self.target = seconds * self.sample_rate as f64;
+    }
+
+    // Current delay in seconds, including any glide in progress
+    pub fn seconds(&self) -> f64 {
+        self.current / self.sample_rate as f64
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.current += (self.target - self.current) * SYNCED_DELAY_GLIDE;
+        let len = self.buffer.len();
+        let read = (self.write_pos as f64 - self.current.max(1.0)).rem_euclid(len as f64);
+        let index = read as usize % len;
+        let fraction = (read - read.floor()) as f32;
+        let delayed = self.buffer[index] + (self.buffer[(index + 1) % len] - self.buffer[index]) * fraction;
+
+        self.buffer[self.write_pos] = input + delayed * self.feedback;
+        self.write_pos = (self.write_pos + 1) % len;
+
+        input * (1.0 - self.wet_mix) + delayed * self.wet_mix
+    }
+}
+
+static MASTER_DELAY: std::sync::OnceLock<std::sync::Mutex<Option<SyncedDelay>>> = std::sync::OnceLock::new();
+
+// The delay `Audio.delay()` puts between the synths and the master bus
+pub fn master_delay() -> &'static std::sync::Mutex<Option<SyncedDelay>> {
+    MASTER_DELAY.get_or_init(|| std::sync::Mutex::new(None))
+}
+
 // Chorus/Flanger with LFO modulation
 pub struct Modulation {
     delay_line: Vec<f32>,
@@ -467,6 +583,12 @@ pub struct BiquadFilter {
     y1: f32, y2: f32,
 }
 
+impl Default for BiquadFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BiquadFilter {
     pub fn new() -> Self {
         Self {
@@ -580,6 +702,12 @@ impl AudioEffect for Reverb {
     fn process(&mut self, input: f32) -> f32 {
         self.process(input)
     }
+
+    fn reset(&mut self) {
+        for line in &mut self.delay_lines {
+            line.buffer.fill(0.0);
+        }
+    }
 }
 
 impl AudioEffect for Filter {
@@ -612,6 +740,33 @@ impl AudioEffect for ParametricEQ {
     }
 }
 
+impl AudioEffect for SyncedDelay {
+    fn process(&mut self, input: f32) -> f32 {
+        self.process(input)
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.current = self.target;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.target *= (sample_rate / self.sample_rate) as f64;
+            self.current = self.target;
+            self.sample_rate = sample_rate;
+            self.buffer = vec![0.0; (SYNCED_DELAY_MAX_SECONDS * sample_rate as f64) as usize + 2];
+            self.write_pos = 0;
+        }
+    }
+}
+
+impl Default for EffectsChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EffectsChain {
     pub fn new() -> Self {
         Self {
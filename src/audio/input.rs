@@ -6,6 +6,8 @@ pub struct AudioInput {
     stream: Option<cpal::Stream>,
     buffer: Arc<RealtimeCircularBuffer>,
     config: cpal::StreamConfig,
+    /// Device settings generation the stream was built for
+    generation: u64,
 }
 
 impl AudioInput {
@@ -15,8 +17,8 @@ impl AudioInput {
             .default_input_device()
             .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::AudioDeviceError, "No input device available"))?;
 
-        let config = device.default_input_config()?;
-        let config = config.into();
+        let mut config: cpal::StreamConfig = device.default_input_config()?.into();
+        let generation = apply_device_settings(&mut config);
         
         let buffer = Arc::new(RealtimeCircularBuffer::new(8192)
             .map_err(|_| crate::errors::synthesis_error(crate::errors::ErrorKind::AudioDeviceError, "Failed to create audio buffer"))?);
@@ -25,9 +27,24 @@ impl AudioInput {
             stream: None,
             buffer,
             config,
+            generation,
         })
     }
 
+    /// Rebuild the stream after `Audio.configure()` changed buffer size or rate.
+    /// Returns true if anything changed.
+    pub fn sync_settings(&mut self) -> crate::Result<bool> {
+        if self.generation == crate::audio::device::audio_device().lock().map(|d| d.generation).unwrap_or(self.generation) {
+            return Ok(false);
+        }
+        self.generation = apply_device_settings(&mut self.config);
+        if self.stream.is_some() {
+            self.stop_capture();
+            self.start_capture()?;
+        }
+        Ok(true)
+    }
+
     pub fn start_capture(&mut self) -> crate::Result<()> {
         let host = cpal::default_host();
         let device = host
@@ -82,8 +99,220 @@ impl AudioInput {
     }
 }
 
+/// Point a stream config at the current device settings; returns their generation
+fn apply_device_settings(config: &mut cpal::StreamConfig) -> u64 {
+    let device = crate::audio::device::audio_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    config.sample_rate = cpal::SampleRate(device.settings.sample_rate);
+    config.buffer_size = cpal::BufferSize::Fixed(device.settings.buffer_size);
+    device.generation
+}
+
 impl Drop for AudioInput {
     fn drop(&mut self) {
         self.stop_capture();
     }
-}
\ No newline at end of file
+}
+
+/// When an input buffer started, with its samples (first channel only)
+type CapturedBuffer = (std::time::Instant, Vec<f32>);
+
+/// One calibration round: play a click on the default output while capturing the default input.
+/// Returns the time from handing the click to the output until it came back, or `None` if the
+/// input never heard it (no loopback cable, or the mic is too far from the speaker).
+pub fn measure_round_trip() -> crate::Result<Option<std::time::Duration>> {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    let host = cpal::default_host();
+    let output_device = host
+        .default_output_device()
+        .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::AudioDeviceError, "No output device available"))?;
+    let input_device = host
+        .default_input_device()
+        .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::AudioDeviceError, "No input device available"))?;
+    let output_config: cpal::StreamConfig = output_device.default_output_config()?.into();
+    let input_config: cpal::StreamConfig = input_device.default_input_config()?.into();
+    if output_config.sample_rate != input_config.sample_rate {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            format!("🎚️ The input runs at {} Hz and the output at {} Hz", input_config.sample_rate.0, output_config.sample_rate.0)
+        )
+        .with_suggestion("Set both devices to the same sample rate in your system's audio settings"));
+    }
+    let sample_rate = output_config.sample_rate.0 as f64;
+
+    let captured: Arc<Mutex<Vec<CapturedBuffer>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&captured);
+    let input_channels = input_config.channels.max(1) as usize;
+    let input_stream = input_device.build_input_stream(
+        &input_config,
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let frames: Vec<f32> = data.iter().step_by(input_channels).copied().collect();
+            let started = Instant::now() - Duration::from_secs_f64(frames.len() as f64 / sample_rate);
+            if let Ok(mut captured) = sink.try_lock() {
+                captured.push((started, frames));
+            }
+        },
+        |err| eprintln!("Audio input error: {}", err),
+        None,
+    )?;
+
+    // Silence first so both streams are running steadily, then the click
+    let click = crate::audio::latency::calibration_click(sample_rate);
+    let click_sent: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+    let sent = Arc::clone(&click_sent);
+    let lead_in = (0.25 * sample_rate) as usize;
+    let output_channels = output_config.channels.max(1) as usize;
+    let mut position = 0usize;
+    let output_stream = output_device.build_output_stream(
+        &output_config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            for frame in data.chunks_mut(output_channels) {
+                if position == lead_in {
+                    if let Ok(mut sent) = sent.try_lock() {
+                        *sent = Some(Instant::now());
+                    }
+                }
+                let sample = position.checked_sub(lead_in).and_then(|i| click.get(i)).copied().unwrap_or(0.0);
+                frame.fill(sample);
+                position += 1;
+            }
+        },
+        |err| eprintln!("Audio output error: {}", err),
+        None,
+    )?;
+
+    input_stream.play()?;
+    output_stream.play()?;
+    std::thread::sleep(Duration::from_millis(900));
+    drop(output_stream);
+    drop(input_stream);
+
+    let Some(sent) = *click_sent.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) else {
+        return Ok(None);
+    };
+    let captured = captured.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let recorded: Vec<f32> = captured.iter().flat_map(|(_, frames)| frames.iter().copied()).collect();
+    let Some(mut index) = crate::audio::latency::detect_click(&recorded) else {
+        return Ok(None);
+    };
+    // Time it from the buffer it landed in, so a dropped buffer earlier on doesn't skew it
+    for (started, frames) in captured.iter() {
+        if index < frames.len() {
+            let heard = *started + Duration::from_secs_f64(index as f64 / sample_rate);
+            return Ok(heard.checked_duration_since(sent));
+        }
+        index -= frames.len();
+    }
+    Ok(None)
+}
+
+/// Open a named device (a case-insensitive part of its name is enough) as an extra input or
+/// output next to the default one. The platform stream lives on its own thread until the
+/// device is closed; its callbacks trade audio with the aggregate device's queue.
+/// Returns the full device name and its sample rate.
+pub fn open_device_stream(name: &str, direction: crate::audio::aggregate::DeviceDirection) -> crate::Result<(String, f64)> {
+    use crate::audio::aggregate::DeviceDirection;
+
+    let host = cpal::default_host();
+    let wanted = name.to_lowercase();
+    let mut devices: Box<dyn Iterator<Item = cpal::Device>> = match direction {
+        DeviceDirection::Input => Box::new(host.input_devices()?),
+        DeviceDirection::Output => Box::new(host.output_devices()?),
+    };
+    let device = devices
+        .find(|device| device.name().map(|n| n.to_lowercase().contains(&wanted)).unwrap_or(false))
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            format!("🎚️ No {} device matching \"{}\"", direction.as_str(), name)
+        )
+        .with_suggestion("Check the device is plugged in; part of its name is enough, e.g. Audio.open_output(\"HDMI\")"))?;
+    let full_name = device.name().unwrap_or_else(|_| name.to_string());
+    let config: cpal::StreamConfig = match direction {
+        DeviceDirection::Input => device.default_input_config()?.into(),
+        DeviceDirection::Output => device.default_output_config()?.into(),
+    };
+    start_device_stream(device, full_name, config, direction)
+}
+
+/// Run `device` as an aggregate stream called `full_name`. An input stream on an output device
+/// records what it plays, where the platform allows that (WASAPI loopback)
+pub(crate) fn start_device_stream(
+    device: cpal::Device,
+    full_name: String,
+    config: cpal::StreamConfig,
+    direction: crate::audio::aggregate::DeviceDirection,
+) -> crate::Result<(String, f64)> {
+    use crate::audio::aggregate::{aggregate_device, DeviceDirection};
+    use std::sync::atomic::Ordering;
+
+    let sample_rate = config.sample_rate.0 as f64;
+    let alive = aggregate_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        .open(&full_name, direction, sample_rate)?
+        .alive
+        .clone();
+
+    let callback_name = full_name.clone();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let channels = config.channels.max(1) as usize;
+        let built = match direction {
+            DeviceDirection::Output => device.build_output_stream(
+                &config,
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    let mut mono = vec![0.0f32; data.len() / channels];
+                    // Never wait on the engine from the audio thread: a missed lock plays silence
+                    if let Ok(mut aggregate) = aggregate_device().try_lock() {
+                        if let Some(stream) = aggregate.stream_mut(&callback_name, direction) {
+                            stream.pop(&mut mono);
+                        }
+                    }
+                    for (frame, sample) in data.chunks_mut(channels).zip(&mono) {
+                        frame.fill(*sample);
+                    }
+                },
+                |err| eprintln!("Audio output error: {}", err),
+                None,
+            ),
+            DeviceDirection::Input => device.build_input_stream(
+                &config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    let mono: Vec<f32> = data.iter().step_by(channels).copied().collect();
+                    if let Ok(mut aggregate) = aggregate_device().try_lock() {
+                        if let Some(stream) = aggregate.stream_mut(&callback_name, direction) {
+                            stream.push(&mono);
+                        }
+                    }
+                },
+                |err| eprintln!("Audio input error: {}", err),
+                None,
+            ),
+        };
+        let stream = match built.map_err(crate::errors::SynthesisError::from).and_then(|stream| {
+            stream.play()?;
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(error) => {
+                let _ = ready_tx.send(Err(error));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        while alive.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        drop(stream);
+    });
+
+    let started = ready_rx.recv().unwrap_or_else(|_| Err(crate::errors::synthesis_error(
+        crate::errors::ErrorKind::AudioDeviceError,
+        format!("🎚️ The stream thread for {} stopped before starting", full_name)
+    )));
+    if let Err(error) = started {
+        aggregate_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).close(&full_name);
+        return Err(error);
+    }
+    tracing::info!(target: "audio", device = %full_name, direction = direction.as_str(), sample_rate, "🎚️ device opened");
+    Ok((full_name, sample_rate))
+}
@@ -0,0 +1,128 @@
+/// Round-trip latency calibration and the compensation it feeds
+///
+/// `synthesis calibrate` plays clicks through the output, listens for them on the input
+/// (a cable or a mic near the speaker) and stores the median round trip. Input streams then
+/// report their data as that much older, so anything lined up against the clock hears the
+/// room when it actually happened rather than when the samples arrived.
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+pub const CALIBRATION_ROUNDS: usize = 8;
+/// Length of one calibration click in seconds
+const CLICK_SECONDS: f64 = 0.002;
+/// A click has to rise this far above the input's noise floor to count
+const CLICK_THRESHOLD: f32 = 8.0;
+/// Rounds that disagree by more than this are reported as unreliable
+const MAX_SPREAD: Duration = Duration::from_millis(5);
+
+/// A short, bright click: a 2ms burst at 2kHz with a raised-cosine envelope
+pub fn calibration_click(sample_rate: f64) -> Vec<f32> {
+    let length = (CLICK_SECONDS * sample_rate).round().max(1.0) as usize;
+    (0..length)
+        .map(|i| {
+            let phase = i as f64 / sample_rate;
+            let envelope = 0.5 - 0.5 * (std::f64::consts::TAU * i as f64 / length as f64).cos();
+            (0.8 * envelope * (std::f64::consts::TAU * 2000.0 * phase).sin()) as f32
+        })
+        .collect()
+}
+
+/// Index of the first sample where the click arrives, if it stands out from the noise
+pub fn detect_click(recorded: &[f32]) -> Option<usize> {
+    if recorded.is_empty() {
+        return None;
+    }
+    // The median level is the noise floor; a click is a handful of samples, so it barely moves it
+    let mut levels: Vec<f32> = recorded.iter().map(|s| s.abs()).collect();
+    let middle = levels.len() / 2;
+    let noise = *levels.select_nth_unstable_by(middle, f32::total_cmp).1;
+    let threshold = (noise * CLICK_THRESHOLD).max(0.01);
+    recorded.iter().position(|s| s.abs() > threshold)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyCalibration {
+    /// Median round trip, output to input
+    pub round_trip: Duration,
+    pub measurements: Vec<Duration>,
+}
+
+impl LatencyCalibration {
+    /// Median of the rounds that heard their click; `None` if none did
+    pub fn from_measurements(mut measurements: Vec<Duration>) -> Option<Self> {
+        if measurements.is_empty() {
+            return None;
+        }
+        measurements.sort();
+        let round_trip = measurements[measurements.len() / 2];
+        Some(Self { round_trip, measurements })
+    }
+
+    /// Rounds that agree to within a few milliseconds; anything else means an unstable setup
+    pub fn is_consistent(&self) -> bool {
+        match (self.measurements.first(), self.measurements.last()) {
+            (Some(low), Some(high)) => *high - *low <= MAX_SPREAD,
+            _ => false,
+        }
+    }
+}
+
+/// Where the stored compensation lives: `$SYNTHESIS_CONFIG_DIR`, else `~/.config/synthesis`
+pub fn latency_file() -> PathBuf {
+    let dir = match std::env::var_os("SYNTHESIS_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".config").join("synthesis"))
+            .unwrap_or_else(|| PathBuf::from(".")),
+    };
+    dir.join("latency.toml")
+}
+
+/// The compensation applied to input streams; zero until a calibration has been stored
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyCompensation {
+    pub round_trip: Duration,
+}
+
+impl LatencyCompensation {
+    pub fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(latency_file()) else {
+            return Self::default();
+        };
+        let round_trip_ms = text.parse::<toml::Table>().ok()
+            .and_then(|table| table.get("round_trip_ms").and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64))))
+            .filter(|ms| ms.is_finite() && *ms >= 0.0)
+            .unwrap_or(0.0);
+        Self { round_trip: Duration::from_secs_f64(round_trip_ms / 1000.0) }
+    }
+
+    pub fn save(&self) -> std::io::Result<PathBuf> {
+        let path = latency_file();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = format!(
+            "# Written by `synthesis calibrate`; input streams are shifted back by this much\nround_trip_ms = {:.3}\n",
+            self.round_trip.as_secs_f64() * 1000.0
+        );
+        std::fs::write(&path, text)?;
+        Ok(path)
+    }
+
+    pub fn samples(&self, sample_rate: f64) -> usize {
+        (self.round_trip.as_secs_f64() * sample_rate).round() as usize
+    }
+}
+
+static LATENCY_COMPENSATION: OnceLock<Mutex<LatencyCompensation>> = OnceLock::new();
+
+pub fn latency_compensation() -> &'static Mutex<LatencyCompensation> {
+    LATENCY_COMPENSATION.get_or_init(|| Mutex::new(LatencyCompensation::load()))
+}
+
+/// The stored round trip, for clocks that need to line input up with output
+pub fn input_latency() -> Duration {
+    latency_compensation().lock().map(|c| c.round_trip).unwrap_or_default()
+}
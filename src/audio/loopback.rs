@@ -0,0 +1,82 @@
+/// Capturing what the machine itself is playing, so visuals can follow a music player, a
+/// browser tab, or anything else and not only the microphone
+///
+/// Windows (WASAPI) can record any output directly. Elsewhere the system has to offer its
+/// output as an input first: PulseAudio and PipeWire have a "Monitor of ..." source for every
+/// output, and macOS needs a virtual device such as BlackHole fed by a Multi-Output Device.
+use cpal::traits::{DeviceTrait, HostTrait};
+
+/// Parts of input device names that mean "what the system plays", most dependable first
+pub const LOOPBACK_MARKERS: &[&str] = &[
+    "monitor",
+    "blackhole",
+    "loopback",
+    "soundflower",
+    "stereo mix",
+    "what u hear",
+    "wave out mix",
+];
+
+/// The input to capture from: the one matching `wanted` if given, otherwise the first that
+/// looks like a loopback or monitor device
+pub fn pick_loopback_device<'a>(names: &'a [String], wanted: Option<&str>) -> Option<&'a str> {
+    let lowercase: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+    let position = match wanted {
+        Some(wanted) => {
+            let wanted = wanted.to_lowercase();
+            lowercase.iter().position(|name| name.contains(&wanted))
+        }
+        None => LOOPBACK_MARKERS.iter()
+            .find_map(|marker| lowercase.iter().position(|name| name.contains(marker))),
+    };
+    position.map(|index| names[index].as_str())
+}
+
+/// How to make the system's output capturable on this platform
+pub fn loopback_setup_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Install BlackHole (brew install blackhole-2ch), create a Multi-Output Device with your speakers and BlackHole in Audio MIDI Setup, and play through it"
+    } else if cfg!(target_os = "linux") {
+        "Pick \"Monitor of ...\" for this app in pavucontrol's Recording tab, or run: pactl set-default-source $(pactl get-default-sink).monitor"
+    } else if cfg!(target_os = "windows") {
+        "Check an output device is enabled in Sound settings; Audio.loopback(\"Speakers\") picks one by name"
+    } else {
+        "Route your system output into a virtual input device, then pass its name: Audio.loopback(\"Loopback\")"
+    }
+}
+
+/// Start capturing system output as an extra input; returns the stream's name and sample rate.
+/// `wanted` narrows the choice to a device whose name contains it
+pub fn open_loopback_stream(wanted: Option<&str>) -> crate::Result<(String, f64)> {
+    use crate::audio::aggregate::DeviceDirection;
+
+    let host = cpal::default_host();
+    if cfg!(target_os = "windows") {
+        // WASAPI records an output when it's opened as an input
+        let device = match wanted {
+            Some(wanted) => {
+                let wanted = wanted.to_lowercase();
+                host.output_devices()?
+                    .find(|device| device.name().map(|n| n.to_lowercase().contains(&wanted)).unwrap_or(false))
+            }
+            None => host.default_output_device(),
+        };
+        if let Some(device) = device {
+            let name = format!("{} (loopback)", device.name().unwrap_or_else(|_| "Output".to_string()));
+            let config: cpal::StreamConfig = device.default_output_config()?.into();
+            return crate::audio::input::start_device_stream(device, name, config, DeviceDirection::Input);
+        }
+    }
+
+    let names: Vec<String> = host.input_devices()?.filter_map(|device| device.name().ok()).collect();
+    let Some(name) = pick_loopback_device(&names, wanted) else {
+        let message = match wanted {
+            Some(wanted) => format!("🎚️ No device matching \"{}\" to capture system audio from", wanted),
+            None => "🎚️ Nothing on this machine offers its sound output as an input yet".to_string(),
+        };
+        return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::AudioDeviceError, message)
+            .with_suggestion(loopback_setup_hint())
+            .with_suggestion("Audio.mic_input() still works for sound in the room"));
+    };
+    crate::audio::input::open_device_stream(name, DeviceDirection::Input)
+}
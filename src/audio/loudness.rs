@@ -0,0 +1,334 @@
+/// Master output safety: BS.1770 loudness, true peak, a brickwall limiter and a clip watchdog
+///
+/// All of it runs per sample on the master bus, after the panic fade. The meter reads the
+/// limited signal (what reaches the speakers); the clip watchdog reads it before the limiter,
+/// so a script that only sounds fine because the limiter is catching it still gets told.
+use std::collections::VecDeque;
+
+/// Quietest reading the meters report; BS.1770's absolute gate
+pub const LOUDNESS_FLOOR: f64 = -70.0;
+pub const DEFAULT_LIMITER_CEILING_DB: f64 = -1.0;
+pub const DEFAULT_LIMITER_RELEASE: f64 = 0.1;
+
+/// Meters and the clip watchdog work in 100ms blocks
+const BLOCK_SECONDS: f64 = 0.1;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+/// Clipping in this share of the last three seconds counts as persistent
+const PERSISTENT_CLIP_SHARE: f64 = 0.5;
+/// Blocks between repeated clip warnings (ten seconds)
+const CLIP_WARNING_INTERVAL: usize = 100;
+
+const TRUE_PEAK_OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS: usize = 12;
+
+pub fn db_to_gain(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+pub fn gain_to_db(gain: f64) -> f64 {
+    if gain > 0.0 { 20.0 * gain.log10() } else { f64::NEG_INFINITY }
+}
+
+fn energy_to_lufs(mean_square: f64) -> f64 {
+    if mean_square > 0.0 {
+        (-0.691 + 10.0 * mean_square.log10()).max(LOUDNESS_FLOOR)
+    } else {
+        LOUDNESS_FLOOR
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The BS.1770 K-weighting pre-filter (head shelf plus high-pass), for any sample rate
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    let k = (std::f64::consts::PI * 1681.974450955533 / sample_rate).tan();
+    let q = 0.7071752369554196;
+    let vh = 10f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Default::default()
+    };
+
+    let k = (std::f64::consts::PI * 38.13547087602444 / sample_rate).tan();
+    let q = 0.5003270373238773;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ..Default::default()
+    };
+    [shelf, high_pass]
+}
+
+/// Momentary, short-term and gated integrated loudness in LUFS
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    filters: [Biquad; 2],
+    block_len: usize,
+    block_energy: f64,
+    block_fill: usize,
+    /// Mean square of recent 100ms blocks, newest last
+    blocks: VecDeque<f64>,
+    /// Mean square of every 400ms gating window so far, for the integrated reading
+    gating_windows: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            filters: k_weighting(sample_rate),
+            block_len: ((sample_rate * BLOCK_SECONDS) as usize).max(1),
+            block_energy: 0.0,
+            block_fill: 0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            gating_windows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sample: f64) {
+        let shelved = self.filters[0].process(sample);
+        let weighted = self.filters[1].process(shelved);
+        self.block_energy += weighted * weighted;
+        self.block_fill += 1;
+        if self.block_fill == self.block_len {
+            self.blocks.push_back(self.block_energy / self.block_len as f64);
+            if self.blocks.len() > SHORT_TERM_BLOCKS {
+                self.blocks.pop_front();
+            }
+            if self.blocks.len() >= MOMENTARY_BLOCKS {
+                self.gating_windows.push(self.mean_of_last(MOMENTARY_BLOCKS));
+            }
+            self.block_energy = 0.0;
+            self.block_fill = 0;
+        }
+    }
+
+    fn mean_of_last(&self, count: usize) -> f64 {
+        let count = count.min(self.blocks.len());
+        if count == 0 {
+            return 0.0;
+        }
+        self.blocks.iter().rev().take(count).sum::<f64>() / count as f64
+    }
+
+    pub fn momentary(&self) -> f64 {
+        energy_to_lufs(self.mean_of_last(MOMENTARY_BLOCKS))
+    }
+
+    pub fn short_term(&self) -> f64 {
+        energy_to_lufs(self.mean_of_last(SHORT_TERM_BLOCKS))
+    }
+
+    /// Programme loudness with the absolute (-70 LUFS) and relative (-10 LU) gates
+    pub fn integrated(&self) -> f64 {
+        let gated_mean = |threshold: f64| {
+            let above: Vec<f64> = self.gating_windows.iter()
+                .copied()
+                .filter(|energy| energy_to_lufs(*energy) > threshold)
+                .collect();
+            if above.is_empty() { 0.0 } else { above.iter().sum::<f64>() / above.len() as f64 }
+        };
+        let relative_gate = energy_to_lufs(gated_mean(LOUDNESS_FLOOR)) - 10.0;
+        energy_to_lufs(gated_mean(relative_gate.max(LOUDNESS_FLOOR)))
+    }
+
+    pub fn reset(&mut self) {
+        self.blocks.clear();
+        self.gating_windows.clear();
+        self.block_energy = 0.0;
+        self.block_fill = 0;
+    }
+}
+
+/// Peak level between samples, found by 4x polyphase oversampling
+#[derive(Debug, Clone)]
+pub struct TruePeakMeter {
+    /// One windowed-sinc filter per oversampling phase
+    phases: Vec<[f64; TRUE_PEAK_TAPS]>,
+    history: VecDeque<f64>,
+    peak: f64,
+}
+
+impl Default for TruePeakMeter {
+    fn default() -> Self {
+        let length = TRUE_PEAK_OVERSAMPLING * TRUE_PEAK_TAPS;
+        let centre = (length - 1) as f64 / 2.0;
+        let taps: Vec<f64> = (0..length)
+            .map(|n| {
+                let t = (n as f64 - centre) / TRUE_PEAK_OVERSAMPLING as f64;
+                let sinc = if t == 0.0 { 1.0 } else { (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t) };
+                let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * (n as f64 + 0.5) / length as f64).cos();
+                sinc * window
+            })
+            .collect();
+        let phases = (0..TRUE_PEAK_OVERSAMPLING)
+            .map(|phase| {
+                let mut filter = [0.0; TRUE_PEAK_TAPS];
+                for (k, tap) in filter.iter_mut().enumerate() {
+                    *tap = taps[phase + k * TRUE_PEAK_OVERSAMPLING];
+                }
+                // Unity gain per phase so a DC level reads as itself
+                let sum: f64 = filter.iter().sum();
+                filter.iter_mut().for_each(|tap| *tap /= sum);
+                filter
+            })
+            .collect();
+        Self { phases, history: VecDeque::from(vec![0.0; TRUE_PEAK_TAPS]), peak: 0.0 }
+    }
+}
+
+impl TruePeakMeter {
+    pub fn push(&mut self, sample: f64) {
+        self.history.pop_back();
+        self.history.push_front(sample);
+        for filter in &self.phases {
+            let value: f64 = filter.iter().zip(&self.history).map(|(tap, x)| tap * x).sum();
+            self.peak = self.peak.max(value.abs());
+        }
+    }
+
+    /// Highest true peak since the last reset, in dBTP
+    pub fn peak_db(&self) -> f64 {
+        gain_to_db(self.peak).max(LOUDNESS_FLOOR)
+    }
+
+    pub fn reset(&mut self) {
+        self.peak = 0.0;
+    }
+}
+
+/// Brickwall limiter: instant attack so no sample passes the ceiling, smooth release
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    pub enabled: bool,
+    pub ceiling_db: f64,
+    pub release: f64,
+    sample_rate: f64,
+    gain: f64,
+    release_coefficient: f64,
+    /// Deepest gain reduction since the last read, in dB (positive)
+    max_reduction_db: f64,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f64) -> Self {
+        let mut limiter = Self {
+            enabled: true,
+            ceiling_db: DEFAULT_LIMITER_CEILING_DB,
+            release: DEFAULT_LIMITER_RELEASE,
+            sample_rate,
+            gain: 1.0,
+            release_coefficient: 0.0,
+            max_reduction_db: 0.0,
+        };
+        limiter.set_release(DEFAULT_LIMITER_RELEASE);
+        limiter
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    pub fn set_release(&mut self, seconds: f64) {
+        self.release = seconds.max(0.001);
+        self.release_coefficient = 1.0 - (-1.0 / (self.release * self.sample_rate)).exp();
+    }
+
+    pub fn process(&mut self, sample: f64) -> f64 {
+        if !self.enabled {
+            return sample;
+        }
+        let ceiling = db_to_gain(self.ceiling_db);
+        let wanted = if sample.abs() > ceiling { ceiling / sample.abs() } else { 1.0 };
+        self.gain = if wanted < self.gain { wanted } else { self.gain + (wanted - self.gain) * self.release_coefficient };
+        self.max_reduction_db = self.max_reduction_db.max(-gain_to_db(self.gain));
+        sample * self.gain
+    }
+
+    /// Current gain reduction in dB (0 when idle)
+    pub fn reduction_db(&self) -> f64 {
+        -gain_to_db(self.gain)
+    }
+
+    pub fn take_max_reduction_db(&mut self) -> f64 {
+        std::mem::take(&mut self.max_reduction_db)
+    }
+}
+
+/// Notices a script that keeps driving the master past 0 dBFS and says so, without flooding the log
+#[derive(Debug, Clone)]
+pub struct ClipMonitor {
+    block_len: usize,
+    block_fill: usize,
+    block_clipped: bool,
+    recent: VecDeque<bool>,
+    since_warning: usize,
+    pub clipped_samples: u64,
+    pub warnings: u64,
+}
+
+impl ClipMonitor {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            block_len: ((sample_rate * BLOCK_SECONDS) as usize).max(1),
+            block_fill: 0,
+            block_clipped: false,
+            recent: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            since_warning: CLIP_WARNING_INTERVAL,
+            clipped_samples: 0,
+            warnings: 0,
+        }
+    }
+
+    /// Returns true when this sample completed a block that tipped clipping into persistent
+    pub fn push(&mut self, sample: f64) -> bool {
+        if sample.abs() > 1.0 {
+            self.clipped_samples += 1;
+            self.block_clipped = true;
+        }
+        self.block_fill += 1;
+        if self.block_fill < self.block_len {
+            return false;
+        }
+
+        self.recent.push_back(std::mem::take(&mut self.block_clipped));
+        if self.recent.len() > SHORT_TERM_BLOCKS {
+            self.recent.pop_front();
+        }
+        self.block_fill = 0;
+        self.since_warning = self.since_warning.saturating_add(1);
+        if self.is_persistent() && self.since_warning >= CLIP_WARNING_INTERVAL {
+            self.since_warning = 0;
+            self.warnings += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Clipping in at least half of the last three seconds
+    pub fn is_persistent(&self) -> bool {
+        let clipped = self.recent.iter().filter(|clipped| **clipped).count();
+        self.recent.len() >= MOMENTARY_BLOCKS
+            && clipped as f64 >= PERSISTENT_CLIP_SHARE * SHORT_TERM_BLOCKS.min(self.recent.len()) as f64
+    }
+}
@@ -0,0 +1,172 @@
+/// The master bus: the last stage before the audio device
+///
+/// Gain changes ramp per sample so muting never clicks. The panic button fades this to silence
+/// over `PANIC_FADE_SECONDS`; offline renders like `Synth.render` bypass it on purpose.
+/// After the fade comes the safety limiter (on unless `config { limiter: false }`) and metering.
+use crate::audio::loudness::{ClipMonitor, Limiter, LoudnessMeter, TruePeakMeter};
+use crate::runtime::Value;
+use std::sync::{Mutex, OnceLock};
+
+pub const MASTER_SAMPLE_RATE: f64 = 44100.0;
+pub const PANIC_FADE_SECONDS: f64 = 0.02;
+
+/// What the master meters read, for `Audio.loudness()` and the GUI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessReading {
+    pub momentary: f64,
+    pub short_term: f64,
+    pub integrated: f64,
+    pub true_peak: f64,
+    /// Deepest limiter gain reduction since the last reading, in dB
+    pub gain_reduction: f64,
+    pub limiter: bool,
+    pub clipping: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MasterBus {
+    pub sample_rate: f64,
+    gain: f32,
+    target: f32,
+    /// Gain change per sample while ramping
+    step: f32,
+    pub limiter: Limiter,
+    meter: LoudnessMeter,
+    true_peak: TruePeakMeter,
+    clips: ClipMonitor,
+}
+
+impl Default for MasterBus {
+    fn default() -> Self {
+        Self::new(MASTER_SAMPLE_RATE)
+    }
+}
+
+impl MasterBus {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate,
+            gain: 1.0,
+            target: 1.0,
+            step: 0.0,
+            limiter: Limiter::new(sample_rate),
+            meter: LoudnessMeter::new(sample_rate),
+            true_peak: TruePeakMeter::default(),
+            clips: ClipMonitor::new(sample_rate),
+        }
+    }
+
+    /// Apply `config { limiter: false }` or `config { limiter: { ceiling: -2, release: 0.2 } }`
+    pub fn apply_limiter_config(&mut self, config: &Value) -> crate::Result<()> {
+        match config {
+            Value::Boolean(enabled) => self.limiter.enabled = *enabled,
+            Value::Object(fields) => {
+                self.limiter.enabled = fields.get("enabled").map(|v| v.is_truthy()).unwrap_or(true);
+                if let Some(ceiling) = fields.get("ceiling").and_then(|v| v.as_number()) {
+                    self.limiter.ceiling_db = ceiling.min(0.0);
+                }
+                if let Some(release) = fields.get("release").and_then(|v| v.as_number()) {
+                    self.limiter.set_release(release);
+                }
+            }
+            _ => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                "🔊 config limiter should be true, false or { ceiling, release }"
+            )
+            .with_suggestion("Try: config { limiter: { ceiling: -1, release: 0.1 } }")),
+        }
+        if !self.limiter.enabled {
+            tracing::warn!(target: "audio", "🔊 master limiter disabled: nothing stops a coding mistake reaching the speakers at full scale");
+        }
+        Ok(())
+    }
+
+    /// Read the meters; gain reduction restarts from zero after each reading
+    pub fn reading(&mut self) -> LoudnessReading {
+        LoudnessReading {
+            momentary: self.meter.momentary(),
+            short_term: self.meter.short_term(),
+            integrated: self.meter.integrated(),
+            true_peak: self.true_peak.peak_db(),
+            gain_reduction: self.limiter.take_max_reduction_db(),
+            limiter: self.limiter.enabled,
+            clipping: self.clips.is_persistent(),
+        }
+    }
+
+    /// Start integrated loudness and the true-peak hold over
+    pub fn reset_meters(&mut self) {
+        self.meter.reset();
+        self.true_peak.reset();
+    }
+
+    /// Rebuild the rate-dependent stages for a new device rate, keeping gain and limiter settings.
+    /// Meters start over: readings taken at two rates don't add up.
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        let mut limiter = Limiter::new(sample_rate);
+        limiter.enabled = self.limiter.enabled;
+        limiter.ceiling_db = self.limiter.ceiling_db;
+        limiter.set_release(self.limiter.release);
+        // Keep any fade in progress taking the same time
+        self.step *= (self.sample_rate / sample_rate) as f32;
+        self.sample_rate = sample_rate;
+        self.limiter = limiter;
+        self.meter = LoudnessMeter::new(sample_rate);
+        self.true_peak = TruePeakMeter::default();
+        self.clips = ClipMonitor::new(sample_rate);
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Ramp linearly to `target` over `seconds`
+    pub fn fade_to(&mut self, target: f32, seconds: f64) {
+        let samples = (seconds.max(0.0) * self.sample_rate).round().max(1.0) as f32;
+        self.target = target.max(0.0);
+        self.step = (self.target - self.gain).abs() / samples;
+    }
+
+    /// Fully faded out, not just on the way down
+    pub fn is_silent(&self) -> bool {
+        self.gain == 0.0 && self.target == 0.0
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            if self.gain != self.target {
+                self.gain = if self.gain < self.target {
+                    (self.gain + self.step).min(self.target)
+                } else {
+                    (self.gain - self.step).max(self.target)
+                };
+            }
+            let faded = (*sample * self.gain) as f64;
+            if self.clips.push(faded) {
+                tracing::warn!(
+                    target: "audio",
+                    clipped_samples = self.clips.clipped_samples,
+                    limiter = self.limiter.enabled,
+                    "🔊 master output keeps clipping: turn the script's levels down"
+                );
+            }
+            let limited = self.limiter.process(faded);
+            self.meter.push(limited);
+            self.true_peak.push(limited);
+            *sample = limited as f32;
+        }
+    }
+}
+
+static MASTER_BUS: OnceLock<Mutex<MasterBus>> = OnceLock::new();
+
+pub fn master_bus() -> &'static Mutex<MasterBus> {
+    MASTER_BUS.get_or_init(|| Mutex::new(MasterBus::default()))
+}
@@ -0,0 +1,128 @@
+/// MIDI output: connected ports register a sink and receive raw messages
+///
+/// Device backends plug in through `MidiSink`; with nothing registered, sends are no-ops.
+/// Input backends hand every incoming message to `receive_midi`, which keeps the latest
+/// value of each controller and queues note-ons and control changes for whoever reads them.
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+/// Control change numbers for the channel mode messages the panic button sends
+pub const ALL_SOUND_OFF: u8 = 120;
+pub const ALL_NOTES_OFF: u8 = 123;
+
+pub trait MidiSink: Send {
+    fn name(&self) -> &str;
+    fn send(&mut self, message: &[u8]) -> crate::Result<()>;
+}
+
+static MIDI_OUTPUTS: OnceLock<Mutex<Vec<Box<dyn MidiSink>>>> = OnceLock::new();
+
+pub fn midi_outputs() -> &'static Mutex<Vec<Box<dyn MidiSink>>> {
+    MIDI_OUTPUTS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+pub fn register_midi_output(sink: Box<dyn MidiSink>) {
+    midi_outputs().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(sink);
+}
+
+/// All-sound-off then all-notes-off on every channel, for gear that only honours one of them
+pub fn all_notes_off_messages() -> Vec<[u8; 3]> {
+    (0..16u8)
+        .flat_map(|channel| [[0xB0 | channel, ALL_SOUND_OFF, 0], [0xB0 | channel, ALL_NOTES_OFF, 0]])
+        .collect()
+}
+
+/// Send to every output; a failing port is logged and skipped so the others still get it
+pub fn broadcast_midi(messages: &[[u8; 3]]) -> usize {
+    let mut outputs = midi_outputs().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut sent = 0;
+    for output in outputs.iter_mut() {
+        for message in messages {
+            match output.send(message) {
+                Ok(()) => sent += 1,
+                Err(error) => {
+                    tracing::warn!(target: "midi", port = output.name(), %error, "🎹 MIDI send failed");
+                    break;
+                }
+            }
+        }
+    }
+    sent
+}
+
+/// Send only to outputs whose port name contains `port`, like LED updates for one controller
+pub fn send_midi_to(port: &str, messages: &[[u8; 3]]) -> usize {
+    let mut outputs = midi_outputs().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut sent = 0;
+    for output in outputs.iter_mut().filter(|output| output.name().contains(port)) {
+        for message in messages {
+            match output.send(message) {
+                Ok(()) => sent += 1,
+                Err(error) => {
+                    tracing::warn!(target: "midi", port = output.name(), %error, "🎹 MIDI send failed");
+                    break;
+                }
+            }
+        }
+    }
+    sent
+}
+
+static MIDI_CONTROLS: OnceLock<Mutex<HashMap<(u8, u8), u8>>> = OnceLock::new();
+
+/// Latest value of every control change seen, keyed by (channel, controller)
+pub fn midi_controls() -> &'static Mutex<HashMap<(u8, u8), u8>> {
+    MIDI_CONTROLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Note-ons and control changes waiting to be read, oldest first
+const MAX_QUEUED_MESSAGES: usize = 256;
+
+static MIDI_INPUT: OnceLock<Mutex<VecDeque<[u8; 3]>>> = OnceLock::new();
+
+fn midi_input() -> &'static Mutex<VecDeque<[u8; 3]>> {
+    MIDI_INPUT.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+fn queue_input(message: [u8; 3]) {
+    let mut queue = midi_input().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if queue.len() >= MAX_QUEUED_MESSAGES {
+        queue.pop_front();
+    }
+    queue.push_back(message);
+}
+
+/// Entry point for input backends: control changes update the controller table, and they
+/// and note-ons queue up for `take_midi_input`; other messages are ignored for now
+pub fn receive_midi(message: &[u8]) {
+    if let [status, data, value, ..] = *message {
+        let message = [status, data & 0x7F, value & 0x7F];
+        match status & 0xF0 {
+            0xB0 => {
+                midi_controls()
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .insert((status & 0x0F, data & 0x7F), value & 0x7F);
+                queue_input(message);
+            }
+            // Velocity 0 is a note-off in disguise
+            0x90 if value > 0 => queue_input(message),
+            _ => {}
+        }
+    }
+}
+
+/// Note-ons and control changes received since the last call
+pub fn take_midi_input() -> Vec<[u8; 3]> {
+    midi_input().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).drain(..).collect()
+}
+
+/// A controller's position scaled to 0..1, or None if it hasn't moved since startup.
+/// Channels count from 0 here, as on the wire.
+pub fn control_value(channel: u8, controller: u8) -> Option<f64> {
+    midi_controls()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&(channel, controller))
+        .map(|value| *value as f64 / 127.0)
+}
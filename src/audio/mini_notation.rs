@@ -0,0 +1,336 @@
+/// TidalCycles/Strudel-style mini-notation: a whole rhythm in one string
+///
+/// ```text
+/// "bd ~ sn [hh hh]"     four steps per cycle, the last split in two
+/// "<bd cp> sn"          alternate bd and cp on successive cycles
+/// "bd(3,8) hh*2 sn/2"   euclidean 3-in-8, twice as fast, every other cycle
+/// "hh? oh?0.2"          drop hh half the time, oh 20% of the time
+/// "[bd, hh hh hh]"      layers played at the same time
+/// ```
+///
+/// Patterns are queried one cycle at a time; events are placed in cycle time (0 = start of the
+/// cycle, 1 = start of the next), so the caller decides how long a cycle lasts.
+use crate::errors::{synthesis_error, ErrorKind, SynthesisError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternNode {
+    /// A sound or note name, like `bd`, `sn:2` or `c4`
+    Atom(String),
+    Rest,
+    /// Steps sharing the time span equally
+    Sequence(Vec<PatternNode>),
+    /// `<a b c>`: one child per cycle, in turn
+    Alternate(Vec<PatternNode>),
+    /// `[a, b]`: layers playing at once
+    Stack(Vec<PatternNode>),
+    /// `x*n`: repeat n times within the step
+    Fast(Box<PatternNode>, u32),
+    /// `x/n`: stretch over n cycles
+    Slow(Box<PatternNode>, u32),
+    /// `x(hits,steps,rotation)`
+    Euclid { node: Box<PatternNode>, hits: u32, steps: u32, rotation: u32 },
+    /// `x?` or `x?0.3`: drop events with this probability
+    Chance(Box<PatternNode>, f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternEvent {
+    pub value: String,
+    /// Onset within the cycle, 0..1
+    pub start: f64,
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MiniPattern {
+    pub source: String,
+    root: PatternNode,
+}
+
+impl MiniPattern {
+    pub fn parse(source: &str) -> crate::Result<Self> {
+        let mut parser = NotationParser { chars: source.chars().collect(), pos: 0 };
+        let root = parser.layers(None)?;
+        Ok(Self { source: source.to_string(), root })
+    }
+
+    pub fn root(&self) -> &PatternNode {
+        &self.root
+    }
+
+    /// Events starting in the given cycle, in order of onset
+    pub fn query(&self, cycle: u64) -> Vec<PatternEvent> {
+        let mut events = Vec::new();
+        render(&self.root, cycle, 0.0, 1.0, &mut events);
+        events.sort_by(|a, b| a.start.total_cmp(&b.start));
+        events
+    }
+
+    /// Events with an onset between two points in cycle time (`from` inclusive), as absolute cycle positions
+    pub fn query_span(&self, from: f64, to: f64) -> Vec<(f64, PatternEvent)> {
+        if to <= from || from < 0.0 {
+            return Vec::new();
+        }
+        let mut found = Vec::new();
+        for cycle in from.floor() as u64..=to.ceil() as u64 {
+            for event in self.query(cycle) {
+                let onset = cycle as f64 + event.start;
+                if onset >= from && onset < to {
+                    found.push((onset, event));
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Fill `[begin, begin + length)` with a node's events for one of its own cycles
+fn render(node: &PatternNode, cycle: u64, begin: f64, length: f64, out: &mut Vec<PatternEvent>) {
+    match node {
+        PatternNode::Atom(value) => out.push(PatternEvent { value: value.clone(), start: begin, duration: length }),
+        PatternNode::Rest => {}
+        PatternNode::Sequence(steps) => {
+            let step = length / steps.len().max(1) as f64;
+            for (i, child) in steps.iter().enumerate() {
+                render(child, cycle, begin + i as f64 * step, step, out);
+            }
+        }
+        PatternNode::Alternate(children) if !children.is_empty() => {
+            let n = children.len() as u64;
+            render(&children[(cycle % n) as usize], cycle / n, begin, length, out);
+        }
+        PatternNode::Alternate(_) => {}
+        PatternNode::Stack(layers) => {
+            for layer in layers {
+                render(layer, cycle, begin, length, out);
+            }
+        }
+        PatternNode::Fast(child, times) => {
+            let step = length / *times as f64;
+            for i in 0..*times as u64 {
+                render(child, cycle * *times as u64 + i, begin + i as f64 * step, step, out);
+            }
+        }
+        PatternNode::Slow(child, times) => {
+            // The child's cycle spans `times` of ours; keep the slice that lands in this one
+            let times = *times as u64;
+            let offset = (cycle % times) as f64 * length;
+            let mut stretched = Vec::new();
+            render(child, cycle / times, begin - offset, length * times as f64, &mut stretched);
+            let end = begin + length;
+            out.extend(stretched.into_iter().filter(|e| e.start >= begin - 1e-9 && e.start < end - 1e-9));
+        }
+        PatternNode::Euclid { node, hits, steps, rotation } => {
+            let pulses = euclid(*hits, *steps, *rotation);
+            let step = length / pulses.len().max(1) as f64;
+            for (i, hit) in pulses.iter().enumerate() {
+                if *hit {
+                    render(node, cycle, begin + i as f64 * step, step, out);
+                }
+            }
+        }
+        PatternNode::Chance(child, probability) => {
+            let mut events = Vec::new();
+            render(child, cycle, begin, length, &mut events);
+            // Seeded by cycle and onset, so a pattern drops the same events every time it's replayed
+            out.extend(events.into_iter().filter(|e| chance(cycle, e.start) >= *probability));
+        }
+    }
+}
+
+/// Bjorklund's algorithm, rotated left; matches Tidal's `(3,8)` = `x..x..x.`
+pub fn euclid(hits: u32, steps: u32, rotation: u32) -> Vec<bool> {
+    let (hits, steps) = (hits.min(steps) as usize, steps as usize);
+    if steps == 0 {
+        return Vec::new();
+    }
+    let mut heads: Vec<Vec<bool>> = vec![vec![true]; hits];
+    let mut tails: Vec<Vec<bool>> = vec![vec![false]; steps - hits];
+    while tails.len() > 1 && !heads.is_empty() {
+        let paired = heads.len().min(tails.len());
+        let remainder = if heads.len() > paired { heads.split_off(paired) } else { tails.split_off(paired) };
+        for (head, tail) in heads.iter_mut().zip(tails) {
+            head.extend(tail);
+        }
+        tails = remainder;
+    }
+    let mut pulses: Vec<bool> = heads.into_iter().chain(tails).flatten().collect();
+    pulses.rotate_left(rotation as usize % steps);
+    pulses
+}
+
+/// Deterministic 0..1 value for an event, via splitmix64
+fn chance(cycle: u64, start: f64) -> f64 {
+    let mut x = cycle.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (start * 1e6).round() as u64;
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+struct NotationParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl NotationParser {
+    fn error(&self, message: impl Into<String>) -> SynthesisError {
+        synthesis_error(ErrorKind::SyntaxError, message)
+            .with_suggestion(format!("Near position {} of the pattern", self.pos + 1))
+            .with_suggestion("Patterns look like \"bd ~ sn [hh hh]\", \"<bd cp>\" or \"bd(3,8)\"")
+            .with_docs("https://synthesis-lang.org/docs/patterns")
+    }
+
+    fn skip_space(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Comma-separated layers up to `close` (or the end of the string)
+    fn layers(&mut self, close: Option<char>) -> crate::Result<PatternNode> {
+        let mut layers = vec![self.sequence(close)?];
+        while self.peek() == Some(',') {
+            self.pos += 1;
+            layers.push(self.sequence(close)?);
+        }
+        match (close, self.peek()) {
+            (Some(close), Some(c)) if c == close => self.pos += 1,
+            (Some(close), _) => return Err(self.error(format!("🥁 Pattern is missing a closing '{}'", close))),
+            (None, Some(c)) => return Err(self.error(format!("🥁 Unexpected '{}' in pattern", c))),
+            (None, None) => {}
+        }
+        Ok(if layers.len() == 1 { layers.remove(0) } else { PatternNode::Stack(layers) })
+    }
+
+    fn sequence(&mut self, close: Option<char>) -> crate::Result<PatternNode> {
+        let mut steps = Vec::new();
+        while let Some(c) = self.peek() {
+            if Some(c) == close || c == ',' {
+                break;
+            }
+            steps.push(self.step()?);
+        }
+        if steps.is_empty() {
+            return Err(self.error("🥁 Empty pattern; use ~ for a rest"));
+        }
+        Ok(if steps.len() == 1 { steps.remove(0) } else { PatternNode::Sequence(steps) })
+    }
+
+    fn step(&mut self) -> crate::Result<PatternNode> {
+        let mut node = match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                self.layers(Some(']'))?
+            }
+            Some('<') => {
+                self.pos += 1;
+                match self.layers(Some('>'))? {
+                    PatternNode::Sequence(steps) => PatternNode::Alternate(steps),
+                    PatternNode::Stack(_) => return Err(self.error("🥁 Layers (,) can't go inside < >")),
+                    single => PatternNode::Alternate(vec![single]),
+                }
+            }
+            Some('~') => {
+                self.pos += 1;
+                PatternNode::Rest
+            }
+            Some(c) if is_word_char(c) => {
+                let start = self.pos;
+                while self.chars.get(self.pos).copied().is_some_and(is_word_char) {
+                    self.pos += 1;
+                }
+                PatternNode::Atom(self.chars[start..self.pos].iter().collect())
+            }
+            Some(c) => return Err(self.error(format!("🥁 Unexpected '{}' in pattern", c))),
+            None => return Err(self.error("🥁 Pattern ends too early")),
+        };
+
+        // Modifiers attach directly to the step: hh*2, bd(3,8), sn?
+        loop {
+            match self.chars.get(self.pos) {
+                Some('*') => {
+                    self.pos += 1;
+                    node = PatternNode::Fast(Box::new(node), self.count("*")?);
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    node = PatternNode::Slow(Box::new(node), self.count("/")?);
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let hits = self.integer()?;
+                    self.expect(',')?;
+                    let steps = self.integer()?;
+                    let rotation = if self.peek() == Some(',') {
+                        self.pos += 1;
+                        self.integer()?
+                    } else {
+                        0
+                    };
+                    self.expect(')')?;
+                    if steps == 0 || hits > steps {
+                        return Err(self.error(format!("🥁 Can't fit {} hits into {} steps", hits, steps)));
+                    }
+                    node = PatternNode::Euclid { node: Box::new(node), hits, steps, rotation };
+                }
+                Some('?') => {
+                    self.pos += 1;
+                    let probability = if self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                        self.number()?
+                    } else {
+                        0.5
+                    };
+                    if !(0.0..=1.0).contains(&probability) {
+                        return Err(self.error("🥁 Chance after ? must be between 0 and 1"));
+                    }
+                    node = PatternNode::Chance(Box::new(node), probability);
+                }
+                _ => return Ok(node),
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> crate::Result<()> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("🥁 Expected '{}' in pattern", c)))
+        }
+    }
+
+    fn number(&mut self) -> crate::Result<f64> {
+        self.skip_space();
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map_err(|_| self.error("🥁 Expected a number in pattern"))
+    }
+
+    fn integer(&mut self) -> crate::Result<u32> {
+        let value = self.number()?;
+        if value.fract() != 0.0 || value < 0.0 {
+            return Err(self.error("🥁 Euclidean rhythms take whole numbers, like bd(3,8)"));
+        }
+        Ok(value as u32)
+    }
+
+    fn count(&mut self, modifier: &str) -> crate::Result<u32> {
+        match self.integer() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(self.error(format!("🥁 {} takes a whole number above zero, like hh{}2", modifier, modifier))),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '.' | ':' | '#' | '_' | '-')
+}
@@ -3,14 +3,42 @@ pub mod analysis;
 pub mod effects;
 pub mod processor;
 pub mod midi;
+pub mod timecode;
+pub mod mini_notation;
+pub mod synth;
+pub mod signal;
+pub mod master;
+pub mod loudness;
+pub mod recorder;
+pub mod latency;
+pub mod device;
+pub mod aggregate;
+pub mod cue;
+pub mod clips;
+pub mod loopback;
+pub mod opus;
 
 // Re-export specific items to avoid naming conflicts
 pub use input::*;
 pub use analysis::*;
 pub use midi::*;
+pub use timecode::*;
+pub use mini_notation::*;
+pub use synth::*;
+pub use signal::*;
+pub use master::*;
+pub use loudness::*;
+pub use recorder::*;
+pub use latency::*;
+pub use device::*;
+pub use aggregate::*;
+pub use cue::*;
+pub use clips::*;
+pub use loopback::*;
+pub use opus::*;
 
 // From effects module
-pub use effects::{AudioEffect as EffectsAudioEffect, Distortion as EffectsDistortion};
+pub use effects::{AudioEffect as EffectsAudioEffect, Distortion as EffectsDistortion, SyncedDelay, master_delay, master_reverb};
 
 // From processor module  
 pub use processor::{AudioProcessor, PitchDetector};
\ No newline at end of file
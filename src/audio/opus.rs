@@ -0,0 +1,162 @@
+/// Opus compression for audio sent over the network
+///
+/// Raw f32 audio at 48kHz is 1.5Mbit/s per channel, more than a busy Wi-Fi link keeps up with
+/// reliably. Opus brings a voice or instrument down to 32-128kbit/s in 10ms frames, and its
+/// in-band forward error correction tucks a rough copy of each frame into the next packet, so a
+/// single lost packet can be rebuilt rather than dropped. Needs a build with `--features audiopus`.
+use crate::errors::SynthesisError;
+
+/// Each packet carries one frame this long; short enough to keep latency down
+pub const OPUS_FRAME_SECONDS: f64 = 0.01;
+/// Sample rates Opus encodes at
+pub const OPUS_SAMPLE_RATES: [u32; 5] = [8000, 12000, 16000, 24000, 48000];
+/// Most lost frames in a row worth papering over; after a longer gap the audio just resumes
+pub const MAX_CONCEALED_FRAMES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpusSettings {
+    /// Bits per second
+    pub bitrate: i32,
+    /// Put a low-bitrate copy of each frame in the next packet
+    pub fec: bool,
+    /// The packet loss FEC is tuned for, 0 to 100
+    pub expected_loss: u8,
+}
+
+impl Default for OpusSettings {
+    fn default() -> Self {
+        Self { bitrate: 64000, fec: true, expected_loss: 10 }
+    }
+}
+
+/// Samples in one frame at `sample_rate`, or None if Opus can't encode at that rate
+pub fn opus_frame_size(sample_rate: f32) -> Option<usize> {
+    let rate = sample_rate as u32;
+    OPUS_SAMPLE_RATES.contains(&rate).then_some((rate as f64 * OPUS_FRAME_SECONDS) as usize)
+}
+
+fn unsupported_rate(sample_rate: f32) -> SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::AudioDeviceError,
+        format!("📡 Opus can't encode audio at {}Hz", sample_rate)
+    )
+    .with_suggestion("Opus works at 8, 12, 16, 24 or 48kHz; send a 48kHz stream, or leave codec off to send it uncompressed")
+}
+
+#[cfg(feature = "audiopus")]
+mod backend {
+    use super::{opus_frame_size, unsupported_rate, OpusSettings};
+    use audiopus::coder::{Decoder, Encoder};
+    use audiopus::packet::Packet;
+    use audiopus::{Application, Bitrate, Channels, MutSignals, SampleRate};
+
+    fn codec_error(error: audiopus::Error) -> crate::errors::SynthesisError {
+        crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            format!("📡 Opus: {}", error)
+        )
+    }
+
+    fn sample_rate(rate: f32) -> crate::Result<SampleRate> {
+        SampleRate::try_from(rate as i32).map_err(|_| unsupported_rate(rate))
+    }
+
+    #[derive(Debug)]
+    pub struct OpusEncoder {
+        encoder: Encoder,
+        frame_size: usize,
+    }
+
+    impl OpusEncoder {
+        pub fn new(rate: f32, settings: &OpusSettings) -> crate::Result<Self> {
+            let frame_size = opus_frame_size(rate).ok_or_else(|| unsupported_rate(rate))?;
+            let mut encoder = Encoder::new(sample_rate(rate)?, Channels::Mono, Application::LowDelay).map_err(codec_error)?;
+            encoder.set_bitrate(Bitrate::BitsPerSecond(settings.bitrate)).map_err(codec_error)?;
+            encoder.set_inband_fec(settings.fec).map_err(codec_error)?;
+            encoder.set_packet_loss_perc(settings.expected_loss.min(100)).map_err(codec_error)?;
+            Ok(Self { encoder, frame_size })
+        }
+
+        pub fn frame_size(&self) -> usize {
+            self.frame_size
+        }
+
+        /// One frame of exactly `frame_size()` samples
+        pub fn encode(&mut self, frame: &[f32]) -> crate::Result<Vec<u8>> {
+            let mut packet = vec![0u8; 1275];
+            let size = self.encoder.encode_float(frame, &mut packet).map_err(codec_error)?;
+            packet.truncate(size);
+            Ok(packet)
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct OpusDecoder {
+        decoder: Decoder,
+        frame_size: usize,
+    }
+
+    impl OpusDecoder {
+        pub fn new(rate: f32) -> crate::Result<Self> {
+            let frame_size = opus_frame_size(rate).ok_or_else(|| unsupported_rate(rate))?;
+            Ok(Self { decoder: Decoder::new(sample_rate(rate)?, Channels::Mono).map_err(codec_error)?, frame_size })
+        }
+
+        /// A frame from its packet; with `fec`, the frame before it from the copy the packet
+        /// carries; with no packet, a guess that smooths over the gap
+        pub fn decode(&mut self, packet: Option<&[u8]>, fec: bool) -> crate::Result<Vec<f32>> {
+            let mut frame = vec![0.0f32; self.frame_size];
+            let packet = packet.map(Packet::try_from).transpose().map_err(codec_error)?;
+            let output = MutSignals::try_from(&mut frame).map_err(codec_error)?;
+            let decoded = self.decoder.decode_float(packet, output, fec).map_err(codec_error)?;
+            frame.truncate(decoded);
+            Ok(frame)
+        }
+    }
+}
+
+#[cfg(not(feature = "audiopus"))]
+mod backend {
+    use super::{opus_frame_size, unsupported_rate, OpusSettings};
+
+    fn unsupported() -> crate::errors::SynthesisError {
+        crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            "📡 This build of Synthesis has no Opus support"
+        )
+        .with_suggestion("Rebuild with --features audiopus, or leave codec off to send audio uncompressed")
+    }
+
+    #[derive(Debug)]
+    pub struct OpusEncoder;
+
+    impl OpusEncoder {
+        pub fn new(rate: f32, _settings: &OpusSettings) -> crate::Result<Self> {
+            opus_frame_size(rate).ok_or_else(|| unsupported_rate(rate))?;
+            Err(unsupported())
+        }
+
+        pub fn frame_size(&self) -> usize {
+            0
+        }
+
+        pub fn encode(&mut self, _frame: &[f32]) -> crate::Result<Vec<u8>> {
+            Err(unsupported())
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct OpusDecoder;
+
+    impl OpusDecoder {
+        pub fn new(_rate: f32) -> crate::Result<Self> {
+            Err(unsupported())
+        }
+
+        pub fn decode(&mut self, _packet: Option<&[u8]>, _fec: bool) -> crate::Result<Vec<f32>> {
+            Err(unsupported())
+        }
+    }
+}
+
+pub use backend::{OpusDecoder, OpusEncoder};
@@ -3,7 +3,7 @@ use crate::runtime::realtime_buffer::{RealtimeCircularBuffer, BufferError};
 
 pub struct AudioProcessor {
     _sample_rate: f32,
-    buffer_size: usize,
+    _buffer_size: usize,
     input_buffer: RealtimeCircularBuffer,
     output_buffer: RealtimeCircularBuffer,
     effects_chain: Vec<Box<dyn AudioEffect + Send>>,
@@ -23,7 +23,7 @@ impl AudioProcessor {
         
         Self {
             _sample_rate: sample_rate,
-            buffer_size,
+            _buffer_size: buffer_size,
             input_buffer: RealtimeCircularBuffer::new(buffer_capacity).unwrap(),
             output_buffer: RealtimeCircularBuffer::new(buffer_capacity).unwrap(),
             effects_chain: Vec::new(),
@@ -49,7 +49,7 @@ impl AudioProcessor {
         }
         
         // Process available samples (bounded by input length)
-        for i in 0..input.len() {
+        for out in output.iter_mut().take(input.len()) {
             match self.input_buffer.read_single() {
                 Ok(mut sample) => {
                     // Apply effects chain
@@ -57,16 +57,16 @@ impl AudioProcessor {
                         sample = effect.process(sample);
                     }
                     
-                    output[i] = sample;
+                    *out = sample;
                     // Store processed sample (silently drop if buffer full)
                     let _ = self.output_buffer.write_single(sample);
                 }
                 Err(BufferError::BufferEmpty) => {
                     // Output silence if no input available
-                    output[i] = 0.0;
+                    *out = 0.0;
                 }
                 Err(_) => {
-                    output[i] = 0.0;
+                    *out = 0.0;
                 }
             }
         }
@@ -0,0 +1,211 @@
+/// Session recorder: the whole performance to disk, as a mixdown plus optional per-synth stems
+///
+/// Each take gets its own timestamped folder (`recordings/session-20260314-213005/`) holding
+/// `mix.wav` (the master output, after the limiter) and, with stems on, one WAV per synth taken
+/// before the master bus. Files are 32-bit float and stay valid if the process dies mid-take:
+/// headers are rewritten every few seconds. Free disk space is checked before starting and
+/// while running, and the take is closed cleanly before the disk fills.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+pub const DEFAULT_RECORDING_DIR: &str = "recordings";
+/// Refuse to start, or stop a running take, below this much free space
+pub const MIN_FREE_BYTES: u64 = 256 * 1024 * 1024;
+/// Seconds of audio between header rewrites and disk-space checks
+const CHECKPOINT_SECONDS: f64 = 5.0;
+
+/// Streaming 32-bit float mono WAV; the header is patched with the real length as it grows
+#[derive(Debug)]
+pub struct WavWriter {
+    pub path: PathBuf,
+    file: BufWriter<File>,
+    sample_rate: u32,
+    samples: u64,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32) -> std::io::Result<Self> {
+        let mut writer = Self { path: path.to_path_buf(), file: BufWriter::new(File::create(path)?), sample_rate, samples: 0 };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    fn write_header(&mut self) -> std::io::Result<()> {
+        let data_len = (self.samples * 4).min(u32::MAX as u64 - 36) as u32;
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(36 + data_len).to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&3u16.to_le_bytes()); // IEEE float
+        header.extend_from_slice(&1u16.to_le_bytes()); // mono
+        header.extend_from_slice(&self.sample_rate.to_le_bytes());
+        header.extend_from_slice(&(self.sample_rate * 4).to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        header.extend_from_slice(&32u16.to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_len.to_le_bytes());
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        for sample in samples {
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+        self.samples += samples.len() as u64;
+        Ok(())
+    }
+
+    /// Bring the file up to date with a header that matches what's written so far
+    pub fn checkpoint(&mut self) -> std::io::Result<()> {
+        self.write_header()?;
+        self.file.flush()
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.samples as f64 / self.sample_rate as f64
+    }
+}
+
+/// Free bytes on the disk holding `path`, where the platform can tell us
+pub fn free_disk_space(path: &Path) -> Option<u64> {
+    if !cfg!(unix) {
+        return None;
+    }
+    let output = std::process::Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Second line: filesystem, blocks, used, available, ...
+    let available_kb: u64 = text.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available_kb * 1024)
+}
+
+/// Stem file name for a synth: lowercase, with anything unusual turned into dashes
+pub fn stem_file_name(name: &str) -> String {
+    let cleaned: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let cleaned = cleaned.trim_matches('-');
+    match cleaned {
+        "" => "stem.wav".to_string(),
+        "mix" => "stem-mix.wav".to_string(),
+        _ => format!("{}.wav", cleaned),
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionRecorder {
+    pub dir: PathBuf,
+    pub stems: bool,
+    sample_rate: u32,
+    mix: WavWriter,
+    stem_writers: BTreeMap<String, WavWriter>,
+    since_checkpoint: f64,
+}
+
+impl SessionRecorder {
+    /// Make a fresh session folder under `base` and open the mixdown
+    pub fn start(base: &Path, stems: bool, sample_rate: u32) -> crate::Result<Self> {
+        std::fs::create_dir_all(base).map_err(|error| recording_error(base, error))?;
+        if let Some(free) = free_disk_space(base) {
+            if free < MIN_FREE_BYTES {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::OutOfMemory,
+                    format!("📼 Only {} MB free on the disk holding {}", free / (1024 * 1024), base.display())
+                )
+                .with_suggestion(format!("Free up space or record somewhere else: Audio.record_session(path: \"/Volumes/External/\") (needs {} MB)", MIN_FREE_BYTES / (1024 * 1024))));
+            }
+        }
+
+        let stamp = chrono::Local::now().format("session-%Y%m%d-%H%M%S").to_string();
+        let mut dir = base.join(&stamp);
+        let mut take = 2;
+        while dir.exists() {
+            dir = base.join(format!("{}-{}", stamp, take));
+            take += 1;
+        }
+        std::fs::create_dir_all(&dir).map_err(|error| recording_error(&dir, error))?;
+        let mix_path = dir.join("mix.wav");
+        let mix = WavWriter::create(&mix_path, sample_rate).map_err(|error| recording_error(&mix_path, error))?;
+        tracing::info!(target: "audio", dir = %dir.display(), stems, "📼 session recording started");
+        Ok(Self { dir, stems, sample_rate, mix, stem_writers: BTreeMap::new(), since_checkpoint: 0.0 })
+    }
+
+    /// Append one block: the master output plus each synth's share of it.
+    /// Stems that start late are padded with silence so every file lines up with the mix.
+    pub fn write_block(&mut self, mix: &[f32], stems: &BTreeMap<String, Vec<f32>>) -> std::io::Result<()> {
+        if self.stems {
+            let position = self.mix.samples;
+            for name in stems.keys() {
+                if !self.stem_writers.contains_key(name) {
+                    let mut writer = WavWriter::create(&self.dir.join(stem_file_name(name)), self.sample_rate)?;
+                    writer.write(&vec![0.0; position as usize])?;
+                    self.stem_writers.insert(name.clone(), writer);
+                }
+            }
+            for (name, writer) in self.stem_writers.iter_mut() {
+                match stems.get(name) {
+                    Some(samples) => writer.write(samples)?,
+                    None => writer.write(&vec![0.0; mix.len()])?,
+                }
+            }
+        }
+        self.mix.write(mix)?;
+
+        self.since_checkpoint += mix.len() as f64 / self.sample_rate as f64;
+        if self.since_checkpoint >= CHECKPOINT_SECONDS {
+            self.since_checkpoint = 0.0;
+            self.checkpoint()?;
+            if free_disk_space(&self.dir).is_some_and(|free| free < MIN_FREE_BYTES) {
+                return Err(std::io::Error::other("disk almost full"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite headers and flush, so every file is playable as it stands
+    pub fn checkpoint(&mut self) -> std::io::Result<()> {
+        self.mix.checkpoint()?;
+        for writer in self.stem_writers.values_mut() {
+            writer.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.mix.seconds()
+    }
+
+    /// Every file in the take, mixdown first
+    pub fn files(&self) -> Vec<PathBuf> {
+        std::iter::once(self.mix.path.clone())
+            .chain(self.stem_writers.values().map(|writer| writer.path.clone()))
+            .collect()
+    }
+
+    /// Finalize headers and hand back the files written
+    pub fn finish(mut self) -> std::io::Result<Vec<PathBuf>> {
+        self.checkpoint()?;
+        tracing::info!(target: "audio", dir = %self.dir.display(), seconds = self.seconds(), "📼 session recording saved");
+        Ok(self.files())
+    }
+}
+
+fn recording_error(path: &Path, error: std::io::Error) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::PermissionDenied,
+        format!("📼 Can't record to {}: {}", path.display(), error)
+    )
+    .with_suggestion("Check the folder exists and is writable, or pass path: to Audio.record_session()")
+}
+
+static SESSION_RECORDER: OnceLock<Mutex<Option<SessionRecorder>>> = OnceLock::new();
+
+pub fn session_recorder() -> &'static Mutex<Option<SessionRecorder>> {
+    SESSION_RECORDER.get_or_init(|| Mutex::new(None))
+}
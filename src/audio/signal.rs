@@ -0,0 +1,158 @@
+/// Test signals with exact lengths, for characterizing effect chains and checking routing
+///
+/// Everything here is deterministic: noise is seeded, and lengths are whole sample counts so a
+/// one-second buffer at 48 kHz is exactly 48000 samples. The same generators back the
+/// script-side `Signal` module and Rust tests.
+use std::f64::consts::TAU;
+
+pub const DEFAULT_SIGNAL_RATE: f64 = 44100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SweepCurve {
+    Linear,
+    /// Equal time per octave, the usual choice for frequency response measurements
+    Exponential,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalGenerator {
+    pub sample_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for SignalGenerator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNAL_RATE)
+    }
+}
+
+impl SignalGenerator {
+    pub fn new(sample_rate: f64) -> Self {
+        Self { sample_rate, seed: 0x5EED }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sample count for a duration, rounded to the nearest sample
+    pub fn samples_for(&self, seconds: f64) -> usize {
+        (seconds.max(0.0) * self.sample_rate).round() as usize
+    }
+
+    pub fn sine(&self, frequency: f64, amplitude: f64, length: usize) -> Vec<f32> {
+        (0..length)
+            .map(|n| (amplitude * (TAU * frequency * n as f64 / self.sample_rate).sin()) as f32)
+            .collect()
+    }
+
+    pub fn sweep(&self, from: f64, to: f64, amplitude: f64, length: usize, curve: SweepCurve) -> Vec<f32> {
+        let duration = length as f64 / self.sample_rate;
+        let mut phase = 0.0;
+        (0..length)
+            .map(|n| {
+                let t = n as f64 / self.sample_rate;
+                let progress = if duration > 0.0 { t / duration } else { 0.0 };
+                let frequency = match curve {
+                    SweepCurve::Exponential if from > 0.0 && to > 0.0 => from * (to / from).powf(progress),
+                    _ => from + (to - from) * progress,
+                };
+                let sample = amplitude * (TAU * phase).sin();
+                phase = (phase + frequency / self.sample_rate).fract();
+                sample as f32
+            })
+            .collect()
+    }
+
+    /// One sample of `amplitude` at `position`, zero elsewhere
+    pub fn impulse(&self, amplitude: f64, length: usize, position: usize) -> Vec<f32> {
+        let mut samples = vec![0.0; length];
+        if let Some(sample) = samples.get_mut(position) {
+            *sample = amplitude as f32;
+        }
+        samples
+    }
+
+    pub fn white_noise(&self, amplitude: f64, length: usize) -> Vec<f32> {
+        let mut state = self.seed.max(1);
+        (0..length).map(|_| (amplitude * uniform(&mut state)) as f32).collect()
+    }
+
+    /// -3 dB/octave noise using Paul Kellet's economy filter, normalized so its peak is `amplitude`
+    pub fn pink_noise(&self, amplitude: f64, length: usize) -> Vec<f32> {
+        let mut state = self.seed.max(1);
+        let (mut b0, mut b1, mut b2) = (0.0, 0.0, 0.0);
+        let pink: Vec<f64> = (0..length)
+            .map(|_| {
+                let white = uniform(&mut state);
+                b0 = 0.99765 * b0 + white * 0.0990460;
+                b1 = 0.96300 * b1 + white * 0.2965164;
+                b2 = 0.57000 * b2 + white * 1.0526913;
+                b0 + b1 + b2 + white * 0.1848
+            })
+            .collect();
+        let peak = pink.iter().fold(0.0f64, |peak, s| peak.max(s.abs()));
+        let gain = if peak > 0.0 { amplitude / peak } else { 0.0 };
+        pink.into_iter().map(|s| (s * gain) as f32).collect()
+    }
+
+    pub fn dc(&self, level: f64, length: usize) -> Vec<f32> {
+        vec![level as f32; length]
+    }
+
+    pub fn silence(&self, length: usize) -> Vec<f32> {
+        vec![0.0; length]
+    }
+}
+
+/// xorshift64 mapped to -1..1
+fn uniform(state: &mut u64) -> f64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    (*state >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+}
+
+/// Level and pitch measurements for checking what came out of a chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalStats {
+    pub length: usize,
+    pub peak: f64,
+    pub rms: f64,
+    /// Mean value; non-zero means the chain added an offset
+    pub dc_offset: f64,
+    /// Rough fundamental from rising zero crossings, 0 if the signal never crosses
+    pub frequency: f64,
+}
+
+impl SignalStats {
+    pub fn measure(samples: &[f32], sample_rate: f64) -> Self {
+        let length = samples.len();
+        if length == 0 {
+            return Self { length, peak: 0.0, rms: 0.0, dc_offset: 0.0, frequency: 0.0 };
+        }
+        let peak = samples.iter().fold(0.0f64, |peak, s| peak.max(s.abs() as f64));
+        let rms = (samples.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / length as f64).sqrt();
+        let dc_offset = samples.iter().map(|s| *s as f64).sum::<f64>() / length as f64;
+
+        let crossings: Vec<usize> = samples.windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0] as f64 - dc_offset <= 0.0 && pair[1] as f64 - dc_offset > 0.0)
+            .map(|(i, _)| i)
+            .collect();
+        let frequency = match (crossings.first(), crossings.last()) {
+            (Some(first), Some(last)) if last > first => {
+                (crossings.len() - 1) as f64 * sample_rate / (last - first) as f64
+            }
+            _ => 0.0,
+        };
+
+        Self { length, peak, rms, dc_offset, frequency }
+    }
+
+    /// RMS in dBFS, where a full-scale sine is about -3 dB
+    pub fn rms_db(&self) -> f64 {
+        if self.rms > 0.0 { 20.0 * self.rms.log10() } else { f64::NEG_INFINITY }
+    }
+}
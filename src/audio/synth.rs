@@ -0,0 +1,731 @@
+/// Named synth definitions, SuperCollider SynthDef style
+///
+/// ```text
+/// synth pluck(note = "A3", amp = 0.5, decay = 0.4) {
+///     tone = Synth.saw(note) + Synth.square(note * 1.01)
+///     Synth.lowpass(tone, 2400) * Synth.env(0.005, decay, 0, 0.1) * amp
+/// }
+///
+/// Synth.play("pluck", note: "E3")
+/// ```
+///
+/// The body is compiled once into a flat `VoiceGraph`: unit generators in evaluation order,
+/// with constant arithmetic folded away. Each played voice gets its own copy of the node
+/// state and renders sample by sample without allocating.
+use crate::errors::{synthesis_error, ErrorKind, SynthesisError};
+use crate::parser::ast::{BinaryOperator, Expression, Literal, Statement, StringPart, UnaryOperator};
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex, OnceLock};
+
+pub const SYNTH_SAMPLE_RATE: f64 = 44100.0;
+
+/// Voices sounding at once before the oldest is stolen
+pub const MAX_VOICES: usize = 32;
+
+/// Seconds a voice is held before release when `Synth.play` isn't given a `dur`
+pub const DEFAULT_NOTE_LENGTH: f64 = 0.5;
+
+/// Where a unit generator input comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Const(f64),
+    Param(usize),
+    Node(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Saw,
+    Square,
+    Triangle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UGen {
+    Osc { shape: Waveform, freq: Input, width: Input },
+    Noise,
+    /// ADSR driven by the voice's gate
+    Env { attack: Input, decay: Input, sustain: Input, release: Input },
+    Lowpass { input: Input, cutoff: Input, resonance: Input },
+    Highpass { input: Input, cutoff: Input, resonance: Input },
+    Binary { op: MathOp, left: Input, right: Input },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MathOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl MathOp {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            MathOp::Add => a + b,
+            MathOp::Subtract => a - b,
+            MathOp::Multiply => a * b,
+            MathOp::Divide if b == 0.0 => 0.0,
+            MathOp::Divide => a / b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthParam {
+    pub name: String,
+    pub default: f64,
+}
+
+/// A compiled synth definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceGraph {
+    pub name: String,
+    pub params: Vec<SynthParam>,
+    pub nodes: Vec<UGen>,
+    pub output: Input,
+}
+
+impl VoiceGraph {
+    /// Compile a `synth` body; parameter defaults are already evaluated
+    pub fn compile(name: &str, params: Vec<SynthParam>, body: &[Statement]) -> crate::Result<Self> {
+        let mut compiler = GraphCompiler { name, params: &params, locals: HashMap::new(), nodes: Vec::new() };
+        let mut output = None;
+
+        for statement in body {
+            match statement {
+                Statement::Assignment { name: local, value } => {
+                    let input = compiler.expression(value)?;
+                    if local == "out" {
+                        output = Some(input);
+                    }
+                    compiler.locals.insert(local.clone(), input);
+                }
+                Statement::Let { name: local, value: Some(value), .. } | Statement::Const { name: local, value, .. } => {
+                    let input = compiler.expression(value)?;
+                    compiler.locals.insert(local.clone(), input);
+                }
+                Statement::Expression(value) => output = Some(compiler.expression(value)?),
+                _ => return Err(compiler.error("🎛️ Synth bodies can only name signals and combine them")
+                    .with_suggestion("Use lines like tone = Synth.saw(note), ending with the signal to play")),
+            }
+        }
+
+        let output = output.ok_or_else(|| compiler.error(format!("🎛️ synth {} doesn't produce a sound", name))
+            .with_suggestion("End the body with the signal to play, like Synth.sine(note) * amp"))?;
+
+        let nodes = compiler.nodes;
+        Ok(Self { name: name.to_string(), params, nodes, output })
+    }
+
+    pub fn param_index(&self, name: &str) -> Option<usize> {
+        self.params.iter().position(|p| p.name == name)
+    }
+
+    fn has_envelope(&self) -> bool {
+        self.nodes.iter().any(|node| matches!(node, UGen::Env { .. }))
+    }
+}
+
+struct GraphCompiler<'a> {
+    name: &'a str,
+    params: &'a [SynthParam],
+    locals: HashMap<String, Input>,
+    nodes: Vec<UGen>,
+}
+
+impl GraphCompiler<'_> {
+    fn error(&self, message: impl Into<String>) -> SynthesisError {
+        synthesis_error(ErrorKind::InvalidExpression, message)
+            .with_suggestion(format!("In synth {}", self.name))
+            .with_docs("https://synthesis-lang.org/docs/synths")
+    }
+
+    fn push(&mut self, node: UGen) -> Input {
+        self.nodes.push(node);
+        Input::Node(self.nodes.len() - 1)
+    }
+
+    fn expression(&mut self, expr: &Expression) -> crate::Result<Input> {
+        match expr {
+            Expression::Literal(Literal::Integer(n) | Literal::BasedInteger(n, _)) => Ok(Input::Const(*n as f64)),
+            Expression::Literal(Literal::Float(f)) => Ok(Input::Const(*f)),
+            Expression::Literal(Literal::String(text)) => self.note(text),
+            Expression::InterpolatedString(parts) if parts.iter().all(|p| matches!(p, StringPart::Text(_))) => {
+                let text: String = parts.iter().map(|p| match p {
+                    StringPart::Text(text) => text.as_str(),
+                    _ => "",
+                }).collect();
+                self.note(&text)
+            }
+            Expression::Identifier(name) => {
+                if let Some(input) = self.locals.get(name) {
+                    Ok(*input)
+                } else if let Some(index) = self.params.iter().position(|p| &p.name == name) {
+                    Ok(Input::Param(index))
+                } else {
+                    Err(self.error(format!("🎛️ '{}' isn't a parameter or signal of this synth", name))
+                        .with_suggestion("Add it to the parameter list, like synth pad(note, cutoff = 800)"))
+                }
+            }
+            Expression::BinaryOp { left, op, right } => {
+                let op = match op {
+                    BinaryOperator::Add => MathOp::Add,
+                    BinaryOperator::Subtract => MathOp::Subtract,
+                    BinaryOperator::Multiply => MathOp::Multiply,
+                    BinaryOperator::Divide => MathOp::Divide,
+                    _ => return Err(self.error("🎛️ Synth signals can be combined with + - * / only")),
+                };
+                let (left, right) = (self.expression(left)?, self.expression(right)?);
+                match (left, right) {
+                    (Input::Const(a), Input::Const(b)) => Ok(Input::Const(op.apply(a, b))),
+                    _ => Ok(self.push(UGen::Binary { op, left, right })),
+                }
+            }
+            Expression::UnaryOp { op: UnaryOperator::Negate, operand } => match self.expression(operand)? {
+                Input::Const(value) => Ok(Input::Const(-value)),
+                input => Ok(self.push(UGen::Binary { op: MathOp::Multiply, left: Input::Const(-1.0), right: input })),
+            },
+            Expression::FunctionCall { module: Some(module), name, args, named_args } if module == "Synth" => {
+                self.ugen(name, args, named_args)
+            }
+            _ => Err(self.error("🎛️ This can't be part of a synth graph")
+                .with_suggestion("Use Synth.sine/saw/square/triangle/noise/env/lowpass/highpass and + - * /")),
+        }
+    }
+
+    fn note(&self, text: &str) -> crate::Result<Input> {
+        note_frequency(text).map(Input::Const).ok_or_else(|| {
+            self.error(format!("🎛️ '{}' isn't a note name", text))
+                .with_suggestion("Notes look like \"A4\", \"C#3\" or \"Eb2\"")
+        })
+    }
+
+    fn ugen(&mut self, name: &str, args: &[Expression], named: &HashMap<String, Expression>) -> crate::Result<Input> {
+        let inputs: &[(&str, Option<f64>)] = match name {
+            "sine" | "saw" | "triangle" => &[("freq", Some(440.0))],
+            "square" => &[("freq", Some(440.0)), ("width", Some(0.5))],
+            "noise" => &[],
+            "env" => &[("attack", Some(0.01)), ("decay", Some(0.3)), ("sustain", Some(0.0)), ("release", Some(0.2))],
+            "lowpass" | "highpass" => &[("input", None), ("cutoff", Some(1000.0)), ("resonance", Some(0.7))],
+            _ => return Err(self.error(format!("🎛️ Synth.{}() isn't a synth building block", name))
+                .with_suggestion("Oscillators: sine, saw, square, triangle, noise")
+                .with_suggestion("Shaping: env, lowpass, highpass")),
+        };
+        if args.len() > inputs.len() {
+            return Err(self.error(format!("🎛️ Synth.{}() takes at most {} inputs", name, inputs.len())));
+        }
+
+        let mut values = Vec::with_capacity(inputs.len());
+        for (i, (input, default)) in inputs.iter().enumerate() {
+            let value = match (args.get(i), named.get(*input), default) {
+                (Some(expr), _, _) | (None, Some(expr), _) => self.expression(expr)?,
+                (None, None, Some(default)) => Input::Const(*default),
+                (None, None, None) => return Err(self.error(format!("🎛️ Synth.{}() needs its {}", name, input))
+                    .with_suggestion(format!("Try: Synth.{}(Synth.saw(note), 1200)", name))),
+            };
+            values.push(value);
+        }
+        if let Some(unknown) = named.keys().find(|key| !inputs.iter().any(|(input, _)| input == key)) {
+            return Err(self.error(format!("🎛️ Synth.{}() has no input called {}", name, unknown)));
+        }
+
+        let node = match name {
+            "sine" | "saw" | "triangle" | "square" => UGen::Osc {
+                shape: match name {
+                    "sine" => Waveform::Sine,
+                    "saw" => Waveform::Saw,
+                    "triangle" => Waveform::Triangle,
+                    _ => Waveform::Square,
+                },
+                freq: values[0],
+                width: values.get(1).copied().unwrap_or(Input::Const(0.5)),
+            },
+            "noise" => UGen::Noise,
+            "env" => UGen::Env { attack: values[0], decay: values[1], sustain: values[2], release: values[3] },
+            "lowpass" => UGen::Lowpass { input: values[0], cutoff: values[1], resonance: values[2] },
+            _ => UGen::Highpass { input: values[0], cutoff: values[1], resonance: values[2] },
+        };
+        Ok(self.push(node))
+    }
+}
+
+/// Frequency of a note name like "A4", "C#3" or "Eb2" (A4 = 440 Hz)
+pub fn note_frequency(name: &str) -> Option<f64> {
+    let midi = note_number(name)?;
+    Some(440.0 * 2f64.powf((midi - 69) as f64 / 12.0))
+}
+
+/// MIDI note number of a note name like "C4" (60) or "A4" (69)
+pub fn note_number(name: &str) -> Option<i32> {
+    let mut chars = name.trim().chars().peekable();
+    let semitone = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match chars.peek() {
+        Some('#') => 1,
+        Some('b') => -1,
+        _ => 0,
+    };
+    if accidental != 0 {
+        chars.next();
+    }
+    let octave: i32 = chars.collect::<String>().parse().ok()?;
+    Some((octave + 1) * 12 + semitone + accidental)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EnvStage {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Done,
+}
+
+/// Per-voice state for one node
+#[derive(Debug, Clone, Copy)]
+enum NodeState {
+    Phase(f64),
+    Noise(u64),
+    Env { stage: EnvStage, level: f64, release_from: f64 },
+    Filter { low: f64, band: f64 },
+    Stateless,
+}
+
+/// One sounding instance of a synth
+#[derive(Debug, Clone)]
+pub struct Voice {
+    pub id: u64,
+    graph: Arc<VoiceGraph>,
+    params: Vec<f64>,
+    state: Vec<NodeState>,
+    values: Vec<f64>,
+    /// Samples left before the gate closes; None holds until released
+    hold: Option<u64>,
+    gate: bool,
+    released_for: u64,
+    sample_rate: f64,
+}
+
+impl Voice {
+    pub fn new(id: u64, graph: Arc<VoiceGraph>, params: Vec<f64>, hold: Option<u64>) -> Self {
+        let state = graph.nodes.iter().enumerate().map(|(i, node)| match node {
+            UGen::Osc { .. } => NodeState::Phase(0.0),
+            UGen::Noise => NodeState::Noise(0x2545_F491_4F6C_DD1D ^ (id << 8) ^ i as u64),
+            UGen::Env { .. } => NodeState::Env { stage: EnvStage::Attack, level: 0.0, release_from: 0.0 },
+            UGen::Lowpass { .. } | UGen::Highpass { .. } => NodeState::Filter { low: 0.0, band: 0.0 },
+            UGen::Binary { .. } => NodeState::Stateless,
+        }).collect();
+        let values = vec![0.0; graph.nodes.len()];
+        Self { id, graph, params, state, values, hold, gate: true, released_for: 0, sample_rate: SYNTH_SAMPLE_RATE }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.graph.name
+    }
+
+    pub fn release(&mut self) {
+        self.gate = false;
+    }
+
+    /// Envelopes have finished, or the gate has closed on a synth without one
+    pub fn is_finished(&self) -> bool {
+        if self.graph.has_envelope() {
+            self.state.iter().all(|s| !matches!(s, NodeState::Env { stage, .. } if *stage != EnvStage::Done))
+        } else {
+            // A short fade after the gate keeps envelope-less synths from clicking off
+            !self.gate && self.released_for as f64 > 0.005 * self.sample_rate
+        }
+    }
+
+    fn input(&self, input: Input) -> f64 {
+        match input {
+            Input::Const(value) => value,
+            Input::Param(index) => self.params[index],
+            Input::Node(index) => self.values[index],
+        }
+    }
+
+    /// Compute the next output sample
+    pub fn next_sample(&mut self, sample_rate: f64) -> f64 {
+        if let Some(hold) = self.hold.as_mut() {
+            if *hold == 0 {
+                self.gate = false;
+            } else {
+                *hold -= 1;
+            }
+        }
+        if !self.gate {
+            self.released_for += 1;
+        }
+
+        for i in 0..self.graph.nodes.len() {
+            let node = self.graph.nodes[i];
+            let value = match (node, self.state[i]) {
+                (UGen::Osc { shape, freq, width }, NodeState::Phase(phase)) => {
+                    let value = match shape {
+                        Waveform::Sine => (phase * TAU).sin(),
+                        Waveform::Saw => 2.0 * phase - 1.0,
+                        Waveform::Square => if phase < self.input(width) { 1.0 } else { -1.0 },
+                        Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                    };
+                    self.state[i] = NodeState::Phase((phase + self.input(freq) / sample_rate).rem_euclid(1.0));
+                    value
+                }
+                (UGen::Noise, NodeState::Noise(seed)) => {
+                    let mut x = seed;
+                    x ^= x << 13;
+                    x ^= x >> 7;
+                    x ^= x << 17;
+                    self.state[i] = NodeState::Noise(x);
+                    (x >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+                }
+                (UGen::Env { attack, decay, sustain, release }, NodeState::Env { stage, level, release_from }) => {
+                    let sustain = self.input(sustain).clamp(0.0, 1.0);
+                    let rate = |seconds: f64| 1.0 / (seconds.max(0.0005) * sample_rate);
+                    let (mut stage, mut level, mut release_from) = (stage, level, release_from);
+                    if !self.gate && !matches!(stage, EnvStage::Release | EnvStage::Done) {
+                        stage = EnvStage::Release;
+                        release_from = level;
+                    }
+                    match stage {
+                        EnvStage::Attack => {
+                            level += rate(self.input(attack));
+                            if level >= 1.0 {
+                                level = 1.0;
+                                stage = EnvStage::Decay;
+                            }
+                        }
+                        EnvStage::Decay => {
+                            level -= (1.0 - sustain) * rate(self.input(decay));
+                            if level <= sustain {
+                                level = sustain;
+                                stage = if sustain <= 0.0 { EnvStage::Done } else { EnvStage::Sustain };
+                            }
+                        }
+                        EnvStage::Sustain => level = sustain,
+                        EnvStage::Release => {
+                            level -= release_from * rate(self.input(release));
+                            if level <= 0.0 {
+                                level = 0.0;
+                                stage = EnvStage::Done;
+                            }
+                        }
+                        EnvStage::Done => level = 0.0,
+                    }
+                    self.state[i] = NodeState::Env { stage, level, release_from };
+                    level
+                }
+                (UGen::Lowpass { input, cutoff, resonance }, NodeState::Filter { low, band })
+                | (UGen::Highpass { input, cutoff, resonance }, NodeState::Filter { low, band }) => {
+                    // Chamberlin state-variable filter; cutoff is kept well under Nyquist for stability
+                    let f = 2.0 * (std::f64::consts::PI * self.input(cutoff).clamp(10.0, sample_rate / 6.0) / sample_rate).sin();
+                    let damping = 1.0 / self.input(resonance).max(0.5);
+                    let low = low + f * band;
+                    let high = self.input(input) - low - damping * band;
+                    let band = band + f * high;
+                    self.state[i] = NodeState::Filter { low, band };
+                    if matches!(node, UGen::Lowpass { .. }) { low } else { high }
+                }
+                (UGen::Binary { op, left, right }, _) => op.apply(self.input(left), self.input(right)),
+                _ => 0.0,
+            };
+            self.values[i] = value;
+        }
+
+        let out = self.input(self.graph.output);
+        if self.graph.has_envelope() || self.gate {
+            out
+        } else {
+            out * (1.0 - self.released_for as f64 / (0.005 * self.sample_rate)).max(0.0)
+        }
+    }
+}
+
+/// Definitions and the voices currently playing them
+#[derive(Debug)]
+pub struct SynthEngine {
+    graphs: HashMap<String, Arc<VoiceGraph>>,
+    voices: Vec<Voice>,
+    next_id: u64,
+    peak: f32,
+    sample_rate: f64,
+    /// Per-synth level as (where the last block ended, where the next one heads), set by crossfades
+    gains: HashMap<String, (f32, f32)>,
+}
+
+impl Default for SynthEngine {
+    fn default() -> Self {
+        Self { graphs: HashMap::new(), voices: Vec::new(), next_id: 0, peak: 0.0, sample_rate: SYNTH_SAMPLE_RATE, gains: HashMap::new() }
+    }
+}
+
+impl SynthEngine {
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Switch rates without cutting voices: note lengths and release fades keep their duration
+    pub fn set_sample_rate(&mut self, sample_rate: f64) {
+        let ratio = sample_rate / self.sample_rate;
+        for voice in &mut self.voices {
+            voice.hold = voice.hold.map(|hold| (hold as f64 * ratio) as u64);
+            voice.released_for = (voice.released_for as f64 * ratio) as u64;
+            voice.sample_rate = sample_rate;
+        }
+        self.sample_rate = sample_rate;
+    }
+
+    pub fn define(&mut self, graph: VoiceGraph) {
+        self.graphs.insert(graph.name.clone(), Arc::new(graph));
+    }
+
+    pub fn graph(&self, name: &str) -> Option<Arc<VoiceGraph>> {
+        self.graphs.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.graphs.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Start a voice, stealing the oldest one if every slot is busy
+    pub fn play(&mut self, name: &str, args: &HashMap<String, f64>, duration: Option<f64>) -> crate::Result<u64> {
+        let graph = self.graphs.get(name).cloned().ok_or_else(|| {
+            let known = self.names();
+            let error = synthesis_error(ErrorKind::UnknownFunction, format!("🎛️ No synth called '{}'", name))
+                .with_docs("https://synthesis-lang.org/docs/synths");
+            if known.is_empty() {
+                error.with_suggestion("Define one first: synth beep(note = \"A4\") { Synth.sine(note) * Synth.env() }")
+            } else {
+                error.with_suggestion(format!("Defined synths: {}", known.join(", ")))
+            }
+        })?;
+
+        let mut params: Vec<f64> = graph.params.iter().map(|p| p.default).collect();
+        for (key, value) in args {
+            match graph.param_index(key) {
+                Some(index) => params[index] = *value,
+                None => return Err(synthesis_error(
+                    ErrorKind::InvalidExpression,
+                    format!("🎛️ synth {} has no parameter called {}", name, key)
+                )
+                .with_suggestion(format!("Its parameters are: {}",
+                    graph.params.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ")))),
+            }
+        }
+
+        if self.voices.len() >= MAX_VOICES {
+            self.voices.remove(0);
+        }
+        self.next_id += 1;
+        let hold = duration.unwrap_or(DEFAULT_NOTE_LENGTH);
+        let hold = (hold.is_finite() && hold >= 0.0).then_some((hold * self.sample_rate) as u64);
+        let mut voice = Voice::new(self.next_id, graph, params, hold);
+        voice.sample_rate = self.sample_rate;
+        self.voices.push(voice);
+        Ok(self.next_id)
+    }
+
+    pub fn release(&mut self, id: u64) -> bool {
+        match self.voices.iter_mut().find(|voice| voice.id == id) {
+            Some(voice) => {
+                voice.release();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
+
+    /// Cut every voice without a release tail; returns how many were sounding
+    pub fn stop_all(&mut self) -> usize {
+        let count = self.voices.len();
+        self.voices.clear();
+        self.peak = 0.0;
+        count
+    }
+
+    /// Mix every voice for `frames` samples, dropping voices that have finished
+    /// Level every voice of a synth plays at; changes ramp over the next block so they never click
+    pub fn set_gain(&mut self, name: &str, gain: f32) {
+        let gain = gain.max(0.0);
+        match self.gains.get_mut(name) {
+            Some((_, target)) => *target = gain,
+            None => {
+                self.gains.insert(name.to_string(), (1.0, gain));
+            }
+        }
+    }
+
+    pub fn gain(&self, name: &str) -> f32 {
+        self.gains.get(name).map(|(_, target)| *target).unwrap_or(1.0)
+    }
+
+    /// Gain for sample `index` of a `frames`-long block
+    fn gain_at(gains: &HashMap<String, (f32, f32)>, name: &str, index: usize, frames: usize) -> f32 {
+        match gains.get(name) {
+            Some((from, to)) => from + (to - from) * (index + 1) as f32 / frames as f32,
+            None => 1.0,
+        }
+    }
+
+    fn settle_gains(&mut self) {
+        for (current, target) in self.gains.values_mut() {
+            *current = *target;
+        }
+    }
+
+    pub fn render(&mut self, frames: usize) -> Vec<f32> {
+        let mut mix = vec![0.0f32; frames];
+        for voice in &mut self.voices {
+            for (index, sample) in mix.iter_mut().enumerate() {
+                let gain = Self::gain_at(&self.gains, &voice.graph.name, index, frames);
+                *sample += voice.next_sample(self.sample_rate) as f32 * gain;
+            }
+        }
+        self.settle_gains();
+        self.voices.retain(|voice| !voice.is_finished());
+        self.peak = mix.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        mix
+    }
+
+    /// Like `render`, but also hands back each synth's share of the mix, keyed by synth name
+    pub fn render_stems(&mut self, frames: usize) -> (Vec<f32>, BTreeMap<String, Vec<f32>>) {
+        let mut stems: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+        for voice in &mut self.voices {
+            let stem = stems.entry(voice.graph.name.clone()).or_insert_with(|| vec![0.0; frames]);
+            for (index, sample) in stem.iter_mut().enumerate() {
+                let gain = Self::gain_at(&self.gains, &voice.graph.name, index, frames);
+                *sample += voice.next_sample(self.sample_rate) as f32 * gain;
+            }
+        }
+        self.settle_gains();
+        self.voices.retain(|voice| !voice.is_finished());
+        let mut mix = vec![0.0f32; frames];
+        for stem in stems.values() {
+            for (total, sample) in mix.iter_mut().zip(stem) {
+                *total += sample;
+            }
+        }
+        self.peak = mix.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        (mix, stems)
+    }
+
+    /// Loudest sample of the last rendered block
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+}
+
+static SYNTH_ENGINE: OnceLock<Mutex<SynthEngine>> = OnceLock::new();
+
+pub fn synth_engine() -> &'static Mutex<SynthEngine> {
+    SYNTH_ENGINE.get_or_init(|| Mutex::new(SynthEngine::default()))
+}
+
+/// Run voices forward by one frame's worth of audio; the frame loop calls this in place of a device callback
+pub fn advance_synths(seconds: f64) {
+    // Read before the audio locks: musical effect times follow the transport tempo
+    let (bpm, beats_per_bar, transport_running) = {
+        let transport = crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        (transport.bpm, transport.beats_per_bar as f64, transport.is_playing)
+    };
+    if let Ok(mut engine) = synth_engine().lock() {
+        let mut master = crate::audio::master::master_bus().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let frames = (seconds.max(0.0) * engine.sample_rate) as usize;
+        let mut recorder = crate::audio::recorder::session_recorder().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut cue = crate::audio::cue::cue_bus().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Clips go first so the notes their patterns start sound in this same block; they
+        // hold still while the transport is stopped
+        let mut clips = crate::audio::clips::clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut controller = crate::hardware::grid_controller().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(controller) = controller.as_mut() {
+            for message in crate::audio::midi::take_midi_input() {
+                controller.handle(&message, &mut *clips);
+            }
+        }
+        let clip_audio = if transport_running && clips.tracks() > 0 {
+            let sample_rate = engine.sample_rate;
+            let (audio, midi) = clips.render(frames, sample_rate, bpm, &mut engine);
+            if !midi.is_empty() {
+                crate::audio::midi::broadcast_midi(&midi);
+            }
+            audio
+        } else {
+            Vec::new()
+        };
+        // The pads follow what this block just launched and stopped
+        if let Some(controller) = controller.as_mut() {
+            let leds = controller.refresh(&*clips);
+            if !leds.is_empty() {
+                crate::audio::midi::send_midi_to(&controller.profile.port, &leds);
+            }
+        }
+        drop(controller);
+        drop(clips);
+        // Silence goes through too, so the meters fall back when nothing is playing
+        let (mut block, stems) = match recorder.as_ref() {
+            _ if cue.is_active() => engine.render_stems(frames),
+            Some(session) if session.stems => engine.render_stems(frames),
+            _ => (engine.render(frames), BTreeMap::new()),
+        };
+        let mut cue_block = if cue.is_active() { cue.split(&mut block, &stems, engine.sample_rate) } else { Vec::new() };
+        for (total, sample) in block.iter_mut().zip(&clip_audio) {
+            *total += sample;
+        }
+        // Audio.delay() sits before the master, so panic fades and the limiter cover its echoes too
+        if let Some(delay) = crate::audio::effects::master_delay().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+            crate::audio::effects::AudioEffect::set_sample_rate(delay, engine.sample_rate as f32);
+            delay.set_tempo(bpm, beats_per_bar);
+            for sample in block.iter_mut() {
+                *sample = delay.process(*sample);
+            }
+        }
+        // Audio.apply_reverb() after the delay, so its echoes get the room too
+        if let Some(reverb) = crate::audio::effects::master_reverb().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+            for sample in block.iter_mut() {
+                *sample = reverb.process(*sample);
+            }
+        }
+        master.process(&mut block);
+        // The cue bus follows master fades, so a panic silences the headphones too
+        for sample in cue_block.iter_mut() {
+            *sample *= master.gain();
+        }
+        if let Some(session) = recorder.as_mut() {
+            if let Err(error) = session.write_block(&block, &stems) {
+                tracing::warn!(target: "audio", %error, "📼 session recording stopped");
+                if let Some(session) = recorder.take() {
+                    let _ = session.finish();
+                }
+            }
+        }
+        // A pending Audio.configure() switches over once the master has faded out
+        crate::audio::device::audio_device().lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .poll(&mut engine, &mut master);
+        // Extra devices opened with Audio.open_output()/open_input() trade blocks here
+        crate::audio::aggregate::aggregate_device().lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .exchange(&block, &cue_block);
+        // Once a panic fade has finished there is nothing left worth rendering
+        if master.is_silent() {
+            engine.stop_all();
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameRate {
+    Fps24,
+    Fps25,
+    Fps2997Drop,
+    Fps30,
+}
+
+impl FrameRate {
+    pub fn from_fps(fps: f64) -> Option<Self> {
+        match fps {
+            f if (f - 24.0).abs() < 0.01 => Some(FrameRate::Fps24),
+            f if (f - 25.0).abs() < 0.01 => Some(FrameRate::Fps25),
+            f if (f - 29.97).abs() < 0.01 => Some(FrameRate::Fps2997Drop),
+            f if (f - 30.0).abs() < 0.01 => Some(FrameRate::Fps30),
+            _ => None,
+        }
+    }
+
+    pub fn fps(&self) -> f64 {
+        match self {
+            FrameRate::Fps24 => 24.0,
+            FrameRate::Fps25 => 25.0,
+            FrameRate::Fps2997Drop => 30000.0 / 1001.0,
+            FrameRate::Fps30 => 30.0,
+        }
+    }
+
+    /// Frame count per second as written in the timecode (30 for drop-frame)
+    pub fn nominal(&self) -> u32 {
+        match self {
+            FrameRate::Fps24 => 24,
+            FrameRate::Fps25 => 25,
+            FrameRate::Fps2997Drop | FrameRate::Fps30 => 30,
+        }
+    }
+
+    fn mtc_code(&self) -> u8 {
+        match self {
+            FrameRate::Fps24 => 0,
+            FrameRate::Fps25 => 1,
+            FrameRate::Fps2997Drop => 2,
+            FrameRate::Fps30 => 3,
+        }
+    }
+
+    fn from_mtc_code(code: u8) -> Self {
+        match code & 0x03 {
+            0 => FrameRate::Fps24,
+            1 => FrameRate::Fps25,
+            2 => FrameRate::Fps2997Drop,
+            _ => FrameRate::Fps30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub rate: FrameRate,
+}
+
+impl Timecode {
+    pub fn new(hours: u8, minutes: u8, seconds: u8, frames: u8, rate: FrameRate) -> Self {
+        Self { hours, minutes, seconds, frames, rate }
+    }
+
+    /// Total frames since 00:00:00:00, honouring drop-frame numbering
+    pub fn to_frames(&self) -> u64 {
+        let nominal = self.rate.nominal() as u64;
+        let total_seconds = self.hours as u64 * 3600 + self.minutes as u64 * 60 + self.seconds as u64;
+        let mut frames = total_seconds * nominal + self.frames as u64;
+
+        if self.rate == FrameRate::Fps2997Drop {
+            // Frames 0 and 1 are skipped every minute except each tenth minute
+            let total_minutes = self.hours as u64 * 60 + self.minutes as u64;
+            frames -= 2 * (total_minutes - total_minutes / 10);
+        }
+
+        frames
+    }
+
+    pub fn from_frames(frames: u64, rate: FrameRate) -> Self {
+        let mut frames = frames;
+
+        if rate == FrameRate::Fps2997Drop {
+            let ten_minute_blocks = frames / 17982;
+            let remainder = frames % 17982;
+            frames += 18 * ten_minute_blocks;
+            if remainder > 2 {
+                frames += 2 * ((remainder - 2) / 1798);
+            }
+        }
+
+        let nominal = rate.nominal() as u64;
+        Self {
+            hours: ((frames / (nominal * 3600)) % 24) as u8,
+            minutes: ((frames / (nominal * 60)) % 60) as u8,
+            seconds: ((frames / nominal) % 60) as u8,
+            frames: (frames % nominal) as u8,
+            rate,
+        }
+    }
+
+    pub fn to_seconds(&self) -> f64 {
+        self.to_frames() as f64 / self.rate.fps()
+    }
+
+    pub fn from_seconds(seconds: f64, rate: FrameRate) -> Self {
+        Self::from_frames((seconds.max(0.0) * rate.fps()).round() as u64, rate)
+    }
+
+    /// Parse "HH:MM:SS:FF" (or "HH:MM:SS;FF" for drop-frame)
+    pub fn parse(text: &str, rate: FrameRate) -> Option<Self> {
+        let fields: Vec<u8> = text
+            .split([':', ';', '.'])
+            .map(|part| part.trim().parse().ok())
+            .collect::<Option<Vec<u8>>>()?;
+
+        if fields.len() != 4 || fields[1] > 59 || fields[2] > 59 || fields[3] as u32 >= rate.nominal() {
+            return None;
+        }
+
+        // Drop-frame numbering has no frames 0 and 1 at the start of most minutes
+        let dropped = rate == FrameRate::Fps2997Drop && fields[2] == 0 && fields[3] < 2 && !fields[1].is_multiple_of(10);
+        if dropped {
+            return None;
+        }
+
+        Some(Self::new(fields[0], fields[1], fields[2], fields[3], rate))
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = if self.rate == FrameRate::Fps2997Drop { ';' } else { ':' };
+        write!(f, "{:02}:{:02}:{:02}{}{:02}", self.hours, self.minutes, self.seconds, separator, self.frames)
+    }
+}
+
+const LTC_FRAME_BITS: usize = 80;
+/// Sync word occupying bits 64-79 of an LTC frame, read as a little-endian value
+const LTC_SYNC_WORD: u128 = 0xBFFC;
+
+fn bcd_field(bits: u128, start: usize, len: usize) -> u8 {
+    ((bits >> start) & ((1u128 << len) - 1)) as u8
+}
+
+/// Decodes SMPTE linear timecode from an audio signal (biphase-mark encoded)
+pub struct LtcDecoder {
+    sample_rate: f32,
+    rate: FrameRate,
+    bit_period: f32,
+    samples_since_edge: f32,
+    last_level: bool,
+    half_bit_pending: bool,
+    shift_register: u128,
+    bits_received: usize,
+}
+
+impl LtcDecoder {
+    pub fn new(sample_rate: f32, rate: FrameRate) -> Self {
+        Self {
+            sample_rate,
+            rate,
+            bit_period: sample_rate / (rate.fps() as f32 * LTC_FRAME_BITS as f32),
+            samples_since_edge: 0.0,
+            last_level: false,
+            half_bit_pending: false,
+            shift_register: 0,
+            bits_received: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Feed audio samples; returns the most recent complete frame found in this block. A frame's
+    /// last bit only ends when the next frame starts, so by then the signal is one frame further on
+    pub fn process(&mut self, samples: &[f32]) -> Option<Timecode> {
+        let mut decoded = None;
+
+        for &sample in samples {
+            self.samples_since_edge += 1.0;
+
+            // A little hysteresis keeps noise around zero from registering as edges
+            let level = if sample > 0.05 {
+                true
+            } else if sample < -0.05 {
+                false
+            } else {
+                self.last_level
+            };
+
+            if level == self.last_level {
+                continue;
+            }
+            self.last_level = level;
+
+            let interval = self.samples_since_edge;
+            self.samples_since_edge = 0.0;
+
+            if interval < self.bit_period * 0.75 {
+                // Half-bit interval: two in a row encode a one
+                self.bit_period = self.bit_period * 0.95 + interval * 2.0 * 0.05;
+                if self.half_bit_pending {
+                    self.half_bit_pending = false;
+                    decoded = self.push_bit(true).or(decoded);
+                } else {
+                    self.half_bit_pending = true;
+                }
+            } else if interval < self.bit_period * 1.5 {
+                self.bit_period = self.bit_period * 0.95 + interval * 0.05;
+                self.half_bit_pending = false;
+                decoded = self.push_bit(false).or(decoded);
+            } else {
+                // Signal dropped out; start over with the nominal period
+                self.half_bit_pending = false;
+                self.bits_received = 0;
+                self.bit_period = self.sample_rate / (self.rate.fps() as f32 * LTC_FRAME_BITS as f32);
+            }
+        }
+
+        decoded
+    }
+
+    fn push_bit(&mut self, bit: bool) -> Option<Timecode> {
+        self.shift_register = (self.shift_register >> 1) | ((bit as u128) << (LTC_FRAME_BITS - 1));
+        self.bits_received += 1;
+
+        if self.bits_received < LTC_FRAME_BITS || (self.shift_register >> 64) != LTC_SYNC_WORD {
+            return None;
+        }
+
+        let bits = self.shift_register;
+        let frames = bcd_field(bits, 0, 4) + bcd_field(bits, 8, 2) * 10;
+        let seconds = bcd_field(bits, 16, 4) + bcd_field(bits, 24, 3) * 10;
+        let minutes = bcd_field(bits, 32, 4) + bcd_field(bits, 40, 3) * 10;
+        let hours = bcd_field(bits, 48, 4) + bcd_field(bits, 56, 2) * 10;
+
+        let rate = if bits & (1 << 10) != 0 { FrameRate::Fps2997Drop } else { self.rate };
+        Some(Timecode::new(hours, minutes, seconds, frames, rate))
+    }
+}
+
+/// Generates LTC audio, e.g. to drive lighting desks from a Synthesis timeline
+pub struct LtcEncoder {
+    sample_rate: f32,
+    level: f32,
+    phase: f32,
+}
+
+impl LtcEncoder {
+    pub fn new(sample_rate: f32) -> Self {
+        Self { sample_rate, level: 0.5, phase: 0.0 }
+    }
+
+    pub fn encode_frame(&mut self, timecode: &Timecode) -> Vec<f32> {
+        let bits = Self::frame_bits(timecode);
+        let samples_per_bit = self.sample_rate / (timecode.rate.fps() as f32 * LTC_FRAME_BITS as f32);
+        let mut output = Vec::with_capacity((samples_per_bit * LTC_FRAME_BITS as f32) as usize + 1);
+
+        for i in 0..LTC_FRAME_BITS {
+            let one = bits & (1u128 << i) != 0;
+            // Every bit starts with a transition; ones add a second one halfway
+            self.level = -self.level;
+            let bit_end = self.phase + samples_per_bit;
+            let half = self.phase + samples_per_bit / 2.0;
+
+            while self.phase < bit_end {
+                if one && self.phase >= half && self.phase - 1.0 < half {
+                    self.level = -self.level;
+                }
+                output.push(self.level);
+                self.phase += 1.0;
+            }
+        }
+
+        self.phase -= output.len() as f32;
+        output
+    }
+
+    fn frame_bits(timecode: &Timecode) -> u128 {
+        let field = |value: u8, start: usize| (value as u128) << start;
+        let mut bits = field(timecode.frames % 10, 0)
+            | field(timecode.frames / 10, 8)
+            | field(timecode.seconds % 10, 16)
+            | field(timecode.seconds / 10, 24)
+            | field(timecode.minutes % 10, 32)
+            | field(timecode.minutes / 10, 40)
+            | field(timecode.hours % 10, 48)
+            | field(timecode.hours / 10, 56)
+            | (LTC_SYNC_WORD << 64);
+
+        if timecode.rate == FrameRate::Fps2997Drop {
+            bits |= 1 << 10;
+        }
+
+        bits
+    }
+}
+
+/// Assembles MIDI timecode from quarter-frame and full-frame messages
+pub struct MtcDecoder {
+    pieces: [u8; 8],
+    received: u8,
+}
+
+impl MtcDecoder {
+    pub fn new() -> Self {
+        Self { pieces: [0; 8], received: 0 }
+    }
+
+    pub fn process_message(&mut self, message: &[u8]) -> Option<Timecode> {
+        match message {
+            [0xF1, data] => self.quarter_frame(*data),
+            // Full frame: F0 7F <device> 01 01 hr mn sc fr F7
+            [0xF0, 0x7F, _, 0x01, 0x01, hr, mn, sc, fr, 0xF7] => {
+                self.received = 0;
+                Some(Timecode::new(hr & 0x1F, *mn, *sc, *fr, FrameRate::from_mtc_code(hr >> 5)))
+            }
+            _ => None,
+        }
+    }
+
+    fn quarter_frame(&mut self, data: u8) -> Option<Timecode> {
+        let piece = (data >> 4) as usize & 0x07;
+        self.pieces[piece] = data & 0x0F;
+        self.received |= 1 << piece;
+
+        if piece != 7 || self.received != 0xFF {
+            return None;
+        }
+        self.received = 0;
+
+        let p = &self.pieces;
+        let rate = FrameRate::from_mtc_code(p[7] >> 1);
+        let timecode = Timecode::new(
+            p[6] | ((p[7] & 0x01) << 4),
+            p[4] | ((p[5] & 0x03) << 4),
+            p[2] | ((p[3] & 0x03) << 4),
+            p[0] | ((p[1] & 0x01) << 4),
+            rate,
+        );
+
+        // A full set of quarter frames spans two frames, so the time has moved on since piece 0
+        Some(Timecode::from_frames(timecode.to_frames() + 2, rate))
+    }
+
+    /// Encode a full-frame SysEx message, used to locate receivers after a seek
+    pub fn full_frame(timecode: &Timecode) -> Vec<u8> {
+        vec![
+            0xF0, 0x7F, 0x7F, 0x01, 0x01,
+            (timecode.rate.mtc_code() << 5) | (timecode.hours & 0x1F),
+            timecode.minutes,
+            timecode.seconds,
+            timecode.frames,
+            0xF7,
+        ]
+    }
+}
+
+impl Default for MtcDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tracks an external timecode source and freewheels between updates
+pub struct TimecodeSync {
+    last_timecode: Option<Timecode>,
+    received_at: Instant,
+    freewheel: Duration,
+    offset: f64,
+}
+
+impl TimecodeSync {
+    pub fn new() -> Self {
+        Self {
+            last_timecode: None,
+            received_at: Instant::now(),
+            freewheel: Duration::from_millis(500),
+            offset: 0.0,
+        }
+    }
+
+    /// How long to keep running after the source goes silent (covers LTC dropouts)
+    pub fn with_freewheel(mut self, freewheel: Duration) -> Self {
+        self.freewheel = freewheel;
+        self
+    }
+
+    /// Shift the reported position, e.g. so show time 10:00:00:00 maps to 0.0
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn update(&mut self, timecode: Timecode) {
+        self.last_timecode = Some(timecode);
+        self.received_at = Instant::now();
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.last_timecode.is_some() && self.received_at.elapsed() <= self.freewheel
+    }
+
+    /// Timeline position in seconds, extrapolated while locked and held when the source stops
+    pub fn position(&self) -> Option<f64> {
+        let timecode = self.last_timecode?;
+        let elapsed = self.received_at.elapsed().min(self.freewheel).as_secs_f64();
+        Some(timecode.to_seconds() + elapsed - self.offset)
+    }
+
+    pub fn current_timecode(&self) -> Option<Timecode> {
+        let rate = self.last_timecode?.rate;
+        self.position().map(|seconds| Timecode::from_seconds(seconds + self.offset, rate))
+    }
+}
+
+impl Default for TimecodeSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -17,17 +17,17 @@ fn main() -> synthesis::Result<()> {
     println!("{}", source_code);
     println!("\n=== TOKENIZATION ===");
     
-    match lexer::tokenize(&source_code) {
-        Ok((remaining, tokens)) => {
-            for (i, token) in tokens.iter().enumerate() {
-                println!("{}: {:?}", i, token);
+    match lexer::tokenize_with_spans(&source_code) {
+        Ok((remaining, (tokens, spans))) => {
+            for (i, (token, span)) in tokens.iter().zip(&spans).enumerate() {
+                println!("{}: {:?} at {}:{}", i, token, span.line, span.column);
             }
             if !remaining.is_empty() {
                 println!("Remaining unparsed: '{}'", remaining);
             }
             
             println!("\n=== PARSING ===");
-            let mut parser = Parser::new(&tokens);
+            let mut parser = Parser::with_spans(&tokens, &spans, filename);
             match parser.parse() {
                 Ok(program) => {
                     println!("Parsed successfully!");
@@ -43,7 +43,12 @@ fn main() {
     }
     
     match args[1].as_str() {
-        "new" => create_new_project(&args[2..]),
+        "new" => {
+            if let Err(e) = create_new_project(&args[2..]) {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
         "build" => build_project(&args[2..]),
         "run" => run_project(&args[2..]),
         "install" => install_package(&args[2..]),
@@ -59,7 +64,7 @@ fn main() {
     }
 }
 
-fn create_new_project(args: &[String]) {
+fn create_new_project(args: &[String]) -> anyhow::Result<()> {
     if args.is_empty() {
         eprintln!("Error: Project name required");
         eprintln!("Usage: syn-pkg new <project-name>");
@@ -93,7 +98,7 @@ fn create_new_project(args: &[String]) {
         package: PackageInfo {
             name: project_name.clone(),
             version: "0.1.0".to_string(),
-            description: Some(format!("A Synthesis language project")),
+            description: Some("A Synthesis language project".to_string()),
             author: None,
             license: Some("MIT".to_string()),
         },
@@ -170,6 +175,7 @@ Visit [synthesis-lang.org](https://synthesis-lang.org) for documentation.
     println!("Next steps:");
     println!("  cd {}", project_name);
     println!("  syn-pkg run");
+    Ok(())
 }
 
 fn build_project(_args: &[String]) {
@@ -240,7 +246,7 @@ fn run_project(args: &[String]) {
         "target/debug/synthesis"
     };
     
-    let main_file = args.get(0)
+    let main_file = args.first()
         .map(|s| s.as_str())
         .unwrap_or("src/main.syn");
     
@@ -77,7 +77,7 @@ fn main() -> Result<()> {
                 options.stream_buffer_size = args[i].parse()
                     .map_err(|_| SynthesisError::new(
                         ErrorKind::InvalidExpression,
-                        &format!("🎚️ Buffer size '{}' isn't a valid number", args[i])
+                        format!("🎚️ Buffer size '{}' isn't a valid number", args[i])
                     )
                     .with_suggestion("Try a number like 512, 1024, or 2048")
                     .with_suggestion("Smaller buffers = lower latency, larger = more stable"))?;
@@ -144,11 +144,10 @@ fn main() -> Result<()> {
 
     // Read source code
     let source_code = fs::read_to_string(&input_path)
-        .map_err(|_| SynthesisError::file_not_found(&input_path))?
-        .trim()
-        .to_string();
+        .map_err(|_| SynthesisError::file_not_found(&input_path))?;
 
-    if source_code.is_empty() {
+    // Not trimmed, so error locations match the lines in the file
+    if source_code.trim().is_empty() {
         return Err(SynthesisError::new(
             ErrorKind::SyntaxError,
             "Your Synthesis file is empty"
@@ -157,20 +156,21 @@ fn main() -> Result<()> {
 
     // Parse the source code
     println!("Parsing...");
-    let (_, tokens) = lexer::tokenize(&source_code)
-        .map_err(|_| SynthesisError::new(
+    let (_, (tokens, spans)) = lexer::tokenize_with_spans(&source_code)
+        .map_err(|error| lexer::literal_error(&source_code, &error, &input_path).unwrap_or_else(|| SynthesisError::new(
             ErrorKind::SyntaxError,
             "🎨 There's a syntax issue in your creative code"
         )
         .with_suggestion("Check for typos or missing punctuation")
-        .with_suggestion("Make sure quotes and brackets are balanced"))?;
+        .with_suggestion("Make sure quotes and brackets are balanced")))?;
 
-    let mut parser = Parser::new(&tokens);
+    let mut parser = Parser::with_spans(&tokens, &spans, &input_path);
     let program = parser.parse()?;
 
     // Compile the program
     println!("Compiling...");
     let mut compiler = Compiler::new();
+    let target = options.target.clone();
     let artifact = compiler.compile(&program, options)?;
 
     // Write output
@@ -193,7 +193,7 @@ fn main() -> Result<()> {
         println!("Dependencies: {}", artifact.metadata.dependencies.join(", "));
     }
 
-    match options.target {
+    match target {
         CompilationTarget::WebAssembly => {
             println!("\nTo run this WebAssembly module:");
             println!("1. Use a WebAssembly runtime like wasmtime or Node.js");
@@ -255,7 +255,7 @@ fn parse_target(target_str: &str) -> synthesis::Result<CompilationTarget> {
         "native-macos" => Ok(CompilationTarget::Native(NativeTarget::X86_64MacOS)),
         "native-macos-arm64" => Ok(CompilationTarget::Native(NativeTarget::AArch64MacOS)),
         "native-linux-arm64" => Ok(CompilationTarget::Native(NativeTarget::AArch64Linux)),
-        _ => Err(anyhow::anyhow!("Unsupported target: {}", target_str)),
+        _ => Err(anyhow::anyhow!("Unsupported target: {}", target_str).into()),
     }
 }
 
@@ -265,6 +265,6 @@ fn parse_optimization_level(level_str: &str) -> synthesis::Result<OptimizationLe
         "basic" | "1" => Ok(OptimizationLevel::Basic),
         "aggressive" | "2" => Ok(OptimizationLevel::Aggressive),
         "creative" | "3" => Ok(OptimizationLevel::Creative),
-        _ => Err(anyhow::anyhow!("Invalid optimization level: {}", level_str)),
+        _ => Err(anyhow::anyhow!("Invalid optimization level: {}", level_str).into()),
     }
 }
\ No newline at end of file
@@ -1,3 +1,6 @@
+// The module descriptions are complete ahead of the emitters that will read every field
+#![allow(dead_code)]
+
 use crate::compiler::{CompilationOptions, CompiledArtifact, ArtifactMetadata, NativeTarget, StreamInterface};
 use crate::compiler::ir::*;
 use crate::Result;
@@ -153,6 +156,12 @@ struct WasmMemory {
     shared: bool,
 }
 
+impl Default for WasmBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WasmBackend {
     pub fn new() -> Self {
         Self {
@@ -342,8 +351,8 @@ impl WasmBackend {
             IRInstruction::Mul { dest, .. } |
             IRInstruction::Div { dest, .. } |
             IRInstruction::Load { dest, .. } => {
-                if !register_locals.contains_key(&dest.id) {
-                    register_locals.insert(dest.id, self.convert_type(&dest.ir_type).unwrap_or(WasmType::I32));
+                if let std::collections::hash_map::Entry::Vacant(e) = register_locals.entry(dest.id) {
+                    e.insert(self.convert_type(&dest.ir_type).unwrap_or(WasmType::I32));
                     *local_index += 1;
                 }
             }
@@ -509,7 +518,7 @@ impl WasmBackend {
     fn get_register_local_index(
         &self,
         register_id: usize,
-        register_locals: &std::collections::HashMap<usize, WasmType>,
+        _register_locals: &std::collections::HashMap<usize, WasmType>,
     ) -> Result<u32> {
         // This is simplified - in a real implementation, we'd maintain a proper mapping
         Ok(register_id as u32)
@@ -562,6 +571,12 @@ impl WasmModuleBuilder {
     }
 }
 
+impl Default for NativeBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NativeBackend {
     pub fn new() -> Self {
         let mut target_configs = std::collections::HashMap::new();
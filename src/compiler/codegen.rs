@@ -1,3 +1,6 @@
+// The module and register models are complete ahead of the emitters that will read every field
+#![allow(dead_code)]
+
 use crate::compiler::ir::*;
 use crate::compiler::{CompilationOptions, CompilationTarget};
 use crate::errors::{SynthesisError, ErrorKind};
@@ -26,6 +29,7 @@ struct RegisterAllocator {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)] // Named as the architecture manuals write them
 enum PhysicalRegister {
     // x86_64 registers
     RAX, RBX, RCX, RDX, RSI, RDI, RBP, RSP,
@@ -39,6 +43,12 @@ struct InstructionSelector {
     target_features: Vec<String>,
 }
 
+impl Default for CodeGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CodeGenerator {
     pub fn new() -> Self {
         Self {
@@ -153,7 +163,7 @@ impl WasmCodeGen {
 
     fn generate_functions(&mut self, module: &mut WasmModule, ir: &IR) -> Result<()> {
         for ir_module in &ir.modules {
-            for function in &ir_module.functions {
+            for _function in &ir_module.functions {
                 let type_idx = self.function_index; // Simplified mapping
                 module.add_function(type_idx);
                 self.function_index += 1;
@@ -102,7 +102,7 @@ pub enum IRConstant {
     Boolean(bool),
     String(String),
     AudioFrequency(f64), // Special type for audio frequencies
-    ColorRGB(u8, u8, u8),
+    ColorRGBA(u8, u8, u8, u8),
     Percentage(f64), // 0.0 to 1.0
 }
 
@@ -157,16 +157,20 @@ pub struct IRParameter {
 pub struct IRGenerator {
     next_register_id: usize,
     next_block_id: usize,
-    current_function: Option<String>,
     symbol_table: HashMap<String, IRRegister>,
 }
 
+impl Default for IRGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IRGenerator {
     pub fn new() -> Self {
         Self {
             next_register_id: 0,
             next_block_id: 0,
-            current_function: None,
             symbol_table: HashMap::new(),
         }
     }
@@ -223,6 +227,9 @@ impl IRGenerator {
                 Item::Struct(_struct_def) => {
                     // TODO: Implement struct definition handling
                 }
+                Item::Synth(_synth_def) => {
+                    // Synth graphs are compiled by the audio engine, not lowered to IR
+                }
             }
         }
 
@@ -233,7 +240,7 @@ impl IRGenerator {
         Ok(ir)
     }
 
-    fn generate_import(&mut self, block: &mut BasicBlock, _import: &crate::parser::ast::ImportItem) -> Result<()> {
+    fn generate_import(&mut self, _block: &mut BasicBlock, _import: &crate::parser::ast::ImportItem) -> Result<()> {
         // For now, imports are handled at compile time
         // In the future, this could generate dynamic loading instructions
         Ok(())
@@ -241,7 +248,7 @@ impl IRGenerator {
 
     fn generate_statement(&mut self, block: &mut BasicBlock, stmt: &Statement) -> Result<()> {
         match stmt {
-            Statement::Assignment { name, value } => {
+            Statement::Assignment { name, value } | Statement::Const { name, value, .. } => {
                 let dest_reg = self.allocate_register(Some(name.clone()), IRType::Any);
                 let value_ir = self.generate_expression(block, value)?;
                 
@@ -252,6 +259,9 @@ impl IRGenerator {
                 
                 self.symbol_table.insert(name.clone(), dest_reg);
             }
+            Statement::FieldAssignment { .. } => {
+                // TODO: Implement field assignment IR generation
+            }
             Statement::Expression(expr) => {
                 self.generate_expression(block, expr)?;
             }
@@ -267,13 +277,19 @@ impl IRGenerator {
             Statement::After { .. } => {
                 // TODO: Implement after statement IR generation
             }
+            Statement::Async { .. } => {
+                // TODO: Implement async block IR generation
+            }
             Statement::While { .. } => {
                 // TODO: Implement while statement IR generation
             }
             Statement::For { .. } => {
                 // TODO: Implement for statement IR generation
             }
-            Statement::Let { .. } => {
+            Statement::Try { .. } => {
+                // TODO: Implement try statement IR generation
+            }
+            Statement::Let { .. } | Statement::LetDestructure { .. } => {
                 // TODO: Implement let statement IR generation
             }
             Statement::Return(_) => {
@@ -285,6 +301,9 @@ impl IRGenerator {
             Statement::Continue => {
                 // TODO: Implement continue statement IR generation
             }
+            Statement::Capabilities(_) => {
+                // Enforced by the runtime, nothing to generate
+            }
         }
         Ok(())
     }
@@ -293,9 +312,10 @@ impl IRGenerator {
         match expr {
             Expression::Literal(literal) => {
                 match literal {
-                    Literal::Integer(n) => Ok(IRValue::Constant(IRConstant::Integer(*n))),
-                    Literal::Float(f) => Ok(IRValue::Constant(IRConstant::Float(*f))),
+                    Literal::Integer(n) | Literal::BasedInteger(n, _) => Ok(IRValue::Constant(IRConstant::Integer(*n))),
+                    Literal::Float(f) | Literal::Percentage(f) => Ok(IRValue::Constant(IRConstant::Float(*f))),
                     Literal::String(s) => Ok(IRValue::Constant(IRConstant::String(s.clone()))),
+                    Literal::Color([r, g, b, a]) => Ok(IRValue::Constant(IRConstant::ColorRGBA(*r, *g, *b, *a))),
                     Literal::Boolean(b) => Ok(IRValue::Constant(IRConstant::Boolean(*b))),
                 }
             }
@@ -353,10 +373,28 @@ impl IRGenerator {
                 block.instructions.push(instruction);
                 Ok(IRValue::Register(dest_reg))
             }
+            Expression::UnaryOp { op, operand } => {
+                let value = self.generate_expression(block, operand)?;
+                let dest_reg = self.allocate_register(None, IRType::Any);
+                let instruction = match op {
+                    UnaryOperator::Negate => IRInstruction::Sub { dest: dest_reg.clone(), left: IRValue::Constant(IRConstant::Integer(0)), right: value },
+                    UnaryOperator::Not => return Err(SynthesisError::new(
+                        ErrorKind::CompilationFailed,
+                        "Logical not can't be compiled yet"
+                    )
+                    .with_suggestion("Compare instead, like: flag == false")),
+                };
+                block.instructions.push(instruction);
+                Ok(IRValue::Register(dest_reg))
+            }
             Expression::Block { .. } => {
                 // TODO: Implement block expression
                 Ok(IRValue::Constant(IRConstant::Integer(0)))
             }
+            Expression::StructLiteral { .. } => {
+                // TODO: Implement struct construction
+                Ok(IRValue::Constant(IRConstant::Integer(0)))
+            }
             Expression::ArrayAccess { .. } => {
                 // TODO: Implement array access
                 Ok(IRValue::Constant(IRConstant::Integer(0)))
@@ -381,7 +419,7 @@ impl IRGenerator {
                 // TODO: Implement unit value
                 Ok(IRValue::Constant(IRConstant::Float(0.0)))
             }
-            Expression::ArrayLiteral(_elements) => {
+            Expression::ArrayLiteral(_) | Expression::Spread(_) => {
                 // TODO: Implement array literal generation
                 Ok(IRValue::Constant(IRConstant::Integer(0)))
             }
@@ -393,7 +431,7 @@ impl IRGenerator {
                 // TODO: Implement lambda generation
                 Ok(IRValue::Constant(IRConstant::Integer(0)))
             }
-            Expression::MethodCall { object: _object, method: _method, args: _args, named_args: _named_args } => {
+            Expression::MethodCall { object: _object, method: _method, args: _args, named_args: _named_args, .. } => {
                 // TODO: Implement method call generation
                 Ok(IRValue::Constant(IRConstant::Integer(0)))
             }
@@ -438,10 +476,9 @@ impl IRGenerator {
                             .with_suggestion("Example: Audio.analyze_fft(audio_data, 8)")
                             .with_docs("https://synthesis-lang.org/docs/audio#fft-analysis"));
                         }
-                        let bands = if args.len() > 1 {
-                            8 // Default or extract from args
-                        } else {
-                            8
+                        let bands = match args.get(1) {
+                            Some(IRValue::Constant(IRConstant::Integer(n))) if *n > 0 => *n as usize,
+                            _ => 8,
                         };
                         
                         if let IRValue::Register(audio_reg) = &args[0] {
@@ -493,7 +530,7 @@ impl IRGenerator {
     fn generate_loop(&mut self, function: &mut IRFunction, loop_block: &LoopBlock) -> Result<()> {
         let loop_label = format!("loop_{}", self.next_block_id);
         self.next_block_id += 1;
-        let exit_label = format!("loop_exit_{}", self.next_block_id);
+        let _exit_label = format!("loop_exit_{}", self.next_block_id);
         self.next_block_id += 1;
 
         // Create loop header block
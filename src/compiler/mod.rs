@@ -56,6 +56,12 @@ impl Default for CompilationOptions {
     }
 }
 
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Compiler {
     pub fn new() -> Self {
         Self {
@@ -67,6 +73,11 @@ impl Compiler {
     }
 
     pub fn compile(&mut self, program: &Program, options: CompilationOptions) -> Result<CompiledArtifact> {
+        // Step 0: Annotations have to hold before anything is generated
+        if let Some(mismatch) = crate::parser::check_types(program).into_iter().next() {
+            return Err(mismatch);
+        }
+        
         // Step 1: Generate Intermediate Representation
         let ir = self.ir_generator.generate(program)?;
         
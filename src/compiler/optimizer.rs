@@ -12,9 +12,7 @@ pub trait OptimizationPass {
     fn run(&mut self, ir: &mut IR, options: &CompilationOptions) -> Result<bool>; // Returns true if changes were made
 }
 
-pub struct DeadCodeElimination {
-    removed_functions: HashSet<String>,
-}
+pub struct DeadCodeElimination;
 
 pub struct StreamFusion {
     fused_streams: HashMap<String, Vec<String>>,
@@ -35,24 +33,28 @@ pub struct RealTimeOptimization {
 
 struct AudioOptimizer {
     buffer_sizes: HashMap<String, usize>,
-    sample_rates: HashMap<String, f32>,
 }
 
 struct GraphicsOptimizer {
     render_batches: Vec<Vec<String>>,
-    shader_cache: HashMap<String, String>,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Optimizer {
     pub fn new() -> Self {
-        let mut passes: Vec<Box<dyn OptimizationPass>> = Vec::new();
-        
-        // Add optimization passes in order of execution
-        passes.push(ConstantFolding::new());
-        passes.push(DeadCodeElimination::new());
-        passes.push(StreamFusion::new());
-        passes.push(CreativeDomainOptimization::new());
-        passes.push(RealTimeOptimization::new());
+        // Optimization passes in order of execution
+        let passes: Vec<Box<dyn OptimizationPass>> = vec![
+            ConstantFolding::new(),
+            DeadCodeElimination::new(),
+            StreamFusion::new(),
+            CreativeDomainOptimization::new(),
+            RealTimeOptimization::new(),
+        ];
 
         Self { passes }
     }
@@ -170,9 +172,7 @@ impl ConstantFolding {
 
 impl DeadCodeElimination {
     fn new() -> Box<Self> {
-        Box::new(Self {
-            removed_functions: HashSet::new(),
-        })
+        Box::new(Self)
     }
 }
 
@@ -242,15 +242,9 @@ impl DeadCodeElimination {
         for block in function.basic_blocks.iter().rev() {
             // Mark registers used in terminator
             match &block.terminator {
-                Terminator::Return(Some(value)) => {
-                    if let IRValue::Register(reg) = value {
-                        live_registers.insert(reg.id);
-                    }
-                }
-                Terminator::Branch { condition, .. } => {
-                    if let IRValue::Register(reg) = condition {
-                        live_registers.insert(reg.id);
-                    }
+                Terminator::Return(Some(IRValue::Register(reg))) |
+                Terminator::Branch { condition: IRValue::Register(reg), .. } => {
+                    live_registers.insert(reg.id);
                 }
                 _ => {}
             }
@@ -357,11 +351,11 @@ impl StreamFusion {
         Ok(opportunities)
     }
 
-    fn fuse_streams(&mut self, _ir: &mut IR, consumer: &str, producers: &Vec<String>) -> Result<bool> {
+    fn fuse_streams(&mut self, _ir: &mut IR, consumer: &str, producers: &[String]) -> Result<bool> {
         // Simplified stream fusion - in reality this would be much more complex
         if producers.len() > 1 {
             println!("Fusing {} producer streams into consumer {}", producers.len(), consumer);
-            self.fused_streams.insert(consumer.to_string(), producers.clone());
+            self.fused_streams.insert(consumer.to_string(), producers.to_vec());
             return Ok(true);
         }
         Ok(false)
@@ -403,7 +397,6 @@ impl AudioOptimizer {
     fn new() -> Self {
         Self {
             buffer_sizes: HashMap::new(),
-            sample_rates: HashMap::new(),
         }
     }
 
@@ -415,7 +408,7 @@ impl AudioOptimizer {
                 for block in &mut function.basic_blocks {
                     for instruction in &mut block.instructions {
                         match instruction {
-                            IRInstruction::AudioAnalyzeFFT { dest, audio, bands } => {
+                            IRInstruction::AudioAnalyzeFFT { dest: _, audio, bands } => {
                                 // Optimize FFT size to power of 2
                                 let optimized_bands = self.next_power_of_2(*bands);
                                 if optimized_bands != *bands {
@@ -476,7 +469,6 @@ impl GraphicsOptimizer {
     fn new() -> Self {
         Self {
             render_batches: Vec::new(),
-            shader_cache: HashMap::new(),
         }
     }
 
@@ -1,4 +1,4 @@
-use crate::errors::{get_error_translator, RustErrorTranslator};
+use crate::errors::get_error_translator;
 
 #[cfg(test)]
 mod tests {
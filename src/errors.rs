@@ -35,6 +35,7 @@ pub enum ErrorKind {
     // Runtime errors
     AudioDeviceError,
     GraphicsContextError,
+    InputDeviceError,
     StreamBufferOverflow,
     PerformanceConstraintViolation,
     
@@ -117,7 +118,7 @@ impl SynthesisError {
             ErrorKind::UnknownModule, 
             format!("Unknown module '{}'", module)
         )
-        .with_suggestion(format!("Did you mean one of these? Audio, Graphics, GUI, Hardware, Math, Time"))
+        .with_suggestion("Did you mean one of these? Audio, Graphics, GUI, Hardware, Math, Time".to_string())
         .with_suggestion("Make sure to import the module at the top of your file")
         .with_docs("https://synthesis-lang.org/docs/modules")
     }
@@ -173,6 +174,15 @@ impl SynthesisError {
         .with_docs("https://synthesis-lang.org/docs/audio-troubleshooting")
     }
 
+    pub fn input_device_error(details: impl Into<String>) -> Self {
+        Self::new(
+            ErrorKind::InputDeviceError,
+            format!("Input device error: {}", details.into())
+        )
+        .with_suggestion("Check that the device is plugged in and that you have permission to open it")
+        .with_docs("https://synthesis-lang.org/docs/api/hardware")
+    }
+
     pub fn stream_overflow(stream_name: impl Into<String>) -> Self {
         Self::new(
             ErrorKind::StreamBufferOverflow,
@@ -223,7 +233,7 @@ impl SynthesisError {
         .with_docs("https://synthesis-lang.org/docs/types")
     }
 
-    pub fn trait_bound_error(type_name: impl Into<String>, missing_trait: impl Into<String>) -> Self {
+    pub fn trait_bound_error(type_name: impl Into<String>, _missing_trait: impl Into<String>) -> Self {
         Self::new(
             ErrorKind::TraitBoundError,
             format!("Type '{}' doesn't support the operation you're trying to perform", type_name.into())
@@ -310,6 +320,7 @@ impl fmt::Display for SynthesisError {
             ErrorKind::TraitBoundError => "🔗",
             ErrorKind::AudioDeviceError | ErrorKind::BufferSizeError | ErrorKind::SampleRateError => "🎧",
             ErrorKind::GraphicsContextError => "🎨",
+            ErrorKind::InputDeviceError => "🕹️",
             ErrorKind::StreamBufferOverflow | ErrorKind::StreamBufferUnderrun => "🌊",
             ErrorKind::StreamConnectionError | ErrorKind::InvalidStreamConnection => "🔌",
             ErrorKind::StreamTimeout | ErrorKind::InvalidStreamFormat => "⏱️",
@@ -319,7 +330,6 @@ impl fmt::Display for SynthesisError {
             ErrorKind::FileNotFound => "📁",
             ErrorKind::PermissionDenied => "🔒",
             ErrorKind::OutOfMemory => "💾",
-            _ => "❗",
         };
 
         writeln!(f, "{} Synthesis Error: {}", emoji, self.message)?;
@@ -446,6 +456,12 @@ impl From<cpal::PlayStreamError> for SynthesisError {
     }
 }
 
+impl From<cpal::DevicesError> for SynthesisError {
+    fn from(err: cpal::DevicesError) -> Self {
+        SynthesisError::audio_device_error(format!("Audio device list error: {}", err))
+    }
+}
+
 // Handle graphics surface errors
 impl From<wgpu::SurfaceError> for SynthesisError {
     fn from(err: wgpu::SurfaceError) -> Self {
@@ -470,6 +486,14 @@ impl From<serialport::Error> for SynthesisError {
     }
 }
 
+// Handle HID errors
+#[cfg(feature = "hidapi")]
+impl From<hidapi::HidError> for SynthesisError {
+    fn from(err: hidapi::HidError) -> Self {
+        SynthesisError::input_device_error(format!("HID: {}", err))
+    }
+}
+
 // Handle MIDI errors
 impl From<midir::InitError> for SynthesisError {
     fn from(err: midir::InitError) -> Self {
@@ -546,6 +570,12 @@ struct ErrorPattern {
     docs_url: Option<String>,
 }
 
+impl Default for RustErrorTranslator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RustErrorTranslator {
     pub fn new() -> Self {
         let mut translator = Self {
@@ -581,6 +611,19 @@ impl RustErrorTranslator {
             Some("https://synthesis-lang.org/docs/types".to_string()),
         );
 
+        // E0277: Trait bound not satisfied  
+        self.add_pattern(
+            r"E0277.*the trait bound.*is not satisfied",
+            ErrorKind::TraitBoundError,
+            |_| "This operation isn't supported for this type of data".to_string(),
+            vec![
+                "Check if you're using compatible data types".to_string(),
+                "Some operations only work with specific types like Numbers or Audio".to_string(),
+                "Try converting your data to the right type first".to_string(),
+            ],
+            Some("https://synthesis-lang.org/docs/types#operations".to_string()),
+        );
+
         // Trait bound errors
         self.add_pattern(
             r"the trait bound.*is not satisfied",
@@ -653,7 +696,7 @@ impl RustErrorTranslator {
 
         // Mismatched types
         self.add_pattern(
-            r"mismatched types.*expected.*found",
+            r"(?s)mismatched types.*expected.*found",
             ErrorKind::TypeMismatch,
             |msg| {
                 // Try to extract type information from error message
@@ -690,25 +733,12 @@ impl RustErrorTranslator {
             Some("https://synthesis-lang.org/docs/functions".to_string()),
         );
 
-        // E0277: Trait bound not satisfied  
-        self.add_pattern(
-            r"E0277.*the trait bound.*is not satisfied",
-            ErrorKind::TraitBoundError,
-            |_| "This operation isn't supported for this type of data".to_string(),
-            vec![
-                "Check if you're using compatible data types".to_string(),
-                "Some operations only work with specific types like Numbers or Audio".to_string(),
-                "Try converting your data to the right type first".to_string(),
-            ],
-            Some("https://synthesis-lang.org/docs/types#operations".to_string()),
-        );
-
         // E0382: Use after move (borrow checker)
         self.add_pattern(
             r"E0382.*use of moved value",
             ErrorKind::InvalidExpression,
             |msg| {
-                if let Some(var_match) = Regex::new(r"value `([^`]+)`").unwrap().captures(msg) {
+                if let Some(var_match) = Regex::new(r"moved value: `([^`]+)`").unwrap().captures(msg) {
                     format!("Variable '{}' was already used and can't be used again", &var_match[1])
                 } else {
                     "Variable was already used and can't be used again".to_string()
@@ -933,7 +963,7 @@ fn simplify_rust_type(rust_type: &str) -> String {
             // Remove generic parameters and crate prefixes
             let simplified = rust_type
                 .split('<').next().unwrap_or(rust_type)
-                .split(':').last().unwrap_or(rust_type)
+                .split(':').next_back().unwrap_or(rust_type)
                 .trim();
             
             if simplified.is_empty() {
@@ -949,7 +979,7 @@ fn simplify_rust_type(rust_type: &str) -> String {
 static ERROR_TRANSLATOR: std::sync::OnceLock<RustErrorTranslator> = std::sync::OnceLock::new();
 
 pub fn get_error_translator() -> &'static RustErrorTranslator {
-    ERROR_TRANSLATOR.get_or_init(|| RustErrorTranslator::new())
+    ERROR_TRANSLATOR.get_or_init(RustErrorTranslator::new)
 }
 
 /// Context-aware suggestion generator
@@ -977,13 +1007,12 @@ pub fn generate_context_suggestions(error_kind: &ErrorKind, message: &str) -> Ve
             suggestions.push("Module names are case-sensitive".to_string());
         }
         
-        ErrorKind::AudioDeviceError => {
-            if message.contains("device") {
+        ErrorKind::AudioDeviceError
+            if message.contains("device") => {
                 suggestions.push("Try plugging/unplugging your audio device".to_string());
                 suggestions.push("Check audio device settings in your system".to_string());
                 suggestions.push("Close other applications that might be using audio".to_string());
             }
-        }
         
         ErrorKind::TypeMismatch => {
             if message.contains("Number") && message.contains("Text") {
@@ -1,7 +1,7 @@
-/// Integration utilities for the error translation system
-/// 
-/// This module provides helper functions and macros to easily integrate
-/// the error translation system throughout the codebase.
+//! Integration utilities for the error translation system
+//!
+//! This module provides helper functions and macros to easily integrate
+//! the error translation system throughout the codebase.
 
 use super::{SynthesisError, ErrorKind, get_error_translator};
 
@@ -22,6 +22,7 @@ macro_rules! synthesis_error_from {
             )
             .with_suggestion("Try simplifying the code around this area")
             .with_suggestion("Check for syntax errors or type issues")
+            .with_suggestion(format!("Context: {}", $context))
         }
     }};
 }
@@ -46,7 +47,8 @@ where
                     format!("Error in {}: {}", context, error_str)
                 )
                 .with_suggestion("Try simplifying the code in this area")
-                .with_suggestion("Check for syntax or type compatibility issues"))
+                .with_suggestion("Check for syntax or type compatibility issues")
+                .with_suggestion(format!("Context: {}", context)))
             }
         }
     }
@@ -90,7 +92,7 @@ pub fn translate_std_error<E: std::error::Error>(error: E, context: &str) -> Syn
 
 /// Create user-friendly errors for compilation context
 pub fn compilation_error(phase: &str, details: &str) -> SynthesisError {
-    let error_str = format!("{}: {}", phase, details);
+    let _error_str = format!("{}: {}", phase, details);
     
     // Try translation first
     if let Some(translated) = get_error_translator().translate_rust_error(details) {
@@ -179,7 +181,7 @@ mod tests {
     #[test]
     fn test_execute_with_translation_error() {
         let result = execute_with_translation(
-            || Err(anyhow::anyhow!("test error")), 
+            || -> anyhow::Result<()> { Err(anyhow::anyhow!("test error")) },
             "test operation"
         );
         assert!(result.is_err());
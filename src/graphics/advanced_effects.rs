@@ -482,10 +482,10 @@ impl BloomEffect {
         let mut sum = 0.0;
         let center = kernel_size / 2;
         
-        for i in 0..kernel_size {
+        for (i, slot) in kernel.iter_mut().enumerate() {
             let x = (i as f32) - (center as f32);
             let weight = (-0.5 * x * x / (sigma * sigma)).exp();
-            kernel[i] = weight;
+            *slot = weight;
             sum += weight;
         }
         
@@ -500,12 +500,11 @@ impl BloomEffect {
             for x in 0..width {
                 let mut result = Color::new(0.0, 0.0, 0.0, 0.0);
                 
-                for i in 0..kernel_size {
+                for (i, &weight) in kernel.iter().enumerate() {
                     let sample_x = (x as i32) + (i as i32) - (center as i32);
                     if sample_x >= 0 && sample_x < width as i32 {
                         let idx = (y * width + sample_x as u32) as usize;
                         let sample = input[idx];
-                        let weight = kernel[i];
                         
                         result.r += sample.r * weight;
                         result.g += sample.g * weight;
@@ -524,12 +523,11 @@ impl BloomEffect {
             for x in 0..width {
                 let mut pixel = Color::new(0.0, 0.0, 0.0, 0.0);
                 
-                for i in 0..kernel_size {
+                for (i, &weight) in kernel.iter().enumerate() {
                     let sample_y = (y as i32) + (i as i32) - (center as i32);
                     if sample_y >= 0 && sample_y < height as i32 {
                         let idx = ((sample_y as u32) * width + x) as usize;
                         let sample = temp[idx];
-                        let weight = kernel[i];
                         
                         pixel.r += sample.r * weight;
                         pixel.g += sample.g * weight;
@@ -718,7 +716,7 @@ impl WindEffect {
         self.time += dt;
     }
     
-    pub fn get_force_at(&self, x: f32, y: f32) -> (f32, f32) {
+    pub fn get_force_at(&self, x: f32, _y: f32) -> (f32, f32) {
         // Base wind direction
         let base_x = self.direction.cos() * self.strength;
         let base_y = self.direction.sin() * self.strength;
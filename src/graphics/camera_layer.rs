@@ -0,0 +1,235 @@
+use crate::graphics::primitives::Color;
+use crate::hardware::webcam::WebcamFrame;
+
+#[derive(Debug, Clone, Copy)]
+pub enum KeyMode {
+    /// Remove pixels close to `key` (green screen)
+    Chroma { key: Color, tolerance: f32, softness: f32, spill: f32 },
+    /// Remove pixels by brightness; `invert` keeps the dark parts instead
+    Luma { threshold: f32, softness: f32, invert: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mirror {
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Mirror {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Mirror::None),
+            "horizontal" | "x" => Some(Mirror::Horizontal),
+            "vertical" | "y" => Some(Mirror::Vertical),
+            "both" | "xy" => Some(Mirror::Both),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColorGrade {
+    pub exposure: f32,    // stops, 0.0 = unchanged
+    pub contrast: f32,    // 1.0 = unchanged
+    pub saturation: f32,  // 1.0 = unchanged
+    pub temperature: f32, // -1.0 (cool) to 1.0 (warm)
+    pub tint: Color,      // multiplied in, white = unchanged
+}
+
+impl ColorGrade {
+    pub fn new() -> Self {
+        Self {
+            exposure: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            temperature: 0.0,
+            tint: Color::WHITE,
+        }
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let gain = 2.0f32.powf(self.exposure);
+        let mut r = color.r * gain;
+        let mut g = color.g * gain;
+        let mut b = color.b * gain;
+
+        // Contrast pivots around mid grey
+        r = (r - 0.5) * self.contrast + 0.5;
+        g = (g - 0.5) * self.contrast + 0.5;
+        b = (b - 0.5) * self.contrast + 0.5;
+
+        let luma = luminance(r, g, b);
+        r = luma + (r - luma) * self.saturation;
+        g = luma + (g - luma) * self.saturation;
+        b = luma + (b - luma) * self.saturation;
+
+        r += self.temperature * 0.1;
+        b -= self.temperature * 0.1;
+
+        Color::new(
+            (r * self.tint.r).clamp(0.0, 1.0),
+            (g * self.tint.g).clamp(0.0, 1.0),
+            (b * self.tint.b).clamp(0.0, 1.0),
+            color.a,
+        )
+    }
+}
+
+impl Default for ColorGrade {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn luminance(r: f32, g: f32, b: f32) -> f32 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
+
+/// Live camera imagery prepared for compositing into a generative scene
+#[derive(Debug, Clone)]
+pub struct CameraLayer {
+    pub key: Option<KeyMode>,
+    pub mirror: Mirror,
+    pub grade: ColorGrade,
+    pub opacity: f32,
+}
+
+impl CameraLayer {
+    pub fn new() -> Self {
+        Self {
+            key: None,
+            mirror: Mirror::Horizontal, // selfie view feels natural for performers
+            grade: ColorGrade::new(),
+            opacity: 1.0,
+        }
+    }
+
+    pub fn with_key(mut self, key: KeyMode) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    pub fn with_mirror(mut self, mirror: Mirror) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    pub fn with_grade(mut self, grade: ColorGrade) -> Self {
+        self.grade = grade;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Convert a captured RGB frame into a color buffer
+    pub fn frame_to_colors(frame: &WebcamFrame) -> Vec<Color> {
+        frame.data
+            .chunks_exact(3)
+            .map(|rgb| Color::rgb(rgb[0] as f32 / 255.0, rgb[1] as f32 / 255.0, rgb[2] as f32 / 255.0))
+            .collect()
+    }
+
+    /// Mirror, key and grade a frame; keyed-out pixels end up with reduced alpha
+    pub fn process(&self, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        let (w, h) = (width as usize, height as usize);
+        let mut output = Vec::with_capacity(w * h);
+
+        for y in 0..h {
+            for x in 0..w {
+                let sx = match self.mirror {
+                    Mirror::Horizontal | Mirror::Both => w - 1 - x,
+                    _ => x,
+                };
+                let sy = match self.mirror {
+                    Mirror::Vertical | Mirror::Both => h - 1 - y,
+                    _ => y,
+                };
+
+                let mut color = input.get(sy * w + sx).copied().unwrap_or(Color::BLACK);
+                let mut alpha = color.a;
+
+                if let Some(key) = &self.key {
+                    let (matte, keyed) = Self::key_pixel(key, color);
+                    alpha *= matte;
+                    color = keyed;
+                }
+
+                let mut graded = self.grade.apply(color);
+                graded.a = alpha * self.opacity;
+                output.push(graded);
+            }
+        }
+
+        output
+    }
+
+    /// Blend the processed camera layer over a background buffer of the same size
+    pub fn composite_over(&self, camera: &[Color], background: &[Color], width: u32, height: u32) -> Vec<Color> {
+        let processed = self.process(camera, width, height);
+        processed
+            .iter()
+            .zip(background.iter())
+            .map(|(fg, bg)| {
+                let a = fg.a;
+                Color::new(
+                    fg.r * a + bg.r * (1.0 - a),
+                    fg.g * a + bg.g * (1.0 - a),
+                    fg.b * a + bg.b * (1.0 - a),
+                    bg.a.max(a),
+                )
+            })
+            .collect()
+    }
+
+    fn key_pixel(key: &KeyMode, color: Color) -> (f32, Color) {
+        match *key {
+            KeyMode::Chroma { key, tolerance, softness, spill } => {
+                // Compare chroma only so shadows on the backdrop still key out
+                let (cb, cr) = chroma(color);
+                let (kb, kr) = chroma(key);
+                let distance = ((cb - kb).powi(2) + (cr - kr).powi(2)).sqrt();
+                let matte = smoothstep(tolerance, tolerance + softness.max(0.0001), distance);
+
+                // Pull the key color out of semi-transparent edges
+                let mut keyed = color;
+                if spill > 0.0 {
+                    let luma = luminance(color.r, color.g, color.b);
+                    let amount = spill * (1.0 - matte);
+                    keyed.r += (luma - color.r) * amount;
+                    keyed.g += (luma - color.g) * amount;
+                    keyed.b += (luma - color.b) * amount;
+                }
+                (matte, keyed)
+            }
+            KeyMode::Luma { threshold, softness, invert } => {
+                let luma = luminance(color.r, color.g, color.b);
+                let matte = smoothstep(threshold, threshold + softness.max(0.0001), luma);
+                (if invert { 1.0 - matte } else { matte }, color)
+            }
+        }
+    }
+}
+
+impl Default for CameraLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Chroma relative to brightness, so a shadowed part of the backdrop still matches its key
+fn chroma(color: Color) -> (f32, f32) {
+    let y = luminance(color.r, color.g, color.b);
+    // Near black there's no hue to speak of; don't let sensor noise blow up
+    let scale = 1.0 / y.max(0.05);
+    ((color.b - y) * 0.565 * scale, (color.r - y) * 0.713 * scale)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -147,7 +147,7 @@ pub struct EffectRenderer;
 
 impl EffectRenderer {
     pub fn create_plasma(args: &[Value]) -> crate::Result<Plasma> {
-        let speed = args.get(0)
+        let speed = args.first()
             .and_then(|v| v.as_number())
             .unwrap_or(1.0) as f32;
         
@@ -157,7 +157,7 @@ impl EffectRenderer {
     }
     
     pub fn create_starfield(args: &[Value]) -> crate::Result<Starfield> {
-        let count = args.get(0)
+        let count = args.first()
             .and_then(|v| v.as_number())
             .unwrap_or(100.0) as usize;
         
@@ -0,0 +1,485 @@
+use crate::graphics::advanced_effects::Particle;
+use crate::graphics::primitives::Color;
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex, OnceLock};
+
+/// Largest grid a fluid can have; the solver's cost grows with the square of it
+pub const MAX_FLUID_RESOLUTION: usize = 256;
+
+static FLUIDS: OnceLock<Mutex<HashMap<String, FluidLayer>>> = OnceLock::new();
+static SOLVER: OnceLock<mpsc::Sender<FluidStep>> = OnceLock::new();
+
+/// Running fluids by name, as scripts set them up; the solver thread steps each one and the
+/// interpreter publishes its dye as a layer
+pub fn fluid_layers() -> &'static Mutex<HashMap<String, FluidLayer>> {
+    FLUIDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// One fluid's dye after a step, for the frame loop to publish as its layer
+#[derive(Debug, Clone)]
+pub struct FluidFrame {
+    pub name: String,
+    pub layer: i64,
+    pub width: usize,
+    pub height: usize,
+    pub dye: Vec<Color>,
+}
+
+struct FluidStep {
+    seconds: f32,
+    levels: HashMap<String, f32>,
+    reply: mpsc::Sender<Vec<FluidFrame>>,
+}
+
+/// Step every running fluid by `seconds` on the solver thread, started on first use, so a large
+/// grid never holds up the script. `levels` has the newest sample of each emitter's source stream.
+/// The frames arrive on the receiver once the step is done
+pub fn step_fluids(seconds: f32, levels: HashMap<String, f32>) -> mpsc::Receiver<Vec<FluidFrame>> {
+    let (reply, frames) = mpsc::channel();
+    let solver = SOLVER.get_or_init(|| {
+        let (requests, steps) = mpsc::channel();
+        if let Err(error) = std::thread::Builder::new().name("fluid solver".to_string()).spawn(move || solve(steps)) {
+            tracing::warn!(target: "graphics", %error, "🌊 couldn't start the fluid solver");
+        }
+        requests
+    });
+    // Without a solver thread the receiver just never gets frames
+    let _ = solver.send(FluidStep { seconds, levels, reply });
+    frames
+}
+
+/// The solver thread: keeps each fluid's simulation and steps them all on request. The shared
+/// settings are only locked long enough to copy them, so module calls never wait on a step
+fn solve(steps: mpsc::Receiver<FluidStep>) {
+    let mut simulations: HashMap<String, FluidSimulation> = HashMap::new();
+    for step in steps {
+        let fluids: Vec<(String, FluidLayer)> = {
+            let mut fluids = fluid_layers().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            fluids.iter_mut()
+                .map(|(name, fluid)| (name.clone(), FluidLayer { forces: std::mem::take(&mut fluid.forces), ..fluid.clone() }))
+                .collect()
+        };
+        simulations.retain(|name, _| fluids.iter().any(|(running, _)| running == name));
+        let frames = fluids.into_iter()
+            .map(|(name, mut fluid)| {
+                let simulation = simulations.entry(name.clone())
+                    .or_insert_with(|| FluidSimulation::new(fluid.resolution, fluid.resolution));
+                let dye = fluid.advance(simulation, step.seconds, |stream| step.levels.get(stream).copied());
+                FluidFrame { name, layer: fluid.layer, width: simulation.width, height: simulation.height, dye }
+            })
+            .collect();
+        let _ = step.reply.send(frames);
+    }
+}
+
+/// Stable-fluids solver (Stam 1999) on a fixed grid, upsampled when rendered as a layer
+#[derive(Debug, Clone)]
+pub struct FluidSimulation {
+    pub width: usize,
+    pub height: usize,
+    pub viscosity: f32,
+    pub diffusion: f32,
+    pub dissipation: f32, // dye fade per second, 0.0 keeps dye forever
+    pub solver_iterations: usize,
+    velocity_x: Vec<f32>,
+    velocity_y: Vec<f32>,
+    dye: [Vec<f32>; 3],
+    scratch: Vec<f32>,
+    pressure: Vec<f32>,
+    divergence: Vec<f32>,
+}
+
+/// Script-controlled injection point, usually driven by an audio or beat stream
+#[derive(Debug, Clone)]
+pub struct FluidEmitter {
+    pub x: f32, // normalized 0.0-1.0
+    pub y: f32,
+    pub angle: f32, // degrees
+    pub strength: f32,
+    pub radius: f32,
+    pub color: Color,
+}
+
+impl FluidSimulation {
+    pub fn new(width: usize, height: usize) -> Self {
+        let width = width.max(8);
+        let height = height.max(8);
+        let cells = width * height;
+
+        Self {
+            width,
+            height,
+            viscosity: 0.0001,
+            diffusion: 0.0,
+            dissipation: 0.2,
+            solver_iterations: 20,
+            velocity_x: vec![0.0; cells],
+            velocity_y: vec![0.0; cells],
+            dye: [vec![0.0; cells], vec![0.0; cells], vec![0.0; cells]],
+            scratch: vec![0.0; cells],
+            pressure: vec![0.0; cells],
+            divergence: vec![0.0; cells],
+        }
+    }
+
+    pub fn with_params(mut self, viscosity: f32, diffusion: f32, dissipation: f32) -> Self {
+        self.viscosity = viscosity.max(0.0);
+        self.diffusion = diffusion.max(0.0);
+        self.dissipation = dissipation.max(0.0);
+        self
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    /// Push the fluid at a normalized position with a Gaussian splat
+    pub fn add_force(&mut self, x: f32, y: f32, force_x: f32, force_y: f32, radius: f32) {
+        let (cx, cy, r) = self.to_grid(x, y, radius);
+        for (idx, weight) in self.splat_weights(cx, cy, r) {
+            self.velocity_x[idx] += force_x * weight;
+            self.velocity_y[idx] += force_y * weight;
+        }
+    }
+
+    pub fn add_dye(&mut self, x: f32, y: f32, color: Color, amount: f32, radius: f32) {
+        let (cx, cy, r) = self.to_grid(x, y, radius);
+        for (idx, weight) in self.splat_weights(cx, cy, r) {
+            self.dye[0][idx] += color.r * amount * weight;
+            self.dye[1][idx] += color.g * amount * weight;
+            self.dye[2][idx] += color.b * amount * weight;
+        }
+    }
+
+    /// Inject force and dye scaled by a control level (e.g. an FFT band or beat envelope)
+    pub fn apply_emitter(&mut self, emitter: &FluidEmitter, level: f32) {
+        if level <= 0.0 {
+            return;
+        }
+
+        let (sin, cos) = emitter.angle.to_radians().sin_cos();
+        let strength = emitter.strength * level;
+        self.add_force(emitter.x, emitter.y, cos * strength, sin * strength, emitter.radius);
+        self.add_dye(emitter.x, emitter.y, emitter.color, level, emitter.radius);
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        // Velocity: diffuse, project, advect, project
+        if self.viscosity > 0.0 {
+            let a = dt * self.viscosity * (self.width * self.height) as f32;
+            self.diffuse_field(Field::VelocityX, a);
+            self.diffuse_field(Field::VelocityY, a);
+        }
+        self.project();
+
+        let vx = self.velocity_x.clone();
+        let vy = self.velocity_y.clone();
+        self.advect_field(Field::VelocityX, &vx, &vy, dt);
+        self.advect_field(Field::VelocityY, &vx, &vy, dt);
+        self.project();
+
+        // Dye: diffuse, advect through the new velocity, fade
+        for channel in 0..3 {
+            if self.diffusion > 0.0 {
+                let a = dt * self.diffusion * (self.width * self.height) as f32;
+                self.diffuse_field(Field::Dye(channel), a);
+            }
+            let vx = std::mem::take(&mut self.velocity_x);
+            let vy = std::mem::take(&mut self.velocity_y);
+            self.advect_field(Field::Dye(channel), &vx, &vy, dt);
+            self.velocity_x = vx;
+            self.velocity_y = vy;
+        }
+
+        if self.dissipation > 0.0 {
+            let fade = (-self.dissipation * dt).exp();
+            for channel in &mut self.dye {
+                for value in channel.iter_mut() {
+                    *value *= fade;
+                }
+            }
+        }
+    }
+
+    /// Bilinearly sampled velocity at a normalized position, in normalized units per second
+    pub fn velocity_at(&self, x: f32, y: f32) -> (f32, f32) {
+        let gx = x * (self.width - 1) as f32;
+        let gy = y * (self.height - 1) as f32;
+        (
+            self.sample(&self.velocity_x, gx, gy) / self.width as f32,
+            self.sample(&self.velocity_y, gx, gy) / self.height as f32,
+        )
+    }
+
+    /// Move particles (positions in pixels of a `screen_width` x `screen_height` frame) with the flow
+    pub fn advect_particles(&self, particles: &mut [Particle], screen_width: f32, screen_height: f32, dt: f32) {
+        for particle in particles {
+            let nx = (particle.position.0 / screen_width).clamp(0.0, 1.0);
+            let ny = (particle.position.1 / screen_height).clamp(0.0, 1.0);
+            let (vx, vy) = self.velocity_at(nx, ny);
+            particle.position.0 += vx * screen_width * dt;
+            particle.position.1 += vy * screen_height * dt;
+        }
+    }
+
+    /// Render the dye field as a layer of the given size
+    pub fn render(&self, width: u32, height: u32) -> Vec<Color> {
+        let mut result = Vec::with_capacity((width * height) as usize);
+        let sx = (self.width - 1) as f32 / width.max(1) as f32;
+        let sy = (self.height - 1) as f32 / height.max(1) as f32;
+
+        for y in 0..height {
+            for x in 0..width {
+                let gx = x as f32 * sx;
+                let gy = y as f32 * sy;
+                let r = self.sample(&self.dye[0], gx, gy).clamp(0.0, 1.0);
+                let g = self.sample(&self.dye[1], gx, gy).clamp(0.0, 1.0);
+                let b = self.sample(&self.dye[2], gx, gy).clamp(0.0, 1.0);
+                result.push(Color::new(r, g, b, r.max(g).max(b)));
+            }
+        }
+
+        result
+    }
+
+    pub fn reset(&mut self) {
+        for field in [&mut self.velocity_x, &mut self.velocity_y, &mut self.pressure, &mut self.divergence] {
+            field.iter_mut().for_each(|v| *v = 0.0);
+        }
+        for channel in &mut self.dye {
+            channel.iter_mut().for_each(|v| *v = 0.0);
+        }
+    }
+
+    fn to_grid(&self, x: f32, y: f32, radius: f32) -> (f32, f32, f32) {
+        (
+            x.clamp(0.0, 1.0) * (self.width - 1) as f32,
+            y.clamp(0.0, 1.0) * (self.height - 1) as f32,
+            (radius * self.width.min(self.height) as f32).max(1.0),
+        )
+    }
+
+    fn splat_weights(&self, cx: f32, cy: f32, radius: f32) -> Vec<(usize, f32)> {
+        let reach = (radius * 3.0).ceil() as i32;
+        let mut weights = Vec::new();
+
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                let x = cx as i32 + dx;
+                let y = cy as i32 + dy;
+                if x < 1 || y < 1 || x >= self.width as i32 - 1 || y >= self.height as i32 - 1 {
+                    continue;
+                }
+                let distance_sq = (x as f32 - cx).powi(2) + (y as f32 - cy).powi(2);
+                let weight = (-distance_sq / (radius * radius)).exp();
+                if weight > 0.001 {
+                    weights.push((self.index(x as usize, y as usize), weight));
+                }
+            }
+        }
+
+        weights
+    }
+
+    fn sample(&self, field: &[f32], x: f32, y: f32) -> f32 {
+        let x = x.clamp(0.0, (self.width - 1) as f32);
+        let y = y.clamp(0.0, (self.height - 1) as f32);
+        let x0 = x.floor() as usize;
+        let y0 = y.floor() as usize;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let fx = x - x0 as f32;
+        let fy = y - y0 as f32;
+
+        let top = field[self.index(x0, y0)] * (1.0 - fx) + field[self.index(x1, y0)] * fx;
+        let bottom = field[self.index(x0, y1)] * (1.0 - fx) + field[self.index(x1, y1)] * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    fn field_mut(&mut self, field: Field) -> &mut Vec<f32> {
+        match field {
+            Field::VelocityX => &mut self.velocity_x,
+            Field::VelocityY => &mut self.velocity_y,
+            Field::Dye(channel) => &mut self.dye[channel],
+        }
+    }
+
+    fn diffuse_field(&mut self, field: Field, a: f32) {
+        let (width, height, iterations) = (self.width, self.height, self.solver_iterations);
+        let initial = self.field_mut(field).clone();
+        let target = self.field_mut(field);
+
+        for _ in 0..iterations {
+            for y in 1..height - 1 {
+                for x in 1..width - 1 {
+                    let i = y * width + x;
+                    let neighbours = target[i - 1] + target[i + 1] + target[i - width] + target[i + width];
+                    target[i] = (initial[i] + a * neighbours) / (1.0 + 4.0 * a);
+                }
+            }
+        }
+
+        self.set_boundary(field);
+    }
+
+    fn advect_field(&mut self, field: Field, vx: &[f32], vy: &[f32], dt: f32) {
+        let source = self.field_mut(field).clone();
+        let mut advected = std::mem::take(&mut self.scratch);
+        advected.clear();
+        advected.resize(source.len(), 0.0);
+
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let i = self.index(x, y);
+                // Trace back along the velocity (semi-Lagrangian)
+                let back_x = x as f32 - dt * vx[i];
+                let back_y = y as f32 - dt * vy[i];
+                advected[i] = self.sample(&source, back_x, back_y);
+            }
+        }
+
+        let target = self.field_mut(field);
+        std::mem::swap(target, &mut advected);
+        self.scratch = advected;
+        self.set_boundary(field);
+    }
+
+    fn project(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let h = 1.0 / width.min(height) as f32;
+
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let i = y * width + x;
+                self.divergence[i] = -0.5 * h * (
+                    self.velocity_x[i + 1] - self.velocity_x[i - 1] +
+                    self.velocity_y[i + width] - self.velocity_y[i - width]
+                );
+                self.pressure[i] = 0.0;
+            }
+        }
+
+        for _ in 0..self.solver_iterations {
+            for y in 1..height - 1 {
+                for x in 1..width - 1 {
+                    let i = y * width + x;
+                    self.pressure[i] = (self.divergence[i] +
+                        self.pressure[i - 1] + self.pressure[i + 1] +
+                        self.pressure[i - width] + self.pressure[i + width]) / 4.0;
+                }
+            }
+        }
+
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let i = y * width + x;
+                self.velocity_x[i] -= 0.5 * (self.pressure[i + 1] - self.pressure[i - 1]) / h;
+                self.velocity_y[i] -= 0.5 * (self.pressure[i + width] - self.pressure[i - width]) / h;
+            }
+        }
+
+        self.set_boundary(Field::VelocityX);
+        self.set_boundary(Field::VelocityY);
+    }
+
+    /// Solid walls: velocity reflects at the edges, dye is copied from the interior
+    fn set_boundary(&mut self, field: Field) {
+        let (width, height) = (self.width, self.height);
+        let (flip_x, flip_y) = match field {
+            Field::VelocityX => (-1.0, 1.0),
+            Field::VelocityY => (1.0, -1.0),
+            Field::Dye(_) => (1.0, 1.0),
+        };
+        let values = self.field_mut(field);
+
+        for y in 1..height - 1 {
+            values[y * width] = flip_x * values[y * width + 1];
+            values[y * width + width - 1] = flip_x * values[y * width + width - 2];
+        }
+        for x in 1..width - 1 {
+            values[x] = flip_y * values[width + x];
+            values[(height - 1) * width + x] = flip_y * values[(height - 2) * width + x];
+        }
+    }
+}
+
+/// A fluid composited as a graphics layer: its grid and settings, with the emitters and pushes
+/// that feed it. The simulation itself lives on the solver thread
+#[derive(Debug, Clone)]
+pub struct FluidLayer {
+    pub resolution: usize,
+    pub viscosity: f32,
+    pub dissipation: f32,
+    pub layer: i64,
+    /// Each emitter with the stream, if any, whose newest sample scales it
+    pub emitters: Vec<(FluidEmitter, Option<String>)>,
+    /// One-off pushes for the next step, as x, y, force_x, force_y and radius
+    pub forces: Vec<[f32; 5]>,
+}
+
+impl FluidLayer {
+    pub fn new(resolution: usize, layer: i64) -> Self {
+        Self {
+            resolution: resolution.min(MAX_FLUID_RESOLUTION),
+            viscosity: 0.0001,
+            dissipation: 0.2,
+            layer,
+            emitters: Vec::new(),
+            forces: Vec::new(),
+        }
+    }
+
+    /// Add an emitter, or replace the one already at the same position so setup can rerun
+    pub fn set_emitter(&mut self, emitter: FluidEmitter, source: Option<String>) {
+        let same_place = |(existing, _): &(FluidEmitter, Option<String>)| {
+            (existing.x - emitter.x).abs() < 0.001 && (existing.y - emitter.y).abs() < 0.001
+        };
+        match self.emitters.iter_mut().find(|entry| same_place(entry)) {
+            Some(entry) => *entry = (emitter, source),
+            None => self.emitters.push((emitter, source)),
+        }
+    }
+
+    /// Bring `simulation` in line with these settings, apply the waiting pushes, inject every
+    /// emitter for `dt` seconds, step it and render the dye at grid resolution. A new resolution
+    /// starts the simulation over; an emitter with a source stream that has no samples yet stays quiet
+    pub fn advance(&mut self, simulation: &mut FluidSimulation, dt: f32, level: impl Fn(&str) -> Option<f32>) -> Vec<Color> {
+        if simulation.width != self.resolution {
+            *simulation = FluidSimulation::new(self.resolution, self.resolution);
+        }
+        simulation.viscosity = self.viscosity;
+        simulation.dissipation = self.dissipation;
+        for [x, y, force_x, force_y, radius] in self.forces.drain(..) {
+            simulation.add_force(x, y, force_x, force_y, radius);
+        }
+        for (emitter, source) in &self.emitters {
+            let level = match source {
+                Some(stream) => level(stream).unwrap_or(0.0),
+                None => 1.0,
+            };
+            simulation.apply_emitter(emitter, level * dt);
+        }
+        simulation.step(dt);
+        simulation.render(simulation.width as u32, simulation.height as u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    VelocityX,
+    VelocityY,
+    Dye(usize),
+}
+
+impl FluidEmitter {
+    pub fn new(x: f32, y: f32, angle: f32, color: Color) -> Self {
+        Self {
+            x,
+            y,
+            angle,
+            strength: 50.0,
+            radius: 0.03,
+            color,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Rolling average weight for new samples (about a second at 60fps)
+const SMOOTHING: f64 = 0.05;
+
+// Latest per-pass timings, shared with the script-facing Graphics.profile() and the debug HUD
+static PROFILE_SNAPSHOT: OnceLock<Mutex<HashMap<String, PassTiming>>> = OnceLock::new();
+
+fn snapshot() -> &'static Mutex<HashMap<String, PassTiming>> {
+    PROFILE_SNAPSHOT.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassKind {
+    Gpu,
+    Cpu,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+    pub name: String,
+    pub kind: PassKind,
+    pub last_ms: f64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+}
+
+fn record_timing(name: &str, kind: PassKind, ms: f64) {
+    if let Ok(mut timings) = snapshot().lock() {
+        let timing = timings.entry(name.to_string()).or_insert_with(|| PassTiming {
+            name: name.to_string(),
+            kind,
+            last_ms: ms,
+            average_ms: ms,
+            max_ms: ms,
+        });
+        timing.last_ms = ms;
+        timing.average_ms += (ms - timing.average_ms) * SMOOTHING;
+        timing.max_ms = timing.max_ms.max(ms);
+    }
+}
+
+/// Record CPU-side work (script evaluation, audio analysis) next to the GPU passes
+pub fn record_cpu_pass(name: &str, duration: Duration) {
+    record_timing(name, PassKind::Cpu, duration.as_secs_f64() * 1000.0);
+}
+
+/// All known passes, most expensive first
+pub fn latest_timings() -> Vec<PassTiming> {
+    let mut timings: Vec<PassTiming> = snapshot()
+        .lock()
+        .map(|timings| timings.values().cloned().collect())
+        .unwrap_or_default();
+    timings.sort_by(|a, b| b.average_ms.partial_cmp(&a.average_ms).unwrap_or(std::cmp::Ordering::Equal));
+    timings
+}
+
+/// Compact lines for the on-screen debug HUD
+pub fn hud_lines() -> Vec<String> {
+    latest_timings()
+        .iter()
+        .map(|t| {
+            let tag = match t.kind {
+                PassKind::Gpu => "gpu",
+                PassKind::Cpu => "cpu",
+            };
+            format!("{:<16} {} {:>6.2}ms", t.name, tag, t.average_ms)
+        })
+        .collect()
+}
+
+/// Multi-line profiler report, e.g. printed by `synthesis run --profile`
+pub fn profile_report() -> String {
+    let timings = latest_timings();
+    let total: f64 = timings.iter().map(|t| t.average_ms).sum();
+    let mut report = String::from("📊 Frame profile (average / last / worst)\n");
+
+    for t in &timings {
+        let share = if total > 0.0 { t.average_ms / total * 100.0 } else { 0.0 };
+        let kind = match t.kind {
+            PassKind::Gpu => "GPU",
+            PassKind::Cpu => "CPU",
+        };
+        report.push_str(&format!(
+            "  {:<20} {} {:>7.2}ms {:>7.2}ms {:>7.2}ms  {:>5.1}%\n",
+            t.name, kind, t.average_ms, t.last_ms, t.max_ms, share
+        ));
+    }
+
+    if let Some(worst) = timings.first() {
+        report.push_str(&format!("  Heaviest: {} ({:.2}ms)\n", worst.name, worst.average_ms));
+    }
+    report
+}
+
+/// Timestamp queries around each render pass. Falls back to doing nothing on adapters
+/// without `TIMESTAMP_QUERY`, so callers never need to branch.
+pub struct GpuProfiler {
+    query_set: Option<wgpu::QuerySet>,
+    resolve_buffer: Option<wgpu::Buffer>,
+    readback_buffer: Option<wgpu::Buffer>,
+    max_passes: u32,
+    pass_names: Vec<String>,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+}
+
+impl GpuProfiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, max_passes: u32) -> Self {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return Self {
+                query_set: None,
+                resolve_buffer: None,
+                readback_buffer: None,
+                max_passes,
+                pass_names: Vec::new(),
+                period: 1.0,
+            };
+        }
+
+        let query_count = max_passes * 2;
+        let size = query_count as u64 * std::mem::size_of::<u64>() as u64;
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Pass Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: query_count,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set: Some(query_set),
+            resolve_buffer: Some(resolve_buffer),
+            readback_buffer: Some(readback_buffer),
+            max_passes,
+            pass_names: Vec::new(),
+            period: queue.get_timestamp_period(),
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.query_set.is_some()
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.pass_names.clear();
+    }
+
+    /// Timestamp writes for a named pass; pass the result to `RenderPassDescriptor::timestamp_writes`
+    pub fn pass_timestamps(&mut self, name: &str) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let query_set = self.query_set.as_ref()?;
+        if self.pass_names.len() as u32 >= self.max_passes {
+            return None;
+        }
+
+        let index = self.pass_names.len() as u32 * 2;
+        self.pass_names.push(name.to_string());
+
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set,
+            beginning_of_pass_write_index: Some(index),
+            end_of_pass_write_index: Some(index + 1),
+        })
+    }
+
+    /// Copy this frame's timestamps somewhere mappable; call before submitting the encoder
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        let (Some(query_set), Some(resolve), Some(readback)) =
+            (&self.query_set, &self.resolve_buffer, &self.readback_buffer)
+        else {
+            return;
+        };
+        if self.pass_names.is_empty() {
+            return;
+        }
+
+        let count = self.pass_names.len() as u32 * 2;
+        encoder.resolve_query_set(query_set, 0..count, resolve, 0);
+        encoder.copy_buffer_to_buffer(resolve, 0, readback, 0, count as u64 * 8);
+    }
+
+    /// Read back the resolved timestamps after submit and publish per-pass timings
+    pub fn collect(&mut self, device: &wgpu::Device) {
+        let readback = match &self.readback_buffer {
+            Some(buffer) if !self.pass_names.is_empty() => buffer,
+            _ => return,
+        };
+
+        let byte_count = self.pass_names.len() as u64 * 16;
+        let slice = readback.slice(0..byte_count);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result.is_ok());
+        });
+        device.poll(wgpu::Maintain::Wait);
+
+        if !receiver.recv().unwrap_or(false) {
+            return;
+        }
+
+        {
+            let data = slice.get_mapped_range();
+            for (i, name) in self.pass_names.iter().enumerate() {
+                let read = |offset: usize| {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(&data[offset..offset + 8]);
+                    u64::from_le_bytes(bytes)
+                };
+                let start = read(i * 16);
+                let end = read(i * 16 + 8);
+                let ms = end.saturating_sub(start) as f64 * self.period as f64 / 1_000_000.0;
+                record_timing(name, PassKind::Gpu, ms);
+            }
+        }
+        readback.unmap();
+    }
+}
@@ -0,0 +1,505 @@
+/// Hydra-style chains: `Graphics.osc(10).rotate(0.1).kaleid(4).out(layer: 0)`
+///
+/// A chain is a source followed by transforms. Compiling it nests the GLSL calls the way Hydra
+/// does: coordinate transforms wrap the texture coordinate, so the last one written runs first,
+/// while color and blend transforms wrap the color. Arguments become uniforms (`p0`, `p1`, ...),
+/// so a chain rebuilt every frame with new numbers keeps the same shader.
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+pub const HYDRA_LAYERS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HydraKind {
+    /// Produces a color from a coordinate
+    Source,
+    /// Moves the coordinate before the chain so far samples it
+    Coord,
+    /// Changes the color coming out of the chain so far
+    Color,
+    /// Mixes the chain with another chain
+    Combine,
+    /// Moves the coordinate by another chain's color
+    CombineCoord,
+}
+
+#[derive(Debug)]
+pub struct HydraFunction {
+    pub name: &'static str,
+    pub kind: HydraKind,
+    pub params: &'static [(&'static str, f64)],
+    body: &'static str,
+}
+
+const HYDRA_FUNCTIONS: &[HydraFunction] = &[
+    HydraFunction {
+        name: "osc",
+        kind: HydraKind::Source,
+        params: &[("frequency", 60.0), ("sync", 0.1), ("offset", 0.0)],
+        body: "float r = sin((_st.x - offset / frequency + time * sync) * frequency) * 0.5 + 0.5;
+    float g = sin((_st.x + time * sync) * frequency) * 0.5 + 0.5;
+    float b = sin((_st.x + offset / frequency + time * sync) * frequency) * 0.5 + 0.5;
+    return vec4(r, g, b, 1.0);",
+    },
+    HydraFunction {
+        name: "noise",
+        kind: HydraKind::Source,
+        params: &[("scale", 10.0), ("offset", 0.1)],
+        body: "return vec4(vec3(_noise(_st * scale + offset * time)), 1.0);",
+    },
+    HydraFunction {
+        name: "voronoi",
+        kind: HydraKind::Source,
+        params: &[("scale", 5.0), ("speed", 0.3), ("blending", 0.3)],
+        body: "vec2 st = _st * scale;
+    vec2 cell = floor(st);
+    float nearest = 10.0;
+    vec2 site = vec2(0.0);
+    for (int j = -1; j <= 1; j++) {
+        for (int i = -1; i <= 1; i++) {
+            vec2 neighbor = vec2(float(i), float(j));
+            vec2 point = _hash2(cell + neighbor);
+            point = 0.5 + 0.5 * sin(time * speed + 6.2831 * point);
+            float dist = length(neighbor + point - fract(st));
+            if (dist < nearest) {
+                nearest = dist;
+                site = point;
+            }
+        }
+    }
+    return vec4(vec3(dot(site, vec2(0.3, 0.6)) * (1.0 - blending * nearest)), 1.0);",
+    },
+    HydraFunction {
+        name: "shape",
+        kind: HydraKind::Source,
+        params: &[("sides", 3.0), ("radius", 0.3), ("smoothing", 0.01)],
+        body: "vec2 st = _st * 2.0 - 1.0;
+    float a = atan(st.x, st.y) + 3.1416;
+    float r = 6.2832 / sides;
+    float d = cos(floor(0.5 + a / r) * r - a) * length(st);
+    return vec4(vec3(1.0 - smoothstep(radius, radius + smoothing, d)), 1.0);",
+    },
+    HydraFunction {
+        name: "gradient",
+        kind: HydraKind::Source,
+        params: &[("speed", 0.0)],
+        body: "return vec4(_st, sin(time * speed), 1.0);",
+    },
+    HydraFunction {
+        name: "solid",
+        kind: HydraKind::Source,
+        params: &[("r", 0.0), ("g", 0.0), ("b", 0.0), ("a", 1.0)],
+        body: "return vec4(r, g, b, a);",
+    },
+    HydraFunction {
+        name: "rotate",
+        kind: HydraKind::Coord,
+        params: &[("angle", 10.0), ("speed", 0.0)],
+        body: "float ang = angle + speed * time;
+    return mat2(cos(ang), -sin(ang), sin(ang), cos(ang)) * (_st - vec2(0.5)) + 0.5;",
+    },
+    HydraFunction {
+        name: "scale",
+        kind: HydraKind::Coord,
+        params: &[("amount", 1.5), ("xMult", 1.0), ("yMult", 1.0)],
+        body: "return (_st - vec2(0.5)) / vec2(amount * xMult, amount * yMult) + 0.5;",
+    },
+    HydraFunction {
+        name: "pixelate",
+        kind: HydraKind::Coord,
+        params: &[("pixelX", 20.0), ("pixelY", 20.0)],
+        body: "vec2 xy = vec2(pixelX, pixelY);
+    return (floor(_st * xy) + 0.5) / xy;",
+    },
+    HydraFunction {
+        name: "repeat",
+        kind: HydraKind::Coord,
+        params: &[("repeatX", 3.0), ("repeatY", 3.0), ("offsetX", 0.0), ("offsetY", 0.0)],
+        body: "vec2 st = _st * vec2(repeatX, repeatY);
+    st.x += step(1.0, mod(st.y, 2.0)) * offsetX;
+    st.y += step(1.0, mod(st.x, 2.0)) * offsetY;
+    return fract(st);",
+    },
+    HydraFunction {
+        name: "kaleid",
+        kind: HydraKind::Coord,
+        params: &[("nSides", 4.0)],
+        body: "vec2 st = _st - 0.5;
+    float a = mod(atan(st.y, st.x), 6.2832 / nSides);
+    a = abs(a - 6.2832 / nSides / 2.0);
+    return length(st) * vec2(cos(a), sin(a));",
+    },
+    HydraFunction {
+        name: "scroll",
+        kind: HydraKind::Coord,
+        params: &[("scrollX", 0.5), ("scrollY", 0.5), ("speedX", 0.0), ("speedY", 0.0)],
+        body: "return fract(_st + vec2(scrollX + time * speedX, scrollY + time * speedY));",
+    },
+    HydraFunction {
+        name: "color",
+        kind: HydraKind::Color,
+        params: &[("r", 1.0), ("g", 1.0), ("b", 1.0), ("a", 1.0)],
+        body: "return _c0 * vec4(r, g, b, a);",
+    },
+    HydraFunction {
+        name: "invert",
+        kind: HydraKind::Color,
+        params: &[("amount", 1.0)],
+        body: "return vec4(mix(_c0.rgb, 1.0 - _c0.rgb, amount), _c0.a);",
+    },
+    HydraFunction {
+        name: "brightness",
+        kind: HydraKind::Color,
+        params: &[("amount", 0.4)],
+        body: "return vec4(_c0.rgb + vec3(amount), _c0.a);",
+    },
+    HydraFunction {
+        name: "contrast",
+        kind: HydraKind::Color,
+        params: &[("amount", 1.6)],
+        body: "return vec4((_c0.rgb - 0.5) * amount + 0.5, _c0.a);",
+    },
+    HydraFunction {
+        name: "saturate",
+        kind: HydraKind::Color,
+        params: &[("amount", 2.0)],
+        body: "return vec4(mix(vec3(_luma(_c0)), _c0.rgb, amount), _c0.a);",
+    },
+    HydraFunction {
+        name: "posterize",
+        kind: HydraKind::Color,
+        params: &[("bins", 3.0), ("gamma", 0.6)],
+        body: "vec4 c = floor(pow(_c0, vec4(gamma)) * bins) / bins;
+    return vec4(pow(c.rgb, vec3(1.0 / gamma)), _c0.a);",
+    },
+    HydraFunction {
+        name: "thresh",
+        kind: HydraKind::Color,
+        params: &[("threshold", 0.5), ("tolerance", 0.04)],
+        body: "return vec4(vec3(smoothstep(threshold - tolerance, threshold + tolerance, _luma(_c0))), _c0.a);",
+    },
+    HydraFunction {
+        name: "add",
+        kind: HydraKind::Combine,
+        params: &[("amount", 1.0)],
+        body: "return _c0 + _c1 * amount;",
+    },
+    HydraFunction {
+        name: "mult",
+        kind: HydraKind::Combine,
+        params: &[("amount", 1.0)],
+        body: "return mix(_c0, _c0 * _c1, amount);",
+    },
+    HydraFunction {
+        name: "diff",
+        kind: HydraKind::Combine,
+        params: &[],
+        body: "return vec4(abs(_c0.rgb - _c1.rgb), max(_c0.a, _c1.a));",
+    },
+    HydraFunction {
+        name: "blend",
+        kind: HydraKind::Combine,
+        params: &[("amount", 0.5)],
+        body: "return mix(_c0, _c1, amount);",
+    },
+    HydraFunction {
+        name: "mask",
+        kind: HydraKind::Combine,
+        params: &[],
+        body: "float a = _luma(_c1);
+    return vec4(_c0.rgb * a, _c0.a * a);",
+    },
+    HydraFunction {
+        name: "modulate",
+        kind: HydraKind::CombineCoord,
+        params: &[("amount", 0.1)],
+        body: "return _st + _c0.xy * amount;",
+    },
+    HydraFunction {
+        name: "modulateRotate",
+        kind: HydraKind::CombineCoord,
+        params: &[("multiple", 1.0), ("offset", 0.0)],
+        body: "float ang = offset + _c0.x * multiple;
+    return mat2(cos(ang), -sin(ang), sin(ang), cos(ang)) * (_st - vec2(0.5)) + 0.5;",
+    },
+    HydraFunction {
+        name: "modulateScale",
+        kind: HydraKind::CombineCoord,
+        params: &[("multiple", 1.0), ("offset", 1.0)],
+        body: "return (_st - vec2(0.5)) / vec2(offset + multiple * _c0.r, offset + multiple * _c0.g) + 0.5;",
+    },
+];
+
+const HYDRA_PRELUDE: &str = "float _luma(vec4 c) {
+    return dot(c.rgb, vec3(0.2125, 0.7154, 0.0721));
+}
+
+vec2 _hash2(vec2 p) {
+    return fract(sin(vec2(dot(p, vec2(127.1, 311.7)), dot(p, vec2(269.5, 183.3)))) * 43758.5453);
+}
+
+float _noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    float a = _hash2(i).x;
+    float b = _hash2(i + vec2(1.0, 0.0)).x;
+    float c = _hash2(i + vec2(0.0, 1.0)).x;
+    float d = _hash2(i + vec2(1.0, 1.0)).x;
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+";
+
+pub fn hydra_function(name: &str) -> Option<&'static HydraFunction> {
+    HYDRA_FUNCTIONS.iter().find(|function| function.name == name)
+}
+
+pub fn hydra_sources() -> impl Iterator<Item = &'static str> {
+    HYDRA_FUNCTIONS.iter().filter(|f| f.kind == HydraKind::Source).map(|f| f.name)
+}
+
+impl HydraFunction {
+    fn signature(&self) -> String {
+        let inputs = match self.kind {
+            HydraKind::Source | HydraKind::Coord => "vec2 _st",
+            HydraKind::Color => "vec4 _c0",
+            HydraKind::Combine => "vec4 _c0, vec4 _c1",
+            HydraKind::CombineCoord => "vec2 _st, vec4 _c0",
+        };
+        let output = match self.kind {
+            HydraKind::Coord | HydraKind::CombineCoord => "vec2",
+            _ => "vec4",
+        };
+        let params: String = self.params.iter().map(|(name, _)| format!(", float {}", name)).collect();
+        format!("{} {}({}{})", output, self.name, inputs, params)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HydraOp {
+    pub name: String,
+    pub args: Vec<f64>,
+    /// The other chain for blends and modulations
+    pub input: Option<HydraChain>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HydraChain {
+    /// A source first, then transforms in the order they were written
+    pub ops: Vec<HydraOp>,
+}
+
+fn hydra_error(message: String) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::UnknownFunction, message)
+        .with_docs("https://synthesis-lang.org/docs/hydra")
+}
+
+/// Positional numbers fill parameters in order, named ones by name, the rest use defaults
+fn bind_args(function: &HydraFunction, positional: &[f64], named: &HashMap<String, f64>) -> crate::Result<Vec<f64>> {
+    if let Some(unknown) = named.keys().find(|key| !function.params.iter().any(|(name, _)| name == key)) {
+        let names: Vec<&str> = function.params.iter().map(|(name, _)| *name).collect();
+        return Err(hydra_error(format!("🎨 {}() has no parameter called {}", function.name, unknown))
+            .with_suggestion(format!("Its parameters are: {}", names.join(", "))));
+    }
+    Ok(function.params.iter()
+        .enumerate()
+        .map(|(i, (name, default))| named.get(*name).or(positional.get(i)).copied().unwrap_or(*default))
+        .collect())
+}
+
+impl HydraChain {
+    pub fn source(name: &str, positional: &[f64], named: &HashMap<String, f64>) -> crate::Result<Self> {
+        let function = match hydra_function(name) {
+            Some(function) if function.kind == HydraKind::Source => function,
+            _ => return Err(hydra_error(format!("🎨 {} isn't a Hydra source", name))
+                .with_suggestion(format!("Chains start with a source: {}", hydra_sources().collect::<Vec<_>>().join(", ")))),
+        };
+        Ok(Self { ops: vec![HydraOp { name: name.to_string(), args: bind_args(function, positional, named)?, input: None }] })
+    }
+
+    pub fn then(mut self, name: &str, positional: &[f64], named: &HashMap<String, f64>, input: Option<HydraChain>) -> crate::Result<Self> {
+        let function = match hydra_function(name) {
+            Some(function) if function.kind != HydraKind::Source => function,
+            Some(_) => return Err(hydra_error(format!("🎨 {}() starts a chain, it can't follow one", name))
+                .with_suggestion(format!("To mix two sources, try .blend(Graphics.{}())", name))),
+            None => return Err(hydra_error(format!("🎨 Hydra chains have no {}() step", name))
+                .with_suggestion("Try rotate, scale, kaleid, pixelate, color, invert, add, blend or modulate")),
+        };
+        let needs_input = matches!(function.kind, HydraKind::Combine | HydraKind::CombineCoord);
+        if needs_input && input.is_none() {
+            return Err(hydra_error(format!("🎨 {}() needs another chain to mix with", name))
+                .with_suggestion(format!("Try: .{}(Graphics.noise(3))", name)));
+        }
+        self.ops.push(HydraOp {
+            name: name.to_string(),
+            args: bind_args(function, positional, named)?,
+            input: if needs_input { input } else { None },
+        });
+        Ok(self)
+    }
+
+    /// The chain as a GLSL expression of `uv`, pushing each argument as a uniform
+    fn expression(ops: &[HydraOp], uv: String, uniforms: &mut Vec<f32>) -> String {
+        let Some((op, rest)) = ops.split_last() else {
+            return "vec4(0.0)".to_string();
+        };
+        let Some(function) = hydra_function(&op.name) else {
+            return Self::expression(rest, uv, uniforms);
+        };
+        let mut params = String::new();
+        for value in &op.args {
+            params.push_str(&format!(", p{}", uniforms.len()));
+            uniforms.push(*value as f32);
+        }
+        let input = |uv: &str, uniforms: &mut Vec<f32>| match &op.input {
+            Some(chain) => Self::expression(&chain.ops, uv.to_string(), uniforms),
+            None => "vec4(0.0)".to_string(),
+        };
+        match function.kind {
+            HydraKind::Source => format!("{}({}{})", op.name, uv, params),
+            HydraKind::Coord => Self::expression(rest, format!("{}({}{})", op.name, uv, params), uniforms),
+            HydraKind::Color => {
+                let color = Self::expression(rest, uv, uniforms);
+                format!("{}({}{})", op.name, color, params)
+            }
+            HydraKind::Combine => {
+                let color = Self::expression(rest, uv.clone(), uniforms);
+                let other = input(&uv, uniforms);
+                format!("{}({}, {}{})", op.name, color, other, params)
+            }
+            HydraKind::CombineCoord => {
+                let other = input(&uv, uniforms);
+                Self::expression(rest, format!("{}({}, {}{})", op.name, uv, other, params), uniforms)
+            }
+        }
+    }
+
+    fn collect_functions(&self, used: &mut Vec<&'static HydraFunction>) {
+        for op in &self.ops {
+            if let Some(function) = hydra_function(&op.name) {
+                if !used.iter().any(|f| f.name == function.name) {
+                    used.push(function);
+                }
+            }
+            if let Some(input) = &op.input {
+                input.collect_functions(used);
+            }
+        }
+    }
+
+    /// Argument values in uniform order: `p0` is the first entry
+    pub fn uniforms(&self) -> Vec<f32> {
+        let mut uniforms = Vec::new();
+        Self::expression(&self.ops, "uv".to_string(), &mut uniforms);
+        uniforms
+    }
+
+    /// A complete fragment shader; only changes when the chain's structure does
+    pub fn glsl(&self) -> String {
+        let mut uniforms = Vec::new();
+        let expression = Self::expression(&self.ops, "uv".to_string(), &mut uniforms);
+        let mut used = Vec::new();
+        self.collect_functions(&mut used);
+
+        let mut shader = String::from("#version 450\n\n");
+        shader.push_str("layout(location = 0) in vec2 uv;\n");
+        shader.push_str("layout(location = 0) out vec4 fragColor;\n");
+        shader.push_str("uniform float time;\nuniform vec2 resolution;\n");
+        for i in 0..uniforms.len() {
+            shader.push_str(&format!("uniform float p{};\n", i));
+        }
+        shader.push('\n');
+        shader.push_str(HYDRA_PRELUDE);
+        for function in used {
+            shader.push_str(&format!("\n{} {{\n    {}\n}}\n", function.signature(), function.body));
+        }
+        shader.push_str(&format!("\nvoid main() {{\n    fragColor = {};\n}}\n", expression));
+        shader
+    }
+
+    pub fn to_value(&self) -> Value {
+        let ops = self.ops.iter()
+            .map(|op| {
+                let mut fields = HashMap::new();
+                fields.insert("name".to_string(), Value::String(op.name.clone()));
+                fields.insert("args".to_string(), Value::Array(op.args.iter().map(|a| Value::Float(*a)).collect()));
+                if let Some(input) = &op.input {
+                    fields.insert("input".to_string(), input.to_value());
+                }
+                Value::Object(fields)
+            })
+            .collect();
+        let mut result = HashMap::new();
+        result.insert("type".to_string(), Value::String("hydra".to_string()));
+        result.insert("ops".to_string(), Value::Array(ops));
+        Value::Object(result)
+    }
+
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let Value::Object(fields) = value else {
+            return None;
+        };
+        if fields.get("type") != Some(&Value::String("hydra".to_string())) {
+            return None;
+        }
+        let Some(Value::Array(items)) = fields.get("ops") else {
+            return None;
+        };
+        let mut ops = Vec::with_capacity(items.len());
+        for item in items {
+            let Value::Object(op) = item else {
+                return None;
+            };
+            let Some(Value::String(name)) = op.get("name") else {
+                return None;
+            };
+            let args = match op.get("args") {
+                Some(Value::Array(args)) => args.iter().filter_map(|a| a.as_number()).collect(),
+                _ => Vec::new(),
+            };
+            let input = match op.get("input") {
+                Some(input) => Some(Self::from_value(input)?),
+                None => None,
+            };
+            ops.push(HydraOp { name: name.clone(), args, input });
+        }
+        Some(Self { ops })
+    }
+}
+
+/// Chains sent to the output layers with `.out()`
+#[derive(Debug, Default)]
+pub struct HydraOutputs {
+    layers: [Option<HydraChain>; HYDRA_LAYERS],
+}
+
+impl HydraOutputs {
+    pub fn set(&mut self, layer: usize, chain: HydraChain) -> crate::Result<()> {
+        match self.layers.get_mut(layer) {
+            Some(slot) => {
+                *slot = Some(chain);
+                Ok(())
+            }
+            None => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🎨 There's no output layer {}", layer)
+            )
+            .with_suggestion(format!("Use a layer from 0 to {}", HYDRA_LAYERS - 1))),
+        }
+    }
+
+    pub fn get(&self, layer: usize) -> Option<&HydraChain> {
+        self.layers.get(layer).and_then(|slot| slot.as_ref())
+    }
+
+    pub fn clear(&mut self) {
+        self.layers = Default::default();
+    }
+}
+
+static HYDRA_OUTPUTS: OnceLock<Mutex<HydraOutputs>> = OnceLock::new();
+
+pub fn hydra_outputs() -> &'static Mutex<HydraOutputs> {
+    HYDRA_OUTPUTS.get_or_init(|| Mutex::new(HydraOutputs::default()))
+}
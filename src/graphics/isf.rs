@@ -0,0 +1,312 @@
+/// Interactive Shader Format (ISF) loading: GLSL fragment shaders with a JSON header
+///
+/// ```text
+/// /*{
+///     "DESCRIPTION": "Shifts colour channels apart",
+///     "CATEGORIES": ["Glitch"],
+///     "INPUTS": [
+///         { "NAME": "inputImage", "TYPE": "image" },
+///         { "NAME": "amount", "TYPE": "float", "DEFAULT": 0.01, "MIN": 0.0, "MAX": 0.1 }
+///     ]
+/// }*/
+/// void main() { ... }
+/// ```
+///
+/// Shaders with an `inputImage` are filters over the layer below; the rest are generators.
+/// Declared inputs become uniforms, named arguments and GUI controls.
+use crate::errors::{synthesis_error, ErrorKind, SynthesisError};
+use crate::runtime::{declare_control, RemoteControl, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsfInputKind {
+    Event,
+    Bool,
+    Long,
+    Float,
+    Point2D,
+    Color,
+    Image,
+    Audio,
+    AudioFft,
+}
+
+impl IsfInputKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "event" => Some(IsfInputKind::Event),
+            "bool" => Some(IsfInputKind::Bool),
+            "long" => Some(IsfInputKind::Long),
+            "float" => Some(IsfInputKind::Float),
+            "point2D" => Some(IsfInputKind::Point2D),
+            "color" => Some(IsfInputKind::Color),
+            "image" => Some(IsfInputKind::Image),
+            "audio" => Some(IsfInputKind::Audio),
+            "audioFFT" => Some(IsfInputKind::AudioFft),
+            _ => None,
+        }
+    }
+
+    fn glsl_type(&self) -> &'static str {
+        match self {
+            IsfInputKind::Event | IsfInputKind::Bool => "bool",
+            IsfInputKind::Long => "int",
+            IsfInputKind::Float => "float",
+            IsfInputKind::Point2D => "vec2",
+            IsfInputKind::Color => "vec4",
+            IsfInputKind::Image | IsfInputKind::Audio | IsfInputKind::AudioFft => "sampler2D",
+        }
+    }
+
+    fn is_texture(&self) -> bool {
+        matches!(self, IsfInputKind::Image | IsfInputKind::Audio | IsfInputKind::AudioFft)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsfInput {
+    pub name: String,
+    pub kind: IsfInputKind,
+    pub label: Option<String>,
+    pub default: Value,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// For `long` inputs shown as a menu: the values and their labels
+    pub values: Vec<i64>,
+    pub labels: Vec<String>,
+}
+
+impl IsfInput {
+    pub fn display_name(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Bring a script value into the shape and range the shader expects
+    pub fn coerce(&self, value: &Value) -> Option<Value> {
+        match self.kind {
+            IsfInputKind::Event | IsfInputKind::Bool => Some(Value::Boolean(value.is_truthy())),
+            IsfInputKind::Long => {
+                let n = value.as_number()? as i64;
+                if self.values.is_empty() || self.values.contains(&n) { Some(Value::Integer(self.clamp(n as f64) as i64)) } else { None }
+            }
+            IsfInputKind::Float => value.as_number().map(|n| Value::Float(self.clamp(n))),
+            IsfInputKind::Point2D => match value {
+                Value::Array(items) if items.len() == 2 => {
+                    let xy: Option<Vec<f64>> = items.iter().map(|v| v.as_number()).collect();
+                    xy.map(|xy| Value::Array(xy.into_iter().map(Value::Float).collect()))
+                }
+                _ => None,
+            },
+            IsfInputKind::Color => match value {
+                // Synthesis colours are 0xRRGGBB integers; ISF wants normalized RGBA
+                Value::Integer(rgb) => Some(Value::Array(vec![
+                    Value::Float(((rgb >> 16) & 0xFF) as f64 / 255.0),
+                    Value::Float(((rgb >> 8) & 0xFF) as f64 / 255.0),
+                    Value::Float((rgb & 0xFF) as f64 / 255.0),
+                    Value::Float(1.0 - ((rgb >> 24) & 0xFF) as f64 / 255.0),
+                ])),
+                Value::Color(rgba) => Some(Value::Array(rgba.iter().map(|&c| Value::Float(c as f64 / 255.0)).collect())),
+                Value::Array(items) if items.len() == 3 || items.len() == 4 => {
+                    let mut rgba: Vec<f64> = items.iter().map(|v| v.as_number()).collect::<Option<_>>()?;
+                    rgba.resize(4, 1.0);
+                    Some(Value::Array(rgba.into_iter().map(|c| Value::Float(c.clamp(0.0, 1.0))).collect()))
+                }
+                _ => None,
+            },
+            // Textures are bound by the renderer: a stream, asset or layer passes through as-is
+            IsfInputKind::Image | IsfInputKind::Audio | IsfInputKind::AudioFft => Some(value.clone()),
+        }
+    }
+
+    fn clamp(&self, n: f64) -> f64 {
+        let n = self.min.map_or(n, |min| n.max(min));
+        self.max.map_or(n, |max| n.min(max))
+    }
+
+    fn control(&self, shader: &str) -> Option<RemoteControl> {
+        let label = format!("{} {}", shader, self.display_name());
+        match self.kind {
+            IsfInputKind::Float => Some(RemoteControl::new(label, "slider", self.default.clone())
+                .with_range(self.min.unwrap_or(0.0), self.max.unwrap_or(1.0))),
+            IsfInputKind::Bool => Some(RemoteControl::new(label, "checkbox", self.default.clone())),
+            IsfInputKind::Event => Some(RemoteControl::new(label, "button", Value::Boolean(false))),
+            IsfInputKind::Long if !self.values.is_empty() => {
+                let mut control = RemoteControl::new(label, "dropdown", self.default.clone());
+                control.options = self.labels.clone();
+                Some(control)
+            }
+            IsfInputKind::Long => Some(RemoteControl::new(label, "slider", self.default.clone())
+                .with_range(self.min.unwrap_or(0.0), self.max.unwrap_or(10.0))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsfPass {
+    pub target: Option<String>,
+    pub persistent: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsfShader {
+    pub name: String,
+    pub description: Option<String>,
+    pub credit: Option<String>,
+    pub categories: Vec<String>,
+    pub inputs: Vec<IsfInput>,
+    pub passes: Vec<IsfPass>,
+    /// The fragment shader as written, without the JSON header
+    pub fragment: String,
+}
+
+fn isf_error(name: &str, message: impl Into<String>) -> SynthesisError {
+    synthesis_error(ErrorKind::InvalidExpression, message)
+        .with_suggestion(format!("While loading the ISF shader {}", name))
+        .with_docs("https://synthesis-lang.org/docs/isf-shaders")
+}
+
+impl IsfShader {
+    pub fn parse(source: &str, name: &str) -> crate::Result<Self> {
+        let start = source.find("/*").ok_or_else(|| isf_error(name, "🎨 This shader has no ISF header")
+            .with_suggestion("ISF files start with a /*{ ... }*/ JSON block describing their inputs"))?;
+        let end = source[start..].find("*/").map(|i| start + i)
+            .ok_or_else(|| isf_error(name, "🎨 The ISF header comment never closes"))?;
+
+        let header: serde_json::Value = serde_json::from_str(&source[start + 2..end]).map_err(|error| {
+            isf_error(name, format!("🎨 The ISF header isn't valid JSON: {}", error))
+                .with_suggestion("Look for a trailing comma or a missing quote in the /*{ }*/ block")
+        })?;
+
+        let text = |key: &str| header.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let categories = header.get("CATEGORIES")
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|c| c.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        let mut inputs = Vec::new();
+        for input in header.get("INPUTS").and_then(|v| v.as_array()).into_iter().flatten() {
+            inputs.push(Self::parse_input(input, name)?);
+        }
+
+        let passes = header.get("PASSES")
+            .and_then(|v| v.as_array())
+            .map(|passes| passes.iter().map(|pass| IsfPass {
+                target: pass.get("TARGET").and_then(|v| v.as_str()).map(str::to_string),
+                persistent: pass.get("PERSISTENT").is_some_and(|v| v.as_bool().unwrap_or(false) || v.as_i64() == Some(1)),
+            }).collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            name: name.to_string(),
+            description: text("DESCRIPTION"),
+            credit: text("CREDIT"),
+            categories,
+            inputs,
+            passes,
+            fragment: format!("{}{}", &source[..start], &source[end + 2..]).trim().to_string(),
+        })
+    }
+
+    fn parse_input(input: &serde_json::Value, shader: &str) -> crate::Result<IsfInput> {
+        let name = input.get("NAME").and_then(|v| v.as_str())
+            .ok_or_else(|| isf_error(shader, "🎨 An ISF input is missing its NAME"))?
+            .to_string();
+        let type_name = input.get("TYPE").and_then(|v| v.as_str()).unwrap_or("");
+        let kind = IsfInputKind::parse(type_name).ok_or_else(|| {
+            isf_error(shader, format!("🎨 Input {} has an unknown TYPE '{}'", name, type_name))
+                .with_suggestion("ISF types are float, bool, long, event, point2D, color, image, audio and audioFFT")
+        })?;
+
+        let number = |key: &str| input.get(key).and_then(|v| v.as_f64());
+        let default = match (kind, input.get("DEFAULT")) {
+            (IsfInputKind::Bool | IsfInputKind::Event, Some(v)) => Value::Boolean(v.as_bool().unwrap_or(v.as_f64() == Some(1.0))),
+            (IsfInputKind::Bool | IsfInputKind::Event, None) => Value::Boolean(false),
+            (IsfInputKind::Long, v) => Value::Integer(v.and_then(|v| v.as_f64()).unwrap_or(0.0) as i64),
+            (IsfInputKind::Float, v) => Value::Float(v.and_then(|v| v.as_f64())
+                .unwrap_or_else(|| (number("MIN").unwrap_or(0.0) + number("MAX").unwrap_or(1.0)) / 2.0)),
+            (IsfInputKind::Point2D | IsfInputKind::Color, Some(serde_json::Value::Array(items))) => {
+                Value::Array(items.iter().map(|v| Value::Float(v.as_f64().unwrap_or(0.0))).collect())
+            }
+            (IsfInputKind::Point2D, _) => Value::Array(vec![Value::Float(0.0), Value::Float(0.0)]),
+            (IsfInputKind::Color, _) => Value::Array(vec![Value::Float(1.0); 4]),
+            _ => Value::Null,
+        };
+
+        let values: Vec<i64> = input.get("VALUES").and_then(|v| v.as_array())
+            .map(|values| values.iter().filter_map(|v| v.as_f64()).map(|v| v as i64).collect())
+            .unwrap_or_default();
+        let labels = input.get("LABELS").and_then(|v| v.as_array())
+            .map(|labels| labels.iter().filter_map(|l| l.as_str().map(str::to_string)).collect())
+            .unwrap_or_else(|| values.iter().map(|v| v.to_string()).collect());
+
+        Ok(IsfInput {
+            name,
+            kind,
+            label: input.get("LABEL").and_then(|v| v.as_str()).map(str::to_string),
+            default,
+            min: number("MIN"),
+            max: number("MAX"),
+            values,
+            labels,
+        })
+    }
+
+    /// Filters read the layer below through `inputImage`; generators draw from nothing
+    pub fn is_filter(&self) -> bool {
+        self.inputs.iter().any(|input| input.name == "inputImage" && input.kind == IsfInputKind::Image)
+    }
+
+    pub fn input(&self, name: &str) -> Option<&IsfInput> {
+        self.inputs.iter().find(|input| input.name == name)
+    }
+
+    /// Fragment source with the uniforms and macros ISF shaders expect declared up front
+    pub fn glsl(&self) -> String {
+        let mut header = String::from("#version 450\n\n");
+        header.push_str("layout(location = 0) in vec2 isf_FragNormCoord;\n");
+        header.push_str("layout(location = 0) out vec4 isf_FragColor;\n");
+        header.push_str("uniform float TIME;\nuniform float TIMEDELTA;\nuniform vec2 RENDERSIZE;\n");
+        header.push_str("uniform int PASSINDEX;\nuniform int FRAMEINDEX;\nuniform vec4 DATE;\n");
+        for input in &self.inputs {
+            header.push_str(&format!("uniform {} {};\n", input.kind.glsl_type(), input.name));
+        }
+        for target in self.passes.iter().filter_map(|pass| pass.target.as_ref()) {
+            header.push_str(&format!("uniform sampler2D {};\n", target));
+        }
+        header.push_str("\n#define gl_FragColor isf_FragColor\n");
+        header.push_str("#define IMG_NORM_PIXEL(image, coord) texture(image, coord)\n");
+        header.push_str("#define IMG_PIXEL(image, coord) texture(image, (coord) / RENDERSIZE)\n");
+        header.push_str("#define IMG_THIS_NORM_PIXEL(image) texture(image, isf_FragNormCoord)\n");
+        header.push_str("#define IMG_THIS_PIXEL(image) texture(image, isf_FragNormCoord)\n");
+        header.push_str("#define IMG_SIZE(image) vec2(textureSize(image, 0))\n\n");
+        header.push_str(&self.fragment);
+        header.push('\n');
+        header
+    }
+
+    /// Resolve every non-texture input: explicit arguments win, then the GUI control, then the default
+    pub fn resolve(&self, args: &HashMap<String, Value>) -> crate::Result<HashMap<String, Value>> {
+        if let Some(unknown) = args.keys().find(|key| self.input(key).is_none()) {
+            let known: Vec<&str> = self.inputs.iter().filter(|i| !i.kind.is_texture()).map(|i| i.name.as_str()).collect();
+            return Err(isf_error(&self.name, format!("🎨 {} has no input called {}", self.name, unknown))
+                .with_suggestion(format!("Its inputs are: {}", known.join(", "))));
+        }
+
+        let mut values = HashMap::new();
+        for input in &self.inputs {
+            let value = match args.get(&input.name) {
+                Some(value) => input.coerce(value).ok_or_else(|| {
+                    isf_error(&self.name, format!("🎨 {} expects a {} value", input.name, input.kind.glsl_type()))
+                })?,
+                None if input.kind.is_texture() => continue,
+                None => match input.control(&self.name) {
+                    Some(control) => input.coerce(&declare_control(control)).unwrap_or_else(|| input.default.clone()),
+                    None => input.default.clone(),
+                },
+            };
+            values.insert(input.name.clone(), value);
+        }
+        Ok(values)
+    }
+}
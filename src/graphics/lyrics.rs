@@ -0,0 +1,265 @@
+/// Timed lyrics and subtitles from LRC and SRT files, for karaoke-style text on screen
+///
+/// Every line gets word timings: enhanced LRC gives them directly (`<00:12.50>word`), and
+/// otherwise they're spread across the line by character count, so progress always moves
+/// through the text at a plausible pace.
+use std::iter;
+
+/// How long the last line stays up when nothing follows it
+const LAST_LINE_HOLD: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricWord {
+    pub start: f64,
+    /// Character offset into the line's text
+    pub offset: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub words: Vec<LyricWord>,
+}
+
+impl LyricLine {
+    /// How many characters of the text have been sung by `time`
+    pub fn sung_chars(&self, time: f64) -> usize {
+        let Some(index) = self.words.iter().rposition(|word| word.start <= time) else {
+            return 0;
+        };
+        let word = &self.words[index];
+        let next = self.words.get(index + 1).map_or(self.end, |next| next.start);
+        let fraction = if next > word.start { ((time - word.start) / (next - word.start)).clamp(0.0, 1.0) } else { 1.0 };
+        let length = word.text.chars().count();
+        word.offset + (length as f64 * fraction).round() as usize
+    }
+
+    /// 0 to 1 through the text by `time`
+    pub fn progress(&self, time: f64) -> f64 {
+        let total = self.text.chars().count();
+        if total == 0 {
+            return 1.0;
+        }
+        self.sung_chars(time) as f64 / total as f64
+    }
+
+    /// The word being sung at `time`
+    pub fn word_at(&self, time: f64) -> Option<&LyricWord> {
+        self.words.iter().rev().find(|word| word.start <= time)
+    }
+
+    /// Give each whitespace-separated word a start time in proportion to where it sits in the line
+    fn spread_words(&mut self) {
+        let total = self.text.chars().count().max(1) as f64;
+        let duration = self.end - self.start;
+        self.words = words(&self.text)
+            .map(|(offset, text)| LyricWord { start: self.start + duration * offset as f64 / total, offset, text })
+            .collect();
+    }
+}
+
+/// Each word with its character offset
+fn words(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    let mut offset = 0;
+    text.split(char::is_whitespace).filter_map(move |word| {
+        let start = offset;
+        offset += word.chars().count() + 1;
+        (!word.is_empty()).then(|| (start, word.to_string()))
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lyrics {
+    /// In order of start time
+    pub lines: Vec<LyricLine>,
+}
+
+impl Lyrics {
+    /// Parse by file extension: `.srt` as subtitles, anything else as LRC
+    pub fn parse(path: &str, text: &str) -> crate::Result<Self> {
+        let lyrics = if path.to_lowercase().ends_with(".srt") { Self::parse_srt(text) } else { Self::parse_lrc(text) };
+        if lyrics.lines.is_empty() {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidStreamFormat,
+                format!("🎤 No timed lines in '{}'", path)
+            )
+            .with_suggestion("LRC lines look like [00:12.50]Some words; SRT blocks have 00:00:12,500 --> 00:00:15,000"));
+        }
+        Ok(lyrics)
+    }
+
+    /// `[mm:ss.xx]line`, several stamps for a repeated line, `<mm:ss.xx>` before words and an `[offset:ms]` tag
+    pub fn parse_lrc(text: &str) -> Self {
+        let mut offset = 0.0;
+        // Start, text and word timings relative to the line's first stamp
+        let mut entries: Vec<(f64, String, Vec<TimedWord>)> = Vec::new();
+        for line in text.lines() {
+            let mut rest = line.trim();
+            let mut stamps = Vec::new();
+            while let Some(close) = rest.strip_prefix('[').and_then(|tag| tag.find(']')) {
+                let tag = &rest[1..close + 1];
+                match parse_clock(tag) {
+                    Some(time) => stamps.push(time),
+                    None => if let Some(("offset", ms)) = tag.split_once(':').map(|(key, value)| (key.trim(), value.trim())) {
+                        // Positive offsets make lyrics come in sooner
+                        offset = ms.parse::<f64>().unwrap_or(0.0) / 1000.0;
+                    },
+                }
+                rest = &rest[close + 2..];
+            }
+            let Some(&first) = stamps.first() else {
+                continue;
+            };
+            let (text, timed_words) = parse_lrc_words(rest);
+            let mut timed_words: Vec<_> = timed_words.into_iter().map(|(time, at, word)| (time - first, at, word)).collect();
+            // Words before the first word stamp start with the line
+            if let Some(&(_, at, _)) = timed_words.first() {
+                let before: String = text.chars().take(at).collect();
+                if !before.trim().is_empty() {
+                    let leading = before.chars().take_while(|c| c.is_whitespace()).count();
+                    timed_words.insert(0, (0.0, leading, before.trim().to_string()));
+                }
+            }
+            for stamp in stamps {
+                entries.push((stamp, text.clone(), timed_words.clone()));
+            }
+        }
+        entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut lines = Vec::new();
+        for (index, (start, text, timed_words)) in entries.iter().enumerate() {
+            let end = entries.get(index + 1).map_or(start + LAST_LINE_HOLD, |next| next.0);
+            // Blank lines only mark where the line before them ends
+            if text.trim().is_empty() {
+                continue;
+            }
+            let mut line = LyricLine { start: start - offset, end: end - offset, text: text.clone(), words: Vec::new() };
+            if timed_words.is_empty() {
+                line.spread_words();
+            } else {
+                line.words = timed_words.iter()
+                    .map(|(time, at, word)| LyricWord { start: line.start + time, offset: *at, text: word.clone() })
+                    .collect();
+            }
+            lines.push(line);
+        }
+        Self { lines }
+    }
+
+    /// Numbered blocks of `00:00:12,500 --> 00:00:15,000` and the text to show, separated by blank lines
+    pub fn parse_srt(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut block: Vec<&str> = Vec::new();
+        for row in text.lines().map(str::trim_end).chain(iter::once("")) {
+            if !row.trim().is_empty() {
+                block.push(row);
+                continue;
+            }
+            let timing = block.iter().position(|row| row.contains("-->"));
+            if let Some(timing) = timing {
+                let (start, end) = block[timing].split_once("-->").unwrap_or_default();
+                if let (Some(start), Some(end)) = (parse_clock(start.trim()), parse_clock(end.split_whitespace().next().unwrap_or(""))) {
+                    let text = strip_tags(&block[timing + 1..].join("\n"));
+                    if !text.trim().is_empty() {
+                        let mut line = LyricLine { start, end, text, words: Vec::new() };
+                        line.spread_words();
+                        lines.push(line);
+                    }
+                }
+            }
+            block.clear();
+        }
+        lines.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Self { lines }
+    }
+
+    /// The line showing at `time`, with its position in the file
+    pub fn line_at(&self, time: f64) -> Option<(usize, &LyricLine)> {
+        let index = self.lines.iter().rposition(|line| line.start <= time)?;
+        let line = &self.lines[index];
+        (time < line.end).then_some((index, line))
+    }
+
+    /// Seconds until the last line ends
+    pub fn duration(&self) -> f64 {
+        self.lines.iter().map(|line| line.end).fold(0.0, f64::max)
+    }
+
+    /// Shift every line and word later by `seconds`
+    pub fn shift(&mut self, seconds: f64) {
+        for line in &mut self.lines {
+            line.start += seconds;
+            line.end += seconds;
+            for word in &mut line.words {
+                word.start += seconds;
+            }
+        }
+    }
+}
+
+/// A word's time, character offset and text
+type TimedWord = (f64, usize, String);
+
+/// `mm:ss.xx`, `hh:mm:ss,mmm` or `ss.xx` as seconds
+fn parse_clock(text: &str) -> Option<f64> {
+    let mut seconds = 0.0;
+    for part in text.split(':') {
+        let part = part.trim().replace(',', ".");
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return None;
+        }
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// The line's text without `<mm:ss.xx>` word stamps, and each stamped word's time, offset and text
+fn parse_lrc_words(text: &str) -> (String, Vec<TimedWord>) {
+    let mut plain = String::new();
+    let mut stamps = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>') else {
+            break;
+        };
+        let Some(time) = parse_clock(&rest[open + 1..open + close]) else {
+            plain.push_str(&rest[..open + close + 1]);
+            rest = &rest[open + close + 1..];
+            continue;
+        };
+        plain.push_str(&rest[..open]);
+        stamps.push((time, plain.chars().count()));
+        rest = &rest[open + close + 1..];
+    }
+    plain.push_str(rest);
+
+    let chars: Vec<char> = plain.chars().collect();
+    let timed = stamps.iter().enumerate().filter_map(|(index, &(time, offset))| {
+        let end = stamps.get(index + 1).map_or(chars.len(), |next| next.1);
+        let raw: String = chars[offset..end].iter().collect();
+        let leading = raw.chars().take_while(|c| c.is_whitespace()).count();
+        let word = raw.trim();
+        (!word.is_empty()).then(|| (time, offset + leading, word.to_string()))
+    });
+    let timed = timed.collect();
+    (plain, timed)
+}
+
+/// Drop `<i>`-style markup and `{\an8}` positioning codes from subtitle text
+fn strip_tags(text: &str) -> String {
+    let mut plain = String::new();
+    let mut closing = None;
+    for c in text.chars() {
+        match (closing, c) {
+            (None, '<') => closing = Some('>'),
+            (None, '{') => closing = Some('}'),
+            (None, c) => plain.push(c),
+            (Some(end), c) if c == end => closing = None,
+            (Some(_), _) => {}
+        }
+    }
+    plain
+}
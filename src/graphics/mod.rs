@@ -3,9 +3,27 @@ pub mod effects;
 pub mod primitives;
 pub mod blend_modes;
 pub mod advanced_effects;
+pub mod stylize;
+pub mod sdf;
+pub mod fluid;
+pub mod typography;
+pub mod lyrics;
+pub mod camera_layer;
+pub mod gpu_profiler;
+pub mod isf;
+pub mod hydra;
 
 pub use renderer::*;
 pub use effects::*;
 pub use primitives::*;
 pub use blend_modes::*;
-pub use advanced_effects::*;
\ No newline at end of file
+pub use advanced_effects::*;
+pub use stylize::*;
+pub use sdf::*;
+pub use fluid::*;
+pub use typography::*;
+pub use lyrics::*;
+pub use camera_layer::*;
+pub use gpu_profiler::*;
+pub use isf::*;
+pub use hydra::*;
\ No newline at end of file
@@ -21,11 +21,13 @@ impl Color {
         Self::new(r, g, b, 1.0)
     }
     
+    /// 0xRRGGBB, with how transparent it is in the byte above as `#rrggbbaa` colours give it
     pub fn from_hex(hex: u32) -> Self {
         let r = ((hex >> 16) & 0xFF) as f32 / 255.0;
         let g = ((hex >> 8) & 0xFF) as f32 / 255.0;
         let b = (hex & 0xFF) as f32 / 255.0;
-        Self::rgb(r, g, b)
+        let a = 1.0 - ((hex >> 24) & 0xFF) as f32 / 255.0;
+        Self::new(r, g, b, a)
     }
     
     pub fn to_array(&self) -> [f32; 4] {
@@ -1,8 +1,21 @@
+use crate::graphics::gpu_profiler::GpuProfiler;
+use std::sync::atomic::{AtomicBool, Ordering};
 use winit::{
     event_loop::EventLoop,
     window::{Window, WindowBuilder},
 };
 
+/// Set by the panic button: every window output shows black until it's cleared
+static BLACKOUT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_blackout(on: bool) {
+    BLACKOUT.store(on, Ordering::SeqCst);
+}
+
+pub fn is_blackout() -> bool {
+    BLACKOUT.load(Ordering::SeqCst)
+}
+
 pub struct Renderer {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
@@ -10,6 +23,8 @@ pub struct Renderer {
     config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
     window: Window,
+    present_modes: Vec<wgpu::PresentMode>,
+    profiler: GpuProfiler,
 }
 
 impl Renderer {
@@ -48,7 +63,8 @@ impl Renderer {
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
+                    // Per-pass GPU timings when the adapter can provide them
+                    required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
                     required_limits: wgpu::Limits::default(),
                     label: None,
                 },
@@ -77,12 +93,16 @@ impl Renderer {
 
         surface.configure(&device, &config);
 
+        let profiler = GpuProfiler::new(&device, &queue, 16);
+
         Ok(Self {
             surface,
             device,
             queue,
             config,
             size,
+            present_modes: surface_caps.present_modes.clone(),
+            profiler,
             window: WindowBuilder::new()
                 .with_title("Synthesis")
                 .with_inner_size(winit::dpi::LogicalSize::new(800, 600))
@@ -94,6 +114,36 @@ impl Renderer {
         &self.window
     }
 
+    /// Window events from the event loop: resizes here, touches and pens to their state, keys to the keyboard state
+    pub fn handle_window_event(&mut self, event: &winit::event::WindowEvent) {
+        match event {
+            winit::event::WindowEvent::Resized(size) => self.resize(*size),
+            other => {
+                if let Ok(mut touch) = crate::hardware::touch().lock() {
+                    touch.handle_window_event(other, (self.size.width, self.size.height));
+                }
+                if let Ok(mut tablet) = crate::hardware::tablet().lock() {
+                    tablet.handle_window_event(other);
+                }
+                let fullscreen = match crate::hardware::keyboard().lock() {
+                    Ok(mut keyboard) => {
+                        keyboard.handle_window_event(other);
+                        keyboard.fullscreen
+                    }
+                    Err(_) => return,
+                };
+                self.sync_fullscreen(fullscreen);
+            }
+        }
+    }
+
+    /// Match the window to the fullscreen hotkey's state
+    pub fn sync_fullscreen(&self, fullscreen: bool) {
+        if self.window.fullscreen().is_some() != fullscreen {
+            self.window.set_fullscreen(fullscreen.then_some(winit::window::Fullscreen::Borderless(None)));
+        }
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
@@ -103,12 +153,36 @@ impl Renderer {
         }
     }
 
+    /// Choose a present mode and swapchain depth for the requested frame pacing
+    pub fn set_frame_pacing(&mut self, pacing: &crate::runtime::FramePacingConfig) {
+        let preferred: &[wgpu::PresentMode] = match (pacing.vsync, pacing.triple_buffering) {
+            (true, false) => &[wgpu::PresentMode::Fifo],
+            (true, true) => &[wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo],
+            (false, _) => &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox, wgpu::PresentMode::Fifo],
+        };
+
+        // Fifo is always supported, so this only falls through on unusual drivers
+        self.config.present_mode = preferred
+            .iter()
+            .copied()
+            .find(|mode| self.present_modes.contains(mode))
+            .unwrap_or(self.present_modes[0]);
+        self.config.desired_maximum_frame_latency = pacing.max_frame_latency();
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    pub fn profiler(&self) -> &GpuProfiler {
+        &self.profiler
+    }
+
     pub fn render(&mut self, clear_color: [f32; 4]) -> crate::Result<()> {
+        let clear_color = if is_blackout() { [0.0, 0.0, 0.0, 1.0] } else { clear_color };
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        self.profiler.begin_frame();
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -133,11 +207,13 @@ impl Renderer {
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: self.profiler.pass_timestamps("clear"),
             });
         }
 
+        self.profiler.resolve(&mut encoder);
         self.queue.submit(std::iter::once(encoder.finish()));
+        self.profiler.collect(&self.device);
         output.present();
 
         Ok(())
@@ -0,0 +1,345 @@
+use crate::graphics::primitives::Color;
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Signed-distance primitives in normalized screen space (0.0-1.0, y scaled by aspect)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdfShape {
+    Circle { x: f32, y: f32, radius: f32 },
+    Box { x: f32, y: f32, half_width: f32, half_height: f32, rounding: f32 },
+    Ring { x: f32, y: f32, radius: f32, thickness: f32 },
+    Segment { x1: f32, y1: f32, x2: f32, y2: f32, thickness: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdfOperation {
+    Union,
+    Subtract,
+    Intersect,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdfNode {
+    Shape(SdfShape),
+    Combine {
+        operation: SdfOperation,
+        left: Box<SdfNode>,
+        right: Box<SdfNode>,
+        smoothness: f32, // 0.0 gives hard edges, larger values blend shapes into blobs
+    },
+}
+
+impl SdfShape {
+    pub fn distance(&self, px: f32, py: f32) -> f32 {
+        match self {
+            SdfShape::Circle { x, y, radius } => {
+                ((px - x).powi(2) + (py - y).powi(2)).sqrt() - radius
+            }
+            SdfShape::Box { x, y, half_width, half_height, rounding } => {
+                let dx = (px - x).abs() - (half_width - rounding);
+                let dy = (py - y).abs() - (half_height - rounding);
+                let outside = (dx.max(0.0).powi(2) + dy.max(0.0).powi(2)).sqrt();
+                let inside = dx.max(dy).min(0.0);
+                outside + inside - rounding
+            }
+            SdfShape::Ring { x, y, radius, thickness } => {
+                (((px - x).powi(2) + (py - y).powi(2)).sqrt() - radius).abs() - thickness * 0.5
+            }
+            SdfShape::Segment { x1, y1, x2, y2, thickness } => {
+                let (pax, pay) = (px - x1, py - y1);
+                let (bax, bay) = (x2 - x1, y2 - y1);
+                let length_sq = (bax * bax + bay * bay).max(f32::EPSILON);
+                let h = ((pax * bax + pay * bay) / length_sq).clamp(0.0, 1.0);
+                ((pax - bax * h).powi(2) + (pay - bay * h).powi(2)).sqrt() - thickness * 0.5
+            }
+        }
+    }
+
+    fn to_wgsl(&self) -> String {
+        match self {
+            SdfShape::Circle { x, y, radius } => {
+                format!("sd_circle(p, vec2<f32>({:.5}, {:.5}), {:.5})", x, y, radius)
+            }
+            SdfShape::Box { x, y, half_width, half_height, rounding } => {
+                format!("sd_box(p, vec2<f32>({:.5}, {:.5}), vec2<f32>({:.5}, {:.5}), {:.5})",
+                        x, y, half_width, half_height, rounding)
+            }
+            SdfShape::Ring { x, y, radius, thickness } => {
+                format!("sd_ring(p, vec2<f32>({:.5}, {:.5}), {:.5}, {:.5})", x, y, radius, thickness)
+            }
+            SdfShape::Segment { x1, y1, x2, y2, thickness } => {
+                format!("sd_segment(p, vec2<f32>({:.5}, {:.5}), vec2<f32>({:.5}, {:.5}), {:.5})",
+                        x1, y1, x2, y2, thickness)
+            }
+        }
+    }
+}
+
+impl SdfNode {
+    pub fn distance(&self, px: f32, py: f32) -> f32 {
+        match self {
+            SdfNode::Shape(shape) => shape.distance(px, py),
+            SdfNode::Combine { operation, left, right, smoothness } => {
+                let a = left.distance(px, py);
+                let b = right.distance(px, py);
+                combine_distances(operation, a, b, *smoothness)
+            }
+        }
+    }
+
+    pub fn to_wgsl(&self) -> String {
+        match self {
+            SdfNode::Shape(shape) => shape.to_wgsl(),
+            SdfNode::Combine { operation, left, right, smoothness } => {
+                let function = match operation {
+                    SdfOperation::Union => "op_union",
+                    SdfOperation::Subtract => "op_subtract",
+                    SdfOperation::Intersect => "op_intersect",
+                };
+                format!("{}({}, {}, {:.5})", function, left.to_wgsl(), right.to_wgsl(), smoothness)
+            }
+        }
+    }
+
+    /// Rebuild a node from the object values returned by the Graphics.sdf_* functions
+    pub fn from_value(value: &Value) -> crate::Result<SdfNode> {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => return Err(sdf_value_error("expected an SDF shape")),
+        };
+
+        let number = |key: &str| -> crate::Result<f32> {
+            fields.get(key)
+                .and_then(|v| v.as_number())
+                .map(|n| n as f32)
+                .ok_or_else(|| sdf_value_error(&format!("missing '{}' value", key)))
+        };
+
+        let shape = match fields.get("shape") {
+            Some(Value::String(shape)) => shape.as_str(),
+            _ => return Err(sdf_value_error("expected an SDF shape")),
+        };
+
+        let node = match shape {
+            "circle" => SdfNode::Shape(SdfShape::Circle {
+                x: number("x")?,
+                y: number("y")?,
+                radius: number("radius")?,
+            }),
+            "box" => SdfNode::Shape(SdfShape::Box {
+                x: number("x")?,
+                y: number("y")?,
+                half_width: number("width")? * 0.5,
+                half_height: number("height")? * 0.5,
+                rounding: number("rounding")?,
+            }),
+            "ring" => SdfNode::Shape(SdfShape::Ring {
+                x: number("x")?,
+                y: number("y")?,
+                radius: number("radius")?,
+                thickness: number("thickness")?,
+            }),
+            "segment" => SdfNode::Shape(SdfShape::Segment {
+                x1: number("x1")?,
+                y1: number("y1")?,
+                x2: number("x2")?,
+                y2: number("y2")?,
+                thickness: number("thickness")?,
+            }),
+            "union" | "subtract" | "intersect" => {
+                let operation = match shape {
+                    "union" => SdfOperation::Union,
+                    "subtract" => SdfOperation::Subtract,
+                    _ => SdfOperation::Intersect,
+                };
+                let left = fields.get("left").ok_or_else(|| sdf_value_error("missing left shape"))?;
+                let right = fields.get("right").ok_or_else(|| sdf_value_error("missing right shape"))?;
+                SdfNode::Combine {
+                    operation,
+                    left: Box::new(SdfNode::from_value(left)?),
+                    right: Box::new(SdfNode::from_value(right)?),
+                    smoothness: number("smoothness").unwrap_or(0.0),
+                }
+            }
+            other => return Err(sdf_value_error(&format!("unknown shape '{}'", other))),
+        };
+
+        Ok(node)
+    }
+}
+
+fn sdf_value_error(details: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("🎨 SDF shapes can't be combined: {}", details)
+    )
+    .with_suggestion("Build shapes with Graphics.sdf_circle(), sdf_box(), sdf_ring() or sdf_segment()")
+    .with_suggestion("Combine them with Graphics.sdf_union(a, b, smoothness)")
+}
+
+/// Polynomial smooth min/max blending (Inigo Quilez)
+fn combine_distances(operation: &SdfOperation, a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return match operation {
+            SdfOperation::Union => a.min(b),
+            SdfOperation::Subtract => a.max(-b),
+            SdfOperation::Intersect => a.max(b),
+        };
+    }
+
+    match operation {
+        SdfOperation::Union => {
+            let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+            b * (1.0 - h) + a * h - k * h * (1.0 - h)
+        }
+        SdfOperation::Subtract => {
+            let h = (0.5 - 0.5 * (a + b) / k).clamp(0.0, 1.0);
+            a * (1.0 - h) + (-b) * h + k * h * (1.0 - h)
+        }
+        SdfOperation::Intersect => {
+            let h = (0.5 - 0.5 * (b - a) / k).clamp(0.0, 1.0);
+            b * (1.0 - h) + a * h + k * h * (1.0 - h)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SdfRenderer {
+    pub fill: Color,
+    pub background: Color,
+    pub glow_color: Color,
+    pub glow_radius: f32, // in normalized units, 0.0 disables glow
+    pub glow_intensity: f32,
+}
+
+impl SdfRenderer {
+    pub fn new(fill: Color) -> Self {
+        Self {
+            fill,
+            background: Color::BLACK,
+            glow_color: fill,
+            glow_radius: 0.0,
+            glow_intensity: 1.0,
+        }
+    }
+
+    pub fn with_glow(mut self, radius: f32, intensity: f32) -> Self {
+        self.glow_radius = radius.max(0.0);
+        self.glow_intensity = intensity;
+        self
+    }
+
+    pub fn render(&self, scene: &SdfNode, width: u32, height: u32) -> Vec<Color> {
+        let scale = 1.0 / height.max(1) as f32;
+        // Anti-alias across roughly one pixel of distance
+        let pixel = scale;
+        let mut result = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let px = (x as f32 + 0.5) * scale;
+                let py = (y as f32 + 0.5) * scale;
+                let distance = scene.distance(px, py);
+                result.push(self.shade(distance, pixel));
+            }
+        }
+
+        result
+    }
+
+    fn shade(&self, distance: f32, pixel: f32) -> Color {
+        let coverage = 1.0 - smoothstep(-pixel, pixel, distance);
+        let mut color = Color::new(
+            self.background.r + (self.fill.r - self.background.r) * coverage,
+            self.background.g + (self.fill.g - self.background.g) * coverage,
+            self.background.b + (self.fill.b - self.background.b) * coverage,
+            1.0,
+        );
+
+        if self.glow_radius > 0.0 && distance > 0.0 {
+            let glow = (-distance / self.glow_radius).exp() * self.glow_intensity * (1.0 - coverage);
+            color.r = (color.r + self.glow_color.r * glow).min(1.0);
+            color.g = (color.g + self.glow_color.g * glow).min(1.0);
+            color.b = (color.b + self.glow_color.b * glow).min(1.0);
+        }
+
+        color
+    }
+
+    /// Full-screen WGSL fragment shader evaluating the whole scene in one pass
+    pub fn to_wgsl(&self, scene: &SdfNode) -> String {
+        format!(
+            r#"struct Uniforms {{
+    resolution: vec2<f32>,
+    time: f32,
+}};
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+fn sd_circle(p: vec2<f32>, c: vec2<f32>, r: f32) -> f32 {{ return length(p - c) - r; }}
+fn sd_box(p: vec2<f32>, c: vec2<f32>, h: vec2<f32>, r: f32) -> f32 {{
+    let d = abs(p - c) - (h - vec2<f32>(r));
+    return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0) - r;
+}}
+fn sd_ring(p: vec2<f32>, c: vec2<f32>, r: f32, t: f32) -> f32 {{ return abs(length(p - c) - r) - t * 0.5; }}
+fn sd_segment(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>, t: f32) -> f32 {{
+    let pa = p - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / max(dot(ba, ba), 1e-6), 0.0, 1.0);
+    return length(pa - ba * h) - t * 0.5;
+}}
+fn op_union(a: f32, b: f32, k: f32) -> f32 {{
+    if (k <= 0.0) {{ return min(a, b); }}
+    let h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) - k * h * (1.0 - h);
+}}
+fn op_subtract(a: f32, b: f32, k: f32) -> f32 {{
+    if (k <= 0.0) {{ return max(a, -b); }}
+    let h = clamp(0.5 - 0.5 * (a + b) / k, 0.0, 1.0);
+    return mix(a, -b, h) + k * h * (1.0 - h);
+}}
+fn op_intersect(a: f32, b: f32, k: f32) -> f32 {{
+    if (k <= 0.0) {{ return max(a, b); }}
+    let h = clamp(0.5 - 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) + k * h * (1.0 - h);
+}}
+
+@fragment
+fn fs_main(@builtin(position) frag: vec4<f32>) -> @location(0) vec4<f32> {{
+    let p = frag.xy / uniforms.resolution.y;
+    let d = {scene};
+    let pixel = 1.0 / uniforms.resolution.y;
+    let coverage = 1.0 - smoothstep(-pixel, pixel, d);
+    let fill = vec3<f32>({fr:.4}, {fg:.4}, {fb:.4});
+    let background = vec3<f32>({br:.4}, {bg:.4}, {bb:.4});
+    var color = mix(background, fill, coverage);
+    if ({glow_radius:.5} > 0.0 && d > 0.0) {{
+        color += vec3<f32>({gr:.4}, {gg:.4}, {gb:.4}) * exp(-d / {glow_radius:.5}) * {glow_intensity:.4} * (1.0 - coverage);
+    }}
+    return vec4<f32>(min(color, vec3<f32>(1.0)), 1.0);
+}}
+"#,
+            scene = scene.to_wgsl(),
+            fr = self.fill.r, fg = self.fill.g, fb = self.fill.b,
+            br = self.background.r, bg = self.background.g, bb = self.background.b,
+            gr = self.glow_color.r, gg = self.glow_color.g, gb = self.glow_color.b,
+            glow_radius = self.glow_radius,
+            glow_intensity = self.glow_intensity,
+        )
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Convenience for building shape objects in script-facing functions
+pub fn shape_value(shape: &str, params: &[(&str, f64)]) -> Value {
+    let mut fields = HashMap::new();
+    fields.insert("type".to_string(), Value::String("sdf".to_string()));
+    fields.insert("shape".to_string(), Value::String(shape.to_string()));
+    for (key, value) in params {
+        fields.insert(key.to_string(), Value::Float(*value));
+    }
+    Value::Object(fields)
+}
@@ -0,0 +1,371 @@
+use crate::graphics::primitives::Color;
+use std::collections::HashMap;
+
+/// Character ramp from empty to dense, used to pick a glyph per cell brightness
+const ASCII_RAMP: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// 5x5 bitmaps for each glyph in ASCII_RAMP (row-major, top-left is bit 24)
+const ASCII_GLYPHS: [u32; 10] = [
+    0b00000_00000_00000_00000_00000, // ' '
+    0b00000_00000_00000_00000_00100, // '.'
+    0b00000_00100_00000_00100_00000, // ':'
+    0b00000_00000_01110_00000_00000, // '-'
+    0b00000_01110_00000_01110_00000, // '='
+    0b00000_00100_01110_00100_00000, // '+'
+    0b10101_01110_11111_01110_10101, // '*'
+    0b01010_11111_01010_11111_01010, // '#'
+    0b11001_11010_00100_01011_10011, // '%'
+    0b01110_10001_10111_10110_01111, // '@'
+];
+
+fn luminance(color: &Color) -> f32 {
+    color.r * 0.299 + color.g * 0.587 + color.b * 0.114
+}
+
+#[derive(Debug, Clone)]
+pub struct AsciiEffect {
+    pub cell_size: u32,
+    pub foreground: Option<Color>, // None keeps the source color of each cell
+    pub background: Color,
+    pub invert: bool,
+}
+
+impl AsciiEffect {
+    pub fn new(cell_size: u32) -> Self {
+        Self {
+            cell_size: cell_size.max(5),
+            foreground: None,
+            background: Color::BLACK,
+            invert: false,
+        }
+    }
+
+    /// Character grid for the frame, useful for printing to a terminal or text layer
+    pub fn glyph_grid(&self, input: &[Color], width: u32, height: u32) -> Vec<String> {
+        let mut rows = Vec::new();
+
+        for cell_y in 0..height.div_ceil(self.cell_size) {
+            let mut row = String::new();
+            for cell_x in 0..width.div_ceil(self.cell_size) {
+                let (average, _) = self.cell_average(input, width, height, cell_x, cell_y);
+                row.push(ASCII_RAMP[self.ramp_index(luminance(&average))]);
+            }
+            rows.push(row);
+        }
+
+        rows
+    }
+
+    pub fn apply(&self, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        let mut result = vec![self.background; input.len()];
+
+        for cell_y in 0..height.div_ceil(self.cell_size) {
+            for cell_x in 0..width.div_ceil(self.cell_size) {
+                let (average, _) = self.cell_average(input, width, height, cell_x, cell_y);
+                let glyph = ASCII_GLYPHS[self.ramp_index(luminance(&average))];
+                let ink = self.foreground.unwrap_or(average);
+
+                for dy in 0..self.cell_size {
+                    for dx in 0..self.cell_size {
+                        let x = cell_x * self.cell_size + dx;
+                        let y = cell_y * self.cell_size + dy;
+                        if x >= width || y >= height {
+                            continue;
+                        }
+
+                        // Scale the 5x5 glyph bitmap to the cell
+                        let gx = dx * 5 / self.cell_size;
+                        let gy = dy * 5 / self.cell_size;
+                        let bit = 24 - (gy * 5 + gx);
+                        if glyph & (1 << bit) != 0 {
+                            result[(y * width + x) as usize] = ink;
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    fn ramp_index(&self, brightness: f32) -> usize {
+        let brightness = if self.invert { 1.0 - brightness } else { brightness };
+        let index = (brightness.clamp(0.0, 1.0) * (ASCII_RAMP.len() - 1) as f32).round() as usize;
+        index.min(ASCII_RAMP.len() - 1)
+    }
+
+    fn cell_average(&self, input: &[Color], width: u32, height: u32, cell_x: u32, cell_y: u32) -> (Color, u32) {
+        block_average(input, width, height, cell_x * self.cell_size, cell_y * self.cell_size, self.cell_size)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HalftoneEffect {
+    pub dot_size: f32,
+    pub angle: f32, // degrees
+    pub monochrome: bool,
+    pub ink: Color,
+    pub paper: Color,
+}
+
+impl HalftoneEffect {
+    pub fn new(dot_size: f32, angle: f32) -> Self {
+        Self {
+            dot_size: dot_size.max(2.0),
+            angle,
+            monochrome: true,
+            ink: Color::BLACK,
+            paper: Color::WHITE,
+        }
+    }
+
+    pub fn apply(&self, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let mut result = Vec::with_capacity(input.len());
+
+        for y in 0..height {
+            for x in 0..width {
+                // Rotate into screen space of the dot grid
+                let rx = x as f32 * cos + y as f32 * sin;
+                let ry = -(x as f32) * sin + y as f32 * cos;
+
+                let cell_cx = (rx / self.dot_size).floor() * self.dot_size + self.dot_size * 0.5;
+                let cell_cy = (ry / self.dot_size).floor() * self.dot_size + self.dot_size * 0.5;
+
+                // Rotate the cell center back to sample the source image
+                let sx = (cell_cx * cos - cell_cy * sin).clamp(0.0, (width - 1) as f32) as u32;
+                let sy = (cell_cx * sin + cell_cy * cos).clamp(0.0, (height - 1) as f32) as u32;
+                let sample = input[(sy * width + sx) as usize];
+
+                let distance = ((rx - cell_cx).powi(2) + (ry - cell_cy).powi(2)).sqrt();
+                let max_radius = self.dot_size * std::f32::consts::FRAC_1_SQRT_2;
+                // A full-size dot reaches the cell corners exactly, so it covers them too
+                let covered = |radius: f32| distance < radius || radius >= max_radius;
+
+                let color = if self.monochrome {
+                    // Darker areas get bigger ink dots
+                    let radius = (1.0 - luminance(&sample)).sqrt() * max_radius;
+                    if covered(radius) { self.ink } else { self.paper }
+                } else {
+                    // Colored dots on black, sized by brightness
+                    let radius = luminance(&sample).sqrt() * max_radius;
+                    if covered(radius) { sample } else { Color::BLACK }
+                };
+
+                result.push(color);
+            }
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DitherEffect {
+    pub matrix_size: u32, // 2, 4 or 8
+    pub levels: u32,      // quantization levels per channel
+    threshold_map: Vec<f32>,
+}
+
+impl DitherEffect {
+    pub fn new(matrix_size: u32, levels: u32) -> Self {
+        let matrix_size = match matrix_size {
+            0..=2 => 2,
+            3..=4 => 4,
+            _ => 8,
+        };
+
+        Self {
+            matrix_size,
+            levels: levels.max(2),
+            threshold_map: bayer_matrix(matrix_size),
+        }
+    }
+
+    pub fn apply(&self, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        let steps = (self.levels - 1) as f32;
+        let mut result = Vec::with_capacity(input.len());
+
+        for y in 0..height {
+            for x in 0..width {
+                let color = input[(y * width + x) as usize];
+                let index = (y % self.matrix_size) * self.matrix_size + (x % self.matrix_size);
+                let threshold = self.threshold_map[index as usize] - 0.5;
+
+                let quantize = |channel: f32| ((channel * steps + threshold).round() / steps).clamp(0.0, 1.0);
+                result.push(Color::new(quantize(color.r), quantize(color.g), quantize(color.b), color.a));
+            }
+        }
+
+        result
+    }
+}
+
+/// Normalized ordered-dither threshold map, built recursively from the 2x2 base
+fn bayer_matrix(size: u32) -> Vec<f32> {
+    let mut matrix = vec![0u32];
+    let mut current = 1;
+
+    while current < size {
+        let next = current * 2;
+        let mut expanded = vec![0u32; (next * next) as usize];
+        for y in 0..current {
+            for x in 0..current {
+                let value = matrix[(y * current + x) as usize] * 4;
+                expanded[(y * next + x) as usize] = value;
+                expanded[(y * next + x + current) as usize] = value + 2;
+                expanded[((y + current) * next + x) as usize] = value + 3;
+                expanded[((y + current) * next + x + current) as usize] = value + 1;
+            }
+        }
+        matrix = expanded;
+        current = next;
+    }
+
+    let cells = (size * size) as f32;
+    matrix.into_iter().map(|v| (v as f32 + 0.5) / cells).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct PixelateEffect {
+    pub block_size: u32,
+    pub palette: Option<Vec<Color>>, // None keeps the block averages unquantized
+}
+
+impl PixelateEffect {
+    pub fn new(block_size: u32) -> Self {
+        Self {
+            block_size: block_size.max(1),
+            palette: None,
+        }
+    }
+
+    pub fn with_palette(mut self, palette: Vec<Color>) -> Self {
+        if !palette.is_empty() {
+            self.palette = Some(palette);
+        }
+        self
+    }
+
+    pub fn apply(&self, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        let mut result = vec![Color::BLACK; input.len()];
+
+        for block_y in 0..height.div_ceil(self.block_size) {
+            for block_x in 0..width.div_ceil(self.block_size) {
+                let start_x = block_x * self.block_size;
+                let start_y = block_y * self.block_size;
+                let (average, _) = block_average(input, width, height, start_x, start_y, self.block_size);
+                let color = match &self.palette {
+                    Some(palette) => nearest_palette_color(palette, average),
+                    None => average,
+                };
+
+                for y in start_y..(start_y + self.block_size).min(height) {
+                    for x in start_x..(start_x + self.block_size).min(width) {
+                        result[(y * width + x) as usize] = color;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+fn block_average(input: &[Color], width: u32, height: u32, start_x: u32, start_y: u32, size: u32) -> (Color, u32) {
+    let mut sum = Color::new(0.0, 0.0, 0.0, 0.0);
+    let mut count = 0;
+
+    for y in start_y..(start_y + size).min(height) {
+        for x in start_x..(start_x + size).min(width) {
+            let sample = input[(y * width + x) as usize];
+            sum.r += sample.r;
+            sum.g += sample.g;
+            sum.b += sample.b;
+            sum.a += sample.a;
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return (Color::BLACK, 0);
+    }
+
+    let n = count as f32;
+    (Color::new(sum.r / n, sum.g / n, sum.b / n, sum.a / n), count)
+}
+
+fn nearest_palette_color(palette: &[Color], color: Color) -> Color {
+    palette.iter()
+        .copied()
+        .min_by(|a, b| {
+            let da = (a.r - color.r).powi(2) + (a.g - color.g).powi(2) + (a.b - color.b).powi(2);
+            let db = (b.r - color.r).powi(2) + (b.g - color.g).powi(2) + (b.b - color.b).powi(2);
+            da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|nearest| Color::new(nearest.r, nearest.g, nearest.b, color.a))
+        .unwrap_or(color)
+}
+
+/// Built-in lo-fi palettes for pixelation
+pub fn named_palette(name: &str) -> Option<Vec<Color>> {
+    let hex: &[u32] = match name {
+        "gameboy" => &[0x0F380F, 0x306230, 0x8BAC0F, 0x9BBC0F],
+        "cga" => &[0x000000, 0x55FFFF, 0xFF55FF, 0xFFFFFF],
+        "pico8" => &[
+            0x000000, 0x1D2B53, 0x7E2553, 0x008751, 0xAB5236, 0x5F574F, 0xC2C3C7, 0xFFF1E8,
+            0xFF004D, 0xFFA300, 0xFFEC27, 0x00E436, 0x29ADFF, 0x83769C, 0xFF77A8, 0xFFCCAA,
+        ],
+        "mono" => &[0x000000, 0xFFFFFF],
+        _ => return None,
+    };
+
+    Some(hex.iter().map(|&h| Color::from_hex(h)).collect())
+}
+
+#[derive(Debug, Clone)]
+pub enum StylizeEffect {
+    Ascii(AsciiEffect),
+    Halftone(HalftoneEffect),
+    Dither(DitherEffect),
+    Pixelate(PixelateEffect),
+}
+
+impl StylizeEffect {
+    pub fn apply(&self, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        match self {
+            StylizeEffect::Ascii(effect) => effect.apply(input, width, height),
+            StylizeEffect::Halftone(effect) => effect.apply(input, width, height),
+            StylizeEffect::Dither(effect) => effect.apply(input, width, height),
+            StylizeEffect::Pixelate(effect) => effect.apply(input, width, height),
+        }
+    }
+}
+
+/// Stylization chains assigned to individual layers
+#[derive(Debug, Clone, Default)]
+pub struct LayerStylizer {
+    layers: HashMap<u32, Vec<StylizeEffect>>,
+}
+
+impl LayerStylizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, layer: u32, effect: StylizeEffect) {
+        self.layers.entry(layer).or_default().push(effect);
+    }
+
+    pub fn clear(&mut self, layer: u32) {
+        self.layers.remove(&layer);
+    }
+
+    pub fn apply(&self, layer: u32, input: &[Color], width: u32, height: u32) -> Vec<Color> {
+        match self.layers.get(&layer) {
+            Some(effects) => effects.iter()
+                .fold(input.to_vec(), |frame, effect| effect.apply(&frame, width, height)),
+            None => input.to_vec(),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::graphics::primitives::{Color, Point};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Horizontal advance of a glyph relative to the font size (monospaced approximation)
+const GLYPH_ADVANCE: f32 = 0.6;
+/// Distance between baselines relative to the font size
+const LINE_HEIGHT: f32 = 1.2;
+
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    pub character: char,
+    pub index: usize,
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32, // degrees
+    pub scale: f32,
+    pub color: Color,
+}
+
+impl Glyph {
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("char".to_string(), Value::String(self.character.to_string()));
+        fields.insert("index".to_string(), Value::Integer(self.index as i64));
+        fields.insert("x".to_string(), Value::Float(self.x as f64));
+        fields.insert("y".to_string(), Value::Float(self.y as f64));
+        fields.insert("rotation".to_string(), Value::Float(self.rotation as f64));
+        fields.insert("scale".to_string(), Value::Float(self.scale as f64));
+        fields.insert("color".to_string(), Value::Integer(color_to_hex(&self.color) as i64));
+        Value::Object(fields)
+    }
+
+    pub fn from_value(value: &Value) -> Option<Glyph> {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => return None,
+        };
+        let number = |key: &str| fields.get(key).and_then(|v| v.as_number());
+
+        Some(Glyph {
+            character: match fields.get("char") {
+                Some(Value::String(s)) => s.chars().next()?,
+                _ => return None,
+            },
+            index: number("index")? as usize,
+            x: number("x")? as f32,
+            y: number("y")? as f32,
+            rotation: number("rotation").unwrap_or(0.0) as f32,
+            scale: number("scale").unwrap_or(1.0) as f32,
+            color: Color::from_hex(number("color").unwrap_or(0xFFFFFF as f64) as u32),
+        })
+    }
+}
+
+fn color_to_hex(color: &Color) -> u32 {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+    (channel(color.r) << 16) | (channel(color.g) << 8) | channel(color.b)
+}
+
+/// Lay out text on a straight baseline starting at (x, y); each `\n` starts a new line below
+pub fn layout_line(text: &str, x: f32, y: f32, size: f32, color: Color) -> Vec<Glyph> {
+    text.split('\n')
+        .enumerate()
+        .flat_map(|(line, text)| text.chars().enumerate().map(move |(column, character)| (line, column, character)))
+        .enumerate()
+        .map(|(index, (line, column, character))| Glyph {
+            character,
+            index,
+            x: x + column as f32 * size * GLYPH_ADVANCE,
+            y: y + line as f32 * size * LINE_HEIGHT,
+            rotation: 0.0,
+            scale: 1.0,
+            color,
+        })
+        .collect()
+}
+
+/// Polyline path with arc-length lookup, used to flow text along curves
+#[derive(Debug, Clone)]
+pub struct TextPath {
+    pub points: Vec<Point>,
+    lengths: Vec<f32>, // cumulative length at each point
+}
+
+impl TextPath {
+    pub fn new(points: Vec<Point>) -> Self {
+        let mut lengths = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                let prev = &points[i - 1];
+                total += ((point.x - prev.x).powi(2) + (point.y - prev.y).powi(2)).sqrt();
+            }
+            lengths.push(total);
+        }
+
+        Self { points, lengths }
+    }
+
+    pub fn circle(center_x: f32, center_y: f32, radius: f32, segments: usize) -> Self {
+        let segments = segments.max(8);
+        let points = (0..=segments)
+            .map(|i| {
+                // Start at the top and go clockwise so text reads upright
+                let angle = -std::f32::consts::FRAC_PI_2 + i as f32 / segments as f32 * std::f32::consts::TAU;
+                Point::new(center_x + radius * angle.cos(), center_y + radius * angle.sin())
+            })
+            .collect();
+        Self::new(points)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.lengths.last().copied().unwrap_or(0.0)
+    }
+
+    /// Position and tangent angle (degrees) at a distance along the path
+    pub fn point_at(&self, distance: f32) -> Option<(f32, f32, f32)> {
+        if self.points.len() < 2 {
+            return None;
+        }
+
+        let distance = distance.clamp(0.0, self.length());
+        let segment = self.lengths
+            .windows(2)
+            .position(|w| distance <= w[1])
+            .unwrap_or(self.points.len() - 2);
+
+        let start = &self.points[segment];
+        let end = &self.points[segment + 1];
+        let segment_length = self.lengths[segment + 1] - self.lengths[segment];
+        let t = if segment_length > 0.0 {
+            (distance - self.lengths[segment]) / segment_length
+        } else {
+            0.0
+        };
+
+        let angle = (end.y - start.y).atan2(end.x - start.x).to_degrees();
+        Some((start.x + (end.x - start.x) * t, start.y + (end.y - start.y) * t, angle))
+    }
+
+    /// Place glyphs along the path; `offset` scrolls the text along it
+    pub fn layout(&self, text: &str, size: f32, offset: f32, color: Color) -> Vec<Glyph> {
+        let advance = size * GLYPH_ADVANCE;
+        let length = self.length();
+
+        text.chars()
+            .enumerate()
+            .filter_map(|(index, character)| {
+                // Center each glyph on its slot along the path
+                let mut distance = offset + (index as f32 + 0.5) * advance;
+                if length > 0.0 && self.is_closed() {
+                    distance = distance.rem_euclid(length);
+                } else if distance > length {
+                    return None;
+                }
+                let (x, y, rotation) = self.point_at(distance)?;
+                Some(Glyph { character, index, x, y, rotation, scale: 1.0, color })
+            })
+            .collect()
+    }
+
+    fn is_closed(&self) -> bool {
+        match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first.x - last.x).abs() < 0.001 && (first.y - last.y).abs() < 0.001,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlyphAnimation {
+    Wave,     // vertical sine offset travelling through the text
+    Spin,     // each glyph rotates with a phase offset by index
+    Pulse,    // scale breathing staggered by index
+    Scatter,  // glyphs fly apart by `amount` and settle back
+    Rainbow,  // hue cycles along the text
+}
+
+impl GlyphAnimation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wave" => Some(GlyphAnimation::Wave),
+            "spin" => Some(GlyphAnimation::Spin),
+            "pulse" => Some(GlyphAnimation::Pulse),
+            "scatter" => Some(GlyphAnimation::Scatter),
+            "rainbow" => Some(GlyphAnimation::Rainbow),
+            _ => None,
+        }
+    }
+
+    /// Apply the animation at `time`; `amount` is typically driven by a stream (0.0-1.0)
+    pub fn apply(&self, glyphs: &mut [Glyph], time: f32, amount: f32, spread: f32) {
+        for glyph in glyphs.iter_mut() {
+            let phase = time + glyph.index as f32 * spread;
+            match self {
+                GlyphAnimation::Wave => {
+                    glyph.y += phase.sin() * amount * 20.0;
+                }
+                GlyphAnimation::Spin => {
+                    glyph.rotation += phase * 90.0 * amount;
+                }
+                GlyphAnimation::Pulse => {
+                    glyph.scale *= 1.0 + phase.sin().max(0.0) * amount;
+                }
+                GlyphAnimation::Scatter => {
+                    // Deterministic pseudo-random direction per glyph
+                    let angle = (glyph.index as f32 * 2.399_963).rem_euclid(std::f32::consts::TAU);
+                    glyph.x += angle.cos() * amount * 100.0;
+                    glyph.y += angle.sin() * amount * 100.0;
+                    glyph.rotation += (angle.to_degrees() - 180.0) * amount;
+                }
+                GlyphAnimation::Rainbow => {
+                    let hue = phase.rem_euclid(std::f32::consts::TAU);
+                    let shifted = Color::new(
+                        hue.sin() * 0.5 + 0.5,
+                        (hue + 2.094).sin() * 0.5 + 0.5,
+                        (hue + 4.189).sin() * 0.5 + 0.5,
+                        glyph.color.a,
+                    );
+                    glyph.color = Color::new(
+                        glyph.color.r + (shifted.r - glyph.color.r) * amount,
+                        glyph.color.g + (shifted.g - glyph.color.g) * amount,
+                        glyph.color.b + (shifted.b - glyph.color.b) * amount,
+                        glyph.color.a,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 //use crate::runtime::Value;
 
 #[derive(Debug, Clone)]
+#[derive(Default)]
 pub struct ControlState {
     pub sliders: HashMap<String, f32>,
     pub buttons: HashMap<String, bool>,
@@ -13,19 +14,6 @@ pub struct ControlState {
     pub color_pickers: HashMap<String, [f32; 3]>,
 }
 
-impl Default for ControlState {
-    fn default() -> Self {
-        Self {
-            sliders: HashMap::new(),
-            buttons: HashMap::new(),
-            toggles: HashMap::new(),
-            dropdowns: HashMap::new(),
-            knobs: HashMap::new(),
-            xy_pads: HashMap::new(),
-            color_pickers: HashMap::new(),
-        }
-    }
-}
 
 pub struct SynthesisGUI {
     pub control_state: ControlState,
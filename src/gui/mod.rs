@@ -61,6 +61,26 @@ impl SynthesisGui {
                 });
                 ui.add(egui::Slider::new(&mut 0.5f32, 0.0..=1.0).text("Test Slider"));
             });
+            
+            // Live tweaks that Ctrl+Z can take back, newest first
+            ui.collapsing("History", |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Undo").clicked() {
+                        crate::runtime::undo::undo_last(None);
+                    }
+                    if ui.button("Redo").clicked() {
+                        crate::runtime::undo::redo_last();
+                    }
+                });
+                let history = crate::runtime::undo::undo_history().lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                if history.entries().is_empty() {
+                    ui.label("No changes yet");
+                }
+                for change in history.entries().iter().rev() {
+                    ui.label(format!("{}: {} → {}", change.target.name(), change.before, change.after));
+                }
+            });
         });
     }
     
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use std::time::Instant;
 
 #[derive(Debug, Clone)]
@@ -294,4 +295,496 @@ impl Default for ControllerManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+// Grid controllers: Launchpad, Push and APC-style pad grids
+//
+// A `GridProfile` says what one model sends and expects: the note under each pad, where the
+// scene and arrow buttons are, and which velocities light which colours. A `GridController`
+// pairs a profile with a page offset and drives anything that implements `GridSurface`,
+// sending only the LEDs that changed since the last refresh.
+
+/// Levels below this show no meter at all
+const METER_FLOOR_DB: f32 = -48.0;
+
+/// One button on a grid controller, as it appears on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GridButton {
+    Note(u8),
+    Cc(u8),
+}
+
+impl GridButton {
+    /// The button a press came from; releases and note-offs are None
+    pub fn pressed(message: &[u8]) -> Option<Self> {
+        match *message {
+            [status, data, value, ..] if status & 0xF0 == 0x90 && value > 0 => Some(Self::Note(data)),
+            [status, data, value, ..] if status & 0xF0 == 0xB0 && value > 0 => Some(Self::Cc(data)),
+            _ => None,
+        }
+    }
+
+    fn message(&self, channel: u8, value: u8) -> [u8; 3] {
+        match self {
+            Self::Note(note) => [0x90 | (channel & 0x0F), *note, value],
+            Self::Cc(controller) => [0xB0 | (channel & 0x0F), *controller, value],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedColor {
+    Off,
+    /// Something is there but idle: a stopped clip, an arrow you can page towards
+    Dim,
+    Green,
+    Yellow,
+    Red,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PadLight {
+    pub color: LedColor,
+    pub flashing: bool,
+}
+
+impl PadLight {
+    pub const OFF: Self = Self::steady(LedColor::Off);
+
+    pub const fn steady(color: LedColor) -> Self {
+        Self { color, flashing: false }
+    }
+
+    pub const fn flashing(color: LedColor) -> Self {
+        Self { color, flashing: true }
+    }
+}
+
+/// How a model makes an LED flash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashStyle {
+    /// The same velocity on another MIDI channel, counting from 0
+    Channel(u8),
+    /// The velocity one above the steady colour
+    NextVelocity,
+}
+
+/// Velocity that lights each colour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedPalette {
+    pub off: u8,
+    pub dim: u8,
+    pub green: u8,
+    pub yellow: u8,
+    pub red: u8,
+}
+
+impl LedPalette {
+    pub fn velocity(&self, color: LedColor) -> u8 {
+        match color {
+            LedColor::Off => self.off,
+            LedColor::Dim => self.dim,
+            LedColor::Green => self.green,
+            LedColor::Yellow => self.yellow,
+            LedColor::Red => self.red,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A press in grid terms; rows count down from the top, like scenes do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridInput {
+    Pad { column: usize, row: usize },
+    Scene(usize),
+    Page(PageDirection),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridProfile {
+    pub name: String,
+    /// Part of the MIDI port name the model shows up as; LED updates only go to that port
+    pub port: String,
+    pub columns: usize,
+    pub rows: usize,
+    /// Note of the bottom-left pad, and how far the notes jump from one row to the next
+    pub first_pad: u8,
+    pub row_stride: u8,
+    /// Scene launch buttons, top to bottom
+    pub scenes: Vec<GridButton>,
+    /// Up, down, left and right
+    pub arrows: [GridButton; 4],
+    pub palette: LedPalette,
+    pub flash: FlashStyle,
+}
+
+impl GridProfile {
+    pub const BUILTIN: [&'static str; 4] = ["launchpad", "push", "apc_mini", "apc40"];
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace([' ', '-'], "_").as_str() {
+            "launchpad" | "launchpad_x" | "launchpad_mini" | "launchpad_pro" => Some(Self::launchpad()),
+            "push" | "push2" | "push_2" => Some(Self::push()),
+            "apc_mini" | "apcmini" => Some(Self::apc_mini()),
+            "apc40" | "apc40_mkii" | "apc40_mk2" => Some(Self::apc40()),
+            _ => None,
+        }
+    }
+
+    /// Novation Launchpad X, Mini and Pro in programmer mode
+    pub fn launchpad() -> Self {
+        Self {
+            name: "launchpad".to_string(),
+            port: "Launchpad".to_string(),
+            columns: 8,
+            rows: 8,
+            first_pad: 11,
+            row_stride: 10,
+            scenes: (1..=8).rev().map(|row| GridButton::Cc(row * 10 + 9)).collect(),
+            arrows: [GridButton::Cc(91), GridButton::Cc(92), GridButton::Cc(93), GridButton::Cc(94)],
+            palette: LedPalette { off: 0, dim: 1, green: 21, yellow: 13, red: 5 },
+            flash: FlashStyle::Channel(1),
+        }
+    }
+
+    /// Ableton Push 2 in user mode; the pads run 36 to 99 from the bottom left
+    pub fn push() -> Self {
+        Self {
+            name: "push".to_string(),
+            port: "Ableton Push".to_string(),
+            columns: 8,
+            rows: 8,
+            first_pad: 36,
+            row_stride: 8,
+            scenes: (36..=43).map(GridButton::Cc).collect(),
+            arrows: [GridButton::Cc(46), GridButton::Cc(47), GridButton::Cc(44), GridButton::Cc(45)],
+            palette: LedPalette { off: 0, dim: 124, green: 126, yellow: 8, red: 127 },
+            // Channel 15 blinks in eighth notes
+            flash: FlashStyle::Channel(14),
+        }
+    }
+
+    /// Akai APC mini: three colours, blinking on the next velocity up
+    pub fn apc_mini() -> Self {
+        Self {
+            name: "apc_mini".to_string(),
+            port: "APC MINI".to_string(),
+            columns: 8,
+            rows: 8,
+            first_pad: 0,
+            row_stride: 8,
+            scenes: (82..=89).map(GridButton::Note).collect(),
+            arrows: [GridButton::Note(64), GridButton::Note(65), GridButton::Note(66), GridButton::Note(67)],
+            palette: LedPalette { off: 0, dim: 5, green: 1, yellow: 5, red: 3 },
+            flash: FlashStyle::NextVelocity,
+        }
+    }
+
+    /// Akai APC40 mkII: eight tracks by five scenes, with the Launchpad colour palette
+    pub fn apc40() -> Self {
+        Self {
+            name: "apc40".to_string(),
+            port: "APC40".to_string(),
+            columns: 8,
+            rows: 5,
+            first_pad: 0,
+            row_stride: 8,
+            scenes: (82..=86).map(GridButton::Note).collect(),
+            arrows: [GridButton::Note(94), GridButton::Note(95), GridButton::Note(97), GridButton::Note(96)],
+            palette: LedPalette { off: 0, dim: 1, green: 21, yellow: 13, red: 5 },
+            // Channel 14 blinks in eighth notes
+            flash: FlashStyle::Channel(13),
+        }
+    }
+
+    /// The pad at a column and row, counting rows down from the top
+    pub fn pad(&self, column: usize, row: usize) -> Option<GridButton> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        let note = self.first_pad as usize + (self.rows - 1 - row) * self.row_stride as usize + column;
+        u8::try_from(note).ok().filter(|note| *note < 128).map(GridButton::Note)
+    }
+
+    pub fn decode(&self, message: &[u8]) -> Option<GridInput> {
+        let button = GridButton::pressed(message)?;
+        if let Some(index) = self.arrows.iter().position(|arrow| *arrow == button) {
+            let direction = [PageDirection::Up, PageDirection::Down, PageDirection::Left, PageDirection::Right][index];
+            return Some(GridInput::Page(direction));
+        }
+        if let Some(row) = self.scenes.iter().position(|scene| *scene == button) {
+            return Some(GridInput::Scene(row));
+        }
+        let GridButton::Note(note) = button else {
+            return None;
+        };
+        let offset = note.checked_sub(self.first_pad)?;
+        let (from_bottom, column) = ((offset / self.row_stride.max(1)) as usize, (offset % self.row_stride.max(1)) as usize);
+        (column < self.columns && from_bottom < self.rows).then(|| GridInput::Pad { column, row: self.rows - 1 - from_bottom })
+    }
+
+    pub fn light_message(&self, button: GridButton, light: PadLight) -> [u8; 3] {
+        let velocity = self.palette.velocity(light.color);
+        match self.flash {
+            FlashStyle::Channel(channel) if light.flashing && light.color != LedColor::Off => button.message(channel, velocity),
+            FlashStyle::NextVelocity if light.flashing && light.color != LedColor::Off => button.message(0, (velocity + 1).min(127)),
+            _ => button.message(0, velocity),
+        }
+    }
+}
+
+/// Anything a grid controller can show and play: the clip grid, a step sequencer, or a
+/// script's own layout. Columns and rows are the whole surface; the controller pages
+/// across it a screenful at a time.
+pub trait GridSurface {
+    fn size(&self) -> (usize, usize);
+    fn light(&self, column: usize, row: usize) -> PadLight;
+    fn press(&mut self, column: usize, row: usize);
+
+    fn row_light(&self, _row: usize) -> PadLight {
+        PadLight::OFF
+    }
+
+    fn press_row(&mut self, _row: usize) {}
+
+    /// Level of a column from 0 to 1, for the meter view
+    fn level(&self, _column: usize) -> f32 {
+        0.0
+    }
+}
+
+/// Tracks across, scenes down; pads launch and stop clips and the scene buttons launch rows
+impl GridSurface for crate::audio::clips::ClipGrid {
+    fn size(&self) -> (usize, usize) {
+        (self.tracks(), self.scenes())
+    }
+
+    fn light(&self, column: usize, row: usize) -> PadLight {
+        use crate::audio::clips::ClipState;
+        match self.state(column, row) {
+            ClipState::Empty => PadLight::OFF,
+            ClipState::Stopped => PadLight::steady(LedColor::Dim),
+            ClipState::Queued => PadLight::flashing(LedColor::Green),
+            ClipState::Playing => PadLight::steady(LedColor::Green),
+            ClipState::Stopping => PadLight::flashing(LedColor::Yellow),
+        }
+    }
+
+    fn press(&mut self, column: usize, row: usize) {
+        self.toggle(column, row);
+    }
+
+    fn row_light(&self, row: usize) -> PadLight {
+        if self.playing().contains(&Some(row)) {
+            PadLight::steady(LedColor::Green)
+        } else if (0..self.tracks()).any(|track| self.clip(track, row).is_some()) {
+            PadLight::steady(LedColor::Dim)
+        } else {
+            PadLight::OFF
+        }
+    }
+
+    fn press_row(&mut self, row: usize) {
+        self.launch_scene(row);
+    }
+
+    fn level(&self, column: usize) -> f32 {
+        crate::audio::clips::ClipGrid::level(self, column)
+    }
+}
+
+/// Steps across, tracks down; the playhead shows yellow and the row buttons mute tracks
+impl GridSurface for crate::modules::time::Sequencer {
+    fn size(&self) -> (usize, usize) {
+        (self.tracks.iter().map(|track| track.length).max().unwrap_or(0), self.tracks.len())
+    }
+
+    fn light(&self, column: usize, row: usize) -> PadLight {
+        let Some(track) = self.tracks.get(row) else {
+            return PadLight::OFF;
+        };
+        let Some(step) = track.steps.get(column) else {
+            return PadLight::OFF;
+        };
+        let current = track.length > 0 && self.get_current_step() % track.length == column;
+        match (step.active, current) {
+            (true, true) => PadLight::steady(LedColor::Yellow),
+            (true, false) => PadLight::steady(LedColor::Green),
+            (false, true) => PadLight::steady(LedColor::Dim),
+            (false, false) => PadLight::OFF,
+        }
+    }
+
+    fn press(&mut self, column: usize, row: usize) {
+        let active = self.tracks.get(row).and_then(|track| track.steps.get(column)).is_some_and(|step| step.active);
+        self.set_step(row, column, !active);
+    }
+
+    fn row_light(&self, row: usize) -> PadLight {
+        match self.tracks.get(row) {
+            Some(track) if track.muted => PadLight::steady(LedColor::Red),
+            Some(_) => PadLight::steady(LedColor::Dim),
+            None => PadLight::OFF,
+        }
+    }
+
+    fn press_row(&mut self, row: usize) {
+        if let Some(track) = self.tracks.get_mut(row) {
+            track.muted = !track.muted;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridView {
+    /// Pads show and play the surface
+    Surface,
+    /// Each column is a level meter for its track
+    Meters,
+}
+
+impl GridView {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Surface => "surface",
+            Self::Meters => "meters",
+        }
+    }
+}
+
+/// One bar of a column meter: green at the bottom, yellow near the top, red at the very top
+fn meter_light(level: f32, row: usize, rows: usize) -> PadLight {
+    let db = 20.0 * level.max(1e-6).log10();
+    let lit = ((1.0 - db / METER_FLOOR_DB).clamp(0.0, 1.0) * rows as f32).round() as usize;
+    let from_bottom = rows - 1 - row;
+    if from_bottom >= lit {
+        return PadLight::OFF;
+    }
+    let height = (from_bottom + 1) as f32 / rows as f32;
+    PadLight::steady(match height {
+        h if h > 0.9 => LedColor::Red,
+        h if h > 0.7 => LedColor::Yellow,
+        _ => LedColor::Green,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct GridController {
+    pub profile: GridProfile,
+    pub view: GridView,
+    /// Top-left corner of the page the pads show
+    pub column_offset: usize,
+    pub row_offset: usize,
+    /// Last message sent to each button, so a refresh only sends changes
+    lit: HashMap<GridButton, [u8; 3]>,
+}
+
+impl GridController {
+    pub fn new(profile: GridProfile) -> Self {
+        Self { profile, view: GridView::Surface, column_offset: 0, row_offset: 0, lit: HashMap::new() }
+    }
+
+    /// Act on one incoming message; false if it wasn't one of this controller's buttons
+    pub fn handle(&mut self, message: &[u8], surface: &mut dyn GridSurface) -> bool {
+        match self.profile.decode(message) {
+            Some(GridInput::Pad { column, row }) => {
+                if self.view == GridView::Surface {
+                    surface.press(column + self.column_offset, row + self.row_offset);
+                }
+                true
+            }
+            Some(GridInput::Scene(row)) => {
+                surface.press_row(row + self.row_offset);
+                true
+            }
+            Some(GridInput::Page(direction)) => {
+                self.page(direction, surface.size());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move a screenful across a surface of `size`, stopping at the last page that has anything on it
+    pub fn page(&mut self, direction: PageDirection, size: (usize, usize)) {
+        let (columns, rows) = (self.profile.columns, self.profile.rows);
+        match direction {
+            PageDirection::Up => self.row_offset = self.row_offset.saturating_sub(rows),
+            PageDirection::Down if self.row_offset + rows < size.1 => self.row_offset += rows,
+            PageDirection::Left => self.column_offset = self.column_offset.saturating_sub(columns),
+            PageDirection::Right if self.column_offset + columns < size.0 => self.column_offset += columns,
+            _ => {}
+        }
+    }
+
+    /// LED messages for every light that changed since the last refresh
+    pub fn refresh(&mut self, surface: &dyn GridSurface) -> Vec<[u8; 3]> {
+        let (columns, rows) = (self.profile.columns, self.profile.rows);
+        let mut lights = Vec::with_capacity(columns * rows + self.profile.scenes.len() + 4);
+        for row in 0..rows {
+            for column in 0..columns {
+                let light = match self.view {
+                    GridView::Surface => surface.light(column + self.column_offset, row + self.row_offset),
+                    GridView::Meters => meter_light(surface.level(column + self.column_offset), row, rows),
+                };
+                if let Some(pad) = self.profile.pad(column, row) {
+                    lights.push((pad, light));
+                }
+            }
+        }
+        for (row, button) in self.profile.scenes.iter().enumerate() {
+            let light = match self.view {
+                GridView::Surface => surface.row_light(row + self.row_offset),
+                GridView::Meters => PadLight::OFF,
+            };
+            lights.push((*button, light));
+        }
+        // Arrows light up when there's another page that way
+        let size = surface.size();
+        let open = [
+            self.row_offset > 0,
+            self.row_offset + rows < size.1,
+            self.column_offset > 0,
+            self.column_offset + columns < size.0,
+        ];
+        for (button, open) in self.profile.arrows.iter().zip(open) {
+            lights.push((*button, if open { PadLight::steady(LedColor::Dim) } else { PadLight::OFF }));
+        }
+
+        lights
+            .into_iter()
+            .filter_map(|(button, light)| {
+                let message = self.profile.light_message(button, light);
+                (self.lit.insert(button, message) != Some(message)).then_some(message)
+            })
+            .collect()
+    }
+
+    /// Messages that turn off everything still lit, for when the controller is let go
+    pub fn blackout(&mut self) -> Vec<[u8; 3]> {
+        let profile = &self.profile;
+        self.lit
+            .drain()
+            .map(|(button, _)| profile.light_message(button, PadLight::OFF))
+            .collect()
+    }
+
+    /// Forget what's lit so the next refresh repaints every LED, after a reconnect say
+    pub fn repaint(&mut self) {
+        self.lit.clear();
+    }
+}
+
+static GRID_CONTROLLER: OnceLock<Mutex<Option<GridController>>> = OnceLock::new();
+
+/// The grid controller driving the clip grid, if one is attached
+pub fn grid_controller() -> &'static Mutex<Option<GridController>> {
+    GRID_CONTROLLER.get_or_init(|| Mutex::new(None))
+}
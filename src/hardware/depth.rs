@@ -0,0 +1,398 @@
+/// Depth cameras: Kinect and RealSense frames as depth textures, point clouds and people
+///
+/// Frames are millimetres per pixel, 0 where the camera got no reading. Each frame the
+/// interpreter publishes a decimated depth texture (near is bright), a user mask and a point
+/// cloud in metres as streams. People are found without middleware: whatever stands between
+/// `near` and `far` and in front of the learned background is split into connected blobs, and
+/// each blob's topmost, leftmost and rightmost points stand in for its head and hands.
+use crate::runtime::types::DataType;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Neighbouring readings further apart than this belong to different things
+const EDGE_MM: u16 = 150;
+/// People found per frame, largest first
+const MAX_USERS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthCameraKind {
+    Kinect,
+    RealSense,
+}
+
+impl DepthCameraKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "kinect" | "openni" => Some(Self::Kinect),
+            "realsense" | "intel" => Some(Self::RealSense),
+            _ => None,
+        }
+    }
+
+    /// Field of view in degrees, across and down
+    pub fn field_of_view(&self) -> (f32, f32) {
+        match self {
+            Self::Kinect => (58.5, 45.6),
+            Self::RealSense => (87.0, 58.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DepthFrame {
+    pub width: usize,
+    pub height: usize,
+    /// Millimetres, row by row; 0 means no reading
+    pub depth: Vec<u16>,
+    pub timestamp: Instant,
+}
+
+impl DepthFrame {
+    pub fn new(width: usize, height: usize, depth: Vec<u16>) -> Self {
+        Self { width, height, depth, timestamp: Instant::now() }
+    }
+
+    pub fn at(&self, x: usize, y: usize) -> u16 {
+        self.depth.get(y * self.width + x).copied().unwrap_or(0)
+    }
+}
+
+pub trait DepthSource: Send {
+    /// Frames that arrived since the last call, oldest first
+    fn read_frames(&mut self) -> crate::Result<Vec<DepthFrame>>;
+}
+
+/// One person standing in front of the camera; positions are 0..1 across and down the image
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthUser {
+    pub id: usize,
+    pub center: (f32, f32),
+    /// Metres from the camera, averaged over the blob
+    pub distance: f32,
+    /// Share of the image the person covers
+    pub size: f32,
+    pub head: (f32, f32),
+    pub left_hand: (f32, f32),
+    pub right_hand: (f32, f32),
+}
+
+/// One decimated image or point cloud, ready to write into a stream
+#[derive(Debug, Clone)]
+pub struct DepthStreamFrame {
+    pub name: String,
+    pub data_type: DataType,
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+pub struct DepthCamera {
+    pub kind: DepthCameraKind,
+    /// Readings outside near..far millimetres are ignored
+    pub near: u16,
+    pub far: u16,
+    /// Use every `step`th pixel across and down for textures, clouds and people
+    pub step: usize,
+    /// How much closer than the background a reading must be to count as someone
+    pub background_margin: u16,
+    source: Option<Box<dyn DepthSource>>,
+    frame: Option<DepthFrame>,
+    background: Option<Vec<u16>>,
+    learn_background: bool,
+}
+
+impl DepthCamera {
+    pub fn new(kind: DepthCameraKind) -> Self {
+        Self {
+            kind,
+            near: 500,
+            far: 4000,
+            step: 4,
+            background_margin: 100,
+            source: None,
+            frame: None,
+            background: None,
+            learn_background: false,
+        }
+    }
+
+    pub fn with_source(mut self, source: Box<dyn DepthSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Read whatever the camera sent since last frame; true if there's a new frame
+    pub fn poll(&mut self) -> crate::Result<bool> {
+        let Some(source) = self.source.as_mut() else {
+            return Ok(false);
+        };
+        // Only the newest frame matters; older ones are already out of date
+        match source.read_frames()?.pop() {
+            Some(frame) => {
+                self.feed(frame);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Use `frame` as the latest one, as if the camera had sent it
+    pub fn feed(&mut self, frame: DepthFrame) {
+        if self.learn_background {
+            self.background = Some(frame.depth.clone());
+            self.learn_background = false;
+        }
+        self.frame = Some(frame);
+    }
+
+    pub fn frame(&self) -> Option<&DepthFrame> {
+        self.frame.as_ref()
+    }
+
+    /// Take the next frame as the empty room, so only what's added to it counts as people
+    pub fn learn_background(&mut self) {
+        self.learn_background = true;
+    }
+
+    pub fn forget_background(&mut self) {
+        self.background = None;
+        self.learn_background = false;
+    }
+
+    fn in_range(&self, depth: u16) -> bool {
+        depth != 0 && (self.near..=self.far).contains(&depth)
+    }
+
+    /// Whether the reading at pixel `index` is something in front of the background
+    fn foreground(&self, frame: &DepthFrame, index: usize) -> bool {
+        let depth = frame.depth[index];
+        if !self.in_range(depth) {
+            return false;
+        }
+        match self.background.as_ref().and_then(|background| background.get(index)) {
+            Some(&background) if background != 0 => depth.saturating_add(self.background_margin) < background,
+            _ => true,
+        }
+    }
+
+    fn grid(&self, frame: &DepthFrame) -> (usize, usize, usize) {
+        let step = self.step.max(1);
+        (step, frame.width.div_ceil(step), frame.height.div_ceil(step))
+    }
+
+    /// Metres at (x, y) given as 0..1 across and down, or `None` without a reading
+    pub fn depth_at(&self, x: f64, y: f64) -> Option<f32> {
+        let frame = self.frame.as_ref()?;
+        let column = ((x.clamp(0.0, 1.0) * frame.width as f64) as usize).min(frame.width.saturating_sub(1));
+        let row = ((y.clamp(0.0, 1.0) * frame.height as f64) as usize).min(frame.height.saturating_sub(1));
+        let depth = frame.at(column, row);
+        self.in_range(depth).then_some(depth as f32 / 1000.0)
+    }
+
+    /// Decimated depth as 0..1, 1 at `near` fading to 0 at `far` and wherever there's no reading
+    pub fn texture(&self) -> Option<DepthStreamFrame> {
+        let frame = self.frame.as_ref()?;
+        let (step, width, height) = self.grid(frame);
+        let span = self.far.saturating_sub(self.near).max(1) as f32;
+        let data = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (column * step, row * step)))
+            .map(|(x, y)| {
+                let depth = frame.at(x, y);
+                if self.in_range(depth) { 1.0 - (depth - self.near) as f32 / span } else { 0.0 }
+            })
+            .collect();
+        Some(DepthStreamFrame { name: "depth.texture".to_string(), data_type: DataType::Visual, width, height, data })
+    }
+
+    /// Decimated 1/0 mask of everything in front of the background and within range
+    pub fn mask(&self) -> Option<DepthStreamFrame> {
+        let frame = self.frame.as_ref()?;
+        let (step, width, height) = self.grid(frame);
+        let data = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row * step) * frame.width + column * step))
+            .map(|index| if self.foreground(frame, index) { 1.0 } else { 0.0 })
+            .collect();
+        Some(DepthStreamFrame { name: "depth.mask".to_string(), data_type: DataType::Visual, width, height, data })
+    }
+
+    /// Points in metres with the camera at the origin, x to the right, y up and z away from it
+    pub fn points(&self) -> Vec<[f32; 3]> {
+        let Some(frame) = self.frame.as_ref() else {
+            return Vec::new();
+        };
+        let (step, width, height) = self.grid(frame);
+        let (fov_x, fov_y) = self.kind.field_of_view();
+        let focal_x = frame.width as f32 / 2.0 / (fov_x.to_radians() / 2.0).tan();
+        let focal_y = frame.height as f32 / 2.0 / (fov_y.to_radians() / 2.0).tan();
+        let (center_x, center_y) = (frame.width as f32 / 2.0, frame.height as f32 / 2.0);
+        (0..height)
+            .flat_map(|row| (0..width).map(move |column| (column * step, row * step)))
+            .filter_map(|(x, y)| {
+                let depth = frame.at(x, y);
+                if !self.in_range(depth) {
+                    return None;
+                }
+                let z = depth as f32 / 1000.0;
+                Some([(x as f32 - center_x) * z / focal_x, (center_y - y as f32) * z / focal_y, z])
+            })
+            .collect()
+    }
+
+    /// The point cloud flattened to x, y, z triples
+    pub fn point_stream(&self) -> Option<DepthStreamFrame> {
+        self.frame.as_ref()?;
+        let data: Vec<f32> = self.points().into_iter().flatten().collect();
+        let count = data.len() / 3;
+        Some(DepthStreamFrame { name: "depth.points".to_string(), data_type: DataType::Generic, width: count, height: 1, data })
+    }
+
+    /// People in front of the camera: connected blobs of foreground, largest first
+    pub fn users(&self) -> Vec<DepthUser> {
+        let Some(frame) = self.frame.as_ref() else {
+            return Vec::new();
+        };
+        let (step, width, height) = self.grid(frame);
+        let cells = width * height;
+        let depth_of = |cell: usize| frame.at((cell % width) * step, (cell / width) * step);
+        let foreground: Vec<bool> = (0..cells)
+            .map(|cell| self.foreground(frame, (cell / width) * step * frame.width + (cell % width) * step))
+            .collect();
+        // Anything under 2% of the image is noise or a pet
+        let min_cells = (cells / 50).max(1);
+
+        let mut seen = vec![false; cells];
+        let mut blobs = Vec::new();
+        for start in 0..cells {
+            if seen[start] || !foreground[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut blob = vec![start];
+            let mut index = 0;
+            while index < blob.len() {
+                let cell = blob[index];
+                index += 1;
+                let (column, row) = (cell % width, cell / width);
+                let neighbours = [
+                    (column > 0).then(|| cell - 1),
+                    (column + 1 < width).then(|| cell + 1),
+                    (row > 0).then(|| cell - width),
+                    (row + 1 < height).then(|| cell + width),
+                ];
+                for next in neighbours.into_iter().flatten() {
+                    if !seen[next] && foreground[next] && depth_of(next).abs_diff(depth_of(cell)) <= EDGE_MM {
+                        seen[next] = true;
+                        blob.push(next);
+                    }
+                }
+            }
+            if blob.len() >= min_cells {
+                blobs.push(blob);
+            }
+        }
+        blobs.sort_by_key(|blob| std::cmp::Reverse(blob.len()));
+        blobs.truncate(MAX_USERS);
+
+        let position = |cell: usize| (
+            ((cell % width) as f32 + 0.5) / width as f32,
+            ((cell / width) as f32 + 0.5) / height as f32,
+        );
+        blobs.into_iter().enumerate().map(|(id, blob)| {
+            let count = blob.len() as f32;
+            let (sum_x, sum_y, sum_depth) = blob.iter().fold((0.0, 0.0, 0.0), |(x, y, depth), &cell| {
+                let (cx, cy) = position(cell);
+                (x + cx, y + cy, depth + depth_of(cell) as f32)
+            });
+            let head = blob.iter().copied().min_by_key(|cell| (cell / width, cell % width)).unwrap_or(blob[0]);
+            let left = blob.iter().copied().min_by_key(|cell| (cell % width, cell / width)).unwrap_or(blob[0]);
+            let right = blob.iter().copied().max_by_key(|cell| (cell % width, std::cmp::Reverse(cell / width))).unwrap_or(blob[0]);
+            DepthUser {
+                id,
+                center: (sum_x / count, sum_y / count),
+                distance: sum_depth / count / 1000.0,
+                size: count / cells as f32,
+                head: position(head),
+                left_hand: position(left),
+                right_hand: position(right),
+            }
+        }).collect()
+    }
+
+    /// Everything the interpreter publishes for the latest frame
+    pub fn stream_frames(&self) -> Vec<DepthStreamFrame> {
+        [self.texture(), self.mask(), self.point_stream()].into_iter().flatten().collect()
+    }
+}
+
+#[cfg(feature = "opencv")]
+mod backend {
+    use super::{DepthCameraKind, DepthFrame, DepthSource};
+    use opencv::{core::Mat, prelude::*, videoio::{self, VideoCapture}};
+
+    struct OpenCvDepthSource {
+        capture: VideoCapture,
+        depth_map: i32,
+    }
+
+    impl DepthSource for OpenCvDepthSource {
+        fn read_frames(&mut self) -> crate::Result<Vec<DepthFrame>> {
+            let grabbed = self.capture.grab().map_err(camera_error)?;
+            if !grabbed {
+                return Ok(Vec::new());
+            }
+            let mut depth = Mat::default();
+            if !self.capture.retrieve(&mut depth, self.depth_map).map_err(camera_error)? || depth.empty() {
+                return Ok(Vec::new());
+            }
+            let width = depth.cols() as usize;
+            let height = depth.rows() as usize;
+            let data = depth.data_typed::<u16>().map_err(camera_error)?.to_vec();
+            Ok(vec![DepthFrame::new(width, height, data)])
+        }
+    }
+
+    fn camera_error(error: opencv::Error) -> crate::errors::SynthesisError {
+        crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            format!("📡 Depth camera error: {}", error)
+        )
+    }
+
+    pub fn open_depth_camera(kind: DepthCameraKind, device: i32) -> crate::Result<Box<dyn DepthSource>> {
+        // OpenNI2 drives the Kinect; OpenCV's RealSense backend shares the Intel Perceptual Computing id
+        let (api, depth_map) = match kind {
+            DepthCameraKind::Kinect => (videoio::CAP_OPENNI2, videoio::CAP_OPENNI_DEPTH_MAP),
+            DepthCameraKind::RealSense => (videoio::CAP_INTELPERC, videoio::CAP_INTELPERC_DEPTH_MAP),
+        };
+        let capture = VideoCapture::new(device, api).map_err(camera_error)?;
+        if !capture.is_opened().map_err(camera_error)? {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::AudioDeviceError,
+                format!("📡 Couldn't open {:?} depth camera {}", kind, device)
+            )
+            .with_suggestion("Check the camera is plugged in and OpenCV was built with OpenNI2 or RealSense support"));
+        }
+        Ok(Box::new(OpenCvDepthSource { capture, depth_map }))
+    }
+}
+
+#[cfg(not(feature = "opencv"))]
+mod backend {
+    use super::{DepthCameraKind, DepthSource};
+
+    pub fn open_depth_camera(_kind: DepthCameraKind, _device: i32) -> crate::Result<Box<dyn DepthSource>> {
+        Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            "📡 This build of Synthesis has no depth camera support"
+        )
+        .with_suggestion("Rebuild with --features opencv, with OpenCV built against OpenNI2 or RealSense"))
+    }
+}
+
+pub use backend::open_depth_camera;
+
+static DEPTH_CAMERA: OnceLock<Mutex<Option<DepthCamera>>> = OnceLock::new();
+
+pub fn depth_camera() -> &'static Mutex<Option<DepthCamera>> {
+    DEPTH_CAMERA.get_or_init(|| Mutex::new(None))
+}
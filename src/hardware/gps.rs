@@ -0,0 +1,307 @@
+/// GPS receivers for sound walks and installations on the move
+///
+/// Positions come from gpsd over its JSON protocol (localhost:2947 by default), or straight
+/// from a receiver on a serial port speaking NMEA 0183. Either way a reader thread hands over
+/// whole lines, and each frame the newest fix is published as `gps.latitude`, `gps.longitude`,
+/// `gps.speed` (metres per second), `gps.heading` (degrees from north) and `gps.altitude`
+/// (metres) control streams. Nothing is published until the receiver has a fix.
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// The values a fix publishes, each as a `gps.<field>` control stream
+pub const GPS_FIELDS: [&str; 5] = ["latitude", "longitude", "speed", "heading", "altitude"];
+pub const GPSD_ADDRESS: &str = "127.0.0.1:2947";
+const KNOTS_TO_METRES_PER_SECOND: f64 = 0.514_444;
+/// Mean radius for distances; good to about half a percent anywhere on Earth
+const EARTH_RADIUS_METRES: f64 = 6_371_000.0;
+
+pub fn gps_stream_name(field: &str) -> String {
+    format!("gps.{}", field)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpsFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+    pub speed: f64,
+    pub heading: f64,
+    /// Satellites used for the fix, when the receiver says
+    pub satellites: Option<u32>,
+}
+
+impl GpsFix {
+    pub fn value(&self, field: &str) -> Option<f64> {
+        match field {
+            "latitude" => Some(self.latitude),
+            "longitude" => Some(self.longitude),
+            "speed" => Some(self.speed),
+            "heading" => Some(self.heading),
+            "altitude" => Some(self.altitude),
+            _ => None,
+        }
+    }
+}
+
+/// `4916.45` with `N` as 49.274 degrees: NMEA writes whole degrees then minutes
+fn nmea_coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let value: f64 = value.parse().ok()?;
+    let degrees = (value / 100.0).trunc() + (value % 100.0) / 60.0;
+    match hemisphere {
+        "N" | "E" => Some(degrees),
+        "S" | "W" => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// The fields of a sentence whose checksum matches, without the `$` and checksum
+fn nmea_fields(line: &str) -> Option<Vec<&str>> {
+    let body = line.trim().strip_prefix('$')?;
+    let body = match body.split_once('*') {
+        Some((body, checksum)) => {
+            let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+            (body.bytes().fold(0, |sum, byte| sum ^ byte) == expected).then_some(body)?
+        }
+        None => body,
+    };
+    Some(body.split(',').collect())
+}
+
+#[derive(Debug, Default)]
+pub struct GpsState {
+    fix: Option<GpsFix>,
+    updated: Option<Instant>,
+    lines: Option<Receiver<String>>,
+    /// Where positions come from, for messages
+    pub source: Option<String>,
+}
+
+impl GpsState {
+    /// Take positions from `lines` from now on, forgetting any earlier receiver
+    pub fn open(&mut self, lines: Receiver<String>, source: String) {
+        self.lines = Some(lines);
+        self.source = Some(source);
+    }
+
+    /// Stop reading; the reader thread notices the next time it has a line to hand over
+    pub fn close(&mut self) -> bool {
+        self.fix = None;
+        self.updated = None;
+        self.lines = None;
+        self.source.take().is_some()
+    }
+
+    pub fn fix(&self) -> Option<&GpsFix> {
+        self.fix.as_ref()
+    }
+
+    /// How long ago the fix last changed
+    pub fn age(&self) -> Option<Duration> {
+        self.updated.map(|updated| updated.elapsed())
+    }
+
+    /// One line from gpsd or an NMEA receiver; true if it updated the fix
+    pub fn handle_line(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let updated = if line.starts_with('{') {
+            self.handle_gpsd(line)
+        } else {
+            self.handle_nmea(line)
+        };
+        if updated {
+            self.updated = Some(Instant::now());
+        }
+        updated
+    }
+
+    fn handle_nmea(&mut self, line: &str) -> bool {
+        let Some(fields) = nmea_fields(line) else {
+            return false;
+        };
+        let field = |index: usize| fields.get(index).copied().unwrap_or("");
+        // The talker (GP, GN, GL...) doesn't matter, only the sentence type after it
+        let kind = field(0).get(2..).unwrap_or("");
+        let position = |at: usize| Some((nmea_coordinate(field(at), field(at + 1))?, nmea_coordinate(field(at + 2), field(at + 3))?));
+        match kind {
+            // Recommended minimum: position, speed over ground and course, if the status is valid
+            "RMC" if field(2) == "A" => {
+                let Some((latitude, longitude)) = position(3) else {
+                    return false;
+                };
+                let fix = self.fix.get_or_insert_with(GpsFix::default);
+                fix.latitude = latitude;
+                fix.longitude = longitude;
+                fix.speed = field(7).parse::<f64>().unwrap_or(0.0) * KNOTS_TO_METRES_PER_SECOND;
+                // Receivers leave the course empty when standing still, so keep the last one
+                if let Ok(heading) = field(8).parse() {
+                    fix.heading = heading;
+                }
+                true
+            }
+            // Fix data: position, satellites and altitude, if the quality says there's a fix
+            "GGA" if field(6).parse::<u32>().is_ok_and(|quality| quality > 0) => {
+                let Some((latitude, longitude)) = position(2) else {
+                    return false;
+                };
+                let fix = self.fix.get_or_insert_with(GpsFix::default);
+                fix.latitude = latitude;
+                fix.longitude = longitude;
+                fix.satellites = field(7).parse().ok();
+                if let Ok(altitude) = field(9).parse() {
+                    fix.altitude = altitude;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_gpsd(&mut self, line: &str) -> bool {
+        let Ok(report) = serde_json::from_str::<serde_json::Value>(line) else {
+            return false;
+        };
+        let number = |name: &str| report.get(name).and_then(|v| v.as_f64());
+        match report.get("class").and_then(|v| v.as_str()) {
+            // Time-position-velocity; mode 2 is a 2D fix and 3 a 3D one
+            Some("TPV") if number("mode").is_some_and(|mode| mode >= 2.0) => {
+                let (Some(latitude), Some(longitude)) = (number("lat"), number("lon")) else {
+                    return false;
+                };
+                let fix = self.fix.get_or_insert_with(GpsFix::default);
+                fix.latitude = latitude;
+                fix.longitude = longitude;
+                if let Some(altitude) = number("altMSL").or_else(|| number("alt")) {
+                    fix.altitude = altitude;
+                }
+                if let Some(speed) = number("speed") {
+                    fix.speed = speed;
+                }
+                if let Some(heading) = number("track") {
+                    fix.heading = heading;
+                }
+                true
+            }
+            Some("SKY") => {
+                let used = number("uSat").map(|used| used as u32).or_else(|| {
+                    let satellites = report.get("satellites")?.as_array()?;
+                    Some(satellites.iter().filter(|s| s.get("used").and_then(|v| v.as_bool()) == Some(true)).count() as u32)
+                });
+                if let (Some(fix), Some(used)) = (self.fix.as_mut(), used) {
+                    fix.satellites = Some(used);
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Read what the receiver sent since the last frame; every field as (stream name, value)
+    /// once there's a fix
+    pub fn poll(&mut self) -> Vec<(String, f64)> {
+        let mut received = Vec::new();
+        if let Some(lines) = &self.lines {
+            loop {
+                match lines.try_recv() {
+                    Ok(line) => received.push(line),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        tracing::warn!(target: "hardware", source = self.source.as_deref().unwrap_or(""), "🛰️ GPS connection closed");
+                        self.lines = None;
+                        break;
+                    }
+                }
+            }
+        }
+        for line in received {
+            self.handle_line(&line);
+        }
+        let Some(fix) = &self.fix else {
+            return Vec::new();
+        };
+        GPS_FIELDS.iter()
+            .filter_map(|field| fix.value(field).map(|value| (gps_stream_name(field), value)))
+            .collect()
+    }
+}
+
+/// Great-circle distance in metres between two (latitude, longitude) points
+pub fn distance_metres(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.1 - from.1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METRES * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+/// Compass bearing in degrees from north to head from `from` towards `to`
+pub fn bearing_degrees(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lon = (to.1 - from.1).to_radians();
+    let y = d_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Hand over `reader`'s lines from a thread of its own until it closes or nobody's listening
+fn spawn_line_reader(name: &str, mut reader: impl Read + Send + 'static) -> crate::Result<Receiver<String>> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::Builder::new()
+        .name(format!("synthesis-gps-{}", name))
+        .spawn(move || {
+            let mut pending = Vec::new();
+            let mut buffer = [0u8; 1024];
+            loop {
+                let read = match reader.read(&mut buffer) {
+                    Ok(0) => return,
+                    Ok(read) => read,
+                    Err(error) if matches!(error.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted) => continue,
+                    Err(error) => {
+                        tracing::warn!(target: "hardware", %error, "🛰️ GPS read failed");
+                        return;
+                    }
+                };
+                pending.extend_from_slice(&buffer[..read]);
+                while let Some(end) = pending.iter().position(|&byte| byte == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    if sender.send(String::from_utf8_lossy(&line).trim().to_string()).is_err() {
+                        return;
+                    }
+                }
+            }
+        })?;
+    Ok(receiver)
+}
+
+/// Ask gpsd at `address` to stream position reports
+pub fn connect_gpsd(address: &str) -> crate::Result<Receiver<String>> {
+    let mut stream = TcpStream::connect(address).map_err(|error| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::StreamConnectionError,
+        format!("🛰️ Couldn't reach gpsd at {}: {}", address, error)
+    )
+    .with_suggestion("Check gpsd is running (systemctl status gpsd) and the receiver is plugged in")
+    .with_suggestion("Or read the receiver directly: GPS.open(\"/dev/ttyUSB0\")"))?;
+    stream.write_all(b"?WATCH={\"enable\":true,\"json\":true};\n")?;
+    spawn_line_reader("gpsd", stream)
+}
+
+/// Read NMEA sentences straight from a receiver on a serial port
+pub fn open_nmea_serial(path: &str, baud_rate: u32) -> crate::Result<Receiver<String>> {
+    let port = serialport::new(path, baud_rate)
+        .timeout(Duration::from_millis(500))
+        .open()
+        .map_err(|error| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::StreamConnectionError,
+            format!("🛰️ Couldn't open the GPS receiver on {}: {}", path, error)
+        )
+        .with_suggestion("Check the port name; most receivers talk at 4800 or 9600 baud: GPS.open(\"/dev/ttyUSB0\", baud: 9600)"))?;
+    spawn_line_reader("serial", port)
+}
+
+static GPS: OnceLock<Mutex<GpsState>> = OnceLock::new();
+
+pub fn gps() -> &'static Mutex<GpsState> {
+    GPS.get_or_init(|| Mutex::new(GpsState::default()))
+}
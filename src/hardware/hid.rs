@@ -0,0 +1,348 @@
+/// Raw HID input: faderboxes, foot pedals and homemade controllers that don't speak MIDI
+///
+/// A device is opened with a small mapping description, one string per field saying where
+/// in the input report the value sits: `"u8@1"` is the second byte, `"u16@2 0..1023"` a
+/// little-endian 10-bit reading in bytes 2-3, `"bit@4.0"` the lowest bit of byte 4. Offsets
+/// count from the start of the report as the device sends it, report ID byte included.
+/// Every field comes out scaled to 0..1 (signed ones to -1..1) and is published each frame
+/// as a control stream named `device.field`.
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Reports read from one device per poll, so a chatty device can't stall the frame
+const MAX_REPORTS_PER_POLL: usize = 64;
+/// Longest input report read; full-speed USB HID tops out at 64 bytes
+const MAX_REPORT_BYTES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HidFieldKind {
+    /// One bit of a byte, 0 or 1
+    Bit(u8),
+    U8,
+    I8,
+    U16 { big_endian: bool },
+    I16 { big_endian: bool },
+}
+
+impl HidFieldKind {
+    fn bytes(&self) -> usize {
+        match self {
+            Self::Bit(_) | Self::U8 | Self::I8 => 1,
+            Self::U16 { .. } | Self::I16 { .. } => 2,
+        }
+    }
+
+    /// Raw value and the full scale it's measured against
+    fn raw(&self, bytes: &[u8]) -> (f64, f64) {
+        let word = |big_endian: bool| if big_endian {
+            u16::from_be_bytes([bytes[0], bytes[1]])
+        } else {
+            u16::from_le_bytes([bytes[0], bytes[1]])
+        };
+        match *self {
+            Self::Bit(bit) => (((bytes[0] >> bit) & 1) as f64, 1.0),
+            Self::U8 => (bytes[0] as f64, u8::MAX as f64),
+            Self::I8 => (bytes[0] as i8 as f64, i8::MAX as f64),
+            Self::U16 { big_endian } => (word(big_endian) as f64, u16::MAX as f64),
+            Self::I16 { big_endian } => (word(big_endian) as i16 as f64, i16::MAX as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HidField {
+    pub name: String,
+    /// Byte offset into the report
+    pub byte: usize,
+    pub kind: HidFieldKind,
+    /// Raw readings that map to 0 and 1, for sensors that don't use their full width
+    pub range: Option<(f64, f64)>,
+}
+
+fn field_error(name: &str, description: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("🕹️ Can't read the HID field {}: \"{}\"", name, description)
+    )
+    .with_suggestion("Fields look like \"u8@1\", \"u16@2 0..1023\", \"i16be@3\" or \"bit@4.0\"")
+}
+
+impl HidField {
+    /// `type@byte[.bit] [min..max]`, with types bit, u8, i8, u16, u16be, i16 and i16be
+    pub fn parse(name: &str, description: &str) -> crate::Result<Self> {
+        let mut parts = description.split_whitespace();
+        let (kind, offset) = parts.next()
+            .and_then(|spec| spec.split_once('@'))
+            .ok_or_else(|| field_error(name, description))?;
+        let (byte, bit) = match offset.split_once('.') {
+            Some((byte, bit)) => (byte, Some(bit.parse::<u8>().ok().filter(|bit| *bit < 8).ok_or_else(|| field_error(name, description))?)),
+            None => (offset, None),
+        };
+        let byte = byte.parse::<usize>().ok()
+            .filter(|byte| *byte < MAX_REPORT_BYTES)
+            .ok_or_else(|| field_error(name, description))?;
+        let kind = match (kind, bit) {
+            ("bit", Some(bit)) => HidFieldKind::Bit(bit),
+            ("u8", None) => HidFieldKind::U8,
+            ("i8", None) => HidFieldKind::I8,
+            ("u16", None) | ("u16le", None) => HidFieldKind::U16 { big_endian: false },
+            ("u16be", None) => HidFieldKind::U16 { big_endian: true },
+            ("i16", None) | ("i16le", None) => HidFieldKind::I16 { big_endian: false },
+            ("i16be", None) => HidFieldKind::I16 { big_endian: true },
+            _ => return Err(field_error(name, description)),
+        };
+        let range = match parts.next() {
+            Some(range) => {
+                let (min, max) = range.split_once("..").ok_or_else(|| field_error(name, description))?;
+                let (min, max) = min.parse::<f64>().ok().zip(max.parse::<f64>().ok())
+                    .filter(|(min, max)| min != max)
+                    .ok_or_else(|| field_error(name, description))?;
+                Some((min, max))
+            }
+            None => None,
+        };
+        if parts.next().is_some() {
+            return Err(field_error(name, description));
+        }
+        Ok(Self { name: name.to_string(), byte, kind, range })
+    }
+
+    /// The field's value in a report, or None if the report is too short to hold it
+    pub fn read(&self, report: &[u8]) -> Option<f64> {
+        let bytes = report.get(self.byte..self.byte + self.kind.bytes())?;
+        let (raw, full_scale) = self.kind.raw(bytes);
+        Some(match self.range {
+            Some((min, max)) => ((raw - min) / (max - min)).clamp(0.0, 1.0),
+            None => (raw / full_scale).clamp(-1.0, 1.0),
+        })
+    }
+}
+
+/// Where reports come from; the hidapi backend implements this, and so can tests
+pub trait HidSource: Send {
+    /// Input reports that arrived since the last call, without blocking
+    fn read_reports(&mut self) -> crate::Result<Vec<Vec<u8>>>;
+}
+
+pub struct HidDevice {
+    pub name: String,
+    pub fields: Vec<HidField>,
+    /// Only reports starting with this ID are decoded, for devices that send several kinds
+    pub report_id: Option<u8>,
+    pub reports: u64,
+    values: HashMap<String, f64>,
+    source: Option<Box<dyn HidSource>>,
+}
+
+impl HidDevice {
+    pub fn new(name: &str, fields: Vec<HidField>, report_id: Option<u8>) -> Self {
+        Self { name: name.to_string(), fields, report_id, reports: 0, values: HashMap::new(), source: None }
+    }
+
+    pub fn with_source(mut self, source: Box<dyn HidSource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn connected(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Decode one input report; false if it carried another report ID
+    pub fn feed(&mut self, report: &[u8]) -> bool {
+        if self.report_id.is_some_and(|id| report.first() != Some(&id)) {
+            return false;
+        }
+        self.reports += 1;
+        for field in &self.fields {
+            if let Some(value) = field.read(report) {
+                self.values.insert(field.name.clone(), value);
+            }
+        }
+        true
+    }
+
+    pub fn value(&self, field: &str) -> Option<f64> {
+        self.values.get(field).copied()
+    }
+
+    fn poll(&mut self) {
+        let Some(source) = self.source.as_mut() else {
+            return;
+        };
+        match source.read_reports() {
+            Ok(reports) => {
+                for report in reports.iter().take(MAX_REPORTS_PER_POLL) {
+                    self.feed(report);
+                }
+            }
+            Err(error) => {
+                // Unplugged, most likely; the fields keep their last values
+                tracing::warn!(target: "hardware", device = self.name.as_str(), %error, "🕹️ HID device stopped reporting");
+                self.source = None;
+            }
+        }
+    }
+}
+
+/// Stream name a field is published under
+pub fn hid_stream_name(device: &str, field: &str) -> String {
+    format!("{}.{}", device, field)
+}
+
+#[derive(Default)]
+pub struct HidInput {
+    devices: Vec<HidDevice>,
+}
+
+impl HidInput {
+    pub fn add(&mut self, device: HidDevice) -> crate::Result<()> {
+        if self.device(&device.name).is_some() {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InputDeviceError,
+                format!("🕹️ A HID device called '{}' is already open", device.name)
+            )
+            .with_suggestion("Give it another name with name:, or Hid.close() the first one"));
+        }
+        self.devices.push(device);
+        Ok(())
+    }
+
+    pub fn close(&mut self, name: &str) -> bool {
+        let before = self.devices.len();
+        self.devices.retain(|device| device.name != name);
+        self.devices.len() != before
+    }
+
+    pub fn device(&self, name: &str) -> Option<&HidDevice> {
+        self.devices.iter().find(|device| device.name == name)
+    }
+
+    pub fn device_mut(&mut self, name: &str) -> Option<&mut HidDevice> {
+        self.devices.iter_mut().find(|device| device.name == name)
+    }
+
+    pub fn devices(&self) -> &[HidDevice] {
+        &self.devices
+    }
+
+    /// Once per frame: read what every device sent, and return each field's stream name and value
+    pub fn poll(&mut self) -> Vec<(String, f64)> {
+        let mut values = Vec::new();
+        for device in &mut self.devices {
+            device.poll();
+            for field in &device.fields {
+                if let Some(value) = device.value(&field.name) {
+                    values.push((hid_stream_name(&device.name, &field.name), value));
+                }
+            }
+        }
+        values
+    }
+}
+
+static HID_INPUT: OnceLock<Mutex<HidInput>> = OnceLock::new();
+
+pub fn hid_input() -> &'static Mutex<HidInput> {
+    HID_INPUT.get_or_init(|| Mutex::new(HidInput::default()))
+}
+
+/// One device the system can see
+#[derive(Debug, Clone, PartialEq)]
+pub struct HidDeviceInfo {
+    pub product: String,
+    pub manufacturer: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub usage_page: u16,
+    pub usage: u16,
+}
+
+impl HidDeviceInfo {
+    /// A product name fragment ("pedal") or a "vid:pid" pair in hex ("0c45:7403")
+    pub fn matches(&self, query: &str) -> bool {
+        if let Some((vendor, product)) = query.split_once(':') {
+            if let (Ok(vendor), Ok(product)) = (u16::from_str_radix(vendor, 16), u16::from_str_radix(product, 16)) {
+                return self.vendor_id == vendor && self.product_id == product;
+            }
+        }
+        self.product.to_lowercase().contains(&query.to_lowercase())
+    }
+}
+
+#[cfg(feature = "hidapi")]
+mod backend {
+    use super::{HidDeviceInfo, HidSource, MAX_REPORTS_PER_POLL, MAX_REPORT_BYTES};
+
+    struct HidapiSource {
+        device: hidapi::HidDevice,
+    }
+
+    impl HidSource for HidapiSource {
+        fn read_reports(&mut self) -> crate::Result<Vec<Vec<u8>>> {
+            let mut reports = Vec::new();
+            let mut buffer = [0u8; MAX_REPORT_BYTES];
+            while reports.len() < MAX_REPORTS_PER_POLL {
+                let read = self.device.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                reports.push(buffer[..read].to_vec());
+            }
+            Ok(reports)
+        }
+    }
+
+    fn info(device: &hidapi::DeviceInfo) -> HidDeviceInfo {
+        HidDeviceInfo {
+            product: device.product_string().unwrap_or_default().to_string(),
+            manufacturer: device.manufacturer_string().unwrap_or_default().to_string(),
+            vendor_id: device.vendor_id(),
+            product_id: device.product_id(),
+            usage_page: device.usage_page(),
+            usage: device.usage(),
+        }
+    }
+
+    pub fn list_devices() -> crate::Result<Vec<HidDeviceInfo>> {
+        let api = hidapi::HidApi::new()?;
+        Ok(api.device_list().map(info).collect())
+    }
+
+    pub fn open_device(query: &str) -> crate::Result<(HidDeviceInfo, Box<dyn HidSource>)> {
+        let api = hidapi::HidApi::new()?;
+        let found = api.device_list().find(|device| info(device).matches(query)).ok_or_else(|| {
+            crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InputDeviceError,
+                format!("🕹️ No HID device matches '{}'", query)
+            )
+            .with_suggestion("Hid.devices() lists what's plugged in; match by product name or \"vid:pid\"")
+        })?;
+        let device = found.open_device(&api)?;
+        device.set_blocking_mode(false)?;
+        Ok((info(found), Box::new(HidapiSource { device })))
+    }
+}
+
+#[cfg(not(feature = "hidapi"))]
+mod backend {
+    use super::{HidDeviceInfo, HidSource};
+
+    fn unsupported() -> crate::errors::SynthesisError {
+        crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InputDeviceError,
+            "🕹️ This build of Synthesis has no HID support"
+        )
+        .with_suggestion("Rebuild with --features hidapi")
+    }
+
+    pub fn list_devices() -> crate::Result<Vec<HidDeviceInfo>> {
+        Err(unsupported())
+    }
+
+    pub fn open_device(_query: &str) -> crate::Result<(HidDeviceInfo, Box<dyn HidSource>)> {
+        Err(unsupported())
+    }
+}
+
+pub use backend::{list_devices, open_device};
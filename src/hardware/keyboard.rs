@@ -0,0 +1,278 @@
+/// Keyboard input: held keys, per-frame key events, and global hotkeys
+///
+/// The window feeds key events in as they arrive; the interpreter calls `begin_frame` so a
+/// script sees every event from between the previous frame and this one exactly once.
+/// Default hotkeys (space, F, Esc, Ctrl+Z) can be rebound per project with `config { keys: { ... } }`.
+use crate::runtime::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+const MODIFIERS: [&str; 4] = ["control", "shift", "alt", "super"];
+
+/// Canonical key names: lowercase, with the usual aliases folded together
+pub fn normalize_key(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.as_str() {
+        " " => "space",
+        "esc" => "escape",
+        "return" => "enter",
+        "ctrl" => "control",
+        "option" => "alt",
+        "cmd" | "command" | "meta" | "win" => "super",
+        "up" => "arrowup",
+        "down" => "arrowdown",
+        "left" => "arrowleft",
+        "right" => "arrowright",
+        _ => return name,
+    }
+    .to_string()
+}
+
+/// A key plus the modifiers held with it, written like "ctrl+shift+s"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub modifiers: BTreeSet<String>,
+    pub key: String,
+}
+
+impl KeyCombo {
+    pub fn parse(text: &str) -> crate::Result<Self> {
+        let parts: Vec<String> = text.split('+').map(normalize_key).collect();
+        match parts.split_last() {
+            Some((key, modifiers)) if !key.is_empty() => {
+                if let Some(unknown) = modifiers.iter().find(|m| !MODIFIERS.contains(&m.as_str())) {
+                    return Err(crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::InvalidExpression,
+                        format!("⌨️ '{}' in \"{}\" isn't a modifier key", unknown, text)
+                    )
+                    .with_suggestion("Modifiers are ctrl, shift, alt and cmd, like \"ctrl+shift+s\""));
+                }
+                Ok(Self { modifiers: modifiers.iter().cloned().collect(), key: key.clone() })
+            }
+            _ => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("⌨️ \"{}\" doesn't name a key", text)
+            )
+            .with_suggestion("Try a key name like \"space\", \"f\" or \"ctrl+s\"")),
+        }
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            let short = if modifier == "control" { "ctrl" } else { modifier.as_str() };
+            write!(f, "{}+", short)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// What a global hotkey does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Start or stop the transport
+    Transport,
+    Fullscreen,
+    /// Silence all audio straight away
+    Panic,
+    /// Take back the latest parameter change
+    Undo,
+    Redo,
+}
+
+impl KeyAction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "transport" => Some(Self::Transport),
+            "fullscreen" => Some(Self::Fullscreen),
+            "panic" => Some(Self::Panic),
+            "undo" => Some(Self::Undo),
+            "redo" => Some(Self::Redo),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Transport => "transport",
+            Self::Fullscreen => "fullscreen",
+            Self::Panic => "panic",
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyEvent {
+    pub key: String,
+    /// The key with whatever modifiers were held, like "ctrl+s"
+    pub combo: String,
+    pub pressed: bool,
+    pub repeat: bool,
+    pub time: Instant,
+}
+
+#[derive(Debug)]
+pub struct KeyboardState {
+    held: HashSet<String>,
+    incoming: Vec<KeyEvent>,
+    frame_events: Vec<KeyEvent>,
+    bindings: HashMap<KeyCombo, KeyAction>,
+    actions: Vec<KeyAction>,
+    pub fullscreen: bool,
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        let mut state = Self {
+            held: HashSet::new(),
+            incoming: Vec::new(),
+            frame_events: Vec::new(),
+            bindings: HashMap::new(),
+            actions: Vec::new(),
+            fullscreen: false,
+        };
+        state.reset_bindings();
+        state
+    }
+}
+
+impl KeyboardState {
+    pub fn reset_bindings(&mut self) {
+        self.bindings.clear();
+        let defaults = [
+            ("space", KeyAction::Transport),
+            ("f", KeyAction::Fullscreen),
+            ("escape", KeyAction::Panic),
+            ("ctrl+z", KeyAction::Undo),
+            ("ctrl+shift+z", KeyAction::Redo),
+        ];
+        for (combo, action) in defaults {
+            if let Ok(combo) = KeyCombo::parse(combo) {
+                self.bindings.insert(combo, action);
+            }
+        }
+    }
+
+    /// Bind a combo to an action, or unbind it with `None`
+    pub fn bind(&mut self, combo: KeyCombo, action: Option<KeyAction>) {
+        match action {
+            Some(action) => self.bindings.insert(combo, action),
+            None => self.bindings.remove(&combo),
+        };
+    }
+
+    pub fn bindings(&self) -> &HashMap<KeyCombo, KeyAction> {
+        &self.bindings
+    }
+
+    /// Apply `config { keys: { space: "transport", escape: "none" } }`
+    pub fn apply_config(&mut self, keys: &Value) -> crate::Result<()> {
+        let Value::Object(entries) = keys else {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                "⌨️ config keys should map keys to actions"
+            )
+            .with_suggestion("Try: config { keys: { space: \"transport\", escape: \"none\" } }"));
+        };
+        for (combo, action) in entries {
+            let combo = KeyCombo::parse(combo)?;
+            let action = match action {
+                Value::String(name) if name == "none" => None,
+                Value::String(name) => Some(KeyAction::from_name(name).ok_or_else(|| unknown_action(name))?),
+                Value::Null => None,
+                other => return Err(unknown_action(&other.to_string())),
+            };
+            self.bind(combo, action);
+        }
+        Ok(())
+    }
+
+    fn combo_for(&self, key: &str) -> KeyCombo {
+        KeyCombo {
+            modifiers: MODIFIERS.iter()
+                .filter(|m| **m != key && self.held.contains(**m))
+                .map(|m| m.to_string())
+                .collect(),
+            key: key.to_string(),
+        }
+    }
+
+    /// A key went down or up; `repeat` is the OS auto-repeat while a key is held
+    pub fn handle_key(&mut self, key: &str, pressed: bool, repeat: bool) {
+        let key = normalize_key(key);
+        let combo = self.combo_for(&key);
+        if pressed {
+            self.held.insert(key.clone());
+            if !repeat {
+                if let Some(action) = self.bindings.get(&combo) {
+                    self.actions.push(*action);
+                }
+            }
+        } else {
+            self.held.remove(&key);
+        }
+        self.incoming.push(KeyEvent { key, combo: combo.to_string(), pressed, repeat, time: Instant::now() });
+    }
+
+    /// The window lost focus, so no key-up events will arrive for what's held
+    pub fn release_all(&mut self) {
+        let held: Vec<String> = self.held.iter().cloned().collect();
+        for key in held {
+            self.handle_key(&key, false, false);
+        }
+    }
+
+    /// Move queued events into this frame and hand back any hotkeys that fired
+    pub fn begin_frame(&mut self) -> Vec<KeyAction> {
+        self.frame_events = std::mem::take(&mut self.incoming);
+        let actions = std::mem::take(&mut self.actions);
+        if actions.contains(&KeyAction::Fullscreen) {
+            self.fullscreen = !self.fullscreen;
+        }
+        actions
+    }
+
+    pub fn is_down(&self, combo: &KeyCombo) -> bool {
+        self.held.contains(&combo.key) && combo.modifiers.iter().all(|m| self.held.contains(m))
+    }
+
+    pub fn frame_events(&self) -> &[KeyEvent] {
+        &self.frame_events
+    }
+
+    pub fn handle_window_event(&mut self, event: &winit::event::WindowEvent) {
+        use winit::event::{ElementState, WindowEvent};
+        use winit::keyboard::Key;
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let name = match &event.logical_key {
+                    Key::Named(named) => format!("{:?}", named),
+                    Key::Character(text) => text.to_string(),
+                    _ => return,
+                };
+                self.handle_key(&name, event.state == ElementState::Pressed, event.repeat);
+            }
+            WindowEvent::Focused(false) => self.release_all(),
+            _ => {}
+        }
+    }
+}
+
+fn unknown_action(name: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("⌨️ '{}' isn't a key action", name)
+    )
+    .with_suggestion("Actions are \"transport\", \"fullscreen\", \"panic\", \"undo\", \"redo\" or \"none\"")
+}
+
+static KEYBOARD: OnceLock<Mutex<KeyboardState>> = OnceLock::new();
+
+pub fn keyboard() -> &'static Mutex<KeyboardState> {
+    KEYBOARD.get_or_init(|| Mutex::new(KeyboardState::default()))
+}
@@ -2,8 +2,22 @@ pub mod controllers;
 pub mod webcam;
 pub mod sensors;
 pub mod osc;
+pub mod keyboard;
+pub mod hid;
+pub mod tablet;
+pub mod touch;
+pub mod depth;
+pub mod screen;
+pub mod gps;
 
 pub use controllers::*;
 pub use webcam::*;
 pub use sensors::*;
-pub use osc::*;
\ No newline at end of file
+pub use osc::*;
+pub use keyboard::*;
+pub use hid::*;
+pub use tablet::*;
+pub use touch::*;
+pub use depth::*;
+pub use screen::*;
+pub use gps::*;
\ No newline at end of file
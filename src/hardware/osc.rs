@@ -33,9 +33,9 @@ impl From<OscType> for OscValue {
     }
 }
 
-impl Into<OscType> for OscValue {
-    fn into(self) -> OscType {
-        match self {
+impl From<OscValue> for OscType {
+    fn from(val: OscValue) -> Self {
+        match val {
             OscValue::Float(f) => OscType::Float(f),
             OscValue::Int(i) => OscType::Int(i),
             OscValue::String(s) => OscType::String(s),
@@ -45,10 +45,12 @@ impl Into<OscType> for OscValue {
     }
 }
 
+type AddressHandler = Box<dyn Fn(&OscMessage) + Send>;
+
 pub struct OscServer {
     socket: Option<UdpSocket>,
     parameters: Arc<Mutex<HashMap<String, OscParameter>>>,
-    address_patterns: HashMap<String, Box<dyn Fn(&OscMessage) + Send>>,
+    address_patterns: HashMap<String, AddressHandler>,
     is_running: bool,
 }
 
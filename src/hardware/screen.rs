@@ -0,0 +1,243 @@
+/// Screen and window capture: another application's output as a texture stream
+///
+/// Grabbing a display can take longer than a frame, so every capture runs on its own thread and the
+/// interpreter publishes whatever it grabbed most recently, scaled down to the capture's `max_width`.
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Captures are scaled down to this width unless a script asks for another
+pub const DEFAULT_CAPTURE_WIDTH: usize = 640;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureTarget {
+    /// A whole display, 0 being the main one
+    Display(usize),
+    /// The first window whose title contains this text, ignoring case
+    Window(String),
+}
+
+impl CaptureTarget {
+    /// `screen.0`, or `window.` and the title in lowercase with anything but letters and digits as `_`
+    pub fn stream_name(&self) -> String {
+        match self {
+            CaptureTarget::Display(index) => format!("screen.{}", index),
+            CaptureTarget::Window(title) => {
+                let slug: String = title
+                    .to_lowercase()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect();
+                format!("window.{}", slug)
+            }
+        }
+    }
+}
+
+/// One grab as RGBA bytes, rows top to bottom
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenFrame {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+impl ScreenFrame {
+    /// RGB from 0 to 1, keeping every nth pixel so the result is at most `max_width` across
+    pub fn downscale(&self, max_width: usize) -> (usize, usize, Vec<f32>) {
+        let step = self.width.div_ceil(max_width.max(1)).max(1);
+        let (width, height) = (self.width.div_ceil(step), self.height.div_ceil(step));
+        let mut data = Vec::with_capacity(width * height * 3);
+        for row in (0..self.height).step_by(step) {
+            for column in (0..self.width).step_by(step) {
+                let index = (row * self.width + column) * 4;
+                let pixel = self.rgba.get(index..index + 3).unwrap_or(&[0, 0, 0]);
+                data.extend(pixel.iter().map(|&channel| channel as f32 / 255.0));
+            }
+        }
+        (width, height, data)
+    }
+}
+
+/// Where grabs come from; the xcap backend implements this, and so can tests
+pub trait ScreenSource: Send {
+    fn grab(&mut self) -> crate::Result<ScreenFrame>;
+}
+
+/// A grab ready for a Visual stream: interleaved RGB, `width` × `height` pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenTexture {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+pub struct ScreenCapture {
+    pub target: CaptureTarget,
+    pub max_width: usize,
+    latest: Arc<Mutex<Option<ScreenFrame>>>,
+    running: Arc<AtomicBool>,
+    /// Microseconds between grabs, shared with the capture thread
+    interval: Arc<AtomicU64>,
+}
+
+fn interval_micros(fps: f64) -> u64 {
+    (1_000_000.0 / fps.max(0.1)) as u64
+}
+
+impl ScreenCapture {
+    /// A capture that only holds what `feed` gives it, for tests and replays
+    pub fn new(target: CaptureTarget) -> Self {
+        Self {
+            target,
+            max_width: DEFAULT_CAPTURE_WIDTH,
+            latest: Arc::new(Mutex::new(None)),
+            running: Arc::new(AtomicBool::new(false)),
+            interval: Arc::new(AtomicU64::new(interval_micros(30.0))),
+        }
+    }
+
+    /// Grab from `source` `fps` times a second on a background thread, until the capture is dropped
+    pub fn start(target: CaptureTarget, mut source: Box<dyn ScreenSource>, fps: f64) -> crate::Result<Self> {
+        let capture = Self::new(target);
+        capture.set_fps(fps);
+        capture.running.store(true, Ordering::Relaxed);
+
+        let (latest, running, interval) = (capture.latest.clone(), capture.running.clone(), capture.interval.clone());
+        let name = capture.target.stream_name();
+        std::thread::Builder::new()
+            .name(format!("capture {}", name))
+            .spawn(move || {
+                let mut failing = false;
+                while running.load(Ordering::Relaxed) {
+                    let started = Instant::now();
+                    match source.grab() {
+                        Ok(frame) => {
+                            *latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(frame);
+                            failing = false;
+                        }
+                        // Warn once per run of failures; a minimised window fails until it's restored
+                        Err(error) => {
+                            if !failing {
+                                tracing::warn!(target: "hardware", capture = name.as_str(), %error, "🖥️ screen capture failed");
+                            }
+                            failing = true;
+                        }
+                    }
+                    let interval = Duration::from_micros(interval.load(Ordering::Relaxed));
+                    std::thread::sleep(interval.saturating_sub(started.elapsed()));
+                }
+            })
+            .map_err(|error| crate::errors::synthesis_error(
+                crate::errors::ErrorKind::GraphicsContextError,
+                format!("🖥️ Couldn't start capturing {}: {}", capture.target.stream_name(), error)
+            ))?;
+        Ok(capture)
+    }
+
+    pub fn set_fps(&self, fps: f64) {
+        self.interval.store(interval_micros(fps), Ordering::Relaxed);
+    }
+
+    pub fn feed(&self, frame: ScreenFrame) {
+        *self.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(frame);
+    }
+
+    /// The newest grab as a texture, if one arrived since the last poll
+    pub fn poll(&self) -> Option<ScreenTexture> {
+        let frame = self.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()?;
+        let (width, height, data) = frame.downscale(self.max_width);
+        Some(ScreenTexture { name: self.target.stream_name(), width, height, data })
+    }
+}
+
+impl Drop for ScreenCapture {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "xcap")]
+mod backend {
+    use super::{CaptureTarget, ScreenFrame, ScreenSource};
+    use xcap::{Monitor, Window};
+
+    /// Looks its display or window up again on every grab, so a window that's closed and
+    /// reopened is picked up again, and no platform handle has to cross threads
+    struct XcapSource {
+        target: CaptureTarget,
+    }
+
+    impl ScreenSource for XcapSource {
+        fn grab(&mut self) -> crate::Result<ScreenFrame> {
+            let image = match &self.target {
+                CaptureTarget::Display(index) => {
+                    let monitors = Monitor::all().map_err(capture_error)?;
+                    let count = monitors.len();
+                    let monitor = monitors.into_iter().nth(*index).ok_or_else(|| crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::GraphicsContextError,
+                        format!("🖥️ There's no display {}", index)
+                    )
+                    .with_suggestion(format!("This machine has {} display(s), numbered from 0", count)))?;
+                    monitor.capture_image()
+                }
+                CaptureTarget::Window(title) => {
+                    let windows = Window::all().map_err(capture_error)?;
+                    let wanted = title.to_lowercase();
+                    match windows.iter().find(|window| window.title().to_lowercase().contains(&wanted)) {
+                        Some(window) => window.capture_image(),
+                        None => {
+                            let titles: Vec<&str> = windows.iter().map(|window| window.title()).filter(|title| !title.is_empty()).take(8).collect();
+                            return Err(crate::errors::synthesis_error(
+                                crate::errors::ErrorKind::GraphicsContextError,
+                                format!("🖥️ No open window has '{}' in its title", title)
+                            )
+                            .with_suggestion(format!("Open windows include: {}", titles.join(", "))));
+                        }
+                    }
+                }
+            }
+            .map_err(capture_error)?;
+            Ok(ScreenFrame { width: image.width() as usize, height: image.height() as usize, rgba: image.into_raw() })
+        }
+    }
+
+    fn capture_error(error: xcap::XCapError) -> crate::errors::SynthesisError {
+        crate::errors::synthesis_error(
+            crate::errors::ErrorKind::GraphicsContextError,
+            format!("🖥️ Screen capture error: {}", error)
+        )
+        .with_suggestion("On macOS, allow Screen Recording for your terminal in System Settings → Privacy & Security")
+    }
+
+    /// Check the target can be grabbed now, so a typo in a window title fails when the script starts
+    pub fn open_screen_capture(target: &CaptureTarget) -> crate::Result<Box<dyn ScreenSource>> {
+        let mut source = XcapSource { target: target.clone() };
+        source.grab()?;
+        Ok(Box::new(source))
+    }
+}
+
+#[cfg(not(feature = "xcap"))]
+mod backend {
+    use super::{CaptureTarget, ScreenSource};
+
+    pub fn open_screen_capture(_target: &CaptureTarget) -> crate::Result<Box<dyn ScreenSource>> {
+        Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::GraphicsContextError,
+            "🖥️ This build of Synthesis has no screen capture support"
+        )
+        .with_suggestion("Rebuild with --features xcap"))
+    }
+}
+
+pub use backend::open_screen_capture;
+
+static SCREEN_CAPTURES: OnceLock<Mutex<BTreeMap<String, ScreenCapture>>> = OnceLock::new();
+
+/// Running captures, by the name of the stream they publish
+pub fn screen_captures() -> &'static Mutex<BTreeMap<String, ScreenCapture>> {
+    SCREEN_CAPTURES.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
@@ -27,10 +27,12 @@ pub enum SensorDataType {
     Custom(String),
 }
 
+type DataParser = Box<dyn Fn(&str) -> Option<Vec<SensorData>> + Send>;
+
 pub struct ArduinoManager {
     connections: HashMap<String, Box<dyn SerialPort>>,
     sensor_data: Arc<Mutex<HashMap<String, SensorData>>>,
-    data_parsers: HashMap<String, Box<dyn Fn(&str) -> Option<Vec<SensorData>> + Send>>,
+    data_parsers: HashMap<String, DataParser>,
 }
 
 impl ArduinoManager {
@@ -212,8 +214,7 @@ impl SensorPresets {
             let mut results = Vec::new();
             
             for section in data.split(',') {
-                if section.starts_with("ACC:") {
-                    let coords = &section[4..];
+                if let Some(coords) = section.strip_prefix("ACC:") {
                     let values: Vec<&str> = coords.split(',').collect();
                     if values.len() >= 3 {
                         for (i, axis) in ["acc_x", "acc_y", "acc_z"].iter().enumerate() {
@@ -227,8 +228,7 @@ impl SensorPresets {
                             }
                         }
                     }
-                } else if section.starts_with("GYRO:") {
-                    let coords = &section[5..];
+                } else if let Some(coords) = section.strip_prefix("GYRO:") {
                     let values: Vec<&str> = coords.split(',').collect();
                     if values.len() >= 3 {
                         for (i, axis) in ["gyro_x", "gyro_y", "gyro_z"].iter().enumerate() {
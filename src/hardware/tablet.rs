@@ -0,0 +1,127 @@
+/// Pen tablet input: pressure, tilt and proximity
+///
+/// The window feeds pen events in as they arrive. winit reports pen pressure on Windows, iOS,
+/// Android and the web, and trackpad pressure on macOS; tilt only arrives where the platform
+/// reports the pen's altitude. Anything else that knows about a pen can call `handle_pen`.
+use std::f64::consts::FRAC_PI_2;
+use std::sync::{Mutex, OnceLock};
+
+/// The values a tablet publishes, each as a `tablet.<field>` control stream
+pub const TABLET_FIELDS: [&str; 3] = ["pressure", "tilt", "proximity"];
+
+pub fn tablet_stream_name(field: &str) -> String {
+    format!("tablet.{}", field)
+}
+
+#[derive(Debug, Default)]
+pub struct TabletState {
+    pressure: f64,
+    tilt: f64,
+    proximity: bool,
+    /// The touch being followed, so a resting finger can't take over from the pen
+    contact: Option<u64>,
+    /// Nothing is published until a pen first shows up
+    seen: bool,
+}
+
+impl TabletState {
+    /// The pen is down with `pressure` from 0 to 1; `tilt` is 0 upright and 1 lying flat
+    pub fn handle_pen(&mut self, pressure: f64, tilt: Option<f64>) {
+        self.pressure = pressure.clamp(0.0, 1.0);
+        if let Some(tilt) = tilt {
+            self.tilt = tilt.clamp(0.0, 1.0);
+        }
+        self.proximity = true;
+        self.seen = true;
+    }
+
+    /// The pen came off the surface; it may still be hovering
+    pub fn lift(&mut self) {
+        self.pressure = 0.0;
+        self.contact = None;
+    }
+
+    /// The pen came into range of the tablet or left it
+    pub fn set_proximity(&mut self, near: bool) {
+        self.proximity = near || self.contact.is_some();
+    }
+
+    pub fn pressure(&self) -> f64 {
+        self.pressure
+    }
+
+    pub fn tilt(&self) -> f64 {
+        self.tilt
+    }
+
+    pub fn proximity(&self) -> bool {
+        self.proximity
+    }
+
+    pub fn touching(&self) -> bool {
+        self.contact.is_some() || self.pressure > 0.0
+    }
+
+    pub fn value(&self, field: &str) -> Option<f64> {
+        match field {
+            "pressure" => Some(self.pressure),
+            "tilt" => Some(self.tilt),
+            "proximity" => Some(if self.proximity { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+
+    /// Every field as (stream name, value), once a pen has been seen
+    pub fn poll(&self) -> Vec<(String, f64)> {
+        if !self.seen {
+            return Vec::new();
+        }
+        TABLET_FIELDS.iter()
+            .filter_map(|field| self.value(field).map(|value| (tablet_stream_name(field), value)))
+            .collect()
+    }
+
+    pub fn handle_window_event(&mut self, event: &winit::event::WindowEvent) {
+        use winit::event::{Force, TouchPhase, WindowEvent};
+        match event {
+            WindowEvent::Touch(touch) => {
+                if self.contact.is_some_and(|id| id != touch.id) {
+                    return;
+                }
+                match touch.phase {
+                    TouchPhase::Started | TouchPhase::Moved => {
+                        self.contact = Some(touch.id);
+                        // Without a force the platform can only say the pen is down
+                        let pressure = touch.force.map_or(1.0, |force| force.normalized());
+                        let tilt = match touch.force {
+                            Some(Force::Calibrated { altitude_angle: Some(altitude), .. }) => Some(1.0 - altitude / FRAC_PI_2),
+                            _ => None,
+                        };
+                        self.handle_pen(pressure, tilt);
+                    }
+                    TouchPhase::Ended | TouchPhase::Cancelled => self.lift(),
+                }
+            }
+            WindowEvent::TouchpadPressure { pressure, .. } => {
+                if *pressure > 0.0 {
+                    self.handle_pen(*pressure as f64, None);
+                } else {
+                    self.lift();
+                }
+            }
+            WindowEvent::CursorEntered { .. } => self.set_proximity(true),
+            WindowEvent::CursorLeft { .. } => self.set_proximity(false),
+            WindowEvent::Focused(false) => {
+                self.lift();
+                self.set_proximity(false);
+            }
+            _ => {}
+        }
+    }
+}
+
+static TABLET: OnceLock<Mutex<TabletState>> = OnceLock::new();
+
+pub fn tablet() -> &'static Mutex<TabletState> {
+    TABLET.get_or_init(|| Mutex::new(TabletState::default()))
+}
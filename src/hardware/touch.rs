@@ -0,0 +1,110 @@
+/// Touchscreen input: every finger on the screen, plus the touches that began and ended each frame
+///
+/// The window feeds touch events in as they arrive; the interpreter calls `begin_frame` so a
+/// script sees each touch start and end exactly once, like key events.
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchPoint {
+    /// Stays the same while the finger is down; the OS may reuse it for a later touch
+    pub id: u64,
+    /// Position across and down the window, 0 to 1
+    pub x: f64,
+    pub y: f64,
+    /// 0 to 1, or 1 on screens that don't sense pressure
+    pub pressure: f64,
+    pub started: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct TouchState {
+    active: BTreeMap<u64, TouchPoint>,
+    incoming_started: Vec<u64>,
+    incoming_ended: Vec<TouchPoint>,
+    started: Vec<u64>,
+    ended: Vec<TouchPoint>,
+}
+
+impl TouchState {
+    /// A finger went down, or moved if it was already down
+    pub fn handle_touch(&mut self, id: u64, x: f64, y: f64, pressure: f64) {
+        let (x, y, pressure) = (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0), pressure.clamp(0.0, 1.0));
+        match self.active.get_mut(&id) {
+            Some(point) => {
+                point.x = x;
+                point.y = y;
+                point.pressure = pressure;
+            }
+            None => {
+                self.active.insert(id, TouchPoint { id, x, y, pressure, started: Instant::now() });
+                self.incoming_started.push(id);
+            }
+        }
+    }
+
+    /// A finger came up, or the OS stopped tracking it
+    pub fn end_touch(&mut self, id: u64) {
+        if let Some(point) = self.active.remove(&id) {
+            self.incoming_ended.push(point);
+        }
+    }
+
+    /// The window lost focus, so no end events will arrive for what's down
+    pub fn release_all(&mut self) {
+        let ids: Vec<u64> = self.active.keys().copied().collect();
+        for id in ids {
+            self.end_touch(id);
+        }
+    }
+
+    /// Move touches that began or ended since the last frame into this one
+    pub fn begin_frame(&mut self) {
+        self.started = std::mem::take(&mut self.incoming_started);
+        self.ended = std::mem::take(&mut self.incoming_ended);
+    }
+
+    /// Fingers down right now, oldest id first
+    pub fn active(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.active.values()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&TouchPoint> {
+        self.active.get(&id)
+    }
+
+    /// Touches that began this frame and are still down
+    pub fn started(&self) -> impl Iterator<Item = &TouchPoint> {
+        self.started.iter().filter_map(|id| self.active.get(id))
+    }
+
+    /// Touches that ended this frame, where they were last
+    pub fn ended(&self) -> &[TouchPoint] {
+        &self.ended
+    }
+
+    /// `size` is the window's size in pixels, to turn touch positions into fractions of it
+    pub fn handle_window_event(&mut self, event: &winit::event::WindowEvent, size: (u32, u32)) {
+        use winit::event::{TouchPhase, WindowEvent};
+        match event {
+            WindowEvent::Touch(touch) => match touch.phase {
+                TouchPhase::Started | TouchPhase::Moved => {
+                    let x = touch.location.x / size.0.max(1) as f64;
+                    let y = touch.location.y / size.1.max(1) as f64;
+                    let pressure = touch.force.map_or(1.0, |force| force.normalized());
+                    self.handle_touch(touch.id, x, y, pressure);
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => self.end_touch(touch.id),
+            },
+            WindowEvent::Focused(false) => self.release_all(),
+            _ => {}
+        }
+    }
+}
+
+static TOUCH: OnceLock<Mutex<TouchState>> = OnceLock::new();
+
+pub fn touch() -> &'static Mutex<TouchState> {
+    TOUCH.get_or_init(|| Mutex::new(TouchState::default()))
+}
@@ -226,7 +226,7 @@ impl WebcamManager {
                     b_total += b;
                     
                     // Calculate brightness (luminance)
-                    let brightness = ((r * 299 + g * 587 + b * 114) / 1000) as u64;
+                    let brightness = (r * 299 + g * 587 + b * 114) / 1000 ;
                     brightness_total += brightness;
                     
                     // Add to histogram
@@ -1,17 +1,462 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs;
-use synthesis::parser::{lexer, Parser};
-use synthesis::runtime::Interpreter;
+use std::path::Path;
+use std::time::Duration;
+use synthesis::parser::{format as format_program, formatter, import_sketch, infer_types, lexer, Linter, Parser, TypeChecker};
+use synthesis::runtime::{artifact_document, examples_by_category, find_example, load_extension, load_plugin, ArtifactOptions, LessonCheck, LessonProgress, Capability, CapabilityPolicy, ControlServer, Interpreter, MetricsServer, SandboxLimits, SandboxSession, Supervisor, SupervisorConfig, Value};
+
+/// Parse frame pacing flags (--fps, --update-rate, --vsync, --no-vsync, --triple-buffer)
+/// into the same shape as a script's `config { }` block
+fn frame_pacing_flags(args: &[String]) -> Result<HashMap<String, Value>, String> {
+    let mut overrides = HashMap::new();
+    let mut iter = args.iter();
+    
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--fps" | "--update-rate" => {
+                let value = iter.next()
+                    .ok_or_else(|| format!("{} needs a value, like {} 30", arg, arg))?;
+                let rate = if value == "unlimited" {
+                    Value::String(value.clone())
+                } else {
+                    Value::Float(value.parse::<f64>()
+                        .map_err(|_| format!("{} expects a number of frames per second, got '{}'", arg, value))?)
+                };
+                let key = if arg == "--fps" { "fps" } else { "update_rate" };
+                overrides.insert(key.to_string(), rate);
+            }
+            "--vsync" => { overrides.insert("vsync".to_string(), Value::Boolean(true)); }
+            "--no-vsync" => { overrides.insert("vsync".to_string(), Value::Boolean(false)); }
+            "--triple-buffer" => { overrides.insert("triple_buffering".to_string(), Value::Boolean(true)); }
+            _ => {}
+        }
+    }
+    
+    Ok(overrides)
+}
+
+/// `synthesis supervise script.syn`: run the script in a child process and restart it on crashes or hangs
+fn supervise(args: &[String]) -> synthesis::Result<()> {
+    let script = match args.get(2) {
+        Some(script) if script.ends_with(".syn") => script,
+        _ => {
+            eprintln!("🛡️  Which script should I supervise? Try: {} supervise installation.syn", args[0]);
+            return Ok(());
+        }
+    };
+    
+    let mut config = SupervisorConfig::for_script(Path::new(script));
+    let mut child_args = Vec::new();
+    let mut iter = args[3..].iter();
+    
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--hang-timeout" => match iter.next().and_then(|v| v.parse::<f64>().ok()) {
+                Some(seconds) if seconds > 0.0 => config.hang_timeout = Duration::from_secs_f64(seconds),
+                _ => {
+                    eprintln!("🛡️  --hang-timeout expects a number of seconds, like --hang-timeout 15");
+                    return Ok(());
+                }
+            },
+            "--incident-log" => match iter.next() {
+                Some(path) => config.incident_log = path.into(),
+                None => {
+                    eprintln!("🛡️  --incident-log needs a file path");
+                    return Ok(());
+                }
+            },
+            // Everything else is for the script itself (--fps, --profile, ...)
+            _ => child_args.push(arg.clone()),
+        }
+    }
+    
+    Supervisor::with_config(script, child_args, config).run()
+}
+
+/// `synthesis examples`: list the bundled examples, run one by name, or `--check` them all
+fn examples(args: &[String]) -> synthesis::Result<()> {
+    match args.get(2).map(String::as_str) {
+        None => {
+            println!("🎨 Bundled examples:");
+            for (category, members) in examples_by_category() {
+                println!("\n  {}", category);
+                for example in members {
+                    println!("    {:<16} {}", example.name, example.description());
+                }
+            }
+            println!("\nRun one with: {} examples <name> [options]", args[0]);
+            Ok(())
+        }
+        Some("--check") => {
+            let frames = match args.iter().position(|arg| arg == "--frames").and_then(|i| args.get(i + 1)) {
+                Some(value) => value.parse::<u64>().unwrap_or(10),
+                None => 10,
+            };
+            let mut failed = 0;
+            for example in synthesis::runtime::examples() {
+                let report = example.check(frames);
+                match &report.error {
+                    None => println!("✅ {:<16} {} frame(s) in {:.1}ms", example.name, report.frames_run, report.elapsed.as_secs_f64() * 1000.0),
+                    Some(error) => {
+                        failed += 1;
+                        println!("❌ {:<16} {}", example.name, error.message);
+                    }
+                }
+            }
+            if failed > 0 {
+                eprintln!("\n{} example(s) failed", failed);
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(name) => match find_example(name) {
+            Some(example) => run_source(&format!("{}.syn", example.name), example.source, &args[3..]),
+            None => {
+                eprintln!("🎨 There's no example called '{}'. Try: {} examples", name, args[0]);
+                Ok(())
+            }
+        },
+    }
+}
+
+/// `synthesis import sketch.pde`: convert a Processing or p5.js sketch to a .syn file next to it
+fn import(args: &[String]) -> synthesis::Result<()> {
+    let sketch = match args.get(2) {
+        Some(sketch) if sketch.ends_with(".pde") || sketch.ends_with(".js") => Path::new(sketch),
+        _ => {
+            eprintln!("🔁 Which sketch should I convert? Try: {} import sketch.pde (or sketch.js)", args[0]);
+            return Ok(());
+        }
+    };
+    let mut output = sketch.with_extension("syn");
+    let mut force = false;
+    let mut iter = args[3..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-o" | "--output" => match iter.next() {
+                Some(path) => output = path.into(),
+                None => {
+                    eprintln!("🔁 {} needs a file name, like {} sketch.syn", arg, arg);
+                    return Ok(());
+                }
+            },
+            "--force" => force = true,
+            other => {
+                eprintln!("🔁 Unknown option '{}'", other);
+                return Ok(());
+            }
+        }
+    }
+    
+    let source = match fs::read_to_string(sketch) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("🔁 Couldn't read {}: {}", sketch.display(), error);
+            return Ok(());
+        }
+    };
+    let name = sketch.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let converted = match import_sketch(&source, &name) {
+        Ok(converted) => converted,
+        Err(error) => {
+            let line = error.location.as_ref().map(|l| format!(" (line {})", l.line)).unwrap_or_default();
+            eprintln!("❌ {}{}", error.message, line);
+            for suggestion in &error.suggestions {
+                eprintln!("   💡 {}", suggestion);
+            }
+            return Ok(());
+        }
+    };
+    
+    if output.exists() && !force {
+        eprintln!("🔁 {} already exists; pass --force to overwrite it or -o to pick another name", output.display());
+        return Ok(());
+    }
+    if let Err(error) = fs::write(&output, &converted.source) {
+        eprintln!("🔁 Couldn't write {}: {}", output.display(), error);
+        return Ok(());
+    }
+    
+    println!("✅ Wrote {}", output.display());
+    if !converted.warnings.is_empty() {
+        println!("\n⚠️  {} thing(s) to check; code that couldn't be converted is left as comments:", converted.warnings.len());
+        for warning in &converted.warnings {
+            println!("   {}:{}  {}", name, warning.line, warning.message);
+        }
+    }
+    println!("\nRun it with: {} {}", args[0], output.display());
+    Ok(())
+}
+
+/// `synthesis fmt sketch.syn`: rewrite scripts in the canonical layout, or with `--check` just
+/// list the ones that aren't
+fn fmt(args: &[String]) -> synthesis::Result<()> {
+    let check = args[2..].iter().any(|arg| arg == "--check");
+    let force = args[2..].iter().any(|arg| arg == "--force");
+    let files: Vec<&String> = args[2..].iter().filter(|arg| !arg.starts_with("--")).collect();
+    if files.is_empty() {
+        eprintln!("✏️  Which script should I format? Try: {} fmt sketch.syn", args[0]);
+        return Ok(());
+    }
+    
+    let mut unformatted = 0;
+    for file in files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("✏️  Couldn't read {}: {}", file, error);
+                continue;
+            }
+        };
+        let Ok((_, (tokens, spans))) = lexer::tokenize_with_spans(&source) else {
+            eprintln!("❌ There's something unusual in {}; fix it before formatting", file);
+            continue;
+        };
+        let program = match Parser::with_spans(&tokens, &spans, file).parse_all() {
+            Ok(program) => program,
+            Err(errors) => {
+                for error in errors {
+                    let line = error.location.as_ref().map(|l| format!(" (line {})", l.line)).unwrap_or_default();
+                    eprintln!("❌ {}: {}{}", file, error.message, line);
+                }
+                continue;
+            }
+        };
+        if let Some(lost) = formatter::would_lose(&source, &tokens) {
+            if !force {
+                eprintln!("✏️  {} has {} that formatting would drop; pass --force to format it anyway", file, lost);
+                continue;
+            }
+        }
+        
+        let formatted = format_program(&program);
+        if formatted == source {
+            continue;
+        }
+        // Formatting should never change what a script does; if it would, leave the file alone
+        let same = lexer::tokenize_with_spans(&formatted).ok()
+            .and_then(|(_, (tokens, spans))| Parser::with_spans(&tokens, &spans, file).parse().ok())
+            .is_some_and(|reparsed| reparsed == program);
+        if !same {
+            eprintln!("❌ Formatting {} would change what it does, so it's left as it is", file);
+            continue;
+        }
+        if check {
+            println!("✏️  {} isn't formatted", file);
+            unformatted += 1;
+        } else if let Err(error) = fs::write(file, formatted) {
+            eprintln!("✏️  Couldn't write {}: {}", file, error);
+        } else {
+            println!("✅ Formatted {}", file);
+        }
+    }
+    if unformatted > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `synthesis lint sketch.syn`: point out what runs but probably isn't what was meant; exits with
+/// 1 if anything was found, for CI
+fn lint(args: &[String]) -> synthesis::Result<()> {
+    let files: Vec<&String> = args[2..].iter().filter(|arg| !arg.starts_with("--")).collect();
+    if files.is_empty() {
+        eprintln!("🔎 Which script should I look over? Try: {} lint sketch.syn", args[0]);
+        return Ok(());
+    }
+    
+    let mut found = 0;
+    for file in files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("🔎 Couldn't read {}: {}", file, error);
+                continue;
+            }
+        };
+        let Ok((_, (tokens, spans))) = lexer::tokenize_with_spans(&source) else {
+            eprintln!("❌ There's something unusual in {}; fix it before linting", file);
+            continue;
+        };
+        let program = match Parser::with_spans(&tokens, &spans, file).parse_all() {
+            Ok(program) => program,
+            Err(errors) => {
+                for error in errors {
+                    let line = error.location.as_ref().map(|l| format!(" (line {})", l.line)).unwrap_or_default();
+                    eprintln!("❌ {}: {}{}", file, error.message, line);
+                }
+                continue;
+            }
+        };
+        let warnings = Linter::with_spans(&tokens, &spans, file).lint(&program);
+        for warning in &warnings {
+            println!("{}", warning);
+        }
+        if warnings.is_empty() {
+            println!("✅ {} looks good", file);
+        }
+        found += warnings.len();
+    }
+    if found > 0 {
+        println!("🔎 {} thing(s) to look at", found);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `synthesis learn [n]`: guided lessons, re-checked every time the lesson file is saved
+fn learn(args: &[String]) -> synthesis::Result<()> {
+    let dir = Path::new("synthesis-lessons");
+    let lessons = synthesis::runtime::lessons();
+    let mut progress = LessonProgress::load(dir);
+    
+    let index = match args.get(2) {
+        Some(n) => match n.parse::<usize>() {
+            Ok(n) if (1..=lessons.len()).contains(&n) => n - 1,
+            _ => {
+                eprintln!("📚 Pick a lesson from 1 to {}, like: {} learn 2", lessons.len(), args[0]);
+                return Ok(());
+            }
+        },
+        None => match progress.next_lesson() {
+            Some(index) => index,
+            None => {
+                println!("🎓 You've finished every lesson! Try the gallery next: {} examples", args[0]);
+                return Ok(());
+            }
+        },
+    };
+    let lesson = &lessons[index];
+    let path = lesson.file_in(dir);
+    
+    if !path.exists() {
+        if let Err(error) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, lesson.starter)) {
+            eprintln!("📚 Couldn't create {}: {}", path.display(), error);
+            return Ok(());
+        }
+    }
+    
+    println!("📚 Lesson {} of {}: {}", index + 1, lessons.len(), lesson.title);
+    println!("\n   {}\n", lesson.task);
+    println!("   Edit {} and save; I'll check it each time (Ctrl+C to stop).", path.display());
+    if let Ok(editor) = env::var("VISUAL").or_else(|_| env::var("EDITOR")) {
+        let _ = std::process::Command::new(editor).arg(&path).spawn();
+    }
+    
+    let mut last_checked = None;
+    loop {
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if modified != last_checked {
+            last_checked = modified;
+            let source = fs::read_to_string(&path).unwrap_or_default();
+            match lesson.check(&source) {
+                LessonCheck::Passed => {
+                    if let Err(error) = progress.complete(lesson) {
+                        eprintln!("📚 Couldn't save your progress: {}", error);
+                    }
+                    println!("\n🎉 Nice work, that's lesson {} done!", index + 1);
+                    match lessons.get(index + 1) {
+                        Some(next) => println!("   Next up: {} ({} learn {})", next.title, args[0], index + 2),
+                        None => println!("   That was the last one. Try the gallery next: {} examples", args[0]),
+                    }
+                    return Ok(());
+                }
+                LessonCheck::Error(error) => {
+                    println!("\n❌ {}", error.message);
+                    for suggestion in &error.suggestions {
+                        println!("   💡 {}", suggestion);
+                    }
+                }
+                LessonCheck::Unmet(hint) => println!("\n🔸 Runs fine, but not quite there yet.\n   💡 {}", hint),
+            }
+        }
+        std::thread::sleep(Duration::from_millis(300));
+    }
+}
+
+/// `synthesis calibrate`: time clicks from the output back into the input and store the round trip
+fn calibrate(args: &[String]) -> synthesis::Result<()> {
+    use synthesis::audio::latency::{LatencyCalibration, LatencyCompensation, CALIBRATION_ROUNDS};
+    
+    let mut rounds = CALIBRATION_ROUNDS;
+    let mut iter = args[2..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rounds" => match iter.next().and_then(|v| v.parse::<usize>().ok()).filter(|n| *n > 0) {
+                Some(n) => rounds = n,
+                None => {
+                    eprintln!("🎚️ --rounds needs a count, like --rounds 12");
+                    return Ok(());
+                }
+            },
+            "--manual" => {
+                let Some(ms) = iter.next().and_then(|v| v.parse::<f64>().ok()).filter(|ms| ms.is_finite() && *ms >= 0.0) else {
+                    eprintln!("🎚️ --manual needs the round trip in milliseconds, like --manual 11.5");
+                    return Ok(());
+                };
+                return save_latency(LatencyCompensation { round_trip: Duration::from_secs_f64(ms / 1000.0) });
+            }
+            "--reset" => return save_latency(LatencyCompensation::default()),
+            other => {
+                eprintln!("🎚️ Unknown option '{}'", other);
+                return Ok(());
+            }
+        }
+    }
+    
+    println!("🎚️ Latency calibration");
+    println!("   Connect the output to the input with a cable, or put the mic near a speaker.");
+    println!("   You'll hear {} clicks; keep the room quiet.\n", rounds);
+    let mut measurements = Vec::new();
+    for round in 1..=rounds {
+        match synthesis::audio::input::measure_round_trip() {
+            Ok(Some(round_trip)) => {
+                println!("   Round {}: {:.1}ms", round, round_trip.as_secs_f64() * 1000.0);
+                measurements.push(round_trip);
+            }
+            Ok(None) => println!("   Round {}: didn't hear the click", round),
+            Err(error) => {
+                eprintln!("❌ {}", error.message);
+                for suggestion in &error.suggestions {
+                    eprintln!("   💡 {}", suggestion);
+                }
+                return Ok(());
+            }
+        }
+    }
+    
+    let Some(calibration) = LatencyCalibration::from_measurements(measurements) else {
+        eprintln!("\n❌ The input never heard a click. Check the cable or turn the speaker up, then try again.");
+        eprintln!("   💡 Or enter a value you measured elsewhere: {} calibrate --manual 12", args[0]);
+        return Ok(());
+    };
+    if !calibration.is_consistent() {
+        println!("\n⚠️  Rounds disagreed by more than a few milliseconds; a larger buffer or a cable may steady it.");
+    }
+    println!("\n   Round trip: {:.1}ms (median of {})", calibration.round_trip.as_secs_f64() * 1000.0, calibration.measurements.len());
+    save_latency(LatencyCompensation { round_trip: calibration.round_trip })
+}
+
+fn save_latency(compensation: synthesis::audio::latency::LatencyCompensation) -> synthesis::Result<()> {
+    match compensation.save() {
+        Ok(path) => println!("✅ Saved {:.1}ms compensation to {}", compensation.round_trip.as_secs_f64() * 1000.0, path.display()),
+        Err(error) => eprintln!("🎚️ Couldn't save the calibration: {}", error),
+    }
+    Ok(())
+}
 
 fn main() -> synthesis::Result<()> {
     let args: Vec<String> = env::args().collect();
+    synthesis::runtime::init_logging();
     
     if args.len() < 2 {
         println!("Synthesis Language Interpreter v0.1.0");
-        println!("Usage: {} <script.syn>", args[0]);
+        println!("Usage: {} [run] <script.syn>", args[0]);
         println!("\nAvailable commands:");
         println!("  --version    Show version information");
         println!("  --help       Show this help message");
+        println!("  --fps <n>    Target frame rate (or 'unlimited')");
         return Ok(());
     }
     
@@ -23,18 +468,80 @@ fn main() -> synthesis::Result<()> {
         }
         "--help" => {
             println!("Synthesis Language Interpreter");
-            println!("Usage: {} <script.syn>", args[0]);
+            println!("Usage: {} [run] <script.syn> [options]", args[0]);
             println!("\nOptions:");
             println!("  --version    Show version information");
             println!("  --help       Show this help message");
+            println!("\nFrame pacing (overrides the script's config block):");
+            println!("  --fps <n>            Target frame rate, or 'unlimited'");
+            println!("  --update-rate <n>    Run the script at a fixed rate and interpolate between updates");
+            println!("  --vsync              Sync presentation to the display refresh");
+            println!("  --no-vsync           Present immediately, even if it tears");
+            println!("  --triple-buffer      Queue an extra frame for smoother pacing");
+            println!("\nDiagnostics:");
+            println!("  --profile            Print per-pass GPU and script timings while running");
+            println!("  --types              Print the inferred type of each variable and the conversions it relies on");
+            println!("  --report-leaks       List textures, buffers, audio and streams still alive at exit");
+            println!("  --metrics-port <p>   Serve Prometheus /metrics and /health (port, or address like 0.0.0.0:9100)");
+            println!("  --control-port <p>   Remote control API for GUI controls and config {{ remote: [...] }} variables");
+            println!("  --control-token <t>  Access token for the control API (default: $SYNTHESIS_CONTROL_TOKEN or random)");
+            println!("\nHeadless output:");
+            println!("  --artifacts <file>   Run without pacing and write frames (PNG), audio and metrics as JSON");
+            println!("  --frames <n>         Frames to run before stopping a loop (default 120)");
+            println!("\nUntrusted scripts:");
+            println!("  --sandbox            Refuse network, camera, microphone, MIDI and file access the script doesn't declare");
+            println!("  --allow <list>       Capabilities a sandboxed script may declare, like camera,filesystem:assets/");
+            println!("\nPlugins:");
+            println!("  --plugin <library>   Load native modules from a plugin library (repeat for more)");
+            println!("  --extension <file>   Load a sandboxed WebAssembly extension, like bitcrush.wasm (repeat for more)");
+            println!("\nLogging:");
+            println!("  SYNTHESIS_LOG=streams=debug,audio=info   Per-module log levels");
+            println!("  SYNTHESIS_LOG_FORMAT=json                One JSON object per log line");
+            println!("\nLearning:");
+            println!("  {} learn [n]                Guided lessons, checked every time you save", args[0]);
+            println!("\nComing from Processing or p5.js:");
+            println!("  {} import <sketch.pde|sketch.js> [-o out.syn] [--force]   Convert a sketch to .syn", args[0]);
+            println!("\nFormatting:");
+            println!("  {} fmt <script.syn>... [--check] [--force]   Rewrite scripts in the standard layout", args[0]);
+            println!("\nLinting:");
+            println!("  {} lint <script.syn>...   Point out unused variables, empty loops, hidden streams and audio-rate stalls", args[0]);
+            println!("\nExample gallery:");
+            println!("  {} examples                 List bundled examples by category", args[0]);
+            println!("  {} examples <name>          Run one, e.g. {} examples beat_pulse", args[0], args[0]);
+            println!("  {} examples --check         Run every example for a few headless frames (for CI)", args[0]);
+            println!("\nAudio setup:");
+            println!("  {} calibrate [--rounds n]   Measure output-to-input latency with clicks and store it", args[0]);
+            println!("  {} calibrate --manual <ms>  Store a round trip measured elsewhere (--reset clears it)", args[0]);
+            println!("\nUnattended installations:");
+            println!("  {} supervise <script.syn> [options]", args[0]);
+            println!("  --hang-timeout <s>   Restart when no frame is produced for this long (default 10)");
+            println!("  --incident-log <f>   Where to log crashes and hangs (default <script>.incidents.log)");
             println!("\nExamples:");
             println!("  {} examples/plasma.syn", args[0]);
+            println!("  {} examples/plasma.syn --fps 30 --no-vsync", args[0]);
+            println!("  {} run installation.syn --report-leaks", args[0]);
             return Ok(());
         }
+        "supervise" => return supervise(&args),
+        "examples" => return examples(&args),
+        "learn" => return learn(&args),
+        "import" => return import(&args),
+        "fmt" => return fmt(&args),
+        "lint" => return lint(&args),
+        "calibrate" => return calibrate(&args),
         _ => {}
     }
     
-    let filename = &args[1];
+    // `synthesis run script.syn` and `synthesis script.syn` are the same thing
+    let script_args = if args[1] == "run" { &args[2..] } else { &args[1..] };
+    let filename = match script_args.first() {
+        Some(filename) => filename,
+        None => {
+            eprintln!("🎵 Which script should I run? Try: {} run examples/plasma.syn", args[0]);
+            return Ok(());
+        }
+    };
+    let flags = &script_args[1..];
     
     if !filename.ends_with(".syn") {
         eprintln!("Error: Synthesis files must have a .syn extension");
@@ -50,24 +557,173 @@ fn main() -> synthesis::Result<()> {
         }
     };
     
+    run_source(filename, &source_code, flags)
+}
+
+/// Parse and run a script; shared by `synthesis run` and `synthesis examples <name>`
+fn run_source(filename: &str, source_code: &str, flags: &[String]) -> synthesis::Result<()> {
+    let mut pacing_overrides = match frame_pacing_flags(flags) {
+        Ok(overrides) => overrides,
+        Err(message) => {
+            eprintln!("🎬 {}", message);
+            return Ok(());
+        }
+    };
+    
     println!("Parsing {}...", filename);
     
-    let (_, tokens) = lexer::tokenize(&source_code)
-        .map_err(|_| synthesis::errors::synthesis_error(
+    let (_, (tokens, spans)) = lexer::tokenize_with_spans(source_code)
+        .map_err(|error| lexer::literal_error(source_code, &error, filename).unwrap_or_else(|| synthesis::errors::synthesis_error(
             synthesis::errors::ErrorKind::SyntaxError,
             "🎵 Oops! There's something unusual in your creative code"
         )
         .with_suggestion("Check for typos, missing quotes, or unusual characters")
         .with_suggestion("Try running with --verbose for more details")
-        .with_docs("https://synthesis-lang.org/docs/syntax-basics"))?;
+        .with_docs("https://synthesis-lang.org/docs/syntax-basics")))?;
     
-    let mut parser = Parser::new(&tokens);
-    let program = parser.parse()?;
+    let mut parser = Parser::with_spans(&tokens, &spans, filename);
+    // Every syntax error is shown at once, so a whole file can be fixed in one go
+    let program = match parser.parse_all() {
+        Ok(program) => program,
+        Err(mut errors) if errors.len() == 1 => return Err(errors.remove(0)),
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            return Err(synthesis::errors::synthesis_error(
+                synthesis::errors::ErrorKind::SyntaxError,
+                format!("🎵 {} syntax errors in {}", errors.len(), filename)
+            ));
+        }
+    };
+    
+    // Annotations that can't hold are all reported before anything starts playing
+    let mismatches = TypeChecker::with_spans(&tokens, &spans, filename).check(&program);
+    if !mismatches.is_empty() {
+        for mismatch in &mismatches {
+            eprintln!("{}", mismatch);
+        }
+        return Err(synthesis::errors::synthesis_error(
+            synthesis::errors::ErrorKind::TypeMismatch,
+            format!("🔄 {} value(s) in {} don't match their type annotations", mismatches.len(), filename)
+        ));
+    }
+    
+    if flags.iter().any(|arg| arg == "--types") {
+        println!("🔄 Types in {}:\n{}", filename, infer_types(&program).report());
+    }
     
     println!("Running {}...", filename);
     
+    // Plugins and extensions register their modules before the interpreter is built, so it picks them up
+    let mut flag = flags.iter();
+    while let Some(arg) = flag.next() {
+        if arg == "--plugin" {
+            match flag.next() {
+                Some(path) => println!("🧩 Loaded {} from {}", load_plugin(Path::new(path))?.join(", "), path),
+                None => {
+                    eprintln!("🧩 --plugin needs the path of a plugin library, like --plugin target/release/liblights.so");
+                    return Ok(());
+                }
+            }
+        }
+        if arg == "--extension" {
+            match flag.next() {
+                Some(path) => {
+                    let (name, functions) = load_extension(Path::new(path))?;
+                    println!("🧩 Loaded {} with {}", name, functions.iter().map(|f| format!("{}()", f)).collect::<Vec<_>>().join(", "));
+                }
+                None => {
+                    eprintln!("🧩 --extension needs the path of a .wasm file, like --extension effects/bitcrush.wasm");
+                    return Ok(());
+                }
+            }
+        }
+    }
+    
     let mut interpreter = Interpreter::new();
-    interpreter.execute(&program)?;
+    interpreter.script_dir = Path::new(filename).parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+    
+    // Headless runs go as fast as they can and write what they drew and played to a JSON document
+    let artifacts = flags.iter().position(|arg| arg == "--artifacts").and_then(|i| flags.get(i + 1)).cloned();
+    if artifacts.is_some() {
+        let frames = match flags.iter().position(|arg| arg == "--frames").and_then(|i| flags.get(i + 1)) {
+            Some(value) => match value.parse::<u64>() {
+                Ok(frames) => frames,
+                Err(_) => {
+                    eprintln!("🎬 --frames expects a number of frames, like --frames 60");
+                    return Ok(());
+                }
+            },
+            None => 120,
+        };
+        interpreter.sandbox = Some(SandboxSession::new(SandboxLimits::headless(frames)));
+        pacing_overrides.insert("fps".to_string(), Value::String("unlimited".to_string()));
+        pacing_overrides.insert("vsync".to_string(), Value::Boolean(false));
+    }
+    interpreter.set_frame_pacing_overrides(pacing_overrides)?;
+    interpreter.profiling = flags.iter().any(|arg| arg == "--profile");
+    if flags.iter().any(|arg| arg == "--sandbox") {
+        let allowed = match flags.iter().position(|arg| arg == "--allow").and_then(|i| flags.get(i + 1)) {
+            Some(list) => Capability::parse_list(list)?,
+            None => Vec::new(),
+        };
+        interpreter.capabilities = CapabilityPolicy::sandboxed(allowed);
+    }
+    
+    // Bound for the whole run so the servers stay up
+    let _control = match flags.iter().position(|arg| arg == "--control-port") {
+        Some(i) => {
+            let token = flags.iter().position(|arg| arg == "--control-token").and_then(|t| flags.get(t + 1)).cloned();
+            match flags.get(i + 1).map(|bind| ControlServer::start(bind, token)) {
+                Some(Ok(server)) => {
+                    println!("🎛️  Remote control on http://{} (token: {})", server.address(), server.token());
+                    Some(server)
+                }
+                Some(Err(error)) => {
+                    eprintln!("🎛️  Couldn't start the remote control API: {}", error);
+                    None
+                }
+                None => {
+                    eprintln!("🎛️  --control-port needs a port, like --control-port 8090");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    
+    let _metrics = match flags.iter().position(|arg| arg == "--metrics-port") {
+        Some(i) => match flags.get(i + 1).map(|bind| MetricsServer::start(bind)) {
+            Some(Ok(server)) => Some(server),
+            Some(Err(error)) => {
+                eprintln!("📈 Couldn't start the metrics endpoint: {}", error);
+                None
+            }
+            None => {
+                eprintln!("📈 --metrics-port needs a port, like --metrics-port 9100");
+                None
+            }
+        },
+        None => None,
+    };
+    let result = interpreter.execute(&program);
+    
+    if let (Some(path), Some(session)) = (&artifacts, interpreter.sandbox.take()) {
+        let report = session.finish(interpreter.variables.clone(), result.as_ref().err().cloned());
+        let document = artifact_document(&report, &ArtifactOptions::default());
+        match fs::write(path, document.to_string()) {
+            Ok(()) => println!("🖼️  Wrote {} frame(s) of artifacts to {}", report.frames_run, path),
+            Err(error) => eprintln!("🖼️  Couldn't write artifacts to {}: {}", path, error),
+        }
+    }
+    
+    if flags.iter().any(|arg| arg == "--report-leaks") {
+        // Drop the interpreter first so only resources that outlive it show up
+        drop(interpreter);
+        print!("{}", synthesis::runtime::leak_report());
+    }
+    result?;
     
     println!("Program completed successfully.");
     Ok(())
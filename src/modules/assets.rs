@@ -0,0 +1,118 @@
+use crate::runtime::assets::asset_manager;
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Accept either a path or an asset object returned by Assets.load()
+fn asset_path(args: &[Value], function: &str) -> crate::Result<String> {
+    match args.first() {
+        Some(Value::String(path)) => Ok(path.clone()),
+        Some(Value::Object(fields)) => match fields.get("path") {
+            Some(Value::String(path)) => Ok(path.clone()),
+            _ => Err(missing_path(function)),
+        },
+        _ => Err(missing_path(function)),
+    }
+}
+
+fn missing_path(function: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("📦 Assets.{}() needs a file path or an asset from Assets.load()", function)
+    )
+    .with_suggestion(format!("Try: Assets.{}(\"textures/noise.png\")", function))
+    .with_docs("https://synthesis-lang.org/docs/assets")
+}
+
+pub fn load(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.load", &[])?;
+    let path = asset_path(args, "load")?;
+    let manager = asset_manager();
+    let handle = manager.load(&path);
+
+    match manager.get(handle) {
+        Some(asset) => Ok(asset.to_value()),
+        None => Err(missing_path("load")),
+    }
+}
+
+pub fn get(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.get", &[])?;
+    // Loading is cached by path, so get() on an unseen path just starts loading it
+    load(args, named)
+}
+
+pub fn reload(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.reload", &[])?;
+    let path = asset_path(args, "reload")?;
+    let manager = asset_manager();
+    let handle = manager.load(&path);
+    manager.reload(handle);
+    println!("Assets.reload: {}", path);
+
+    Ok(manager.get(handle).map(|asset| asset.to_value()).unwrap_or(Value::Null))
+}
+
+pub fn unload(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.unload", &[])?;
+    let path = asset_path(args, "unload")?;
+    let handle = crate::runtime::assets::AssetHandle::for_path(std::path::Path::new(&path));
+    Ok(Value::Boolean(asset_manager().unload(handle)))
+}
+
+pub fn progress(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.progress", &[])?;
+    Ok(asset_manager().progress().to_value())
+}
+
+pub fn watch(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.watch", &[])?;
+    let enabled = args.first().map(|v| v.is_truthy()).unwrap_or(true);
+
+    if enabled {
+        asset_manager().watch();
+        println!("Assets.watch: hot reload on");
+    } else {
+        asset_manager().stop_watching();
+        println!("Assets.watch: hot reload off");
+    }
+    Ok(Value::Boolean(enabled))
+}
+
+pub fn changed(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.changed", &[])?;
+    let manager = asset_manager();
+    let changed = manager
+        .take_changed()
+        .into_iter()
+        .filter_map(|handle| manager.get(handle))
+        .map(|asset| Value::String(asset.path.display().to_string()))
+        .collect();
+    Ok(Value::Array(changed))
+}
+
+
+// Resource Cleanup
+
+pub fn stats(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.stats", &[])?;
+    let stats = crate::runtime::resources::resource_stats();
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("resource_stats".to_string()));
+    result.insert("total_bytes".to_string(), Value::Integer(stats.iter().map(|s| s.bytes as i64).sum()));
+    for stat in stats {
+        result.insert(stat.kind.name().to_string(), stat.to_value());
+    }
+    Ok(Value::Object(result))
+}
+
+pub fn cleanup(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Assets.cleanup", &[])?;
+    // Idle time in minutes; 0 unloads every resident asset (they reload on next use)
+    let minutes = args.first().and_then(|v| v.as_number()).unwrap_or(5.0).max(0.0);
+    let unloaded = asset_manager().unload_unused(std::time::Duration::from_secs_f64(minutes * 60.0));
+    println!("Assets.cleanup: unloaded {} asset(s) idle for {:.1} min", unloaded.len(), minutes);
+
+    Ok(Value::Array(
+        unloaded.into_iter().map(|path| Value::String(path.display().to_string())).collect()
+    ))
+}
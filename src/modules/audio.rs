@@ -1,5 +1,6 @@
 use crate::runtime::{Value, types::{Stream, DataType}};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
 
 // Real-time safe frame counter (no system calls)
 static FRAME_COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -10,7 +11,8 @@ fn get_normalized_time() -> f64 {
     frames as f64 / 44100.0 // Convert to seconds
 }
 
-pub fn mic_input(_args: &[Value]) -> crate::Result<Value> {
+pub fn mic_input(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.mic_input", &[])?;
     // Return a mock audio stream
     Ok(Value::Stream(Stream {
         name: "microphone".to_string(),
@@ -19,12 +21,15 @@ pub fn mic_input(_args: &[Value]) -> crate::Result<Value> {
     }))
 }
 
-pub fn analyze_fft(args: &[Value]) -> crate::Result<Value> {
+pub fn analyze_fft(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.analyze_fft", &["bands", "bins"])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "analyze_fft requires at least 1 argument (audio stream)"));
     }
     
-    let bands = args.get(1)
+    let bands = named.get("bands")
+        .or_else(|| named.get("bins"))
+        .or_else(|| args.get(1))
         .and_then(|v| v.as_number())
         .unwrap_or(8.0) as usize;
     
@@ -46,7 +51,8 @@ pub fn analyze_fft(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Array(fft_data))
 }
 
-pub fn beat_detect(args: &[Value]) -> crate::Result<Value> {
+pub fn beat_detect(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.beat_detect", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "beat_detect requires an audio stream argument"));
     }
@@ -61,7 +67,8 @@ pub fn beat_detect(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Boolean(is_beat))
 }
 
-pub fn load_file(args: &[Value]) -> crate::Result<Value> {
+pub fn load_file(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.load_file", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "load_file requires a filename argument"));
     }
@@ -81,9 +88,15 @@ pub fn load_file(args: &[Value]) -> crate::Result<Value> {
     }))
 }
 
-pub fn play(args: &[Value]) -> crate::Result<Value> {
+pub fn play(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.play", &[])?;
     if args.is_empty() {
-        return Err(crate::errors::synthesis_error(\n            crate::errors::ErrorKind::InvalidExpression,\n            \"🎵 Audio.play() needs an audio stream to play\"\n        )\n        .with_suggestion(\"Try: Audio.play(Audio.mic_input())\")\n        .with_suggestion(\"Connect an audio source like a microphone or file\"));
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎵 Audio.play() needs an audio stream to play"
+        )
+        .with_suggestion("Try: Audio.play(Audio.mic_input())")
+        .with_suggestion("Connect an audio source like a microphone or file"));
     }
     
     match &args[0] {
@@ -91,11 +104,17 @@ pub fn play(args: &[Value]) -> crate::Result<Value> {
             println!("Playing audio stream: {}", stream.name);
             Ok(Value::Boolean(true))
         }
-        _ => Err(crate::errors::synthesis_error(\n            crate::errors::ErrorKind::TypeMismatch,\n            \"🎵 Audio.play() expected an audio stream, not this type\"\n        )\n        .with_suggestion(\"Use Audio.mic_input(), Audio.load_file(), or another audio source\")\n        .with_suggestion(\"Make sure you're passing audio data, not text or numbers\")),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🎵 Audio.play() expected an audio stream, not this type"
+        )
+        .with_suggestion("Use Audio.mic_input(), Audio.load_file(), or another audio source")
+        .with_suggestion("Make sure you're passing audio data, not text or numbers")),
     }
 }
 
-pub fn volume(args: &[Value]) -> crate::Result<Value> {
+pub fn volume(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.volume", &[])?;
     if args.len() < 2 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "volume requires audio stream and volume level"));
     }
@@ -103,7 +122,7 @@ pub fn volume(args: &[Value]) -> crate::Result<Value> {
     let volume_level = args[1].as_number()
         .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "volume level must be a number"))?;
     
-    if volume_level < 0.0 || volume_level > 1.0 {
+    if !(0.0..=1.0).contains(&volume_level) {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "volume level must be between 0.0 and 1.0"));
     }
     
@@ -118,7 +137,8 @@ pub fn volume(args: &[Value]) -> crate::Result<Value> {
 
 // Audio Classification Functions
 
-pub fn classify_beat(args: &[Value]) -> crate::Result<Value> {
+pub fn classify_beat(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.classify_beat", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "classify_beat requires audio data argument"));
     }
@@ -167,7 +187,8 @@ pub fn classify_beat(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn classify_mood(args: &[Value]) -> crate::Result<Value> {
+pub fn classify_mood(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.classify_mood", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "classify_mood requires audio data argument"));
     }
@@ -229,7 +250,8 @@ pub fn classify_mood(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn onset_detection(args: &[Value]) -> crate::Result<Value> {
+pub fn onset_detection(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.onset_detection", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "onset_detection requires audio data"));
     }
@@ -261,7 +283,8 @@ pub fn onset_detection(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn tempo_detection(args: &[Value]) -> crate::Result<Value> {
+pub fn tempo_detection(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.tempo_detection", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "tempo_detection requires audio data"));
     }
@@ -285,7 +308,7 @@ pub fn tempo_detection(args: &[Value]) -> crate::Result<Value> {
             
             // Estimate BPM based on peaks (very simplified)
             let estimated_bpm = (peaks * 6) as f64; // Rough conversion
-            let bpm = estimated_bpm.max(60.0).min(200.0); // Clamp to reasonable range
+            let bpm = estimated_bpm.clamp(60.0, 200.0); // Clamp to reasonable range
             
             println!("Audio.tempo_detection: Detected {} peaks, estimated BPM: {:.1}", 
                      peaks, bpm);
@@ -295,7 +318,8 @@ pub fn tempo_detection(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn spectral_centroid(args: &[Value]) -> crate::Result<Value> {
+pub fn spectral_centroid(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.spectral_centroid", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "spectral_centroid requires audio data"));
     }
@@ -327,4 +351,407 @@ pub fn spectral_centroid(args: &[Value]) -> crate::Result<Value> {
         }
         _ => Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "spectral_centroid requires audio data array")),
     }
-}
\ No newline at end of file
+}
+/// Fade everything to silence over 20ms, send MIDI all-notes-off, black out video and stop the transport
+pub fn audio_panic(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.panic", &[])?;
+    let report = crate::runtime::panic::trigger_panic();
+    let mut fields = std::collections::HashMap::new();
+    fields.insert("fade_seconds".to_string(), Value::Float(report.fade_seconds));
+    fields.insert("midi_messages".to_string(), Value::Integer(report.midi_messages as i64));
+    Ok(Value::Object(fields))
+}
+
+/// Undo a panic: sound fades back in and video returns; the transport stays stopped
+pub fn audio_resume(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.resume", &[])?;
+    crate::runtime::panic::release_panic();
+    Ok(Value::Null)
+}
+
+/// Master meters: LUFS (momentary, short-term, integrated), true peak in dBTP and limiter activity
+pub fn audio_loudness(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.loudness", &[])?;
+    let reading = crate::audio::master::master_bus().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .reading();
+    let mut fields = std::collections::HashMap::new();
+    fields.insert("momentary".to_string(), Value::Float(reading.momentary));
+    fields.insert("short_term".to_string(), Value::Float(reading.short_term));
+    fields.insert("integrated".to_string(), Value::Float(reading.integrated));
+    fields.insert("true_peak".to_string(), Value::Float(reading.true_peak));
+    fields.insert("gain_reduction".to_string(), Value::Float(reading.gain_reduction));
+    fields.insert("limiter".to_string(), Value::Boolean(reading.limiter));
+    fields.insert("clipping".to_string(), Value::Boolean(reading.clipping));
+    Ok(Value::Object(fields))
+}
+
+fn recording_status(session: &crate::audio::recorder::SessionRecorder) -> Value {
+    let mut fields = std::collections::HashMap::new();
+    fields.insert("dir".to_string(), Value::String(session.dir.display().to_string()));
+    fields.insert("stems".to_string(), Value::Boolean(session.stems));
+    fields.insert("seconds".to_string(), Value::Float(session.seconds()));
+    fields.insert("files".to_string(), Value::Array(
+        session.files().iter().map(|path| Value::String(path.display().to_string())).collect()
+    ));
+    Value::Object(fields)
+}
+
+/// Record the performance: Audio.record_session(stems: true, path: "takes/").
+/// Calling it again while a take is running just reports on that take.
+pub fn audio_record_session(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.record_session", &["stems", "path"])?;
+    let mut recorder = crate::audio::recorder::session_recorder().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(session) = recorder.as_ref() {
+        return Ok(recording_status(session));
+    }
+    let stems = named.get("stems").map(|v| v.is_truthy()).unwrap_or(false);
+    let path = match named.get("path") {
+        Some(Value::String(path)) => path.clone(),
+        _ => crate::audio::recorder::DEFAULT_RECORDING_DIR.to_string(),
+    };
+    let session = crate::audio::recorder::SessionRecorder::start(
+        std::path::Path::new(&path),
+        stems,
+        crate::audio::device::audio_settings().sample_rate,
+    )?;
+    let status = recording_status(&session);
+    *recorder = Some(session);
+    Ok(status)
+}
+
+/// Close the running take; returns the files written, or null if nothing was recording
+pub fn audio_stop_recording(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.stop_recording", &[])?;
+    let session = crate::audio::recorder::session_recorder().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .take();
+    let Some(session) = session else {
+        return Ok(Value::Null);
+    };
+    let mut status = recording_status(&session);
+    let dir = session.dir.clone();
+    let files = session.finish().map_err(|error| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::PermissionDenied,
+        format!("📼 Couldn't finish the recording in {}: {}", dir.display(), error)
+    ))?;
+    if let Value::Object(fields) = &mut status {
+        fields.insert("files".to_string(), Value::Array(
+            files.iter().map(|path| Value::String(path.display().to_string())).collect()
+        ));
+    }
+    Ok(status)
+}
+
+/// The stored round-trip latency from `synthesis calibrate`
+pub fn audio_latency(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.latency", &[])?;
+    let compensation = *crate::audio::latency::latency_compensation().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut fields = std::collections::HashMap::new();
+    fields.insert("round_trip_ms".to_string(), Value::Float(compensation.round_trip.as_secs_f64() * 1000.0));
+    fields.insert("samples".to_string(), Value::Integer(compensation.samples(crate::audio::device::audio_settings().sample_rate as f64) as i64));
+    Ok(Value::Object(fields))
+}
+
+/// Change buffer size and sample rate mid-session: Audio.configure(buffer: 128, rate: 48000).
+/// Anything left out keeps its current value; with no arguments this reports the current settings.
+pub fn audio_configure(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.configure", &["buffer", "buffer_size", "rate", "sample_rate"])?;
+    let mut engine = crate::audio::synth::synth_engine().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut master = crate::audio::master::master_bus().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let recording = crate::audio::recorder::session_recorder().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .is_some();
+    let mut device = crate::audio::device::audio_device().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut settings = device.pending().unwrap_or(device.settings);
+    let field = |names: &[&str]| names.iter().find_map(|name| named.get(*name).and_then(|v| v.as_number()));
+    if let Some(buffer) = field(&["buffer", "buffer_size"]) {
+        settings.buffer_size = buffer.max(0.0) as u32;
+    }
+    if let Some(rate) = field(&["rate", "sample_rate"]) {
+        settings.sample_rate = rate.max(0.0) as u32;
+    }
+    if recording && settings.sample_rate != device.settings.sample_rate {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::SampleRateError,
+            "🎚️ Can't change the sample rate while a session is recording"
+        )
+        .with_suggestion("Call Audio.stop_recording() first; the take's files have a fixed rate"));
+    }
+
+    let applied = device.request(settings, &mut engine, &mut master)?;
+    let mut status = settings.to_value();
+    if let Value::Object(fields) = &mut status {
+        fields.insert("pending".to_string(), Value::Boolean(!applied));
+    }
+    Ok(status)
+}
+
+/// `Audio.delay(time: 1/8.note, feedback: 40%, mix: 30%)` sets the master delay; musical
+/// times follow `Timeline.tempo()`. `Audio.delay(false)` removes it, `Audio.delay()` reports it.
+pub fn audio_delay(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.delay", &["time", "feedback", "mix"])?;
+    let (bpm, beats_per_bar) = {
+        let transport = crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        (transport.bpm, transport.beats_per_bar as f64)
+    };
+    let sample_rate = crate::audio::synth::synth_engine().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .sample_rate();
+    let mut slot = crate::audio::effects::master_delay().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if matches!(args.first(), Some(Value::Boolean(false)) | Some(Value::Null)) {
+        return Ok(Value::Boolean(slot.take().is_some()));
+    }
+    let time = match named.get("time").or_else(|| args.first()) {
+        Some(Value::UnitValue(time)) => Some(time.clone()),
+        Some(other) => Some(crate::runtime::units::UnitValue::new(
+            other.as_number().ok_or_else(|| crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🔊 A delay time should be a length, not {}", other.type_name())
+            )
+            .with_suggestion("Try: Audio.delay(time: 1/8.note) or Audio.delay(time: 0.375)"))?,
+            crate::runtime::units::Unit::Second,
+        )),
+        None => None,
+    };
+    let level = |name: &str, max: f32| -> crate::Result<Option<f32>> {
+        match named.get(name).and_then(|v| v.as_number()) {
+            Some(value) if (0.0..=max as f64).contains(&value) => Ok(Some(value as f32)),
+            Some(value) => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🔊 Delay {} of {} is out of range", name, value)
+            )
+            .with_suggestion(format!("Use 0 to {}%, like {}: 40%", (max * 100.0).round(), name))),
+            None => Ok(None),
+        }
+    };
+    let feedback = level("feedback", crate::audio::effects::SYNCED_DELAY_MAX_FEEDBACK)?;
+    let mix = level("mix", 1.0)?;
+
+    if slot.is_none() && args.is_empty() && named.is_empty() {
+        return Ok(Value::Null);
+    }
+    let delay = match slot.as_mut() {
+        Some(delay) => {
+            if let Some(time) = time {
+                delay.set_time(time, bpm, beats_per_bar)?;
+            }
+            delay
+        }
+        None => {
+            let default = crate::runtime::units::UnitValue::new(0.25, crate::runtime::units::Unit::Note);
+            slot.insert(crate::audio::effects::SyncedDelay::new(time.unwrap_or(default), sample_rate as f32, bpm, beats_per_bar)?)
+        }
+    };
+    if let Some(feedback) = feedback {
+        delay.feedback = feedback;
+    }
+    if let Some(mix) = mix {
+        delay.wet_mix = mix;
+    }
+
+    let mut status = std::collections::HashMap::new();
+    status.insert("time".to_string(), Value::UnitValue(delay.time.clone()));
+    status.insert("seconds".to_string(), Value::Float(delay.time.to_seconds(bpm, beats_per_bar).unwrap_or(0.0)));
+    status.insert("feedback".to_string(), Value::Float(delay.feedback as f64));
+    status.insert("mix".to_string(), Value::Float(delay.wet_mix as f64));
+    Ok(Value::Object(status))
+}
+
+/// `Audio.apply_reverb(room_size: 0.8, wet_mix: 30%)` puts a reverb after `Audio.delay()` on the
+/// way to the master; `Audio.apply_reverb(false)` removes it, `Audio.apply_reverb()` reports it.
+/// Given a list of samples it returns them through a reverb of their own instead, and a stream
+/// comes back as it is, since everything playing goes through the master's.
+pub fn audio_apply_reverb(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.apply_reverb", &["room_size", "wet_mix"])?;
+    let amount = |name: &str| -> crate::Result<Option<f32>> {
+        match named.get(name).map(|v| (v, v.as_number())) {
+            Some((_, Some(value))) if (0.0..=1.0).contains(&value) => Ok(Some(value as f32)),
+            Some((v, _)) => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🔊 Reverb {} of {} is out of range", name, v)
+            )
+            .with_suggestion(format!("Use 0 to 1, like {}: 0.5", name))),
+            None => Ok(None),
+        }
+    };
+    let room_size = amount("room_size")?;
+    let wet_mix = amount("wet_mix")?;
+    let sample_rate = crate::audio::synth::synth_engine().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .sample_rate();
+    let configure = |reverb: &mut crate::audio::effects::Reverb| {
+        if let Some(room_size) = room_size {
+            reverb.set_room_size(room_size);
+        }
+        if let Some(wet_mix) = wet_mix {
+            reverb.set_wet_mix(wet_mix);
+        }
+    };
+
+    if let Some(Value::Array(samples)) = args.first() {
+        let mut reverb = crate::audio::effects::Reverb::new(sample_rate as f32);
+        configure(&mut reverb);
+        return samples.iter()
+            .map(|sample| match sample.as_number() {
+                Some(sample) => Ok(Value::Float(reverb.process(sample as f32) as f64)),
+                None => Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🔊 Audio.apply_reverb() needs a list of samples, and {} isn't one", sample.type_name())
+                )),
+            })
+            .collect::<crate::Result<Vec<_>>>()
+            .map(Value::Array);
+    }
+
+    let mut slot = crate::audio::effects::master_reverb().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if matches!(args.first(), Some(Value::Boolean(false)) | Some(Value::Null)) {
+        return Ok(Value::Boolean(slot.take().is_some()));
+    }
+    if slot.is_none() && args.is_empty() && named.is_empty() {
+        return Ok(Value::Null);
+    }
+    let reverb = slot.get_or_insert_with(|| crate::audio::effects::Reverb::new(sample_rate as f32));
+    configure(reverb);
+    if let Some(stream @ Value::Stream(_)) = args.first() {
+        return Ok(stream.clone());
+    }
+
+    let mut status = std::collections::HashMap::new();
+    status.insert("room_size".to_string(), Value::Float(reverb.room_size() as f64));
+    status.insert("wet_mix".to_string(), Value::Float(reverb.wet_mix() as f64));
+    Ok(Value::Object(status))
+}
+
+fn device_name_arg(args: &[Value], function: &str) -> crate::Result<String> {
+    match args.first() {
+        Some(Value::String(name)) => Ok(name.clone()),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🎚️ Audio.{}() needs a device name", function)
+        )
+        .with_suggestion(format!("Part of the name is enough: Audio.{}(\"Scarlett\")", function))),
+    }
+}
+
+fn open_device(args: &[Value], function: &str, direction: crate::audio::aggregate::DeviceDirection) -> crate::Result<Value> {
+    let name = device_name_arg(args, function)?;
+    let (full_name, _) = crate::audio::input::open_device_stream(&name, direction)?;
+    let aggregate = crate::audio::aggregate::aggregate_device().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(aggregate.stream(&full_name, direction).map(|stream| stream.to_value()).unwrap_or(Value::Null))
+}
+
+/// Send the master mix to another device as well: Audio.open_output("HDMI")
+pub fn audio_open_output(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.open_output", &[])?;
+    open_device(args, "open_output", crate::audio::aggregate::DeviceDirection::Output)
+}
+
+/// Capture from another device as well: Audio.open_input("Scarlett")
+pub fn audio_open_input(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.open_input", &[])?;
+    open_device(args, "open_input", crate::audio::aggregate::DeviceDirection::Input)
+}
+
+/// Capture whatever the machine is playing: Audio.loopback(), or Audio.loopback("BlackHole")
+/// for a particular device. Read it with Audio.device_input() like any other extra input
+pub fn audio_loopback(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.loopback", &[])?;
+    let wanted = match args.first() {
+        Some(Value::String(name)) => Some(name.as_str()),
+        _ => None,
+    };
+    let (full_name, _) = crate::audio::loopback::open_loopback_stream(wanted)?;
+    let aggregate = crate::audio::aggregate::aggregate_device().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(aggregate.stream(&full_name, crate::audio::aggregate::DeviceDirection::Input).map(|stream| stream.to_value()).unwrap_or(Value::Null))
+}
+
+/// Close a device opened with open_output/open_input; returns whether anything was open
+pub fn audio_close_device(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.close_device", &[])?;
+    let name = device_name_arg(args, "close_device")?;
+    let closed = crate::audio::aggregate::aggregate_device().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .close(&name);
+    Ok(Value::Boolean(closed > 0))
+}
+
+/// Every extra device stream with its measured clock drift and queue state
+pub fn audio_devices(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.devices", &[])?;
+    let aggregate = crate::audio::aggregate::aggregate_device().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Array(aggregate.streams().iter().map(|stream| stream.to_value()).collect()))
+}
+
+/// The latest block from an extra input, resampled to the engine's rate
+pub fn audio_device_input(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.device_input", &[])?;
+    let name = device_name_arg(args, "device_input")?;
+    let aggregate = crate::audio::aggregate::aggregate_device().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let stream = aggregate.streams().iter()
+        .find(|stream| stream.direction == crate::audio::aggregate::DeviceDirection::Input && stream.name.to_lowercase().contains(&name.to_lowercase()))
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::AudioDeviceError,
+            format!("🎚️ No input named \"{}\" is open", name)
+        )
+        .with_suggestion(format!("Open it first: Audio.open_input(\"{}\")", name)))?;
+    Ok(Value::Array(stream.last_input.iter().map(|s| Value::Float(*s as f64)).collect()))
+}
+
+/// Send the cue bus to an extra output: Audio.cue_output("Headphones", level: 0.8).
+/// The device is opened if it isn't already; null stops cueing to any device, and with no
+/// name this reports the current cue output.
+pub fn audio_cue_output(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.cue_output", &["level"])?;
+    use crate::audio::aggregate::{aggregate_device, DeviceDirection};
+    if let Some(level) = named.get("level").and_then(|v| v.as_number()) {
+        crate::audio::cue::cue_bus().lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .level = level.clamp(0.0, 2.0) as f32;
+    }
+    let name = match args.first() {
+        Some(Value::Null) => {
+            aggregate_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).cue_output = None;
+            return Ok(Value::Null);
+        }
+        None => {
+            let aggregate = aggregate_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            return Ok(aggregate.cue_output.clone().map(Value::String).unwrap_or(Value::Null));
+        }
+        _ => device_name_arg(args, "cue_output")?,
+    };
+    let open = aggregate_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        .streams().iter()
+        .find(|stream| stream.direction == DeviceDirection::Output && stream.name.to_lowercase().contains(&name.to_lowercase()))
+        .map(|stream| stream.name.clone());
+    let full_name = match open {
+        Some(full_name) => full_name,
+        None => crate::audio::input::open_device_stream(&name, DeviceDirection::Output)?.0,
+    };
+    aggregate_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).cue_output = Some(full_name.clone());
+    Ok(Value::String(full_name))
+}
+
+/// What's on the cue bus: each synth with "preview" (held out of the main mix) or "listen"
+pub fn audio_cues(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Audio.cues", &[])?;
+    let cue = crate::audio::cue::cue_bus().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let fields = cue.sources()
+        .map(|(name, mode)| (name.to_string(), Value::String(match mode {
+            crate::audio::cue::CueMode::Preview => "preview".to_string(),
+            crate::audio::cue::CueMode::Listen => "listen".to_string(),
+        })))
+        .collect();
+    Ok(Value::Object(fields))
+}
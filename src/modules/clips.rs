@@ -0,0 +1,281 @@
+use crate::audio::clips::{clip_grid, Clip, ClipContent, ClipGrid, FollowAction};
+use crate::hardware::controllers::{grid_controller, GridController, GridProfile, GridView};
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn index(args: &[Value], position: usize, what: &str, function: &str) -> crate::Result<usize> {
+    match args.get(position).and_then(|v| v.as_number()) {
+        Some(n) if n >= 0.0 && n.fract() == 0.0 => Ok(n as usize),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🟩 Clips.{}() needs a {} number, counting from 0", function, what)
+        )
+        .with_suggestion(format!("Try: Clips.{}(0, 0)", function))),
+    }
+}
+
+/// Tempo and bar length the grid measures in
+fn tempo() -> (f64, f64) {
+    let transport = crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    (transport.bpm, transport.beats_per_bar as f64)
+}
+
+/// A length in beats: plain numbers are beats, unit values like 1.bar or 1/8.note convert
+fn beats(value: &Value, what: &str) -> crate::Result<f64> {
+    let (bpm, beats_per_bar) = tempo();
+    let beats = match value {
+        Value::UnitValue(unit) => unit.to_seconds(bpm, beats_per_bar).map(|seconds| seconds * bpm / 60.0),
+        other => other.as_number(),
+    };
+    match beats {
+        Some(beats) if beats >= 0.0 && beats.is_finite() => Ok(beats),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🟩 {} should be a length like 1.bar, 2.beats or 1/4.note", what)
+        )),
+    }
+}
+
+fn status(grid: &ClipGrid) -> Value {
+    let states = (0..grid.tracks())
+        .map(|track| Value::Array((0..grid.scenes())
+            .map(|scene| Value::String(grid.state(track, scene).as_str().to_string()))
+            .collect()))
+        .collect();
+    let playing = grid.playing().into_iter()
+        .map(|scene| scene.map(|s| Value::Integer(s as i64)).unwrap_or(Value::Null))
+        .collect();
+    let mut fields = HashMap::new();
+    fields.insert("states".to_string(), Value::Array(states));
+    fields.insert("playing".to_string(), Value::Array(playing));
+    fields.insert("beat".to_string(), Value::Float(grid.beat()));
+    fields.insert("quantum".to_string(), Value::Float(grid.quantum));
+    Value::Object(fields)
+}
+
+/// `Clips.set(0, 1, samples, length: 2.bars, follow: "next")` or
+/// `Clips.set(1, 0, "c3 e3 g3 ~", synth: "pluck")` for a note pattern
+pub fn clips_set(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.set", &["synth", "channel", "length", "name", "gain", "loop", "follow", "after"])?;
+    let track = index(args, 0, "track", "set")?;
+    let scene = index(args, 1, "scene", "set")?;
+    let (bpm, beats_per_bar) = tempo();
+    let sample_rate = crate::audio::synth::synth_engine().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .sample_rate();
+
+    let (content, natural_length) = match args.get(2) {
+        Some(Value::Array(samples)) => {
+            let samples: Vec<f32> = samples.iter().map(|s| s.as_number().unwrap_or(0.0) as f32).collect();
+            // Round to whole beats so the loop sits on the grid
+            let seconds = samples.len() as f64 / sample_rate;
+            let length = (seconds * bpm / 60.0).round().max(1.0);
+            (ClipContent::Audio { samples: Arc::new(samples), sample_rate }, length)
+        }
+        Some(Value::String(source)) => {
+            let pattern = crate::audio::mini_notation::MiniPattern::parse(source)?;
+            let synth = match named.get("synth") {
+                Some(Value::String(name)) => {
+                    let engine = crate::audio::synth::synth_engine().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if engine.graph(name).is_none() {
+                        return Err(crate::errors::synthesis_error(
+                            crate::errors::ErrorKind::UnknownFunction,
+                            format!("🟩 No synth called '{}' to play the clip", name)
+                        )
+                        .with_suggestion("Define the synth before setting the clip, or leave synth: out to send MIDI"));
+                    }
+                    Some(name.clone())
+                }
+                _ => None,
+            };
+            let channel = named.get("channel").and_then(|v| v.as_number()).unwrap_or(1.0);
+            if !(1.0..=16.0).contains(&channel) {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    format!("🟩 MIDI channel {} doesn't exist", channel)
+                )
+                .with_suggestion("Channels go from 1 to 16"));
+            }
+            (ClipContent::Pattern { pattern, synth, channel: channel as u8 - 1 }, beats_per_bar)
+        }
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🟩 A clip is a sample buffer or a note pattern"
+        )
+        .with_suggestion("Try: Clips.set(0, 0, Signal.sine(220, 2)) or Clips.set(0, 0, \"c3 e3 g3 ~\", synth: \"pluck\")")),
+    };
+
+    let length = match named.get("length") {
+        Some(value) => beats(value, "A clip length")?,
+        None => natural_length,
+    };
+    if length <= 0.0 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🟩 A clip needs some length to loop over"
+        )
+        .with_suggestion("Try: length: 1.bar"));
+    }
+    let name = match named.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => format!("{}:{}", track, scene),
+    };
+    let mut clip = Clip::new(&name, content, length);
+    if let Some(gain) = named.get("gain").and_then(|v| v.as_number()) {
+        clip.gain = gain.max(0.0) as f32;
+    }
+    if let Some(looping) = named.get("loop") {
+        clip.looping = looping.is_truthy();
+    }
+    if let Some(follow) = named.get("follow") {
+        clip.follow = match follow {
+            Value::String(action) => Some(FollowAction::parse(action)?),
+            _ => None,
+        };
+    }
+    if let Some(after) = named.get("after").and_then(|v| v.as_number()) {
+        clip.follow_after = after.max(1.0) as u32;
+    }
+
+    let mut grid = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    grid.set(track, scene, clip)?;
+    Ok(status(&grid))
+}
+
+pub fn clips_launch(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.launch", &[])?;
+    let track = index(args, 0, "track", "launch")?;
+    let scene = index(args, 1, "scene", "launch")?;
+    let mut grid = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    grid.launch(track, scene)?;
+    Ok(status(&grid))
+}
+
+pub fn clips_scene(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.scene", &[])?;
+    let scene = index(args, 0, "scene", "scene")?;
+    let mut grid = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    grid.launch_scene(scene);
+    Ok(status(&grid))
+}
+
+/// `Clips.stop(2)` stops one track at the next boundary; `Clips.stop()` stops them all
+pub fn clips_stop(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.stop", &[])?;
+    let mut grid = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if args.is_empty() {
+        grid.stop_all();
+    } else {
+        grid.stop(index(args, 0, "track", "stop")?);
+    }
+    Ok(status(&grid))
+}
+
+pub fn clips_clear(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.clear", &[])?;
+    let track = index(args, 0, "track", "clear")?;
+    let scene = index(args, 1, "scene", "clear")?;
+    let cleared = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear(track, scene);
+    Ok(Value::Boolean(cleared))
+}
+
+/// `Clips.quantize(1.bar)`, `Clips.quantize(1/4.note)`, or `Clips.quantize(0)` to launch at once
+pub fn clips_quantize(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.quantize", &[])?;
+    let mut grid = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(value) = args.first() {
+        grid.quantum = beats(value, "The launch quantum")?;
+    }
+    Ok(Value::Float(grid.quantum))
+}
+
+pub fn clips_grid(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.grid", &[])?;
+    Ok(status(&clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner())))
+}
+
+/// One GUI pad per filled slot plus a launch button per scene; call it every frame
+pub fn clips_pads(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.pads", &[])?;
+    let mut grid = clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for scene in 0..grid.scenes() {
+        for track in 0..grid.tracks() {
+            let Some(clip) = grid.clip(track, scene) else {
+                continue;
+            };
+            let label = format!("Clip {}", clip.name);
+            let pressed = crate::runtime::declare_control(
+                crate::runtime::RemoteControl::new(label, "button", Value::Boolean(false))
+            ).is_truthy();
+            if pressed {
+                grid.toggle(track, scene);
+            }
+        }
+        let pressed = crate::runtime::declare_control(
+            crate::runtime::RemoteControl::new(format!("Scene {}", scene), "button", Value::Boolean(false))
+        ).is_truthy();
+        if pressed {
+            grid.launch_scene(scene);
+        }
+    }
+    Ok(status(&grid))
+}
+
+fn controller_status(controller: &GridController) -> Value {
+    let mut fields = HashMap::new();
+    fields.insert("profile".to_string(), Value::String(controller.profile.name.clone()));
+    fields.insert("port".to_string(), Value::String(controller.profile.port.clone()));
+    fields.insert("view".to_string(), Value::String(controller.view.as_str().to_string()));
+    fields.insert("track".to_string(), Value::Integer(controller.column_offset as i64));
+    fields.insert("scene".to_string(), Value::Integer(controller.row_offset as i64));
+    Value::Object(fields)
+}
+
+/// `Clips.controller("launchpad")` plays the grid from a pad controller and lights it up;
+/// `view: "meters"` turns the pads into track meters and `Clips.controller(false)` lets go
+pub fn clips_controller(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Clips.controller", &["port", "view"])?;
+    let mut attached = grid_controller().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let profile = match args.first() {
+        Some(Value::Boolean(false)) | Some(Value::Null) => {
+            if let Some(mut controller) = attached.take() {
+                let off = controller.blackout();
+                crate::audio::midi::send_midi_to(&controller.profile.port, &off);
+            }
+            return Ok(Value::Boolean(false));
+        }
+        Some(Value::Boolean(true)) => Some(GridProfile::launchpad()),
+        Some(Value::String(name)) => Some(GridProfile::builtin(name).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🟩 No controller profile called '{}'", name)
+        )
+        .with_suggestion(format!("Built-in profiles: {}", GridProfile::BUILTIN.join(", "))))?),
+        _ => None,
+    };
+    if let Some(mut profile) = profile {
+        if let Some(Value::String(port)) = named.get("port") {
+            profile.port = port.clone();
+        }
+        if let Some(mut previous) = attached.take() {
+            let off = previous.blackout();
+            crate::audio::midi::send_midi_to(&previous.profile.port, &off);
+        }
+        *attached = Some(GridController::new(profile));
+    }
+    let Some(controller) = attached.as_mut() else {
+        return Ok(Value::Boolean(false));
+    };
+    if let Some(view) = named.get("view") {
+        controller.view = match view {
+            Value::String(name) if name == "meters" => GridView::Meters,
+            Value::String(name) if name == "clips" || name == "surface" => GridView::Surface,
+            other => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🟩 '{}' isn't a controller view", other)
+            )
+            .with_suggestion("Views are \"clips\" and \"meters\"")),
+        };
+    }
+    Ok(controller_status(controller))
+}
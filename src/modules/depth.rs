@@ -0,0 +1,152 @@
+use crate::hardware::depth::{depth_camera, open_depth_camera, DepthCamera, DepthCameraKind};
+use crate::runtime::types::{DataType, Stream};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+fn no_camera() -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::AudioDeviceError,
+        "📡 No depth camera is open"
+    )
+    .with_suggestion("Open one first: Depth.open(camera: \"kinect\")")
+}
+
+fn stream(name: &str, data_type: DataType) -> Value {
+    Value::Stream(Stream { name: name.to_string(), data_type, sample_rate: None })
+}
+
+fn position((x, y): (f32, f32)) -> Value {
+    let mut fields = HashMap::new();
+    fields.insert("x".to_string(), Value::Float(x as f64));
+    fields.insert("y".to_string(), Value::Float(y as f64));
+    Value::Object(fields)
+}
+
+/// Metres from a script, as millimetres for the camera
+fn millimetres(options: &HashMap<String, Value>, name: &str, default: u16) -> crate::Result<u16> {
+    match options.get(name) {
+        None => Ok(default),
+        Some(value) => match value.as_number() {
+            Some(metres) if (0.0..=65.0).contains(&metres) => Ok((metres * 1000.0) as u16),
+            _ => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("📡 {}: should be a distance in metres, not {}", name, value)
+            )
+            .with_suggestion("Try: Depth.open(near: 0.5, far: 4)")),
+        },
+    }
+}
+
+/// `Depth.open(camera: "kinect", near: 0.5, far: 4, step: 4)` starts a depth camera and returns
+/// its `texture`, `mask` and `points` streams; a device number picks between several cameras
+pub fn depth_open(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Depth.open", &["camera", "near", "far", "step"])?;
+    let device = match args.first() {
+        Some(Value::Integer(device)) => *device as i32,
+        _ => 0,
+    };
+    let kind = match named.get("camera") {
+        Some(Value::String(name)) => DepthCameraKind::from_name(name).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📡 '{}' isn't a depth camera Synthesis knows", name)
+        )
+        .with_suggestion("Cameras are \"kinect\" and \"realsense\""))?,
+        _ => DepthCameraKind::Kinect,
+    };
+    let near = millimetres(named, "near", 500)?;
+    let far = millimetres(named, "far", 4000)?;
+    if near >= far {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "📡 near: has to be closer than far:"
+        )
+        .with_suggestion("Try: Depth.open(near: 0.5, far: 4)"));
+    }
+    let step = named.get("step").and_then(|v| v.as_number()).map_or(4, |step| step.max(1.0) as usize);
+
+    let mut camera = depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Setup that reruns keeps the running camera and only changes its settings
+    if !camera.as_ref().is_some_and(|camera| camera.kind == kind) {
+        *camera = Some(DepthCamera::new(kind).with_source(open_depth_camera(kind, device)?));
+    }
+    if let Some(camera) = camera.as_mut() {
+        camera.near = near;
+        camera.far = far;
+        camera.step = step;
+    }
+
+    let mut streams = HashMap::new();
+    streams.insert("texture".to_string(), stream("depth.texture", DataType::Visual));
+    streams.insert("mask".to_string(), stream("depth.mask", DataType::Visual));
+    streams.insert("points".to_string(), stream("depth.points", DataType::Generic));
+    Ok(Value::Object(streams))
+}
+
+/// `Depth.at(0.5, 0.5)`: metres to whatever is at that spot in the image, or 0 without a reading
+pub fn depth_at(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Depth.at", &[])?;
+    let (x, y) = match (args.first().and_then(|v| v.as_number()), args.get(1).and_then(|v| v.as_number())) {
+        (Some(x), Some(y)) => (x, y),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "📡 Depth.at() needs x and y, from 0 to 1 across and down the image"
+        )
+        .with_suggestion("Try: Depth.at(0.5, 0.5)")),
+    };
+    let camera = depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let camera = camera.as_ref().ok_or_else(no_camera)?;
+    Ok(Value::Float(camera.depth_at(x, y).unwrap_or(0.0) as f64))
+}
+
+/// The latest point cloud as [x, y, z] in metres, x right, y up and z away from the camera
+pub fn depth_points(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Depth.points", &[])?;
+    let camera = depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let camera = camera.as_ref().ok_or_else(no_camera)?;
+    let points = camera.points()
+        .into_iter()
+        .map(|point| Value::Array(point.iter().map(|&v| Value::Float(v as f64)).collect()))
+        .collect();
+    Ok(Value::Array(points))
+}
+
+/// People in view, largest first, with their centre, distance, head and hands
+pub fn depth_users(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Depth.users", &[])?;
+    let camera = depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let camera = camera.as_ref().ok_or_else(no_camera)?;
+    let users = camera.users()
+        .into_iter()
+        .map(|user| {
+            let mut fields = HashMap::new();
+            fields.insert("id".to_string(), Value::Integer(user.id as i64));
+            fields.insert("x".to_string(), Value::Float(user.center.0 as f64));
+            fields.insert("y".to_string(), Value::Float(user.center.1 as f64));
+            fields.insert("distance".to_string(), Value::Float(user.distance as f64));
+            fields.insert("size".to_string(), Value::Float(user.size as f64));
+            fields.insert("head".to_string(), position(user.head));
+            fields.insert("left_hand".to_string(), position(user.left_hand));
+            fields.insert("right_hand".to_string(), position(user.right_hand));
+            Value::Object(fields)
+        })
+        .collect();
+    Ok(Value::Array(users))
+}
+
+/// `Depth.background()` takes the next frame as the empty room; `Depth.background(false)` forgets it
+pub fn depth_background(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Depth.background", &[])?;
+    let mut camera = depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let camera = camera.as_mut().ok_or_else(no_camera)?;
+    match args.first() {
+        Some(Value::Boolean(false)) => camera.forget_background(),
+        _ => camera.learn_background(),
+    }
+    Ok(Value::Null)
+}
+
+pub fn depth_close(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Depth.close", &[])?;
+    let mut camera = depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(camera.take().is_some()))
+}
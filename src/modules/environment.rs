@@ -0,0 +1,178 @@
+use crate::runtime::astronomy::{daylight, moon_phase, sun_position, sun_times};
+use crate::runtime::weather::{current_weather, WEATHER_KEY_VARIABLE};
+use crate::runtime::Value;
+use chrono::{Datelike, TimeZone, Timelike};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+static LOCATION: OnceLock<Mutex<Option<(f64, f64)>>> = OnceLock::new();
+
+/// Where the installation is, once `Environment.location()` has been told
+fn location() -> &'static Mutex<Option<(f64, f64)>> {
+    LOCATION.get_or_init(|| Mutex::new(None))
+}
+
+/// `lat:`/`lon:` if given, otherwise the location set for the whole script
+fn place(options: &HashMap<String, Value>, function: &str) -> crate::Result<(f64, f64)> {
+    let given = (
+        options.get("lat").and_then(|v| v.as_number()),
+        options.get("lon").and_then(|v| v.as_number()),
+    );
+    let set = *location().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match (given, set) {
+        ((Some(lat), Some(lon)), _) => Ok((lat, lon)),
+        (_, Some(place)) => Ok(place),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🌍 Environment.{}() needs to know where the installation is", function)
+        )
+        .with_suggestion("Set it once before the loop: Environment.location(52.37, 4.90)")
+        .with_suggestion(format!("Or pass it along: Environment.{}(lat: 52.37, lon: 4.90)", function))),
+    }
+}
+
+/// Now in Unix seconds, or `time:` for trying out another moment
+fn unix_time(options: &HashMap<String, Value>) -> f64 {
+    options.get("time").and_then(|v| v.as_number()).unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .unwrap_or_default()
+    })
+}
+
+fn local_time(unix: f64) -> chrono::DateTime<chrono::Local> {
+    chrono::Local.timestamp_opt(unix.floor() as i64, 0).single().unwrap_or_else(chrono::Local::now)
+}
+
+/// Hours past local midnight, like 18.5 for half past six in the evening
+fn local_hour(unix: f64) -> f64 {
+    let time = local_time(unix);
+    time.hour() as f64 + time.minute() as f64 / 60.0 + time.second() as f64 / 3600.0
+}
+
+/// `Environment.location(52.37, 4.90)` sets where the installation is, in degrees north and east;
+/// with no arguments it returns `{ lat, lon }`, or null if it isn't set
+pub fn environment_location(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Environment.location", &["lat", "lon"])?;
+    let number = |index: usize, name: &str| args.get(index)
+        .and_then(|v| v.as_number())
+        .or_else(|| named.get(name).and_then(|v| v.as_number()));
+    let mut location = location().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match (number(0, "lat"), number(1, "lon")) {
+        (Some(lat), Some(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => *location = Some((lat, lon)),
+        (None, None) => {}
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🌍 Environment.location() needs a latitude from -90 to 90 and a longitude from -180 to 180"
+        )
+        .with_suggestion("South and west are negative: Environment.location(-33.87, 151.21) for Sydney")),
+    }
+    Ok(match *location {
+        Some((lat, lon)) => {
+            let mut fields = HashMap::new();
+            fields.insert("lat".to_string(), Value::Float(lat));
+            fields.insert("lon".to_string(), Value::Float(lon));
+            Value::Object(fields)
+        }
+        None => Value::Null,
+    })
+}
+
+/// `Environment.sun()`: `{ sunrise, noon, sunset }` as local hours (null during polar night or
+/// midnight sun), `day_length` in hours, the sun's `elevation` and `azimuth` in degrees, and
+/// `daylight` easing from 0 at the end of twilight to 1 once the sun is up
+pub fn environment_sun(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Environment.sun", &["lat", "lon", "time"])?;
+    let (lat, lon) = place(named, "sun")?;
+    let now = unix_time(named);
+    let times = sun_times(now, lat, lon);
+    let position = sun_position(now, lat, lon);
+    let hour = |time: Option<f64>| time.map(|time| Value::Float(local_hour(time))).unwrap_or(Value::Null);
+
+    let mut fields = HashMap::new();
+    fields.insert("sunrise".to_string(), hour(times.sunrise));
+    fields.insert("noon".to_string(), hour(Some(times.noon)));
+    fields.insert("sunset".to_string(), hour(times.sunset));
+    fields.insert("day_length".to_string(), Value::Float(times.day_length));
+    fields.insert("elevation".to_string(), Value::Float(position.elevation));
+    fields.insert("azimuth".to_string(), Value::Float(position.azimuth));
+    fields.insert("daylight".to_string(), Value::Float(daylight(position.elevation)));
+    fields.insert("is_day".to_string(), Value::Boolean(position.elevation > -0.833));
+    Ok(Value::Object(fields))
+}
+
+/// `Environment.moon()`: `{ phase, illumination, age, name }`, where phase runs 0 (new) to 0.5
+/// (full) and back towards 1; the same everywhere, so it needs no location
+pub fn environment_moon(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Environment.moon", &["time"])?;
+    let moon = moon_phase(unix_time(named));
+    let mut fields = HashMap::new();
+    fields.insert("phase".to_string(), Value::Float(moon.phase));
+    fields.insert("illumination".to_string(), Value::Float(moon.illumination));
+    fields.insert("age".to_string(), Value::Float(moon.age));
+    fields.insert("name".to_string(), Value::String(moon.name().to_string()));
+    Ok(Value::Object(fields))
+}
+
+/// `Environment.time_of_day()`: the local `hour` with its fraction, `day` and `year` as 0-1
+/// progress, the `period` ("night", "morning", "afternoon" or "evening") and the `weekday`
+pub fn environment_time_of_day(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Environment.time_of_day", &["time"])?;
+    let unix = unix_time(named);
+    let time = local_time(unix);
+    let hour = local_hour(unix);
+    let days_in_year = if chrono::NaiveDate::from_ymd_opt(time.year(), 2, 29).is_some() { 366.0 } else { 365.0 };
+    let period = match hour {
+        h if h < 6.0 => "night",
+        h if h < 12.0 => "morning",
+        h if h < 18.0 => "afternoon",
+        h if h < 22.0 => "evening",
+        _ => "night",
+    };
+
+    let mut fields = HashMap::new();
+    fields.insert("hour".to_string(), Value::Float(hour));
+    fields.insert("day".to_string(), Value::Float(hour / 24.0));
+    fields.insert("year".to_string(), Value::Float((time.ordinal0() as f64 + hour / 24.0) / days_in_year));
+    fields.insert("period".to_string(), Value::String(period.to_string()));
+    fields.insert("weekday".to_string(), Value::String(time.weekday().to_string()));
+    fields.insert("day_of_year".to_string(), Value::Integer(time.ordinal() as i64));
+    Ok(Value::Object(fields))
+}
+
+/// `Environment.weather(key: "...")`: `{ temperature, feels_like, humidity, pressure, wind_speed,
+/// wind_direction, clouds, rain, condition, age }` from OpenWeatherMap, in metric units. Refreshes
+/// every ten minutes in the background; null until the first report arrives
+pub fn environment_weather(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Environment.weather", &["lat", "lon", "key"])?;
+    let (lat, lon) = place(named, "weather")?;
+    let key = match named.get("key") {
+        Some(Value::String(key)) => Some(key.clone()),
+        _ => std::env::var(WEATHER_KEY_VARIABLE).ok(),
+    };
+    let Some(key) = key.filter(|key| !key.trim().is_empty()) else {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🌦️ Environment.weather() needs an OpenWeatherMap API key"
+        )
+        .with_suggestion(format!("Get a free key at openweathermap.org and set {} before starting", WEATHER_KEY_VARIABLE))
+        .with_suggestion("Or pass it in: Environment.weather(key: \"your key\")"));
+    };
+
+    let Some((report, age)) = current_weather(lat, lon, key.trim()) else {
+        return Ok(Value::Null);
+    };
+    let mut fields = HashMap::new();
+    fields.insert("temperature".to_string(), Value::Float(report.temperature));
+    fields.insert("feels_like".to_string(), Value::Float(report.feels_like));
+    fields.insert("humidity".to_string(), Value::Float(report.humidity));
+    fields.insert("pressure".to_string(), Value::Float(report.pressure));
+    fields.insert("wind_speed".to_string(), Value::Float(report.wind_speed));
+    fields.insert("wind_direction".to_string(), Value::Float(report.wind_direction));
+    fields.insert("clouds".to_string(), Value::Float(report.clouds));
+    fields.insert("rain".to_string(), Value::Float(report.rain));
+    fields.insert("condition".to_string(), Value::String(report.condition));
+    fields.insert("age".to_string(), Value::Float(age.as_secs_f64()));
+    Ok(Value::Object(fields))
+}
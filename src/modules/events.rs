@@ -0,0 +1,150 @@
+use crate::runtime::{Event, EventStream, Value};
+use std::collections::HashMap;
+
+/// Seconds for a time or length: plain numbers are seconds, notes, beats and bars follow the tempo
+fn seconds(value: &Value) -> Option<f64> {
+    crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).seconds(value)
+}
+
+/// One event from `{ time, value }`, `[time, value]`, or what `Timeline.pattern_events()` gives
+fn event(item: &Value) -> Option<Event> {
+    match item {
+        Value::Object(fields) => {
+            let time = seconds(fields.get("time").or_else(|| fields.get("start"))?)?;
+            // Without a `value` the rest of the object is the event, like { time: 0, note: 60, velocity: 100 }
+            let value = fields.get("value").cloned().unwrap_or_else(|| {
+                Value::Object(fields.iter().filter(|(key, _)| key.as_str() != "time").map(|(k, v)| (k.clone(), v.clone())).collect())
+            });
+            Some(Event { time, value })
+        }
+        Value::Array(pair) if pair.len() == 2 => Some(Event { time: seconds(&pair[0])?, value: pair[1].clone() }),
+        _ => None,
+    }
+}
+
+fn event_value(event: &Event) -> Value {
+    let mut fields = HashMap::new();
+    fields.insert("time".to_string(), Value::Float(event.time));
+    fields.insert("value".to_string(), event.value.clone());
+    Value::Object(fields)
+}
+
+/// `Events.from([{ time: 0, value: 60 }, [0.5, 64]])`: an event stream from a list
+pub fn events_from(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Events.from", &[])?;
+    let items = match args.first() {
+        Some(Value::Events(events)) => return Ok(Value::Events(events.clone())),
+        Some(Value::Array(items)) => items,
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "📨 Events.from() needs a list of events"
+        )
+        .with_suggestion("Try: Events.from([{ time: 0, value: 60 }, { time: 0.5, value: 64 }])")),
+    };
+    let events = items.iter()
+        .map(|item| event(item).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("📨 {} isn't an event: it needs a time", item)
+        )
+        .with_suggestion("Write events as { time: 0.5, value: 64 } or [0.5, 64]")))
+        .collect::<crate::Result<Vec<_>>>()?;
+    Ok(Value::Events(EventStream::new(events)))
+}
+
+/// `Events.merge(drums, bass)`: every event of each, in time order
+pub fn events_merge(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Events.merge", &[])?;
+    let streams = args.iter()
+        .map(|arg| match arg {
+            Value::Events(events) => Ok(events.clone()),
+            other => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("📨 Events.merge() merges event streams, not {}", other.type_name())
+            )
+            .with_suggestion("Turn lists into events first: Events.merge(Events.from(notes), drums)")),
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    Ok(Value::Events(EventStream::merge(&streams)))
+}
+
+/// Whether `filter()`'s argument lets an event's value through: a range takes numbers inside
+/// it, an object takes objects with the same fields, anything else has to be equal
+fn keeps(criterion: &Value, value: &Value) -> bool {
+    match (criterion, value) {
+        (Value::Range { start, end, inclusive }, value) => value.as_number().is_some_and(|n| {
+            n >= *start as f64 && if *inclusive { n <= *end as f64 } else { n < *end as f64 }
+        }),
+        (Value::Object(wanted), Value::Object(fields)) => wanted.iter().all(|(key, want)| fields.get(key) == Some(want)),
+        (criterion, value) => match (criterion.as_number(), value.as_number()) {
+            (Some(a), Some(b)) => a == b,
+            _ => criterion == value,
+        },
+    }
+}
+
+/// Methods on an event stream, like `notes.delay(10.ms)` or `notes.quantize(1/16.note)`
+pub fn events_method(events: &EventStream, name: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    // `filter(kind: "note")` keeps events with those fields, so any name goes there
+    if name != "filter" {
+        crate::modules::check_named(named, name, if name == "quantize" { &["strength"] } else { &[] })?;
+    }
+    let time = |index: usize, what: &str| args.get(index).and_then(seconds).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("📨 {}() needs {} like 0.25, 10.ms or 1/16.note", name, what)
+    ));
+    match name {
+        "delay" => Ok(Value::Events(events.delay(time(0, "a time")?))),
+        "quantize" => {
+            let grid = time(0, "a grid")?;
+            let strength = args.get(1).and_then(|v| v.as_number())
+                .or_else(|| named.get("strength").and_then(|v| v.as_number()))
+                .unwrap_or(1.0);
+            Ok(Value::Events(events.quantize(grid, strength)))
+        }
+        "filter" => {
+            let fields = (!named.is_empty()).then(|| Value::Object(named.clone()));
+            let Some(criterion) = args.first().or(fields.as_ref()) else {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    "📨 filter() needs something to keep"
+                )
+                .with_suggestion("Try: notes.filter(60..72), hits.filter(\"kick\") or events.filter(kind: \"note\")"));
+            };
+            Ok(Value::Events(events.filter(|event| keeps(criterion, &event.value))))
+        }
+        "merge" => {
+            let mut all = vec![Value::Events(events.clone())];
+            all.extend(args.iter().cloned());
+            events_merge(&all, named)
+        }
+        "between" => {
+            let (from, to) = (time(0, "a start time")?, time(1, "an end time")?);
+            Ok(Value::Array(events.between(from, to).iter().map(event_value).collect()))
+        }
+        // What to play this frame
+        "due" => {
+            let (from, to) = crate::modules::time::frame_window();
+            Ok(Value::Array(events.between(from, to).iter().map(|event| event.value.clone()).collect()))
+        }
+        "block" => {
+            let start = time(0, "the block's start time")?;
+            let frames = args.get(1).and_then(|v| v.as_number()).unwrap_or(0.0).max(0.0) as usize;
+            let sample_rate = args.get(2).and_then(|v| v.as_number()).unwrap_or(crate::audio::signal::DEFAULT_SIGNAL_RATE);
+            Ok(Value::Array(events.block(start, frames, sample_rate).into_iter()
+                .map(|(offset, event)| {
+                    let mut fields = HashMap::new();
+                    fields.insert("offset".to_string(), Value::Integer(offset as i64));
+                    fields.insert("value".to_string(), event.value.clone());
+                    Value::Object(fields)
+                })
+                .collect()))
+        }
+        "count" => Ok(Value::Integer(events.len() as i64)),
+        "list" => Ok(Value::Array(events.events().iter().map(event_value).collect())),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::UnknownFunction,
+            format!("📨 Event streams don't have a {}() method", name)
+        )
+        .with_suggestion("They have delay, quantize, filter, merge, between, due, block, count and list")),
+    }
+}
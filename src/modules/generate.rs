@@ -1,5 +1,25 @@
-use crate::runtime::types::Value;
-use std::collections::HashMap;
+use crate::runtime::types::{DataType, Stream, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+/// Longest string an L-system may grow to; every generation can multiply its length
+const MAX_L_SYSTEM_SYMBOLS: usize = 500_000;
+
+/// Largest heightmap `Generate.fractal_terrain()` builds, 512 by 512
+const MAX_TERRAIN_POINTS: usize = 262_144;
+
+/// Most sites `Generate.voronoi()` and `Generate.delaunay()` take; cells cost the square of this
+const MAX_SITES: usize = 2_000;
+
+/// Most points an attractor steps in one frame, however high its rate or long the frame
+pub const MAX_ATTRACTOR_POINTS_PER_FRAME: usize = 10_000;
+
+static ATTRACTORS: OnceLock<Mutex<HashMap<String, Attractor>>> = OnceLock::new();
+
+/// Running attractors by name; the interpreter steps each one every frame
+pub fn attractors() -> &'static Mutex<HashMap<String, Attractor>> {
+    ATTRACTORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
 
 // L-System implementation
 #[derive(Debug, Clone)]
@@ -37,6 +57,84 @@ impl LSystem {
         
         current
     }
+    
+    /// Like `generate`, but gives up with None once the string grows past `limit` symbols
+    pub fn generate_within(&self, limit: usize) -> Option<String> {
+        let mut current = self.axiom.clone();
+        for _ in 0..self.iterations {
+            let mut next = String::with_capacity(current.len() * 2);
+            for ch in current.chars() {
+                match self.rules.get(&ch) {
+                    Some(replacement) => next.push_str(replacement),
+                    None => next.push(ch),
+                }
+                if next.len() > limit {
+                    return None;
+                }
+            }
+            current = next;
+        }
+        Some(current)
+    }
+}
+
+/// Turtle graphics for L-system strings, in screen coordinates (y grows downwards)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Turtle {
+    pub x: f64,
+    pub y: f64,
+    /// Degrees anticlockwise from pointing right, so 90 points up the screen
+    pub heading: f64,
+    pub step: f64,
+}
+
+impl Turtle {
+    /// Follow `symbols`: `F` and `G` draw a step, `f` moves a step without drawing, `+` and `-`
+    /// turn left and right by `angle` degrees, `|` turns around, and `[` and `]` save and return
+    /// to a position. Other symbols only steer the rewriting. Each unbroken stroke is one path
+    pub fn trace(&self, symbols: &str, angle: f64) -> Vec<Vec<(f64, f64)>> {
+        let mut turtle = *self;
+        let mut saved = Vec::new();
+        let mut paths = Vec::new();
+        let mut stroke: Vec<(f64, f64)> = Vec::new();
+        let mut end_stroke = |stroke: &mut Vec<(f64, f64)>| {
+            if stroke.len() >= 2 {
+                paths.push(std::mem::take(stroke));
+            }
+            stroke.clear();
+        };
+        for symbol in symbols.chars() {
+            match symbol {
+                'F' | 'G' | 'f' => {
+                    let start = (turtle.x, turtle.y);
+                    let radians = turtle.heading.to_radians();
+                    turtle.x += turtle.step * radians.cos();
+                    turtle.y -= turtle.step * radians.sin();
+                    if symbol == 'f' {
+                        end_stroke(&mut stroke);
+                    } else {
+                        if stroke.is_empty() {
+                            stroke.push(start);
+                        }
+                        stroke.push((turtle.x, turtle.y));
+                    }
+                }
+                '+' => turtle.heading += angle,
+                '-' => turtle.heading -= angle,
+                '|' => turtle.heading += 180.0,
+                '[' => saved.push(turtle),
+                ']' => {
+                    if let Some(restored) = saved.pop() {
+                        end_stroke(&mut stroke);
+                        turtle = restored;
+                    }
+                }
+                _ => {}
+            }
+        }
+        end_stroke(&mut stroke);
+        paths
+    }
 }
 
 // Perlin Noise implementation
@@ -46,10 +144,7 @@ pub struct PerlinNoise {
 
 impl PerlinNoise {
     pub fn new(seed: u32) -> Self {
-        let mut perm = [0; 256];
-        for i in 0..256 {
-            perm[i] = i;
-        }
+        let mut perm: [usize; 256] = std::array::from_fn(|i| i);
         
         // Fisher-Yates shuffle with seed
         let mut rng_state = seed;
@@ -118,7 +213,6 @@ impl PerlinNoise {
 
 // Euclidean Rhythm implementation
 pub struct EuclideanRhythm {
-    hits: usize,
     steps: usize,
     pattern: Vec<bool>,
     position: usize,
@@ -128,7 +222,6 @@ impl EuclideanRhythm {
     pub fn new(hits: usize, steps: usize) -> Self {
         let pattern = Self::generate_pattern(hits, steps);
         Self {
-            hits,
             steps,
             pattern,
             position: 0,
@@ -222,10 +315,16 @@ impl FractalTerrain {
     }
     
     pub fn generate_heightmap(&self) -> Vec<Vec<f64>> {
+        self.generate_heightmap_at(0.0)
+    }
+    
+    /// Sample the octaves on the slice `z` of 3D noise; nearby slices give nearby terrain, so
+    /// sliding `z` over time morphs the landscape smoothly
+    pub fn generate_heightmap_at(&self, z: f64) -> Vec<Vec<f64>> {
         let mut heightmap = vec![vec![0.0; self.width]; self.height];
         
-        for y in 0..self.height {
-            for x in 0..self.width {
+        for (y, row) in heightmap.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
                 let mut value = 0.0;
                 let mut amplitude = 1.0;
                 let mut frequency = self.scale;
@@ -234,30 +333,455 @@ impl FractalTerrain {
                     let sample_x = x as f64 * frequency;
                     let sample_y = y as f64 * frequency;
                     
-                    let noise_value = self.noise.noise(sample_x, sample_y, 0.0);
+                    let noise_value = self.noise.noise(sample_x, sample_y, z);
                     value += noise_value * amplitude;
                     
                     amplitude *= self.persistence;
                     frequency *= 2.0;
                 }
                 
-                heightmap[y][x] = value * self.height_multiplier;
+                *cell = value * self.height_multiplier;
             }
         }
         
         heightmap
     }
+    
+    /// Midpoint displacement on a square grid of 2^n + 1 cells covering the terrain, cropped
+    /// to size. Each halving of the step shrinks the random offsets by `persistence`
+    pub fn generate_diamond_square(&self, seed: u32) -> Vec<Vec<f64>> {
+        let mut size = 2;
+        while size + 1 < self.width.max(self.height) {
+            size *= 2;
+        }
+        let mut rng_state = seed;
+        let mut random = move || {
+            rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+            (rng_state >> 8) as f64 / (1u32 << 24) as f64 * 2.0 - 1.0
+        };
+        
+        let mut grid = vec![vec![0.0; size + 1]; size + 1];
+        for (y, x) in [(0, 0), (0, size), (size, 0), (size, size)] {
+            grid[y][x] = random();
+        }
+        let mut step = size;
+        let mut roughness = 1.0;
+        while step > 1 {
+            let half = step / 2;
+            // Diamond: the centre of each square from its corners
+            for y in (half..size).step_by(step) {
+                for x in (half..size).step_by(step) {
+                    let corners = grid[y - half][x - half] + grid[y - half][x + half]
+                        + grid[y + half][x - half] + grid[y + half][x + half];
+                    grid[y][x] = corners / 4.0 + random() * roughness;
+                }
+            }
+            // Square: the edge midpoints from whichever neighbours are on the grid
+            for y in (0..=size).step_by(half) {
+                let start = if (y / half) % 2 == 0 { half } else { 0 };
+                for x in (start..=size).step_by(step) {
+                    let neighbours = [
+                        (y >= half).then(|| grid[y - half][x]),
+                        (y + half <= size).then(|| grid[y + half][x]),
+                        (x >= half).then(|| grid[y][x - half]),
+                        (x + half <= size).then(|| grid[y][x + half]),
+                    ];
+                    let (sum, count) = neighbours.iter().flatten().fold((0.0, 0.0), |(sum, count), v| (sum + v, count + 1.0));
+                    grid[y][x] = sum / count + random() * roughness;
+                }
+            }
+            step = half;
+            roughness *= self.persistence;
+        }
+        
+        grid.truncate(self.height);
+        grid.into_iter()
+            .map(|mut row| {
+                row.truncate(self.width);
+                row.into_iter().map(|value| value * self.height_multiplier).collect()
+            })
+            .collect()
+    }
+}
+
+/// Rescale a heightmap so its lowest point is 0 and its highest 1; a flat map becomes all 0
+fn normalize_heights(heightmap: &mut [Vec<f64>]) {
+    let (low, high) = heightmap.iter().flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let range = high - low;
+    for value in heightmap.iter_mut().flatten() {
+        *value = if range > 0.0 { (*value - low) / range } else { 0.0 };
+    }
+}
+
+/// Thermal erosion: wherever a cell stands more than `talus` above its lowest neighbour, half
+/// the excess slides down. Sharp peaks and cliffs wear into slopes with each iteration
+pub fn erode(heightmap: &mut [Vec<f64>], iterations: usize, talus: f64) {
+    let rows = heightmap.len();
+    let columns = heightmap.first().map_or(0, |row| row.len());
+    for _ in 0..iterations {
+        for y in 0..rows {
+            for x in 0..columns {
+                let lowest = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)]
+                    .into_iter()
+                    .filter(|&(nx, ny)| nx < columns && ny < rows)
+                    .min_by(|a, b| heightmap[a.1][a.0].total_cmp(&heightmap[b.1][b.0]));
+                let Some((lx, ly)) = lowest else { continue };
+                let drop = heightmap[y][x] - heightmap[ly][lx];
+                if drop > talus {
+                    let moved = (drop - talus) / 2.0;
+                    heightmap[y][x] -= moved;
+                    heightmap[ly][lx] += moved;
+                }
+            }
+        }
+    }
+}
+
+// Strange attractors
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttractorKind {
+    Lorenz,
+    Rossler,
+    /// Peter de Jong's map, which jumps between points rather than flowing
+    DeJong,
+}
+
+impl AttractorKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace([' ', '-'], "_").as_str() {
+            "lorenz" => Some(Self::Lorenz),
+            "rossler" | "rössler" => Some(Self::Rossler),
+            "de_jong" | "dejong" => Some(Self::DeJong),
+            _ => None,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lorenz => "lorenz",
+            Self::Rossler => "rossler",
+            Self::DeJong => "de_jong",
+        }
+    }
+    
+    /// Parameters and the values that give the classic shapes
+    pub fn defaults(&self) -> &'static [(&'static str, f64)] {
+        match self {
+            Self::Lorenz => &[("sigma", 10.0), ("rho", 28.0), ("beta", 8.0 / 3.0)],
+            Self::Rossler => &[("a", 0.2), ("b", 0.2), ("c", 5.7)],
+            Self::DeJong => &[("a", 1.4), ("b", -2.3), ("c", 2.4), ("d", -2.1)],
+        }
+    }
+    
+    /// How far x usually strays from the middle, for scaling drawings and mapping to pitch
+    pub fn extent(&self) -> f64 {
+        match self {
+            Self::Lorenz => 20.0,
+            Self::Rossler => 12.0,
+            Self::DeJong => 2.0,
+        }
+    }
+    
+    /// A point as it's usually drawn, centred: the Lorenz butterfly faces along y
+    pub fn project(&self, [x, y, z]: [f64; 3]) -> (f64, f64) {
+        match self {
+            Self::Lorenz => (x, z - 25.0),
+            _ => (x, y),
+        }
+    }
+    
+    /// The next point: the flows move on by `dt` with a Runge-Kutta step, the map ignores it
+    pub fn step(&self, point: [f64; 3], dt: f64, param: impl Fn(&str) -> f64) -> [f64; 3] {
+        if *self == Self::DeJong {
+            let [x, y, _] = point;
+            return [
+                (param("a") * y).sin() - (param("b") * x).cos(),
+                (param("c") * x).sin() - (param("d") * y).cos(),
+                0.0,
+            ];
+        }
+        let derivative = |[x, y, z]: [f64; 3]| match self {
+            Self::Lorenz => [param("sigma") * (y - x), x * (param("rho") - z) - y, x * y - param("beta") * z],
+            _ => [-y - z, x + param("a") * y, param("b") + z * (x - param("c"))],
+        };
+        let offset = |p: [f64; 3], d: [f64; 3], scale: f64| [p[0] + d[0] * scale, p[1] + d[1] * scale, p[2] + d[2] * scale];
+        let k1 = derivative(point);
+        let k2 = derivative(offset(point, k1, dt / 2.0));
+        let k3 = derivative(offset(point, k2, dt / 2.0));
+        let k4 = derivative(offset(point, k3, dt));
+        [0, 1, 2].map(|i| point[i] + dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]))
+    }
+}
+
+/// A parameter that's either set from the script or follows a stream's newest sample
+#[derive(Debug, Clone, PartialEq)]
+pub enum Modulation {
+    Fixed(f64),
+    Stream(String),
+}
+
+/// One running attractor: where it is, the points it's been through, and its settings
+#[derive(Debug, Clone)]
+pub struct Attractor {
+    pub kind: AttractorKind,
+    pub point: [f64; 3],
+    /// The kind's parameters plus `rate`, `speed`, `low` and `high`
+    pub params: HashMap<String, Modulation>,
+    pub trail: VecDeque<[f64; 3]>,
+    pub trail_length: usize,
+    /// Part of a point carried over to the next frame, so low rates still come out even
+    pending: f64,
+}
+
+impl Attractor {
+    pub fn new(kind: AttractorKind) -> Self {
+        Self {
+            kind,
+            point: [0.1, 0.0, 0.0],
+            params: HashMap::new(),
+            trail: VecDeque::new(),
+            trail_length: 500,
+            pending: 0.0,
+        }
+    }
+    
+    /// A parameter's value now; `sample` reads a stream's newest sample
+    pub fn param(&self, name: &str, sample: &impl Fn(&str) -> Option<f64>) -> f64 {
+        let default = self.kind.defaults().iter()
+            .chain(&[("rate", 100.0), ("speed", 0.01), ("low", 110.0), ("high", 880.0)])
+            .find(|(key, _)| *key == name)
+            .map_or(0.0, |(_, value)| *value);
+        match self.params.get(name) {
+            Some(Modulation::Fixed(value)) => *value,
+            Some(Modulation::Stream(stream)) => sample(stream).unwrap_or(default),
+            None => default,
+        }
+    }
+    
+    /// Step through the points due in `seconds` at the attractor's rate, keeping them in the trail
+    pub fn advance(&mut self, seconds: f64, sample: impl Fn(&str) -> Option<f64>) -> Vec<[f64; 3]> {
+        self.pending += self.param("rate", &sample).max(0.0) * seconds.max(0.0);
+        let count = (self.pending.floor() as usize).min(MAX_ATTRACTOR_POINTS_PER_FRAME);
+        self.pending = (self.pending - count as f64).min(1.0);
+        let dt = self.param("speed", &sample);
+        let mut points = Vec::with_capacity(count);
+        for _ in 0..count {
+            let next = self.kind.step(self.point, dt, |name| self.param(name, &sample));
+            // A parameter pushed too far can fling the point off to infinity; start it again
+            self.point = if next.iter().all(|v| v.is_finite() && v.abs() < 1e6) { next } else { [0.1, 0.0, 0.0] };
+            points.push(self.point);
+        }
+        self.trail.extend(&points);
+        while self.trail.len() > self.trail_length {
+            self.trail.pop_front();
+        }
+        points
+    }
+    
+    /// Pitch for a point: x across its usual range, from `low` to `high` Hz on a musical (log) scale
+    pub fn frequency(&self, point: [f64; 3], sample: impl Fn(&str) -> Option<f64>) -> f64 {
+        let (low, high) = (self.param("low", &sample).max(1.0), self.param("high", &sample).max(1.0));
+        let t = ((point[0] / self.kind.extent() + 1.0) / 2.0).clamp(0.0, 1.0);
+        low * (high / low).powf(t)
+    }
+}
+
+// Voronoi and Delaunay geometry
+
+/// Delaunay triangulation by Bowyer-Watson: each triangle's circumcircle holds no other point.
+/// Gives triangles as indices into `points`, counter-clockwise; repeated points are left out
+pub fn delaunay(points: &[(f64, f64)]) -> Vec<[usize; 3]> {
+    let (min_x, min_y, max_x, max_y) = points.iter().fold(
+        (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+    );
+    if points.len() < 3 || !min_x.is_finite() {
+        return Vec::new();
+    }
+    // A triangle around everything to start from, removed again at the end
+    let span = (max_x - min_x).max(max_y - min_y).max(1.0) * 20.0;
+    let (mid_x, mid_y) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+    let mut vertices = points.to_vec();
+    let outer = vertices.len();
+    vertices.extend([(mid_x - span, mid_y - span), (mid_x + span, mid_y - span), (mid_x, mid_y + span)]);
+    let mut triangles = vec![[outer, outer + 1, outer + 2]];
+    
+    for (index, &point) in points.iter().enumerate() {
+        if points[..index].iter().any(|&earlier| distance_squared(earlier, point) < 1e-18) {
+            continue;
+        }
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles.into_iter()
+            .partition(|triangle| in_circumcircle(&vertices, triangle, point));
+        // The hole's outline is the edges only one removed triangle has
+        let mut outline: Vec<(usize, usize)> = Vec::new();
+        for triangle in &bad {
+            for edge in [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])] {
+                match outline.iter().position(|&(a, b)| (a, b) == (edge.1, edge.0)) {
+                    Some(shared) => {
+                        outline.swap_remove(shared);
+                    }
+                    None => outline.push(edge),
+                }
+            }
+        }
+        triangles = good;
+        triangles.extend(outline.into_iter().map(|(a, b)| [a, b, index]));
+    }
+    
+    triangles.retain(|triangle| triangle.iter().all(|&vertex| vertex < outer));
+    triangles
+}
+
+fn distance_squared(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+/// Whether `point` is inside the circle through a counter-clockwise triangle's corners
+fn in_circumcircle(vertices: &[(f64, f64)], triangle: &[usize; 3], point: (f64, f64)) -> bool {
+    let [a, b, c] = triangle.map(|vertex| (vertices[vertex].0 - point.0, vertices[vertex].1 - point.1));
+    let lift = |p: (f64, f64)| p.0 * p.0 + p.1 * p.1;
+    let determinant = (a.0 * (b.1 * lift(c) - lift(b) * c.1))
+        - (a.1 * (b.0 * lift(c) - lift(b) * c.0))
+        + (lift(a) * (b.0 * c.1 - b.1 * c.0));
+    determinant > 0.0
+}
+
+/// The Voronoi cell of `sites[index]` inside the rectangle `bounds` (x, y, width, height): the
+/// part closer to that site than to any other, as a convex polygon. A repeated site has no cell
+pub fn voronoi_cell(sites: &[(f64, f64)], index: usize, bounds: (f64, f64, f64, f64)) -> Vec<(f64, f64)> {
+    let (x, y, width, height) = bounds;
+    let site = sites[index];
+    let mut cell = vec![(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+    for (other_index, &other) in sites.iter().enumerate() {
+        if other_index == index {
+            continue;
+        }
+        if distance_squared(site, other) < 1e-18 {
+            // The first of two identical sites keeps the cell
+            if other_index < index {
+                return Vec::new();
+            }
+            continue;
+        }
+        // Keep the side of the perpendicular bisector that the site is on
+        let middle = ((site.0 + other.0) / 2.0, (site.1 + other.1) / 2.0);
+        let away = (other.0 - site.0, other.1 - site.1);
+        let side = |p: (f64, f64)| (p.0 - middle.0) * away.0 + (p.1 - middle.1) * away.1;
+        let mut clipped = Vec::with_capacity(cell.len() + 1);
+        for (i, &current) in cell.iter().enumerate() {
+            let next = cell[(i + 1) % cell.len()];
+            let (a, b) = (side(current), side(next));
+            if a <= 0.0 {
+                clipped.push(current);
+            }
+            if (a < 0.0 && b > 0.0) || (a > 0.0 && b < 0.0) {
+                let t = a / (a - b);
+                clipped.push((current.0 + (next.0 - current.0) * t, current.1 + (next.1 - current.1) * t));
+            }
+        }
+        cell = clipped;
+        if cell.is_empty() {
+            break;
+        }
+    }
+    cell
 }
 
 // Module functions for the runtime
-pub fn l_system(_args: &[Value]) -> crate::Result<Value> {
-    // For now, return a placeholder object
+
+fn number(args: &[Value], index: usize) -> Option<f64> {
+    args.get(index).and_then(|v| v.as_number())
+}
+
+fn l_system_error(message: impl Into<String>) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, message)
+        .with_suggestion("Try: Generate.l_system(\"F\", {F: \"F[+F]F[-F]F\"}, 25, 4)")
+}
+
+/// `Generate.l_system(axiom, rules, angle, iterations, length:, x:, y:, heading:, grow:)`: rewrite
+/// the axiom `iterations` times and walk the result with a turtle. Gives the drawn strokes as
+/// `paths` of `[x, y]` points for `Graphics.path()`; `grow` between 0 and 1 keeps only that share
+/// of the strokes, in drawing order, so a plant can be animated growing one generation at a time
+pub fn l_system(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.l_system", &["angle", "iterations", "length", "x", "y", "heading", "grow"])?;
+    let axiom = match args.first() {
+        Some(Value::String(axiom)) if !axiom.is_empty() => axiom.clone(),
+        _ => return Err(l_system_error("🌿 Generate.l_system() needs an axiom to start from, like \"F\"")),
+    };
+    let mut rules = HashMap::new();
+    match args.get(1) {
+        Some(Value::Object(fields)) => {
+            for (symbol, replacement) in fields {
+                let mut chars = symbol.chars();
+                let (Some(symbol), None, Value::String(replacement)) = (chars.next(), chars.next(), replacement) else {
+                    return Err(l_system_error(format!("🌿 The rule for {} has to turn one symbol into text", symbol)));
+                };
+                rules.insert(symbol, replacement.clone());
+            }
+        }
+        _ => return Err(l_system_error("🌿 Generate.l_system() needs rules, like {F: \"F+F\"}")),
+    }
+    let option = |name: &str, index: Option<usize>, default: f64| {
+        index.and_then(|index| number(args, index))
+            .or_else(|| named.get(name).and_then(|v| v.as_number()))
+            .unwrap_or(default)
+    };
+    let angle = option("angle", Some(2), 90.0);
+    let iterations = option("iterations", Some(3), 3.0).max(0.0) as usize;
+    let grow = option("grow", None, 1.0).clamp(0.0, 1.0);
+    let turtle = Turtle {
+        x: option("x", None, 400.0),
+        y: option("y", None, 600.0),
+        heading: option("heading", None, 90.0),
+        step: option("length", None, 10.0),
+    };
+    
+    let system = LSystem::new(axiom, rules, angle, iterations);
+    let symbols = system.generate_within(MAX_L_SYSTEM_SYMBOLS).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("🌿 {} generations grow this L-system past {} symbols", iterations, MAX_L_SYSTEM_SYMBOLS)
+    )
+    .with_suggestion("Use fewer iterations; each one can multiply the size"))?;
+    let mut paths = turtle.trace(&symbols, angle);
+    
+    let total: usize = paths.iter().map(|path| path.len() - 1).sum();
+    let mut keep = (total as f64 * grow).ceil() as usize;
+    paths.retain_mut(|path| {
+        let segments = (path.len() - 1).min(keep);
+        path.truncate(segments + 1);
+        keep -= segments;
+        segments > 0
+    });
+    
+    let points = paths.iter().flatten();
+    let (min_x, max_x) = points.clone().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+    let (min_y, max_y) = points.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    let mut bounds = HashMap::new();
+    if min_x.is_finite() {
+        bounds.insert("x".to_string(), Value::Float(min_x));
+        bounds.insert("y".to_string(), Value::Float(min_y));
+        bounds.insert("width".to_string(), Value::Float(max_x - min_x));
+        bounds.insert("height".to_string(), Value::Float(max_y - min_y));
+    }
+    
     let mut result = HashMap::new();
     result.insert("type".to_string(), Value::String("l_system".to_string()));
+    result.insert("generation".to_string(), Value::Integer(iterations as i64));
+    result.insert("segments".to_string(), Value::Integer(paths.iter().map(|path| path.len() as i64 - 1).sum()));
+    result.insert("symbols".to_string(), Value::String(symbols));
+    result.insert("bounds".to_string(), Value::Object(bounds));
+    result.insert("paths".to_string(), Value::Array(paths.into_iter()
+        .map(|path| Value::Array(path.into_iter()
+            .map(|(x, y)| Value::Array(vec![Value::Float(x), Value::Float(y)]))
+            .collect()))
+        .collect()));
     Ok(Value::Object(result))
 }
 
-pub fn perlin_noise(args: &[Value]) -> crate::Result<Value> {
+pub fn perlin_noise(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.perlin_noise", &[])?;
     if args.len() < 3 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "perlin_noise requires 3 arguments (x, y, z)"));
     }
@@ -272,7 +796,8 @@ pub fn perlin_noise(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Float(value))
 }
 
-pub fn euclidean(args: &[Value]) -> crate::Result<Value> {
+pub fn euclidean(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.euclidean", &[])?;
     if args.len() < 2 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "euclidean requires 2 arguments (hits, steps)"));
     }
@@ -293,9 +818,355 @@ pub fn euclidean(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Array(pattern))
 }
 
-pub fn fractal_terrain(_args: &[Value]) -> crate::Result<Value> {
-    // For now, return a placeholder object
+fn terrain_error(message: impl Into<String>) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, message)
+        .with_suggestion("Try: Generate.fractal_terrain(128, 128, seed: 7, erosion: 20, mesh: true)")
+}
+
+/// `Generate.fractal_terrain(width, height, method:, seed:, octaves:, persistence:, scale:,
+/// erosion:, talus:, morph:, mesh:, spacing:, height_multiplier:)`: a heightmap scaled to 0..1,
+/// as `heights` rows and as a `texture` laid out like depth and camera frames. `method` is
+/// `"fbm"` (layered Perlin noise, the default) or `"diamond_square"`. `morph` moves through
+/// neighbouring terrains, so animating it reshapes the land smoothly. `mesh: true` also gives
+/// `vertices` and `triangles` for a 3D scene, with `height_multiplier` as the tallest peak
+pub fn fractal_terrain(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.fractal_terrain", &[
+        "width", "height", "method", "seed", "octaves", "persistence", "scale",
+        "erosion", "talus", "morph", "mesh", "spacing", "height_multiplier",
+    ])?;
+    let option = |name: &str, index: Option<usize>, default: f64| {
+        index.and_then(|index| number(args, index))
+            .or_else(|| named.get(name).and_then(|v| v.as_number()))
+            .unwrap_or(default)
+    };
+    let width = option("width", Some(0), 64.0);
+    let height = option("height", Some(1), 64.0);
+    if width < 2.0 || height < 2.0 {
+        return Err(terrain_error(format!("🏔️ Terrain needs at least 2 by 2 points, not {} by {}", width, height)));
+    }
+    let (width, height) = (width as usize, height as usize);
+    if width * height > MAX_TERRAIN_POINTS {
+        return Err(terrain_error(format!("🏔️ {} by {} is more than the {} points a terrain can have", width, height, MAX_TERRAIN_POINTS)));
+    }
+    
+    let seed = option("seed", None, 0.0) as i64 as u32;
+    let octaves = option("octaves", None, 6.0).clamp(1.0, 16.0) as usize;
+    let persistence = option("persistence", None, 0.5);
+    let scale = option("scale", None, 0.05);
+    let morph = option("morph", None, 0.0);
+    let method = match named.get("method") {
+        Some(Value::String(method)) => method.replace('-', "_"),
+        _ => "fbm".to_string(),
+    };
+    
+    let terrain = FractalTerrain::new(width, height, octaves, persistence, scale, 1.0, seed);
+    let mut heightmap = match method.as_str() {
+        "fbm" => terrain.generate_heightmap_at(morph),
+        "diamond_square" => {
+            // Blend the maps of neighbouring seeds, so a whole step of morph is a new landscape
+            let base = morph.floor();
+            let blend = morph - base;
+            let from = terrain.generate_diamond_square(seed.wrapping_add(base as i64 as u32));
+            if blend > 0.0 {
+                let to = terrain.generate_diamond_square(seed.wrapping_add(base as i64 as u32).wrapping_add(1));
+                from.into_iter().zip(to)
+                    .map(|(a, b)| a.into_iter().zip(b).map(|(a, b)| a + (b - a) * blend).collect())
+                    .collect()
+            } else {
+                from
+            }
+        }
+        other => return Err(terrain_error(format!("🏔️ There's no terrain method called \"{}\"; use \"fbm\" or \"diamond_square\"", other))),
+    };
+    normalize_heights(&mut heightmap);
+    let erosion = option("erosion", None, 0.0).max(0.0) as usize;
+    if erosion > 0 {
+        erode(&mut heightmap, erosion, option("talus", None, 1.0 / width.max(height) as f64));
+    }
+    
+    let mut texture = HashMap::new();
+    texture.insert("type".to_string(), Value::String("texture".to_string()));
+    texture.insert("width".to_string(), Value::Integer(width as i64));
+    texture.insert("height".to_string(), Value::Integer(height as i64));
+    texture.insert("data".to_string(), Value::Array(heightmap.iter().flatten().map(|&v| Value::Float(v)).collect()));
+    
     let mut result = HashMap::new();
     result.insert("type".to_string(), Value::String("fractal_terrain".to_string()));
+    result.insert("method".to_string(), Value::String(method));
+    result.insert("seed".to_string(), Value::Integer(seed as i64));
+    result.insert("width".to_string(), Value::Integer(width as i64));
+    result.insert("height".to_string(), Value::Integer(height as i64));
+    result.insert("texture".to_string(), Value::Object(texture));
+    if matches!(named.get("mesh"), Some(Value::Boolean(true))) {
+        let spacing = option("spacing", None, 1.0);
+        let elevation = option("height_multiplier", None, 10.0);
+        // Laid out on the ground plane with y up, one vertex per height, two triangles per cell
+        let vertices = heightmap.iter().enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &h)| Value::Array(vec![
+                Value::Float(x as f64 * spacing),
+                Value::Float(h * elevation),
+                Value::Float(y as f64 * spacing),
+            ])))
+            .collect();
+        let mut triangles = Vec::with_capacity((width - 1) * (height - 1) * 2);
+        for y in 0..height - 1 {
+            for x in 0..width - 1 {
+                let corner = (y * width + x) as i64;
+                let below = corner + width as i64;
+                triangles.push(Value::Array(vec![Value::Integer(corner), Value::Integer(below), Value::Integer(corner + 1)]));
+                triangles.push(Value::Array(vec![Value::Integer(corner + 1), Value::Integer(below), Value::Integer(below + 1)]));
+            }
+        }
+        let mut mesh = HashMap::new();
+        mesh.insert("vertices".to_string(), Value::Array(vertices));
+        mesh.insert("triangles".to_string(), Value::Array(triangles));
+        result.insert("mesh".to_string(), Value::Object(mesh));
+    }
+    result.insert("heights".to_string(), Value::Array(heightmap.into_iter()
+        .map(|row| Value::Array(row.into_iter().map(Value::Float).collect()))
+        .collect()));
     Ok(Value::Object(result))
-}
\ No newline at end of file
+}
+
+fn geometry_error(message: impl Into<String>) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, message)
+        .with_suggestion("Try: Generate.voronoi([[100, 100], [400, 300], [650, 150]]) or Generate.voronoi(40, seed: 3)")
+}
+
+/// `[x, y]` or `{x, y}`
+fn site(value: &Value) -> Option<(f64, f64)> {
+    match value {
+        Value::Array(xy) if xy.len() == 2 => Some((xy[0].as_number()?, xy[1].as_number()?)),
+        Value::Object(fields) => Some((fields.get("x")?.as_number()?, fields.get("y")?.as_number()?)),
+        _ => None,
+    }
+}
+
+fn point_value((x, y): (f64, f64)) -> Value {
+    Value::Array(vec![Value::Float(x), Value::Float(y)])
+}
+
+/// Sites from a list of points, the `sites` of an earlier result, or a count scattered at random
+/// inside `bounds` (the same `seed` scatters them the same way)
+fn sites(args: &[Value], options: &HashMap<String, Value>, bounds: (f64, f64, f64, f64), what: &str) -> crate::Result<Vec<(f64, f64)>> {
+    let list = match args.first() {
+        Some(Value::Object(fields)) => fields.get("sites"),
+        other => other,
+    };
+    let sites = match list {
+        Some(Value::Array(points)) => points.iter()
+            .map(|point| site(point).ok_or_else(|| geometry_error(format!("📐 {} takes points as [x, y] or {{x, y}}, not {}", what, point))))
+            .collect::<crate::Result<Vec<_>>>()?,
+        Some(count) if count.as_number().is_some() => {
+            let count = count.as_number().unwrap_or(0.0).max(0.0) as usize;
+            if count > MAX_SITES {
+                return Err(geometry_error(format!("📐 {} takes up to {} sites, not {}", what, MAX_SITES, count)));
+            }
+            let mut rng_state = options.get("seed").and_then(|v| v.as_number()).unwrap_or(0.0) as i64 as u32;
+            let mut random = move || {
+                rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+                (rng_state >> 8) as f64 / (1u32 << 24) as f64
+            };
+            (0..count).map(|_| (bounds.0 + random() * bounds.2, bounds.1 + random() * bounds.3)).collect()
+        }
+        _ => return Err(geometry_error(format!("📐 {} needs points, or how many to scatter", what))),
+    };
+    if sites.len() > MAX_SITES {
+        return Err(geometry_error(format!("📐 {} takes up to {} sites, not {}", what, MAX_SITES, sites.len())));
+    }
+    Ok(sites)
+}
+
+/// `x:`, `y:`, `width:` and `height:` options, defaulting to an 800 by 600 canvas
+fn bounds(options: &HashMap<String, Value>) -> (f64, f64, f64, f64) {
+    let option = |name: &str, default: f64| options.get(name).and_then(|v| v.as_number()).unwrap_or(default);
+    (option("x", 0.0), option("y", 0.0), option("width", 800.0).max(0.0), option("height", 600.0).max(0.0))
+}
+
+/// `Generate.voronoi(points, width:, height:, seed:)`: the cell around each point holding
+/// everything closer to it than to any other, clipped to the canvas. Gives `cells` (one closed
+/// polygon per site, in the same order), the `edges` between cells and the Delaunay `triangles`;
+/// `Graphics.path()` draws the cells
+pub fn voronoi(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.voronoi", &["x", "y", "width", "height", "seed"])?;
+    let bounds = bounds(named);
+    let sites = sites(args, named, bounds, "Generate.voronoi()")?;
+    
+    let cells: Vec<Vec<(f64, f64)>> = (0..sites.len()).map(|index| voronoi_cell(&sites, index, bounds)).collect();
+    // Cell sides inside the canvas are shared by two cells; count each once
+    let on_border = |p: (f64, f64), q: (f64, f64)| {
+        let (x, y, width, height) = bounds;
+        [(p.0, q.0, x), (p.0, q.0, x + width), (p.1, q.1, y), (p.1, q.1, y + height)]
+            .iter()
+            .any(|&(a, b, line)| (a - line).abs() < 1e-9 && (b - line).abs() < 1e-9)
+    };
+    let key = |p: (f64, f64)| ((p.0 * 1e6).round() as i64, (p.1 * 1e6).round() as i64);
+    let mut seen = std::collections::HashSet::new();
+    let mut edges = Vec::new();
+    for cell in &cells {
+        for (i, &from) in cell.iter().enumerate() {
+            let to = cell[(i + 1) % cell.len()];
+            if on_border(from, to) || key(from) == key(to) {
+                continue;
+            }
+            let (a, b) = (key(from).min(key(to)), key(from).max(key(to)));
+            if seen.insert((a, b)) {
+                edges.push(Value::Array(vec![point_value(from), point_value(to)]));
+            }
+        }
+    }
+    
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("voronoi".to_string()));
+    result.insert("triangles".to_string(), triangle_indices(&delaunay(&sites)));
+    result.insert("edges".to_string(), Value::Array(edges));
+    result.insert("paths".to_string(), Value::Array(cells.iter()
+        .filter(|cell| !cell.is_empty())
+        .map(|cell| Value::Array(cell.iter().chain(cell.first()).map(|&p| point_value(p)).collect()))
+        .collect()));
+    result.insert("cells".to_string(), Value::Array(cells.into_iter()
+        .map(|cell| Value::Array(cell.into_iter().map(point_value).collect()))
+        .collect()));
+    result.insert("sites".to_string(), Value::Array(sites.into_iter().map(point_value).collect()));
+    Ok(Value::Object(result))
+}
+
+fn triangle_indices(triangles: &[[usize; 3]]) -> Value {
+    Value::Array(triangles.iter()
+        .map(|triangle| Value::Array(triangle.iter().map(|&vertex| Value::Integer(vertex as i64)).collect()))
+        .collect())
+}
+
+/// `Generate.delaunay(points, seed:)`: joins the points into triangles that are as close to
+/// equilateral as they can be. Gives `triangles` as indices into `sites`, each `edge` once as a
+/// pair of indices, and the triangle outlines as `paths` for `Graphics.path()`
+pub fn delaunay_triangulation(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.delaunay", &["x", "y", "width", "height", "seed"])?;
+    let sites = sites(args, named, bounds(named), "Generate.delaunay()")?;
+    let triangles = delaunay(&sites);
+    
+    let mut edges: Vec<(usize, usize)> = triangles.iter()
+        .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("delaunay".to_string()));
+    result.insert("triangles".to_string(), triangle_indices(&triangles));
+    result.insert("edges".to_string(), Value::Array(edges.into_iter()
+        .map(|(a, b)| Value::Array(vec![Value::Integer(a as i64), Value::Integer(b as i64)]))
+        .collect()));
+    result.insert("paths".to_string(), Value::Array(triangles.iter()
+        .map(|t| Value::Array([t[0], t[1], t[2], t[0]].iter().map(|&vertex| point_value(sites[vertex])).collect()))
+        .collect()));
+    result.insert("sites".to_string(), Value::Array(sites.into_iter().map(point_value).collect()));
+    Ok(Value::Object(result))
+}
+
+/// `Generate.nearest(sites, x, y)`: which site is closest to a point, for modulating by region.
+/// Gives its `index` and `site`, the `distance` to it and to the `second` closest, and `edge`,
+/// the difference between those two, which falls to 0 on the border between cells
+pub fn nearest(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Generate.nearest", &[])?;
+    let sites = sites(args, &HashMap::new(), (0.0, 0.0, 0.0, 0.0), "Generate.nearest()")?;
+    let (Some(x), Some(y)) = (number(args, 1), number(args, 2)) else {
+        return Err(geometry_error("📐 Generate.nearest() needs the point to look from, like Generate.nearest(cells, mouse_x, mouse_y)"));
+    };
+    let mut distances: Vec<(usize, f64)> = sites.iter()
+        .enumerate()
+        .map(|(index, &site)| (index, distance_squared(site, (x, y)).sqrt()))
+        .collect();
+    distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let Some(&(index, distance)) = distances.first() else {
+        return Ok(Value::Null);
+    };
+    let second = distances.get(1).map_or(distance, |&(_, d)| d);
+    
+    let mut result = HashMap::new();
+    result.insert("index".to_string(), Value::Integer(index as i64));
+    result.insert("site".to_string(), point_value(sites[index]));
+    result.insert("distance".to_string(), Value::Float(distance));
+    result.insert("second".to_string(), Value::Float(second));
+    result.insert("edge".to_string(), Value::Float(second - distance));
+    Ok(Value::Object(result))
+}
+
+fn attractor_error(message: impl Into<String>) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, message)
+        .with_suggestion("Try: Generate.attractor(\"lorenz\", rate: 200, rho: 28)")
+        .with_suggestion("Attractors are \"lorenz\", \"rossler\" and \"de_jong\"")
+}
+
+/// `Generate.attractor(kind, name:, rate:, speed:, trail:, scale:, x:, y:, low:, high:, ...)`:
+/// start a Lorenz, Rössler or de Jong attractor that moves on by `rate` points a second, every
+/// frame, and streams them as `points` (x, y, z triples) and `frequency` (x as a pitch between
+/// `low` and `high`). Its own parameters (`sigma`, `rho`, `beta`; `a`, `b`, `c`, `d`) take a
+/// number or a stream to follow. Calling it again with the same name changes the settings and
+/// gives the newest `x`, `y`, `z` and `trail`, with the trail drawn flat as `paths`
+pub fn attractor(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    let kind = match args.first() {
+        Some(Value::String(name)) => AttractorKind::from_name(name)
+            .ok_or_else(|| attractor_error(format!("🌀 There's no attractor called \"{}\"", name)))?,
+        _ => return Err(attractor_error("🌀 Generate.attractor() needs to know which attractor, like \"lorenz\"")),
+    };
+    let accepted: Vec<&str> = kind.defaults().iter().map(|(key, _)| *key)
+        .chain(["name", "rate", "speed", "trail", "scale", "x", "y", "low", "high"])
+        .collect();
+    crate::modules::check_named(named, "Generate.attractor", &accepted)?;
+    let name = match named.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => kind.name().to_string(),
+    };
+    let number = |key: &str, default: f64| named.get(key).and_then(|v| v.as_number()).unwrap_or(default);
+    
+    let mut running = attractors().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let attractor = running.entry(name.clone()).or_insert_with(|| Attractor::new(kind));
+    if attractor.kind != kind {
+        *attractor = Attractor::new(kind);
+    }
+    let settable = kind.defaults().iter().map(|(key, _)| *key).chain(["rate", "speed", "low", "high"]);
+    for key in settable {
+        let modulation = match named.get(key) {
+            None => continue,
+            Some(Value::Stream(stream)) => Modulation::Stream(stream.name.clone()),
+            Some(value) => Modulation::Fixed(value.as_number().ok_or_else(|| {
+                attractor_error(format!("🌀 {}: should be a number or a stream, not {}", key, value))
+            })?),
+        };
+        attractor.params.insert(key.to_string(), modulation);
+    }
+    if let Some(length) = named.get("trail").and_then(|v| v.as_number()) {
+        attractor.trail_length = length.clamp(0.0, MAX_ATTRACTOR_POINTS_PER_FRAME as f64) as usize;
+    }
+    
+    let scale = number("scale", 240.0 / kind.extent());
+    let (center_x, center_y) = (number("x", 400.0), number("y", 300.0));
+    let stream = |suffix: &str, data_type: DataType| Value::Stream(Stream {
+        name: format!("{}.{}", name, suffix),
+        data_type,
+        sample_rate: None,
+    });
+    let [x, y, z] = attractor.point;
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("attractor".to_string()));
+    result.insert("kind".to_string(), Value::String(kind.name().to_string()));
+    result.insert("name".to_string(), Value::String(name.clone()));
+    result.insert("x".to_string(), Value::Float(x));
+    result.insert("y".to_string(), Value::Float(y));
+    result.insert("z".to_string(), Value::Float(z));
+    result.insert("points".to_string(), stream("points", DataType::Generic));
+    result.insert("frequency".to_string(), stream("frequency", DataType::Control));
+    result.insert("trail".to_string(), Value::Array(attractor.trail.iter()
+        .map(|point| Value::Array(point.iter().map(|&v| Value::Float(v)).collect()))
+        .collect()));
+    // Screen y grows downwards, so up in the attractor is up on screen
+    result.insert("paths".to_string(), Value::Array(vec![Value::Array(attractor.trail.iter()
+        .map(|&point| {
+            let (px, py) = kind.project(point);
+            point_value((center_x + px * scale, center_y - py * scale))
+        })
+        .collect())]));
+    Ok(Value::Object(result))
+}
@@ -0,0 +1,136 @@
+use crate::hardware::gps::{bearing_degrees, connect_gpsd, distance_metres, gps, gps_stream_name, open_nmea_serial, GPSD_ADDRESS, GPS_FIELDS};
+use crate::runtime::types::{DataType, Stream};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+fn stream(field: &str) -> Value {
+    Value::Stream(Stream {
+        name: gps_stream_name(field),
+        data_type: DataType::Control,
+        sample_rate: None,
+    })
+}
+
+/// The current (latitude, longitude), or an error that says why there isn't one
+fn here(function: &str) -> crate::Result<(f64, f64)> {
+    let state = gps().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match (state.fix(), &state.source) {
+        (Some(fix), _) => Ok((fix.latitude, fix.longitude)),
+        (None, Some(source)) => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::StreamConnectionError,
+            format!("🛰️ GPS.{}() has no position yet; {} hasn't reported a fix", function, source)
+        )
+        .with_suggestion("Receivers need a clear view of the sky, and can take a minute after starting")
+        .with_suggestion("GPS.position() returns null until there's a fix, so you can wait for it")),
+        (None, None) => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::StreamConnectionError,
+            "🛰️ No GPS receiver is open"
+        )
+        .with_suggestion("Open one first: GPS.open() for gpsd, or GPS.open(\"/dev/ttyUSB0\") for a serial receiver")),
+    }
+}
+
+/// The (latitude, longitude) given as the first two arguments
+fn target(args: &[Value], function: &str) -> crate::Result<(f64, f64)> {
+    match (args.first().and_then(|v| v.as_number()), args.get(1).and_then(|v| v.as_number())) {
+        (Some(latitude), Some(longitude)) => Ok((latitude, longitude)),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🛰️ GPS.{}() needs the latitude and longitude of a place", function)
+        )
+        .with_suggestion(format!("Try: GPS.{}(52.3731, 4.8926)", function))),
+    }
+}
+
+/// `GPS.open()` reads positions from gpsd, `GPS.open(host: "10.0.0.5:2947")` from gpsd elsewhere,
+/// and `GPS.open("/dev/ttyUSB0", baud: 9600)` NMEA straight from a serial receiver. Returns the
+/// `latitude`, `longitude`, `speed`, `heading` and `altitude` streams
+pub fn gps_open(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.open", &["baud", "host"])?;
+    let (lines, source) = match args.first() {
+        Some(Value::String(port)) => {
+            let baud = named.get("baud").and_then(|v| v.as_number()).unwrap_or(4800.0) as u32;
+            (open_nmea_serial(port, baud)?, port.clone())
+        }
+        _ => {
+            let address = match named.get("host") {
+                Some(Value::String(host)) if host.contains(':') => host.clone(),
+                Some(Value::String(host)) => format!("{}:2947", host),
+                _ => GPSD_ADDRESS.to_string(),
+            };
+            (connect_gpsd(&address)?, format!("gpsd at {}", address))
+        }
+    };
+    tracing::info!(target: "hardware", source = source.as_str(), "🛰️ GPS opened");
+    gps().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).open(lines, source);
+
+    let streams = GPS_FIELDS.iter().map(|field| (field.to_string(), stream(field))).collect();
+    Ok(Value::Object(streams))
+}
+
+/// Degrees north, as the `gps.latitude` stream
+pub fn gps_latitude(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.latitude", &[])?;
+    Ok(stream("latitude"))
+}
+
+/// Degrees east, as the `gps.longitude` stream
+pub fn gps_longitude(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.longitude", &[])?;
+    Ok(stream("longitude"))
+}
+
+/// Metres per second over the ground, as the `gps.speed` stream
+pub fn gps_speed(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.speed", &[])?;
+    Ok(stream("speed"))
+}
+
+/// Direction of travel in degrees clockwise from north, as the `gps.heading` stream
+pub fn gps_heading(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.heading", &[])?;
+    Ok(stream("heading"))
+}
+
+/// Metres above sea level, as the `gps.altitude` stream
+pub fn gps_altitude(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.altitude", &[])?;
+    Ok(stream("altitude"))
+}
+
+/// `GPS.position()`: `{ latitude, longitude, speed, heading, altitude, satellites, age }`, or null
+/// until the receiver has a fix; `age` is seconds since it last moved on
+pub fn gps_position(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.position", &[])?;
+    let state = gps().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(fix) = state.fix() else {
+        return Ok(Value::Null);
+    };
+    let mut fields: HashMap<String, Value> = GPS_FIELDS.iter()
+        .filter_map(|field| fix.value(field).map(|value| (field.to_string(), Value::Float(value))))
+        .collect();
+    fields.insert("satellites".to_string(), fix.satellites.map(|count| Value::Integer(count as i64)).unwrap_or(Value::Null));
+    fields.insert("age".to_string(), Value::Float(state.age().map(|age| age.as_secs_f64()).unwrap_or(0.0)));
+    Ok(Value::Object(fields))
+}
+
+/// `GPS.distance(52.3731, 4.8926)`: metres from here to that place
+pub fn gps_distance(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.distance", &[])?;
+    let place = target(args, "distance")?;
+    Ok(Value::Float(distance_metres(here("distance")?, place)))
+}
+
+/// `GPS.bearing(52.3731, 4.8926)`: which way that place is, in degrees clockwise from north
+pub fn gps_bearing(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.bearing", &[])?;
+    let place = target(args, "bearing")?;
+    Ok(Value::Float(bearing_degrees(here("bearing")?, place)))
+}
+
+/// Stop reading positions; returns whether a receiver was open
+pub fn gps_close(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GPS.close", &[])?;
+    let mut state = gps().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(state.close()))
+}
@@ -1,8 +1,9 @@
 use crate::runtime::Value;
 use std::collections::HashMap;
 
-pub fn clear(args: &[Value]) -> crate::Result<Value> {
-    let color = args.get(0)
+pub fn clear(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.clear", &[])?;
+    let color = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(0x000000 as f64) as i64; // Default to black
     
@@ -10,27 +11,17 @@ pub fn clear(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Null)
 }
 
-pub fn plasma(args: &[Value]) -> crate::Result<Value> {
-    let mut params = HashMap::new();
-    
-    // Parse named arguments from block expressions
-    for arg in args {
-        if let Value::Object(fields) = arg {
-            for (key, value) in fields {
-                params.insert(key.clone(), value.clone());
-            }
-        }
-    }
-    
-    let speed = params.get("speed")
+pub fn plasma(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.plasma", &["speed", "intensity", "palette"])?;
+    let speed = named.get("speed")
         .and_then(|v| v.as_number())
         .unwrap_or(1.0);
     
-    let intensity = params.get("intensity")
+    let intensity = named.get("intensity")
         .and_then(|v| v.as_number())
         .unwrap_or(0.5);
     
-    let palette = params.get("palette")
+    let palette = named.get("palette")
         .map(|v| match v {
             Value::String(s) => s.clone(),
             _ => "default".to_string(),
@@ -41,22 +32,14 @@ pub fn plasma(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Null)
 }
 
-pub fn starfield(args: &[Value]) -> crate::Result<Value> {
-    let mut params = HashMap::new();
-    
-    for arg in args {
-        if let Value::Object(fields) = arg {
-            for (key, value) in fields {
-                params.insert(key.clone(), value.clone());
-            }
-        }
-    }
-    
-    let count = params.get("count")
+pub fn starfield(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.starfield", &["count", "speed"])?;
+    let count = named.get("count")
         .and_then(|v| v.as_number())
+        .map(|c| crate::runtime::quality_governor::current_quality().scale_count(c))
         .unwrap_or(100.0) as i64;
     
-    let speed = params.get("speed")
+    let speed = named.get("speed")
         .and_then(|v| v.as_number())
         .unwrap_or(1.0);
     
@@ -65,7 +48,16 @@ pub fn starfield(args: &[Value]) -> crate::Result<Value> {
 }
 
 
-pub fn rect(args: &[Value]) -> crate::Result<Value> {
+/// A shape's colour, as `color:` or the argument after its geometry; white if it has neither
+fn shape_color(args: &[Value], named: &HashMap<String, Value>, index: usize) -> i64 {
+    named.get("color")
+        .or_else(|| args.get(index))
+        .and_then(|v| v.as_number())
+        .unwrap_or(0xFFFFFF as f64) as i64
+}
+
+pub fn rect(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.rect", &["color"])?;
     if args.len() < 4 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "rect requires x, y, width, height arguments"));
     }
@@ -79,16 +71,15 @@ pub fn rect(args: &[Value]) -> crate::Result<Value> {
     let height = args[3].as_number()
         .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "rect height must be a number"))?;
     
-    let color = args.get(4)
-        .and_then(|v| v.as_number())
-        .unwrap_or(0xFFFFFF as f64) as i64;
+    let color = shape_color(args, named, 4);
     
     println!("Graphics.rect: x={:.1}, y={:.1}, w={:.1}, h={:.1}, color=0x{:06X}", 
              x, y, width, height, color);
     Ok(Value::Null)
 }
 
-pub fn circle(args: &[Value]) -> crate::Result<Value> {
+pub fn circle(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.circle", &["color"])?;
     if args.len() < 3 {
         return Err(crate::errors::synthesis_error(
             crate::errors::ErrorKind::InvalidExpression,
@@ -119,16 +110,15 @@ pub fn circle(args: &[Value]) -> crate::Result<Value> {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "circle radius must be positive"));
     }
     
-    let color = args.get(3)
-        .and_then(|v| v.as_number())
-        .unwrap_or(0xFFFFFF as f64) as i64;
+    let color = shape_color(args, named, 3);
     
     println!("Graphics.circle: x={:.1}, y={:.1}, radius={:.1}, color=0x{:06X}", 
              x, y, radius, color);
     Ok(Value::Null)
 }
 
-pub fn line(args: &[Value]) -> crate::Result<Value> {
+pub fn line(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.line", &["color"])?;
     if args.len() < 4 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "line requires x1, y1, x2, y2 arguments"));
     }
@@ -142,16 +132,51 @@ pub fn line(args: &[Value]) -> crate::Result<Value> {
     let y2 = args[3].as_number()
         .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "line y2 must be a number"))?;
     
-    let color = args.get(4)
-        .and_then(|v| v.as_number())
-        .unwrap_or(0xFFFFFF as f64) as i64;
+    let color = shape_color(args, named, 4);
     
     println!("Graphics.line: ({:.1},{:.1}) to ({:.1},{:.1}), color=0x{:06X}", 
              x1, y1, x2, y2, color);
     Ok(Value::Null)
 }
 
-pub fn text(args: &[Value]) -> crate::Result<Value> {
+/// `Graphics.path(points, color, thickness)`: a stroke through `[x, y]` points, a list of such
+/// strokes, or anything with `paths`, like what `Generate.l_system()` gives
+pub fn path(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.path", &["color", "thickness"])?;
+    let strokes: Vec<&Vec<Value>> = match args.first() {
+        Some(Value::Object(fields)) => match fields.get("paths") {
+            Some(Value::Array(paths)) => paths.iter().filter_map(|path| match path {
+                Value::Array(points) => Some(points),
+                _ => None,
+            }).collect(),
+            _ => Vec::new(),
+        },
+        // A list of strokes starts with a list of points rather than a point
+        Some(Value::Array(items)) if matches!(items.first(), Some(Value::Array(first)) if first.first().and_then(path_point).is_some()) => {
+            items.iter().filter_map(|path| match path {
+                Value::Array(points) => Some(points),
+                _ => None,
+            }).collect()
+        }
+        Some(Value::Array(points)) => vec![points],
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "✏️ Graphics.path() needs points to draw through"
+        )
+        .with_suggestion("Try: Graphics.path([[0, 300], [400, 200], [800, 300]])")
+        .with_suggestion("Or draw a plant: Graphics.path(Generate.l_system(\"F\", {F: \"F[+F]F[-F]F\"}, 25, 4))")),
+    };
+    let color = shape_color(args, named, 1);
+    let thickness = named.get("thickness").or_else(|| args.get(2)).and_then(|v| v.as_number()).unwrap_or(1.0);
+    
+    let points: usize = strokes.iter().map(|stroke| stroke.iter().filter_map(path_point).count()).sum();
+    println!("Graphics.path: {} strokes through {} points, color=0x{:06X}, thickness={:.1}",
+             strokes.len(), points, color, thickness);
+    Ok(Value::Null)
+}
+
+pub fn text(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.text", &["color", "size"])?;
     if args.len() < 3 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "text requires text, x, y arguments"));
     }
@@ -166,11 +191,10 @@ pub fn text(args: &[Value]) -> crate::Result<Value> {
     let y = args[2].as_number()
         .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "text y must be a number"))?;
     
-    let color = args.get(3)
-        .and_then(|v| v.as_number())
-        .unwrap_or(0xFFFFFF as f64) as i64;
+    let color = shape_color(args, named, 3);
     
-    let size = args.get(4)
+    let size = named.get("size")
+        .or_else(|| args.get(4))
         .and_then(|v| v.as_number())
         .unwrap_or(16.0);
     
@@ -181,7 +205,8 @@ pub fn text(args: &[Value]) -> crate::Result<Value> {
 
 // Advanced Effects Functions
 
-pub fn particle_system(args: &[Value]) -> crate::Result<Value> {
+pub fn particle_system(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.particle_system", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "particle_system requires a name argument"));
     }
@@ -191,17 +216,25 @@ pub fn particle_system(args: &[Value]) -> crate::Result<Value> {
         _ => return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "particle system name must be a string")),
     };
     
-    println!("Graphics.particle_system: Creating '{}' particle system", name);
+    // Requested particle budget, scaled down by the quality governor under load
+    let max_particles = args.get(1)
+        .and_then(|v| v.as_number())
+        .map(|c| crate::runtime::quality_governor::current_quality().scale_count(c))
+        .unwrap_or(1000.0) as i64;
+    
+    println!("Graphics.particle_system: Creating '{}' particle system (max {} particles)", name, max_particles);
     
     let mut result = std::collections::HashMap::new();
     result.insert("type".to_string(), Value::String("particle_system".to_string()));
     result.insert("name".to_string(), Value::String(name));
     result.insert("active".to_string(), Value::Boolean(true));
+    result.insert("max_particles".to_string(), Value::Integer(max_particles));
     Ok(Value::Object(result))
 }
 
-pub fn bloom_effect(args: &[Value]) -> crate::Result<Value> {
-    let threshold = args.get(0)
+pub fn bloom_effect(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.bloom_effect", &[])?;
+    let threshold = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(0.8);
     
@@ -211,7 +244,7 @@ pub fn bloom_effect(args: &[Value]) -> crate::Result<Value> {
     
     let radius = args.get(2)
         .and_then(|v| v.as_number())
-        .unwrap_or(5.0);
+        .unwrap_or(5.0) * crate::runtime::quality_governor::current_quality().effect_quality as f64;
     
     println!("Graphics.bloom_effect: threshold={:.2}, intensity={:.2}, radius={:.1}", 
              threshold, intensity, radius);
@@ -224,8 +257,9 @@ pub fn bloom_effect(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn depth_of_field(args: &[Value]) -> crate::Result<Value> {
-    let focus_distance = args.get(0)
+pub fn depth_of_field(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.depth_of_field", &[])?;
+    let focus_distance = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(5.0);
     
@@ -243,8 +277,9 @@ pub fn depth_of_field(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn screen_shake(args: &[Value]) -> crate::Result<Value> {
-    let intensity = args.get(0)
+pub fn screen_shake(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.screen_shake", &[])?;
+    let intensity = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(10.0);
     
@@ -262,8 +297,9 @@ pub fn screen_shake(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn wind_effect(args: &[Value]) -> crate::Result<Value> {
-    let direction = args.get(0)
+pub fn wind_effect(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.wind_effect", &[])?;
+    let direction = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(0.0);
     
@@ -286,8 +322,9 @@ pub fn wind_effect(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn flash(args: &[Value]) -> crate::Result<Value> {
-    let color = args.get(0)
+pub fn flash(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.flash", &[])?;
+    let color = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(0xFFFFFF as f64) as i64;
     
@@ -304,8 +341,9 @@ pub fn flash(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn lightning_strike(args: &[Value]) -> crate::Result<Value> {
-    let position_x = args.get(0)
+pub fn lightning_strike(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.lightning_strike", &[])?;
+    let position_x = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(50.0);
     
@@ -333,8 +371,9 @@ pub fn lightning_strike(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn rainbow_arc(args: &[Value]) -> crate::Result<Value> {
-    let center_x = args.get(0)
+pub fn rainbow_arc(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.rainbow_arc", &[])?;
+    let center_x = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(50.0);
     
@@ -362,8 +401,9 @@ pub fn rainbow_arc(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn rain_effect(args: &[Value]) -> crate::Result<Value> {
-    let intensity = args.get(0)
+pub fn rain_effect(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.rain_effect", &[])?;
+    let intensity = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(0.5);
     
@@ -379,4 +419,1019 @@ pub fn rain_effect(args: &[Value]) -> crate::Result<Value> {
     result.insert("intensity".to_string(), Value::Float(intensity));
     result.insert("duration".to_string(), Value::Float(duration));
     Ok(Value::Object(result))
-}
\ No newline at end of file
+}
+
+// Stylization Effects
+
+pub fn ascii_art(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.ascii_art", &[])?;
+    let cell_size = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(8.0);
+    
+    let invert = args.get(1)
+        .and_then(|v| match v { Value::Boolean(b) => Some(*b), _ => None })
+        .unwrap_or(false);
+    
+    let layer = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    if cell_size < 5.0 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎨 Graphics.ascii_art() cells must be at least 5 pixels"
+        )
+        .with_suggestion("Try: Graphics.ascii_art(8)")
+        .with_suggestion("Bigger cells give chunkier, more readable characters"));
+    }
+    
+    println!("Graphics.ascii_art: cell_size={:.0}, invert={}, layer={}", cell_size, invert, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("ascii_art".to_string()));
+    result.insert("cell_size".to_string(), Value::Float(cell_size));
+    result.insert("invert".to_string(), Value::Boolean(invert));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+pub fn halftone(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.halftone", &[])?;
+    let dot_size = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(6.0);
+    
+    let angle = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(45.0);
+    
+    let monochrome = args.get(2)
+        .and_then(|v| match v { Value::Boolean(b) => Some(*b), _ => None })
+        .unwrap_or(true);
+    
+    let layer = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Graphics.halftone: dot_size={:.1}, angle={:.1}°, monochrome={}, layer={}", 
+             dot_size, angle, monochrome, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("halftone".to_string()));
+    result.insert("dot_size".to_string(), Value::Float(dot_size));
+    result.insert("angle".to_string(), Value::Float(angle));
+    result.insert("monochrome".to_string(), Value::Boolean(monochrome));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+pub fn dither(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.dither", &[])?;
+    let levels = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(2.0) as i64;
+    
+    let matrix_size = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(4.0) as i64;
+    
+    let layer = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    if !matches!(matrix_size, 2 | 4 | 8) {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🎨 Graphics.dither() matrix size {} isn't supported", matrix_size)
+        )
+        .with_suggestion("Bayer matrices come in sizes 2, 4 or 8")
+        .with_suggestion("Try: Graphics.dither(2, 4)"));
+    }
+    
+    println!("Graphics.dither: levels={}, matrix={}x{}, layer={}", levels, matrix_size, matrix_size, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("dither".to_string()));
+    result.insert("levels".to_string(), Value::Integer(levels.max(2)));
+    result.insert("matrix_size".to_string(), Value::Integer(matrix_size));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+pub fn pixelate(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.pixelate", &[])?;
+    let block_size = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(8.0);
+    
+    let palette = match args.get(1) {
+        Some(Value::String(name)) => {
+            if crate::graphics::stylize::named_palette(name).is_none() {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    format!("🎨 Unknown palette '{}'", name)
+                )
+                .with_suggestion("Available palettes: gameboy, cga, pico8, mono")
+                .with_suggestion("Leave the palette out to keep the original colors"));
+            }
+            Value::String(name.clone())
+        }
+        _ => Value::Null,
+    };
+    
+    let layer = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Graphics.pixelate: block_size={:.0}, palette={}, layer={}", block_size, palette, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("pixelate".to_string()));
+    result.insert("block_size".to_string(), Value::Float(block_size.max(1.0)));
+    result.insert("palette".to_string(), palette);
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+// Signed-Distance-Field Shapes
+
+fn sdf_number(args: &[Value], index: usize, function: &str, param: &str) -> crate::Result<f64> {
+    args.get(index)
+        .and_then(|v| v.as_number())
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🎨 Graphics.{}() needs a number for {}", function, param)
+        )
+        .with_suggestion("SDF positions and sizes use 0.0-1.0 screen units")
+        .with_suggestion("Try: Graphics.sdf_circle(0.5, 0.5, 0.2)"))
+}
+
+pub fn sdf_circle(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_circle", &[])?;
+    let x = sdf_number(args, 0, "sdf_circle", "x")?;
+    let y = sdf_number(args, 1, "sdf_circle", "y")?;
+    let radius = sdf_number(args, 2, "sdf_circle", "radius")?;
+    
+    Ok(crate::graphics::sdf::shape_value("circle", &[("x", x), ("y", y), ("radius", radius)]))
+}
+
+pub fn sdf_box(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_box", &[])?;
+    let x = sdf_number(args, 0, "sdf_box", "x")?;
+    let y = sdf_number(args, 1, "sdf_box", "y")?;
+    let width = sdf_number(args, 2, "sdf_box", "width")?;
+    let height = sdf_number(args, 3, "sdf_box", "height")?;
+    let rounding = args.get(4)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0)
+        .min(width.min(height) * 0.5);
+    
+    Ok(crate::graphics::sdf::shape_value("box", &[
+        ("x", x), ("y", y), ("width", width), ("height", height), ("rounding", rounding),
+    ]))
+}
+
+pub fn sdf_ring(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_ring", &[])?;
+    let x = sdf_number(args, 0, "sdf_ring", "x")?;
+    let y = sdf_number(args, 1, "sdf_ring", "y")?;
+    let radius = sdf_number(args, 2, "sdf_ring", "radius")?;
+    let thickness = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.02);
+    
+    Ok(crate::graphics::sdf::shape_value("ring", &[
+        ("x", x), ("y", y), ("radius", radius), ("thickness", thickness),
+    ]))
+}
+
+pub fn sdf_segment(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_segment", &[])?;
+    let x1 = sdf_number(args, 0, "sdf_segment", "x1")?;
+    let y1 = sdf_number(args, 1, "sdf_segment", "y1")?;
+    let x2 = sdf_number(args, 2, "sdf_segment", "x2")?;
+    let y2 = sdf_number(args, 3, "sdf_segment", "y2")?;
+    let thickness = args.get(4)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.02);
+    
+    Ok(crate::graphics::sdf::shape_value("segment", &[
+        ("x1", x1), ("y1", y1), ("x2", x2), ("y2", y2), ("thickness", thickness),
+    ]))
+}
+
+fn sdf_combine(args: &[Value], operation: &str) -> crate::Result<Value> {
+    if args.len() < 2 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🎨 Graphics.sdf_{}() needs two shapes to combine", operation)
+        )
+        .with_suggestion(format!("Try: Graphics.sdf_{}(circle_a, circle_b, 0.1)", operation))
+        .with_suggestion("The optional third value controls how smoothly the shapes melt together"));
+    }
+    
+    // Validate both sides early so mistakes point at the combine call
+    crate::graphics::sdf::SdfNode::from_value(&args[0])?;
+    crate::graphics::sdf::SdfNode::from_value(&args[1])?;
+    
+    let smoothness = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0)
+        .max(0.0);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("sdf".to_string()));
+    result.insert("shape".to_string(), Value::String(operation.to_string()));
+    result.insert("left".to_string(), args[0].clone());
+    result.insert("right".to_string(), args[1].clone());
+    result.insert("smoothness".to_string(), Value::Float(smoothness));
+    Ok(Value::Object(result))
+}
+
+pub fn sdf_union(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_union", &[])?;
+    sdf_combine(args, "union")
+}
+
+pub fn sdf_subtract(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_subtract", &[])?;
+    sdf_combine(args, "subtract")
+}
+
+pub fn sdf_intersect(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_intersect", &[])?;
+    sdf_combine(args, "intersect")
+}
+
+pub fn sdf_render(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.sdf_render", &[])?;
+    let scene = match args.first() {
+        Some(value) => crate::graphics::sdf::SdfNode::from_value(value)?,
+        None => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎨 Graphics.sdf_render() needs a shape to draw"
+        )
+        .with_suggestion("Try: Graphics.sdf_render(Graphics.sdf_circle(0.5, 0.5, 0.2))")),
+    };
+    
+    let color = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0xFFFFFF as f64) as i64;
+    
+    let glow = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0)
+        .max(0.0);
+    
+    let renderer = crate::graphics::sdf::SdfRenderer::new(crate::graphics::Color::from_hex(color as u32))
+        .with_glow(glow as f32, 1.0);
+    let shader = renderer.to_wgsl(&scene);
+    
+    println!("Graphics.sdf_render: color=0x{:06X}, glow={:.3}, shader={} bytes", color, glow, shader.len());
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("sdf_render".to_string()));
+    result.insert("shape".to_string(), args[0].clone());
+    result.insert("color".to_string(), Value::Integer(color));
+    result.insert("glow".to_string(), Value::Float(glow));
+    result.insert("shader".to_string(), Value::String(shader));
+    Ok(Value::Object(result))
+}
+
+// Fluid Simulation
+
+/// Name of the fluid a call refers to: `name:`, or "fluid"
+fn fluid_name(named: &HashMap<String, Value>) -> String {
+    match named.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => "fluid".to_string(),
+    }
+}
+
+fn missing_fluid(function: &str, name: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("🌊 Graphics.{}() has no fluid called \"{}\" to push", function, name)
+    )
+    .with_suggestion("Start one first with Graphics.fluid(128)")
+    .with_suggestion("Pass name: to reach a fluid started with a name, like Graphics.fluid(128, name: \"smoke\")")
+}
+
+/// `Graphics.fluid(resolution, viscosity, dissipation, layer, name:)`: start a fluid the frame loop
+/// steps and draws as `layer`, or change the settings of the one already running under that name.
+/// Its dye is published every frame as the RGBA `texture` stream
+pub fn fluid(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.fluid", &["name"])?;
+    let resolution = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(128.0);
+    
+    let viscosity = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0001);
+    
+    let dissipation = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.2);
+    
+    let layer = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    let max = crate::graphics::MAX_FLUID_RESOLUTION;
+    if !(16.0..=max as f64).contains(&resolution) {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🌊 Graphics.fluid() resolution {} is out of range", resolution)
+        )
+        .with_suggestion(format!("Use a grid resolution between 16 and {}", max))
+        .with_suggestion("128 is smooth and light enough for real-time use"));
+    }
+    
+    let resolution = (resolution * crate::runtime::quality_governor::current_quality().resolution_scale as f64).max(16.0) as usize;
+    let name = fluid_name(named);
+    
+    let mut fluids = crate::graphics::fluid_layers().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Setup that reruns keeps the running fluid; the solver starts it over if its grid changes size
+    let running = fluids.entry(name.clone())
+        .or_insert_with(|| crate::graphics::FluidLayer::new(resolution, layer));
+    running.resolution = resolution;
+    running.viscosity = viscosity.max(0.0) as f32;
+    running.dissipation = dissipation.max(0.0) as f32;
+    running.layer = layer;
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("fluid".to_string()));
+    result.insert("name".to_string(), Value::String(name.clone()));
+    result.insert("resolution".to_string(), Value::Integer(resolution as i64));
+    result.insert("viscosity".to_string(), Value::Float(viscosity.max(0.0)));
+    result.insert("dissipation".to_string(), Value::Float(dissipation.max(0.0)));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    result.insert("texture".to_string(), Value::Stream(crate::runtime::types::Stream {
+        name: format!("{}.texture", name),
+        data_type: crate::runtime::types::DataType::Visual,
+        sample_rate: None,
+    }));
+    Ok(Value::Object(result))
+}
+
+/// `Graphics.fluid_emitter(x, y, angle, color, strength, source, name:)`: keep pushing dye and force
+/// into a fluid every frame, scaled by the newest sample of `source` when one is given. Calling it
+/// again at the same position changes that emitter rather than adding another
+pub fn fluid_emitter(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.fluid_emitter", &["name"])?;
+    let x = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.5);
+    
+    let y = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.5);
+    
+    let angle = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(-90.0);
+    
+    let color = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0x33CCFF as f64) as i64;
+    
+    let strength = args.get(4)
+        .and_then(|v| v.as_number())
+        .unwrap_or(50.0);
+    
+    // An optional stream (e.g. a beat or FFT band) scales the injection each frame
+    let source = match args.get(5) {
+        Some(Value::Stream(stream)) => Some(stream.name.clone()),
+        Some(Value::String(name)) => Some(name.clone()),
+        _ => None,
+    };
+    
+    let name = fluid_name(named);
+    let mut fluids = crate::graphics::fluid_layers().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let running = fluids.get_mut(&name).ok_or_else(|| missing_fluid("fluid_emitter", &name))?;
+    let mut emitter = crate::graphics::FluidEmitter::new(
+        x as f32, y as f32, angle as f32, crate::graphics::Color::from_hex(color as u32)
+    );
+    emitter.strength = strength as f32;
+    running.set_emitter(emitter, source.clone());
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("fluid_emitter".to_string()));
+    result.insert("fluid".to_string(), Value::String(name));
+    result.insert("x".to_string(), Value::Float(x));
+    result.insert("y".to_string(), Value::Float(y));
+    result.insert("angle".to_string(), Value::Float(angle));
+    result.insert("color".to_string(), Value::Integer(color));
+    result.insert("strength".to_string(), Value::Float(strength));
+    result.insert("source".to_string(), source.map_or(Value::Null, Value::String));
+    Ok(Value::Object(result))
+}
+
+/// `Graphics.fluid_force(x, y, force_x, force_y, radius, name:)`: one push, felt from the next frame on
+pub fn fluid_force(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.fluid_force", &["name"])?;
+    if args.len() < 4 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🌊 Graphics.fluid_force() needs a position and a push direction"
+        )
+        .with_suggestion("Try: Graphics.fluid_force(0.5, 0.5, 10, 0)")
+        .with_suggestion("Arguments are x, y, force_x, force_y and an optional radius"));
+    }
+    
+    let mut values = Vec::with_capacity(4);
+    for (arg, name) in args.iter().zip(["x", "y", "force_x", "force_y"]) {
+        values.push(arg.as_number()
+            .ok_or_else(|| crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🌊 Graphics.fluid_force() {} must be a number", name)
+            ))?);
+    }
+    
+    let radius = args.get(4)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.03);
+    
+    let name = fluid_name(named);
+    let mut fluids = crate::graphics::fluid_layers().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let running = fluids.get_mut(&name).ok_or_else(|| missing_fluid("fluid_force", &name))?;
+    running.forces.push([values[0] as f32, values[1] as f32, values[2] as f32, values[3] as f32, radius as f32]);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("fluid_force".to_string()));
+    result.insert("fluid".to_string(), Value::String(name));
+    result.insert("x".to_string(), Value::Float(values[0]));
+    result.insert("y".to_string(), Value::Float(values[1]));
+    result.insert("force_x".to_string(), Value::Float(values[2]));
+    result.insert("force_y".to_string(), Value::Float(values[3]));
+    result.insert("radius".to_string(), Value::Float(radius));
+    Ok(Value::Object(result))
+}
+
+
+// Kinetic Typography
+
+fn glyphs_value(glyphs: &[crate::graphics::typography::Glyph]) -> Value {
+    Value::Array(glyphs.iter().map(|g| g.to_value()).collect())
+}
+
+fn text_argument(args: &[Value], function: &str) -> crate::Result<String> {
+    match args.first() {
+        Some(Value::String(s)) => Ok(s.clone()),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🔤 Graphics.{}() needs the text to lay out as its first argument", function)
+        )
+        .with_suggestion(format!("Try: Graphics.{}(\"hello\", ...)", function))),
+    }
+}
+
+fn path_point(value: &Value) -> Option<crate::graphics::primitives::Point> {
+    match value {
+        Value::Array(coords) if coords.len() >= 2 => Some(crate::graphics::primitives::Point::new(
+            coords[0].as_number()? as f32,
+            coords[1].as_number()? as f32,
+        )),
+        Value::Object(fields) => Some(crate::graphics::primitives::Point::new(
+            fields.get("x")?.as_number()? as f32,
+            fields.get("y")?.as_number()? as f32,
+        )),
+        _ => None,
+    }
+}
+
+pub fn text_glyphs(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.text_glyphs", &[])?;
+    let text = text_argument(args, "text_glyphs")?;
+    
+    let x = args.get(1).and_then(|v| v.as_number()).unwrap_or(0.0) as f32;
+    let y = args.get(2).and_then(|v| v.as_number()).unwrap_or(0.0) as f32;
+    let size = args.get(3).and_then(|v| v.as_number()).unwrap_or(16.0) as f32;
+    let color = args.get(4).and_then(|v| v.as_number()).unwrap_or(0xFFFFFF as f64) as u32;
+    
+    let glyphs = crate::graphics::typography::layout_line(&text, x, y, size, crate::graphics::primitives::Color::from_hex(color));
+    
+    println!("Graphics.text_glyphs: '{}' -> {} glyphs at ({:.1},{:.1}), size={:.1}", 
+             text, glyphs.len(), x, y, size);
+    Ok(glyphs_value(&glyphs))
+}
+
+pub fn text_on_path(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.text_on_path", &[])?;
+    let text = text_argument(args, "text_on_path")?;
+    
+    let points: Vec<_> = match args.get(1) {
+        Some(Value::Array(items)) => items.iter().filter_map(path_point).collect(),
+        _ => Vec::new(),
+    };
+    
+    if points.len() < 2 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🔤 Graphics.text_on_path() needs a path with at least two points"
+        )
+        .with_suggestion("Try: Graphics.text_on_path(\"hello\", [[0, 300], [400, 200], [800, 300]])")
+        .with_suggestion("Points can be [x, y] arrays or {x: .., y: ..} objects"));
+    }
+    
+    let size = args.get(2).and_then(|v| v.as_number()).unwrap_or(16.0) as f32;
+    let offset = args.get(3).and_then(|v| v.as_number()).unwrap_or(0.0) as f32;
+    let color = args.get(4).and_then(|v| v.as_number()).unwrap_or(0xFFFFFF as f64) as u32;
+    
+    let path = crate::graphics::typography::TextPath::new(points);
+    let glyphs = path.layout(&text, size, offset, crate::graphics::primitives::Color::from_hex(color));
+    
+    println!("Graphics.text_on_path: '{}' along {:.1}px path, {} glyphs placed, offset={:.1}", 
+             text, path.length(), glyphs.len(), offset);
+    Ok(glyphs_value(&glyphs))
+}
+
+pub fn text_on_circle(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.text_on_circle", &[])?;
+    let text = text_argument(args, "text_on_circle")?;
+    
+    let cx = args.get(1).and_then(|v| v.as_number()).unwrap_or(400.0) as f32;
+    let cy = args.get(2).and_then(|v| v.as_number()).unwrap_or(300.0) as f32;
+    let radius = args.get(3).and_then(|v| v.as_number()).unwrap_or(150.0) as f32;
+    let size = args.get(4).and_then(|v| v.as_number()).unwrap_or(16.0) as f32;
+    let offset = args.get(5).and_then(|v| v.as_number()).unwrap_or(0.0) as f32;
+    let color = args.get(6).and_then(|v| v.as_number()).unwrap_or(0xFFFFFF as f64) as u32;
+    
+    let path = crate::graphics::typography::TextPath::circle(cx, cy, radius, 96);
+    let glyphs = path.layout(&text, size, offset, crate::graphics::primitives::Color::from_hex(color));
+    
+    println!("Graphics.text_on_circle: '{}' around ({:.1},{:.1}) r={:.1}, offset={:.1}", 
+             text, cx, cy, radius, offset);
+    Ok(glyphs_value(&glyphs))
+}
+
+pub fn animate_glyphs(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.animate_glyphs", &[])?;
+    let mut glyphs: Vec<_> = match args.first() {
+        Some(Value::Array(items)) => items.iter()
+            .filter_map(crate::graphics::typography::Glyph::from_value)
+            .collect(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🔤 Graphics.animate_glyphs() needs glyphs from text_glyphs() or text_on_path()"
+        )
+        .with_suggestion("Try: glyphs = Graphics.text_glyphs(\"hello\", 100, 100, 32)")
+        .with_suggestion("Then: Graphics.animate_glyphs(glyphs, \"wave\", time, Audio.beat())")),
+    };
+    
+    let mode = match args.get(1) {
+        Some(Value::String(s)) => s.as_str(),
+        _ => "wave",
+    };
+    let animation = crate::graphics::typography::GlyphAnimation::from_name(mode)
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🔤 Unknown glyph animation '{}'", mode)
+        )
+        .with_suggestion("Available animations: wave, spin, pulse, scatter, rainbow"))?;
+    
+    let time = args.get(2).and_then(|v| v.as_number()).unwrap_or(0.0) as f32;
+    let amount = args.get(3).and_then(|v| v.as_number()).unwrap_or(1.0) as f32;
+    let spread = args.get(4).and_then(|v| v.as_number()).unwrap_or(0.3) as f32;
+    
+    animation.apply(&mut glyphs, time, amount, spread);
+    
+    println!("Graphics.animate_glyphs: {} glyphs, mode={}, time={:.2}, amount={:.2}", 
+             glyphs.len(), mode, time, amount);
+    Ok(glyphs_value(&glyphs))
+}
+
+
+// Camera Compositing
+
+pub fn camera_layer(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.camera_layer", &[])?;
+    let device = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    let mirror = match args.get(1) {
+        Some(Value::String(name)) => {
+            if crate::graphics::camera_layer::Mirror::from_name(name).is_none() {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    format!("📷 Unknown mirror mode '{}'", name)
+                )
+                .with_suggestion("Available modes: none, horizontal, vertical, both"));
+            }
+            name.clone()
+        }
+        _ => "horizontal".to_string(),
+    };
+    
+    let opacity = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(1.0)
+        .clamp(0.0, 1.0);
+    
+    let layer = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Graphics.camera_layer: device={}, mirror={}, opacity={:.2}, layer={}", device, mirror, opacity, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("camera_layer".to_string()));
+    result.insert("device".to_string(), Value::Integer(device));
+    result.insert("mirror".to_string(), Value::String(mirror));
+    result.insert("opacity".to_string(), Value::Float(opacity));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+pub fn chroma_key(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.chroma_key", &[])?;
+    let key_color = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(0x00FF00 as f64) as i64;
+    
+    let tolerance = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.1);
+    
+    let softness = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.05);
+    
+    let spill = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.5)
+        .clamp(0.0, 1.0);
+    
+    let layer = args.get(4)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Graphics.chroma_key: key=0x{:06X}, tolerance={:.3}, softness={:.3}, spill={:.2}, layer={}", 
+             key_color, tolerance, softness, spill, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("chroma_key".to_string()));
+    result.insert("key_color".to_string(), Value::Integer(key_color));
+    result.insert("tolerance".to_string(), Value::Float(tolerance));
+    result.insert("softness".to_string(), Value::Float(softness));
+    result.insert("spill".to_string(), Value::Float(spill));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+pub fn luma_key(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.luma_key", &[])?;
+    let threshold = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.2)
+        .clamp(0.0, 1.0);
+    
+    let softness = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.1);
+    
+    let invert = args.get(2)
+        .map(|v| v.is_truthy())
+        .unwrap_or(false);
+    
+    let layer = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Graphics.luma_key: threshold={:.2}, softness={:.2}, invert={}, layer={}", threshold, softness, invert, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("luma_key".to_string()));
+    result.insert("threshold".to_string(), Value::Float(threshold));
+    result.insert("softness".to_string(), Value::Float(softness));
+    result.insert("invert".to_string(), Value::Boolean(invert));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+pub fn color_grade(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.color_grade", &[])?;
+    let exposure = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0);
+    
+    let contrast = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(1.0);
+    
+    let saturation = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(1.0);
+    
+    let temperature = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0)
+        .clamp(-1.0, 1.0);
+    
+    let layer = args.get(4)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Graphics.color_grade: exposure={:.2}, contrast={:.2}, saturation={:.2}, temperature={:.2}, layer={}", 
+             exposure, contrast, saturation, temperature, layer);
+    
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("color_grade".to_string()));
+    result.insert("exposure".to_string(), Value::Float(exposure));
+    result.insert("contrast".to_string(), Value::Float(contrast));
+    result.insert("saturation".to_string(), Value::Float(saturation));
+    result.insert("temperature".to_string(), Value::Float(temperature));
+    result.insert("layer".to_string(), Value::Integer(layer));
+    Ok(Value::Object(result))
+}
+
+
+// Screen Capture
+
+/// Start capturing `target`, or change the settings of the capture already running for it
+fn start_capture(target: crate::hardware::CaptureTarget, named: &HashMap<String, Value>) -> crate::Result<Value> {
+    use crate::hardware::{open_screen_capture, screen_captures, ScreenCapture, DEFAULT_CAPTURE_WIDTH};
+    
+    let max_width = named.get("width")
+        .and_then(|v| v.as_number())
+        .map_or(DEFAULT_CAPTURE_WIDTH, |width| width.max(16.0) as usize);
+    let fps = named.get("fps")
+        .and_then(|v| v.as_number())
+        .map_or(30.0, |fps| fps.clamp(1.0, 120.0));
+    
+    let name = target.stream_name();
+    let mut captures = screen_captures().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Setup that reruns keeps the running capture and only changes its settings
+    if !captures.contains_key(&name) {
+        let source = open_screen_capture(&target)?;
+        captures.insert(name.clone(), ScreenCapture::start(target, source, fps)?);
+    }
+    if let Some(capture) = captures.get_mut(&name) {
+        capture.max_width = max_width;
+        capture.set_fps(fps);
+    }
+    Ok(Value::Stream(crate::runtime::types::Stream {
+        name,
+        data_type: crate::runtime::types::DataType::Visual,
+        sample_rate: None,
+    }))
+}
+
+/// `Graphics.capture_screen(1, width: 960, fps: 30)`: a display as an RGB texture stream, 0 being the main one
+pub fn capture_screen(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.capture_screen", &["width", "fps"])?;
+    let display = match args.first() {
+        None => 0,
+        Some(Value::Integer(display)) if *display >= 0 => *display as usize,
+        Some(other) => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🖥️ Graphics.capture_screen() takes a display number, not {}", other)
+        )
+        .with_suggestion("Try: Graphics.capture_screen(0) for the main display")),
+    };
+    start_capture(crate::hardware::CaptureTarget::Display(display), named)
+}
+
+/// `Graphics.capture_window("Ableton")`: the first window whose title contains the text, as an RGB texture stream
+pub fn capture_window(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.capture_window", &["width", "fps"])?;
+    let title = match args.first() {
+        Some(Value::String(title)) if !title.trim().is_empty() => title.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🖥️ Graphics.capture_window() needs part of the window's title"
+        )
+        .with_suggestion("Try: Graphics.capture_window(\"Ableton\", width: 960)")),
+    };
+    start_capture(crate::hardware::CaptureTarget::Window(title), named)
+}
+
+/// `Graphics.stop_capture(screen)` with a stream a capture returned; true if it was running
+pub fn stop_capture(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.stop_capture", &[])?;
+    let name = match args.first() {
+        Some(Value::Stream(stream)) => stream.name.clone(),
+        Some(Value::String(name)) => name.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🖥️ Graphics.stop_capture() needs the stream a capture returned"
+        )
+        .with_suggestion("Try: screen = Graphics.capture_screen(0) and later Graphics.stop_capture(screen)")),
+    };
+    let mut captures = crate::hardware::screen_captures().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(captures.remove(&name).is_some()))
+}
+
+
+// Adaptive Quality
+
+pub fn quality(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.quality", &[])?;
+    Ok(crate::runtime::quality_governor::current_quality().to_value())
+}
+
+pub fn set_quality(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.set_quality", &[])?;
+    let level = args.first()
+        .and_then(|v| v.as_number())
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "⚡ Graphics.set_quality() needs a level between 0.0 and 1.0"
+        )
+        .with_suggestion("Try: Graphics.set_quality(0.5)")
+        .with_suggestion("Or let it adapt automatically: config { adaptive_quality: true }"))?;
+    
+    let settings = crate::runtime::quality_governor::set_quality_level(level as f32);
+    println!("Graphics.set_quality: level={:.2} ({})", settings.level, settings.tier.name());
+    Ok(settings.to_value())
+}
+
+
+// Frame Profiling
+
+pub fn profile(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.profile", &[])?;
+    let timings = crate::graphics::latest_timings();
+    let passes: Vec<Value> = timings
+        .iter()
+        .map(|timing| {
+            let mut pass = HashMap::new();
+            pass.insert("name".to_string(), Value::String(timing.name.clone()));
+            pass.insert("kind".to_string(), Value::String(match timing.kind {
+                crate::graphics::PassKind::Gpu => "gpu".to_string(),
+                crate::graphics::PassKind::Cpu => "cpu".to_string(),
+            }));
+            pass.insert("ms".to_string(), Value::Float(timing.average_ms));
+            pass.insert("last_ms".to_string(), Value::Float(timing.last_ms));
+            pass.insert("max_ms".to_string(), Value::Float(timing.max_ms));
+            Value::Object(pass)
+        })
+        .collect();
+    
+    let total: f64 = timings.iter().map(|t| t.average_ms).sum();
+    
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("frame_profile".to_string()));
+    result.insert("passes".to_string(), Value::Array(passes));
+    result.insert("total_ms".to_string(), Value::Float(total));
+    Ok(Value::Object(result))
+}
+
+pub fn debug_hud(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Graphics.debug_hud", &[])?;
+    let x = args.first().and_then(|v| v.as_number()).unwrap_or(10.0);
+    let y = args.get(1).and_then(|v| v.as_number()).unwrap_or(10.0);
+    let size = args.get(2).and_then(|v| v.as_number()).unwrap_or(12.0);
+    
+    let frame = crate::runtime::current_frame();
+    let quality = crate::runtime::quality_governor::current_quality();
+    let mut lines = vec![
+        format!("{:.1} fps  {:.2}ms  dropped {}", frame.fps, frame.delta_time * 1000.0, frame.dropped),
+        format!("quality {} ({:.0}%)", quality.tier.name(), quality.level * 100.0),
+    ];
+    lines.extend(crate::graphics::hud_lines());
+    
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("debug_hud".to_string()));
+    result.insert("lines".to_string(), Value::Array(lines.into_iter().map(Value::String).collect()));
+    result.insert("x".to_string(), Value::Float(x));
+    result.insert("y".to_string(), Value::Float(y));
+    result.insert("size".to_string(), Value::Float(size));
+    Ok(Value::Object(result))
+}
+
+// ISF shaders
+
+pub fn isf(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    let (path, source) = match args.first() {
+        Some(Value::String(path)) => {
+            let asset = crate::runtime::asset_manager().load_blocking(path);
+            match asset {
+                Some(asset) if asset.is_ready() => (path.clone(), String::from_utf8_lossy(&asset.data).into_owned()),
+                _ => return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::FileNotFound,
+                    format!("🎨 Couldn't read the shader '{}'", path)
+                )
+                .with_suggestion("Check the path is relative to your sketch, like \"shaders/glitch.fs\"")),
+            }
+        }
+        // An asset from Assets.load("shaders/glitch.fs") carries its source once ready
+        Some(Value::Object(fields)) if fields.get("source").is_some() => {
+            let text = |key: &str| match fields.get(key) {
+                Some(Value::String(s)) => s.clone(),
+                _ => String::new(),
+            };
+            (text("path"), text("source"))
+        }
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🎨 Graphics.isf() needs the path of an ISF shader"
+        )
+        .with_suggestion("Try: Graphics.isf(\"shaders/glitch.fs\", amount: 0.5)")),
+    };
+
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "shader".to_string());
+    let shader = crate::graphics::isf::IsfShader::parse(&source, &name)?;
+
+    // Named arguments set the shader's inputs, which checks their names
+    let params = shader.resolve(named)?;
+
+    let kind = if shader.is_filter() { "filter" } else { "generator" };
+    println!("Graphics.isf: {} ({}), {} inputs, {} passes", name, kind, shader.inputs.len(), shader.passes.len().max(1));
+
+    let mut result = std::collections::HashMap::new();
+    result.insert("type".to_string(), Value::String("isf_effect".to_string()));
+    result.insert("name".to_string(), Value::String(name));
+    result.insert("path".to_string(), Value::String(path));
+    result.insert("kind".to_string(), Value::String(kind.to_string()));
+    result.insert("description".to_string(), shader.description.clone().map(Value::String).unwrap_or(Value::Null));
+    result.insert("categories".to_string(), Value::Array(shader.categories.iter().cloned().map(Value::String).collect()));
+    result.insert("passes".to_string(), Value::Integer(shader.passes.len().max(1) as i64));
+    result.insert("params".to_string(), Value::Object(params));
+    Ok(Value::Object(result))
+}
+
+// Hydra-style chains
+
+/// Positional and named numbers; the chain checks the names against the function's parameters
+fn hydra_args(name: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<(Vec<f64>, HashMap<String, f64>)> {
+    let positional = args.iter().filter_map(|arg| arg.as_number()).collect();
+    let mut numbers = HashMap::new();
+    for (key, value) in named {
+        let number = value.as_number().ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🎨 {}() needs a number for {}", name, key)
+        ))?;
+        numbers.insert(key.clone(), number);
+    }
+    Ok((positional, numbers))
+}
+
+fn hydra_source(name: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    let (positional, named) = hydra_args(name, args, named)?;
+    Ok(crate::graphics::HydraChain::source(name, &positional, &named)?.to_value())
+}
+
+pub fn hydra_osc(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    hydra_source("osc", args, named)
+}
+
+pub fn hydra_noise(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    hydra_source("noise", args, named)
+}
+
+pub fn hydra_voronoi(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    hydra_source("voronoi", args, named)
+}
+
+pub fn hydra_shape(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    hydra_source("shape", args, named)
+}
+
+pub fn hydra_gradient(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    hydra_source("gradient", args, named)
+}
+
+pub fn hydra_solid(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    hydra_source("solid", args, named)
+}
+
+/// A step on a chain: `.rotate(0.1)`, `.blend(other, 0.3)`, `.out(layer: 1)` or `.glsl()`
+pub fn hydra_method(chain: crate::graphics::HydraChain, name: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    match name {
+        "out" => crate::modules::check_named(named, name, &["layer"])?,
+        "glsl" => crate::modules::check_named(named, name, &[])?,
+        _ => {}
+    }
+    let (positional, named) = hydra_args(name, args, named)?;
+    match name {
+        "out" => {
+            let layer = named.get("layer").or(positional.first()).copied().unwrap_or(0.0).max(0.0) as usize;
+            let steps = chain.ops.len();
+            crate::graphics::hydra_outputs()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .set(layer, chain.clone())?;
+            println!("Graphics.out: layer {}, {} steps", layer, steps);
+            
+            let mut result = match chain.to_value() {
+                Value::Object(fields) => fields,
+                _ => HashMap::new(),
+            };
+            result.insert("layer".to_string(), Value::Integer(layer as i64));
+            Ok(Value::Object(result))
+        }
+        "glsl" => Ok(Value::String(chain.glsl())),
+        _ => {
+            // Blends and modulations take the other chain as their first argument
+            let input = args.first().and_then(crate::graphics::HydraChain::from_value);
+            Ok(chain.then(name, &positional, &named, input)?.to_value())
+        }
+    }
+}
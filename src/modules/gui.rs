@@ -1,7 +1,8 @@
 use crate::runtime::Value;
 use std::collections::HashMap;
 
-pub fn window(args: &[Value]) -> crate::Result<Value> {
+pub fn window(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GUI.window", &["theme"])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "window requires a title argument"));
     }
@@ -11,27 +12,19 @@ pub fn window(args: &[Value]) -> crate::Result<Value> {
         _ => return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "window title must be a string")),
     };
     
-    let mut params = HashMap::new();
-    for arg in &args[1..] {
-        if let Value::Object(fields) = arg {
-            for (key, value) in fields {
-                params.insert(key.clone(), value.clone().into());
-            }
-        }
-    }
-    
-    let theme = params.get("theme")
+    let theme = named.get("theme")
         .map(|v| match v {
             Value::String(s) => s.clone(),
             _ => "light".to_string(),
         })
-        .unwrap_or_else(|| "light".to_string().into());
+        .unwrap_or_else(|| "light".to_string());
     
     println!("GUI.window: title='{}', theme='{}'", title, theme);
     Ok(Value::Null)
 }
 
-pub fn button(args: &[Value]) -> crate::Result<Value> {
+pub fn button(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GUI.button", &["style"])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "button requires a label argument"));
     }
@@ -41,21 +34,12 @@ pub fn button(args: &[Value]) -> crate::Result<Value> {
         _ => return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "button label must be a string")),
     };
     
-    let mut params = HashMap::new();
-    for arg in &args[1..] {
-        if let Value::Object(fields) = arg {
-            for (key, value) in fields {
-                params.insert(key.clone(), value.clone().into());
-            }
-        }
-    }
-    
-    let style = params.get("style")
+    let style = named.get("style")
         .map(|v| match v {
             Value::String(s) => s.clone(),
             _ => "default".to_string(),
         })
-        .unwrap_or_else(|| "default".to_string().into());
+        .unwrap_or_else(|| "default".to_string());
     
     // Mock button click (randomly return true/false for demo)
     let clicked = std::time::SystemTime::now()
@@ -63,11 +47,17 @@ pub fn button(args: &[Value]) -> crate::Result<Value> {
         .unwrap()
         .as_millis() % 100 < 5; // 5% chance of being "clicked"
     
+    // A press from the remote control API counts as a click
+    let clicked = crate::runtime::declare_control(
+        crate::runtime::RemoteControl::new(label.clone(), "button", Value::Boolean(clicked))
+    ).is_truthy();
+    
     println!("GUI.button: label='{}', style='{}', clicked={}", label, style, clicked);
     Ok(Value::Boolean(clicked))
 }
 
-pub fn slider(args: &[Value]) -> crate::Result<Value> {
+pub fn slider(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GUI.slider", &[])?;
     if args.len() < 3 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "slider requires label, min, max arguments"));
     }
@@ -86,7 +76,7 @@ pub fn slider(args: &[Value]) -> crate::Result<Value> {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "slider min must be less than max"));
     }
     
-    let default_val = args.get(3)
+    let _default_val = args.get(3)
         .and_then(|v| v.as_number())
         .unwrap_or((min_val + max_val) / 2.0);
     
@@ -98,13 +88,18 @@ pub fn slider(args: &[Value]) -> crate::Result<Value> {
     
     let normalized = (time_factor.sin() + 1.0) / 2.0; // 0.0 to 1.0
     let current_value = min_val + normalized * (max_val - min_val);
+    let current_value = crate::runtime::declare_control(
+        crate::runtime::RemoteControl::new(label.clone(), "slider", Value::Float(current_value))
+            .with_range(min_val, max_val)
+    ).as_number().unwrap_or(current_value);
     
     println!("GUI.slider: label='{}', range=[{:.2}, {:.2}], value={:.2}", 
              label, min_val, max_val, current_value);
     Ok(Value::Float(current_value))
 }
 
-pub fn checkbox(args: &[Value]) -> crate::Result<Value> {
+pub fn checkbox(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GUI.checkbox", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "checkbox requires a label argument"));
     }
@@ -114,7 +109,7 @@ pub fn checkbox(args: &[Value]) -> crate::Result<Value> {
         _ => return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "checkbox label must be a string")),
     };
     
-    let default_checked = args.get(1)
+    let _default_checked = args.get(1)
         .map(|v| v.is_truthy())
         .unwrap_or(false);
     
@@ -125,12 +120,16 @@ pub fn checkbox(args: &[Value]) -> crate::Result<Value> {
         .as_secs() % 10; // Toggle every 10 seconds
     
     let checked = time_factor < 5;
+    let checked = crate::runtime::declare_control(
+        crate::runtime::RemoteControl::new(label.clone(), "checkbox", Value::Boolean(checked))
+    ).is_truthy();
     
     println!("GUI.checkbox: label='{}', checked={}", label, checked);
     Ok(Value::Boolean(checked))
 }
 
-pub fn dropdown(args: &[Value]) -> crate::Result<Value> {
+pub fn dropdown(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GUI.dropdown", &[])?;
     if args.len() < 2 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "dropdown requires label and options arguments"));
     }
@@ -158,12 +157,12 @@ pub fn dropdown(args: &[Value]) -> crate::Result<Value> {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "dropdown must have at least one option"));
     }
     
-    let default_option = args.get(2)
+    let _default_option = args.get(2)
         .map(|v| match v {
             Value::String(s) => s.clone(),
             _ => options[0].clone(),
         })
-        .unwrap_or_else(|| options[0].clone().into());
+        .unwrap_or_else(|| options[0].clone());
     
     // Mock selection (cycle through options for demo)
     let time_factor = std::time::SystemTime::now()
@@ -171,14 +170,21 @@ pub fn dropdown(args: &[Value]) -> crate::Result<Value> {
         .unwrap()
         .as_secs() as usize % options.len();
     
-    let selected = &options[time_factor];
+    let selected = match crate::runtime::declare_control(
+        crate::runtime::RemoteControl::new(label.clone(), "dropdown", Value::String(options[time_factor].clone()))
+            .with_options(options.clone())
+    ) {
+        Value::String(selected) => selected,
+        _ => options[time_factor].clone(),
+    };
     
     println!("GUI.dropdown: label='{}', selected='{}' from {:?}", 
              label, selected, options);
-    Ok(Value::String(selected.clone()))
+    Ok(Value::String(selected))
 }
 
-pub fn control_group(args: &[Value]) -> crate::Result<Value> {
+pub fn control_group(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "GUI.control_group", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "control_group requires a title argument"));
     }
@@ -192,9 +198,9 @@ pub fn control_group(args: &[Value]) -> crate::Result<Value> {
     
     // Return an object representing the control group
     let mut controls = HashMap::new();
-    controls.insert("title".to_string(), Value::String(title).into());
-    controls.insert("sensitivity".to_string(), Value::Float(1.0).into());
-    controls.insert("effect_type".to_string(), Value::String("plasma".to_string()).into());
+    controls.insert("title".to_string(), Value::String(title));
+    controls.insert("sensitivity".to_string(), Value::Float(1.0));
+    controls.insert("effect_type".to_string(), Value::String("plasma".to_string()));
     
     Ok(Value::Object(controls))
 }
\ No newline at end of file
@@ -0,0 +1,140 @@
+use crate::hardware::hid::{hid_input, hid_stream_name, open_device, list_devices, HidDevice, HidField};
+use crate::runtime::types::{DataType, Stream};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Options that configure the device rather than name a field
+const DEVICE_OPTIONS: [&str; 2] = ["name", "report"];
+
+fn device_name(args: &[Value], function: &str) -> crate::Result<String> {
+    match args.first() {
+        Some(Value::String(name)) => Ok(name.clone()),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🕹️ Hid.{}() needs the device's name", function)
+        )
+        .with_suggestion(format!("Try: Hid.{}(\"pedal\")", function))),
+    }
+}
+
+fn streams(device: &HidDevice) -> Value {
+    let fields = device.fields.iter()
+        .map(|field| (field.name.clone(), Value::Stream(Stream {
+            name: hid_stream_name(&device.name, &field.name),
+            data_type: DataType::Control,
+            sample_rate: None,
+        })))
+        .collect();
+    Value::Object(fields)
+}
+
+/// `pedal = Hid.open("Foot Switch", volume: "u8@1", hold: "bit@2.0")` opens the first device
+/// whose product name contains "Foot Switch" (or a "vid:pid" pair) and returns a stream per field
+pub fn hid_open(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    // Every name that isn't a device option is a field, so there's no name to reject
+    let query = device_name(args, "open")?;
+    let mut fields = named.iter()
+        .filter(|(name, _)| !DEVICE_OPTIONS.contains(&name.as_str()))
+        .map(|(name, description)| match description {
+            Value::String(description) => HidField::parse(name, description),
+            other => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🕹️ The HID field {} should be a description like \"u8@1\", not {}", name, other.type_name())
+            )),
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    if fields.is_empty() {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🕹️ Hid.open(\"{}\") needs at least one field to read", query)
+        )
+        .with_suggestion("Name each value and say where it sits in the report: Hid.open(\"pedal\", volume: \"u8@1\")"));
+    }
+    // Same order every run, so stream creation and errors don't depend on hashing
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+    let report_id = match named.get("report").and_then(|v| v.as_number()) {
+        Some(id) if (0.0..=255.0).contains(&id) => Some(id as u8),
+        Some(id) => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🕹️ {} isn't a HID report ID", id)
+        )
+        .with_suggestion("Report IDs go from 0 to 255")),
+        None => None,
+    };
+    let name = match named.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => query.clone(),
+    };
+
+    let mut input = hid_input().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Scripts usually open inside setup that may rerun; the same name hands back the same streams
+    if let Some(device) = input.device_mut(&name) {
+        device.fields = fields;
+        device.report_id = report_id;
+        return Ok(streams(device));
+    }
+    let (_, source) = open_device(&query)?;
+    let device = HidDevice::new(&name, fields, report_id).with_source(source);
+    let value = streams(&device);
+    input.add(device)?;
+    Ok(value)
+}
+
+/// `Hid.read("Foot Switch", "volume")`, or `Hid.read(stream)` with one of the streams
+/// `Hid.open()` returned: the field's latest value, or 0 until the device first reports
+pub fn hid_read(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Hid.read", &[])?;
+    let (device, field) = match (args.first(), args.get(1)) {
+        (Some(Value::Stream(stream)), _) => match stream.name.rsplit_once('.') {
+            Some((device, field)) => (device.to_string(), field.to_string()),
+            None => (stream.name.clone(), String::new()),
+        },
+        (Some(Value::String(device)), Some(Value::String(field))) => (device.clone(), field.clone()),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🕹️ Hid.read() needs a field stream, or a device and field name"
+        )
+        .with_suggestion("Try: Hid.read(\"pedal\", \"volume\")")),
+    };
+    let input = hid_input().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(device) = input.device(&device) else {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InputDeviceError,
+            format!("🕹️ No HID device called '{}' is open", device)
+        )
+        .with_suggestion("Open it first: Hid.open(\"pedal\", volume: \"u8@1\")"));
+    };
+    if !device.fields.iter().any(|known| known.name == field) {
+        let known: Vec<&str> = device.fields.iter().map(|known| known.name.as_str()).collect();
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::UnknownFunction,
+            format!("🕹️ {} has no field called '{}'", device.name, field)
+        )
+        .with_suggestion(format!("Fields: {}", known.join(", "))));
+    }
+    Ok(Value::Float(device.value(&field).unwrap_or(0.0)))
+}
+
+pub fn hid_close(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Hid.close", &[])?;
+    let name = device_name(args, "close")?;
+    Ok(Value::Boolean(hid_input().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).close(&name)))
+}
+
+/// Every HID device plugged in, to find the name or vid:pid to open
+pub fn hid_devices(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Hid.devices", &[])?;
+    let devices = list_devices()?
+        .into_iter()
+        .map(|device| {
+            let mut fields = HashMap::new();
+            fields.insert("product".to_string(), Value::String(device.product));
+            fields.insert("manufacturer".to_string(), Value::String(device.manufacturer));
+            fields.insert("id".to_string(), Value::String(format!("{:04x}:{:04x}", device.vendor_id, device.product_id)));
+            fields.insert("usage_page".to_string(), Value::Integer(device.usage_page as i64));
+            fields.insert("usage".to_string(), Value::Integer(device.usage as i64));
+            Value::Object(fields)
+        })
+        .collect();
+    Ok(Value::Array(devices))
+}
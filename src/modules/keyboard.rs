@@ -0,0 +1,86 @@
+use crate::hardware::keyboard::{keyboard, KeyAction, KeyCombo};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+fn combo(args: &[Value], function: &str) -> crate::Result<KeyCombo> {
+    match args.first() {
+        Some(Value::String(text)) => KeyCombo::parse(text),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("⌨️ Keyboard.{}() needs a key name", function)
+        )
+        .with_suggestion(format!("Try: Keyboard.{}(\"space\") or Keyboard.{}(\"ctrl+s\")", function, function))),
+    }
+}
+
+/// Whether a key (or every key in a combo) is held right now
+pub fn keyboard_down(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Keyboard.down", &[])?;
+    let combo = combo(args, "down")?;
+    let state = keyboard().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(state.is_down(&combo)))
+}
+
+/// True on the one frame a key or combo went down
+pub fn keyboard_pressed(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Keyboard.pressed", &[])?;
+    let combo = combo(args, "pressed")?.to_string();
+    let state = keyboard().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(state.frame_events().iter().any(|e| e.pressed && !e.repeat && e.combo == combo)))
+}
+
+/// True on the one frame a key came up
+pub fn keyboard_released(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Keyboard.released", &[])?;
+    let combo = combo(args, "released")?;
+    let state = keyboard().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(state.frame_events().iter().any(|e| !e.pressed && e.key == combo.key)))
+}
+
+/// Every key event since the last frame, oldest first
+pub fn keyboard_events(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Keyboard.events", &[])?;
+    let state = keyboard().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let events = state.frame_events()
+        .iter()
+        .map(|event| {
+            let mut fields = HashMap::new();
+            fields.insert("key".to_string(), Value::String(event.key.clone()));
+            fields.insert("combo".to_string(), Value::String(event.combo.clone()));
+            fields.insert("pressed".to_string(), Value::Boolean(event.pressed));
+            fields.insert("repeat".to_string(), Value::Boolean(event.repeat));
+            Value::Object(fields)
+        })
+        .collect();
+    Ok(Value::Array(events))
+}
+
+/// Rebind a hotkey: Keyboard.bind("ctrl+p", "panic"), or "none" to free the key for the script
+pub fn keyboard_bind(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Keyboard.bind", &[])?;
+    let combo = combo(args, "bind")?;
+    let action = match args.get(1) {
+        Some(Value::String(name)) if name == "none" => None,
+        Some(Value::String(name)) => match KeyAction::from_name(name) {
+            Some(action) => Some(action),
+            None => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("⌨️ '{}' isn't a key action", name)
+            )
+            .with_suggestion("Actions are \"transport\", \"fullscreen\", \"panic\", \"undo\", \"redo\" or \"none\"")),
+        },
+        _ => None,
+    };
+    keyboard().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).bind(combo, action);
+    Ok(Value::Null)
+}
+
+pub fn keyboard_bindings(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Keyboard.bindings", &[])?;
+    let state = keyboard().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let bindings = state.bindings()
+        .iter()
+        .map(|(combo, action)| (combo.to_string(), Value::String(action.name().to_string())))
+        .collect();
+    Ok(Value::Object(bindings))
+}
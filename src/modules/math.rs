@@ -1,23 +1,30 @@
+use crate::runtime::complex;
+use crate::runtime::vectors::{self, Linear, IDENTITY3, IDENTITY4};
 use crate::runtime::Value;
+use num_complex::Complex64;
+use std::collections::HashMap;
 
-pub fn sin(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn sin(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.sin", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.sin()))
     } else {
         Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "sin() requires a numeric argument"))
     }
 }
 
-pub fn cos(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn cos(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.cos", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.cos()))
     } else {
         Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "cos() requires a numeric argument"))
     }
 }
 
-pub fn sqrt(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn sqrt(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.sqrt", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         if value < 0.0 {
             Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "sqrt() requires a non-negative argument"))
         } else {
@@ -28,15 +35,21 @@ pub fn sqrt(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn abs(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn abs(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.abs", &[])?;
+    // The distance from zero, which for a complex number is its magnitude
+    if let Some(z) = args.first().and_then(complex::from_value) {
+        return Ok(Value::Float(z.norm()));
+    }
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.abs()))
     } else {
         Err(crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, "abs() requires a numeric argument"))
     }
 }
 
-pub fn min(args: &[Value]) -> crate::Result<Value> {
+pub fn min(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.min", &[])?;
     if args.len() < 2 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "min() requires at least 2 arguments"));
     }
@@ -55,7 +68,8 @@ pub fn min(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Float(min_val))
 }
 
-pub fn max(args: &[Value]) -> crate::Result<Value> {
+pub fn max(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.max", &[])?;
     if args.len() < 2 {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "max() requires at least 2 arguments"));
     }
@@ -66,7 +80,7 @@ pub fn max(args: &[Value]) -> crate::Result<Value> {
             "📊 Math.max() needs numbers to compare"
         )
         .with_suggestion("Try: Math.max(3.5, 1.2, 4.8)")
-        .with_suggestion("All arguments must be numbers, not text or other types"))?
+        .with_suggestion("All arguments must be numbers, not text or other types"))?;
     
     for arg in &args[1..] {
         let val = arg.as_number()
@@ -84,8 +98,9 @@ pub fn max(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Float(max_val))
 }
 
-pub fn floor(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn floor(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.floor", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.floor()))
     } else {
         Err(crate::errors::synthesis_error(
@@ -97,8 +112,9 @@ pub fn floor(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn ceil(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn ceil(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.ceil", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.ceil()))
     } else {
         Err(crate::errors::synthesis_error(
@@ -110,8 +126,9 @@ pub fn ceil(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn round(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn round(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.round", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.round()))
     } else {
         Err(crate::errors::synthesis_error(
@@ -123,7 +140,8 @@ pub fn round(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn pow(args: &[Value]) -> crate::Result<Value> {
+pub fn pow(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.pow", &[])?;
     if args.len() < 2 {
         return Err(crate::errors::synthesis_error(
             crate::errors::ErrorKind::InvalidExpression,
@@ -133,6 +151,10 @@ pub fn pow(args: &[Value]) -> crate::Result<Value> {
         .with_suggestion("First number is the base, second is the power"));
     }
     
+    if let (Some(z), Some(exponent)) = (complex::from_value(&args[0]), args[1].as_number()) {
+        return Ok(complex::to_value(z.powf(exponent)));
+    }
+    
     let base = args[0].as_number()
         .ok_or_else(|| crate::errors::synthesis_error(
             crate::errors::ErrorKind::TypeMismatch,
@@ -151,8 +173,9 @@ pub fn pow(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Float(base.powf(exponent)))
 }
 
-pub fn log(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn log(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.log", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         if value <= 0.0 {
             Err(crate::errors::synthesis_error(
                 crate::errors::ErrorKind::InvalidExpression,
@@ -173,8 +196,12 @@ pub fn log(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn exp(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn exp(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.exp", &[])?;
+    if let Some(z) = args.first().and_then(complex::from_value) {
+        return Ok(complex::to_value(z.exp()));
+    }
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.exp()))
     } else {
         Err(crate::errors::synthesis_error(
@@ -186,8 +213,9 @@ pub fn exp(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn tan(args: &[Value]) -> crate::Result<Value> {
-    if let Some(value) = args.get(0).and_then(|v| v.as_number()) {
+pub fn tan(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.tan", &[])?;
+    if let Some(value) = args.first().and_then(|v| v.as_number()) {
         Ok(Value::Float(value.tan()))
     } else {
         Err(crate::errors::synthesis_error(
@@ -199,7 +227,8 @@ pub fn tan(args: &[Value]) -> crate::Result<Value> {
     }
 }
 
-pub fn clamp(args: &[Value]) -> crate::Result<Value> {
+pub fn clamp(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.clamp", &[])?;
     if args.len() < 3 {
         return Err(crate::errors::synthesis_error(
             crate::errors::ErrorKind::InvalidExpression,
@@ -236,7 +265,7 @@ pub fn clamp(args: &[Value]) -> crate::Result<Value> {
             crate::errors::ErrorKind::InvalidExpression,
             "🎯 Math.clamp() minimum value is larger than maximum"
         )
-        .with_suggestion(&format!("You have min={}, max={} - try swapping them", min_val, max_val))
+        .with_suggestion(format!("You have min={}, max={} - try swapping them", min_val, max_val))
         .with_suggestion("Example: Math.clamp(value, 0, 100) - min should be smaller"));
     }
     
@@ -244,7 +273,8 @@ pub fn clamp(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Float(clamped))
 }
 
-pub fn lerp(args: &[Value]) -> crate::Result<Value> {
+pub fn lerp(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.lerp", &[])?;
     if args.len() < 3 {
         return Err(crate::errors::synthesis_error(
             crate::errors::ErrorKind::InvalidExpression,
@@ -278,4 +308,509 @@ pub fn lerp(args: &[Value]) -> crate::Result<Value> {
     
     let result = start + t * (end - start);
     Ok(Value::Float(result))
-}
\ No newline at end of file
+}
+
+/// The numbers after the value
+fn bounds(args: &[Value], function: &str, example: &str) -> crate::Result<Vec<f64>> {
+    args.iter()
+        .skip(1)
+        .map(|v| v.as_number().ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🎚️ Math.{}() range limits must be numbers", function)
+        )
+        .with_suggestion(format!("Try: {}", example))))
+        .collect()
+}
+
+/// Apply `f` to a number, or to every number in a list (FFT bands, a block of samples);
+/// null passes through so a stream with nothing in it yet doesn't stop the script
+fn each(value: &Value, function: &str, example: &str, f: &impl Fn(f64) -> f64) -> crate::Result<Value> {
+    match value {
+        Value::Array(items) => items.iter()
+            .map(|item| each(item, function, example, f))
+            .collect::<crate::Result<Vec<_>>>()
+            .map(Value::Array),
+        Value::Null => Ok(Value::Null),
+        other => match other.as_number() {
+            Some(number) => Ok(Value::Float(f(number))),
+            None => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🎚️ Math.{}() needs a number, a list of numbers or a stream", function)
+            )
+            .with_suggestion(format!("Try: {}", example))),
+        },
+    }
+}
+
+/// How a 0-1 position bends on its way from one end of a range to the other
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Curve {
+    Linear,
+    /// Eased at both ends, like smoothstep
+    Smooth,
+    /// Bent by an amount: positive starts slow and rises late, negative rises early
+    Bend(f64),
+}
+
+impl Curve {
+    /// Bend of the "exp" and "log" presets; about right for frequencies and fader travel
+    const PRESET_BEND: f64 = 4.0;
+
+    fn from_options(options: &HashMap<String, Value>, function: &str) -> crate::Result<Self> {
+        match options.get("curve") {
+            None => Ok(Curve::Linear),
+            Some(Value::String(name)) => match name.as_str() {
+                "linear" => Ok(Curve::Linear),
+                "exp" => Ok(Curve::Bend(Self::PRESET_BEND)),
+                "log" => Ok(Curve::Bend(-Self::PRESET_BEND)),
+                "smooth" => Ok(Curve::Smooth),
+                other => Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    format!("🎚️ Math.{}() doesn't know the curve \"{}\"", function, other)
+                )
+                .with_suggestion("Curves are \"linear\", \"exp\", \"log\" and \"smooth\", or a number to bend by")),
+            },
+            Some(amount) => match amount.as_number() {
+                Some(amount) if amount.abs() < 1e-3 => Ok(Curve::Linear),
+                Some(amount) => Ok(Curve::Bend(amount)),
+                None => Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🎚️ Math.{}() curve must be a name or a number", function)
+                )
+                .with_suggestion(format!("Try: Math.{}(level, 0, 1, 200, 2000, curve: \"exp\")", function))),
+            },
+        }
+    }
+
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            Curve::Linear => t,
+            Curve::Smooth => {
+                let t = t.clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            Curve::Bend(amount) => (1.0 - (amount * t).exp()) / (1.0 - amount.exp()),
+        }
+    }
+}
+
+/// `Math.map(level, 0, 1, 200, 2000, curve: "exp")`: rescale from one range to another, for
+/// numbers, lists and streams alike. Values outside the input range carry on past the output
+/// range; wrap it in `Math.clip()` to keep them in
+pub fn map(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.map", &["curve"])?;
+    const EXAMPLE: &str = "Math.map(level, 0, 1, 200, 2000)";
+    let value = args.first().unwrap_or(&Value::Null);
+    let [in_min, in_max, out_min, out_max] = bounds(args, "map", EXAMPLE)?[..] else {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎚️ Math.map() needs a value, the range it comes in and the range it should go to"
+        )
+        .with_suggestion(format!("Try: {} turns 0-1 into 200-2000", EXAMPLE)));
+    };
+    if in_min == in_max {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🎚️ Math.map() input range starts and ends at {}", in_min)
+        )
+        .with_suggestion("The input range needs two different ends, like 0 and 1"));
+    }
+    let curve = Curve::from_options(named, "map")?;
+    each(value, "map", EXAMPLE, &|x| out_min + curve.apply((x - in_min) / (in_max - in_min)) * (out_max - out_min))
+}
+
+/// `Math.normalize(x, min, max)`: where x sits in the range, from 0 to 1. Given just a list,
+/// like FFT bands, it stretches the list so its quietest is 0 and its loudest 1; given a vector,
+/// it's the same direction with length 1
+pub fn normalize(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.normalize", &[])?;
+    const EXAMPLE: &str = "Math.normalize(volume, 0, 0.3)";
+    let value = args.first().unwrap_or(&Value::Null);
+    if let Some(v) = Linear::from_value(value) {
+        return vectors::normalized(v).map(Linear::to_value).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("📐 Math.normalize() works on vectors, not a {}", v.name())
+        )
+        .with_suggestion("Try: Math.normalize(velocity) for its direction"));
+    }
+    let (low, high) = match (value, &bounds(args, "normalize", EXAMPLE)?[..]) {
+        (_, [low, high]) => (*low, *high),
+        (Value::Array(items), []) => {
+            let numbers = items.iter().filter_map(|item| item.as_number());
+            numbers.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), x| (low.min(x), high.max(x)))
+        }
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎚️ Math.normalize() needs the range the value comes in"
+        )
+        .with_suggestion(format!("Try: {} gives 0 when silent and 1 at 0.3", EXAMPLE))
+        .with_suggestion("Or give it a whole list: Math.normalize(bands)")),
+    };
+    // An empty or flat list has nothing to stretch
+    if high <= low {
+        return each(value, "normalize", EXAMPLE, &|_| 0.0);
+    }
+    each(value, "normalize", EXAMPLE, &|x| ((x - low) / (high - low)).clamp(0.0, 1.0))
+}
+
+/// `Math.clip(x)` keeps x between 0 and 1, `Math.clip(x, min, max)` between min and max; unlike
+/// `Math.clamp()` it also takes lists and streams
+pub fn clip(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.clip", &[])?;
+    const EXAMPLE: &str = "Math.clip(gain * 2, 0, 1)";
+    let value = args.first().unwrap_or(&Value::Null);
+    let (low, high) = match bounds(args, "clip", EXAMPLE)?[..] {
+        [] => (0.0, 1.0),
+        [low, high] => (low.min(high), low.max(high)),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎚️ Math.clip() needs both ends of the range, or none for 0 to 1"
+        )
+        .with_suggestion(format!("Try: {}", EXAMPLE))),
+    };
+    each(value, "clip", EXAMPLE, &|x| x.clamp(low, high))
+}
+
+/// `Math.deadzone(x, 0.1)`: anything within 0.1 of zero becomes zero, and the rest is stretched
+/// so it still reaches ±1 without a jump; `max:` for ranges that aren't ±1. Good for noise
+/// floors and joysticks that never quite rest at centre
+pub fn deadzone(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.deadzone", &["max"])?;
+    const EXAMPLE: &str = "Math.deadzone(stick_x, 0.1)";
+    let value = args.first().unwrap_or(&Value::Null);
+    let max = named.get("max").and_then(|v| v.as_number()).unwrap_or(1.0).abs();
+    let threshold = match bounds(args, "deadzone", EXAMPLE)?[..] {
+        [threshold] if threshold >= 0.0 && threshold < max => threshold,
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🎚️ Math.deadzone() needs a threshold from 0 up to {}", max)
+        )
+        .with_suggestion(format!("Try: {} ignores the wobble around the middle", EXAMPLE))),
+    };
+    each(value, "deadzone", EXAMPLE, &|x| {
+        if x.abs() <= threshold {
+            0.0
+        } else {
+            x.signum() * (x.abs() - threshold) / (max - threshold) * max
+        }
+    })
+}
+
+/// The vector or matrix at `index`
+fn linear(args: &[Value], index: usize, function: &str, example: &str) -> crate::Result<Linear> {
+    args.get(index).and_then(Linear::from_value).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("📐 Math.{}() needs {}", function, if index == 0 { "a vector" } else { "two vectors" })
+    )
+    .with_suggestion(format!("Try: {}", example))
+    .with_suggestion("Make vectors with Math.vec2(x, y) or Vec3 { x: 1, y: 0, z: 0 }"))
+}
+
+/// Plain numbers, or the components of one vector
+fn components(args: &[Value], function: &str, example: &str) -> crate::Result<Vec<f64>> {
+    if let Some(v) = args.first().and_then(Linear::from_value).filter(Linear::is_vector) {
+        return Ok(v.components().to_vec());
+    }
+    args.iter()
+        .filter(|v| !matches!(v, Value::Object(_)))
+        .map(|v| v.as_number().ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("📐 Math.{}() needs numbers or a vector", function)
+        )
+        .with_suggestion(format!("Try: {}", example))))
+        .collect()
+}
+
+fn construct(args: &[Value], function: &str, example: &str, size: usize, build: impl Fn(Vec<f64>) -> Linear) -> crate::Result<Value> {
+    let numbers = components(args, function, example)?;
+    match numbers.len() {
+        0 if size > 3 => Ok(build(Vec::new()).to_value()),
+        n if n == size => Ok(build(numbers).to_value()),
+        // One number for every component: Math.vec3(0) is the origin
+        1 if size <= 3 => Ok(build(vec![numbers[0]; size]).to_value()),
+        n => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📐 Math.{}() takes {} numbers, not {}", function, size, n)
+        )
+        .with_suggestion(format!("Try: {}", example))),
+    }
+}
+
+/// `Math.vec2(x, y)`, or `Math.vec2(1)` for both at once
+pub fn vec2(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.vec2", &[])?;
+    construct(args, "vec2", "Math.vec2(0.5, 0.25)", 2, |n| Linear::Vec2([n[0], n[1]]))
+}
+
+/// `Math.vec3(x, y, z)`, or `Math.vec3(0)` for the origin
+pub fn vec3(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.vec3", &[])?;
+    construct(args, "vec3", "Math.vec3(0, 1, 0)", 3, |n| Linear::Vec3([n[0], n[1], n[2]]))
+}
+
+/// `Math.mat3()` is the identity; with nine numbers they're read column by column
+pub fn mat3(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.mat3", &[])?;
+    construct(args, "mat3", "Math.mat3() for the identity", 9, |n| Linear::Mat3(n.try_into().unwrap_or(IDENTITY3)))
+}
+
+/// `Math.mat4()` is the identity; with sixteen numbers they're read column by column
+pub fn mat4(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.mat4", &[])?;
+    construct(args, "mat4", "Math.mat4() for the identity", 16, |n| Linear::Mat4(n.try_into().unwrap_or(IDENTITY4)))
+}
+
+pub fn dot(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.dot", &[])?;
+    const EXAMPLE: &str = "Math.dot(facing, to_target)";
+    let (a, b) = (linear(args, 0, "dot", EXAMPLE)?, linear(args, 1, "dot", EXAMPLE)?);
+    vectors::dot(a, b).map(Value::Float).ok_or_else(|| mismatched("dot", a, b))
+}
+
+/// `Math.cross(a, b)`: a Vec3 at right angles to both, or for Vec2s the signed area between them
+pub fn cross(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.cross", &[])?;
+    const EXAMPLE: &str = "Math.cross(forward, up)";
+    let (a, b) = (linear(args, 0, "cross", EXAMPLE)?, linear(args, 1, "cross", EXAMPLE)?);
+    vectors::cross(a, b).ok_or_else(|| mismatched("cross", a, b))
+}
+
+pub fn length(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.length", &[])?;
+    let v = linear(args, 0, "length", "Math.length(velocity)")?;
+    vectors::length(v).map(Value::Float).ok_or_else(|| mismatched("length", v, v))
+}
+
+pub fn distance(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.distance", &[])?;
+    const EXAMPLE: &str = "Math.distance(player, target)";
+    let (a, b) = (linear(args, 0, "distance", EXAMPLE)?, linear(args, 1, "distance", EXAMPLE)?);
+    a.zip(b, |x, y| x - y)
+        .and_then(vectors::length)
+        .map(Value::Float)
+        .ok_or_else(|| mismatched("distance", a, b))
+}
+
+fn mismatched(function: &str, a: Linear, b: Linear) -> crate::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("📐 Math.{}() can't work with {} and {}", function, a.name(), b.name())
+    )
+    .with_suggestion("Both have to be vectors of the same size")
+}
+
+/// `Math.rotation(angle)` turns 2D points counter-clockwise as a Mat3; `Math.rotation(angle, axis)`
+/// turns 3D points around a Vec3 axis as a Mat4. Angles are radians, or `90.degrees`
+pub fn rotation(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.rotation", &[])?;
+    let Some(angle) = args.first().and_then(|v| v.as_number()) else {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "📐 Math.rotation() needs an angle"
+        )
+        .with_suggestion("Try: Math.rotation(45.degrees), or Math.rotation(time, Math.vec3(0, 1, 0)) to spin around y"));
+    };
+    match args.get(1).and_then(Linear::from_value) {
+        None => Ok(vectors::rotation_2d(angle).to_value()),
+        Some(Linear::Vec3(axis)) => vectors::rotation_3d(angle, axis).map(Linear::to_value).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "📐 Math.rotation() can't turn around a zero-length axis"
+        )
+        .with_suggestion("Try an axis like Math.vec3(0, 1, 0)")),
+        Some(other) => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("📐 Math.rotation() axis must be a Vec3, not a {}", other.name())
+        )
+        .with_suggestion("Leave the axis out for a 2D rotation")),
+    }
+}
+
+/// `Math.translation(offset)`: a Mat3 that moves 2D points by a Vec2, or a Mat4 for a Vec3
+pub fn translation(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.translation", &[])?;
+    const EXAMPLE: &str = "Math.translation(Math.vec2(100, 50))";
+    let offset = components(args, "translation", EXAMPLE)?;
+    vectors::translation(&offset).map(Linear::to_value).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        "📐 Math.translation() needs a 2D or 3D offset"
+    )
+    .with_suggestion(format!("Try: {}", EXAMPLE)))
+}
+
+/// `Math.scaling(2, 0.5)` or `Math.scaling(size)`: a Mat3 that stretches 2D points, or a Mat4 for 3D
+pub fn scaling(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.scaling", &[])?;
+    const EXAMPLE: &str = "Math.scaling(2, 2) or Math.scaling(Math.vec3(1, 2, 1))";
+    let factors = components(args, "scaling", EXAMPLE)?;
+    vectors::scaling(&factors).map(Linear::to_value).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        "📐 Math.scaling() needs a factor for each of 2 or 3 axes"
+    )
+    .with_suggestion(format!("Try: {}", EXAMPLE)))
+}
+
+fn matrix_argument(args: &[Value], function: &str) -> crate::Result<Linear> {
+    args.first()
+        .and_then(Linear::from_value)
+        .filter(|m| !m.is_vector())
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("📐 Math.{}() needs a Mat3 or Mat4", function)
+        )
+        .with_suggestion(format!("Try: Math.{}(Math.rotation(0.5))", function)))
+}
+
+pub fn transpose(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.transpose", &[])?;
+    let m = matrix_argument(args, "transpose")?;
+    Ok(vectors::transpose(m).unwrap_or(m).to_value())
+}
+
+/// The matrix that undoes `m`, so `Math.inverse(view) * point` goes back to world coordinates
+pub fn inverse(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.inverse", &[])?;
+    let m = matrix_argument(args, "inverse")?;
+    vectors::inverse(m).map(Linear::to_value).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("📐 This {} can't be undone; it squashes everything flat", m.name())
+    )
+    .with_suggestion("A scaling of 0 on some axis does this; keep scale factors away from zero"))
+}
+
+fn complex_argument(args: &[Value], index: usize, function: &str, example: &str) -> crate::Result<Complex64> {
+    args.get(index).and_then(complex::from_number).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("🌀 Math.{}() needs a complex number", function)
+    )
+    .with_suggestion(format!("Try: {}", example)))
+}
+
+/// A list of coefficients; a single number is a constant
+fn coefficients(value: Option<&Value>, function: &str, example: &str) -> crate::Result<Vec<Complex64>> {
+    let invalid = || crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("🌀 Math.{}() needs a list of coefficients", function)
+    )
+    .with_suggestion(format!("Try: {}", example))
+    .with_suggestion("Make one with Math.poly(1, -2, 1), highest power first");
+    match value {
+        Some(Value::Array(items)) if !items.is_empty() => items.iter().map(|item| complex::from_number(item).ok_or_else(invalid)).collect(),
+        Some(other) => complex::from_number(other).map(|c| vec![c]).ok_or_else(invalid),
+        None => Err(invalid()),
+    }
+}
+
+/// `Math.complex(re, im)`; the imaginary part defaults to 0
+pub fn complex(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.complex", &[])?;
+    let part = |index: usize| args.get(index).map(|v| v.as_number()).unwrap_or(Some(0.0));
+    match (part(0), part(1)) {
+        (Some(re), Some(im)) if !args.is_empty() => Ok(complex::to_value(Complex64::new(re, im))),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🌀 Math.complex() needs a real and an imaginary part"
+        )
+        .with_suggestion("Try: Math.complex(-0.8, 0.156) for a Julia set constant")),
+    }
+}
+
+/// `Math.polar(magnitude, angle)`: the complex number that far out at that angle in radians
+pub fn polar(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.polar", &[])?;
+    match (args.first().and_then(|v| v.as_number()), args.get(1).and_then(|v| v.as_number())) {
+        (Some(magnitude), Some(angle)) => Ok(complex::to_value(Complex64::from_polar(magnitude, angle))),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🌀 Math.polar() needs a magnitude and an angle"
+        )
+        .with_suggestion("Try: Math.polar(1, time) to go round the unit circle")),
+    }
+}
+
+/// The angle of a complex number in radians, from -π to π
+pub fn phase(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.phase", &[])?;
+    Ok(Value::Float(complex_argument(args, 0, "phase", "Math.phase(response)")?.arg()))
+}
+
+/// The complex conjugate: the same real part with the imaginary part flipped
+pub fn conj(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.conj", &[])?;
+    Ok(complex::to_value(complex_argument(args, 0, "conj", "Math.conj(z)")?.conj()))
+}
+
+/// `Math.poly(1, -2, 1)` is the coefficient list of x² - 2x + 1, highest power first
+pub fn poly(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.poly", &[])?;
+    let numbers = args.iter()
+        .map(|v| match complex::from_value(v) {
+            Some(_) => Some(v.clone()),
+            None => v.as_number().map(Value::Float),
+        })
+        .collect::<Option<Vec<Value>>>();
+    match numbers {
+        Some(numbers) if !numbers.is_empty() => Ok(Value::Array(numbers)),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🌀 Math.poly() needs its coefficients as numbers, highest power first"
+        )
+        .with_suggestion("Try: Math.poly(1, 0, -1) for x² - 1")),
+    }
+}
+
+/// `Math.polyval(Math.poly(1, 0, -1), x)`: the polynomial at a real or complex x
+pub fn polyval(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.polyval", &[])?;
+    const EXAMPLE: &str = "Math.polyval(Math.poly(1, 0, -1), 3)";
+    let coefficients = coefficients(args.first(), "polyval", EXAMPLE)?;
+    let x = complex_argument(args, 1, "polyval", EXAMPLE)?;
+    let result = complex::polyval(&coefficients, x);
+    // Real in, real out
+    let real = coefficients.iter().all(|c| c.im == 0.0) && x.im == 0.0;
+    Ok(if real { Value::Float(result.re) } else { complex::to_value(result) })
+}
+
+/// Every root of a polynomial as a list of complex numbers, like the poles of a filter
+pub fn roots(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.roots", &[])?;
+    let coefficients = coefficients(args.first(), "roots", "Math.roots(Math.poly(1, 0, -1))")?;
+    Ok(Value::Array(complex::roots(&coefficients).into_iter().map(complex::to_value).collect()))
+}
+
+/// `Math.roots_of_unity(5)`: five points evenly round the unit circle, starting from 1
+pub fn roots_of_unity(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.roots_of_unity", &[])?;
+    match args.first().and_then(|v| v.as_number()) {
+        Some(n) if n >= 1.0 && n.fract() == 0.0 => Ok(Value::Array(complex::roots_of_unity(n as usize).into_iter().map(complex::to_value).collect())),
+        _ => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🌀 Math.roots_of_unity() needs a whole number of roots, 1 or more"
+        )
+        .with_suggestion("Try: Math.roots_of_unity(6) for the corners of a hexagon")),
+    }
+}
+
+/// `Math.transfer(b, a, 1000, sample_rate: 48000)`: a filter's complex response at 1 kHz, with its
+/// coefficients in ascending powers of z⁻¹ as filter designs print them. `Math.abs()` of the
+/// result is the gain and `Math.phase()` the phase shift
+pub fn transfer(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Math.transfer", &["sample_rate"])?;
+    const EXAMPLE: &str = "Math.transfer(Math.poly(0.5, 0.5), Math.poly(1), 1000)";
+    let numerator = coefficients(args.first(), "transfer", EXAMPLE)?;
+    let denominator = coefficients(args.get(1), "transfer", EXAMPLE)?;
+    let frequency = args.get(2).and_then(|v| v.as_number()).ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        "🌀 Math.transfer() needs the frequency to look at, in Hz"
+    )
+    .with_suggestion(format!("Try: {}", EXAMPLE)))?;
+    let sample_rate = named.get("sample_rate").and_then(|v| v.as_number()).unwrap_or(44100.0);
+    let response = complex::transfer(&numerator, &denominator, frequency, sample_rate);
+    if !response.re.is_finite() || !response.im.is_finite() {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("🌀 The filter has a pole right at {} Hz, so its response there is infinite", frequency)
+        )
+        .with_suggestion("Look at a frequency just beside it, or check the denominator coefficients"));
+    }
+    Ok(complex::to_value(response))
+}
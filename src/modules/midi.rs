@@ -0,0 +1,469 @@
+use crate::runtime::units::UnitValue;
+use crate::runtime::{Event, EventStream, Value};
+use std::collections::HashMap;
+
+/// Seconds for a time or length: plain numbers are seconds, notes, beats and bars follow the tempo
+fn seconds(value: &Value) -> Option<f64> {
+    crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).seconds(value)
+}
+
+/// Seconds in `value` of `unit` at the transport's tempo
+fn tempo_length(value: f64, unit: &str) -> f64 {
+    UnitValue::from_string(value, unit)
+        .and_then(|length| seconds(&Value::UnitValue(length)))
+        .unwrap_or(0.0)
+}
+
+/// A chord's notes as MIDI numbers, from `[60, 64, 67]`, `["C4", "E4", "G4"]`, `{ notes: [...] }`,
+/// `{ note: 60 }` or a single note
+pub fn chord_notes(value: &Value) -> Option<Vec<i32>> {
+    match value {
+        Value::Array(items) => items.iter().map(note).collect(),
+        Value::Object(fields) => match (fields.get("notes"), fields.get("note")) {
+            (Some(notes), _) => chord_notes(notes),
+            (None, Some(single)) => Some(vec![note(single)?]),
+            _ => None,
+        },
+        single => Some(vec![note(single)?]),
+    }
+}
+
+fn note(value: &Value) -> Option<i32> {
+    match value {
+        Value::String(name) => crate::audio::synth::note_number(name),
+        other => other.as_number().map(|n| n.round() as i32),
+    }
+}
+
+/// The fields of a chord's value that every note played from it keeps, like `velocity` or `channel`
+fn chord_fields(value: &Value) -> HashMap<String, Value> {
+    match value {
+        Value::Object(fields) => fields.iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "notes" | "note" | "duration"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+fn chord_duration(value: &Value) -> Option<f64> {
+    match value {
+        Value::Object(fields) => fields.get("duration").and_then(seconds).filter(|&d| d > 0.0),
+        _ => None,
+    }
+}
+
+fn note_value(fields: &HashMap<String, Value>, note: i32, duration: Option<f64>) -> Value {
+    let mut fields = fields.clone();
+    fields.insert("note".to_string(), Value::Integer(note as i64));
+    if let Some(duration) = duration {
+        fields.insert("duration".to_string(), Value::Float(duration));
+    }
+    Value::Object(fields)
+}
+
+/// The order an arpeggiator plays a chord's notes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArpPattern {
+    Up,
+    Down,
+    /// Up then back down, without playing the top and bottom notes twice
+    UpDown,
+    DownUp,
+    /// The order the chord lists its notes in
+    Order,
+    Random,
+}
+
+impl ArpPattern {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "up" => Some(Self::Up),
+            "down" => Some(Self::Down),
+            "updown" | "up_down" => Some(Self::UpDown),
+            "downup" | "down_up" => Some(Self::DownUp),
+            "order" | "played" => Some(Self::Order),
+            "random" => Some(Self::Random),
+            _ => None,
+        }
+    }
+
+    /// One cycle of the pattern over `notes`, which are lowest first unless the pattern is `Order`
+    fn cycle(&self, notes: &[i32]) -> Vec<i32> {
+        let up = notes.to_vec();
+        let down: Vec<i32> = notes.iter().rev().copied().collect();
+        let inner = |notes: &[i32]| notes.get(1..notes.len().saturating_sub(1)).unwrap_or_default().iter().rev().copied().collect::<Vec<_>>();
+        match self {
+            Self::Up | Self::Order | Self::Random => up,
+            Self::Down => down,
+            Self::UpDown => [up.clone(), inner(&up)].concat(),
+            Self::DownUp => [down.clone(), inner(&down)].concat(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arpeggiator {
+    pub pattern: ArpPattern,
+    /// Seconds between notes
+    pub rate: f64,
+    /// Octaves the chord is spread over, 1 for just the chord as written
+    pub octaves: u32,
+    /// How much of each step a note lasts, from 0 to 1
+    pub gate: f64,
+    /// Where the random pattern starts, so the same seed plays the same notes
+    pub seed: u32,
+}
+
+impl Arpeggiator {
+    /// Each chord played one note at a time, `rate` apart. A chord lasts as long as its
+    /// `duration`, or until the next chord, or a bar for the last one. Notes fall on multiples
+    /// of `rate` since the sketch started, so they line up with the Timeline however late the
+    /// chord arrives
+    pub fn arpeggiate(&self, chords: &EventStream) -> EventStream {
+        if self.rate <= 0.0 {
+            return EventStream::default();
+        }
+        let bar = tempo_length(1.0, "bar");
+        let chords = chords.events();
+        let mut rng_state = self.seed;
+        let mut notes = Vec::new();
+
+        for (index, chord) in chords.iter().enumerate() {
+            let Some(mut written) = chord_notes(&chord.value).filter(|notes| !notes.is_empty()) else {
+                continue;
+            };
+            if self.pattern != ArpPattern::Order {
+                written.sort();
+            }
+            let spread: Vec<i32> = (0..self.octaves.max(1) as i32)
+                .flat_map(|octave| written.iter().map(move |note| note + octave * 12))
+                .collect();
+            let cycle = self.pattern.cycle(&spread);
+
+            let end = chord_duration(&chord.value)
+                .or_else(|| chords.get(index + 1).map(|next| next.time - chord.time).filter(|&d| d > 0.0))
+                .unwrap_or(bar) + chord.time;
+            let fields = chord_fields(&chord.value);
+            // A little slack so a chord on the grid isn't pushed to the next step by rounding
+            let mut step = (chord.time / self.rate - 1e-9).ceil() as i64;
+            let mut played = 0;
+            while (step as f64) * self.rate < end - 1e-9 {
+                let note = match self.pattern {
+                    ArpPattern::Random => {
+                        rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+                        cycle[(rng_state >> 16) as usize % cycle.len()]
+                    }
+                    _ => cycle[played % cycle.len()],
+                };
+                notes.push(Event {
+                    time: step as f64 * self.rate,
+                    value: note_value(&fields, note, Some(self.rate * self.gate)),
+                });
+                step += 1;
+                played += 1;
+            }
+        }
+        EventStream::new(notes)
+    }
+}
+
+/// Which way a strum runs across a chord
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrumDirection {
+    /// Lowest note first, like a downstroke on a guitar
+    Down,
+    Up,
+    /// Down, then up, then down again, chord by chord
+    Alternate,
+}
+
+impl StrumDirection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "down" => Some(Self::Down),
+            "up" => Some(Self::Up),
+            "alternate" | "alternating" => Some(Self::Alternate),
+            _ => None,
+        }
+    }
+}
+
+/// Each chord's notes spread over `spread` seconds instead of all starting at once. Notes from a
+/// chord with a `duration` still end together
+pub fn strum(chords: &EventStream, spread: f64, direction: StrumDirection) -> EventStream {
+    let mut notes = Vec::new();
+    for (index, chord) in chords.events().iter().enumerate() {
+        let Some(mut written) = chord_notes(&chord.value).filter(|notes| !notes.is_empty()) else {
+            continue;
+        };
+        written.sort();
+        let upward = match direction {
+            StrumDirection::Down => true,
+            StrumDirection::Up => false,
+            StrumDirection::Alternate => index % 2 == 0,
+        };
+        if !upward {
+            written.reverse();
+        }
+        let fields = chord_fields(&chord.value);
+        let duration = chord_duration(&chord.value);
+        let gap = if written.len() > 1 { spread.max(0.0) / (written.len() - 1) as f64 } else { 0.0 };
+        for (position, note) in written.into_iter().enumerate() {
+            let offset = position as f64 * gap;
+            notes.push(Event {
+                time: chord.time + offset,
+                value: note_value(&fields, note, duration.map(|d| (d - offset).max(0.0))),
+            });
+        }
+    }
+    EventStream::new(notes)
+}
+
+/// Semitones above the root of each scale `Scale::named` knows
+const SCALES: &[(&str, &[i32])] = &[
+    ("major", &[0, 2, 4, 5, 7, 9, 11]),
+    ("ionian", &[0, 2, 4, 5, 7, 9, 11]),
+    ("minor", &[0, 2, 3, 5, 7, 8, 10]),
+    ("aeolian", &[0, 2, 3, 5, 7, 8, 10]),
+    ("harmonic_minor", &[0, 2, 3, 5, 7, 8, 11]),
+    ("melodic_minor", &[0, 2, 3, 5, 7, 9, 11]),
+    ("dorian", &[0, 2, 3, 5, 7, 9, 10]),
+    ("phrygian", &[0, 1, 3, 5, 7, 8, 10]),
+    ("lydian", &[0, 2, 4, 6, 7, 9, 11]),
+    ("mixolydian", &[0, 2, 4, 5, 7, 9, 10]),
+    ("locrian", &[0, 1, 3, 5, 6, 8, 10]),
+    ("pentatonic", &[0, 2, 4, 7, 9]),
+    ("major_pentatonic", &[0, 2, 4, 7, 9]),
+    ("minor_pentatonic", &[0, 3, 5, 7, 10]),
+    ("blues", &[0, 3, 5, 6, 7, 10]),
+    ("whole_tone", &[0, 2, 4, 6, 8, 10]),
+    ("chromatic", &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+];
+
+/// The notes a scale allows, as pitch classes from 0 (C) to 11 (B)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scale {
+    pub pitches: Vec<i32>,
+}
+
+impl Scale {
+    /// A scale like "C major", "F# minor pentatonic" or just "dorian", which starts on C
+    pub fn named(name: &str) -> Option<Self> {
+        let mut words: Vec<&str> = name.split_whitespace().collect();
+        let root = match words.first().and_then(|word| crate::audio::synth::note_number(&format!("{}4", word))) {
+            Some(root) => {
+                words.remove(0);
+                root
+            }
+            None => 0,
+        };
+        let kind = if words.is_empty() { "major".to_string() } else { words.join("_").to_lowercase().replace('-', "_") };
+        let (_, intervals) = SCALES.iter().find(|(name, _)| *name == kind)?;
+        Self::from_notes(&intervals.iter().map(|interval| root + interval).collect::<Vec<_>>())
+    }
+
+    /// The scale made of these notes in every octave
+    pub fn from_notes(notes: &[i32]) -> Option<Self> {
+        let mut pitches: Vec<i32> = notes.iter().map(|note| note.rem_euclid(12)).collect();
+        pitches.sort();
+        pitches.dedup();
+        (!pitches.is_empty()).then_some(Self { pitches })
+    }
+
+    pub fn contains(&self, note: i32) -> bool {
+        self.pitches.contains(&note.rem_euclid(12))
+    }
+
+    /// The nearest note in the scale, the lower one when two are as near
+    pub fn quantize(&self, note: i32) -> i32 {
+        (0..12)
+            .flat_map(|distance| [note - distance, note + distance])
+            .find(|&candidate| self.contains(candidate))
+            .unwrap_or(note)
+    }
+}
+
+/// An event's value with `change` made to each note in it, whether it's a note number or name,
+/// a chord, or an object with a `note` or `notes` field; anything else is left as it is
+fn map_notes(value: &Value, change: &dyn Fn(i32) -> i32) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(|item| map_notes(item, change)).collect()),
+        Value::Object(fields) => {
+            let mut fields = fields.clone();
+            for key in ["note", "notes"] {
+                if let Some(notes) = fields.get(key).map(|notes| map_notes(notes, change)) {
+                    fields.insert(key.to_string(), notes);
+                }
+            }
+            Value::Object(fields)
+        }
+        single => note(single).map_or_else(|| single.clone(), |n| Value::Integer(change(n) as i64)),
+    }
+}
+
+/// Every note moved to the nearest note of `scale`, so generated melodies stay in key
+pub fn quantize_to_scale(events: &EventStream, scale: &Scale) -> EventStream {
+    let events = events.events().iter()
+        .map(|event| Event { time: event.time, value: map_notes(&event.value, &|n| scale.quantize(n)) })
+        .collect();
+    EventStream::new(events)
+}
+
+/// Every event moved up to `timing` seconds either way, and its `velocity` changed by up to
+/// `velocity` (0.15 for 15%) of itself, so a sequence sounds played rather than programmed. The
+/// same `seed` always gives the same result. Velocities above 1 are taken as MIDI's 1 to 127
+pub fn humanize(events: &EventStream, timing: f64, velocity: f64, seed: u32) -> EventStream {
+    let mut rng_state = seed;
+    let mut random = || {
+        rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+        (rng_state >> 8) as f64 / (1u32 << 24) as f64 * 2.0 - 1.0
+    };
+    let events = events.events().iter()
+        .map(|event| {
+            let time = (event.time + random() * timing).max(0.0);
+            let change = 1.0 + random() * velocity;
+            let value = match &event.value {
+                Value::Object(fields) => {
+                    let mut fields = fields.clone();
+                    let louder = match fields.get("velocity") {
+                        Some(Value::Integer(v)) => Some(Value::Integer((*v as f64 * change).round().clamp(1.0, 127.0) as i64)),
+                        Some(Value::Float(v)) if *v > 1.0 => Some(Value::Integer((v * change).round().clamp(1.0, 127.0) as i64)),
+                        Some(Value::Float(v)) => Some(Value::Float((v * change).clamp(0.0, 1.0))),
+                        _ => None,
+                    };
+                    if let Some(louder) = louder {
+                        fields.insert("velocity".to_string(), louder);
+                    }
+                    Value::Object(fields)
+                }
+                other => other.clone(),
+            };
+            Event { time, value }
+        })
+        .collect();
+    EventStream::new(events)
+}
+
+fn midi_error(message: impl Into<String>, suggestion: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::TypeMismatch, message).with_suggestion(suggestion)
+}
+
+/// The chords or notes a MIDI function was given, as events; a list goes through `Events.from()`
+fn chord_events(args: &[Value], function: &str) -> crate::Result<EventStream> {
+    match args.first() {
+        Some(Value::Events(events)) => Ok(events.clone()),
+        Some(list @ Value::Array(_)) => match crate::modules::events::events_from(std::slice::from_ref(list), &HashMap::new())? {
+            Value::Events(events) => Ok(events),
+            _ => unreachable!("Events.from() always gives events"),
+        },
+        _ => Err(midi_error(
+            format!("🎹 MIDI.{}() needs chords as events", function),
+            "Try: Events.from([{ time: 0, notes: [\"C4\", \"E4\", \"G4\"] }, { time: 2, notes: [57, 60, 64] }])"
+        )),
+    }
+}
+
+/// `MIDI.arpeggiate(chords, pattern: "up", rate: 1/16.note, octaves: 1, gate: 0.5, seed: 1)`:
+/// chord events played one note at a time. Patterns are up, down, updown, downup, order and random
+pub fn midi_arpeggiate(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "MIDI.arpeggiate", &["pattern", "rate", "octaves", "gate", "seed"])?;
+    let chords = chord_events(args, "arpeggiate")?;
+    let pattern = match named.get("pattern") {
+        None => ArpPattern::Up,
+        Some(Value::String(name)) => ArpPattern::from_name(name).ok_or_else(|| midi_error(
+            format!("🎹 '{}' isn't an arpeggio pattern", name),
+            "Patterns are up, down, updown, downup, order and random"
+        ))?,
+        Some(other) => return Err(midi_error(
+            format!("🎹 pattern: should be a name, not {}", other.type_name()),
+            "Try: MIDI.arpeggiate(chords, pattern: \"updown\")"
+        )),
+    };
+    let rate = match named.get("rate") {
+        None => tempo_length(1.0 / 16.0, "note"),
+        Some(rate) => seconds(rate).filter(|&r| r > 0.0).ok_or_else(|| midi_error(
+            "🎹 rate: should be a length like 1/16.note or 0.125",
+            "Try: MIDI.arpeggiate(chords, rate: 1/8.note)"
+        ))?,
+    };
+    let number = |key: &str| named.get(key).and_then(|v| v.as_number());
+    let arpeggiator = Arpeggiator {
+        pattern,
+        rate,
+        octaves: number("octaves").map_or(1, |n| n.clamp(1.0, 8.0) as u32),
+        gate: number("gate").map_or(0.5, |n| n.clamp(0.0, 1.0)),
+        seed: number("seed").map_or(1, |n| n as u32),
+    };
+    Ok(Value::Events(arpeggiator.arpeggiate(&chords)))
+}
+
+/// `MIDI.strum(chords, spread: 30.ms, direction: "down")`: each chord's notes a little apart, like
+/// a hand across guitar strings. Directions are down (lowest first), up and alternate
+pub fn midi_strum(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "MIDI.strum", &["spread", "direction"])?;
+    let chords = chord_events(args, "strum")?;
+    let spread = match named.get("spread") {
+        None => 0.03,
+        Some(spread) => seconds(spread).filter(|&s| s >= 0.0).ok_or_else(|| midi_error(
+            "🎹 spread: should be a time like 30.ms",
+            "Try: MIDI.strum(chords, spread: 40.ms)"
+        ))?,
+    };
+    let direction = match named.get("direction") {
+        None => StrumDirection::Down,
+        Some(Value::String(name)) => StrumDirection::from_name(name).ok_or_else(|| midi_error(
+            format!("🎹 '{}' isn't a strum direction", name),
+            "Directions are down, up and alternate"
+        ))?,
+        Some(other) => return Err(midi_error(
+            format!("🎹 direction: should be a name, not {}", other.type_name()),
+            "Try: MIDI.strum(chords, direction: \"alternate\")"
+        )),
+    };
+    Ok(Value::Events(strum(&chords, spread, direction)))
+}
+
+/// `MIDI.quantize_to_scale(notes, "D minor")`: every note moved to the nearest note of the scale.
+/// The scale can be a name like "C major" or "A minor pentatonic", or notes like ["C", "Eb", "G"]
+pub fn midi_quantize_to_scale(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "MIDI.quantize_to_scale", &[])?;
+    let events = chord_events(args, "quantize_to_scale")?;
+    let scale = match args.get(1) {
+        Some(Value::String(name)) => Scale::named(name),
+        Some(Value::Array(notes)) => notes.iter()
+            .map(|value| match value {
+                // A note without an octave, like "Eb", is just its pitch
+                Value::String(name) => crate::audio::synth::note_number(name)
+                    .or_else(|| crate::audio::synth::note_number(&format!("{}4", name))),
+                other => note(other),
+            })
+            .collect::<Option<Vec<i32>>>()
+            .and_then(|notes| Scale::from_notes(&notes)),
+        _ => None,
+    };
+    let scale = scale.ok_or_else(|| midi_error(
+        "🎹 MIDI.quantize_to_scale() needs a scale like \"C major\" or a list of notes",
+        "Scales are major, minor, harmonic_minor, melodic_minor, dorian, phrygian, lydian, mixolydian, locrian, pentatonic, minor_pentatonic, blues, whole_tone and chromatic"
+    ))?;
+    Ok(Value::Events(quantize_to_scale(&events, &scale)))
+}
+
+/// `MIDI.humanize(notes, timing: 10.ms, velocity: 15%, seed: 1)`: small random changes to when
+/// notes play and how hard, so generated sequences sound less robotic
+pub fn midi_humanize(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "MIDI.humanize", &["timing", "velocity", "seed"])?;
+    let events = chord_events(args, "humanize")?;
+    let timing = match named.get("timing") {
+        None => 0.01,
+        Some(timing) => seconds(timing).filter(|&t| t >= 0.0).ok_or_else(|| midi_error(
+            "🎹 timing: should be a time like 10.ms",
+            "Try: MIDI.humanize(notes, timing: 15.ms)"
+        ))?,
+    };
+    // 15% arrives as 0.15, but 15 on its own means the same
+    let velocity = named.get("velocity").and_then(|v| v.as_number())
+        .map_or(0.15, |v| if v > 1.0 { v / 100.0 } else { v.max(0.0) });
+    let seed = named.get("seed").and_then(|v| v.as_number()).map_or(1, |n| n as u32);
+    Ok(Value::Events(humanize(&events, timing, velocity, seed)))
+}
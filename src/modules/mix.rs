@@ -0,0 +1,212 @@
+/// Crossfading between two of anything: audio, graphics layers or whole scenes
+///
+/// `Mix.crossfade(a, b, position)` goes from all `a` at 0 to all `b` at 1. What gets mixed
+/// depends on what's passed in:
+/// - two synth names set those synths' levels in the engine (equal-power by default)
+/// - two sample buffers mix sample by sample (equal-power by default)
+/// - two Graphics chains blend, `position` being the opacity of `b`
+/// - two scene objects mix every numeric field and switch everything else at the halfway mark
+///
+/// `fader: "X-Fade"` puts the position on a GUI slider and `cc: 1` hands it to a MIDI
+/// controller; once the controller has moved it wins over the slider.
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Fraction of the travel the `cut` curve takes to bring a side all the way in
+const CUT_WIDTH: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossfadeCurve {
+    Linear,
+    /// Constant power through the middle, for uncorrelated audio
+    EqualPower,
+    /// Linear with eased ends
+    Smooth,
+    /// DJ scratch curve: both sides at full level except right at the ends
+    Cut,
+}
+
+impl CrossfadeCurve {
+    pub fn parse(name: &str) -> crate::Result<Self> {
+        match name {
+            "linear" => Ok(Self::Linear),
+            "equal_power" | "power" => Ok(Self::EqualPower),
+            "smooth" => Ok(Self::Smooth),
+            "cut" => Ok(Self::Cut),
+            _ => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🔀 Unknown crossfade curve '{}'", name)
+            )
+            .with_suggestion("Curves are linear, equal_power, smooth and cut")),
+        }
+    }
+
+    /// Levels of `a` and `b` at `position`, clamped to 0..1
+    pub fn gains(&self, position: f64) -> (f64, f64) {
+        let position = if position.is_nan() { 0.5 } else { position.clamp(0.0, 1.0) };
+        match self {
+            Self::Linear => (1.0 - position, position),
+            Self::EqualPower => {
+                let angle = position * std::f64::consts::FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+            Self::Smooth => {
+                let eased = position * position * (3.0 - 2.0 * position);
+                (1.0 - eased, eased)
+            }
+            Self::Cut => (((1.0 - position) / CUT_WIDTH).min(1.0), (position / CUT_WIDTH).min(1.0)),
+        }
+    }
+}
+
+/// Where the fader sits: a MIDI controller that has moved, else the GUI fader, else the argument
+fn position(args: &[Value], options: &HashMap<String, Value>) -> crate::Result<f64> {
+    let mut position = match args.get(2) {
+        None => 0.5,
+        Some(value) => value.as_number().ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🔀 The crossfade position should be a number from 0 to 1, not {}", value.type_name())
+        ))?,
+    };
+    if let Some(fader) = options.get("fader") {
+        let label = match fader {
+            Value::String(label) => label.clone(),
+            _ => "Crossfade".to_string(),
+        };
+        position = crate::runtime::declare_control(
+            crate::runtime::RemoteControl::new(label, "slider", Value::Float(position.clamp(0.0, 1.0)))
+                .with_range(0.0, 1.0)
+        ).as_number().unwrap_or(position);
+    }
+    if let Some(controller) = options.get("cc").and_then(|v| v.as_number()) {
+        let channel = options.get("channel").and_then(|v| v.as_number()).unwrap_or(1.0);
+        if !(0.0..=127.0).contains(&controller) || !(1.0..=16.0).contains(&channel) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🔀 MIDI CC {} on channel {} doesn't exist", controller, channel)
+            )
+            .with_suggestion("Controllers go from 0 to 127 and channels from 1 to 16: Mix.crossfade(a, b, cc: 1, channel: 1)"));
+        }
+        if let Some(value) = crate::audio::midi::control_value(channel as u8 - 1, controller as u8) {
+            position = value;
+        }
+    }
+    Ok(position.clamp(0.0, 1.0))
+}
+
+fn is_audio(value: &Value) -> bool {
+    match value {
+        Value::Array(samples) => samples.iter().all(|sample| sample.as_number().is_some()),
+        _ => false,
+    }
+}
+
+fn mismatch(a: &Value, b: &Value) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("🔀 Can't crossfade a {} into a {}", a.type_name(), b.type_name())
+    )
+    .with_suggestion("Both sides should be the same kind: two synths, two buffers, two Graphics chains or two scenes")
+}
+
+/// Mix one pair of values, recursing through arrays and scene objects
+fn blend(a: &Value, b: &Value, gains: (f64, f64), position: f64) -> crate::Result<Value> {
+    let (gain_a, gain_b) = gains;
+    if let (Some(chain_a), Some(chain_b)) = (crate::graphics::HydraChain::from_value(a), crate::graphics::HydraChain::from_value(b)) {
+        // Opacity of the top layer, so curves that sum past 1 still land between the two
+        let total = gain_a + gain_b;
+        let amount = if total > 0.0 { gain_b / total } else { position };
+        return Ok(chain_a.then("blend", &[amount], &HashMap::new(), Some(chain_b))?.to_value());
+    }
+    match (a, b) {
+        (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+            let (x, y) = (a.as_number().unwrap_or(0.0), b.as_number().unwrap_or(0.0));
+            Ok(Value::Float(x * gain_a + y * gain_b))
+        }
+        (Value::Array(xs), Value::Array(ys)) => {
+            let silence = Value::Float(0.0);
+            (0..xs.len().max(ys.len()))
+                .map(|index| match (xs.get(index), ys.get(index)) {
+                    (Some(x), Some(y)) => blend(x, y, gains, position),
+                    // A shorter buffer has gone quiet; anything else just carries on
+                    (Some(x), None) if x.as_number().is_some() => blend(x, &silence, gains, position),
+                    (None, Some(y)) if y.as_number().is_some() => blend(&silence, y, gains, position),
+                    (x, y) => Ok(x.or(y).cloned().unwrap_or(Value::Null)),
+                })
+                .collect::<crate::Result<Vec<_>>>()
+                .map(Value::Array)
+        }
+        (Value::Object(xs), Value::Object(ys)) => {
+            let mut scene = HashMap::new();
+            for (key, x) in xs {
+                let value = match ys.get(key) {
+                    Some(y) => blend(x, y, gains, position)?,
+                    None => x.clone(),
+                };
+                scene.insert(key.clone(), value);
+            }
+            for (key, y) in ys {
+                scene.entry(key.clone()).or_insert_with(|| y.clone());
+            }
+            Ok(Value::Object(scene))
+        }
+        // Strings, flags and the like can't be halfway between: switch in the middle
+        _ if std::mem::discriminant(a) == std::mem::discriminant(b) => {
+            Ok(if position < 0.5 { a.clone() } else { b.clone() })
+        }
+        _ => Err(mismatch(a, b)),
+    }
+}
+
+/// Two synth names: set their levels in the engine and report them
+fn crossfade_synths(a: &str, b: &str, gains: (f64, f64)) -> crate::Result<Value> {
+    let mut engine = crate::audio::synth::synth_engine().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for name in [a, b] {
+        if engine.graph(name).is_none() {
+            let known = engine.names();
+            let error = crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownFunction,
+                format!("🔀 No synth called '{}' to crossfade", name)
+            );
+            return Err(if known.is_empty() {
+                error.with_suggestion("Define both synths first, then Mix.crossfade(\"pad\", \"lead\", 0.5)")
+            } else {
+                error.with_suggestion(format!("Defined synths: {}", known.join(", ")))
+            });
+        }
+    }
+    engine.set_gain(a, gains.0 as f32);
+    engine.set_gain(b, gains.1 as f32);
+    let mut levels = HashMap::new();
+    levels.insert(a.to_string(), Value::Float(gains.0));
+    levels.insert(b.to_string(), Value::Float(gains.1));
+    Ok(Value::Object(levels))
+}
+
+pub fn mix_crossfade(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Mix.crossfade", &["fader", "cc", "channel", "curve"])?;
+    let (Some(a), Some(b)) = (args.first(), args.get(1)) else {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🔀 Mix.crossfade() needs two things to fade between"
+        )
+        .with_suggestion("Try: Mix.crossfade(\"pad\", \"lead\", 0.5) or Mix.crossfade(layer_a, layer_b, fader: \"X-Fade\")"));
+    };
+    let position = position(args, named)?;
+    let audio = matches!((a, b), (Value::String(_), Value::String(_))) || (is_audio(a) && is_audio(b));
+    let curve = match named.get("curve") {
+        Some(Value::String(name)) => CrossfadeCurve::parse(name)?,
+        Some(other) => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🔀 curve should be a name, not {}", other.type_name())
+        )
+        .with_suggestion("Try: curve: \"equal_power\"")),
+        None if audio => CrossfadeCurve::EqualPower,
+        None => CrossfadeCurve::Linear,
+    };
+    let gains = curve.gains(position);
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => crossfade_synths(a, b, gains),
+        _ => blend(a, b, gains, position),
+    }
+}
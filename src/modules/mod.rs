@@ -5,6 +5,45 @@ pub mod math;
 pub mod time;
 pub mod web;
 pub mod generate;
+pub mod assets;
+pub mod synth;
+pub mod signal;
+pub mod keyboard;
+pub mod undo;
+pub mod mix;
+pub mod clips;
+pub mod hid;
+pub mod tablet;
+pub mod touch;
+pub mod depth;
+pub mod text;
+pub mod environment;
+pub mod gps;
+pub mod events;
+pub mod network;
+pub mod vision;
+pub mod midi;
+
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Refuse any named argument `function` doesn't take, so a misspelt one is an error rather
+/// than silently ignored
+pub(crate) fn check_named(named: &HashMap<String, Value>, function: &str, accepted: &[&str]) -> crate::Result<()> {
+    let mut unknown: Vec<&String> = named.keys().filter(|name| !accepted.contains(&name.as_str())).collect();
+    unknown.sort();
+    match unknown.first() {
+        Some(unknown) => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🧩 {}() has no parameter called {}", function, unknown)
+        )
+        .with_suggestion(match accepted {
+            [] => "It takes no named arguments; pass the values in order".to_string(),
+            _ => format!("It takes {}", accepted.iter().map(|name| format!("{}:", name)).collect::<Vec<_>>().join(", ")),
+        })),
+        None => Ok(()),
+    }
+}
 
 pub use graphics::*;
 pub use audio::*;
@@ -12,4 +51,21 @@ pub use gui::*;
 pub use math::*;
 pub use time::*;
 pub use web::*;
-pub use generate::*;
\ No newline at end of file
+pub use generate::*;
+pub use assets::*;
+pub use synth::*;
+pub use signal::*;
+pub use keyboard::*;
+pub use undo::*;
+pub use mix::*;
+pub use clips::*;
+pub use hid::*;
+pub use tablet::*;
+pub use touch::*;
+pub use depth::*;
+pub use text::*;
+pub use environment::*;
+pub use gps::*;
+pub use events::*;
+pub use vision::*;
+pub use midi::*;
\ No newline at end of file
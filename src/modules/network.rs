@@ -0,0 +1,94 @@
+use crate::audio::opus::OpusSettings;
+use crate::runtime::network_streams::{network_receivers, network_stream_name, DEFAULT_JITTER_SECONDS};
+use crate::runtime::types::{DataType, Stream};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// The UDP port given first
+fn port(args: &[Value], function: &str) -> crate::Result<u16> {
+    args.first()
+        .and_then(|v| v.as_number())
+        .filter(|port| (1.0..=65535.0).contains(port))
+        .map(|port| port as u16)
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("📡 Stream.{}() needs a port number between 1 and 65535", function)
+        )
+        .with_suggestion(format!("Try: Stream.{}(7000)", function)))
+}
+
+/// Opus settings from `send_to()`'s options: `codec: "opus"` with `bitrate: 96000`, `fec: false`
+/// or `loss: 20` (the percentage of packets error correction plans for)
+pub(crate) fn opus_settings(options: &HashMap<String, Value>) -> crate::Result<Option<OpusSettings>> {
+    match options.get("codec") {
+        None => return Ok(None),
+        Some(Value::String(codec)) if codec == "raw" => return Ok(None),
+        Some(Value::String(codec)) if codec == "opus" => {}
+        Some(other) => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📡 send_to() doesn't know the codec {}", other)
+        )
+        .with_suggestion("Use codec: \"opus\" to compress audio, or leave it off to send samples as they are")),
+    }
+    let defaults = OpusSettings::default();
+    let bitrate = options.get("bitrate").and_then(|v| v.as_number()).unwrap_or(defaults.bitrate as f64);
+    if !(6000.0..=510000.0).contains(&bitrate) {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📡 Opus can't send at {} bits per second", bitrate)
+        )
+        .with_suggestion("Pick a bitrate between 6000 and 510000; 64000 suits most instruments"));
+    }
+    Ok(Some(OpusSettings {
+        bitrate: bitrate as i32,
+        fec: options.get("fec").map(|v| v.is_truthy()).unwrap_or(defaults.fec),
+        expected_loss: options.get("loss").and_then(|v| v.as_number()).map(|loss| loss.clamp(0.0, 100.0) as u8).unwrap_or(defaults.expected_loss),
+    }))
+}
+
+/// `Stream.receive(7000)` listens for streams sent with `value.send_to("this-machine:7000")` and
+/// returns them as one stream; `name: "level"` picks out one sender's stream, and `latency: 40.ms`
+/// waits longer for late packets (20ms by default)
+pub fn stream_receive(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Stream.receive", &["name", "latency"])?;
+    let port = port(args, "receive")?;
+    let delay = match named.get("latency") {
+        Some(latency) => crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+            .seconds(latency)
+            .unwrap_or(DEFAULT_JITTER_SECONDS),
+        None => DEFAULT_JITTER_SECONDS,
+    };
+    let name = match named.get("name") {
+        Some(Value::String(name)) => Some(name.clone()),
+        _ => None,
+    };
+    network_receivers().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).listen(port, delay, name.clone())?;
+    Ok(Value::Stream(Stream {
+        name: network_stream_name(port, name.as_deref()),
+        data_type: DataType::Control,
+        sample_rate: None,
+    }))
+}
+
+/// `Stream.network_stats(7000)`: packets received, lost, late and rebuilt on a port, and the jitter in ms
+pub fn stream_network_stats(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Stream.network_stats", &[])?;
+    let port = port(args, "network_stats")?;
+    let Some(stats) = network_receivers().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).stats(port) else {
+        return Ok(Value::Null);
+    };
+    let mut fields = HashMap::new();
+    fields.insert("received".to_string(), Value::Integer(stats.received as i64));
+    fields.insert("lost".to_string(), Value::Integer(stats.lost as i64));
+    fields.insert("late".to_string(), Value::Integer(stats.late as i64));
+    fields.insert("recovered".to_string(), Value::Integer(stats.recovered as i64));
+    fields.insert("jitter_ms".to_string(), Value::Float(stats.jitter * 1000.0));
+    Ok(Value::Object(fields))
+}
+
+/// `Stream.close(7000)` stops listening; true if the port was open
+pub fn stream_close(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Stream.close", &[])?;
+    let port = port(args, "close")?;
+    Ok(Value::Boolean(network_receivers().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).close(port)))
+}
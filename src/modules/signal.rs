@@ -0,0 +1,137 @@
+use crate::audio::signal::{SignalGenerator, SignalStats, SweepCurve, DEFAULT_SIGNAL_RATE};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Longest test signal a script can ask for, to keep a typo from allocating gigabytes
+const MAX_SIGNAL_SECONDS: f64 = 600.0;
+
+/// Named arguments every generator takes
+const GENERATOR_OPTIONS: [&str; 2] = ["sample_rate", "seed"];
+
+fn number(args: &[Value], index: usize) -> Option<f64> {
+    args.get(index).and_then(|v| v.as_number())
+}
+
+fn generator(named: &HashMap<String, Value>) -> crate::Result<SignalGenerator> {
+    let sample_rate = named.get("sample_rate").and_then(|v| v.as_number()).unwrap_or(DEFAULT_SIGNAL_RATE);
+    if !(1000.0..=384000.0).contains(&sample_rate) {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📈 {} Hz isn't a usable sample rate", sample_rate)
+        )
+        .with_suggestion("Common rates are 44100 and 48000"));
+    }
+    let mut generator = SignalGenerator::new(sample_rate);
+    if let Some(seed) = named.get("seed").and_then(|v| v.as_number()) {
+        generator = generator.with_seed(seed as u64);
+    }
+    Ok(generator)
+}
+
+fn length(generator: &SignalGenerator, args: &[Value], index: usize, function: &str) -> crate::Result<usize> {
+    // Seconds, or a unit value like 250.ms
+    let seconds = number(args, index).unwrap_or(1.0);
+    if !(0.0..=MAX_SIGNAL_SECONDS).contains(&seconds) {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📈 Signal.{}() length must be between 0 and {} seconds", function, MAX_SIGNAL_SECONDS)
+        )
+        .with_suggestion(format!("Try: Signal.{}(..., 1) for one second", function)));
+    }
+    Ok(generator.samples_for(seconds))
+}
+
+fn samples(samples: Vec<f32>) -> Value {
+    Value::Array(samples.into_iter().map(|s| Value::Float(s as f64)).collect())
+}
+
+pub fn signal_sine(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.sine", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let frequency = number(args, 0).unwrap_or(1000.0);
+    let length = length(&generator, args, 1, "sine")?;
+    let amplitude = number(args, 2).unwrap_or(1.0);
+    Ok(samples(generator.sine(frequency, amplitude, length)))
+}
+
+pub fn signal_sweep(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.sweep", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let from = number(args, 0).unwrap_or(20.0);
+    let to = number(args, 1).unwrap_or(20000.0);
+    let length = length(&generator, args, 2, "sweep")?;
+    let amplitude = number(args, 3).unwrap_or(1.0);
+    let curve = match args.get(4) {
+        Some(Value::String(curve)) if curve == "linear" => SweepCurve::Linear,
+        Some(Value::String(curve)) if curve == "exponential" || curve == "log" => SweepCurve::Exponential,
+        Some(Value::String(curve)) => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("📈 Unknown sweep curve '{}'", curve)
+        )
+        .with_suggestion("Use \"exponential\" (equal time per octave) or \"linear\"")),
+        _ => SweepCurve::Exponential,
+    };
+    Ok(samples(generator.sweep(from, to, amplitude, length, curve)))
+}
+
+pub fn signal_impulse(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.impulse", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let length = length(&generator, args, 0, "impulse")?;
+    let position = generator.samples_for(number(args, 1).unwrap_or(0.0));
+    let amplitude = number(args, 2).unwrap_or(1.0);
+    Ok(samples(generator.impulse(amplitude, length, position)))
+}
+
+pub fn signal_white_noise(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.white_noise", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let length = length(&generator, args, 0, "white_noise")?;
+    let amplitude = number(args, 1).unwrap_or(1.0);
+    Ok(samples(generator.white_noise(amplitude, length)))
+}
+
+pub fn signal_pink_noise(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.pink_noise", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let length = length(&generator, args, 0, "pink_noise")?;
+    let amplitude = number(args, 1).unwrap_or(1.0);
+    Ok(samples(generator.pink_noise(amplitude, length)))
+}
+
+pub fn signal_dc(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.dc", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let level = number(args, 0).unwrap_or(0.5);
+    let length = length(&generator, args, 1, "dc")?;
+    Ok(samples(generator.dc(level, length)))
+}
+
+pub fn signal_silence(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.silence", &GENERATOR_OPTIONS)?;
+    let generator = generator(named)?;
+    let length = length(&generator, args, 0, "silence")?;
+    Ok(samples(generator.silence(length)))
+}
+
+pub fn signal_stats(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Signal.stats", &["sample_rate"])?;
+    let input: Vec<f32> = match args.first() {
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_number()).map(|s| s as f32).collect(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "📈 Signal.stats() needs an array of samples"
+        )
+        .with_suggestion("Try: Signal.stats(Signal.sine(440, 1))")),
+    };
+    let stats = SignalStats::measure(&input, generator(named)?.sample_rate);
+
+    let mut result = HashMap::new();
+    result.insert("length".to_string(), Value::Integer(stats.length as i64));
+    result.insert("peak".to_string(), Value::Float(stats.peak));
+    result.insert("rms".to_string(), Value::Float(stats.rms));
+    result.insert("rms_db".to_string(), Value::Float(stats.rms_db().max(-144.0)));
+    result.insert("dc_offset".to_string(), Value::Float(stats.dc_offset));
+    result.insert("frequency".to_string(), Value::Float(stats.frequency));
+    Ok(Value::Object(result))
+}
@@ -0,0 +1,94 @@
+use crate::audio::synth::{note_frequency, synth_engine};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+/// Synth parameters accept numbers or note names, so `note: "E3"` and `note: 164.8` mean the same
+pub fn synth_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(text) => note_frequency(text),
+        other => other.as_number(),
+    }
+}
+
+fn engine_error() -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::AudioDeviceError,
+        "🎛️ The synth engine stopped after an earlier error"
+    )
+    .with_suggestion("Restart the sketch to reset audio")
+}
+
+pub fn synth_play(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    let name = match args.first() {
+        Some(Value::String(name)) => name.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🎛️ Synth.play() needs the name of a synth"
+        )
+        .with_suggestion("Try: Synth.play(\"pluck\", note: \"E3\")")),
+    };
+
+    // Named arguments set the synth's parameters, which it checks: Synth.play("pluck", note: "E3", dur: 0.25)
+    let mut params = HashMap::new();
+    let mut duration = None;
+    for (key, value) in named {
+        if key == "dur" && *value == Value::String("hold".to_string()) {
+            duration = Some(f64::INFINITY);
+            continue;
+        }
+        let number = synth_value(value).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🎛️ {} should be a number or a note name like \"E3\"", key)
+        ))?;
+        if key == "dur" {
+            duration = Some(number);
+        } else {
+            params.insert(key.clone(), number);
+        }
+    }
+
+    let id = synth_engine().lock().map_err(|_| engine_error())?.play(&name, &params, duration)?;
+
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("voice".to_string()));
+    result.insert("synth".to_string(), Value::String(name));
+    result.insert("id".to_string(), Value::Integer(id as i64));
+    Ok(Value::Object(result))
+}
+
+pub fn synth_release(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Synth.release", &[])?;
+    let id = match args.first() {
+        Some(Value::Object(fields)) => fields.get("id").and_then(|v| v.as_number()),
+        Some(other) => other.as_number(),
+        None => None,
+    };
+    let id = id.ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        "🎛️ Synth.release() needs a voice from Synth.play()"
+    )
+    .with_suggestion("Try: voice = Synth.play(\"pad\", dur: \"hold\") then Synth.release(voice)"))?;
+
+    let released = synth_engine().lock().map_err(|_| engine_error())?.release(id as u64);
+    Ok(Value::Boolean(released))
+}
+
+/// Render the next stretch of the mix as samples, for offline bounces and headless runs
+pub fn synth_render(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Synth.render", &[])?;
+    let seconds = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(1.0)
+        .clamp(0.0, 60.0);
+
+    let mut engine = synth_engine().lock().map_err(|_| engine_error())?;
+    let frames = (seconds * engine.sample_rate()) as usize;
+    let samples = engine.render(frames);
+    Ok(Value::Array(samples.into_iter().map(|s| Value::Float(s as f64)).collect()))
+}
+
+pub fn synth_voices(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Synth.voices", &[])?;
+    let engine = synth_engine().lock().map_err(|_| engine_error())?;
+    Ok(Value::Integer(engine.voices().len() as i64))
+}
@@ -0,0 +1,60 @@
+use crate::hardware::tablet::{tablet, tablet_stream_name, TABLET_FIELDS};
+use crate::runtime::types::{DataType, Stream};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+fn stream(field: &str) -> Value {
+    Value::Stream(Stream {
+        name: tablet_stream_name(field),
+        data_type: DataType::Control,
+        sample_rate: None,
+    })
+}
+
+/// `Tablet.pressure()`: how hard the pen presses, 0 to 1, as the `tablet.pressure` stream
+pub fn tablet_pressure(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Tablet.pressure", &[])?;
+    Ok(stream("pressure"))
+}
+
+/// How far the pen leans, 0 upright to 1 lying flat, on platforms that report it
+pub fn tablet_tilt(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Tablet.tilt", &[])?;
+    Ok(stream("tilt"))
+}
+
+/// 1 while the pen is near enough for the tablet to see it, 0 otherwise
+pub fn tablet_proximity(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Tablet.proximity", &[])?;
+    Ok(stream("proximity"))
+}
+
+/// `Tablet.read(Tablet.pressure())` or `Tablet.read("tilt")`: a field's value right now
+pub fn tablet_read(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Tablet.read", &[])?;
+    let field = match args.first() {
+        Some(Value::Stream(stream)) => stream.name.strip_prefix("tablet.").unwrap_or(&stream.name).to_string(),
+        Some(Value::String(field)) => field.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🖊️ Tablet.read() needs a tablet stream or a field name"
+        )
+        .with_suggestion("Try: Tablet.read(\"pressure\")")),
+    };
+    let state = tablet().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match state.value(&field) {
+        Some(value) => Ok(Value::Float(value)),
+        None => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::UnknownFunction,
+            format!("🖊️ The tablet has no field called '{}'", field)
+        )
+        .with_suggestion(format!("Fields: {}", TABLET_FIELDS.join(", ")))),
+    }
+}
+
+/// Whether the pen is on the surface right now
+pub fn tablet_touching(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Tablet.touching", &[])?;
+    let state = tablet().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Boolean(state.touching()))
+}
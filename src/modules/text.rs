@@ -0,0 +1,141 @@
+use crate::graphics::lyrics::Lyrics;
+use crate::graphics::primitives::Color;
+use crate::graphics::typography::layout_line;
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+static LYRICS: OnceLock<Mutex<HashMap<String, Arc<Lyrics>>>> = OnceLock::new();
+
+/// Lyric files loaded so far, by path
+fn loaded() -> &'static Mutex<HashMap<String, Arc<Lyrics>>> {
+    LYRICS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn load(path: &str, offset: f64) -> crate::Result<Arc<Lyrics>> {
+    let text = match crate::runtime::asset_manager().load_blocking(path) {
+        Some(asset) if asset.is_ready() => String::from_utf8_lossy(&asset.data).into_owned(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::FileNotFound,
+            format!("🎤 Couldn't read the lyrics '{}'", path)
+        )
+        .with_suggestion("Check the path is relative to your sketch, like \"lyrics/song.lrc\"")),
+    };
+    let mut lyrics = Lyrics::parse(path, &text)?;
+    lyrics.shift(offset);
+    let lyrics = Arc::new(lyrics);
+    loaded().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(path.to_string(), lyrics.clone());
+    Ok(lyrics)
+}
+
+/// What `Text.load_lyrics()` returned, or a path, loaded on first use
+fn lyrics_for(value: Option<&Value>, function: &str) -> crate::Result<Arc<Lyrics>> {
+    let path = match value {
+        Some(Value::Object(fields)) => match fields.get("path") {
+            Some(Value::String(path)) => path.clone(),
+            _ => String::new(),
+        },
+        Some(Value::String(path)) => path.clone(),
+        _ => String::new(),
+    };
+    if path.is_empty() {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🎤 Text.{}() needs lyrics from Text.load_lyrics()", function)
+        )
+        .with_suggestion(format!("Try: song = Text.load_lyrics(\"song.lrc\") and then Text.{}(song)", function)));
+    }
+    let cached = loaded().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&path).cloned();
+    match cached {
+        Some(lyrics) => Ok(lyrics),
+        None => load(&path, 0.0),
+    }
+}
+
+/// Seconds into the song: `time:` if the script gives one, otherwise where the transport is
+fn song_time(options: &HashMap<String, Value>) -> f64 {
+    match options.get("time").and_then(|v| v.as_number()) {
+        Some(time) => time,
+        None => {
+            let mut transport = crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            transport.update();
+            transport.current_time
+        }
+    }
+}
+
+/// `Text.load_lyrics("song.lrc", offset: 0.2)` reads LRC or SRT timings; `offset:` moves every line later by that many seconds
+pub fn text_load_lyrics(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Text.load_lyrics", &["offset"])?;
+    let path = match args.first() {
+        Some(Value::String(path)) => path.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🎤 Text.load_lyrics() needs the path of an .lrc or .srt file"
+        )
+        .with_suggestion("Try: Text.load_lyrics(\"lyrics/song.lrc\")")),
+    };
+    let offset = named.get("offset").and_then(|v| v.as_number()).unwrap_or(0.0);
+    let lyrics = load(&path, offset)?;
+
+    let mut info = HashMap::new();
+    info.insert("type".to_string(), Value::String("lyrics".to_string()));
+    info.insert("path".to_string(), Value::String(path));
+    info.insert("lines".to_string(), Value::Integer(lyrics.lines.len() as i64));
+    info.insert("duration".to_string(), Value::Float(lyrics.duration()));
+    Ok(Value::Object(info))
+}
+
+/// `Text.lyric(song)`: the line showing now as `{ text, index, start, end, progress, word, sung, unsung }`,
+/// or null between lines. Follows the transport unless given `time:` in seconds
+pub fn text_lyric(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Text.lyric", &["time"])?;
+    let lyrics = lyrics_for(args.first(), "lyric")?;
+    let time = song_time(named);
+    let Some((index, line)) = lyrics.line_at(time) else {
+        return Ok(Value::Null);
+    };
+    let sung = line.sung_chars(time);
+
+    let mut fields = HashMap::new();
+    fields.insert("text".to_string(), Value::String(line.text.clone()));
+    fields.insert("index".to_string(), Value::Integer(index as i64));
+    fields.insert("start".to_string(), Value::Float(line.start));
+    fields.insert("end".to_string(), Value::Float(line.end));
+    fields.insert("progress".to_string(), Value::Float(line.progress(time)));
+    fields.insert("word".to_string(), Value::String(line.word_at(time).map(|word| word.text.clone()).unwrap_or_default()));
+    fields.insert("sung".to_string(), Value::String(line.text.chars().take(sung).collect()));
+    fields.insert("unsung".to_string(), Value::String(line.text.chars().skip(sung).collect()));
+    Ok(Value::Object(fields))
+}
+
+/// `Text.karaoke(song, 40, 600, size: 48, color: 0xFFFFFF, highlight: 0xFFCC00)`: glyphs for the
+/// line showing now, with what's been sung in the highlight color; no glyphs between lines
+pub fn text_karaoke(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Text.karaoke", &["time", "size", "color", "highlight"])?;
+    let lyrics = lyrics_for(args.first(), "karaoke")?;
+    let time = song_time(named);
+    let Some((_, line)) = lyrics.line_at(time) else {
+        return Ok(Value::Array(Vec::new()));
+    };
+
+    let number = |index: usize| args.get(index).and_then(|v| v.as_number());
+    let x = number(1).unwrap_or(0.0) as f32;
+    let y = number(2).unwrap_or(0.0) as f32;
+    let size = named.get("size").and_then(|v| v.as_number()).unwrap_or(32.0) as f32;
+    let color = Color::from_hex(named.get("color").and_then(|v| v.as_number()).unwrap_or(0xFFFFFF as f64) as u32);
+    let highlight = Color::from_hex(named.get("highlight").and_then(|v| v.as_number()).unwrap_or(0xFFCC00 as f64) as u32);
+
+    // Glyph indices skip line breaks, so count sung characters the same way
+    let sung = line.text.chars().take(line.sung_chars(time)).filter(|&c| c != '\n').count();
+    let glyphs = layout_line(&line.text, x, y, size, color)
+        .into_iter()
+        .map(|mut glyph| {
+            if glyph.index < sung {
+                glyph.color = highlight;
+            }
+            glyph.to_value()
+        })
+        .collect();
+    Ok(Value::Array(glyphs))
+}
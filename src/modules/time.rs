@@ -2,20 +2,48 @@ use crate::runtime::Value;
 use std::time::{SystemTime, UNIX_EPOCH, Instant, Duration};
 use std::collections::HashMap;
 
-pub fn now(_args: &[Value]) -> crate::Result<Value> {
+pub fn now(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.now", &[])?;
     let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default() // A clock set before 1970 reads as the epoch
         .as_secs_f64();
     Ok(Value::Float(timestamp))
 }
 
-pub fn delta_time(_args: &[Value]) -> crate::Result<Value> {
-    // This would typically be calculated by the main loop
-    Ok(Value::Float(0.016667)) // 60 FPS default
+pub fn delta_time(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.delta_time", &[])?;
+    // Measured by the frame pacer driving `loop { }`; 60 FPS until the first frame
+    Ok(Value::Float(crate::runtime::frame_pacing::current_frame().delta_time as f64))
 }
 
-pub fn fps(_args: &[Value]) -> crate::Result<Value> {
-    Ok(Value::Float(60.0))
+pub fn fps(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.fps", &[])?;
+    Ok(Value::Float(crate::runtime::frame_pacing::current_frame().fps as f64))
+}
+
+pub fn frame_alpha(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.frame_alpha", &[])?;
+    Ok(Value::Float(crate::runtime::frame_pacing::current_frame().alpha as f64))
+}
+
+pub fn interpolate(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.interpolate", &[])?;
+    if args.len() < 2 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🎬 Timeline.interpolate() needs the previous and current value"
+        )
+        .with_suggestion("Try: Timeline.interpolate(previous_position, position)")
+        .with_suggestion("The blend amount defaults to Timeline.frame_alpha()"));
+    }
+    
+    let alpha = args.get(2)
+        .and_then(|v| v.as_number())
+        .map(|a| a as f32)
+        .unwrap_or_else(|| crate::runtime::frame_pacing::current_frame().alpha);
+    
+    Ok(crate::runtime::frame_pacing::interpolate_value(&args[0], &args[1], alpha))
 }
 
 #[derive(Debug, Clone)]
@@ -29,8 +57,15 @@ pub struct Timeline {
     pub loop_end: f64,
     pub markers: Vec<TimelineMarker>,
     pub events: Vec<TimelineEvent>,
+    /// Tempo musical units (1/8.note, 2.beats, 1.bar) resolve against
+    pub bpm: f64,
+    pub beats_per_bar: u32,
 }
 
+/// Tempos `Timeline.tempo()` accepts
+pub const MIN_TEMPO: f64 = 20.0;
+pub const MAX_TEMPO: f64 = 400.0;
+
 #[derive(Debug, Clone)]
 pub struct TimelineMarker {
     pub name: String,
@@ -68,6 +103,28 @@ impl Timeline {
             loop_end: 60.0, // Default 60 second loop
             markers: Vec::new(),
             events: Vec::new(),
+            bpm: 120.0,
+            beats_per_bar: 4,
+        }
+    }
+    
+    pub fn set_tempo(&mut self, bpm: f64) -> crate::Result<()> {
+        if !(MIN_TEMPO..=MAX_TEMPO).contains(&bpm) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🎬 {} BPM is outside the {}-{} range the transport runs at", bpm, MIN_TEMPO, MAX_TEMPO)
+            )
+            .with_suggestion("Try: Timeline.tempo(120)"));
+        }
+        self.bpm = bpm;
+        Ok(())
+    }
+    
+    /// Seconds for a time value at the current tempo: plain numbers are already seconds
+    pub fn seconds(&self, value: &Value) -> Option<f64> {
+        match value {
+            Value::UnitValue(unit_value) => unit_value.to_seconds(self.bpm, self.beats_per_bar as f64),
+            other => other.as_number(),
         }
     }
     
@@ -86,6 +143,21 @@ impl Timeline {
         self.start_time = Instant::now();
     }
     
+    /// Carry on from the current position, unlike `play` which restarts the clock
+    pub fn resume(&mut self) {
+        self.is_playing = true;
+        self.start_time = Instant::now() - Duration::from_secs_f64(self.current_time.max(0.0) / self.speed);
+    }
+    
+    pub fn toggle(&mut self) {
+        if self.is_playing {
+            self.update();
+            self.pause();
+        } else {
+            self.resume();
+        }
+    }
+    
     pub fn seek(&mut self, time: f64) {
         self.current_time = time;
         self.start_time = Instant::now();
@@ -488,8 +560,74 @@ impl Default for AnimationCurve {
     }
 }
 
+static TRANSPORT: std::sync::OnceLock<std::sync::Mutex<Timeline>> = std::sync::OnceLock::new();
+
+/// The global transport, running from launch and toggled by the space bar
+pub fn transport() -> &'static std::sync::Mutex<Timeline> {
+    TRANSPORT.get_or_init(|| {
+        let mut timeline = Timeline::new();
+        timeline.play();
+        std::sync::Mutex::new(timeline)
+    })
+}
+
+pub fn transport_playing(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.playing", &[])?;
+    let playing = transport().lock().map(|t| t.is_playing).unwrap_or(false);
+    Ok(Value::Boolean(playing))
+}
+
+pub fn transport_toggle(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.toggle", &[])?;
+    let mut transport = transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    transport.toggle();
+    Ok(Value::Boolean(transport.is_playing))
+}
+
+pub fn transport_time(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.transport_time", &[])?;
+    let mut transport = transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    transport.update();
+    Ok(Value::Float(transport.current_time))
+}
+
+/// `Timeline.tempo()` reads the transport tempo; `Timeline.tempo(128, beats: 3)` sets it.
+/// Anything timed in notes, beats or bars follows along.
+pub fn timeline_tempo(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.tempo", &["beats"])?;
+    let mut transport = transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(bpm) = args.first().and_then(|v| v.as_number()) {
+        transport.set_tempo(bpm)?;
+    }
+    if let Some(beats) = named.get("beats").and_then(|v| v.as_number()) {
+        if !(1.0..=32.0).contains(&beats) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🎬 A bar of {} beats isn't a time signature", beats)
+            )
+            .with_suggestion("Try: Timeline.tempo(90, beats: 3) for 3/4"));
+        }
+        transport.beats_per_bar = beats as u32;
+    }
+    Ok(Value::Float(transport.bpm))
+}
+
+/// `Timeline.seconds(1/8.note)`: how long a musical length lasts at the current tempo
+pub fn timeline_seconds(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.seconds", &[])?;
+    let transport = transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    args.first()
+        .and_then(|value| transport.seconds(value))
+        .map(Value::Float)
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🎬 Timeline.seconds() needs a length, like 1/8.note, 2.beats or 250.ms"
+        ))
+}
+
 // Module functions for the runtime
-pub fn timeline_create(_args: &[Value]) -> crate::Result<Value> {
+pub fn timeline_create(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.create", &[])?;
     let timeline = Timeline::new();
     let mut result = HashMap::new();
     result.insert("type".to_string(), Value::String("timeline".to_string()));
@@ -498,8 +636,9 @@ pub fn timeline_create(_args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn sequencer_create(args: &[Value]) -> crate::Result<Value> {
-    let bpm = args.get(0)
+pub fn sequencer_create(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.sequencer", &[])?;
+    let bpm = args.first()
         .and_then(|v| v.as_number())
         .unwrap_or(120.0) as f32;
     
@@ -511,15 +650,17 @@ pub fn sequencer_create(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn animation_curve_create(_args: &[Value]) -> crate::Result<Value> {
-    let curve = AnimationCurve::new();
+pub fn animation_curve_create(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.animation_curve", &[])?;
+    let _curve = AnimationCurve::new();
     let mut result = HashMap::new();
     result.insert("type".to_string(), Value::String("animation_curve".to_string()));
     result.insert("keyframes".to_string(), Value::Array(Vec::new()));
     Ok(Value::Object(result))
 }
 
-pub fn every(args: &[Value]) -> crate::Result<Value> {
+pub fn every(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.every", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "every requires a duration argument"));
     }
@@ -532,7 +673,8 @@ pub fn every(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn after(args: &[Value]) -> crate::Result<Value> {
+pub fn after(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.after", &[])?;
     if args.is_empty() {
         return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, "after requires a duration argument"));
     }
@@ -545,11 +687,249 @@ pub fn after(args: &[Value]) -> crate::Result<Value> {
     Ok(Value::Object(result))
 }
 
-pub fn sequence(args: &[Value]) -> crate::Result<Value> {
-    let steps = args.iter().cloned().collect();
+pub fn sequence(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.sequence", &[])?;
+    let steps = args.to_vec();
     
     let mut result = HashMap::new();
     result.insert("type".to_string(), Value::String("sequence".to_string()));
     result.insert("steps".to_string(), Value::Array(steps));
     Ok(Value::Object(result))
-}
\ No newline at end of file
+}
+// Timecode synchronization
+
+fn timecode_rate(args: &[Value], index: usize) -> crate::Result<crate::audio::timecode::FrameRate> {
+    let fps = args.get(index)
+        .and_then(|v| v.as_number())
+        .unwrap_or(25.0);
+    
+    crate::audio::timecode::FrameRate::from_fps(fps)
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("⏱️ {} is not a timecode frame rate", fps)
+        )
+        .with_suggestion("Timecode runs at 24, 25, 29.97 (drop-frame) or 30 fps"))
+}
+
+pub fn timecode_sync(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.timecode", &[])?;
+    let source = match args.first() {
+        Some(Value::String(s)) if s == "ltc" || s == "mtc" => s.clone(),
+        Some(Value::String(s)) => {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("⏱️ Unknown timecode source '{}'", s)
+            )
+            .with_suggestion("Use \"ltc\" to decode timecode from an audio input")
+            .with_suggestion("Use \"mtc\" to follow MIDI timecode from a lighting desk or DAW"));
+        }
+        _ => "ltc".to_string(),
+    };
+    
+    let rate = timecode_rate(args, 1)?;
+    
+    // Offset in seconds, or a timecode string marking the start of the show
+    let offset = match args.get(2) {
+        Some(Value::String(s)) => crate::audio::timecode::Timecode::parse(s, rate)
+            .map(|tc| tc.to_seconds())
+            .ok_or_else(|| crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("⏱️ Could not read timecode '{}'", s)
+            )
+            .with_suggestion("Timecode looks like \"10:00:00:00\" (hours:minutes:seconds:frames)"))?,
+        Some(v) => v.as_number().unwrap_or(0.0),
+        None => 0.0,
+    };
+    
+    let input = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0.0) as i64;
+    
+    println!("Timeline.timecode: source={}, fps={:.2}, offset={:.3}s, input={}", source, rate.fps(), offset, input);
+    
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("timecode_sync".to_string()));
+    result.insert("source".to_string(), Value::String(source));
+    result.insert("fps".to_string(), Value::Float(rate.fps()));
+    result.insert("offset".to_string(), Value::Float(offset));
+    result.insert("input".to_string(), Value::Integer(input));
+    result.insert("locked".to_string(), Value::Boolean(false));
+    result.insert("position".to_string(), Value::Float(0.0));
+    Ok(Value::Object(result))
+}
+
+pub fn timecode_format(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.timecode_format", &[])?;
+    let seconds = args.first()
+        .and_then(|v| v.as_number())
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "⏱️ Timeline.timecode_format() needs a time in seconds"
+        )
+        .with_suggestion("Try: Timeline.timecode_format(Timeline.now(), 25)"))?;
+    
+    let rate = timecode_rate(args, 1)?;
+    let timecode = crate::audio::timecode::Timecode::from_seconds(seconds, rate);
+    Ok(Value::String(timecode.to_string()))
+}
+
+pub fn timecode_parse(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.timecode_parse", &[])?;
+    let text = match args.first() {
+        Some(Value::String(s)) => s.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "⏱️ Timeline.timecode_parse() needs a timecode string"
+        )
+        .with_suggestion("Try: Timeline.timecode_parse(\"01:00:00:00\", 25)")),
+    };
+    
+    let rate = timecode_rate(args, 1)?;
+    let timecode = crate::audio::timecode::Timecode::parse(&text, rate)
+        .ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            format!("⏱️ Could not read timecode '{}'", text)
+        )
+        .with_suggestion("Timecode looks like \"10:00:00:00\" (hours:minutes:seconds:frames)")
+        .with_suggestion("The frame number must be below the frame rate"))?;
+    
+    Ok(Value::Float(timecode.to_seconds()))
+}
+
+pub fn timecode_overlay(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.timecode_overlay", &[])?;
+    let x = args.first()
+        .and_then(|v| v.as_number())
+        .unwrap_or(10.0);
+    
+    let y = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(10.0);
+    
+    let size = args.get(2)
+        .and_then(|v| v.as_number())
+        .unwrap_or(16.0);
+    
+    let color = args.get(3)
+        .and_then(|v| v.as_number())
+        .unwrap_or(0xFFFFFF as f64) as i64;
+    
+    println!("Timeline.timecode_overlay: at ({:.1},{:.1}), size={:.1}, color=0x{:06X}", x, y, size, color);
+    
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("timecode_overlay".to_string()));
+    result.insert("x".to_string(), Value::Float(x));
+    result.insert("y".to_string(), Value::Float(y));
+    result.insert("size".to_string(), Value::Float(size));
+    result.insert("color".to_string(), Value::Integer(color));
+    Ok(Value::Object(result))
+}
+
+// Mini-notation patterns
+
+/// Default tempo: one cycle every two seconds, Tidal's 0.5 cycles per second
+const DEFAULT_CPS: f64 = 0.5;
+
+fn pattern_source(args: &[Value], function: &str) -> crate::Result<(crate::audio::mini_notation::MiniPattern, f64)> {
+    let (source, cps) = match args.first() {
+        Some(Value::String(s)) => (s.clone(), DEFAULT_CPS),
+        Some(Value::Object(fields)) if matches!(fields.get("type"), Some(Value::String(t)) if t == "pattern") => {
+            let source = match fields.get("source") {
+                Some(Value::String(s)) => s.clone(),
+                _ => String::new(),
+            };
+            (source, fields.get("cps").and_then(|v| v.as_number()).unwrap_or(DEFAULT_CPS))
+        }
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🥁 Timeline.{}() needs a pattern", function)
+        )
+        .with_suggestion("Try: drums = Timeline.pattern(\"bd ~ sn [hh hh]\")")),
+    };
+    Ok((crate::audio::mini_notation::MiniPattern::parse(&source)?, cps))
+}
+
+/// The window the current frame covers on the pattern clock
+pub(crate) fn frame_window() -> (f64, f64) {
+    let tick = crate::runtime::frame_pacing::current_frame();
+    (tick.elapsed, tick.elapsed + tick.delta_time as f64)
+}
+
+fn pattern_value(text: &str) -> Value {
+    // Numeric steps like "0 3 7" come back as numbers so they can feed notes or positions directly
+    if let Ok(i) = text.parse::<i64>() {
+        Value::Integer(i)
+    } else if let Ok(f) = text.parse::<f64>() {
+        Value::Float(f)
+    } else {
+        Value::String(text.to_string())
+    }
+}
+
+pub fn pattern(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.pattern", &[])?;
+    let source = match args.first() {
+        Some(Value::String(s)) => s.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🥁 Timeline.pattern() needs a pattern string"
+        )
+        .with_suggestion("Try: Timeline.pattern(\"bd ~ sn [hh hh]\")")),
+    };
+
+    let cps = args.get(1)
+        .and_then(|v| v.as_number())
+        .unwrap_or(DEFAULT_CPS);
+    if cps <= 0.0 {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🥁 Pattern speed must be above zero cycles per second"
+        )
+        .with_suggestion("0.5 plays one cycle every two seconds"));
+    }
+
+    // Parse now so mistakes show up where the pattern is written, not on the first frame
+    crate::audio::mini_notation::MiniPattern::parse(&source)?;
+
+    let mut result = HashMap::new();
+    result.insert("type".to_string(), Value::String("pattern".to_string()));
+    result.insert("source".to_string(), Value::String(source));
+    result.insert("cps".to_string(), Value::Float(cps));
+    Ok(Value::Object(result))
+}
+
+pub fn pattern_events(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.pattern_events", &[])?;
+    let (pattern, cps) = pattern_source(args, "pattern_events")?;
+    let (from, to) = match (args.get(1).and_then(|v| v.as_number()), args.get(2).and_then(|v| v.as_number())) {
+        (Some(from), Some(to)) => (from, to),
+        _ => frame_window(),
+    };
+
+    let events = pattern.query_span(from * cps, to * cps)
+        .into_iter()
+        .map(|(onset, event)| {
+            let mut fields = HashMap::new();
+            fields.insert("value".to_string(), pattern_value(&event.value));
+            fields.insert("start".to_string(), Value::Float(onset / cps));
+            fields.insert("duration".to_string(), Value::Float(event.duration / cps));
+            fields.insert("cycle".to_string(), Value::Integer(onset.floor() as i64));
+            Value::Object(fields)
+        })
+        .collect();
+    Ok(Value::Array(events))
+}
+
+pub fn pattern_hit(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Timeline.pattern_hit", &[])?;
+    let (pattern, cps) = pattern_source(args, "pattern_hit")?;
+    let (from, to) = frame_window();
+    let events = pattern.query_span(from * cps, to * cps);
+
+    let hit = match args.get(1) {
+        Some(Value::String(name)) => events.iter().any(|(_, event)| &event.value == name),
+        Some(other) => events.iter().any(|(_, event)| pattern_value(&event.value) == *other),
+        None => !events.is_empty(),
+    };
+    Ok(Value::Boolean(hit))
+}
@@ -0,0 +1,55 @@
+use crate::hardware::touch::{touch, TouchPoint};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+fn point(point: &TouchPoint) -> Value {
+    let mut fields = HashMap::new();
+    fields.insert("id".to_string(), Value::Integer(point.id as i64));
+    fields.insert("x".to_string(), Value::Float(point.x));
+    fields.insert("y".to_string(), Value::Float(point.y));
+    fields.insert("pressure".to_string(), Value::Float(point.pressure));
+    fields.insert("age".to_string(), Value::Float(point.started.elapsed().as_secs_f64()));
+    Value::Object(fields)
+}
+
+/// Every finger on the screen as `{ id, x, y, pressure, age }`, with x and y from 0 to 1
+pub fn touch_active(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Touch.active", &[])?;
+    let state = touch().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Array(state.active().map(point).collect()))
+}
+
+pub fn touch_count(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Touch.count", &[])?;
+    let state = touch().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Integer(state.active().count() as i64))
+}
+
+/// `Touch.get(id)`: one finger by the id `Touch.active()` gave it, or null once it's lifted
+pub fn touch_get(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Touch.get", &[])?;
+    let id = match args.first() {
+        Some(Value::Integer(id)) if *id >= 0 => *id as u64,
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "👆 Touch.get() needs a touch id"
+        )
+        .with_suggestion("Ids come from Touch.active() or Touch.started(), like Touch.get(touch_id)")),
+    };
+    let state = touch().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(state.get(id).map(point).unwrap_or(Value::Null))
+}
+
+/// Fingers that went down since the last frame
+pub fn touch_started(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Touch.started", &[])?;
+    let state = touch().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Array(state.started().map(point).collect()))
+}
+
+/// Fingers that came up since the last frame, where they last were
+pub fn touch_ended(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Touch.ended", &[])?;
+    let state = touch().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Array(state.ended().iter().map(point).collect()))
+}
@@ -0,0 +1,40 @@
+use crate::runtime::undo::{redo_last, undo_history, undo_last, ParamChange};
+use crate::runtime::Value;
+use std::collections::HashMap;
+
+fn undone(change: Option<ParamChange>) -> Value {
+    change.map(|change| change.to_value()).unwrap_or(Value::Null)
+}
+
+/// Revert the latest tweak, or the latest tweak to one control: Undo.last("cutoff")
+pub fn undo_last_change(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Undo.last", &[])?;
+    let name = match args.first() {
+        Some(Value::String(name)) => Some(name.as_str()),
+        None | Some(Value::Null) => None,
+        Some(other) => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("↩️ Undo.last() takes a control or variable name, not {}", other.type_name())
+        )
+        .with_suggestion("Try: Undo.last() or Undo.last(\"cutoff\")")),
+    };
+    Ok(undone(undo_last(name)))
+}
+
+pub fn undo_redo(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Undo.redo", &[])?;
+    Ok(undone(redo_last()))
+}
+
+/// Undoable changes, oldest first
+pub fn undo_entries(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Undo.history", &[])?;
+    let history = undo_history().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(Value::Array(history.entries().iter().map(ParamChange::to_value).collect()))
+}
+
+pub fn undo_clear(_args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Undo.clear", &[])?;
+    undo_history().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    Ok(Value::Null)
+}
@@ -0,0 +1,414 @@
+use crate::runtime::streams::StreamData;
+use crate::runtime::types::{DataType, Stream};
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Pictures are analysed at most this many pixels across or down, keeping every nth pixel
+pub const MAX_ANALYSIS_SIZE: usize = 256;
+
+/// How far apart (in RGB from 0 to 1) two palette colours have to be to both be kept
+const DISTINCT_COLORS: f32 = 0.2;
+
+/// Levels per channel when counting colours, so 8 × 8 × 8 buckets
+const COLOR_LEVELS: usize = 8;
+
+static WATCHED: OnceLock<Mutex<HashMap<String, VisionWatch>>> = OnceLock::new();
+
+/// Streams `Vision.analyze()` was given, by the name their results are published under
+pub fn vision_watches() -> &'static Mutex<HashMap<String, VisionWatch>> {
+    WATCHED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn vision_error(kind: crate::errors::ErrorKind, message: impl Into<String>) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(kind, message)
+        .with_suggestion("Try: Vision.analyze(\"photo.png\", colors: 5, regions: 4)")
+        .with_suggestion("Streams work too: Vision.analyze(Graphics.capture_screen(0))")
+}
+
+/// Pixels with `channels` values each (1 for grey, 3 for RGB, 4 for RGBA), all from 0 to 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize,
+    pub data: Vec<f32>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, channels: usize, data: Vec<f32>) -> crate::Result<Self> {
+        if width == 0 || height == 0 || !matches!(channels, 1 | 3 | 4) || data.len() != width * height * channels {
+            return Err(vision_error(
+                crate::errors::ErrorKind::InvalidStreamFormat,
+                format!("👁️ {} values don't make a {}×{} picture with {} channel(s)", data.len(), width, height, channels)
+            ));
+        }
+        Ok(Self { width, height, channels, data })
+    }
+
+    /// A texture object like `{ width: 2, height: 1, data: [0, 1] }`, with the channels worked
+    /// out from how much data there is; values above 1 are taken to run to 255
+    pub fn from_fields(fields: &HashMap<String, Value>) -> crate::Result<Self> {
+        let size = |key: &str| fields.get(key).and_then(|v| v.as_number()).filter(|&n| n >= 1.0).map(|n| n as usize);
+        let (Some(width), Some(height), Some(Value::Array(values))) = (size("width"), size("height"), fields.get("data")) else {
+            return Err(vision_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                "👁️ A picture needs a width, a height and a list of pixel data"
+            ));
+        };
+        let mut data = values.iter()
+            .map(|value| value.as_number().map(|v| v as f32))
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(|| vision_error(crate::errors::ErrorKind::TypeMismatch, "👁️ Pixel data should all be numbers"))?;
+        if data.iter().any(|&v| v > 1.0) {
+            data.iter_mut().for_each(|v| *v /= 255.0);
+        }
+        let channels = data.len() / (width * height).max(1);
+        Self::new(width, height, channels, data)
+    }
+
+    /// The frame a Visual stream holds, sized by its `width`, `height` and `channels` metadata
+    pub fn from_stream(stream: &StreamData) -> Option<Self> {
+        let metadata = |key: &str| stream.metadata.get(key).and_then(|v| v.as_number()).map(|n| n as usize);
+        let (width, height) = (metadata("width")?, metadata("height")?);
+        let data = stream.buffer.iter().copied().collect();
+        Self::new(width, height, metadata("channels").unwrap_or(1), data).ok()
+    }
+
+    pub fn load_png(path: &str) -> crate::Result<Self> {
+        let file = std::fs::File::open(path).map_err(|error| vision_error(
+            crate::errors::ErrorKind::FileNotFound,
+            format!("👁️ Couldn't open '{}': {}", path, error)
+        ))?;
+        let unreadable = |error: png::DecodingError| vision_error(
+            crate::errors::ErrorKind::InvalidStreamFormat,
+            format!("👁️ '{}' isn't a PNG Synthesis can read: {}", path, error)
+        );
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(unreadable)?;
+        let mut bytes = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut bytes).map_err(unreadable)?;
+        let channels = match info.color_type {
+            png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => 1,
+            _ => 3,
+        };
+        // Alpha doesn't change what the picture looks like to analyse, so it's dropped
+        let stride = info.color_type.samples();
+        let data = bytes[..info.buffer_size()]
+            .chunks_exact(stride)
+            .flat_map(|pixel| pixel[..channels].iter().map(|&v| v as f32 / 255.0))
+            .collect();
+        Self::new(info.width as usize, info.height as usize, channels, data)
+    }
+
+    /// Every nth pixel, so neither side is more than `max` pixels
+    pub fn shrink(&self, max: usize) -> Self {
+        let step = self.width.max(self.height).div_ceil(max.max(1)).max(1);
+        if step == 1 {
+            return self.clone();
+        }
+        let mut data = Vec::new();
+        for y in (0..self.height).step_by(step) {
+            for x in (0..self.width).step_by(step) {
+                let index = (y * self.width + x) * self.channels;
+                data.extend_from_slice(&self.data[index..index + self.channels]);
+            }
+        }
+        Self { width: self.width.div_ceil(step), height: self.height.div_ceil(step), channels: self.channels, data }
+    }
+
+    pub fn rgb(&self, x: usize, y: usize) -> [f32; 3] {
+        let index = (y * self.width + x) * self.channels;
+        match self.channels {
+            1 => [self.data[index]; 3],
+            _ => [self.data[index], self.data[index + 1], self.data[index + 2]],
+        }
+    }
+
+    /// Perceived brightness from 0 to 1 (Rec. 709 weights)
+    pub fn brightness(&self, x: usize, y: usize) -> f32 {
+        let [r, g, b] = self.rgb(x, y);
+        (0.2126 * r + 0.7152 * g + 0.0722 * b).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalysisSettings {
+    /// Most colours in the palette
+    pub colors: usize,
+    /// Regions across and down, so `regions` × `regions` of them
+    pub regions: usize,
+    /// Brightness histogram bins per region
+    pub bins: usize,
+    /// How strong an edge has to be, from 0 to 1, to count towards the edge density
+    pub threshold: f32,
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        Self { colors: 5, regions: 4, bins: 8, threshold: 0.25 }
+    }
+}
+
+/// One of the palette's colours and the share of the picture closest to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swatch {
+    pub color: [f32; 3],
+    pub weight: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub column: usize,
+    pub row: usize,
+    pub brightness: f32,
+    /// The share of the region's pixels in each brightness band, darkest first
+    pub histogram: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Analysis {
+    pub brightness: f32,
+    /// Most common colours first
+    pub palette: Vec<Swatch>,
+    /// The share of the picture on an edge, from 0 for flat colour to 1
+    pub edges: f32,
+    /// Row by row from the top left
+    pub regions: Vec<Region>,
+}
+
+/// Measure a picture's brightness, dominant colours, edge density and regions
+pub fn analyze(image: &Image, settings: &AnalysisSettings) -> Analysis {
+    let image = image.shrink(MAX_ANALYSIS_SIZE);
+    let (width, height) = (image.width, image.height);
+    let brightness: Vec<f32> = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| image.brightness(x, y))
+        .collect();
+
+    Analysis {
+        brightness: brightness.iter().sum::<f32>() / brightness.len() as f32,
+        palette: palette(&image, settings.colors),
+        edges: edge_density(&brightness, width, height, settings.threshold),
+        regions: regions(&brightness, width, height, settings.regions, settings.bins),
+    }
+}
+
+/// The most common colours, skipping any too close to one already picked, each weighted by
+/// how much of the picture is nearest to it
+fn palette(image: &Image, colors: usize) -> Vec<Swatch> {
+    let level = |v: f32| ((v.clamp(0.0, 1.0) * COLOR_LEVELS as f32) as usize).min(COLOR_LEVELS - 1);
+    let mut buckets = vec![([0.0f32; 3], 0usize); COLOR_LEVELS.pow(3)];
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let rgb = image.rgb(x, y);
+            let (sum, count) = &mut buckets[(level(rgb[0]) * COLOR_LEVELS + level(rgb[1])) * COLOR_LEVELS + level(rgb[2])];
+            sum.iter_mut().zip(rgb).for_each(|(total, v)| *total += v);
+            *count += 1;
+        }
+    }
+    let mut buckets: Vec<([f32; 3], usize)> = buckets.into_iter()
+        .filter(|&(_, count)| count > 0)
+        .map(|(sum, count)| (sum.map(|total| total / count as f32), count))
+        .collect();
+    // Ties go to the darker bucket so the palette doesn't depend on the sort
+    buckets.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.iter().sum::<f32>().total_cmp(&b.0.iter().sum::<f32>())));
+
+    let distance = |a: [f32; 3], b: [f32; 3]| a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt();
+    let mut palette: Vec<Swatch> = Vec::new();
+    for &(color, _) in &buckets {
+        if palette.len() == colors {
+            break;
+        }
+        if palette.iter().all(|swatch| distance(swatch.color, color) >= DISTINCT_COLORS) {
+            palette.push(Swatch { color, weight: 0.0 });
+        }
+    }
+    let total = (image.width * image.height) as f32;
+    for &(color, count) in &buckets {
+        let nearest = palette.iter_mut().min_by(|a, b| distance(a.color, color).total_cmp(&distance(b.color, color)));
+        if let Some(swatch) = nearest {
+            swatch.weight += count as f32 / total;
+        }
+    }
+    palette.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    palette
+}
+
+/// The share of pixels whose Sobel gradient is over `threshold`, where a hard black-to-white
+/// edge measures 1
+fn edge_density(brightness: &[f32], width: usize, height: usize, threshold: f32) -> f32 {
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    let at = |x: usize, y: usize| brightness[y * width + x];
+    let mut edges = 0;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1) - 2.0 * at(x - 1, y) - at(x - 1, y + 1);
+            let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1) - 2.0 * at(x, y - 1) - at(x + 1, y - 1);
+            if (gx * gx + gy * gy).sqrt() / 4.0 > threshold {
+                edges += 1;
+            }
+        }
+    }
+    edges as f32 / ((width - 2) * (height - 2)) as f32
+}
+
+fn regions(brightness: &[f32], width: usize, height: usize, grid: usize, bins: usize) -> Vec<Region> {
+    let (columns, rows) = (grid.clamp(1, width), grid.clamp(1, height));
+    let bins = bins.max(1);
+    let mut regions = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (left, right) = (column * width / columns, (column + 1) * width / columns);
+            let (top, bottom) = (row * height / rows, (row + 1) * height / rows);
+            let mut histogram = vec![0.0; bins];
+            let mut sum = 0.0;
+            for y in top..bottom {
+                for &value in &brightness[y * width + left..y * width + right] {
+                    histogram[((value * bins as f32) as usize).min(bins - 1)] += 1.0;
+                    sum += value;
+                }
+            }
+            let pixels = ((right - left) * (bottom - top)) as f32;
+            histogram.iter_mut().for_each(|count| *count /= pixels);
+            regions.push(Region { column, row, brightness: sum / pixels, histogram });
+        }
+    }
+    regions
+}
+
+impl Analysis {
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.fields())
+    }
+
+    fn fields(&self) -> HashMap<String, Value> {
+        let number = |v: f32| Value::Float(v as f64);
+        let palette = self.palette.iter()
+            .map(|swatch| {
+                let [r, g, b] = swatch.color;
+                let byte = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+                let mut fields = HashMap::new();
+                fields.insert("r".to_string(), number(r));
+                fields.insert("g".to_string(), number(g));
+                fields.insert("b".to_string(), number(b));
+                fields.insert("hex".to_string(), Value::String(format!("#{:02x}{:02x}{:02x}", byte(r), byte(g), byte(b))));
+                fields.insert("weight".to_string(), number(swatch.weight));
+                Value::Object(fields)
+            })
+            .collect();
+        let regions = self.regions.iter()
+            .map(|region| {
+                let mut fields = HashMap::new();
+                fields.insert("column".to_string(), Value::Integer(region.column as i64));
+                fields.insert("row".to_string(), Value::Integer(region.row as i64));
+                fields.insert("brightness".to_string(), number(region.brightness));
+                fields.insert("histogram".to_string(), Value::Array(region.histogram.iter().map(|&v| number(v)).collect()));
+                Value::Object(fields)
+            })
+            .collect();
+
+        let mut result = HashMap::new();
+        result.insert("type".to_string(), Value::String("vision".to_string()));
+        result.insert("brightness".to_string(), number(self.brightness));
+        result.insert("edges".to_string(), number(self.edges));
+        result.insert("palette".to_string(), Value::Array(palette));
+        result.insert("regions".to_string(), Value::Array(regions));
+        result
+    }
+
+    /// The palette as one row of r, g, b and weight per colour
+    pub fn palette_frame(&self) -> Vec<f32> {
+        self.palette.iter().flat_map(|swatch| [swatch.color[0], swatch.color[1], swatch.color[2], swatch.weight]).collect()
+    }
+
+    /// Regions across the picture
+    pub fn columns(&self) -> usize {
+        self.regions.iter().filter(|region| region.row == 0).count().max(1)
+    }
+
+    /// Each region's brightness, row by row
+    pub fn region_frame(&self) -> Vec<f32> {
+        self.regions.iter().map(|region| region.brightness).collect()
+    }
+}
+
+/// A Visual stream analysed every frame, with the last results for scripts to read
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisionWatch {
+    pub source: String,
+    pub settings: AnalysisSettings,
+    pub latest: Option<Analysis>,
+}
+
+/// `Vision.analyze(picture, colors: 5, regions: 4, bins: 8, threshold: 0.25)`: how bright a
+/// picture is, its dominant colours, how much of it is edges and the brightness of a grid of
+/// regions. The picture can be a PNG file, a texture object or a Visual stream like a screen
+/// capture; a stream is analysed every frame into `<name>.brightness`, `<name>.edges`,
+/// `<name>.palette` and `<name>.regions` streams, named "vision" unless `name:` says otherwise
+pub fn vision_analyze(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Vision.analyze", &["colors", "regions", "bins", "threshold", "name"])?;
+    let count = |key: &str, default: usize| named.get(key).and_then(|v| v.as_number()).map_or(default, |n| n.max(1.0) as usize);
+    let defaults = AnalysisSettings::default();
+    let settings = AnalysisSettings {
+        colors: count("colors", defaults.colors).min(16),
+        regions: count("regions", defaults.regions).min(32),
+        bins: count("bins", defaults.bins).min(64),
+        threshold: named.get("threshold").and_then(|v| v.as_number()).map_or(defaults.threshold, |v| v.clamp(0.0, 1.0) as f32),
+    };
+
+    let image = match args.first() {
+        Some(Value::String(path)) => Image::load_png(path)?,
+        // An image from Assets.load() carries its path but not its pixels
+        Some(Value::Object(fields)) if !fields.contains_key("data") && fields.contains_key("path") => match fields.get("path") {
+            Some(Value::String(path)) => Image::load_png(path)?,
+            _ => Image::from_fields(fields)?,
+        },
+        Some(Value::Object(fields)) => Image::from_fields(fields)?,
+        Some(Value::Stream(stream)) => return watch(&stream.name, named, settings),
+        _ => return Err(vision_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "👁️ Vision.analyze() needs a picture: a PNG file, a texture or a Visual stream"
+        )),
+    };
+    Ok(analyze(&image, &settings).to_value())
+}
+
+/// Analyse `source` every frame from now on, returning the last results and the streams
+fn watch(source: &str, options: &HashMap<String, Value>, settings: AnalysisSettings) -> crate::Result<Value> {
+    let name = match options.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => "vision".to_string(),
+    };
+    let mut watching = vision_watches().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let watch = watching.entry(name.clone()).or_insert_with(|| VisionWatch {
+        source: source.to_string(),
+        settings,
+        latest: None,
+    });
+    // Setup that reruns each frame keeps the last results unless it points somewhere else
+    if watch.source != source {
+        watch.latest = None;
+    }
+    watch.source = source.to_string();
+    watch.settings = settings;
+
+    let stream = |suffix: &str, data_type: DataType| Value::Stream(Stream {
+        name: format!("{}.{}", name, suffix),
+        data_type,
+        sample_rate: None,
+    });
+    let mut result = watch.latest.clone().unwrap_or_default().fields();
+    result.insert("name".to_string(), Value::String(name.clone()));
+    let mut streams = HashMap::new();
+    streams.insert("brightness".to_string(), stream("brightness", DataType::Control));
+    streams.insert("edges".to_string(), stream("edges", DataType::Control));
+    streams.insert("palette".to_string(), stream("palette", DataType::Generic));
+    streams.insert("regions".to_string(), stream("regions", DataType::Generic));
+    result.insert("streams".to_string(), Value::Object(streams));
+    Ok(Value::Object(result))
+}
@@ -1,9 +1,12 @@
+use crate::runtime::web_server::{serve_web_routes, WebRoute};
 use crate::runtime::Value;
+use std::collections::{BTreeMap, HashMap};
 
-pub fn export_webapp(args: &[Value]) -> crate::Result<Value> {
+pub fn export_webapp(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Web.export_webapp", &[])?;
     println!("Web.export_webapp called with {} args", args.len());
     
-    if let Some(Value::String(name)) = args.get(0) {
+    if let Some(Value::String(name)) = args.first() {
         println!("Exporting webapp: {}", name);
         Ok(Value::Boolean(true))
     } else {
@@ -14,4 +17,61 @@ pub fn export_webapp(args: &[Value]) -> crate::Result<Value> {
         .with_suggestion("Try: Web.export_webapp(\"MyAudioVisualizer\")")
         .with_suggestion("Use a text name to identify your web app"))
     }
-}
\ No newline at end of file
+}
+
+/// `Web.serve(8080, { level: "level", vote: { get: "votes", post: "choice" } }, page: "dashboard.html")`
+/// answers GETs with script values as JSON and lets POSTs set the listed variables; `page:` is served at `/`
+pub fn web_serve(args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+    crate::modules::check_named(named, "Web.serve", &["page"])?;
+    let port = match args.first() {
+        Some(Value::Integer(port)) if (0..=65535).contains(port) => *port as u16,
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🌐 Web.serve() needs a port number and the routes to serve"
+        )
+        .with_suggestion("Try: Web.serve(8080, { level: \"level\" })")),
+    };
+    let spec = match args.get(1) {
+        Some(Value::Object(routes)) => routes.clone(),
+        _ => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🌐 Web.serve() needs its routes as an object of paths and variable names"
+        )
+        .with_suggestion("Try: Web.serve(8080, { level: \"level\", vote: { get: \"votes\", post: \"choice\" } })")),
+    };
+
+    let mut routes = BTreeMap::new();
+    for (path, route) in &spec {
+        let route = WebRoute::from_value(route).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🌐 The route {} should name the variables it serves, not {}", path, route)
+        )
+        .with_suggestion("A route is a variable name, a list of names, or { get: \"name\", post: \"name\" }"))?;
+        routes.insert(format!("/{}", path.trim_matches('/')), route);
+    }
+
+    let page = match named.get("page") {
+        Some(Value::String(path)) => match crate::runtime::asset_manager().load_blocking(path) {
+            Some(asset) if asset.is_ready() => Some(String::from_utf8_lossy(&asset.data).into_owned()),
+            _ => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::FileNotFound,
+                format!("🌐 Couldn't read the page '{}'", path)
+            )
+            .with_suggestion("Check the path is relative to your sketch, like \"web/index.html\"")),
+        },
+        _ => None,
+    };
+
+    let paths: Vec<Value> = routes.keys().map(|path| Value::String(path.clone())).collect();
+    let address = serve_web_routes(port, routes, page).map_err(|error| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::StreamConnectionError,
+        format!("🌐 Couldn't serve on port {}: {}", port, error)
+    )
+    .with_suggestion("Another program may be using that port; try a different one"))?;
+
+    let mut server = HashMap::new();
+    server.insert("port".to_string(), Value::Integer(address.port() as i64));
+    server.insert("url".to_string(), Value::String(format!("http://localhost:{}", address.port())));
+    server.insert("routes".to_string(), Value::Array(paths));
+    Ok(Value::Object(server))
+}
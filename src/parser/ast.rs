@@ -1,3 +1,4 @@
+use crate::parser::lexer::Span;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,12 +14,15 @@ pub enum Item {
     Function(FunctionDef),
     Class(ClassDef),
     Struct(StructDef),
+    Synth(SynthDef),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImportItem {
     pub module: String,
     pub items: Option<Vec<String>>,
+    /// `import Graphics as G`: the name the module is used under instead
+    pub alias: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -29,9 +33,15 @@ pub struct LoopBlock {
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionDef {
     pub name: String,
+    /// `func first<T>(items: List<T>) -> T`: names that stand for whatever type a call passes
+    pub type_params: Vec<String>,
     pub parameters: Vec<Parameter>,
+    /// `...rest` after the parameters: any further positional arguments, as a list
+    pub rest: Option<String>,
     pub return_type: Option<TypeAnnotation>,
     pub body: Vec<Statement>,
+    /// The `///` lines just above `func`, joined with newlines
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -52,6 +62,16 @@ pub struct ClassDef {
 pub struct StructDef {
     pub name: String,
     pub fields: Vec<Field>,
+    /// Usually operators like `add` and `compare`; structs stay plain values either way
+    pub methods: Vec<FunctionDef>,
+}
+
+/// `synth pluck(note = "A3", decay = 0.4) { ... }`: a sound compiled to a voice graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthDef {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub body: Vec<Statement>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -81,6 +101,12 @@ pub enum Statement {
         name: String,
         value: Expression,
     },
+    /// `player.position.x = 10`: `path` holds the fields after `target`, outermost first
+    FieldAssignment {
+        target: String,
+        path: Vec<String>,
+        value: Expression,
+    },
     Expression(Expression),
     If {
         condition: Expression,
@@ -99,6 +125,11 @@ pub enum Statement {
         duration: Expression,
         body: Vec<Statement>,
     },
+    /// `async { ... }`: starts a new run of `body` each time it's reached, which can `wait()`
+    /// while the rest of the script carries on
+    Async {
+        body: Vec<Statement>,
+    },
     While {
         condition: Expression,
         body: Vec<Statement>,
@@ -108,19 +139,57 @@ pub enum Statement {
         iterable: Expression,
         body: Vec<Statement>,
     },
+    /// `try { ... } catch (err) { ... }`: an error in `body` runs `handler` instead of stopping the
+    /// script, with `error` naming the variable that holds what went wrong
+    Try {
+        body: Vec<Statement>,
+        error: Option<String>,
+        handler: Vec<Statement>,
+    },
     Let {
         name: String,
         type_annotation: Option<TypeAnnotation>,
         value: Option<Expression>,
     },
+    /// `const SAMPLE_RATE = 48000`: set once, and an error to set again. `span` is where it's
+    /// declared, so the same declaration can run again (in a loop, say) but no other can
+    Const {
+        name: String,
+        value: Expression,
+        span: Span,
+    },
+    /// `let [low, mid, high] = bands` or `let {left, right} = channels`
+    LetDestructure {
+        pattern: Destructure,
+        value: Expression,
+    },
     Return(Option<Expression>),
     Break,
     Continue,
+    Capabilities(Vec<CapabilityRequest>),
+}
+
+/// One entry of `capabilities { network, filesystem("assets/") }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapabilityRequest {
+    pub name: String,
+    pub scope: Option<String>,
+}
+
+/// The left side of a destructuring `let`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destructure {
+    /// Binds by position; `None` is a `_` that skips one
+    Array(Vec<Option<String>>),
+    /// Binds fields as (field, variable), so `{left: l}` puts `left` in `l`
+    Object(Vec<(String, String)>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchArm {
     pub pattern: Pattern,
+    /// `pattern if condition => { ... }`, checked once the pattern's names are bound
+    pub guard: Option<Expression>,
     pub body: Vec<Statement>,
 }
 
@@ -133,6 +202,21 @@ pub enum Pattern {
         fields: Option<Vec<Pattern>>,
     },
     Wildcard,
+    /// A name inside a list or object pattern, bound to whatever is there
+    Binding(String),
+    /// `[low, _, high]`; with `...` at the end, longer lists match too
+    Array {
+        items: Vec<Pattern>,
+        rest: bool,
+    },
+    /// `{ kind: "note", velocity }`; fields not mentioned are ignored
+    Object(Vec<(String, Pattern)>),
+    /// `0..10` leaves out the end, `0..=10` includes it
+    Range {
+        start: Literal,
+        end: Literal,
+        inclusive: bool,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -150,6 +234,11 @@ pub enum Expression {
         op: BinaryOperator,
         right: Box<Expression>,
     },
+    /// `-x`, `!flag` or `not flag`
+    UnaryOp {
+        op: UnaryOperator,
+        operand: Box<Expression>,
+    },
     Block {
         fields: HashMap<String, Expression>,
     },
@@ -165,6 +254,8 @@ pub enum Expression {
         left: Box<Expression>,
         right: Box<Expression>,
     },
+    /// `...list` in a list literal or a call's arguments, standing for all of its items
+    Spread(Box<Expression>),
     StreamBranch {
         stream: Box<Expression>,
         count: u8,
@@ -192,6 +283,13 @@ pub enum Expression {
         method: String,
         args: Vec<Expression>,
         named_args: HashMap<String, Expression>,
+        /// Written `?.`: a null object gives null rather than reaching for the field
+        optional: bool,
+    },
+    /// `Point { x: 1, y: 2 }`: fields left out take the defaults from the `struct` definition
+    StructLiteral {
+        name: String,
+        fields: HashMap<String, Expression>,
     },
     InterpolatedString(Vec<StringPart>),
     ConditionalExpression {
@@ -218,10 +316,20 @@ pub enum StringPart {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Integer(i64),
+    /// `0xFF00AA` or `0b1010`, with the radix it was written in so formatting can keep it
+    BasedInteger(i64, u32),
     Float(f64),
     Percentage(f64),
     String(String),
     Boolean(bool),
+    /// `#ff00aa`, as red, green, blue and alpha
+    Color([u8; 4]),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -238,6 +346,8 @@ pub enum BinaryOperator {
     GreaterThanOrEqual,
     LogicalAnd,
     LogicalOr,
+    /// `??`: the left side unless it's null, then the right
+    NullCoalesce,
     Pipe,
     BiDirectionalPipe,
 }
\ No newline at end of file
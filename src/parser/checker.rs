@@ -0,0 +1,523 @@
+/// Checks `let x: Number = ...` annotations before a script runs
+///
+/// Synthesis stays dynamic: values without annotations are never questioned, and anything the
+/// checker can't work out from the code alone (a module call, a stream, a pipe) is taken on
+/// trust. Only an annotation that disagrees with a type it can see for certain is a mistake, and
+/// every one is reported at once rather than the show stopping at the first.
+use crate::errors::{ErrorKind, SourceLocation, SynthesisError};
+use crate::parser::ast::*;
+use crate::parser::lexer::{Span, Token};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// What the checker knows about a value's type before it runs
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaticType {
+    Integer,
+    Float,
+    /// A number that could be either
+    Number,
+    Text,
+    Boolean,
+    List(Box<StaticType>),
+    Object,
+    Range,
+    Function,
+    /// A number with a unit, like `500.ms` or `440.Hz`
+    Unit,
+    /// `#ff00aa`
+    Color,
+    /// A struct or class the script defines
+    Named(String),
+    /// Could be anything; always fits
+    Unknown,
+}
+
+impl StaticType {
+    /// The type an annotation names
+    pub fn from_annotation(annotation: &TypeAnnotation) -> Self {
+        match annotation {
+            TypeAnnotation::Simple(name) => match name.as_str() {
+                "Integer" | "Int" => StaticType::Integer,
+                "Float" => StaticType::Float,
+                "Number" => StaticType::Number,
+                "Text" | "String" => StaticType::Text,
+                "Boolean" | "Bool" => StaticType::Boolean,
+                "List" | "Array" => StaticType::List(Box::new(StaticType::Unknown)),
+                "Object" => StaticType::Object,
+                "Range" => StaticType::Range,
+                "Function" => StaticType::Function,
+                "Color" | "Colour" => StaticType::Color,
+                "Any" => StaticType::Unknown,
+                _ => StaticType::Named(name.clone()),
+            },
+            TypeAnnotation::Generic { base, params } if matches!(base.as_str(), "List" | "Array") => {
+                StaticType::List(Box::new(params.first().map(Self::from_annotation).unwrap_or(StaticType::Unknown)))
+            }
+            TypeAnnotation::Generic { base, .. } => StaticType::Named(base.clone()),
+            TypeAnnotation::Array(item) => StaticType::List(Box::new(Self::from_annotation(item))),
+            TypeAnnotation::Function { .. } => StaticType::Function,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, StaticType::Integer | StaticType::Float | StaticType::Number)
+    }
+
+    /// Whether a value of this type can go where `expected` is asked for
+    pub fn fits(&self, expected: &StaticType) -> bool {
+        match (self, expected) {
+            (StaticType::Unknown, _) | (_, StaticType::Unknown) => true,
+            // Whole numbers widen to floats, and a number with a unit is still a number
+            (StaticType::Integer, StaticType::Float) => true,
+            (found, StaticType::Number) if found.is_numeric() => true,
+            (StaticType::Number, StaticType::Integer | StaticType::Float) => true,
+            (StaticType::Unit, StaticType::Number | StaticType::Float) => true,
+            // A colour is also its 0xRRGGBB number
+            (StaticType::Color, StaticType::Number | StaticType::Integer) => true,
+            (StaticType::List(found), StaticType::List(expected)) => found.fits(expected),
+            (found, expected) => found == expected,
+        }
+    }
+
+    /// The type either of two values could have
+    fn join(self, other: StaticType) -> StaticType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (a, b) if a.is_numeric() && b.is_numeric() => StaticType::Number,
+            _ => StaticType::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for StaticType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaticType::Integer => write!(f, "Integer"),
+            StaticType::Float => write!(f, "Float"),
+            StaticType::Number => write!(f, "Number"),
+            StaticType::Text => write!(f, "Text"),
+            StaticType::Boolean => write!(f, "Boolean"),
+            StaticType::List(item) if **item == StaticType::Unknown => write!(f, "List"),
+            StaticType::List(item) => write!(f, "List<{}>", item),
+            StaticType::Object => write!(f, "Object"),
+            StaticType::Range => write!(f, "Range"),
+            StaticType::Function => write!(f, "Function"),
+            StaticType::Unit => write!(f, "a number with a unit"),
+            StaticType::Color => write!(f, "Color"),
+            StaticType::Named(name) => write!(f, "{}", name),
+            StaticType::Unknown => write!(f, "Any"),
+        }
+    }
+}
+
+/// A user function's annotations, kept whole so each call of a generic one can work out what its
+/// type parameters stand for
+#[derive(Debug, Clone)]
+struct Signature {
+    type_params: Vec<String>,
+    params: Vec<Option<TypeAnnotation>>,
+    returns: Option<TypeAnnotation>,
+}
+
+pub struct TypeChecker {
+    /// Innermost last; each function body gets its own
+    scopes: Vec<HashMap<String, StaticType>>,
+    /// The user functions, by name
+    functions: HashMap<String, Signature>,
+    /// Type parameters of the function being checked; inside it they could be anything
+    type_params: HashSet<String>,
+    /// The structs and classes the script defines
+    types: HashSet<String>,
+    /// Where each annotated `let` is, by name and in order, so errors can point at it
+    let_sites: HashMap<String, VecDeque<SourceLocation>>,
+    errors: Vec<SynthesisError>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            type_params: HashSet::new(),
+            types: HashSet::new(),
+            let_sites: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// A checker whose errors point at the line and column `lexer::tokenize_with_spans` recorded
+    pub fn with_spans(tokens: &[Token], spans: &[Span], filename: &str) -> Self {
+        let mut checker = Self::new();
+        for (index, window) in tokens.windows(3).enumerate() {
+            if let [Token::Let, Token::Identifier(name), Token::Colon] = window {
+                if let Some(span) = spans.get(index) {
+                    checker.let_sites.entry(name.clone()).or_default().push_back(SourceLocation {
+                        line: span.line,
+                        column: span.column,
+                        filename: filename.to_string(),
+                    });
+                }
+            }
+        }
+        checker
+    }
+
+    /// Every annotation the program's values don't match, in the order they appear
+    pub fn check(mut self, program: &Program) -> Vec<SynthesisError> {
+        for item in &program.items {
+            match item {
+                Item::Function(function) => self.declare_function(function),
+                Item::Class(ClassDef { name, .. }) | Item::Struct(StructDef { name, .. }) => {
+                    self.types.insert(name.clone());
+                }
+                _ => {}
+            }
+        }
+        for item in &program.items {
+            match item {
+                Item::Import(_) => {}
+                Item::Statement(statement) => self.check_statement(statement),
+                Item::Loop(LoopBlock { body }) => self.check_block(body),
+                Item::Function(function) => self.check_function(function),
+                Item::Class(ClassDef { methods, .. }) | Item::Struct(StructDef { methods, .. }) => {
+                    methods.iter().for_each(|method| self.check_function(method));
+                }
+                Item::Synth(synth) => {
+                    let scope = self.parameter_scope(&synth.parameters);
+                    self.scopes.push(scope);
+                    self.check_block(&synth.body);
+                    self.scopes.pop();
+                }
+            }
+        }
+        self.errors
+    }
+
+    fn declare_function(&mut self, function: &FunctionDef) {
+        self.functions.insert(function.name.clone(), Signature {
+            type_params: function.type_params.clone(),
+            params: function.parameters.iter().map(|parameter| parameter.type_annotation.clone()).collect(),
+            returns: function.return_type.clone(),
+        });
+    }
+
+    fn parameter_scope(&mut self, parameters: &[Parameter]) -> HashMap<String, StaticType> {
+        parameters.iter().map(|parameter| {
+            let declared = parameter.type_annotation.as_ref().map(|annotation| self.declared(annotation));
+            let default = parameter.default_value.as_ref().map(|value| self.infer(value));
+            (parameter.name.clone(), declared.or(default).unwrap_or(StaticType::Unknown))
+        }).collect()
+    }
+
+    fn check_function(&mut self, function: &FunctionDef) {
+        let outer = std::mem::replace(&mut self.type_params, function.type_params.iter().cloned().collect());
+        let mut scope = self.parameter_scope(&function.parameters);
+        if let Some(rest) = &function.rest {
+            scope.insert(rest.clone(), StaticType::List(Box::new(StaticType::Unknown)));
+        }
+        self.scopes.push(scope);
+        self.check_block(&function.body);
+        self.scopes.pop();
+        self.type_params = outer;
+    }
+
+    fn check_block(&mut self, body: &[Statement]) {
+        body.iter().for_each(|statement| self.check_statement(statement));
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let { name, type_annotation: Some(annotation), value } => {
+                let declared = self.declared(annotation);
+                let location = self.let_sites.get_mut(name).and_then(|sites| sites.pop_front());
+                if let Some(value) = value {
+                    let found = self.infer(value);
+                    if !found.fits(&declared) {
+                        self.mismatch(name, &declared, &found, location);
+                    }
+                }
+                self.bind(name, declared);
+            }
+            Statement::Let { name, type_annotation: None, value } => {
+                let found = value.as_ref().map(|value| self.infer(value)).unwrap_or(StaticType::Unknown);
+                self.bind(name, found);
+            }
+            Statement::Const { name, value, .. } => {
+                let found = self.infer(value);
+                self.bind(name, found);
+            }
+            Statement::Assignment { name, value } => {
+                let found = self.infer(value);
+                // A variable that changes type is one nothing more can be said about
+                if let Some(known) = self.lookup(name).cloned() {
+                    if !found.fits(&known) {
+                        self.bind(name, StaticType::Unknown);
+                    }
+                } else {
+                    self.bind(name, found);
+                }
+            }
+            Statement::LetDestructure { pattern, value } => {
+                self.infer(value);
+                match pattern {
+                    Destructure::Array(names) => names.iter().flatten().for_each(|name| self.bind(name, StaticType::Unknown)),
+                    Destructure::Object(bindings) => bindings.iter().for_each(|(_, name)| self.bind(name, StaticType::Unknown)),
+                }
+            }
+            Statement::FieldAssignment { value, .. } | Statement::Expression(value) | Statement::Return(Some(value)) => {
+                self.infer(value);
+            }
+            Statement::If { condition, then_branch, else_branch } => {
+                self.infer(condition);
+                self.check_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.check_block(else_branch);
+                }
+            }
+            Statement::Match { expression, arms } => {
+                self.infer(expression);
+                for arm in arms {
+                    self.check_block(&arm.body);
+                }
+            }
+            Statement::Every { duration: condition, body }
+            | Statement::After { duration: condition, body }
+            | Statement::While { condition, body } => {
+                self.infer(condition);
+                self.check_block(body);
+            }
+            Statement::For { variable, iterable, body } => {
+                let item = match self.infer(iterable) {
+                    StaticType::List(item) => *item,
+                    StaticType::Range => StaticType::Integer,
+                    _ => StaticType::Unknown,
+                };
+                self.bind(variable, item);
+                self.check_block(body);
+            }
+            Statement::Async { body } => self.check_block(body),
+            Statement::Try { body, error, handler } => {
+                self.check_block(body);
+                if let Some(error) = error {
+                    self.bind(error, StaticType::Unknown);
+                }
+                self.check_block(handler);
+            }
+            Statement::Return(None) | Statement::Break | Statement::Continue | Statement::Capabilities(_) => {}
+        }
+    }
+
+    fn bind(&mut self, name: &str, found: StaticType) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), found);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&StaticType> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn mismatch(&mut self, name: &str, declared: &StaticType, found: &StaticType, location: Option<SourceLocation>) {
+        let mut error = SynthesisError::new(
+            ErrorKind::TypeMismatch,
+            format!("'{}' is declared as {}, but it's given {}", name, declared, found)
+        )
+        .with_suggestion(format!("Change the annotation to let {}: {}, or leave it off", name, found))
+        .with_suggestion(format!("Or give it a {} value", declared))
+        .with_docs("https://synthesis-lang.org/docs/types");
+        if let Some(location) = location {
+            error = error.with_location(location);
+        }
+        self.errors.push(error);
+    }
+
+    /// The type an expression has, as far as can be told without running it
+    pub fn infer(&mut self, expression: &Expression) -> StaticType {
+        match expression {
+            Expression::Literal(Literal::Integer(_) | Literal::BasedInteger(..)) => StaticType::Integer,
+            Expression::Literal(Literal::Float(_) | Literal::Percentage(_)) => StaticType::Float,
+            Expression::Literal(Literal::String(_)) | Expression::InterpolatedString(_) => StaticType::Text,
+            Expression::Literal(Literal::Boolean(_)) => StaticType::Boolean,
+            Expression::Literal(Literal::Color(_)) => StaticType::Color,
+            Expression::Identifier(name) => match self.lookup(name) {
+                Some(found) => found.clone(),
+                None if self.functions.contains_key(name) => StaticType::Function,
+                None => StaticType::Unknown,
+            },
+            Expression::FunctionCall { module: None, name, args, .. } => self.infer_call(name, args),
+            Expression::BinaryOp { left, op, right } => {
+                let (left, right) = (self.infer(left), self.infer(right));
+                infer_binary(left, op, right)
+            }
+            Expression::UnaryOp { op: UnaryOperator::Not, .. } => StaticType::Boolean,
+            Expression::UnaryOp { op: UnaryOperator::Negate, operand } => match self.infer(operand) {
+                found @ (StaticType::Integer | StaticType::Float | StaticType::Number | StaticType::Unit) => found,
+                _ => StaticType::Unknown,
+            },
+            Expression::Block { .. } => StaticType::Object,
+            Expression::StructLiteral { name, .. } => self.known(StaticType::Named(name.clone())),
+            Expression::ArrayAccess { array, .. } => match self.infer(array) {
+                StaticType::List(item) => *item,
+                _ => StaticType::Unknown,
+            },
+            Expression::ArrayLiteral(items) => {
+                let mut item = None;
+                for expression in items {
+                    let found = match expression {
+                        Expression::Spread(inner) => match self.infer(inner) {
+                            StaticType::List(inner) => *inner,
+                            _ => StaticType::Unknown,
+                        },
+                        other => self.infer(other),
+                    };
+                    item = Some(match item {
+                        Some(item) => StaticType::join(item, found),
+                        None => found,
+                    });
+                }
+                StaticType::List(Box::new(item.unwrap_or(StaticType::Unknown)))
+            }
+            Expression::Range { .. } => StaticType::Range,
+            Expression::UnitValue { .. } => StaticType::Unit,
+            Expression::Lambda { .. } => StaticType::Function,
+            Expression::ConditionalExpression { true_expr, false_expr, .. } => {
+                let when_true = self.infer(true_expr);
+                let when_false = self.infer(false_expr);
+                when_true.join(when_false)
+            }
+            Expression::TypeCast { target_type, .. } => self.declared(target_type),
+            // Arms are blocks of statements, and their annotations count too
+            Expression::MatchExpression { arms, .. } => {
+                for arm in arms {
+                    self.check_block(&arm.body);
+                }
+                StaticType::Unknown
+            }
+            Expression::FunctionCall { .. } | Expression::MethodCall { .. } | Expression::Pipe { .. }
+            | Expression::BiDirectionalPipe { .. } | Expression::Spread(_) | Expression::StreamBranch { .. }
+            | Expression::StreamMerge { .. } => StaticType::Unknown,
+        }
+    }
+
+    /// What a call of a user function gives. A generic one's type parameters stand for what the
+    /// arguments are, so `first([1, 2])` gives an Integer; one given two different types is reported
+    fn infer_call(&mut self, name: &str, args: &[Expression]) -> StaticType {
+        let Some(signature) = self.functions.get(name).cloned() else {
+            return StaticType::Unknown;
+        };
+        let mut bound = HashMap::new();
+        if !signature.type_params.is_empty() {
+            bound = signature.type_params.iter().map(|param| (param.clone(), StaticType::Unknown)).collect();
+            for (annotation, arg) in signature.params.iter().zip(args) {
+                if let (Some(annotation), false) = (annotation, matches!(arg, Expression::Spread(_))) {
+                    let found = self.infer(arg);
+                    self.bind_type_params(name, annotation, found, &mut bound);
+                }
+            }
+        }
+        signature.returns.as_ref().map(|annotation| self.substitute(annotation, &bound)).unwrap_or(StaticType::Unknown)
+    }
+
+    /// Match an argument's type against its parameter's annotation, noting what each type parameter
+    /// in it stands for
+    fn bind_type_params(&mut self, function: &str, annotation: &TypeAnnotation, found: StaticType, bound: &mut HashMap<String, StaticType>) {
+        match annotation {
+            TypeAnnotation::Simple(param) => {
+                let Some(earlier) = bound.get(param).cloned() else {
+                    return;
+                };
+                if found.fits(&earlier) && earlier != StaticType::Unknown {
+                    return;
+                }
+                if earlier.fits(&found) {
+                    // The first time it's seen, or an Integer that turns out to be a Float
+                    bound.insert(param.clone(), found);
+                    return;
+                }
+                self.errors.push(SynthesisError::new(
+                    ErrorKind::TypeMismatch,
+                    format!("{}() needs the same type everywhere it uses {}, but it's given {} and {}", function, param, earlier, found)
+                )
+                .with_suggestion(format!("Pass {} values throughout, or give {}() a second type parameter", earlier, function))
+                .with_docs("https://synthesis-lang.org/docs/types"));
+            }
+            TypeAnnotation::Generic { base, params } if matches!(base.as_str(), "List" | "Array") => {
+                if let (Some(item), StaticType::List(found)) = (params.first(), found) {
+                    self.bind_type_params(function, item, *found, bound);
+                }
+            }
+            TypeAnnotation::Array(item) => {
+                if let StaticType::List(found) = found {
+                    self.bind_type_params(function, item, *found, bound);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The type an annotation names, with type parameters standing for what `bound` says
+    fn substitute(&self, annotation: &TypeAnnotation, bound: &HashMap<String, StaticType>) -> StaticType {
+        match annotation {
+            TypeAnnotation::Simple(name) if bound.contains_key(name) => bound[name].clone(),
+            TypeAnnotation::Simple(name) if self.type_params.contains(name) => StaticType::Unknown,
+            TypeAnnotation::Generic { base, params } if matches!(base.as_str(), "List" | "Array") => {
+                StaticType::List(Box::new(params.first().map(|item| self.substitute(item, bound)).unwrap_or(StaticType::Unknown)))
+            }
+            TypeAnnotation::Array(item) => StaticType::List(Box::new(self.substitute(item, bound))),
+            other => self.known(StaticType::from_annotation(other)),
+        }
+    }
+
+    /// The type an annotation names, if it's one the checker can hold values to
+    fn declared(&self, annotation: &TypeAnnotation) -> StaticType {
+        self.substitute(annotation, &HashMap::new())
+    }
+
+    /// Names other than the script's own structs and classes, like `Audio` or `Frequency`, cover
+    /// values of many shapes, so they're left unchecked
+    fn known(&self, found: StaticType) -> StaticType {
+        match found {
+            StaticType::Named(name) if !self.types.contains(&name) => StaticType::Unknown,
+            StaticType::List(item) => StaticType::List(Box::new(self.known(*item))),
+            other => other,
+        }
+    }
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What an operator gives for operands of these types, following the interpreter's rules
+fn infer_binary(left: StaticType, op: &BinaryOperator, right: StaticType) -> StaticType {
+    use StaticType::*;
+    match op {
+        BinaryOperator::Equal | BinaryOperator::NotEqual | BinaryOperator::LessThan | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThan | BinaryOperator::GreaterThanOrEqual => Boolean,
+        BinaryOperator::Add if left == Text && right == Text => Text,
+        BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply => match (left, right) {
+            (Integer, Integer) => Integer,
+            (Float, Integer | Float) | (Integer, Float) => Float,
+            (Unit, Integer | Float | Number | Unit) | (Integer | Float | Number, Unit) => Unit,
+            (a, b) if a.is_numeric() && b.is_numeric() => Number,
+            _ => Unknown,
+        },
+        // Dividing whole numbers still gives a float, and 1/8.note is a fraction of a note
+        BinaryOperator::Divide => match (left, right) {
+            (Unit, _) | (_, Unit) => Unit,
+            (a, b) if a.is_numeric() && b.is_numeric() => Float,
+            _ => Unknown,
+        },
+        // These give back one of their operands
+        BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr | BinaryOperator::NullCoalesce => left.join(right),
+        BinaryOperator::Pipe | BinaryOperator::BiDirectionalPipe => Unknown,
+    }
+}
+
+/// Check a parsed program's type annotations; an empty list means it's fine to run
+pub fn check_types(program: &Program) -> Vec<SynthesisError> {
+    TypeChecker::new().check(program)
+}
@@ -0,0 +1,643 @@
+/// The canonical layout for `.syn` source, so shared patches differ only where the code does
+///
+/// `format` prints a parsed program back out with four-space indentation, one statement per line
+/// and a blank line around functions, types and loops. Calls, lists and objects stay on one line
+/// while they fit in `MAX_WIDTH` columns and otherwise put one argument on each line, with a
+/// trailing comma. Named arguments and object fields come out in alphabetical order, since the
+/// program doesn't remember the order they were written in.
+///
+/// Formatting works from the program, not the text, so whatever the parser doesn't keep is lost:
+/// comments other than a function's `///` docs, and macro definitions, which have already been
+/// expanded. `would_lose` says whether a script has any of those. Hex and binary numbers keep
+/// their radix but come out in canonical form, like `0xFF` for `0x0ff`.
+use crate::parser::ast::*;
+use crate::parser::lexer::{self, Token};
+
+pub const MAX_WIDTH: usize = 100;
+const INDENT: &str = "    ";
+
+/// Precedence levels, loosest first, in the order `parse_pipe` down to `parse_call` reads them
+const PIPE: u8 = 0;
+const COALESCE: u8 = 1;
+const OR: u8 = 2;
+const AND: u8 = 3;
+const EQUALITY: u8 = 4;
+const COMPARISON: u8 = 5;
+const RANGE: u8 = 6;
+const TERM: u8 = 7;
+const FACTOR: u8 = 8;
+const UNARY: u8 = 9;
+const POSTFIX: u8 = 10;
+
+pub fn format(program: &Program) -> String {
+    let mut formatter = Formatter::default();
+    let mut previous = None;
+    for item in &program.items {
+        if previous.is_some_and(|previous| needs_gap(previous, item)) {
+            formatter.out.push('\n');
+        }
+        formatter.item(item);
+        previous = Some(item);
+    }
+    formatter.out
+}
+
+/// What formatting `source` would drop, if anything: `"comments"` or `"macros"`
+pub fn would_lose(source: &str, tokens: &[Token]) -> Option<&'static str> {
+    // Doc comments are only kept on a `func` right below them
+    let stray_docs = tokens.windows(2).any(|pair| {
+        matches!(pair[0], Token::DocComment(_)) && !matches!(pair[1], Token::DocComment(_) | Token::Func)
+    }) || matches!(tokens.last(), Some(Token::DocComment(_)));
+    if lexer::has_comments(source) || stray_docs {
+        Some("comments")
+    } else if tokens.contains(&Token::Macro) {
+        Some("macros")
+    } else {
+        None
+    }
+}
+
+fn needs_gap(previous: &Item, item: &Item) -> bool {
+    let standalone = |item: &Item| {
+        matches!(item, Item::Loop(_) | Item::Function(_) | Item::Class(_) | Item::Struct(_) | Item::Synth(_))
+    };
+    standalone(previous) || standalone(item) || (matches!(previous, Item::Import(_)) && !matches!(item, Item::Import(_)))
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+}
+
+impl Formatter {
+    fn line(&mut self, text: &str) {
+        self.out.push_str(&INDENT.repeat(self.indent));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn column(&self) -> usize {
+        self.indent * INDENT.len()
+    }
+
+    /// An expression starting after `prefix` on a new line
+    fn expression_line(&mut self, prefix: &str, expr: &Expression) {
+        let value = expression(expr, self.indent, self.column() + width(prefix));
+        self.line(&format!("{}{}", prefix, value));
+    }
+
+    /// `head { ... }`, then `} next { ... }` for each further part, as for `if`/`else`
+    fn blocks(&mut self, parts: &[(String, &[Statement])]) {
+        if let [(head, [])] = parts {
+            self.line(&format!("{} {{}}", head));
+            return;
+        }
+        for (i, (head, body)) in parts.iter().enumerate() {
+            if i == 0 {
+                self.line(&format!("{} {{", head));
+            } else {
+                self.line(&format!("}} {} {{", head));
+            }
+            self.indent += 1;
+            for statement in *body {
+                self.statement(statement);
+            }
+            self.indent -= 1;
+        }
+        self.line("}");
+    }
+
+    fn block(&mut self, head: String, body: &[Statement]) {
+        self.blocks(&[(head, body)]);
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Import(import) => {
+                let mut text = format!("import {}", module_path(&import.module));
+                if let Some(items) = &import.items {
+                    text.push_str(&format!(".{{{}}}", items.join(", ")));
+                }
+                if let Some(alias) = &import.alias {
+                    text.push_str(&format!(" as {}", alias));
+                }
+                self.line(&text);
+            }
+            Item::Statement(statement) => self.statement(statement),
+            Item::Loop(loop_block) => self.block("loop".to_string(), &loop_block.body),
+            Item::Function(function) => self.function(function),
+            Item::Class(class) => self.type_def("class", &class.name, &class.fields, &class.methods),
+            Item::Struct(def) => self.type_def("struct", &def.name, &def.fields, &def.methods),
+            Item::Synth(synth) => {
+                let head = format!("synth {}({})", synth.name, parameters(&synth.parameters, None));
+                self.block(head, &synth.body);
+            }
+        }
+    }
+
+    fn function(&mut self, function: &FunctionDef) {
+        for line in function.doc.iter().flat_map(|doc| doc.split('\n')) {
+            if line.is_empty() {
+                self.line("///");
+            } else {
+                self.line(&format!("/// {}", line));
+            }
+        }
+        let mut head = format!("func {}", function.name);
+        if !function.type_params.is_empty() {
+            head.push_str(&format!("<{}>", function.type_params.join(", ")));
+        }
+        head.push_str(&format!("({})", parameters(&function.parameters, function.rest.as_deref())));
+        if let Some(return_type) = &function.return_type {
+            head.push_str(&format!(" -> {}", type_annotation(return_type)));
+        }
+        self.block(head, &function.body);
+    }
+
+    fn type_def(&mut self, keyword: &str, name: &str, fields: &[Field], methods: &[FunctionDef]) {
+        if fields.is_empty() && methods.is_empty() {
+            self.line(&format!("{} {} {{}}", keyword, name));
+            return;
+        }
+        self.line(&format!("{} {} {{", keyword, name));
+        self.indent += 1;
+        for field in fields {
+            let mut text = field.name.clone();
+            // Fields without a type are `Any` once parsed
+            if field.type_annotation != TypeAnnotation::Simple("Any".to_string()) {
+                text.push_str(&format!(": {}", type_annotation(&field.type_annotation)));
+            }
+            match &field.default_value {
+                Some(value) => self.expression_line(&format!("{} = ", text), value),
+                None => self.line(&text),
+            }
+        }
+        for (i, method) in methods.iter().enumerate() {
+            if i > 0 || !fields.is_empty() {
+                self.out.push('\n');
+            }
+            self.function(method);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            // `config { fps: 30 }` is how scripts usually write their settings
+            Statement::Assignment { name, value: value @ Expression::Block { .. } } if name == "config" => {
+                self.expression_line("config ", value);
+            }
+            Statement::Assignment { name, value } => self.expression_line(&format!("{} = ", name), value),
+            Statement::FieldAssignment { target, path, value } => {
+                self.expression_line(&format!("{}.{} = ", target, path.join(".")), value);
+            }
+            Statement::Expression(expr) => self.expression_line("", expr),
+            Statement::If { condition, then_branch, else_branch } => {
+                let head = format!("if {}", flat(condition));
+                match else_branch {
+                    Some(else_branch) => self.blocks(&[(head, then_branch.as_slice()), ("else".to_string(), else_branch.as_slice())]),
+                    None => self.block(head, then_branch),
+                }
+            }
+            Statement::Match { expression: scrutinee, arms } => {
+                self.line(&format!("match {} {{", flat(scrutinee)));
+                self.indent += 1;
+                self.arms(arms);
+                self.indent -= 1;
+                self.line("}");
+            }
+            Statement::Every { duration, body } => self.block(format!("every({})", flat(duration)), body),
+            Statement::After { duration, body } => self.block(format!("after({})", flat(duration)), body),
+            Statement::Async { body } => self.block("async".to_string(), body),
+            Statement::While { condition, body } => self.block(format!("while {}", flat(condition)), body),
+            Statement::For { variable, iterable, body } => {
+                self.block(format!("for {} in {}", variable, flat(iterable)), body);
+            }
+            Statement::Try { body, error, handler } => {
+                let catch = match error {
+                    Some(error) => format!("catch ({})", error),
+                    None => "catch".to_string(),
+                };
+                self.blocks(&[("try".to_string(), body.as_slice()), (catch, handler.as_slice())]);
+            }
+            Statement::Let { name, type_annotation: annotation, value } => {
+                let mut text = format!("let {}", name);
+                if let Some(annotation) = annotation {
+                    text.push_str(&format!(": {}", type_annotation(annotation)));
+                }
+                match value {
+                    Some(value) => self.expression_line(&format!("{} = ", text), value),
+                    None => self.line(&text),
+                }
+            }
+            Statement::Const { name, value, .. } => self.expression_line(&format!("const {} = ", name), value),
+            Statement::LetDestructure { pattern, value } => {
+                let pattern = match pattern {
+                    Destructure::Array(names) => {
+                        let names: Vec<&str> = names.iter().map(|name| name.as_deref().unwrap_or("_")).collect();
+                        format!("[{}]", names.join(", "))
+                    }
+                    Destructure::Object(fields) => {
+                        let fields: Vec<String> = fields.iter().map(|(field, variable)| {
+                            if field == variable { field.clone() } else { format!("{}: {}", field, variable) }
+                        }).collect();
+                        format!("{{{}}}", fields.join(", "))
+                    }
+                };
+                self.expression_line(&format!("let {} = ", pattern), value);
+            }
+            Statement::Return(None) => self.line("return"),
+            Statement::Return(Some(value)) => self.expression_line("return ", value),
+            Statement::Break => self.line("break"),
+            Statement::Continue => self.line("continue"),
+            Statement::Capabilities(requests) => {
+                let requests: Vec<String> = requests.iter().map(|request| match &request.scope {
+                    Some(scope) => format!("{}({})", request.name, quote(scope)),
+                    None => request.name.clone(),
+                }).collect();
+                self.line(&format!("capabilities {{ {} }}", requests.join(", ")));
+            }
+        }
+    }
+
+    fn arms(&mut self, arms: &[MatchArm]) {
+        for arm in arms {
+            let mut head = pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                head.push_str(&format!(" if {}", flat(guard)));
+            }
+            self.block(format!("{} =>", head), &arm.body);
+        }
+    }
+}
+
+/// `func` and `synth` parameters
+fn parameters(parameters: &[Parameter], rest: Option<&str>) -> String {
+    let mut list: Vec<String> = parameters.iter().map(|parameter| {
+        let mut text = parameter.name.clone();
+        if let Some(annotation) = &parameter.type_annotation {
+            text.push_str(&format!(": {}", type_annotation(annotation)));
+        }
+        if let Some(default) = &parameter.default_value {
+            text.push_str(&format!(" = {}", flat(default)));
+        }
+        text
+    }).collect();
+    list.extend(rest.map(|rest| format!("...{}", rest)));
+    list.join(", ")
+}
+
+fn type_annotation(annotation: &TypeAnnotation) -> String {
+    match annotation {
+        TypeAnnotation::Simple(name) => name.clone(),
+        TypeAnnotation::Generic { base, params } => {
+            let params: Vec<String> = params.iter().map(type_annotation).collect();
+            format!("{}<{}>", base, params.join(", "))
+        }
+        TypeAnnotation::Array(item) => format!("[{}]", type_annotation(item)),
+        TypeAnnotation::Function { params, return_type } => {
+            let params: Vec<String> = params.iter().map(type_annotation).collect();
+            format!("({}) -> {}", params.join(", "), type_annotation(return_type))
+        }
+    }
+}
+
+fn pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Literal(Literal::String(text)) => quote(text),
+        Pattern::Literal(literal) => self::literal(literal),
+        Pattern::Identifier(name) | Pattern::Binding(name) => name.clone(),
+        Pattern::Enum { name, fields: None } => name.clone(),
+        Pattern::Enum { name, fields: Some(fields) } => {
+            let fields: Vec<String> = fields.iter().map(self::pattern).collect();
+            format!("{}({})", name, fields.join(", "))
+        }
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Array { items, rest } => {
+            let mut items: Vec<String> = items.iter().map(self::pattern).collect();
+            if *rest {
+                items.push("...".to_string());
+            }
+            format!("[{}]", items.join(", "))
+        }
+        Pattern::Object(fields) => {
+            let fields: Vec<String> = fields.iter().map(|(field, inner)| match inner {
+                Pattern::Binding(name) if name == field => field.clone(),
+                _ => format!("{}: {}", field, self::pattern(inner)),
+            }).collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        Pattern::Range { start, end, inclusive } => {
+            format!("{}{}{}", literal(start), if *inclusive { "..=" } else { ".." }, literal(end))
+        }
+    }
+}
+
+/// An expression on one line however long it gets, for conditions and defaults
+fn flat(expr: &Expression) -> String {
+    render(expr, 0, 0, false)
+}
+
+/// An expression starting at `column`, wrapping lists that don't fit onto lines indented one
+/// level past `indent`
+fn expression(expr: &Expression, indent: usize, column: usize) -> String {
+    render(expr, indent, column, true)
+}
+
+fn render(expr: &Expression, indent: usize, column: usize, wrap: bool) -> String {
+    // A child written `at` some column, in parentheses when it binds looser than `min`
+    let child = |inner: &Expression, min: u8, at: usize| {
+        if precedence(inner) < min {
+            format!("({})", render(inner, indent, at + 1, wrap))
+        } else {
+            render(inner, indent, at, wrap)
+        }
+    };
+    match expr {
+        Expression::Literal(Literal::String(text)) => quote(text),
+        Expression::Literal(literal) => self::literal(literal),
+        Expression::Identifier(name) => name.clone(),
+        Expression::InterpolatedString(parts) => interpolated(parts),
+        Expression::UnitValue { value, unit } => format!("{}.{}", flat(value), unit),
+        Expression::BinaryOp { left, op, right } => {
+            let level = binary_precedence(op);
+            infix(left, operator(op), right, level, level + 1, column, &child)
+        }
+        Expression::Pipe { left, right } => infix(left, "|>", right, PIPE, PIPE + 1, column, &child),
+        Expression::BiDirectionalPipe { left, right } => infix(left, "<>", right, PIPE, PIPE + 1, column, &child),
+        Expression::Range { start, end, inclusive } => {
+            infix(start, if *inclusive { "..=" } else { ".." }, end, TERM, TERM, column, &child)
+        }
+        Expression::UnaryOp { op, operand } => {
+            let sign = match op {
+                UnaryOperator::Negate => "-",
+                UnaryOperator::Not => "!",
+            };
+            format!("{}{}", sign, child(operand, UNARY, column + 1))
+        }
+        Expression::Spread(inner) => format!("...{}", render(inner, indent, column + 3, wrap)),
+        Expression::FunctionCall { module, name, args, named_args } => {
+            let callee = match module {
+                Some(module) => format!("{}.{}", module, name),
+                None => name.clone(),
+            };
+            let arguments = arguments(args, named_args);
+            format!("{}{}", callee, list("(", &arguments, ")", false, indent, column + width(&callee), wrap))
+        }
+        Expression::MethodCall { object, method, args, named_args, optional } => {
+            // `x.f(1)` would read as a call of `f` in module `x`, and `2.f` as a number with a unit
+            let needs_parens = match object.as_ref() {
+                Expression::Identifier(_) => !*optional && (!args.is_empty() || !named_args.is_empty()),
+                Expression::Literal(_) | Expression::UnitValue { .. } => true,
+                other => precedence(other) < POSTFIX,
+            };
+            let object = if needs_parens {
+                format!("({})", render(object, indent, column + 1, wrap))
+            } else {
+                render(object, indent, column, wrap)
+            };
+            let callee = format!("{}{}{}", object, if *optional { "?." } else { "." }, method);
+            if args.is_empty() && named_args.is_empty() {
+                return callee;
+            }
+            let arguments = arguments(args, named_args);
+            format!("{}{}", callee, list("(", &arguments, ")", false, indent, end_column(column, &callee), wrap))
+        }
+        Expression::ArrayAccess { array, index } => {
+            let array = child(array, POSTFIX, column);
+            let index = render(index, indent, end_column(column, &array) + 1, wrap);
+            format!("{}[{}]", array, index)
+        }
+        Expression::ArrayLiteral(items) => {
+            let items: Vec<(String, &Expression)> = items.iter().map(|item| (String::new(), item)).collect();
+            list("[", &items, "]", false, indent, column, wrap)
+        }
+        Expression::Block { fields } => list("{", &fields_of(fields), "}", true, indent, column, wrap),
+        Expression::StructLiteral { name, fields } => {
+            let fields = list("{", &fields_of(fields), "}", true, indent, column + width(name) + 1, wrap);
+            format!("{} {}", name, fields)
+        }
+        Expression::StreamBranch { stream, count } => format!("branch({})({})", count, flat(stream)),
+        Expression::StreamMerge { streams, output_name } => {
+            let streams: Vec<String> = streams.iter().map(flat).collect();
+            format!("merge({}) as {}", streams.join(", "), output_name)
+        }
+        Expression::Lambda { parameters, body } => format!("|{}| {}", parameters.join(", "), flat(body)),
+        Expression::ConditionalExpression { condition, true_expr, false_expr } => {
+            format!("if {} {{ {} }} else {{ {} }}", flat(condition), flat(true_expr), flat(false_expr))
+        }
+        Expression::MatchExpression { expr, arms } => {
+            let mut formatter = Formatter { out: String::new(), indent: indent + 1 };
+            formatter.arms(arms);
+            format!("match {} {{\n{}{}}}", flat(expr), formatter.out, INDENT.repeat(indent))
+        }
+        Expression::TypeCast { expr, target_type } => {
+            format!("{} as {}", child(expr, POSTFIX, column), type_annotation(target_type))
+        }
+    }
+}
+
+/// `left op right`, with each side in parentheses when it binds looser than its minimum
+fn infix(
+    left: &Expression,
+    op: &str,
+    right: &Expression,
+    left_min: u8,
+    right_min: u8,
+    column: usize,
+    child: &dyn Fn(&Expression, u8, usize) -> String,
+) -> String {
+    let left = child(left, left_min, column);
+    let separator = if op.starts_with("..") { op.to_string() } else { format!(" {} ", op) };
+    let right = child(right, right_min, end_column(column, &left) + width(&separator));
+    format!("{}{}{}", left, separator, right)
+}
+
+/// `open items close` on one line when it fits, otherwise one item per line
+fn list(open: &str, items: &[(String, &Expression)], close: &str, padded: bool, indent: usize, column: usize, wrap: bool) -> String {
+    if items.is_empty() {
+        return format!("{}{}", open, close);
+    }
+    let pad = if padded { " " } else { "" };
+    let flat_items: Vec<String> = items.iter().map(|(prefix, item)| format!("{}{}", prefix, flat(item))).collect();
+    let line = format!("{}{}{}{}{}", open, pad, flat_items.join(", "), pad, close);
+    if !wrap || (!line.contains('\n') && column + width(&line) <= MAX_WIDTH) {
+        return line;
+    }
+    let inner = indent + 1;
+    let mut out = format!("{}\n", open);
+    for (prefix, item) in items {
+        let value = expression(item, inner, inner * INDENT.len() + width(prefix));
+        out.push_str(&format!("{}{}{},\n", INDENT.repeat(inner), prefix, value));
+    }
+    out.push_str(&INDENT.repeat(indent));
+    out.push_str(close);
+    out
+}
+
+/// Positional arguments, then named ones by name
+fn arguments<'a>(args: &'a [Expression], named_args: &'a std::collections::HashMap<String, Expression>) -> Vec<(String, &'a Expression)> {
+    let mut named: Vec<(&String, &Expression)> = named_args.iter().collect();
+    named.sort_by(|a, b| a.0.cmp(b.0));
+    args.iter().map(|arg| (String::new(), arg))
+        .chain(named.into_iter().map(|(name, value)| (format!("{}: ", name), value)))
+        .collect()
+}
+
+/// An object's or struct literal's fields by name, quoting keys that aren't plain names
+fn fields_of(fields: &std::collections::HashMap<String, Expression>) -> Vec<(String, &Expression)> {
+    let mut fields: Vec<(&String, &Expression)> = fields.iter().collect();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+    fields.into_iter().map(|(key, value)| {
+        let key = if is_name(key) { key.clone() } else { quote(key) };
+        (format!("{}: ", key), value)
+    }).collect()
+}
+
+fn precedence(expr: &Expression) -> u8 {
+    match expr {
+        Expression::BinaryOp { op, .. } => binary_precedence(op),
+        Expression::Pipe { .. } | Expression::BiDirectionalPipe { .. } => PIPE,
+        Expression::Range { .. } => RANGE,
+        Expression::UnaryOp { .. } | Expression::Spread(_) => UNARY,
+        // A negative number is written with a minus in front
+        Expression::Literal(Literal::Integer(n) | Literal::BasedInteger(n, _)) if *n < 0 => UNARY,
+        Expression::Literal(Literal::Float(n)) if n.is_sign_negative() => UNARY,
+        Expression::Lambda { .. } | Expression::ConditionalExpression { .. } | Expression::TypeCast { .. }
+        | Expression::StreamMerge { .. } => PIPE,
+        _ => POSTFIX,
+    }
+}
+
+fn binary_precedence(op: &BinaryOperator) -> u8 {
+    match op {
+        BinaryOperator::Pipe | BinaryOperator::BiDirectionalPipe => PIPE,
+        BinaryOperator::NullCoalesce => COALESCE,
+        BinaryOperator::LogicalOr => OR,
+        BinaryOperator::LogicalAnd => AND,
+        BinaryOperator::Equal | BinaryOperator::NotEqual => EQUALITY,
+        BinaryOperator::LessThan | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThan | BinaryOperator::GreaterThanOrEqual => COMPARISON,
+        BinaryOperator::Add | BinaryOperator::Subtract => TERM,
+        BinaryOperator::Multiply | BinaryOperator::Divide => FACTOR,
+    }
+}
+
+fn operator(op: &BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Equal => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::LessThanOrEqual => "<=",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::GreaterThanOrEqual => ">=",
+        BinaryOperator::LogicalAnd => "&&",
+        BinaryOperator::LogicalOr => "||",
+        BinaryOperator::NullCoalesce => "??",
+        BinaryOperator::Pipe => "|>",
+        BinaryOperator::BiDirectionalPipe => "<>",
+    }
+}
+
+/// `0xFF00AA` or `0b1010`; anything but hex or binary is written in decimal
+pub fn based_integer(n: i64, radix: u32) -> String {
+    let sign = if n < 0 { "-" } else { "" };
+    match radix {
+        16 => format!("{}0x{:X}", sign, n.unsigned_abs()),
+        2 => format!("{}0b{:b}", sign, n.unsigned_abs()),
+        _ => n.to_string(),
+    }
+}
+
+fn literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Integer(n) => n.to_string(),
+        Literal::BasedInteger(n, radix) => based_integer(*n, *radix),
+        Literal::Float(n) => float(*n),
+        Literal::Percentage(fraction) => percentage(*fraction),
+        Literal::String(text) => quote(text),
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Color(rgba) => crate::runtime::Value::Color(*rgba).to_string(),
+    }
+}
+
+/// Always with a decimal point, so it reads back as a float
+fn float(n: f64) -> String {
+    let text = n.to_string();
+    if text.contains('.') || !n.is_finite() { text } else { format!("{}.0", text) }
+}
+
+/// The fewest decimals that read back as the same fraction
+fn percentage(fraction: f64) -> String {
+    let percent = fraction * 100.0;
+    (0..=17)
+        .map(|decimals| format!("{:.*}", decimals, percent))
+        .find(|text| text.parse::<f64>().is_ok_and(|value| value / 100.0 == fraction))
+        .unwrap_or_else(|| percent.to_string())
+        + "%"
+}
+
+/// An ordinary string, with escapes for anything that would end or interpolate it
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '\0' => quoted.push_str("\\0"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn interpolated(parts: &[StringPart]) -> String {
+    let mut text = String::from("\"");
+    for part in parts {
+        match part {
+            StringPart::Text(part) => {
+                let quoted = quote(part);
+                text.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            // The lexer keeps what's between `${` and `}` as it was written
+            StringPart::Interpolation(Expression::Identifier(source)) => text.push_str(&format!("${{{}}}", source)),
+            StringPart::Interpolation(expr) => text.push_str(&format!("${{{}}}", flat(expr))),
+        }
+    }
+    text.push('"');
+    text
+}
+
+/// `lib/drums` as written, or a quoted path for anything else
+fn module_path(module: &str) -> String {
+    if module.split('/').all(is_name) { module.to_string() } else { quote(module) }
+}
+
+/// Whether `text` reads back as a single identifier rather than a keyword or something else
+fn is_name(text: &str) -> bool {
+    matches!(lexer::tokenize(text), Ok(("", tokens)) if tokens == [Token::Identifier(text.to_string())])
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// The column after `text` when it starts at `column`
+fn end_column(column: usize, text: &str) -> usize {
+    match text.rfind('\n') {
+        Some(newline) => width(&text[newline + 1..]),
+        None => column + width(text),
+    }
+}
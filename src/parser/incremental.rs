@@ -0,0 +1,226 @@
+/// Incremental parsing for editors: `IncrementalParser` keeps a script's text and program, and
+/// after each edit reparses only the top-level items the edit touched.
+///
+/// The script is split into chunks, one per top-level item, each running from the item's first
+/// token to the next item's. An edit reparses the chunks it falls in along with the chunk before
+/// (whose last expression could carry on into the edit) and the chunk after. The chunk after has
+/// to come out as an item starting exactly where it did before, or the edit changed the shape of
+/// the script, like an opened `{` swallowing what follows, and the whole script is reparsed
+/// instead. Scripts with macros are always reparsed whole, since a macro changes how everything
+/// after it parses.
+use crate::errors::{ErrorKind, SourceLocation, SynthesisError};
+use crate::parser::ast::{Item, Program};
+use crate::parser::lexer::{self, Span, Token};
+use crate::parser::parser::Parser;
+
+/// Replace the text from `start` up to, but not including, `end` with `text`. Positions are lines
+/// and columns counted from 1, like the spans tokens carry; a column past the end of its line
+/// means the end of the line
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub start: Span,
+    pub end: Span,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(start: Span, end: Span, text: impl Into<String>) -> Self {
+        Self { start, end, text: text.into() }
+    }
+
+    /// `text` typed at `at`, replacing nothing
+    pub fn insert(at: Span, text: impl Into<String>) -> Self {
+        Self::new(at, at, text)
+    }
+}
+
+/// How a program changed: `removed` items from `start` on were replaced by `inserted`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProgramDelta {
+    pub start: usize,
+    pub removed: usize,
+    pub inserted: Vec<Item>,
+}
+
+impl ProgramDelta {
+    /// The change from `old` to `new`, leaving out the items at either end they share
+    fn between(old: &[Item], new: Vec<Item>, offset: usize) -> Self {
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+        let inserted = new[prefix..new.len() - suffix].to_vec();
+        Self { start: offset + prefix, removed: old.len() - prefix - suffix, inserted }
+    }
+
+    /// Whether the edit left the program as it was, like one inside a comment
+    pub fn is_empty(&self) -> bool {
+        self.removed == 0 && self.inserted.is_empty()
+    }
+
+    /// Make the same change to a copy of the program, such as one an editor or LSP keeps
+    pub fn apply(&self, program: &mut Program) {
+        let end = (self.start + self.removed).min(program.items.len());
+        program.items.splice(self.start.min(end)..end, self.inserted.iter().cloned());
+    }
+}
+
+/// The items one top-level item parsed into and the byte its text starts at
+#[derive(Debug, Clone, PartialEq)]
+struct Chunk {
+    start: usize,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IncrementalParser {
+    filename: String,
+    source: String,
+    chunks: Vec<Chunk>,
+    program: Program,
+    /// The next edit reparses the whole script: after a syntax error, or when it has macros
+    whole: bool,
+}
+
+impl IncrementalParser {
+    pub fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+            source: String::new(),
+            chunks: Vec::new(),
+            program: Program { items: Vec::new() },
+            whole: true,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The program as of the last edit that parsed; while the script has syntax errors this is
+    /// still the last version without them
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Replace the whole script, as when a file is opened
+    pub fn set_text(&mut self, source: &str) -> Result<ProgramDelta, Vec<SynthesisError>> {
+        self.parse_whole(source.to_string())
+    }
+
+    /// Apply an edit and reparse what it touched. The text always changes; on syntax errors the
+    /// program stays as it was and the errors, for the whole script, are returned
+    pub fn edit(&mut self, edit: &TextEdit) -> Result<ProgramDelta, Vec<SynthesisError>> {
+        let (from, to) = (offset(&self.source, edit.start), offset(&self.source, edit.end));
+        let (from, to) = (from.min(to), from.max(to));
+        let mut source = self.source.clone();
+        source.replace_range(from..to, &edit.text);
+
+        if !self.whole {
+            if let Some(delta) = self.reparse(&source, from, to, edit.text.len()) {
+                self.source = source;
+                return Ok(delta);
+            }
+        }
+        self.parse_whole(source)
+    }
+
+    fn parse_whole(&mut self, source: String) -> Result<ProgramDelta, Vec<SynthesisError>> {
+        let parsed = parse_chunks(&source, &self.filename);
+        self.source = source;
+        let (mut chunks, has_macros) = match parsed {
+            Ok(parsed) => parsed,
+            Err(errors) => {
+                self.whole = true;
+                return Err(errors);
+            }
+        };
+        if let Some(first) = chunks.first_mut() {
+            // Comments and blank lines before the first item belong to it
+            first.start = 0;
+        }
+        let items: Vec<Item> = chunks.iter().flat_map(|chunk| chunk.items.iter().cloned()).collect();
+        let delta = ProgramDelta::between(&self.program.items, items, 0);
+        delta.apply(&mut self.program);
+        self.whole = has_macros || chunks.is_empty();
+        self.chunks = chunks;
+        Ok(delta)
+    }
+
+    /// Reparse the chunks around an edit that replaced bytes `from` to `to` of the old text with
+    /// `inserted` bytes, or None if the edit reaches further than they do
+    fn reparse(&mut self, source: &str, from: usize, to: usize, inserted: usize) -> Option<ProgramDelta> {
+        let moved = |old: usize| old + inserted - (to - from);
+        let chunk_at = |at: usize| self.chunks.partition_point(|chunk| chunk.start <= at).saturating_sub(1);
+        let first = chunk_at(from).saturating_sub(1);
+        let guard = chunk_at(to) + 1;
+        let region_start = self.chunks[first].start;
+        let guard_start = self.chunks.get(guard).map(|chunk| moved(chunk.start));
+        let region_end = self.chunks.get(guard + 1).map_or(source.len(), |chunk| moved(chunk.start));
+
+        let (mut chunks, has_macros) = parse_chunks(&source[region_start..region_end], &self.filename).ok()?;
+        if has_macros {
+            return None;
+        }
+        chunks.iter_mut().for_each(|chunk| chunk.start += region_start);
+        if let Some(guard_start) = guard_start {
+            // The chunk after the edit has to start where it did, and it parses as it did before
+            if chunks.pop()?.start != guard_start {
+                return None;
+            }
+        }
+        if let Some(chunk) = chunks.first_mut() {
+            chunk.start = region_start;
+        }
+
+        let item_index = |chunk: usize| self.chunks[..chunk].iter().map(|chunk| chunk.items.len()).sum::<usize>();
+        let (start, end) = (item_index(first), item_index(guard.min(self.chunks.len())));
+        let items: Vec<Item> = chunks.iter().flat_map(|chunk| chunk.items.iter().cloned()).collect();
+        let delta = ProgramDelta::between(&self.program.items[start..end], items, start);
+        delta.apply(&mut self.program);
+
+        let parsed = chunks.len();
+        self.chunks.splice(first..guard.min(self.chunks.len()), chunks);
+        // The chunks after the edit haven't changed, but the text before them has
+        for chunk in &mut self.chunks[first + parsed..] {
+            chunk.start = moved(chunk.start);
+        }
+        Some(delta)
+    }
+}
+
+/// The chunks `source` parses into and whether it defines or imports macros
+fn parse_chunks(source: &str, filename: &str) -> Result<(Vec<Chunk>, bool), Vec<SynthesisError>> {
+    let (rest, (tokens, spans)) = lexer::tokenize_with_spans(source)
+        .map_err(|error| vec![lexer::literal_error(source, &error, filename).unwrap_or_else(|| unreadable(source, source, filename))])?;
+    if !lexer::is_blank(rest) {
+        return Err(vec![unreadable(source, rest, filename)]);
+    }
+    let mut parser = Parser::with_spans(&tokens, &spans, filename);
+    let located = parser.parse_located_items()?;
+    let has_macros = !parser.macros().is_empty() || tokens.contains(&Token::Macro);
+    let chunks = located.into_iter()
+        .map(|(token, items)| Chunk { start: offset(source, spans[token]), items })
+        .collect();
+    Ok((chunks, has_macros))
+}
+
+/// The error for text the lexer stopped reading at, where `rest` is what it couldn't read
+fn unreadable(source: &str, rest: &str, filename: &str) -> SynthesisError {
+    let at = Span::start().after(&source[..source.len() - rest.len()]);
+    SynthesisError::new(ErrorKind::SyntaxError, "There's something here that isn't part of Synthesis")
+        .with_suggestion("Check for typos, missing quotes, or unusual characters")
+        .with_location(SourceLocation { line: at.line, column: at.column, filename: filename.to_string() })
+}
+
+/// The byte in `source` at a line and column, both counted from 1
+fn offset(source: &str, at: Span) -> usize {
+    let mut line_start = 0;
+    for _ in 1..at.line {
+        match source[line_start..].find('\n') {
+            Some(end) => line_start += end + 1,
+            None => return source.len(),
+        }
+    }
+    let line = &source[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    line.char_indices().nth(at.column.saturating_sub(1)).map_or(line_start + line.len(), |(i, _)| line_start + i)
+}
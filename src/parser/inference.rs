@@ -0,0 +1,765 @@
+/// Works out what kind of value each variable holds, before a script runs
+///
+/// Where the annotation checker only looks at values it can see, inference follows them: through
+/// variables, into and out of user functions, and along pipes, so `mic |> Audio.analyze_fft(16)`
+/// is known to give a list of numbers and a function parameter passed to `Signal.sine()` is known
+/// to be a frequency. It's Hindley-Milner without generalisation: each unknown gets a type
+/// variable, and uses narrow it down by unification. Anything that won't unify is left dynamic
+/// and reported rather than refused.
+///
+/// The types it finds are the creative ones `CreativeTypeSystem` works with, so the coercions a
+/// script relies on, like a note name where a frequency goes or 50% where a 0-1 level goes, show
+/// up in the report instead of happening silently.
+use crate::errors::{ErrorKind, SynthesisError};
+use crate::parser::ast::*;
+use crate::runtime::creative_types::{
+    AudioType, BooleanType, CreativeType, CreativeTypeSystem, DurationType, FrequencyType, NumberType, StreamType, TextType, VisualType,
+};
+use crate::runtime::units::Unit;
+use crate::runtime::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InferredType {
+    /// Not known yet; narrowed down as it's used
+    Var(usize),
+    Number,
+    /// A 0-1 level, like a mix or an opacity
+    Normalized,
+    Percentage,
+    Frequency,
+    Duration,
+    /// Text that names a note, like "A4"
+    Note,
+    Text,
+    Boolean,
+    /// `#ff00aa`
+    Color,
+    /// Streams
+    Audio,
+    Graphics,
+    Control,
+    List(Box<InferredType>),
+    Function {
+        params: Vec<InferredType>,
+        returns: Box<InferredType>,
+    },
+    /// Changes type, or comes from somewhere inference can't see; fits anywhere
+    Unknown,
+}
+
+impl InferredType {
+    /// The creative type this corresponds to, for types `CreativeTypeSystem` knows
+    pub fn creative(&self) -> Option<CreativeType> {
+        Some(match self {
+            InferredType::Number => CreativeType::Number(NumberType::Float),
+            InferredType::Normalized => CreativeType::Number(NumberType::Normalized),
+            InferredType::Percentage => CreativeType::Number(NumberType::Percentage),
+            InferredType::Frequency => CreativeType::Frequency(FrequencyType::Hertz),
+            InferredType::Duration => CreativeType::Duration(DurationType::Seconds),
+            InferredType::Note => CreativeType::Text(TextType::NoteName),
+            InferredType::Text => CreativeType::Text(TextType::PlainText),
+            InferredType::Boolean => CreativeType::Boolean(BooleanType::Switch),
+            InferredType::Audio => CreativeType::Stream(StreamType::Audio(AudioType::Mono)),
+            InferredType::Graphics => CreativeType::Stream(StreamType::Visual(VisualType::RGB)),
+            InferredType::Control => CreativeType::Stream(StreamType::Control),
+            _ => return None,
+        })
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, InferredType::Number | InferredType::Normalized | InferredType::Frequency | InferredType::Duration)
+    }
+
+    fn is_stream(&self) -> bool {
+        matches!(self, InferredType::Audio | InferredType::Graphics | InferredType::Control)
+    }
+}
+
+impl fmt::Display for InferredType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferredType::Var(_) | InferredType::Unknown => write!(f, "Any"),
+            InferredType::Number => write!(f, "Number"),
+            InferredType::Normalized => write!(f, "Level (0-1)"),
+            InferredType::Percentage => write!(f, "Percentage"),
+            InferredType::Frequency => write!(f, "Frequency"),
+            InferredType::Duration => write!(f, "Duration"),
+            InferredType::Note => write!(f, "Note"),
+            InferredType::Text => write!(f, "Text"),
+            InferredType::Boolean => write!(f, "Boolean"),
+            InferredType::Color => write!(f, "Color"),
+            InferredType::Audio => write!(f, "Audio stream"),
+            InferredType::Graphics => write!(f, "Graphics stream"),
+            InferredType::Control => write!(f, "Control stream"),
+            InferredType::List(item) => write!(f, "List<{}>", item),
+            InferredType::Function { params, returns } => {
+                let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+                write!(f, "({}) -> {}", params.join(", "), returns)
+            }
+        }
+    }
+}
+
+/// A value converted on its way into a function, and the rule that does it
+#[derive(Debug, Clone)]
+pub struct Coercion {
+    /// Where, like `Signal.sine() argument 1` or `Audio.apply_reverb(room_size:)`
+    pub site: String,
+    pub from: CreativeType,
+    pub to: CreativeType,
+    /// The `CreativeTypeSystem` rule that applies
+    pub rule: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Inference {
+    /// Top-level variables, by name
+    pub variables: BTreeMap<String, InferredType>,
+    /// User functions' parameter and return types
+    pub functions: BTreeMap<String, InferredType>,
+    pub coercions: Vec<Coercion>,
+    /// Values used as something they can't be converted to; `TypeInferenceError`s, as warnings
+    pub conflicts: Vec<SynthesisError>,
+}
+
+impl Inference {
+    /// What a top-level variable holds, in `CreativeTypeSystem`'s terms
+    pub fn creative_type(&self, name: &str) -> Option<CreativeType> {
+        self.variables.get(name).and_then(InferredType::creative)
+    }
+
+    /// The variables, functions and coercions, one per line, for `--types`
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for (name, found) in &self.variables {
+            report.push_str(&format!("  {}: {}\n", name, found));
+        }
+        for (name, found) in &self.functions {
+            report.push_str(&format!("  func {}: {}\n", name, found));
+        }
+        for coercion in &self.coercions {
+            report.push_str(&format!("  ↪ {}: {} becomes {} ({})\n", coercion.site, coercion.from, coercion.to, coercion.rule));
+        }
+        for conflict in &self.conflicts {
+            report.push_str(&format!("  ⚠ {}\n", conflict.message));
+        }
+        report
+    }
+}
+
+/// Parameters and result of a module function whose types are worth knowing
+struct Signature {
+    /// The stream a pipe feeds in, or the first argument
+    input: Option<InferredType>,
+    params: Vec<(&'static str, InferredType)>,
+    returns: InferredType,
+}
+
+fn signature(module: &str, function: &str) -> Option<Signature> {
+    use InferredType::*;
+    let (input, params, returns) = match (module, function) {
+        ("Audio", "mic_input") => (None, vec![], Audio),
+        ("Audio", "load_file") => (None, vec![("path", Text)], Audio),
+        ("Audio", "analyze_fft") => (Some(Audio), vec![("bands", Number)], List(Box::new(Number))),
+        ("Audio", "beat_detect") => (Some(Audio), vec![], Boolean),
+        ("Audio", "spectral_centroid") => (Some(Audio), vec![], Frequency),
+        ("Audio", "tempo_detection") => (Some(Audio), vec![], Number),
+        ("Audio", "apply_reverb") => (Some(Audio), vec![("room_size", Normalized), ("wet_mix", Normalized)], Audio),
+        ("Audio", "bandpass") => (Some(Audio), vec![("low", Frequency), ("high", Frequency)], Audio),
+        ("Signal", "sine") => (None, vec![("frequency", Frequency), ("length", Duration), ("amplitude", Normalized)], List(Box::new(Number))),
+        ("Signal", "sweep") => (None, vec![("from", Frequency), ("to", Frequency), ("length", Duration)], List(Box::new(Number))),
+        ("Graphics", "plasma") => (None, vec![("speed", Number)], Graphics),
+        ("Graphics", "starfield") => (None, vec![("count", Number), ("speed", Number)], Graphics),
+        ("Graphics", "circle") => (None, vec![("x", Number), ("y", Number), ("radius", Number)], Graphics),
+        ("Math", "sin" | "cos" | "tan" | "abs" | "sqrt") => (None, vec![("x", Number)], Number),
+        ("Math", "map") => (None, vec![("value", Number), ("from_low", Number), ("from_high", Number), ("to_low", Number), ("to_high", Number)], Number),
+        _ => return None,
+    };
+    Some(Signature { input, params, returns })
+}
+
+pub struct TypeInference {
+    system: CreativeTypeSystem,
+    /// What each type variable has been narrowed to
+    bindings: Vec<Option<InferredType>>,
+    /// Innermost last; each function body gets its own
+    scopes: Vec<HashMap<String, InferredType>>,
+    functions: HashMap<String, (Vec<String>, InferredType)>,
+    /// The type variables a generic function's type parameters became; each call gets fresh ones
+    generics: HashMap<String, Vec<usize>>,
+    /// The function whose body is being inferred, for `return`
+    returns: Vec<InferredType>,
+    coercions: Vec<Coercion>,
+    conflicts: Vec<SynthesisError>,
+}
+
+impl TypeInference {
+    pub fn new(system: CreativeTypeSystem) -> Self {
+        Self {
+            system,
+            bindings: Vec::new(),
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            generics: HashMap::new(),
+            returns: Vec::new(),
+            coercions: Vec::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    pub fn infer(mut self, program: &Program) -> Inference {
+        let definitions: Vec<&FunctionDef> = program.items.iter().filter_map(|item| match item {
+            Item::Function(function) => Some(function),
+            _ => None,
+        }).collect();
+        for function in &definitions {
+            let type_params: HashMap<String, InferredType> = function.type_params.iter().map(|param| (param.clone(), self.fresh())).collect();
+            if !type_params.is_empty() {
+                let vars = type_params.values().filter_map(|param| match param {
+                    InferredType::Var(var) => Some(*var),
+                    _ => None,
+                }).collect();
+                self.generics.insert(function.name.clone(), vars);
+            }
+            let params = function.parameters.iter().map(|parameter| match &parameter.type_annotation {
+                Some(annotation) => annotated_with(annotation, &type_params),
+                None => self.fresh(),
+            }).collect();
+            let returns = match &function.return_type {
+                Some(annotation) => annotated_with(annotation, &type_params),
+                None => self.fresh(),
+            };
+            let names = function.parameters.iter().map(|parameter| parameter.name.clone()).collect();
+            self.functions.insert(function.name.clone(), (names, InferredType::Function { params, returns: Box::new(returns) }));
+        }
+        // Bodies first, so calls at the top level see what the parameters were narrowed to
+        for function in definitions {
+            self.infer_function(function);
+        }
+        for item in &program.items {
+            match item {
+                Item::Statement(statement) => self.infer_statement(statement),
+                Item::Loop(LoopBlock { body }) => self.infer_block(body),
+                _ => {}
+            }
+        }
+
+        let globals = self.scopes.first().cloned().unwrap_or_default();
+        let variables = globals.iter().map(|(name, found)| (name.clone(), self.resolve_fully(found))).collect();
+        let functions = self.functions.iter().map(|(name, (_, found))| (name.clone(), self.resolve_fully(found))).collect();
+        Inference { variables, functions, coercions: self.coercions, conflicts: self.conflicts }
+    }
+
+    fn fresh(&mut self) -> InferredType {
+        self.bindings.push(None);
+        InferredType::Var(self.bindings.len() - 1)
+    }
+
+    fn resolve(&self, found: &InferredType) -> InferredType {
+        match found {
+            InferredType::Var(var) => match &self.bindings[*var] {
+                Some(bound) => self.resolve(bound),
+                None => found.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// With every variable inside lists and functions replaced by what it's bound to
+    fn resolve_fully(&self, found: &InferredType) -> InferredType {
+        match self.resolve(found) {
+            InferredType::List(item) => InferredType::List(Box::new(self.resolve_fully(&item))),
+            InferredType::Function { params, returns } => InferredType::Function {
+                params: params.iter().map(|param| self.resolve_fully(param)).collect(),
+                returns: Box::new(self.resolve_fully(&returns)),
+            },
+            other => other,
+        }
+    }
+
+    /// A generic function's type with new variables for its type parameters, so what one call
+    /// passes doesn't decide the types for the next
+    fn instantiate(&mut self, found: &InferredType, generic: &[usize], fresh: &mut HashMap<usize, InferredType>) -> InferredType {
+        match self.resolve(found) {
+            InferredType::Var(var) if generic.contains(&var) => fresh.entry(var).or_insert_with(|| self.fresh()).clone(),
+            InferredType::List(item) => InferredType::List(Box::new(self.instantiate(&item, generic, fresh))),
+            InferredType::Function { params, returns } => InferredType::Function {
+                params: params.iter().map(|param| self.instantiate(param, generic, fresh)).collect(),
+                returns: Box::new(self.instantiate(&returns, generic, fresh)),
+            },
+            other => other,
+        }
+    }
+
+    fn occurs(&self, var: usize, found: &InferredType) -> bool {
+        match self.resolve(found) {
+            InferredType::Var(other) => other == var,
+            InferredType::List(item) => self.occurs(var, &item),
+            InferredType::Function { params, returns } => params.iter().any(|param| self.occurs(var, param)) || self.occurs(var, &returns),
+            _ => false,
+        }
+    }
+
+    /// Make two types the same, binding variables as needed; false if they can't be
+    fn unify(&mut self, a: &InferredType, b: &InferredType) -> bool {
+        match (self.resolve(a), self.resolve(b)) {
+            (InferredType::Var(a), InferredType::Var(b)) if a == b => true,
+            (InferredType::Var(var), other) | (other, InferredType::Var(var)) => {
+                if self.occurs(var, &other) {
+                    return false;
+                }
+                self.bindings[var] = Some(other);
+                true
+            }
+            (InferredType::Unknown, _) | (_, InferredType::Unknown) => true,
+            (InferredType::List(a), InferredType::List(b)) => self.unify(&a, &b),
+            (InferredType::Function { params: a, returns: ra }, InferredType::Function { params: b, returns: rb }) => {
+                a.len() == b.len() && a.iter().zip(&b).all(|(a, b)| self.unify(a, b)) && self.unify(&ra, &rb)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// A value going where `expected` is asked for: the same type, one read in the default unit,
+    /// a coercion `CreativeTypeSystem` has a rule for, or a conflict
+    fn flow(&mut self, found: &InferredType, expected: &InferredType, site: String) {
+        if self.unify(found, expected) {
+            return;
+        }
+        let (found, expected) = (self.resolve(found), self.resolve(expected));
+        // A bare number is read in the unit the parameter expects
+        if (found == InferredType::Number && expected.is_numeric()) || (found.is_numeric() && expected == InferredType::Number) {
+            return;
+        }
+        // Colours go where numbers do as their 0xRRGGBB value
+        if found == InferredType::Color && expected == InferredType::Number {
+            return;
+        }
+        // Where a number goes, a percentage arrives as its 0-1 fraction
+        let target = match (&found, &expected) {
+            (InferredType::Percentage, InferredType::Number) => InferredType::Normalized,
+            _ => expected.clone(),
+        };
+        if let (Some(from), Some(to)) = (found.creative(), target.creative()) {
+            if let Some(rule) = self.system.coercion_between(&from, &to) {
+                let rule = rule.name.clone();
+                self.coercions.push(Coercion { site, from, to, rule });
+                return;
+            }
+        }
+        self.conflicts.push(SynthesisError::new(
+            ErrorKind::TypeInferenceError,
+            format!("🔄 {} expects {}, but it's given {}", site, expected, found)
+        )
+        .with_suggestion(format!("Pass {} here, or convert it first", expected))
+        .with_docs("https://synthesis-lang.org/docs/types"));
+    }
+
+    fn bind(&mut self, name: &str, found: InferredType) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), found);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&InferredType> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// A variable that's given something that won't unify changes type, and stops being tracked
+    fn assign(&mut self, name: &str, found: InferredType) {
+        match self.lookup(name).cloned() {
+            Some(known) => {
+                if !self.unify(&known, &found) {
+                    self.bind(name, InferredType::Unknown);
+                }
+            }
+            None => self.bind(name, found),
+        }
+    }
+
+    fn infer_function(&mut self, function: &FunctionDef) {
+        let Some((_, InferredType::Function { params, returns })) = self.functions.get(&function.name).cloned() else {
+            return;
+        };
+        let mut scope: HashMap<String, InferredType> = function.parameters.iter().map(|parameter| parameter.name.clone()).zip(params.iter().cloned()).collect();
+        if let Some(rest) = &function.rest {
+            scope.insert(rest.clone(), InferredType::List(Box::new(InferredType::Unknown)));
+        }
+        for (parameter, expected) in function.parameters.iter().zip(&params) {
+            if let Some(default) = &parameter.default_value {
+                let found = self.infer_expression(default);
+                self.unify(&found, expected);
+            }
+        }
+        self.scopes.push(scope);
+        self.returns.push(*returns);
+        self.infer_block(&function.body);
+        self.returns.pop();
+        self.scopes.pop();
+    }
+
+    fn infer_block(&mut self, body: &[Statement]) {
+        body.iter().for_each(|statement| self.infer_statement(statement));
+    }
+
+    fn infer_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assignment { name, value } | Statement::Let { name, value: Some(value), .. } | Statement::Const { name, value, .. } => {
+                let found = self.infer_expression(value);
+                if let Statement::Let { type_annotation: Some(annotation), .. } = statement {
+                    self.bind(name, annotated(annotation));
+                    self.flow(&found, &annotated(annotation), format!("let {}", name));
+                } else {
+                    self.assign(name, found);
+                }
+            }
+            Statement::Let { name, type_annotation, value: None } => {
+                let found = type_annotation.as_ref().map(annotated).unwrap_or_else(|| self.fresh());
+                self.bind(name, found);
+            }
+            Statement::LetDestructure { pattern, value } => {
+                let found = self.infer_expression(value);
+                let item = match self.resolve(&found) {
+                    InferredType::List(item) => *item,
+                    _ => InferredType::Unknown,
+                };
+                match pattern {
+                    Destructure::Array(names) => names.iter().flatten().for_each(|name| self.assign(name, item.clone())),
+                    Destructure::Object(bindings) => bindings.iter().for_each(|(_, name)| self.assign(name, InferredType::Unknown)),
+                }
+            }
+            Statement::Return(Some(value)) => {
+                let found = self.infer_expression(value);
+                if let Some(returns) = self.returns.last().cloned() {
+                    if !self.unify(&found, &returns) {
+                        self.returns.pop();
+                        self.returns.push(InferredType::Unknown);
+                    }
+                }
+            }
+            Statement::FieldAssignment { value, .. } | Statement::Expression(value) => {
+                self.infer_expression(value);
+            }
+            Statement::If { condition, then_branch, else_branch } => {
+                self.infer_expression(condition);
+                self.infer_block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.infer_block(else_branch);
+                }
+            }
+            Statement::Match { expression, arms } => {
+                self.infer_expression(expression);
+                for arm in arms {
+                    self.infer_block(&arm.body);
+                }
+            }
+            Statement::Every { duration, body } | Statement::After { duration, body } => {
+                let found = self.infer_expression(duration);
+                self.flow(&found, &InferredType::Duration, "every/after".to_string());
+                self.infer_block(body);
+            }
+            Statement::While { condition, body } => {
+                self.infer_expression(condition);
+                self.infer_block(body);
+            }
+            Statement::For { variable, iterable, body } => {
+                let found = self.infer_expression(iterable);
+                let item = match self.resolve(&found) {
+                    InferredType::List(item) => *item,
+                    _ => InferredType::Unknown,
+                };
+                self.bind(variable, item);
+                self.infer_block(body);
+            }
+            Statement::Async { body } => self.infer_block(body),
+            Statement::Try { body, error, handler } => {
+                self.infer_block(body);
+                if let Some(error) = error {
+                    self.bind(error, InferredType::Unknown);
+                }
+                self.infer_block(handler);
+            }
+            Statement::Return(None) | Statement::Break | Statement::Continue | Statement::Capabilities(_) => {}
+        }
+    }
+
+    fn infer_text(&self, text: &str) -> InferredType {
+        match self.system.infer_creative_type(&Value::String(text.to_string()), None) {
+            CreativeType::Text(TextType::NoteName) => InferredType::Note,
+            _ => InferredType::Text,
+        }
+    }
+
+    fn infer_expression(&mut self, expression: &Expression) -> InferredType {
+        match expression {
+            Expression::Literal(Literal::Integer(_) | Literal::BasedInteger(..) | Literal::Float(_)) => InferredType::Number,
+            Expression::Literal(Literal::Percentage(_)) => InferredType::Percentage,
+            Expression::Literal(Literal::Boolean(_)) => InferredType::Boolean,
+            Expression::Literal(Literal::Color(_)) => InferredType::Color,
+            Expression::Literal(Literal::String(text)) => self.infer_text(text),
+            // The lexer hands over plain strings as a single text part
+            Expression::InterpolatedString(parts) => match parts.as_slice() {
+                [StringPart::Text(text)] => self.infer_text(text),
+                _ => InferredType::Text,
+            },
+            Expression::UnitValue { unit, .. } => match Unit::from_string(unit) {
+                Some(Unit::Hertz | Unit::Kilohertz) => InferredType::Frequency,
+                Some(Unit::Second | Unit::Millisecond | Unit::Note | Unit::Beat | Unit::Bar) => InferredType::Duration,
+                Some(Unit::Percent) => InferredType::Percentage,
+                _ => InferredType::Number,
+            },
+            Expression::Identifier(name) => match self.lookup(name) {
+                Some(found) => found.clone(),
+                None => self.functions.get(name).map(|(_, found)| found.clone()).unwrap_or(InferredType::Unknown),
+            },
+            Expression::FunctionCall { module, name, args, named_args } => self.infer_call(module.as_deref(), name, None, args, named_args),
+            Expression::MethodCall { object, method, args, named_args, .. } => {
+                // `Audio.analyze_fft(...)` parses as a method call on the module name
+                match object.as_ref() {
+                    Expression::Identifier(module) if self.lookup(module).is_none() => {
+                        self.infer_call(Some(module), method, None, args, named_args)
+                    }
+                    _ => {
+                        self.infer_expression(object);
+                        self.infer_arguments(args, named_args);
+                        InferredType::Unknown
+                    }
+                }
+            }
+            Expression::Pipe { left, right } => {
+                let input = self.infer_expression(left);
+                match right.as_ref() {
+                    Expression::FunctionCall { module, name, args, named_args } => {
+                        self.infer_call(module.as_deref(), name, Some(input), args, named_args)
+                    }
+                    Expression::MethodCall { object, method, args, named_args, .. } => match object.as_ref() {
+                        Expression::Identifier(module) => self.infer_call(Some(module), method, Some(input), args, named_args),
+                        _ => self.infer_expression(right),
+                    },
+                    other => self.infer_expression(other),
+                }
+            }
+            Expression::BiDirectionalPipe { left, right } => {
+                let left = self.infer_expression(left);
+                let right = self.infer_expression(right);
+                self.unify(&left, &right);
+                left
+            }
+            Expression::BinaryOp { left, op, right } => {
+                let left = self.infer_expression(left);
+                let right = self.infer_expression(right);
+                self.infer_binary(left, op, right)
+            }
+            Expression::UnaryOp { op: UnaryOperator::Not, operand } => {
+                self.infer_expression(operand);
+                InferredType::Boolean
+            }
+            Expression::UnaryOp { op: UnaryOperator::Negate, operand } => self.infer_expression(operand),
+            Expression::ArrayLiteral(items) => {
+                let item = self.fresh();
+                for expression in items {
+                    let found = match expression {
+                        Expression::Spread(inner) => {
+                            let spread = self.infer_expression(inner);
+                            match self.resolve(&spread) {
+                                InferredType::List(inner) => *inner,
+                                _ => InferredType::Unknown,
+                            }
+                        }
+                        other => self.infer_expression(other),
+                    };
+                    // Mixed lists are fine, they just aren't a list of anything in particular
+                    if !self.unify(&item, &found) {
+                        return InferredType::List(Box::new(InferredType::Unknown));
+                    }
+                }
+                InferredType::List(Box::new(item))
+            }
+            Expression::ArrayAccess { array, index } => {
+                self.infer_expression(index);
+                let found = self.infer_expression(array);
+                match self.resolve(&found) {
+                    InferredType::List(item) => *item,
+                    _ => InferredType::Unknown,
+                }
+            }
+            Expression::ConditionalExpression { condition, true_expr, false_expr } => {
+                self.infer_expression(condition);
+                let when_true = self.infer_expression(true_expr);
+                let when_false = self.infer_expression(false_expr);
+                if self.unify(&when_true, &when_false) { when_true } else { InferredType::Unknown }
+            }
+            Expression::StreamBranch { stream, .. } => {
+                let found = self.infer_expression(stream);
+                InferredType::List(Box::new(found))
+            }
+            Expression::StreamMerge { streams, .. } => {
+                let merged = self.fresh();
+                for stream in streams {
+                    let found = self.infer_expression(stream);
+                    if !self.unify(&merged, &found) {
+                        return InferredType::Unknown;
+                    }
+                }
+                merged
+            }
+            Expression::TypeCast { expr, target_type } => {
+                self.infer_expression(expr);
+                annotated(target_type)
+            }
+            Expression::Lambda { parameters, .. } => InferredType::Function {
+                params: parameters.iter().map(|_| InferredType::Unknown).collect(),
+                returns: Box::new(InferredType::Unknown),
+            },
+            Expression::Block { fields } | Expression::StructLiteral { fields, .. } => {
+                fields.values().for_each(|value| { self.infer_expression(value); });
+                InferredType::Unknown
+            }
+            Expression::MatchExpression { expr, arms } => {
+                self.infer_expression(expr);
+                for arm in arms {
+                    self.infer_block(&arm.body);
+                }
+                InferredType::Unknown
+            }
+            Expression::Range { start, end, .. } => {
+                self.infer_expression(start);
+                self.infer_expression(end);
+                InferredType::List(Box::new(InferredType::Number))
+            }
+            Expression::Spread(inner) => {
+                self.infer_expression(inner);
+                InferredType::Unknown
+            }
+        }
+    }
+
+    fn infer_arguments(&mut self, args: &[Expression], named_args: &HashMap<String, Expression>) {
+        for arg in args.iter().chain(named_args.values()) {
+            self.infer_expression(arg);
+        }
+    }
+
+    /// A module or user function call; `input` is what a pipe feeds in
+    fn infer_call(&mut self, module: Option<&str>, name: &str, input: Option<InferredType>, args: &[Expression], named_args: &HashMap<String, Expression>) -> InferredType {
+        let call = match module {
+            Some(module) => format!("{}.{}()", module, name),
+            None => format!("{}()", name),
+        };
+        if let Some(signature) = module.and_then(|module| signature(module, name)) {
+            let mut args = args.iter();
+            if let Some(expected) = &signature.input {
+                // Without a pipe the stream comes first
+                let found = match input {
+                    Some(found) => Some(found),
+                    None => args.next().map(|arg| self.infer_expression(arg)),
+                };
+                if let Some(found) = found {
+                    self.flow(&found, expected, format!("{} input", call));
+                }
+            }
+            for (position, (arg, (_, expected))) in args.zip(&signature.params).enumerate() {
+                let found = self.infer_expression(arg);
+                self.flow(&found, expected, format!("{} argument {}", call, position + 1));
+            }
+            for (param, arg) in named_args {
+                let found = self.infer_expression(arg);
+                if let Some((_, expected)) = signature.params.iter().find(|(name, _)| name == param) {
+                    self.flow(&found, expected, format!("{}({}:)", call.trim_end_matches("()"), param));
+                }
+            }
+            return signature.returns;
+        }
+        if let (None, Some((names, function))) = (module, self.functions.get(name).cloned()) {
+            let function = match self.generics.get(name).cloned() {
+                Some(generic) => self.instantiate(&function, &generic, &mut HashMap::new()),
+                None => function,
+            };
+            let InferredType::Function { params, returns } = function else {
+                return InferredType::Unknown;
+            };
+            let found: Vec<InferredType> = input.into_iter().chain(args.iter().map(|arg| self.infer_expression(arg))).collect();
+            for (position, (found, expected)) in found.iter().zip(&params).enumerate() {
+                self.flow(found, expected, format!("{} argument {}", call, position + 1));
+            }
+            for (param, arg) in named_args {
+                let found = self.infer_expression(arg);
+                if let Some(expected) = names.iter().position(|name| name == param).and_then(|index| params.get(index)) {
+                    self.flow(&found, expected, format!("{}({}:)", name, param));
+                }
+            }
+            return *returns;
+        }
+        self.infer_arguments(args, named_args);
+        InferredType::Unknown
+    }
+
+    fn infer_binary(&mut self, left: InferredType, op: &BinaryOperator, right: InferredType) -> InferredType {
+        use InferredType::*;
+        match op {
+            BinaryOperator::Equal | BinaryOperator::NotEqual | BinaryOperator::LessThan | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan | BinaryOperator::GreaterThanOrEqual => Boolean,
+            BinaryOperator::Pipe | BinaryOperator::BiDirectionalPipe => right,
+            BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr | BinaryOperator::NullCoalesce => {
+                if self.unify(&left, &right) { left } else { Unknown }
+            }
+            BinaryOperator::Add | BinaryOperator::Subtract | BinaryOperator::Multiply | BinaryOperator::Divide => {
+                match (self.resolve(&left), self.resolve(&right)) {
+                    (Text | Note, Text | Note) if *op == BinaryOperator::Add => Text,
+                    // Scaling or offsetting a stream or a quantity keeps what it is
+                    (kept, Number | Normalized | Percentage) | (Number | Normalized | Percentage, kept)
+                        if kept.is_stream() || matches!(kept, Frequency | Duration) => kept,
+                    (Number | Normalized | Percentage, Number | Normalized | Percentage) => Number,
+                    (a, b) if a == b && (a.is_stream() || a.is_numeric()) => a,
+                    // `x * 2` works on numbers and streams alike, so it doesn't say which x is
+                    _ => Unknown,
+                }
+            }
+        }
+    }
+}
+
+/// The inferred type an annotation names; ones inference doesn't track are left dynamic
+fn annotated(annotation: &TypeAnnotation) -> InferredType {
+    annotated_with(annotation, &HashMap::new())
+}
+
+/// Like `annotated`, with a generic function's type parameters standing for its type variables
+fn annotated_with(annotation: &TypeAnnotation, type_params: &HashMap<String, InferredType>) -> InferredType {
+    let annotated = |annotation| annotated_with(annotation, type_params);
+    match annotation {
+        TypeAnnotation::Simple(name) if type_params.contains_key(name) => type_params[name].clone(),
+        TypeAnnotation::Simple(name) => match name.as_str() {
+            "Number" | "Integer" | "Int" | "Float" => InferredType::Number,
+            "Level" | "Normalized" => InferredType::Normalized,
+            "Percentage" => InferredType::Percentage,
+            "Frequency" => InferredType::Frequency,
+            "Duration" | "Time" => InferredType::Duration,
+            "Note" => InferredType::Note,
+            "Text" | "String" => InferredType::Text,
+            "Boolean" | "Bool" => InferredType::Boolean,
+            "Color" | "Colour" => InferredType::Color,
+            "Audio" => InferredType::Audio,
+            "Graphics" | "Visual" => InferredType::Graphics,
+            "Control" => InferredType::Control,
+            "List" | "Array" => InferredType::List(Box::new(InferredType::Unknown)),
+            _ => InferredType::Unknown,
+        },
+        TypeAnnotation::Array(item) => InferredType::List(Box::new(annotated(item))),
+        TypeAnnotation::Generic { base, params } if matches!(base.as_str(), "List" | "Array") => {
+            InferredType::List(Box::new(params.first().map(annotated).unwrap_or(InferredType::Unknown)))
+        }
+        TypeAnnotation::Generic { .. } => InferredType::Unknown,
+        TypeAnnotation::Function { params, return_type } => InferredType::Function {
+            params: params.iter().map(annotated).collect(),
+            returns: Box::new(annotated(return_type)),
+        },
+    }
+}
+
+/// Infer a program's types with the default coercion rules
+pub fn infer_types(program: &Program) -> Inference {
+    TypeInference::new(CreativeTypeSystem::new()).infer(program)
+}
@@ -2,9 +2,9 @@ use nom::{
     branch::alt,
     bytes::complete::{tag, take_while, take_while1},
     character::complete::{alpha1, alphanumeric1, char, multispace0},
-    combinator::{map, map_res, recognize},
+    combinator::{map, map_res, not, peek, recognize},
     multi::{many0, many1},
-    sequence::{delimited, pair, preceded},
+    sequence::{delimited, pair},
     IResult,
 };
 
@@ -18,18 +18,25 @@ pub enum Token {
     Match,
     Every,
     After,
+    /// `async { ... }`: a block that runs alongside the rest of the frame, pausing at `wait()`
+    Async,
     While,
     For,
     In,
     Func,
+    /// `macro drumkit(name) { ... }`: statements pasted in where it's called, while parsing
+    Macro,
     Class,
     Struct,
     Enum,
     Let,
     Mut,
+    Const,
     Return,
     Break,
     Continue,
+    Try,
+    Catch,
     Main,
     As,
     Content,
@@ -38,13 +45,16 @@ pub enum Token {
     // Identifiers and literals
     Identifier(String),
     Integer(i64),
+    /// `0xFF00AA` or `0b1010`, as the value and its radix
+    BasedInteger(i64, u32),
     Float(f64),
     Percentage(f64),
     String(String),
     InterpolatedString(Vec<crate::parser::ast::StringPart>),
     Boolean(bool),
+    /// `#ff00aa` or `#ff00aa80`, as red, green, blue and alpha
+    Color([u8; 4]),
     Unit(String),
-    ArrayLiteral(Vec<Token>),
     
     // Operators
     Plus,
@@ -59,8 +69,16 @@ pub enum Token {
     GreaterThanOrEqual,
     LogicalAnd,
     LogicalOr,
+    /// `!` or the word `not`
+    Not,
+    /// `a ?? b`: `a` unless it's null
+    NullCoalesce,
+    /// `a?.b`: null instead of a field or method when `a` is null
+    QuestionDot,
     Range,
     RangeInclusive,
+    /// `...list`: every item of a list, in a list literal or a call's arguments
+    Spread,
     Pipe,
     BiDirectionalPipe,
     Branch(u8),
@@ -84,24 +102,178 @@ pub enum Token {
     Pipe2,
     
     // Special
+    /// One `///` line, without the slashes
+    DocComment(String),
     Newline,
     Eof,
 }
 
+/// Where a token starts in the source, both counted from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn start() -> Self {
+        Self { line: 1, column: 1 }
+    }
+
+    /// The position just after `text`, when `text` begins at this position
+    pub fn after(self, text: &str) -> Self {
+        text.chars().fold(self, |span, c| match c {
+            '\n' => Span { line: span.line + 1, column: 1 },
+            _ => Span { column: span.column + 1, ..span },
+        })
+    }
+}
+
 pub fn tokenize(input: &str) -> IResult<&str, Vec<Token>> {
-    many0(preceded(skip_whitespace_comments, token))(input)
+    let (remaining, (tokens, _)) = tokenize_with_spans(input)?;
+    Ok((remaining, tokens))
+}
+
+/// Tokenize, also returning where each token starts so errors can point at the right line
+pub fn tokenize_with_spans(input: &str) -> IResult<&str, (Vec<Token>, Vec<Span>)> {
+    let mut tokens = Vec::new();
+    let mut spans = Vec::new();
+    let mut remaining = input;
+    let mut position = Span::start();
+    let mut context = ValueContext::default();
+
+    while let Ok((start, _)) = skip_whitespace_comments(remaining, context.expects_value(tokens.last())) {
+        match token(start) {
+            Ok((rest, _)) if rest.len() == remaining.len() => {
+                // Same guard as many0: a token that consumes nothing would loop forever
+                return Err(nom::Err::Error(nom::error::Error::new(remaining, nom::error::ErrorKind::Many0)));
+            }
+            Ok((rest, token)) => {
+                position = position.after(&remaining[..remaining.len() - start.len()]);
+                spans.push(position);
+                context.push(&token);
+                tokens.push(token);
+                position = position.after(&start[..start.len() - rest.len()]);
+                remaining = rest;
+            }
+            Err(nom::Err::Error(_)) => break,
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok((remaining, (tokens, spans)))
+}
+
+/// A friendly error for a literal the lexer refused outright, like a hex number too large to
+/// hold, pointing at where it starts in `source`. `None` for anything else, which callers
+/// describe in their own words
+pub fn literal_error(source: &str, error: &nom::Err<nom::error::Error<&str>>, filename: &str) -> Option<crate::errors::SynthesisError> {
+    match error {
+        nom::Err::Failure(error) if error.code == nom::error::ErrorKind::TooLarge && source.ends_with(error.input) => {
+            let literal = error.input.split(|c: char| !c.is_ascii_alphanumeric()).next().unwrap_or(error.input);
+            let at = Span::start().after(&source[..source.len() - error.input.len()]);
+            Some(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::SyntaxError,
+                format!("🔢 {} is too large a number", literal)
+            )
+            .with_suggestion(format!("Whole numbers go up to 0x{:X}, which is {}", i64::MAX, i64::MAX))
+            .with_location(crate::errors::SourceLocation { line: at.line, column: at.column, filename: filename.to_string() }))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `input` has `//`, `#` or `/* */` comments, which are skipped rather than kept as tokens
+pub fn has_comments(input: &str) -> bool {
+    let mut remaining = input;
+    let mut previous = None;
+    let mut context = ValueContext::default();
+    while let Ok((start, _)) = skip_whitespace_comments(remaining, context.expects_value(previous.as_ref())) {
+        let whitespace: IResult<&str, &str> = multispace0(remaining);
+        if whitespace.is_ok_and(|(after, _)| after.len() != start.len()) {
+            return true;
+        }
+        match token(start) {
+            Ok((rest, token)) if rest.len() < start.len() => {
+                remaining = rest;
+                context.push(&token);
+                previous = Some(token);
+            }
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Whether `input` holds nothing but whitespace and comments, like what's left after the last token
+pub fn is_blank(input: &str) -> bool {
+    skip_whitespace_comments(input, false).is_ok_and(|(rest, _)| rest.is_empty())
+}
+
+/// Which braces so far open a `match` body, so the lexer knows where an arm's pattern can start
+#[derive(Default)]
+struct ValueContext {
+    after_match: bool,
+    match_bodies: Vec<bool>,
+}
+
+impl ValueContext {
+    fn push(&mut self, token: &Token) {
+        match token {
+            Token::Match => self.after_match = true,
+            Token::LeftBrace => self.match_bodies.push(std::mem::take(&mut self.after_match)),
+            Token::RightBrace => {
+                self.match_bodies.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether a value comes next: an expression, or a pattern at the start of a `match` arm.
+    /// Only there is `#c0ffee` a colour; anywhere else, such as the start of a line or after a
+    /// value, `#` begins a comment
+    fn expects_value(&self, previous: Option<&Token>) -> bool {
+        let arm_start = matches!(previous, Some(Token::LeftBrace | Token::RightBrace | Token::Comma));
+        expects_expression(previous) || (arm_start && self.match_bodies.last() == Some(&true))
+    }
 }
 
-fn skip_whitespace_comments(input: &str) -> IResult<&str, ()> {
+/// Whether the token after `previous` has to start an expression: after an operator, an opening
+/// bracket, a comma or colon, `=` or a keyword like `return`
+fn expects_expression(previous: Option<&Token>) -> bool {
+    matches!(previous, Some(
+        Token::Plus | Token::Minus | Token::Multiply | Token::Divide
+        | Token::Equals | Token::NotEqual | Token::LessThan | Token::LessThanOrEqual
+        | Token::GreaterThan | Token::GreaterThanOrEqual | Token::LogicalAnd | Token::LogicalOr
+        | Token::Not | Token::NullCoalesce | Token::Range | Token::RangeInclusive | Token::Spread
+        | Token::Pipe | Token::BiDirectionalPipe | Token::Branch(_) | Token::Pipe2
+        | Token::LeftParen | Token::LeftBracket | Token::Comma | Token::Colon | Token::Assignment | Token::Arrow
+        | Token::Return | Token::In | Token::If | Token::While | Token::Match
+    ))
+}
+
+/// Skip whitespace and comments; `colors` says a `#` followed by hex digits is a colour to keep
+fn skip_whitespace_comments(input: &str, colors: bool) -> IResult<&str, ()> {
     let (mut input, _) = multispace0(input)?;
     
     // Handle comments
     loop {
-        if let Ok((remaining, _)) = tag::<&str, &str, nom::error::Error<&str>>("//")(input) {
+        if is_doc_comment(input) {
+            // Doc comments are tokens, so the parser can attach them to what follows
+            break;
+        } else if let Some(remaining) = input.strip_prefix("/*") {
+            // Found a /* block comment */, skip past its end (or to the end of an unclosed one)
+            let remaining = remaining.find("*/").map_or("", |end| &remaining[end + 2..]);
+            let (remaining, _) = multispace0(remaining)?;
+            input = remaining;
+        } else if let Ok((remaining, _)) = tag::<&str, &str, nom::error::Error<&str>>("//")(input) {
             // Found a // comment, skip to end of line
             let (remaining, _) = take_while(|c| c != '\n' && c != '\r')(remaining)?;
             let (remaining, _) = multispace0(remaining)?;
             input = remaining;
+        } else if colors && is_color(input) {
+            // `#ff00aa` where a value goes is a colour, not a comment
+            break;
         } else if let Ok((remaining, _)) = tag::<&str, &str, nom::error::Error<&str>>("#")(input) {
             // Found a # comment, skip to end of line
             let (remaining, _) = take_while(|c| c != '\n' && c != '\r')(remaining)?;
@@ -115,57 +287,101 @@ fn skip_whitespace_comments(input: &str) -> IResult<&str, ()> {
     Ok((input, ()))
 }
 
+/// `/// text` but not `//// text`, which stays an ordinary comment
+fn is_doc_comment(input: &str) -> bool {
+    input.starts_with("///") && !input.starts_with("////")
+}
+
+fn doc_comment(input: &str) -> IResult<&str, Token> {
+    if !is_doc_comment(input) {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag)));
+    }
+    let (input, text) = take_while(|c| c != '\n' && c != '\r')(&input[3..])?;
+    let text = text.strip_prefix(' ').unwrap_or(text).trim_end();
+    Ok((input, Token::DocComment(text.to_string())))
+}
+
 fn token(input: &str) -> IResult<&str, Token> {
     alt((
+        doc_comment,
         keyword,
         boolean,
+        based_integer,
         percentage,
         float_with_unit,
         integer_with_unit,
         float,
         integer,
+        raw_string,
+        multiline_string,
         interpolated_string,
         string_literal,
-        array_literal,
+        color,
         identifier,
         operator,
         punctuation,
     ))(input)
 }
 
+/// Keywords and booleans only count as whole words, so `invert` or `format` stay identifiers
+fn word_end(input: &str) -> IResult<&str, ()> {
+    not(peek(take_while1(|c: char| c.is_alphanumeric() || c == '_')))(input)
+}
+
 fn keyword(input: &str) -> IResult<&str, Token> {
+    let (input, token) = keyword_prefix(input)?;
+    let (input, _) = word_end(input)?;
+    Ok((input, token))
+}
+
+fn keyword_prefix(input: &str) -> IResult<&str, Token> {
     alt((
         map(tag("import"), |_| Token::Import),
         map(tag("loop"), |_| Token::Loop),
         map(tag("match"), |_| Token::Match),
         map(tag("every"), |_| Token::Every),
         map(tag("after"), |_| Token::After),
+        map(tag("async"), |_| Token::Async),
         map(tag("while"), |_| Token::While),
         map(tag("for"), |_| Token::For),
         map(tag("in"), |_| Token::In),
         map(tag("if"), |_| Token::If),
         map(tag("else"), |_| Token::Else),
         map(tag("func"), |_| Token::Func),
+        map(tag("macro"), |_| Token::Macro),
         map(tag("class"), |_| Token::Class),
         map(tag("struct"), |_| Token::Struct),
         map(tag("enum"), |_| Token::Enum),
         map(tag("let"), |_| Token::Let),
         map(tag("mut"), |_| Token::Mut),
+        map(tag("const"), |_| Token::Const),
+        keyword_prefix_rest,
+    ))(input)
+}
+
+// nom's `alt` takes at most 21 branches, so the keyword list is split in two.
+fn keyword_prefix_rest(input: &str) -> IResult<&str, Token> {
+    alt((
         map(tag("return"), |_| Token::Return),
         map(tag("break"), |_| Token::Break),
         map(tag("continue"), |_| Token::Continue),
+        map(tag("try"), |_| Token::Try),
+        map(tag("catch"), |_| Token::Catch),
         map(tag("main"), |_| Token::Main),
         map(tag("as"), |_| Token::As),
+        map(tag("not"), |_| Token::Not),
         map(tag("content"), |_| Token::Content),
         map(tag("style"), |_| Token::Style),
     ))(input)
 }
 
 fn boolean(input: &str) -> IResult<&str, Token> {
-    alt((
+    let (input, token) = alt((
         map(tag("true"), |_| Token::Boolean(true)),
         map(tag("false"), |_| Token::Boolean(false)),
-    ))(input)
+    ))(input)?;
+    let (input, _) = word_end(input)?;
+    Ok((input, token))
 }
 
 fn integer(input: &str) -> IResult<&str, Token> {
@@ -179,6 +395,43 @@ fn integer(input: &str) -> IResult<&str, Token> {
     )(input)
 }
 
+/// `0xFF00AA` in hex or `0b1010` in binary
+fn based_integer(input: &str) -> IResult<&str, Token> {
+    let (rest, (radix, digits)) = alt((
+        pair(map(alt((tag("0x"), tag("0X"))), |_| 16), take_while1(|c: char| c.is_ascii_hexdigit())),
+        pair(map(alt((tag("0b"), tag("0B"))), |_| 2), take_while1(|c: char| c == '0' || c == '1')),
+    ))(input)?;
+    let (rest, _) = word_end(rest)?;
+    // Too many digits is a mistake in the number, not a different token, so stop here
+    let value = i64::from_str_radix(digits, radix)
+        .map_err(|_| nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::TooLarge)))?;
+    Ok((rest, Token::BasedInteger(value, radix)))
+}
+
+/// Whether `input` starts with `#` and six or eight hex digits, which is a colour wherever an
+/// value is expected (see `ValueContext::expects_value`)
+fn is_color(input: &str) -> bool {
+    let Some(digits) = input.strip_prefix('#') else {
+        return false;
+    };
+    let count = digits.chars().take_while(|c| c.is_ascii_hexdigit()).count();
+    matches!(count, 6 | 8) && !digits[count..].starts_with(|c: char| c.is_alphanumeric() || c == '_')
+}
+
+fn color(input: &str) -> IResult<&str, Token> {
+    if !is_color(input) {
+        return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag)));
+    }
+    let digits = &input[1..];
+    let count = digits.chars().take_while(|c| c.is_ascii_hexdigit()).count();
+    let mut rgba = [0, 0, 0, 255];
+    for (channel, value) in rgba.iter_mut().zip((0..count).step_by(2)) {
+        *channel = u8::from_str_radix(&digits[value..value + 2], 16)
+            .map_err(|_| nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::HexDigit)))?;
+    }
+    Ok((&digits[count..], Token::Color(rgba)))
+}
+
 fn float(input: &str) -> IResult<&str, Token> {
     map_res(
         recognize(pair(
@@ -209,15 +462,67 @@ fn percentage(input: &str) -> IResult<&str, Token> {
 
 fn string_literal(input: &str) -> IResult<&str, Token> {
     map(
-        delimited(
-            char('"'),
-            take_while1(|c| c != '"' && c != '$'),
-            char('"'),
-        ),
-        |s: &str| Token::String(s.to_string()),
+        delimited(char('"'), string_text, char('"')),
+        Token::String,
     )(input)
 }
 
+/// Text up to the closing quote or the next `${`, with escapes like `\n` and `\u{1F3B5}` resolved
+fn string_text(input: &str) -> IResult<&str, String> {
+    text_until(input, true)
+}
+
+/// Text up to the next `${` or the end, and the closing quote too with `stop_at_quote`
+fn text_until(input: &str, stop_at_quote: bool) -> IResult<&str, String> {
+    let mut text = String::new();
+    let mut rest = input;
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            None => return Ok((rest, text)),
+            Some('"') if stop_at_quote => return Ok((rest, text)),
+            Some('$') if rest.starts_with("${") => return Ok((rest, text)),
+            Some('\\') => {
+                // A bad escape is a mistake in the string, not a cue to try lexing it another way
+                let (after, c) = escape(chars.as_str())
+                    .ok_or_else(|| nom::Err::Failure(nom::error::Error::new(rest, nom::error::ErrorKind::Escaped)))?;
+                text.push(c);
+                rest = after;
+            }
+            Some(c) => {
+                text.push(c);
+                rest = chars.as_str();
+            }
+        }
+    }
+}
+
+/// The character after a backslash: `n`, `t`, `r`, `0`, `"`, `\`, `$`, or `u{...}` with up to six hex digits
+fn escape(input: &str) -> Option<(&str, char)> {
+    let mut chars = input.chars();
+    let c = match chars.next()? {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '0' => '\0',
+        '"' => '"',
+        '\\' => '\\',
+        '$' => '$',
+        'u' => {
+            let digits = chars.as_str().strip_prefix('{')?;
+            let end = digits.find('}')?;
+            let hex = &digits[..end];
+            if hex.is_empty() || hex.len() > 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+            return Some((&digits[end + 1..], c));
+        }
+        _ => return None,
+    };
+    Some((chars.as_str(), c))
+}
+
 fn interpolated_string(input: &str) -> IResult<&str, Token> {
     let (input, _) = char('"')(input)?;
     let mut parts = Vec::new();
@@ -225,14 +530,18 @@ fn interpolated_string(input: &str) -> IResult<&str, Token> {
     
     loop {
         // Parse text part
-        let (rest, text) = take_while(|c| c != '$' && c != '"')(remaining)?;
+        let (rest, text) = string_text(remaining)?;
         if !text.is_empty() {
-            parts.push(crate::parser::ast::StringPart::Text(text.to_string()));
+            parts.push(crate::parser::ast::StringPart::Text(text));
         }
         
         if let Ok((rest, _)) = char::<&str, nom::error::Error<&str>>('"')(rest) {
-            // End of string
-            return Ok((rest, Token::InterpolatedString(parts)));
+            // End of string; without interpolations it's plain text
+            return Ok((rest, match parts.as_slice() {
+                [] => Token::String(String::new()),
+                [crate::parser::ast::StringPart::Text(text)] => Token::String(text.clone()),
+                _ => Token::InterpolatedString(parts),
+            }));
         } else if let Ok((rest, _)) = tag::<&str, &str, nom::error::Error<&str>>("${")(rest) {
             // Start of interpolation
             let (rest, expr) = take_while(|c| c != '}')(rest)?;
@@ -250,36 +559,80 @@ fn interpolated_string(input: &str) -> IResult<&str, Token> {
     }
 }
 
-fn array_literal(input: &str) -> IResult<&str, Token> {
-    let (input, _) = char('[')(input)?;
-    let (input, _) = multispace0(input)?;
-    
-    let mut elements = Vec::new();
-    let mut remaining = input;
-    
-    while !remaining.is_empty() {
-        if let Ok((rest, _)) = char::<&str, nom::error::Error<&str>>(']')(remaining) {
-            return Ok((rest, Token::ArrayLiteral(elements)));
-        }
-        
-        // Parse element
-        if let Ok((rest, token)) = token(remaining) {
-            elements.push(token);
-            let (rest, _) = multispace0(rest)?;
-            
-            if let Ok((rest, _)) = char::<&str, nom::error::Error<&str>>(',')(rest) {
-                let (rest, _) = multispace0(rest)?;
-                remaining = rest;
-            } else {
-                remaining = rest;
+/// `"""` strings: they can span lines and hold quotes, and escapes and `${}` work as in `"`
+/// strings. A line break straight after the opening quotes is dropped, and so is the indentation
+/// every line shares, so a block can be indented along with the code around it
+fn multiline_string(input: &str) -> IResult<&str, Token> {
+    let (body, _) = tag("\"\"\"")(input)?;
+    // An escaped quote can't close the string
+    let mut end = None;
+    let mut chars = body.char_indices();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
             }
-        } else {
-            break;
+            '"' if body[at..].starts_with("\"\"\"") => {
+                end = Some(at);
+                break;
+            }
+            _ => {}
         }
     }
-    
-    let (input, _) = char(']')(remaining)?;
-    Ok((input, Token::ArrayLiteral(elements)))
+    let end = end.ok_or_else(|| nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::TakeUntil)))?;
+    let text = dedent(&body[..end]);
+    let parts = string_parts(&text).ok_or_else(|| nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Escaped)))?;
+    Ok((&body[end + 3..], Token::InterpolatedString(parts)))
+}
+
+/// Text and `${}` interpolations, for a string body whose quotes are already gone
+fn string_parts(mut text: &str) -> Option<Vec<crate::parser::ast::StringPart>> {
+    let mut parts = Vec::new();
+    loop {
+        let (rest, literal) = text_until(text, false).ok()?;
+        if !literal.is_empty() {
+            parts.push(crate::parser::ast::StringPart::Text(literal));
+        }
+        let Some(rest) = rest.strip_prefix("${") else {
+            return Some(parts);
+        };
+        let end = rest.find('}')?;
+        parts.push(crate::parser::ast::StringPart::Interpolation(crate::parser::ast::Expression::Identifier(rest[..end].to_string())));
+        text = &rest[end + 1..];
+    }
+}
+
+/// `r"C:\shaders"` keeps backslashes as they are; `r#"say "hi""#` can hold quotes, with as many
+/// `#`s as it needs; `r"""..."""` spans lines like `"""` strings. Nothing inside is interpolated
+fn raw_string(input: &str) -> IResult<&str, Token> {
+    let (rest, _) = char('r')(input)?;
+    let (rest, hashes) = take_while(|c| c == '#')(rest)?;
+    let multiline = rest.starts_with("\"\"\"");
+    let quotes = if multiline { "\"\"\"" } else { "\"" };
+    let (body, _) = tag(quotes)(rest)?;
+    let close = format!("{}{}", quotes, hashes);
+    let end = body.find(&close).ok_or_else(|| nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::TakeUntil)))?;
+    let text = if multiline { dedent(&body[..end]) } else { body[..end].to_string() };
+    Ok((&body[end + close.len()..], Token::String(text)))
+}
+
+/// A multiline string's text without the line break after its opening quotes, the whitespace
+/// before its closing ones, or the indentation its lines have in common
+fn dedent(text: &str) -> String {
+    let text = text.strip_prefix("\r\n").or_else(|| text.strip_prefix('\n')).unwrap_or(text);
+    let text = match text.rfind('\n') {
+        Some(last) if text[last + 1..].trim().is_empty() => text[..last].trim_end_matches('\r'),
+        _ => text,
+    };
+    let indent = text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn identifier(input: &str) -> IResult<&str, Token> {
@@ -311,8 +664,9 @@ fn float_with_unit(input: &str) -> IResult<&str, Token> {
 
 fn unit_suffix(input: &str) -> IResult<&str, &str> {
     alt((
-        tag("px"), tag("s"), tag("ms"), tag("Hz"), tag("kHz"), 
-        tag("degrees"), tag("radians"), tag("percent"), tag("%")
+        tag("px"), tag("seconds"), tag("second"), tag("s"), tag("ms"), tag("Hz"), tag("kHz"), tag("hz"), tag("khz"),
+        tag("degrees"), tag("radians"), tag("percent"), tag("%"),
+        tag("notes"), tag("note"), tag("beats"), tag("beat"), tag("bars"), tag("bar")
     ))(input)
 }
 
@@ -321,12 +675,20 @@ fn operator(input: &str) -> IResult<&str, Token> {
         map(tag("=>"), |_| Token::Arrow),
         map(tag("=="), |_| Token::Equals),
         map(tag("!="), |_| Token::NotEqual),
+        map(tag("!"), |_| Token::Not),
+        alt((
+            map(tag("??"), |_| Token::NullCoalesce),
+            map(tag("?."), |_| Token::QuestionDot),
+        )),
         map(tag("<="), |_| Token::LessThanOrEqual),
         map(tag(">="), |_| Token::GreaterThanOrEqual),
         map(tag("&&"), |_| Token::LogicalAnd),
         map(tag("||"), |_| Token::LogicalOr),
-        map(tag("..="), |_| Token::RangeInclusive),
-        map(tag(".."), |_| Token::Range),
+        alt((
+            map(tag("..."), |_| Token::Spread),
+            map(tag("..="), |_| Token::RangeInclusive),
+            map(tag(".."), |_| Token::Range),
+        )),
         map(tag("<>"), |_| Token::BiDirectionalPipe),
         map(tag("<"), |_| Token::LessThan),
         map(tag(">"), |_| Token::GreaterThan),
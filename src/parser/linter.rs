@@ -0,0 +1,581 @@
+/// Lints: things that run but probably aren't what was meant
+///
+/// Nothing here stops a script. The type checker reports what can't work; the linter points out
+/// what works badly on stage: variables set and never used, a `loop {}` with nothing in it, a
+/// stream hidden by another variable of the same name, calls that wait on files, devices or the
+/// network inside an `every()` fast enough to be audio rate, and sample rates written out by
+/// hand. Each warning is a `SynthesisError` with suggestions, pointing at its line when the
+/// linter was made `with_spans`.
+use crate::errors::{ErrorKind, SourceLocation, SynthesisError};
+use crate::parser::ast::*;
+use crate::parser::lexer::{Span, Token};
+use std::collections::{HashMap, HashSet};
+
+/// An `every()` at least this fast (in seconds) runs at audio rate, where nothing may block
+pub const AUDIO_RATE_PERIOD: f64 = 0.01;
+
+/// Numbers that are almost certainly sample rates when they turn up in a script
+const SAMPLE_RATES: &[i64] = &[11025, 22050, 44100, 48000, 88200, 96000, 176400, 192000];
+
+/// Module calls that wait on files, devices or the network before they return
+const BLOCKING_CALLS: &[(&str, &str)] = &[
+    ("Audio", "load_file"),
+    ("Audio", "open_input"),
+    ("Audio", "open_output"),
+    ("Audio", "devices"),
+    ("Audio", "configure"),
+    ("Assets", "load"),
+    ("Assets", "get"),
+    ("Assets", "reload"),
+    ("Text", "load_lyrics"),
+    ("Environment", "location"),
+    ("Environment", "weather"),
+    ("Web", "serve"),
+    ("Web", "export_webapp"),
+    ("Depth", "open"),
+    ("Stream", "receive"),
+    ("Graphics", "capture_screen"),
+    ("Graphics", "capture_window"),
+];
+
+/// Module calls that give back a stream
+const STREAM_SOURCES: &[(&str, &str)] = &[
+    ("Audio", "mic_input"),
+    ("Audio", "load_file"),
+    ("Graphics", "capture_screen"),
+    ("Graphics", "capture_window"),
+    ("Stream", "receive"),
+];
+
+/// Module calls whose `name:` names the streams they publish, so two can't share one
+const NAMED_STREAMS: &[(&str, &str)] = &[
+    ("Generate", "attractor"),
+    ("Vision", "analyze"),
+    ("Stream", "receive"),
+];
+
+pub struct Linter {
+    tokens: Vec<Token>,
+    spans: Vec<Span>,
+    filename: String,
+    /// Tokens already pointed at, so a second warning of the same kind finds the next one
+    reported: HashSet<usize>,
+    /// Every name something reads
+    reads: HashSet<String>,
+    /// Variables the script sets, in the order they're first set
+    assigned: Vec<String>,
+    /// Top-level variables that hold a stream, and the statement that set each up
+    streams: HashMap<String, usize>,
+    /// `name:`s given to the calls in `NAMED_STREAMS`, and how many times
+    stream_names: HashMap<(String, String, String), usize>,
+    /// Constant numbers, so `every(1 / RATE)` can be worked out
+    constants: HashMap<String, f64>,
+    /// The period of the `every()` being linted, if it runs at audio rate
+    audio_rate: Option<f64>,
+    warnings: Vec<SynthesisError>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self {
+            tokens: Vec::new(),
+            spans: Vec::new(),
+            filename: String::new(),
+            reported: HashSet::new(),
+            reads: HashSet::new(),
+            assigned: Vec::new(),
+            streams: HashMap::new(),
+            stream_names: HashMap::new(),
+            constants: HashMap::new(),
+            audio_rate: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// A linter whose warnings point at the line and column `lexer::tokenize_with_spans` recorded
+    pub fn with_spans(tokens: &[Token], spans: &[Span], filename: &str) -> Self {
+        Self {
+            tokens: tokens.to_vec(),
+            spans: spans.to_vec(),
+            filename: filename.to_string(),
+            ..Self::new()
+        }
+    }
+
+    /// Every warning for the program, in the order they appear in the script
+    pub fn lint(mut self, program: &Program) -> Vec<SynthesisError> {
+        for item in &program.items {
+            match item {
+                Item::Statement(statement) => self.note_stream(statement),
+                Item::Loop(LoopBlock { body }) => body.iter().for_each(|statement| self.note_stream(statement)),
+                _ => {}
+            }
+        }
+        for item in &program.items {
+            match item {
+                Item::Import(_) => {}
+                Item::Statement(statement) => self.statement(statement),
+                Item::Loop(LoopBlock { body }) => {
+                    if body.is_empty() {
+                        self.empty_loop();
+                    }
+                    self.block(body);
+                }
+                Item::Function(function) => self.function(function),
+                Item::Class(ClassDef { fields, methods, .. }) | Item::Struct(StructDef { fields, methods, .. }) => {
+                    fields.iter().filter_map(|field| field.default_value.as_ref()).for_each(|value| self.expression(value));
+                    methods.iter().for_each(|method| self.function(method));
+                }
+                Item::Synth(synth) => {
+                    self.parameters(&synth.parameters);
+                    self.block(&synth.body);
+                }
+            }
+        }
+        self.unused_variables();
+
+        // Warnings without a place go last
+        let mut warnings = self.warnings;
+        warnings.sort_by_key(|warning| warning.location.as_ref().map_or((usize::MAX, 0), |location| (location.line, location.column)));
+        warnings
+    }
+
+    /// Remember a top-level variable set up with a stream, so a later one can't hide it unnoticed
+    fn note_stream(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assignment { name, value } | Statement::Let { name, value: Some(value), .. }
+                if is_stream(value) && !self.streams.contains_key(name) =>
+            {
+                self.streams.insert(name.clone(), statement as *const Statement as usize);
+            }
+            _ => {}
+        }
+    }
+
+    fn function(&mut self, function: &FunctionDef) {
+        self.parameters(&function.parameters);
+        self.block(&function.body);
+    }
+
+    fn parameters(&mut self, parameters: &[Parameter]) {
+        parameters.iter().filter_map(|parameter| parameter.default_value.as_ref()).for_each(|value| self.expression(value));
+    }
+
+    fn block(&mut self, body: &[Statement]) {
+        body.iter().for_each(|statement| self.statement(statement));
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assignment { name, value } => {
+                self.expression(value);
+                self.assign(name);
+            }
+            Statement::Let { name, value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+                self.shadowing(name, statement, Token::Let);
+                self.assign(name);
+            }
+            Statement::Const { name, value, .. } => {
+                if let Some(number) = self.constant(value) {
+                    self.constants.insert(name.clone(), number);
+                }
+                // `const SAMPLE_RATE = 48000` is exactly how a sample rate should be written
+                match value {
+                    Expression::Literal(literal) if sample_rate(literal).is_some() => {
+                        self.locate(|tokens, i| is_number(&tokens[i], literal));
+                    }
+                    _ => self.expression(value),
+                }
+                self.assign(name);
+            }
+            Statement::LetDestructure { pattern, value } => {
+                self.expression(value);
+                match pattern {
+                    Destructure::Array(names) => names.iter().flatten().for_each(|name| self.assign(name)),
+                    Destructure::Object(bindings) => bindings.iter().for_each(|(_, name)| self.assign(name)),
+                }
+            }
+            Statement::FieldAssignment { target, value, .. } => {
+                self.reads.insert(target.clone());
+                self.expression(value);
+            }
+            Statement::Expression(value) | Statement::Return(Some(value)) => self.expression(value),
+            Statement::If { condition, then_branch, else_branch } => {
+                self.expression(condition);
+                self.block(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.block(else_branch);
+                }
+            }
+            Statement::Match { expression, arms } => {
+                self.expression(expression);
+                self.arms(arms);
+            }
+            Statement::Every { duration, body } => {
+                self.expression(duration);
+                let period = self.period(duration).filter(|&period| period < AUDIO_RATE_PERIOD);
+                let outer = self.audio_rate;
+                self.audio_rate = outer.or(period);
+                self.block(body);
+                self.audio_rate = outer;
+            }
+            Statement::After { duration: condition, body } | Statement::While { condition, body } => {
+                self.expression(condition);
+                self.block(body);
+            }
+            Statement::For { variable, iterable, body } => {
+                self.expression(iterable);
+                self.shadowing(variable, statement, Token::For);
+                self.block(body);
+            }
+            Statement::Async { body } => {
+                // An async block runs on its own, so it's where blocking calls belong
+                let outer = self.audio_rate.take();
+                self.block(body);
+                self.audio_rate = outer;
+            }
+            Statement::Try { body, handler, .. } => {
+                self.block(body);
+                self.block(handler);
+            }
+            Statement::Return(None) | Statement::Break | Statement::Continue | Statement::Capabilities(_) => {}
+        }
+    }
+
+    fn arms(&mut self, arms: &[MatchArm]) {
+        for arm in arms {
+            if let Pattern::Identifier(name) = &arm.pattern {
+                self.reads.insert(name.clone());
+            }
+            if let Some(guard) = &arm.guard {
+                self.expression(guard);
+            }
+            self.block(&arm.body);
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(Literal::String(text)) => {
+                // `config { remote: ["speed"] }` reads a variable by name
+                self.reads.insert(text.clone());
+            }
+            Expression::Literal(literal) => {
+                if let Some(rate) = sample_rate(literal) {
+                    self.magic_sample_rate(literal, rate);
+                }
+            }
+            Expression::Identifier(name) => {
+                self.reads.insert(name.clone());
+            }
+            Expression::FunctionCall { module, name, args, named_args } => {
+                match module {
+                    Some(module) => self.module_call(module, name, named_args),
+                    None if name == "wait" => self.blocking("wait()", |tokens, i| {
+                        tokens[i] == Token::Identifier("wait".to_string()) && tokens.get(i + 1) == Some(&Token::LeftParen)
+                    }),
+                    None => {
+                        self.reads.insert(name.clone());
+                    }
+                }
+                args.iter().for_each(|arg| self.expression(arg));
+                for (key, value) in sorted(named_args) {
+                    // `Audio.configure(sample_rate: 48000)` already says what the number is
+                    match value {
+                        Expression::Literal(literal) if key.contains("rate") && sample_rate(literal).is_some() => {
+                            self.locate(|tokens, i| is_number(&tokens[i], literal));
+                        }
+                        _ => self.expression(value),
+                    }
+                }
+            }
+            Expression::MethodCall { object, args, named_args, .. } => {
+                self.expression(object);
+                args.iter().for_each(|arg| self.expression(arg));
+                sorted(named_args).into_iter().for_each(|(_, value)| self.expression(value));
+            }
+            Expression::BinaryOp { left, right, .. }
+            | Expression::Pipe { left, right }
+            | Expression::BiDirectionalPipe { left, right } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::ArrayAccess { array, index } => {
+                self.expression(array);
+                self.expression(index);
+            }
+            Expression::Range { start, end, .. } => {
+                self.expression(start);
+                self.expression(end);
+            }
+            Expression::UnaryOp { operand: inner, .. }
+            | Expression::Spread(inner)
+            | Expression::StreamBranch { stream: inner, .. }
+            | Expression::UnitValue { value: inner, .. }
+            | Expression::TypeCast { expr: inner, .. }
+            | Expression::Lambda { body: inner, .. } => self.expression(inner),
+            Expression::Block { fields } | Expression::StructLiteral { fields, .. } => {
+                sorted(fields).into_iter().for_each(|(_, value)| self.expression(value));
+            }
+            Expression::StreamMerge { streams: items, .. } | Expression::ArrayLiteral(items) => {
+                items.iter().for_each(|item| self.expression(item));
+            }
+            Expression::InterpolatedString(parts) => {
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => {
+                            self.reads.insert(text.clone());
+                        }
+                        StringPart::Interpolation(inner) => self.expression(inner),
+                    }
+                }
+            }
+            Expression::ConditionalExpression { condition, true_expr, false_expr } => {
+                self.expression(condition);
+                self.expression(true_expr);
+                self.expression(false_expr);
+            }
+            Expression::MatchExpression { expr, arms } => {
+                self.expression(expr);
+                self.arms(arms);
+            }
+        }
+    }
+
+    fn module_call(&mut self, module: &str, function: &str, named_args: &HashMap<String, Expression>) {
+        if BLOCKING_CALLS.contains(&(module, function)) {
+            self.blocking(&format!("{}.{}()", module, function), |tokens, i| {
+                tokens[i] == Token::Identifier(module.to_string())
+                    && tokens.get(i + 1) == Some(&Token::Dot)
+                    && tokens.get(i + 2) == Some(&Token::Identifier(function.to_string()))
+            });
+        }
+        if !NAMED_STREAMS.contains(&(module, function)) {
+            return;
+        }
+        let Some(name) = named_args.get("name").and_then(text) else {
+            return;
+        };
+        let is_name = |tokens: &[Token], i: usize| {
+            tokens[i] == Token::Identifier("name".to_string())
+                && tokens.get(i + 1) == Some(&Token::Colon)
+                && tokens.get(i + 2).is_some_and(|token| token_text(token) == Some(name))
+        };
+        let uses = self.stream_names.entry((module.to_string(), function.to_string(), name.to_string())).or_insert(0);
+        *uses += 1;
+        match *uses {
+            1 => return,
+            // The first one is fine; it's the one after that takes its streams
+            2 => {
+                self.locate(is_name);
+            }
+            _ => {}
+        }
+        let location = self.locate(is_name);
+        self.warn(SynthesisError::new(
+            ErrorKind::InvalidStreamConnection,
+            format!("Two {}.{}() calls both name their streams \"{}\", so the second takes over the first's", module, function, name)
+        )
+        .with_suggestion(format!("Give each its own name, like name: \"{}_2\"", name)), location);
+    }
+
+    fn empty_loop(&mut self) {
+        let location = self.locate(|tokens, i| {
+            tokens[i] == Token::Loop && tokens.get(i + 1) == Some(&Token::LeftBrace) && tokens.get(i + 2) == Some(&Token::RightBrace)
+        });
+        self.warn(SynthesisError::new(
+            ErrorKind::InvalidExpression,
+            "This loop has nothing in it, so every frame draws and plays nothing"
+        )
+        .with_suggestion("Put what should happen each frame inside it, like Graphics.clear(Graphics.black)")
+        .with_suggestion("Or leave the loop out if the script only needs to run once"), location);
+    }
+
+    /// `let` or `for` giving a top-level stream's name to something else
+    fn shadowing(&mut self, name: &str, statement: &Statement, keyword: Token) {
+        match self.streams.get(name) {
+            Some(&defined) if defined != statement as *const Statement as usize => {}
+            _ => return,
+        }
+        let location = self.locate(|tokens, i| tokens[i] == keyword && tokens.get(i + 1) == Some(&Token::Identifier(name.to_string())));
+        self.warn(SynthesisError::new(
+            ErrorKind::InvalidStreamConnection,
+            format!("'{}' already holds a stream, and this hides it", name)
+        )
+        .with_suggestion(format!("Give this one its own name, like {}_2, so it's clear which stream is meant", name))
+        .with_suggestion(format!("To change the stream itself, assign to it: {} = ...", name)), location);
+    }
+
+    fn blocking(&mut self, call: &str, found: impl Fn(&[Token], usize) -> bool) {
+        let Some(period) = self.audio_rate else {
+            return;
+        };
+        let location = self.locate(found);
+        self.warn(SynthesisError::new(
+            ErrorKind::RealTimeViolation,
+            format!("{} can block, and this every() runs about {} times a second", call, (1.0 / period).round())
+        )
+        .with_suggestion("Call it once before the loop and keep what it gives back in a variable")
+        .with_suggestion("Or move it into an async { } block so it waits on its own"), location);
+    }
+
+    fn magic_sample_rate(&mut self, literal: &Literal, rate: i64) {
+        let location = self.locate(|tokens, i| is_number(&tokens[i], literal));
+        self.warn(SynthesisError::new(
+            ErrorKind::SampleRateError,
+            format!("{} looks like a sample rate written out by hand", rate)
+        )
+        .with_suggestion(format!("Name it once with const SAMPLE_RATE = {} and use SAMPLE_RATE", rate))
+        .with_suggestion("Then moving to an interface with another rate is one change"), location);
+    }
+
+    fn unused_variables(&mut self) {
+        let unused: Vec<String> = self.assigned.iter()
+            .filter(|name| !self.reads.contains(*name))
+            // `'` marks one a macro renamed, and `config` is read by the runtime
+            .filter(|name| !name.contains('\'') && *name != "config")
+            .cloned()
+            .collect();
+        for name in unused {
+            let defined = |tokens: &[Token], i: usize| {
+                tokens[i] == Token::Identifier(name.clone())
+                    && (tokens.get(i + 1) == Some(&Token::Assignment)
+                        || i.checked_sub(1).is_some_and(|before| matches!(tokens[before], Token::Let | Token::Const)))
+            };
+            let location = match self.locate(defined) {
+                Some(location) => Some(location),
+                None => self.locate(|tokens, i| tokens[i] == Token::Identifier(name.clone())),
+            };
+            self.warn(SynthesisError::new(
+                ErrorKind::InvalidExpression,
+                format!("'{}' is set but never used", name)
+            )
+            .with_suggestion("Remove it if it's left over from an earlier version")
+            .with_suggestion(format!("If a script that imports this one reads {}, it can stay", name)), location);
+        }
+    }
+
+    fn assign(&mut self, name: &str) {
+        if !self.assigned.iter().any(|assigned| assigned == name) {
+            self.assigned.push(name.to_string());
+        }
+    }
+
+    /// Where the first token `found` picks out that no warning has pointed at yet is
+    fn locate(&mut self, found: impl Fn(&[Token], usize) -> bool) -> Option<SourceLocation> {
+        let index = (0..self.tokens.len()).find(|&i| !self.reported.contains(&i) && found(&self.tokens, i))?;
+        self.reported.insert(index);
+        self.spans.get(index).map(|span| SourceLocation {
+            line: span.line,
+            column: span.column,
+            filename: self.filename.clone(),
+        })
+    }
+
+    fn warn(&mut self, warning: SynthesisError, location: Option<SourceLocation>) {
+        let warning = warning.with_docs("https://synthesis-lang.org/docs/lint");
+        self.warnings.push(match location {
+            Some(location) => warning.with_location(location),
+            None => warning,
+        });
+    }
+
+    /// How many seconds an `every()` waits, if it can be worked out without running anything
+    fn period(&self, duration: &Expression) -> Option<f64> {
+        match duration {
+            Expression::UnitValue { value, unit } => {
+                crate::runtime::units::UnitValue::from_string(self.constant(value)?, unit)?.to_seconds(120.0, 4.0)
+            }
+            other => self.constant(other),
+        }
+    }
+
+    fn constant(&self, expression: &Expression) -> Option<f64> {
+        match expression {
+            Expression::Literal(Literal::Integer(n) | Literal::BasedInteger(n, _)) => Some(*n as f64),
+            Expression::Literal(Literal::Float(n)) => Some(*n),
+            Expression::Identifier(name) => self.constants.get(name).copied(),
+            Expression::UnaryOp { op: UnaryOperator::Negate, operand } => self.constant(operand).map(|n| -n),
+            Expression::BinaryOp { left, op, right } => {
+                let (left, right) = (self.constant(left)?, self.constant(right)?);
+                match op {
+                    BinaryOperator::Add => Some(left + right),
+                    BinaryOperator::Subtract => Some(left - right),
+                    BinaryOperator::Multiply => Some(left * right),
+                    BinaryOperator::Divide if right != 0.0 => Some(left / right),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether an expression gives a stream: a pipe, a branch or merge, or a module call that makes one
+fn is_stream(expression: &Expression) -> bool {
+    match expression {
+        Expression::Pipe { .. } | Expression::BiDirectionalPipe { .. } | Expression::StreamBranch { .. } | Expression::StreamMerge { .. } => true,
+        Expression::FunctionCall { module: Some(module), name, .. } => STREAM_SOURCES.contains(&(module.as_str(), name.as_str())),
+        _ => false,
+    }
+}
+
+fn sample_rate(literal: &Literal) -> Option<i64> {
+    let rate = match literal {
+        Literal::Integer(n) | Literal::BasedInteger(n, _) => *n,
+        Literal::Float(n) if n.fract() == 0.0 => *n as i64,
+        _ => return None,
+    };
+    SAMPLE_RATES.contains(&rate).then_some(rate)
+}
+
+fn is_number(token: &Token, literal: &Literal) -> bool {
+    match (token, literal) {
+        (Token::Integer(a), Literal::Integer(b)) | (Token::BasedInteger(a, _), Literal::BasedInteger(b, _)) => a == b,
+        (Token::Float(a), Literal::Float(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn text(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Literal(Literal::String(text)) => Some(text),
+        Expression::InterpolatedString(parts) => match parts.as_slice() {
+            [StringPart::Text(text)] => Some(text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn token_text(token: &Token) -> Option<&str> {
+    match token {
+        Token::String(text) => Some(text),
+        Token::InterpolatedString(parts) => match parts.as_slice() {
+            [StringPart::Text(text)] => Some(text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Named arguments and fields in a fixed order, so warnings come out the same every time
+fn sorted(fields: &HashMap<String, Expression>) -> Vec<(&String, &Expression)> {
+    let mut fields: Vec<_> = fields.iter().collect();
+    fields.sort_by_key(|(key, _)| *key);
+    fields
+}
+
+/// Lint a parsed program; an empty list means nothing looks off
+pub fn lint(program: &Program) -> Vec<SynthesisError> {
+    Linter::new().lint(program)
+}
@@ -0,0 +1,192 @@
+/// Macros: `macro drumkit(name) { ... }` packages up a run of statements, and `drumkit(kick)` pastes
+/// them in while the script is parsed, with `kick` wherever the body says `name`.
+///
+/// Expansion is hygienic. Variables the body sets for itself, with `=`, `let`, `const`, `for` or
+/// `catch`, are renamed for each place the macro is used, so they never clash with the caller's
+/// variables or with another use of the same macro. Anything meant for the caller goes through a
+/// parameter: `drumkit(kick)` assigning to `name` sets the caller's `kick`.
+///
+/// Macros can be shared between scripts: `import lib/fx` makes the macros `lib/fx.syn` defines
+/// available to the importing script as well as its functions and variables.
+use crate::parser::ast::{Expression, MatchArm, StringPart};
+use crate::parser::lexer::Token;
+use std::collections::HashSet;
+
+/// How deep macros can use other macros, so one that uses itself stops with an error
+pub const MAX_MACRO_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub params: Vec<String>,
+    /// The tokens between the braces, expanded afresh at each use
+    pub body: Vec<Token>,
+}
+
+impl Macro {
+    /// The body for the `id`th expansion, with `args` (as tokens and as parsed expressions) in
+    /// place of the parameters and the body's own variables renamed
+    pub fn expand(&self, args: &[Vec<Token>], values: &[Expression], id: usize) -> Vec<Token> {
+        let locals = self.introduced_names();
+        let hygienic = |name: &str| format!("{}'{}{}", name, self.name, id);
+        let mut tokens = Vec::with_capacity(self.body.len());
+
+        for (i, token) in self.body.iter().enumerate() {
+            match token {
+                Token::Identifier(name) if is_variable(&self.body, i) => {
+                    if let Some(index) = self.params.iter().position(|param| param == name) {
+                        let arg = &args[index];
+                        if arg.len() == 1 {
+                            tokens.push(arg[0].clone());
+                        } else {
+                            tokens.push(Token::LeftParen);
+                            tokens.extend(arg.iter().cloned());
+                            tokens.push(Token::RightParen);
+                        }
+                    } else if locals.contains(name) {
+                        tokens.push(Token::Identifier(hygienic(name)));
+                    } else {
+                        tokens.push(token.clone());
+                    }
+                }
+                // `"${name}"` was parsed by the lexer, so its names are replaced in the expression
+                Token::InterpolatedString(parts) => {
+                    let replace = |name: &str| match self.params.iter().position(|param| param == name) {
+                        Some(index) => Some(values[index].clone()),
+                        None => locals.contains(name).then(|| Expression::Identifier(hygienic(name))),
+                    };
+                    let parts = parts.iter().map(|part| match part {
+                        StringPart::Interpolation(expr) => {
+                            let mut expr = expr.clone();
+                            rename(&mut expr, &replace);
+                            StringPart::Interpolation(expr)
+                        }
+                        text => text.clone(),
+                    }).collect();
+                    tokens.push(Token::InterpolatedString(parts));
+                }
+                _ => tokens.push(token.clone()),
+            }
+        }
+        tokens
+    }
+
+    /// Names the body gives values to that aren't parameters
+    fn introduced_names(&self) -> HashSet<String> {
+        let body = &self.body;
+        let mut names = HashSet::new();
+        let mut i = 0;
+        while i < body.len() {
+            match (&body[i], body.get(i + 1)) {
+                (Token::Identifier(name), Some(Token::Assignment)) if is_variable(body, i) => {
+                    names.insert(name.clone());
+                }
+                (Token::Let | Token::Const | Token::For, Some(Token::Identifier(name))) => {
+                    names.insert(name.clone());
+                }
+                (Token::Catch, Some(Token::LeftParen)) => {
+                    if let Some(Token::Identifier(name)) = body.get(i + 2) {
+                        names.insert(name.clone());
+                    }
+                }
+                (Token::Catch, Some(Token::Identifier(name))) => {
+                    names.insert(name.clone());
+                }
+                // `let [low, _, high]` and `let {left, right: r}`
+                (Token::Let, Some(open @ (Token::LeftBracket | Token::LeftBrace))) => {
+                    let close = if *open == Token::LeftBracket { Token::RightBracket } else { Token::RightBrace };
+                    i += 2;
+                    while i < body.len() && body[i] != close {
+                        if let Token::Identifier(name) = &body[i] {
+                            if body.get(i + 1) != Some(&Token::Colon) {
+                                names.insert(name.clone());
+                            }
+                        }
+                        i += 1;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        names.retain(|name| !self.params.contains(name));
+        names
+    }
+}
+
+/// Whether the identifier at `i` names a variable, rather than a field after `.` or a key before `:`
+fn is_variable(tokens: &[Token], i: usize) -> bool {
+    let before = i.checked_sub(1).map(|before| &tokens[before]);
+    if matches!(before, Some(Token::Dot | Token::QuestionDot)) {
+        return false;
+    }
+    // `let gain: Number = ...` is still a variable
+    tokens.get(i + 1) != Some(&Token::Colon) || matches!(before, Some(Token::Let | Token::Const))
+}
+
+/// Swap the names `replace` knows for what it gives back, throughout `expr`
+fn rename(expr: &mut Expression, replace: &dyn Fn(&str) -> Option<Expression>) {
+    match expr {
+        Expression::Identifier(name) => {
+            if let Some(replacement) = replace(name) {
+                *expr = replacement;
+            }
+        }
+        Expression::Literal(_) => {}
+        Expression::FunctionCall { args, named_args, .. } => {
+            args.iter_mut().chain(named_args.values_mut()).for_each(|arg| rename(arg, replace));
+        }
+        Expression::MethodCall { object, args, named_args, .. } => {
+            rename(object, replace);
+            args.iter_mut().chain(named_args.values_mut()).for_each(|arg| rename(arg, replace));
+        }
+        Expression::BinaryOp { left, right, .. }
+        | Expression::Pipe { left, right }
+        | Expression::BiDirectionalPipe { left, right } => {
+            rename(left, replace);
+            rename(right, replace);
+        }
+        Expression::ArrayAccess { array, index } => {
+            rename(array, replace);
+            rename(index, replace);
+        }
+        Expression::Range { start, end, .. } => {
+            rename(start, replace);
+            rename(end, replace);
+        }
+        Expression::UnaryOp { operand: inner, .. }
+        | Expression::Spread(inner)
+        | Expression::StreamBranch { stream: inner, .. }
+        | Expression::UnitValue { value: inner, .. }
+        | Expression::TypeCast { expr: inner, .. } => rename(inner, replace),
+        Expression::Block { fields } | Expression::StructLiteral { fields, .. } => {
+            fields.values_mut().for_each(|field| rename(field, replace));
+        }
+        Expression::StreamMerge { streams: items, .. } | Expression::ArrayLiteral(items) => {
+            items.iter_mut().for_each(|item| rename(item, replace));
+        }
+        Expression::Lambda { parameters, body } => {
+            // The lambda's own parameters hide whatever they share a name with
+            let parameters = parameters.clone();
+            rename(body, &|name: &str| if parameters.iter().any(|param| param == name) { None } else { replace(name) });
+        }
+        Expression::InterpolatedString(parts) => {
+            for part in parts {
+                if let StringPart::Interpolation(inner) = part {
+                    rename(inner, replace);
+                }
+            }
+        }
+        Expression::ConditionalExpression { condition, true_expr, false_expr } => {
+            rename(condition, replace);
+            rename(true_expr, replace);
+            rename(false_expr, replace);
+        }
+        Expression::MatchExpression { expr, arms } => {
+            rename(expr, replace);
+            arms.iter_mut()
+                .filter_map(|arm: &mut MatchArm| arm.guard.as_mut())
+                .for_each(|guard| rename(guard, replace));
+        }
+    }
+}
@@ -1,10 +1,25 @@
 pub mod lexer;
+#[allow(clippy::module_inception)]
 pub mod parser;
 pub mod ast;
+pub mod processing;
+pub mod checker;
+pub mod inference;
+pub mod macros;
+pub mod formatter;
+pub mod linter;
+pub mod incremental;
 
 #[cfg(test)]
 mod parser_test;
 
 pub use lexer::*;
 pub use parser::*;
-pub use ast::*;
\ No newline at end of file
+pub use ast::*;
+pub use processing::*;
+pub use checker::{check_types, StaticType, TypeChecker};
+pub use inference::{infer_types, Coercion, Inference, InferredType, TypeInference};
+pub use macros::Macro;
+pub use formatter::format;
+pub use linter::{lint, Linter};
+pub use incremental::{IncrementalParser, ProgramDelta, TextEdit};
\ No newline at end of file
@@ -1,32 +1,98 @@
-use crate::parser::{ast::*, lexer::Token};
+use crate::parser::{ast::*, lexer::{Span, Token}};
+use crate::parser::macros::{Macro, MAX_MACRO_DEPTH};
 use crate::errors::{SynthesisError, ErrorKind, SourceLocation};
 use std::collections::HashMap;
+use std::path::Path;
 
 pub struct Parser<'a> {
     tokens: &'a [Token],
+    spans: &'a [Span],
+    filename: &'a str,
     position: usize,
+    /// Macros defined so far, in this file or one it imports
+    macros: HashMap<String, Macro>,
+    /// Macro uses expanded so far, numbering each one's renamed variables
+    expansions: usize,
+    /// How many macros deep the tokens being parsed came from
+    depth: usize,
+    /// Syntax errors parsing carried on past, in the order they were found
+    errors: Vec<SynthesisError>,
 }
 
 impl<'a> Parser<'a> {
+    /// A parser over tokens that don't come from a source file, such as hand-built ones in tests;
+    /// its errors can't say which line they're on, so anything parsing a file uses `with_spans`
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, position: 0 }
+        Self::with_spans(tokens, &[], "")
+    }
+
+    /// A parser whose errors point at the line and column `lexer::tokenize_with_spans` recorded
+    pub fn with_spans(tokens: &'a [Token], spans: &'a [Span], filename: &'a str) -> Self {
+        Self { tokens, spans, filename, position: 0, macros: HashMap::new(), expansions: 0, depth: 0, errors: Vec::new() }
+    }
+
+    /// The macros this parser has seen defined or imported
+    pub fn macros(&self) -> &HashMap<String, Macro> {
+        &self.macros
     }
     
+    /// The script as a program, or the first syntax error in it
     pub fn parse(&mut self) -> crate::Result<Program> {
-        let items = self.parse_items()?;
+        self.parse_all().map_err(|mut errors| errors.remove(0))
+    }
+    
+    /// The script as a program, or every syntax error in it: after a mistake parsing skips to
+    /// the next statement and carries on, so a whole file can be fixed in one go
+    pub fn parse_all(&mut self) -> Result<Program, Vec<SynthesisError>> {
+        let items = self.parse_located_items()?.into_iter().flat_map(|(_, items)| items).collect();
         Ok(Program { items })
     }
     
-    fn parse_items(&mut self) -> crate::Result<Vec<Item>> {
+    /// What each top-level item parsed into, with the index of the token it starts at, so an
+    /// editor can reparse one at a time. A macro definition parses into nothing and a macro use
+    /// into its statements
+    pub fn parse_located_items(&mut self) -> Result<Vec<(usize, Vec<Item>)>, Vec<SynthesisError>> {
+        let items = self.parse_items();
+        if self.errors.is_empty() {
+            Ok(items)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+    
+    fn parse_items(&mut self) -> Vec<(usize, Vec<Item>)> {
         let mut items = Vec::new();
         
         while !self.is_at_end() {
-            if let Some(item) = self.parse_item()? {
-                items.push(item);
+            let start = self.position;
+            let parsed = if self.at_macro_call() {
+                self.expand_macro().map(|stmts| stmts.into_iter().map(Item::Statement).collect())
+            } else {
+                self.parse_item().map(|item| item.into_iter().collect::<Vec<_>>())
+            };
+            match parsed {
+                Ok(parsed) => items.push((start, parsed)),
+                Err(error) => {
+                    self.record_error(error);
+                    self.synchronize_after_error(start);
+                    // A `}` left over from the broken statement has no block to close out here
+                    if self.match_token(&Token::RightBrace) {
+                        self.advance();
+                    }
+                }
             }
         }
         
-        Ok(items)
+        items
+    }
+    
+    /// Keep an error to report once parsing is done, pointing at where it was found
+    fn record_error(&mut self, error: SynthesisError) {
+        let error = match error.location {
+            None if !self.spans.is_empty() => error.with_location(self.current_location(self.filename)),
+            _ => error,
+        };
+        self.errors.push(error);
     }
     
     fn parse_item(&mut self) -> crate::Result<Option<Item>> {
@@ -43,6 +109,40 @@ impl<'a> Parser<'a> {
                 let loop_block = self.parse_loop()?;
                 Ok(Some(Item::Loop(loop_block)))
             }
+            Some(Token::Func) => {
+                let function = self.parse_function_def()?;
+                Ok(Some(Item::Function(function)))
+            }
+            Some(Token::Macro) => {
+                self.parse_macro_def()?;
+                Ok(None)
+            }
+            Some(Token::Struct) => {
+                let struct_def = self.parse_struct_def()?;
+                Ok(Some(Item::Struct(struct_def)))
+            }
+            Some(Token::Class) => {
+                let class_def = self.parse_class_def()?;
+                Ok(Some(Item::Class(class_def)))
+            }
+            Some(Token::DocComment(_)) => {
+                let doc = self.take_doc_comments();
+                match self.current_token() {
+                    Some(Token::Func) => {
+                        let mut function = self.parse_function_def()?;
+                        function.doc = doc;
+                        Ok(Some(Item::Function(function)))
+                    }
+                    // Only functions keep their docs; elsewhere they read like ordinary comments
+                    _ => Ok(None),
+                }
+            }
+            Some(Token::Identifier(name)) if name == "synth"
+                && matches!(self.peek_token(1), Some(Token::Identifier(_)))
+                && self.peek_token(2) == Some(&Token::LeftParen) => {
+                let synth = self.parse_synth_def()?;
+                Ok(Some(Item::Synth(synth)))
+            }
             _ => {
                 let stmt = self.parse_statement()?;
                 Ok(Some(Item::Statement(stmt)))
@@ -54,10 +154,33 @@ impl<'a> Parser<'a> {
         self.consume_token(Token::Import)?;
         
         let module = match self.current_token() {
+            // `import lib/drums` for a file in a folder
             Some(Token::Identifier(name)) => {
-                let name = name.clone();
+                let mut path = name.clone();
                 self.advance();
-                name
+                while self.match_token(&Token::Divide) {
+                    match self.peek_token(1) {
+                        Some(Token::Identifier(part)) => path = format!("{}/{}", path, part),
+                        _ => break,
+                    }
+                    self.advance();
+                    self.advance();
+                }
+                path
+            }
+            // `import "../shared/util.syn"` for paths that aren't plain names
+            Some(Token::String(path)) => {
+                let path = path.clone();
+                self.advance();
+                path
+            }
+            Some(Token::InterpolatedString(parts)) if parts.iter().all(|p| matches!(p, StringPart::Text(_))) => {
+                let path = parts.iter().map(|p| match p {
+                    StringPart::Text(text) => text.as_str(),
+                    _ => "",
+                }).collect();
+                self.advance();
+                path
             }
             _ => return Err(SynthesisError::new(
                 ErrorKind::SyntaxError,
@@ -65,6 +188,7 @@ impl<'a> Parser<'a> {
             )
             .with_suggestion("Add a module name like: import Audio")
             .with_suggestion("Available modules: Audio, Graphics, GUI, Hardware, Math, Time")
+            .with_suggestion("Or import one of your own files: import lib/drums")
             .with_docs("https://synthesis-lang.org/docs/modules")),
         };
         
@@ -82,7 +206,55 @@ impl<'a> Parser<'a> {
             None
         };
         
-        Ok(ImportItem { module, items })
+        let alias = if self.match_token(&Token::As) {
+            self.advance();
+            match self.advance() {
+                Some(Token::Identifier(alias)) => Some(alias.clone()),
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    "Expected a name after 'as'"
+                )
+                .with_suggestion("Example: import Graphics as G")),
+            }
+        } else {
+            None
+        };
+        
+        self.import_macros(&module)?;
+        Ok(ImportItem { module, items, alias })
+    }
+    
+    /// Pick up the macros an imported file defines, so they can be used further down this one
+    fn import_macros(&mut self, module: &str) -> crate::Result<()> {
+        if self.filename.is_empty() {
+            return Ok(());
+        }
+        let dir = Path::new(self.filename).parent().unwrap_or(Path::new(""));
+        let path = crate::runtime::imports::import_path(dir, module);
+        // Modules and packages aren't files, and a file that can't be read is reported when it runs
+        let Ok(source) = std::fs::read_to_string(&path) else {
+            return Ok(());
+        };
+        let Ok((_, (tokens, spans))) = super::lexer::tokenize_with_spans(&source) else {
+            return Ok(());
+        };
+        let filename = path.display().to_string();
+        let mut parser = Parser::with_spans(&tokens, &spans, &filename);
+        while !parser.is_at_end() {
+            if parser.match_token(&Token::Macro) {
+                if let Err(error) = parser.parse_macro_def() {
+                    parser.record_error(error);
+                    break;
+                }
+            } else {
+                parser.advance();
+            }
+        }
+        if !parser.errors.is_empty() {
+            return Err(parser.errors.remove(0));
+        }
+        self.macros.extend(parser.macros);
+        Ok(())
     }
     
     fn parse_import_list(&mut self) -> crate::Result<Vec<String>> {
@@ -104,6 +276,454 @@ impl<'a> Parser<'a> {
         Ok(items)
     }
     
+    /// `macro drumkit(name) { name = Audio.sample("${name}.wav") |> Effects.compress() }`, kept as
+    /// tokens to expand wherever it's used
+    fn parse_macro_def(&mut self) -> crate::Result<()> {
+        self.consume_token(Token::Macro)?;
+        let name = match self.advance() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "🧩 Expected a name after 'macro'"
+            )
+            .with_suggestion("Example: macro drumkit(name) { name = Audio.sample(\"${name}.wav\") }")),
+        };
+        self.consume_token(Token::LeftParen)?;
+        
+        let mut params: Vec<String> = Vec::new();
+        while let Some(Token::Identifier(param)) = self.current_token() {
+            if params.contains(param) {
+                return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🧩 Macro {} has two parameters called {}", name, param)
+                )
+                .with_suggestion("Give each parameter its own name"));
+            }
+            params.push(param.clone());
+            self.advance();
+            if self.match_token(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume_token(Token::RightParen)?;
+        
+        // Parsing the body here reports its mistakes once, where they were written
+        self.consume_token(Token::LeftBrace)?;
+        let start = self.position;
+        self.parse_statements()?;
+        let body = self.tokens[start..self.position].to_vec();
+        self.consume_token(Token::RightBrace)?;
+        
+        self.macros.insert(name.clone(), Macro { name, params, body });
+        Ok(())
+    }
+    
+    /// Whether the tokens ahead read `name(` for a macro defined above
+    fn at_macro_call(&self) -> bool {
+        matches!(self.current_token(), Some(Token::Identifier(name)) if self.macros.contains_key(name))
+            && self.peek_token(1) == Some(&Token::LeftParen)
+    }
+    
+    /// `drumkit(kick)`: the macro's statements, with `kick` wherever it says `name`
+    fn expand_macro(&mut self) -> crate::Result<Vec<Statement>> {
+        let call = self.position;
+        let definition = match self.tokens.get(call) {
+            Some(Token::Identifier(name)) => self.macros[name].clone(),
+            _ => unreachable!("at_macro_call checked for a macro's name"),
+        };
+        self.advance();
+        self.consume_token(Token::LeftParen)?;
+        let args = self.macro_arguments(&definition.name)?;
+        
+        if args.len() != definition.params.len() {
+            return Err(SynthesisError::new(
+                ErrorKind::InvalidExpression,
+                format!("🧩 Macro {} takes {} {} but was given {}", definition.name, definition.params.len(),
+                    if definition.params.len() == 1 { "argument" } else { "arguments" }, args.len())
+            )
+            .with_suggestion(format!("Use it like: {}({})", definition.name, definition.params.join(", ")))
+            .with_location(self.location_at(call, self.filename)));
+        }
+        if self.depth >= MAX_MACRO_DEPTH {
+            return Err(SynthesisError::new(
+                ErrorKind::InvalidExpression,
+                format!("🧩 Macro {} keeps expanding into more macros", definition.name)
+            )
+            .with_suggestion("A macro that uses itself never finishes; use a func for repetition")
+            .with_location(self.location_at(call, self.filename)));
+        }
+        
+        let mut values = Vec::with_capacity(args.len());
+        // Arguments sit back to back after the name and `(`, each followed by a `,` or `)`
+        let mut start = call + 2;
+        for arg in &args {
+            let spans = self.spans.get(start..start + arg.len()).unwrap_or(&[]);
+            start += arg.len() + 1;
+            let mut parser = Parser::with_spans(arg, spans, self.filename);
+            let value = parser.parse_expression().map_err(|error| match error.location {
+                None if !spans.is_empty() => error.with_location(parser.current_location(self.filename)),
+                _ => error,
+            })?;
+            if !parser.is_at_end() {
+                return Err(SynthesisError::new(
+                    ErrorKind::InvalidExpression,
+                    format!("🧩 Each argument to macro {} should be a single value", definition.name)
+                )
+                .with_location(self.location_at(call, self.filename)));
+            }
+            values.push(value);
+        }
+        
+        self.expansions += 1;
+        let tokens = definition.expand(&args, &values, self.expansions);
+        let mut parser = Parser {
+            tokens: &tokens,
+            spans: &[],
+            filename: self.filename,
+            position: 0,
+            macros: self.macros.clone(),
+            expansions: self.expansions,
+            depth: self.depth + 1,
+            errors: Vec::new(),
+        };
+        let body = parser.parse_statements();
+        self.expansions = parser.expansions;
+        let location = self.location_at(call, self.filename);
+        let in_macro = |error: SynthesisError| match error.location {
+            None => {
+                let message = format!("{} (in macro {})", error.message, definition.name);
+                SynthesisError { message, ..error }.with_location(location.clone())
+            }
+            _ => error,
+        };
+        self.errors.extend(parser.errors.into_iter().map(in_macro));
+        body.map_err(in_macro)
+    }
+    
+    /// The tokens of each argument up to the closing `)`, split at the commas between them
+    fn macro_arguments(&mut self, name: &str) -> crate::Result<Vec<Vec<Token>>> {
+        let mut args = Vec::new();
+        let mut arg = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            let token = match self.advance() {
+                Some(token) => token.clone(),
+                None => return Err(SynthesisError::new(
+                    ErrorKind::MissingToken,
+                    format!("🧩 The call to macro {} needs a closing )", name)
+                )),
+            };
+            match token {
+                Token::RightParen | Token::Comma if depth == 0 => {
+                    let closing = token == Token::RightParen;
+                    if !arg.is_empty() {
+                        args.push(std::mem::take(&mut arg));
+                    } else if !closing || !args.is_empty() {
+                        return Err(SynthesisError::new(
+                            ErrorKind::InvalidExpression,
+                            format!("🧩 An argument to macro {} is missing", name)
+                        ));
+                    }
+                    if closing {
+                        return Ok(args);
+                    }
+                }
+                Token::LeftParen | Token::LeftBracket | Token::LeftBrace => {
+                    depth += 1;
+                    arg.push(token);
+                }
+                Token::RightParen | Token::RightBracket | Token::RightBrace => {
+                    depth = depth.saturating_sub(1);
+                    arg.push(token);
+                }
+                _ => arg.push(token),
+            }
+        }
+    }
+    
+    /// `synth pluck(note = "A3", amp = 0.5) { Synth.saw(note) * amp }`
+    fn parse_synth_def(&mut self) -> crate::Result<SynthDef> {
+        self.advance();
+        let name = match self.advance() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "🎛️ Expected a name after 'synth'"
+            )
+            .with_suggestion("Example: synth pluck(note = \"A3\") { Synth.saw(note) * Synth.env() }")),
+        };
+        self.consume_token(Token::LeftParen)?;
+        
+        let mut parameters = Vec::new();
+        while !self.match_token(&Token::RightParen) && !self.is_at_end() {
+            let param = match self.current_token() {
+                Some(Token::Identifier(param)) => param.clone(),
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🎛️ Expected a parameter name in synth {}", name)
+                )
+                .with_suggestion("Parameters are names with optional defaults: synth pad(note = \"C3\", amp = 0.3)")),
+            };
+            self.advance();
+            
+            let default_value = if self.match_token(&Token::Assignment) {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            parameters.push(Parameter { name: param, type_annotation: None, default_value });
+            
+            if self.match_token(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume_token(Token::RightParen)?;
+        
+        self.consume_token(Token::LeftBrace)?;
+        let body = self.parse_statements()?;
+        self.consume_token(Token::RightBrace)?;
+        
+        Ok(SynthDef { name, parameters, body })
+    }
+    
+    /// `func scale(x, low = 0, high: Number = 1) -> Number { return low + x * (high - low) }`, and
+    /// generic ones like `func first<T>(items: List<T>) -> T { return items[0] }`
+    fn parse_function_def(&mut self) -> crate::Result<FunctionDef> {
+        self.consume_token(Token::Func)?;
+        let name = match self.advance() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "🧩 Expected a name after 'func'"
+            )
+            .with_suggestion("Example: func double(x) { return x * 2 }")),
+        };
+        let type_params = if self.match_token(&Token::LessThan) {
+            self.parse_type_params(&name)?
+        } else {
+            Vec::new()
+        };
+        self.consume_token(Token::LeftParen)?;
+        
+        let mut parameters = Vec::new();
+        let mut rest = None;
+        while !self.match_token(&Token::RightParen) && !self.is_at_end() {
+            if self.match_token(&Token::Spread) {
+                self.advance();
+                match self.advance() {
+                    Some(Token::Identifier(param)) => rest = Some(param.clone()),
+                    _ => return Err(SynthesisError::new(
+                        ErrorKind::SyntaxError,
+                        format!("🧩 Expected a name after ... in func {}", name)
+                    )
+                    .with_suggestion("Example: func chord(root, ...intervals) { ... }")),
+                }
+                if !self.match_token(&Token::RightParen) {
+                    return Err(SynthesisError::new(
+                        ErrorKind::SyntaxError,
+                        format!("🧩 ...{} has to be the last parameter of func {}", rest.unwrap_or_default(), name)
+                    )
+                    .with_suggestion("It collects whatever arguments are left over: func pulse(freq = 440, ...rest)"));
+                }
+                break;
+            }
+            let param = match self.current_token() {
+                Some(Token::Identifier(param)) => param.clone(),
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🧩 Expected a parameter name in func {}", name)
+                )
+                .with_suggestion("Parameters are names with optional types and defaults: func pulse(rate: Number, depth = 0.5)")),
+            };
+            self.advance();
+            
+            let type_annotation = if self.match_token(&Token::Colon) {
+                self.advance();
+                Some(self.parse_type_annotation()?)
+            } else {
+                None
+            };
+            let default_value = if self.match_token(&Token::Assignment) {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            parameters.push(Parameter { name: param, type_annotation, default_value });
+            
+            if self.match_token(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume_token(Token::RightParen)?;
+        
+        // `-> Type` arrives as a minus followed by a greater-than
+        let return_type = if self.match_token(&Token::Minus) && self.peek_token(1) == Some(&Token::GreaterThan) {
+            self.advance();
+            self.advance();
+            Some(self.parse_type_annotation()?)
+        } else {
+            None
+        };
+        
+        self.consume_token(Token::LeftBrace)?;
+        let body = self.parse_statements()?;
+        self.consume_token(Token::RightBrace)?;
+        
+        Ok(FunctionDef { name, type_params, parameters, rest, return_type, body, doc: None })
+    }
+    
+    /// `<T, U>` after a function's name
+    fn parse_type_params(&mut self, function: &str) -> crate::Result<Vec<String>> {
+        self.consume_token(Token::LessThan)?;
+        let mut type_params: Vec<String> = Vec::new();
+        loop {
+            match self.advance() {
+                Some(Token::Identifier(param)) if type_params.contains(param) => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🧩 func {} names the type parameter {} twice", function, param)
+                )
+                .with_suggestion("Give each one its own name: func pair<A, B>(a: A, b: B)")),
+                Some(Token::Identifier(param)) => type_params.push(param.clone()),
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🧩 Expected a type parameter name in func {}<...>", function)
+                )
+                .with_suggestion("Example: func first<T>(items: List<T>) -> T")),
+            }
+            if self.match_token(&Token::Comma) {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.consume_token(Token::GreaterThan)?;
+        Ok(type_params)
+    }
+    
+    /// `struct Point { x: Number = 0, y = 0, label }`: types and defaults are both optional
+    fn parse_struct_def(&mut self) -> crate::Result<StructDef> {
+        self.consume_token(Token::Struct)?;
+        let name = match self.advance() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "🧱 Expected a name after 'struct'"
+            )
+            .with_suggestion("Example: struct Point { x = 0, y = 0 }")),
+        };
+        self.consume_token(Token::LeftBrace)?;
+        
+        let mut fields = Vec::new();
+        let mut methods = Vec::new();
+        while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
+            let doc = self.take_doc_comments();
+            match self.current_token() {
+                Some(Token::Func) => {
+                    let mut method = self.parse_function_def()?;
+                    method.doc = doc;
+                    methods.push(method);
+                }
+                Some(Token::Identifier(field)) => {
+                    let field = field.clone();
+                    fields.push(self.parse_field(field)?);
+                }
+                Some(Token::RightBrace) => {}
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🧱 Expected a field name in struct {}", name)
+                )
+                .with_suggestion("Fields are names with optional types and defaults: struct Ball { x: Number, speed = 1 }")),
+            }
+            
+            if self.match_token(&Token::Comma) {
+                self.advance();
+            }
+        }
+        self.consume_token(Token::RightBrace)?;
+        
+        Ok(StructDef { name, fields, methods })
+    }
+    
+    /// `class Counter { count = 0  func bump(by = 1) { self.count = self.count + by } }`
+    fn parse_class_def(&mut self) -> crate::Result<ClassDef> {
+        self.consume_token(Token::Class)?;
+        let name = match self.advance() {
+            Some(Token::Identifier(name)) => name.clone(),
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "🧱 Expected a name after 'class'"
+            )
+            .with_suggestion("Example: class Counter { count = 0  func bump() { self.count = self.count + 1 } }")),
+        };
+        self.consume_token(Token::LeftBrace)?;
+        
+        let mut fields = Vec::new();
+        let mut methods = Vec::new();
+        while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
+            let doc = self.take_doc_comments();
+            match self.current_token() {
+                Some(Token::Func) => {
+                    let mut method = self.parse_function_def()?;
+                    method.doc = doc;
+                    methods.push(method);
+                }
+                Some(Token::Identifier(field)) => {
+                    let field = field.clone();
+                    fields.push(self.parse_field(field)?);
+                }
+                Some(Token::RightBrace) => {}
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    format!("🧱 Expected a field or a func in class {}", name)
+                )
+                .with_suggestion("Classes hold fields like `steps = 16` and methods like `func next() { ... }`")),
+            }
+            
+            if self.match_token(&Token::Comma) {
+                self.advance();
+            }
+        }
+        self.consume_token(Token::RightBrace)?;
+        
+        Ok(ClassDef { name, fields, methods })
+    }
+    
+    /// One `name: Type = default` in a struct or class, starting at its name
+    fn parse_field(&mut self, name: String) -> crate::Result<Field> {
+        self.advance();
+        let type_annotation = if self.match_token(&Token::Colon) {
+            self.advance();
+            self.parse_type_annotation()?
+        } else {
+            TypeAnnotation::Simple("Any".to_string())
+        };
+        let default_value = if self.match_token(&Token::Assignment) {
+            self.advance();
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        Ok(Field { name, type_annotation, default_value })
+    }
+    
+    /// Consume a run of `///` lines, returning their text
+    fn take_doc_comments(&mut self) -> Option<String> {
+        let mut lines = Vec::new();
+        while let Some(Token::DocComment(line)) = self.current_token() {
+            lines.push(line.clone());
+            self.advance();
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+    
     fn parse_loop(&mut self) -> crate::Result<LoopBlock> {
         self.consume_token(Token::Loop)?;
         self.consume_token(Token::LeftBrace)?;
@@ -115,17 +735,26 @@ impl<'a> Parser<'a> {
         Ok(LoopBlock { body })
     }
     
+    /// Statements up to the `}` closing the block. A statement with a mistake in it is recorded
+    /// and skipped so the rest of the block is still parsed
     fn parse_statements(&mut self) -> crate::Result<Vec<Statement>> {
         let mut statements = Vec::new();
         
         while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
-            match self.parse_statement() {
-                Ok(stmt) => statements.push(stmt),
+            if self.take_doc_comments().is_some() {
+                continue;
+            }
+            let start = self.position;
+            let parsed = if self.at_macro_call() {
+                self.expand_macro()
+            } else {
+                self.parse_statement().map(|stmt| vec![stmt])
+            };
+            match parsed {
+                Ok(stmts) => statements.extend(stmts),
                 Err(err) => {
-                    // Error recovery: skip to next likely statement start or block end
-                    self.synchronize_after_error();
-                    // Re-throw the error with recovery context
-                    return Err(err.with_suggestion("Parser recovered and continued after this error"));
+                    self.record_error(err);
+                    self.synchronize_after_error(start);
                 }
             }
         }
@@ -133,40 +762,176 @@ impl<'a> Parser<'a> {
         Ok(statements)
     }
 
-    /// Skip tokens until we find a likely place to resume parsing
-    fn synchronize_after_error(&mut self) {
-        self.advance(); // Skip the problematic token
+    /// Skip tokens until we find a likely place to resume parsing: a keyword that starts a
+    /// statement, the `}` closing the block, or a name at the start of a later line. Blocks
+    /// opened along the way are skipped whole. If the statement that began at `start` broke on
+    /// the start of the next one, like a missing value before a `}`, parsing resumes right there
+    fn synchronize_after_error(&mut self, start: usize) {
+        let line_of = |parser: &Self, position: usize| parser.spans.get(position).map(|span| span.line);
+        if self.position > start && (self.at_statement_boundary()
+            || self.peek_token(1) == Some(&Token::Assignment) && line_of(self, self.position) > line_of(self, start)) {
+            return;
+        }
+        let line = line_of(self, self.position);
+        let mut depth = 0usize;
         
-        while !self.is_at_end() {
-            // Stop at statement boundaries
-            match self.current_token() {
-                Some(Token::Let) | Some(Token::If) | Some(Token::While) | 
-                Some(Token::For) | Some(Token::Match) | Some(Token::Every) | 
-                Some(Token::After) | Some(Token::RightBrace) => return,
+        loop {
+            // Skip the problematic token, so recovery can't get stuck on it
+            match self.advance() {
+                Some(Token::LeftBrace) => depth += 1,
+                Some(Token::RightBrace) => depth = depth.saturating_sub(1),
+                None => return,
                 _ => {}
             }
-            
+            if self.is_at_end() {
+                return;
+            }
+            if depth > 0 {
+                continue;
+            }
+            if self.at_statement_boundary()
+                || matches!(self.current_token(), Some(Token::Identifier(_))) && line_of(self, self.position) > line {
+                return;
+            }
+        }
+    }
+    
+    /// Whether the current token starts a statement or closes a block
+    fn at_statement_boundary(&self) -> bool {
+        matches!(self.current_token(),
+            Some(Token::Let) | Some(Token::Const) | Some(Token::If) | Some(Token::While) | 
+            Some(Token::For) | Some(Token::Match) | Some(Token::Every) | Some(Token::Try) |
+            Some(Token::After) | Some(Token::Async) | Some(Token::Loop) | Some(Token::Func) |
+            Some(Token::Import) | Some(Token::Struct) | Some(Token::Class) | Some(Token::Macro) |
+            Some(Token::Return) | Some(Token::RightBrace))
+    }
+    
+    fn parse_statement(&mut self) -> crate::Result<Statement> {
+        match self.current_token() {
+            Some(Token::If) => self.parse_if_statement(),
+            Some(Token::Match) => self.parse_match_statement(),
+            Some(Token::Every) => self.parse_temporal_statement(),
+            Some(Token::After) => self.parse_temporal_statement(),
+            Some(Token::Async) => self.parse_temporal_statement(),
+            Some(Token::While) => self.parse_temporal_statement(),
+            Some(Token::For) => self.parse_for_statement(),
+            Some(Token::Try) => self.parse_try_statement(),
+            Some(Token::Let) => self.parse_let_statement(),
+            Some(Token::Const) => self.parse_const_statement(),
+            Some(Token::Return) => {
+                self.advance();
+                if self.match_token(&Token::RightBrace) || self.is_at_end() {
+                    Ok(Statement::Return(None))
+                } else {
+                    Ok(Statement::Return(Some(self.parse_expression()?)))
+                }
+            }
+            Some(Token::Break) => {
+                self.advance();
+                Ok(Statement::Break)
+            }
+            Some(Token::Continue) => {
+                self.advance();
+                Ok(Statement::Continue)
+            }
+            Some(Token::Identifier(_)) if self.peek_token(1) == Some(&Token::Assignment) => {
+                self.parse_assignment()
+            }
+            Some(Token::Identifier(target)) if self.at_field_assignment() => {
+                let target = target.clone();
+                self.parse_field_assignment(target)
+            }
+            Some(Token::Identifier(name)) if name == "config" && self.peek_token(1) == Some(&Token::LeftBrace) => {
+                // `config { fps: 30 }` is shorthand for `config = { fps: 30 }`
+                self.advance();
+                let value = self.parse_block()?;
+                Ok(Statement::Assignment { name: "config".to_string(), value })
+            }
+            Some(Token::Identifier(name)) if name == "capabilities" && self.peek_token(1) == Some(&Token::LeftBrace) => {
+                self.parse_capabilities()
+            }
+            _ => {
+                let expr = self.parse_expression()?;
+                Ok(Statement::Expression(expr))
+            }
+        }
+    }
+    
+    /// Whether the tokens ahead read `name.field(.field)* =`
+    fn at_field_assignment(&self) -> bool {
+        let mut offset = 1;
+        while self.peek_token(offset) == Some(&Token::Dot)
+            && matches!(self.peek_token(offset + 1), Some(Token::Identifier(_))) {
+            offset += 2;
+        }
+        offset > 1 && self.peek_token(offset) == Some(&Token::Assignment)
+    }
+    
+    /// `ball.position.x = 10`
+    fn parse_field_assignment(&mut self, target: String) -> crate::Result<Statement> {
+        self.advance();
+        let mut path = Vec::new();
+        while self.match_token(&Token::Dot) {
             self.advance();
+            if let Some(Token::Identifier(field)) = self.advance() {
+                path.push(field.clone());
+            }
         }
+        self.consume_token(Token::Assignment)?;
+        let value = self.parse_expression()?;
+        Ok(Statement::FieldAssignment { target, path, value })
     }
     
-    fn parse_statement(&mut self) -> crate::Result<Statement> {
-        match self.current_token() {
-            Some(Token::If) => self.parse_if_statement(),
-            Some(Token::Match) => self.parse_match_statement(),
-            Some(Token::Every) => self.parse_temporal_statement(),
-            Some(Token::After) => self.parse_temporal_statement(),
-            Some(Token::While) => self.parse_temporal_statement(),
-            Some(Token::For) => self.parse_for_statement(),
-            Some(Token::Let) => self.parse_let_statement(),
-            Some(Token::Identifier(_)) if self.peek_token(1) == Some(&Token::Assignment) => {
-                self.parse_assignment()
+    /// `capabilities { network, camera, filesystem("assets/") }`
+    fn parse_capabilities(&mut self) -> crate::Result<Statement> {
+        self.advance();
+        self.consume_token(Token::LeftBrace)?;
+        
+        let mut requests = Vec::new();
+        while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
+            let name = match self.current_token() {
+                Some(Token::Identifier(name)) => name.clone(),
+                _ => return Err(SynthesisError::new(
+                    ErrorKind::SyntaxError,
+                    "🔒 Expected a capability name like network or camera"
+                )
+                .with_suggestion("Example: capabilities { network, filesystem(\"assets/\") }")),
+            };
+            self.advance();
+            
+            let mut scope = None;
+            if self.match_token(&Token::LeftParen) {
+                self.advance();
+                match self.current_token() {
+                    Some(Token::String(path)) => {
+                        scope = Some(path.clone());
+                        self.advance();
+                    }
+                    // The lexer hands plain strings over as interpolated ones with only text parts
+                    Some(Token::InterpolatedString(parts)) if parts.iter().all(|p| matches!(p, StringPart::Text(_))) => {
+                        scope = Some(parts.iter().map(|p| match p {
+                            StringPart::Text(text) => text.as_str(),
+                            _ => "",
+                        }).collect());
+                        self.advance();
+                    }
+                    _ => return Err(SynthesisError::new(
+                        ErrorKind::SyntaxError,
+                        format!("🔒 {}(...) expects a folder in quotes", name)
+                    )
+                    .with_suggestion(format!("Example: {}(\"assets/\")", name))),
+                }
+                self.consume_token(Token::RightParen)?;
             }
-            _ => {
-                let expr = self.parse_expression()?;
-                Ok(Statement::Expression(expr))
+            requests.push(CapabilityRequest { name, scope });
+            
+            if self.match_token(&Token::Comma) {
+                self.advance();
             }
         }
+        
+        self.consume_token(Token::RightBrace)?;
+        Ok(Statement::Capabilities(requests))
     }
     
     fn parse_assignment(&mut self) -> crate::Result<Statement> {
@@ -224,12 +989,18 @@ impl<'a> Parser<'a> {
         let mut arms = Vec::new();
         while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
             let pattern = self.parse_pattern()?;
+            let guard = if self.match_token(&Token::If) {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
             self.consume_token(Token::Arrow)?;
             self.consume_token(Token::LeftBrace)?;
             let body = self.parse_statements()?;
             self.consume_token(Token::RightBrace)?;
             
-            arms.push(MatchArm { pattern, body });
+            arms.push(MatchArm { pattern, guard, body });
             
             if self.match_token(&Token::Comma) {
                 self.advance();
@@ -241,16 +1012,83 @@ impl<'a> Parser<'a> {
     }
     
     fn parse_pattern(&mut self) -> crate::Result<Pattern> {
+        self.parse_pattern_in(false)
+    }
+    
+    /// Inside list and object patterns (`nested`) a bare name binds rather than naming a variant
+    fn parse_pattern_in(&mut self, nested: bool) -> crate::Result<Pattern> {
         match self.current_token() {
             Some(Token::Underscore) => {
                 self.advance();
                 Ok(Pattern::Wildcard)
             }
+            Some(Token::LeftBracket) => {
+                self.advance();
+                let mut items = Vec::new();
+                let mut rest = false;
+                while !self.match_token(&Token::RightBracket) && !self.is_at_end() {
+                    if self.match_token(&Token::Spread) {
+                        self.advance();
+                        rest = true;
+                        break;
+                    }
+                    items.push(self.parse_pattern_in(true)?);
+                    if self.match_token(&Token::Comma) {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+                self.consume_token(Token::RightBracket)?;
+                Ok(Pattern::Array { items, rest })
+            }
+            Some(Token::LeftBrace) => {
+                self.advance();
+                let mut fields = Vec::new();
+                while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
+                    let field = match self.current_token() {
+                        Some(Token::Identifier(field)) => field.clone(),
+                        _ => return Err(SynthesisError::new(
+                            ErrorKind::SyntaxError,
+                            "Expected a field name in an object pattern"
+                        )
+                        .with_suggestion("Example: { kind: \"note\", velocity } => { ... }")),
+                    };
+                    self.advance();
+                    let pattern = if self.match_token(&Token::Colon) {
+                        self.advance();
+                        self.parse_pattern_in(true)?
+                    } else {
+                        Pattern::Binding(field.clone())
+                    };
+                    fields.push((field, pattern));
+                    if self.match_token(&Token::Comma) {
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+                self.consume_token(Token::RightBrace)?;
+                Ok(Pattern::Object(fields))
+            }
+            Some(Token::Integer(_) | Token::BasedInteger(..) | Token::Float(_) | Token::Minus) => {
+                let start = self.parse_pattern_number()?;
+                let inclusive = match self.current_token() {
+                    Some(Token::Range) => false,
+                    Some(Token::RangeInclusive) => true,
+                    _ => return Ok(Pattern::Literal(start)),
+                };
+                self.advance();
+                let end = self.parse_pattern_number()?;
+                Ok(Pattern::Range { start, end, inclusive })
+            }
             Some(Token::Identifier(name)) => {
                 let name = name.clone();
                 self.advance();
                 
-                if self.match_token(&Token::LeftParen) {
+                if nested && !self.match_token(&Token::LeftParen) {
+                    Ok(Pattern::Binding(name))
+                } else if self.match_token(&Token::LeftParen) {
                     self.advance();
                     let mut fields = Vec::new();
                     while !self.match_token(&Token::RightParen) && !self.is_at_end() {
@@ -265,16 +1103,6 @@ impl<'a> Parser<'a> {
                     Ok(Pattern::Identifier(name))
                 }
             }
-            Some(Token::Integer(val)) => {
-                let val = *val;
-                self.advance();
-                Ok(Pattern::Literal(Literal::Integer(val)))
-            }
-            Some(Token::Float(val)) => {
-                let val = *val;
-                self.advance();
-                Ok(Pattern::Literal(Literal::Float(val)))
-            }
             Some(Token::Percentage(val)) => {
                 let val = *val;
                 self.advance();
@@ -285,11 +1113,24 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(Pattern::Literal(Literal::String(val)))
             }
+            Some(Token::InterpolatedString(parts)) if parts.iter().all(|p| matches!(p, StringPart::Text(_))) => {
+                let val = parts.iter().map(|p| match p {
+                    StringPart::Text(text) => text.as_str(),
+                    _ => "",
+                }).collect();
+                self.advance();
+                Ok(Pattern::Literal(Literal::String(val)))
+            }
             Some(Token::Boolean(val)) => {
                 let val = *val;
                 self.advance();
                 Ok(Pattern::Literal(Literal::Boolean(val)))
             }
+            Some(Token::Color(rgba)) => {
+                let rgba = *rgba;
+                self.advance();
+                Ok(Pattern::Literal(Literal::Color(rgba)))
+            }
             _ => {
                 let found_desc = self.current_token()
                     .map(token_description)
@@ -299,13 +1140,33 @@ impl<'a> Parser<'a> {
                     ErrorKind::InvalidExpression,
                     format!("Invalid pattern: {}", found_desc)
                 )
-                .with_suggestion("Pattern matching supports numbers, strings, and wildcards")
+                .with_suggestion("Pattern matching supports numbers, ranges, strings, lists, objects and wildcards")
                 .with_suggestion("Use _ for catch-all patterns")
                 .with_docs("https://synthesis-lang.org/docs/pattern-matching"))
             }
         }
     }
     
+    /// A number in a pattern or at either end of a range, possibly negative
+    fn parse_pattern_number(&mut self) -> crate::Result<Literal> {
+        let negative = self.match_token(&Token::Minus);
+        if negative {
+            self.advance();
+        }
+        let literal = match self.current_token() {
+            Some(Token::Integer(val)) => Literal::Integer(if negative { -val } else { *val }),
+            Some(Token::BasedInteger(val, radix)) => Literal::BasedInteger(if negative { -val } else { *val }, *radix),
+            Some(Token::Float(val)) => Literal::Float(if negative { -val } else { *val }),
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "Expected a number in the pattern"
+            )
+            .with_suggestion("Ranges need numbers at both ends: 0..10 or -1.0..=1.0")),
+        };
+        self.advance();
+        Ok(literal)
+    }
+    
     fn parse_temporal_statement(&mut self) -> crate::Result<Statement> {
         let token = self.current_token().unwrap().clone();
         self.advance();
@@ -329,6 +1190,12 @@ impl<'a> Parser<'a> {
                 self.consume_token(Token::RightBrace)?;
                 Ok(Statement::After { duration, body })
             }
+            Token::Async => {
+                self.consume_token(Token::LeftBrace)?;
+                let body = self.parse_statements()?;
+                self.consume_token(Token::RightBrace)?;
+                Ok(Statement::Async { body })
+            }
             Token::While => {
                 let condition = self.parse_expression()?;
                 self.consume_token(Token::LeftBrace)?;
@@ -340,12 +1207,50 @@ impl<'a> Parser<'a> {
                 ErrorKind::UnexpectedToken,
                 "Invalid temporal statement"
             )
-            .with_suggestion("Use 'every', 'after', or 'while' for time-based logic")
+            .with_suggestion("Use 'every', 'after', 'async' or 'while' for time-based logic")
             .with_suggestion("Example: every(1.seconds) { ... }")
             .with_docs("https://synthesis-lang.org/docs/time")),
         }
     }
 
+    /// `try { ... } catch (err) { ... }`; the name after `catch` is optional, with or without parentheses
+    fn parse_try_statement(&mut self) -> crate::Result<Statement> {
+        self.consume_token(Token::Try)?;
+        self.consume_token(Token::LeftBrace)?;
+        let body = self.parse_statements()?;
+        self.consume_token(Token::RightBrace)?;
+        
+        if !self.match_token(&Token::Catch) {
+            return Err(SynthesisError::new(
+                ErrorKind::MissingToken,
+                "A try block needs a catch block after it"
+            )
+            .with_suggestion("Say what to do instead: try { song = Audio.load_file(\"set.wav\") } catch (err) { song = Audio.mic_input() }")
+            .with_docs("https://synthesis-lang.org/docs/errors"));
+        }
+        self.advance();
+        let parenthesized = self.match_token(&Token::LeftParen);
+        if parenthesized {
+            self.advance();
+        }
+        let error = match self.current_token() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.advance();
+                Some(name)
+            }
+            _ => None,
+        };
+        if parenthesized {
+            self.consume_token(Token::RightParen)?;
+        }
+        self.consume_token(Token::LeftBrace)?;
+        let handler = self.parse_statements()?;
+        self.consume_token(Token::RightBrace)?;
+        
+        Ok(Statement::Try { body, error, handler })
+    }
+
     fn parse_for_statement(&mut self) -> crate::Result<Statement> {
         self.consume_token(Token::For)?;
         
@@ -380,6 +1285,10 @@ impl<'a> Parser<'a> {
     fn parse_let_statement(&mut self) -> crate::Result<Statement> {
         self.consume_token(Token::Let)?;
         
+        if self.match_token(&Token::LeftBracket) || self.match_token(&Token::LeftBrace) {
+            return self.parse_let_destructure();
+        }
+        
         let name = match self.current_token() {
             Some(Token::Identifier(name)) => {
                 let name = name.clone();
@@ -415,12 +1324,130 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// `const NAME = value`; unlike `let`, the value can't be left out
+    fn parse_const_statement(&mut self) -> crate::Result<Statement> {
+        let span = self.span_at(self.position);
+        self.consume_token(Token::Const)?;
+        let name = match self.current_token() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.advance();
+                name
+            }
+            _ => return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "Expected a name after 'const'"
+            )
+            .with_suggestion("Example: const SAMPLE_RATE = 48000")),
+        };
+        if !self.match_token(&Token::Assignment) {
+            return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                format!("Constant '{}' needs a value", name)
+            )
+            .with_suggestion(format!("A constant is set where it's declared: const {} = 48000", name))
+            .with_suggestion("Use let for a variable that gets its value later"));
+        }
+        self.advance();
+        let value = self.parse_expression()?;
+        Ok(Statement::Const { name, value, span })
+    }
+
+    /// `let [a, _, c] = list` or `let {left, right: r} = object`; the value is required
+    fn parse_let_destructure(&mut self) -> crate::Result<Statement> {
+        let pattern = if self.match_token(&Token::LeftBracket) {
+            self.advance();
+            let mut names = Vec::new();
+            while !self.match_token(&Token::RightBracket) && !self.is_at_end() {
+                names.push(match self.current_token() {
+                    Some(Token::Underscore) => None,
+                    Some(Token::Identifier(name)) => Some(name.clone()),
+                    _ => return Err(SynthesisError::new(
+                        ErrorKind::SyntaxError,
+                        "Expected a variable name or _ in let [...]"
+                    )
+                    .with_suggestion("Example: let [low, mid, high] = bands")),
+                });
+                self.advance();
+                if self.match_token(&Token::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            self.consume_token(Token::RightBracket)?;
+            Destructure::Array(names)
+        } else {
+            self.consume_token(Token::LeftBrace)?;
+            let mut fields = Vec::new();
+            while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
+                let field = match self.current_token() {
+                    Some(Token::Identifier(field)) => field.clone(),
+                    _ => return Err(SynthesisError::new(
+                        ErrorKind::SyntaxError,
+                        "Expected a field name in let {...}"
+                    )
+                    .with_suggestion("Example: let {left, right} = channels")),
+                };
+                self.advance();
+                let variable = if self.match_token(&Token::Colon) {
+                    self.advance();
+                    match self.current_token() {
+                        Some(Token::Identifier(variable)) => {
+                            let variable = variable.clone();
+                            self.advance();
+                            variable
+                        }
+                        _ => return Err(SynthesisError::new(
+                            ErrorKind::SyntaxError,
+                            format!("Expected a variable name after {}:", field)
+                        )
+                        .with_suggestion(format!("Example: let {{{}: renamed}} = object", field))),
+                    }
+                } else {
+                    field.clone()
+                };
+                fields.push((field, variable));
+                if self.match_token(&Token::Comma) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            self.consume_token(Token::RightBrace)?;
+            Destructure::Object(fields)
+        };
+        
+        if !self.match_token(&Token::Assignment) {
+            return Err(SynthesisError::new(
+                ErrorKind::SyntaxError,
+                "Destructuring needs a value to take apart"
+            )
+            .with_suggestion("Example: let [a, b] = pair"));
+        }
+        self.advance();
+        let value = self.parse_expression()?;
+        
+        Ok(Statement::LetDestructure { pattern, value })
+    }
+
+    /// `Number`, or `List<T>` and `Map<Text, Number>` with the types they hold
     fn parse_type_annotation(&mut self) -> crate::Result<TypeAnnotation> {
         match self.current_token() {
             Some(Token::Identifier(name)) => {
                 let name = name.clone();
                 self.advance();
-                Ok(TypeAnnotation::Simple(name))
+                if !self.match_token(&Token::LessThan) {
+                    return Ok(TypeAnnotation::Simple(name));
+                }
+                self.advance();
+                let mut params = vec![self.parse_type_annotation()?];
+                while self.match_token(&Token::Comma) {
+                    self.advance();
+                    params.push(self.parse_type_annotation()?);
+                }
+                self.consume_token(Token::GreaterThan)?;
+                Ok(TypeAnnotation::Generic { base: name, params })
             }
             _ => Err(SynthesisError::new(
                 ErrorKind::SyntaxError,
@@ -431,24 +1458,24 @@ impl<'a> Parser<'a> {
         }
     }
     
-    fn parse_expression(&mut self) -> crate::Result<Expression> {
+    pub(crate) fn parse_expression(&mut self) -> crate::Result<Expression> {
         self.parse_pipe()
     }
     
     fn parse_pipe(&mut self) -> crate::Result<Expression> {
-        let mut expr = self.parse_equality()?;
+        let mut expr = self.parse_null_coalesce()?;
         
         while self.match_token(&Token::Pipe) || self.match_token(&Token::BiDirectionalPipe) {
             if self.match_token(&Token::Pipe) {
                 self.advance();
-                let right = self.parse_equality()?;
+                let right = self.parse_null_coalesce()?;
                 expr = Expression::Pipe {
                     left: Box::new(expr),
                     right: Box::new(right),
                 };
             } else if self.match_token(&Token::BiDirectionalPipe) {
                 self.advance();
-                let right = self.parse_equality()?;
+                let right = self.parse_null_coalesce()?;
                 expr = Expression::BiDirectionalPipe {
                     left: Box::new(expr),
                     right: Box::new(right),
@@ -459,6 +1486,55 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
     
+    /// `beat?.bpm ?? 120` binds looser than `||`, so a whole condition can fall back to a default
+    fn parse_null_coalesce(&mut self) -> crate::Result<Expression> {
+        let mut expr = self.parse_logical_or()?;
+        
+        while self.match_token(&Token::NullCoalesce) {
+            self.advance();
+            let right = self.parse_logical_or()?;
+            expr = Expression::BinaryOp {
+                left: Box::new(expr),
+                op: BinaryOperator::NullCoalesce,
+                right: Box::new(right),
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_logical_or(&mut self) -> crate::Result<Expression> {
+        let mut expr = self.parse_logical_and()?;
+        
+        while self.match_token(&Token::LogicalOr) {
+            self.advance();
+            let right = self.parse_logical_and()?;
+            expr = Expression::BinaryOp {
+                left: Box::new(expr),
+                op: BinaryOperator::LogicalOr,
+                right: Box::new(right),
+            };
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_logical_and(&mut self) -> crate::Result<Expression> {
+        let mut expr = self.parse_equality()?;
+        
+        while self.match_token(&Token::LogicalAnd) {
+            self.advance();
+            let right = self.parse_equality()?;
+            expr = Expression::BinaryOp {
+                left: Box::new(expr),
+                op: BinaryOperator::LogicalAnd,
+                right: Box::new(right),
+            };
+        }
+        
+        Ok(expr)
+    }
+    
     fn parse_equality(&mut self) -> crate::Result<Expression> {
         let mut expr = self.parse_comparison()?;
         
@@ -541,7 +1617,20 @@ impl<'a> Parser<'a> {
     }
     
     fn parse_unary(&mut self) -> crate::Result<Expression> {
-        self.parse_call()
+        let op = match self.current_token() {
+            Some(Token::Minus) => UnaryOperator::Negate,
+            Some(Token::Not) => UnaryOperator::Not,
+            _ => return self.parse_call(),
+        };
+        self.advance();
+        let operand = self.parse_unary()?;
+        Ok(match (op, operand) {
+            // `-3` is just a number, so it can go anywhere a literal can
+            (UnaryOperator::Negate, Expression::Literal(Literal::Integer(n))) => Expression::Literal(Literal::Integer(-n)),
+            (UnaryOperator::Negate, Expression::Literal(Literal::BasedInteger(n, radix))) => Expression::Literal(Literal::BasedInteger(-n, radix)),
+            (UnaryOperator::Negate, Expression::Literal(Literal::Float(n))) => Expression::Literal(Literal::Float(-n)),
+            (op, operand) => Expression::UnaryOp { op, operand: Box::new(operand) },
+        })
     }
     
     fn parse_call(&mut self) -> crate::Result<Expression> {
@@ -550,7 +1639,7 @@ impl<'a> Parser<'a> {
         loop {
             if self.match_token(&Token::LeftParen) {
                 self.advance();
-                let args = self.parse_arguments()?;
+                let (args, named_args) = self.parse_function_arguments()?;
                 self.consume_token(Token::RightParen)?;
                 
                 if let Expression::Identifier(name) = expr {
@@ -558,7 +1647,7 @@ impl<'a> Parser<'a> {
                         module: None,
                         name,
                         args,
-                        named_args: HashMap::new(),
+                        named_args,
                     };
                 } else {
                     return Err(SynthesisError::new(
@@ -577,6 +1666,36 @@ impl<'a> Parser<'a> {
                     array: Box::new(expr),
                     index: Box::new(index),
                 };
+            } else if self.match_token(&Token::Dot) || self.match_token(&Token::QuestionDot) {
+                // Chained calls on a result: Graphics.osc(10).rotate(0.1).out(), or beat?.bpm
+                let optional = self.match_token(&Token::QuestionDot);
+                let method = match self.peek_token(1) {
+                    Some(Token::Identifier(method)) => method.clone(),
+                    _ if optional => return Err(SynthesisError::new(
+                        ErrorKind::SyntaxError,
+                        "Expected a field or method name after '?.'"
+                    )
+                    .with_suggestion("Try: beat?.bpm or clip?.play()")),
+                    _ => break,
+                };
+                self.advance();
+                self.advance();
+                let (args, named_args) = if self.match_token(&Token::LeftParen) {
+                    self.advance();
+                    let arguments = self.parse_function_arguments()?;
+                    self.consume_token(Token::RightParen)?;
+                    arguments
+                } else {
+                    (Vec::new(), HashMap::new())
+                };
+                
+                expr = Expression::MethodCall {
+                    object: Box::new(expr),
+                    method,
+                    args,
+                    named_args,
+                    optional,
+                };
             } else {
                 break;
             }
@@ -592,6 +1711,11 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(Expression::Literal(Literal::Integer(n)))
             }
+            Some(Token::BasedInteger(n, radix)) => {
+                let (n, radix) = (*n, *radix);
+                self.advance();
+                Ok(Expression::Literal(Literal::BasedInteger(n, radix)))
+            }
             Some(Token::Float(f)) => {
                 let f = *f;
                 self.advance();
@@ -617,15 +1741,18 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(Expression::Literal(Literal::Boolean(b)))
             }
+            Some(Token::Color(rgba)) => {
+                let rgba = *rgba;
+                self.advance();
+                Ok(Expression::Literal(Literal::Color(rgba)))
+            }
             Some(Token::Unit(unit_string)) => {
                 let unit_string = unit_string.clone();
                 self.advance();
                 
-                // Parse "value.unit" format
-                let parts: Vec<&str> = unit_string.split('.').collect();
-                if parts.len() == 2 {
-                    let value_str = parts[0];
-                    let unit = parts[1].to_string();
+                // Parse "value.unit" format; the value may have its own decimal point
+                if let Some((value_str, unit)) = unit_string.rsplit_once('.') {
+                    let unit = unit.to_string();
                     
                     if let Ok(int_val) = value_str.parse::<i64>() {
                         Ok(Expression::UnitValue {
@@ -658,6 +1785,9 @@ impl<'a> Parser<'a> {
                 let name = name.clone();
                 self.advance();
                 
+                if self.at_struct_literal(&name) {
+                    return self.parse_struct_literal(name);
+                }
                 if self.match_token(&Token::Dot) {
                     self.advance();
                     if let Some(Token::Identifier(func_name)) = self.current_token() {
@@ -682,6 +1812,7 @@ impl<'a> Parser<'a> {
                                 method: func_name,
                                 args: Vec::new(),
                                 named_args: HashMap::new(),
+                                optional: false,
                             })
                         }
                     } else {
@@ -735,13 +1866,29 @@ impl<'a> Parser<'a> {
         }
     }
     
+    /// `Point { x: ...` or `Point {}`; the capital letter keeps `if ready { x = 1 }` a plain block
+    fn at_struct_literal(&self, name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_uppercase())
+            && self.match_token(&Token::LeftBrace)
+            && match self.peek_token(1) {
+                Some(Token::RightBrace) => true,
+                Some(Token::Identifier(_)) => self.peek_token(2) == Some(&Token::Colon),
+                _ => false,
+            }
+    }
+    
+    fn parse_struct_literal(&mut self, name: String) -> crate::Result<Expression> {
+        let fields = self.parse_block_fields()?;
+        Ok(Expression::StructLiteral { name, fields })
+    }
+    
     fn parse_array_literal(&mut self) -> crate::Result<Expression> {
         self.consume_token(Token::LeftBracket)?;
         
         let mut elements = Vec::new();
         
         while !self.match_token(&Token::RightBracket) && !self.is_at_end() {
-            elements.push(self.parse_expression()?);
+            elements.push(self.parse_spreadable()?);
             
             if self.match_token(&Token::Comma) {
                 self.advance();
@@ -754,14 +1901,39 @@ impl<'a> Parser<'a> {
         Ok(Expression::ArrayLiteral(elements))
     }
 
+    /// An element or argument, which may be `...list` to stand for all of the list's items
+    fn parse_spreadable(&mut self) -> crate::Result<Expression> {
+        if self.match_token(&Token::Spread) {
+            self.advance();
+            return Ok(Expression::Spread(Box::new(self.parse_expression()?)));
+        }
+        self.parse_expression()
+    }
+
     fn parse_block(&mut self) -> crate::Result<Expression> {
+        let fields = self.parse_block_fields()?;
+        Ok(Expression::Block { fields })
+    }
+    
+    /// The `{ key: value, ... }` shared by blocks and struct literals
+    fn parse_block_fields(&mut self) -> crate::Result<HashMap<String, Expression>> {
         self.consume_token(Token::LeftBrace)?;
         
         let mut fields = HashMap::new();
         
         while !self.match_token(&Token::RightBrace) && !self.is_at_end() {
-            if let Some(Token::Identifier(key)) = self.current_token() {
-                let key = key.clone();
+            // Quoted keys allow names that aren't identifiers, like "/api/level"
+            let key = match self.current_token() {
+                Some(Token::Identifier(key) | Token::String(key)) => Some(key.clone()),
+                Some(Token::InterpolatedString(parts)) if parts.iter().all(|p| matches!(p, StringPart::Text(_))) => {
+                    Some(parts.iter().map(|p| match p {
+                        StringPart::Text(text) => text.as_str(),
+                        _ => "",
+                    }).collect())
+                }
+                _ => None,
+            };
+            if let Some(key) = key {
                 self.advance();
                 self.consume_token(Token::Colon)?;
                 let value = self.parse_expression()?;
@@ -777,7 +1949,7 @@ impl<'a> Parser<'a> {
         
         self.consume_token(Token::RightBrace)?;
         
-        Ok(Expression::Block { fields })
+        Ok(fields)
     }
     
     fn parse_function_arguments(&mut self) -> crate::Result<(Vec<Expression>, HashMap<String, Expression>)> {
@@ -810,7 +1982,7 @@ impl<'a> Parser<'a> {
                     args.push(self.parse_expression()?);
                 }
             } else {
-                args.push(self.parse_expression()?);
+                args.push(self.parse_spreadable()?);
             }
             
             if self.match_token(&Token::Comma) {
@@ -823,11 +1995,6 @@ impl<'a> Parser<'a> {
         Ok((args, named_args))
     }
 
-    fn parse_arguments(&mut self) -> crate::Result<Vec<Expression>> {
-        let (args, _) = self.parse_function_arguments()?;
-        Ok(args)
-    }
-    
     // Helper methods
     fn current_token(&self) -> Option<&Token> {
         self.tokens.get(self.position)
@@ -875,13 +2042,27 @@ impl<'a> Parser<'a> {
     
     /// Create a source location for error reporting
     fn current_location(&self, filename: &str) -> SourceLocation {
+        self.location_at(self.position, filename)
+    }
+
+    fn location_at(&self, position: usize, filename: &str) -> SourceLocation {
+        let span = self.span_at(position);
         SourceLocation {
-            line: 1, // TODO: Track line numbers in lexer
-            column: self.position,
+            line: span.line,
+            column: span.column,
             filename: filename.to_string(),
         }
     }
 
+    /// Where the token at `position` starts; without spans its index stands in for the column
+    fn span_at(&self, position: usize) -> Span {
+        // Past the last token the error is at the end of the file, so point at the last token
+        match self.spans.get(position).or(self.spans.last()) {
+            Some(span) => *span,
+            None => Span { line: 1, column: position },
+        }
+    }
+
     fn match_equality_op(&self) -> Option<BinaryOperator> {
         match self.current_token() {
             Some(Token::Equals) => Some(BinaryOperator::Equal),
@@ -938,29 +2119,41 @@ fn token_description(token: &Token) -> String {
         Token::Match => "match".to_string(),
         Token::Every => "every".to_string(),
         Token::After => "after".to_string(),
+        Token::Async => "async".to_string(),
         Token::While => "while".to_string(),
         Token::For => "for".to_string(),
         Token::In => "in".to_string(),
         Token::Func => "func".to_string(),
+        Token::Macro => "macro".to_string(),
         Token::Let => "let".to_string(),
+        Token::Const => "const".to_string(),
         Token::Return => "return".to_string(),
+        Token::Try => "try".to_string(),
+        Token::Catch => "catch".to_string(),
         Token::Plus => "+".to_string(),
         Token::Minus => "-".to_string(),
         Token::Multiply => "*".to_string(),
         Token::Divide => "/".to_string(),
         Token::Equals => "==".to_string(),
         Token::NotEqual => "!=".to_string(),
+        Token::Not => "!".to_string(),
+        Token::NullCoalesce => "??".to_string(),
+        Token::QuestionDot => "?.".to_string(),
+        Token::Spread => "...".to_string(),
         Token::LessThan => "<".to_string(),
         Token::LessThanOrEqual => "<=".to_string(),
         Token::GreaterThan => ">".to_string(),
         Token::GreaterThanOrEqual => ">=".to_string(),
         Token::Identifier(name) => format!("identifier '{}'", name),
         Token::Integer(num) => format!("number {}", num),
+        Token::BasedInteger(num, radix) => format!("number {}", crate::parser::formatter::based_integer(*num, *radix)),
         Token::Float(num) => format!("number {}", num),
         Token::Percentage(num) => format!("percentage {}%", num * 100.0),
         Token::String(s) => format!("string \"{}\"", s),
         Token::Boolean(b) => format!("boolean {}", b),
+        Token::Color(rgba) => format!("colour {}", crate::runtime::Value::Color(*rgba)),
         Token::Unit(u) => format!("unit value {}", u),
+        Token::DocComment(_) => "doc comment".to_string(),
         Token::Newline => "newline".to_string(),
         Token::Eof => "end of file".to_string(),
         _ => format!("{:?}", token).to_lowercase(),
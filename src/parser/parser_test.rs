@@ -33,8 +33,8 @@ mod tests {
         assert!(matches!(expr, Expression::Literal(Literal::Integer(42))));
 
         // Float literal
-        let expr = parse_expression_from_str("3.14").unwrap();
-        assert!(matches!(expr, Expression::Literal(Literal::Float(f)) if (f - 3.14).abs() < f64::EPSILON));
+        let expr = parse_expression_from_str("2.5").unwrap();
+        assert!(matches!(expr, Expression::Literal(Literal::Float(f)) if (f - 2.5).abs() < f64::EPSILON));
 
         // Percentage literal
         let expr = parse_expression_from_str("50%").unwrap();
@@ -177,6 +177,17 @@ mod tests {
         } else {
             panic!("Expected import item");
         }
+
+        let program = parse_program_from_str("import lib/drums.{kick}\nimport \"../shared/util.syn\"\nimport Graphics as G").unwrap();
+        match program.items.as_slice() {
+            [Item::Import(drums), Item::Import(util), Item::Import(graphics)] => {
+                assert_eq!(drums.module, "lib/drums");
+                assert_eq!(drums.items, Some(vec!["kick".to_string()]));
+                assert_eq!(util.module, "../shared/util.syn");
+                assert_eq!((graphics.module.as_str(), graphics.alias.as_deref()), ("Graphics", Some("G")));
+            }
+            other => panic!("Expected three imports, got {:?}", other),
+        }
     }
 
     #[test]
@@ -261,6 +272,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_config_blocks() {
+        let program = parse_program_from_str("config { fps: 30, vsync: false }").unwrap();
+        if let Some(Item::Statement(Statement::Assignment { name, value })) = program.items.first() {
+            assert_eq!(name, "config");
+            if let Expression::Block { fields } = value {
+                assert!(fields.contains_key("fps"));
+                assert!(fields.contains_key("vsync"));
+            } else {
+                panic!("Expected config fields");
+            }
+        } else {
+            panic!("Expected config block");
+        }
+    }
+
     #[test]
     fn test_temporal_statements() {
         let program = parse_program_from_str("every(1.0) { tick() }").unwrap();
@@ -0,0 +1,1405 @@
+/// Processing and p5.js sketches converted to Synthesis, for `synthesis import`
+///
+/// `setup()` becomes top-level statements and `draw()` becomes a `loop { }`. The common drawing
+/// calls map onto the Graphics module, with fill and stroke kept in plain variables:
+///
+/// ```text
+/// void draw() {                   loop {
+///   fill(255, 0, 0);        =>        fill_color = 16711680
+///   ellipse(x, 50, 20, 20);           Graphics.circle(x, 50, 10, fill_color)
+/// }                               }
+/// ```
+///
+/// Anything without a Synthesis equivalent, like transforms, events, arrays or user functions,
+/// is kept as a comment and reported as a warning with its line number. The output still parses,
+/// and the leftover work is easy to find.
+use crate::errors::{synthesis_error, ErrorKind, SourceLocation, SynthesisError};
+use std::collections::HashSet;
+
+/// Synthesis keywords are matched as prefixes, so names starting with one get renamed
+const KEYWORD_PREFIXES: &[&str] = &[
+    "import", "loop", "match", "every", "after", "while", "for", "in", "if", "else", "func",
+    "class", "struct", "enum", "let", "mut", "return", "break", "continue", "main", "as",
+    "content", "style", "true", "false",
+];
+
+/// Types that start a variable declaration
+const DECLARATION_TYPES: &[&str] = &[
+    "int", "float", "double", "long", "short", "byte", "char", "boolean", "String", "color",
+    "let", "var", "const",
+];
+
+const MODIFIERS: &[&str] = &["final", "static", "public", "private", "protected"];
+
+/// Longest first, so `+=` wins over `+`
+const PUNCTUATION: &[&str] = &[
+    "===", "!==", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "++", "--",
+    "+", "-", "*", "/", "%", "<", ">", "=", "!", "?", ":", ";", ",", ".", "(", ")", "{", "}",
+    "[", "]", "&", "|",
+];
+
+/// Defaults Processing starts every sketch with
+const DEFAULT_FILL: i64 = 0xFFFFFF;
+const DEFAULT_STROKE: i64 = 0x000000;
+const DEFAULT_TEXT_SIZE: i64 = 12;
+const DEFAULT_CANVAS: (&str, &str) = ("100", "100");
+/// Stand-in for p5's `windowWidth`/`windowHeight`
+const WINDOW_SIZE: (&str, &str) = ("1280", "720");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportWarning {
+    /// Line in the original sketch
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SketchImport {
+    pub source: String,
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Convert a `.pde` or p5 `.js` sketch; `name` only goes into the header comment
+pub fn import_sketch(source: &str, name: &str) -> crate::Result<SketchImport> {
+    let tokens = tokenize(source)?;
+    let items = SketchParser { tokens, pos: 0, source }.items()?;
+    Ok(Converter::default().convert(&items, name))
+}
+
+fn import_error(line: usize, message: impl Into<String>, suggestion: &str) -> SynthesisError {
+    synthesis_error(ErrorKind::SyntaxError, message)
+        .with_suggestion(suggestion)
+        .with_docs("https://synthesis-lang.org/docs/import")
+        .with_location(SourceLocation { line, column: 1, filename: String::new() })
+}
+
+/// A parse error's message without its emoji, for use as a warning
+fn reason(error: SynthesisError) -> String {
+    error.message.trim_start_matches("🔁 ").to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    /// Already normalized to something Synthesis lexes (`.5f` -> `0.5`, `0xFF` -> `255`)
+    Number(String),
+    Str(String),
+    /// Processing's `#FF8800`
+    Color(i64),
+    Punct(&'static str),
+    Comment(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    line: usize,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(source: &str) -> crate::Result<Vec<Token>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let rest = &source[i..];
+
+        let tok = if c == b'\n' {
+            line += 1;
+            i += 1;
+            continue;
+        } else if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        } else if rest.starts_with("//") {
+            i = rest.find('\n').map_or(bytes.len(), |n| start + n);
+            Tok::Comment(source[start + 2..i].trim().to_string())
+        } else if rest.starts_with("/*") {
+            let close = rest.find("*/")
+                .ok_or_else(|| import_error(line, "🔁 This /* comment never ends", "Close it with */"))?;
+            i = start + close + 2;
+            let text = rest[2..close].lines()
+                .map(|l| l.trim().trim_start_matches('*').trim())
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let token = Token { tok: Tok::Comment(text), line, start, end: i };
+            line += rest[..close].matches('\n').count();
+            tokens.push(token);
+            continue;
+        } else if c == b'"' || c == b'\'' || c == b'`' {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != c && bytes[j] != b'\n' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            if j >= bytes.len() || bytes[j] != c {
+                return Err(import_error(line, "🔁 This string never ends", "Close it on the same line"));
+            }
+            i = j + 1;
+            Tok::Str(source[start + 1..j].to_string())
+        } else if c == b'#' {
+            let digits = rest[1..].find(|ch: char| !ch.is_ascii_hexdigit()).unwrap_or(rest.len() - 1);
+            let value = i64::from_str_radix(&rest[1..1 + digits], 16)
+                .map_err(|_| import_error(line, "🔁 Color literals look like #FF8800", "Use six hex digits after the #"))?;
+            i += 1 + digits;
+            Tok::Color(value)
+        } else if c.is_ascii_digit() || (c == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) {
+            let (len, number) = read_number(rest);
+            i += len;
+            Tok::Number(number)
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'$' {
+            let len = rest.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '$')).unwrap_or(rest.len());
+            i += len;
+            Tok::Ident(rest[..len].to_string())
+        } else if let Some(punct) = PUNCTUATION.iter().find(|p| rest.starts_with(**p)) {
+            i += punct.len();
+            Tok::Punct(punct)
+        } else {
+            let ch = rest.chars().next().unwrap_or(' ');
+            return Err(import_error(line, format!("🔁 Unexpected '{}' in the sketch", ch), "Check the sketch runs in Processing or p5.js first"));
+        };
+        tokens.push(Token { tok, line, start, end: i });
+    }
+    Ok(tokens)
+}
+
+/// Read a Java/JS number literal; returns its length and a Synthesis spelling of it
+fn read_number(text: &str) -> (usize, String) {
+    if text.starts_with("0x") || text.starts_with("0X") {
+        let digits = text[2..].find(|ch: char| !ch.is_ascii_hexdigit()).unwrap_or(text.len() - 2);
+        let value = i64::from_str_radix(&text[2..2 + digits], 16).unwrap_or(0);
+        return (2 + digits, value.to_string());
+    }
+
+    let bytes = text.as_bytes();
+    let digits = |from: usize| from + bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut len = digits(0);
+    let mut is_float = false;
+    if bytes.get(len) == Some(&b'.') {
+        is_float = true;
+        len = digits(len + 1);
+    }
+    let mut exponent = false;
+    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        if bytes.get(len + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            exponent = true;
+            len = digits(len + 1 + sign);
+        }
+    }
+    let literal = &text[..len];
+    if matches!(bytes.get(len), Some(b'f' | b'F' | b'd' | b'D' | b'l' | b'L')) {
+        len += 1;
+    }
+
+    let spelled = if exponent {
+        format_number(literal.parse::<f64>().unwrap_or(0.0))
+    } else if is_float {
+        let literal = if literal.starts_with('.') { format!("0{}", literal) } else { literal.to_string() };
+        if literal.ends_with('.') { format!("{}0", literal) } else { literal }
+    } else {
+        literal.to_string()
+    };
+    (len, spelled)
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(String),
+    Str(String),
+    Bool(bool),
+    Ident(String),
+    /// `name(args)`; after conversion the name may be qualified, like `Math.sin`
+    Call(String, Vec<Expr>),
+    Member(Box<Expr>, String),
+    MethodCall(Box<Expr>, String, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Parsed so the statement can be skipped, but never convertible
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone)]
+struct Stmt {
+    line: usize,
+    /// The statement as written, kept as a comment if it can't be converted
+    text: String,
+    kind: StmtKind,
+}
+
+#[derive(Debug, Clone)]
+enum StmtKind {
+    Comment(String),
+    Block(Vec<Stmt>),
+    Declare { ty: String, name: String, value: Option<Expr> },
+    Assign { target: Expr, op: &'static str, value: Expr },
+    Increment { target: Expr, delta: i64 },
+    Expr(Expr),
+    If { condition: Expr, then_branch: Vec<Stmt>, else_branch: Option<Vec<Stmt>> },
+    While { condition: Expr, body: Vec<Stmt> },
+    For { init: Vec<Stmt>, condition: Option<Expr>, update: Vec<Stmt>, body: Vec<Stmt> },
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Statement(Stmt),
+    /// `setup()` and `draw()` are parsed; other functions are only kept as text
+    Function { name: String, line: usize, body: Option<Vec<Stmt>>, text: String },
+    /// Classes and library imports
+    Unsupported { line: usize, text: String, reason: String },
+}
+
+struct SketchParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    source: &'a str,
+}
+
+impl SketchParser<'_> {
+    fn items(&mut self) -> crate::Result<Vec<Item>> {
+        let mut items = Vec::new();
+        while let Some(token) = self.tokens.get(self.pos).cloned() {
+            if let Some((name, body_start)) = self.function_header() {
+                let end = self.matching_brace(body_start)?;
+                let body = if name == "setup" || name == "draw" {
+                    self.pos = body_start;
+                    Some(self.block()?)
+                } else {
+                    None
+                };
+                self.pos = end + 1;
+                let text = self.source[token.start..self.tokens[end].end].to_string();
+                items.push(Item::Function { name, line: token.line, body, text });
+                continue;
+            }
+            match &token.tok {
+                Tok::Ident(word) if word == "class" => {
+                    let open = (self.pos..self.tokens.len()).find(|&i| self.tokens[i].tok == Tok::Punct("{"));
+                    let end = match open {
+                        Some(open) => self.matching_brace(open)?,
+                        None => self.tokens.len() - 1,
+                    };
+                    self.pos = end + 1;
+                    items.push(Item::Unsupported {
+                        line: token.line,
+                        text: self.source[token.start..self.tokens[end].end].to_string(),
+                        reason: "classes aren't converted yet".to_string(),
+                    });
+                }
+                Tok::Ident(word) if word == "import" => {
+                    let stmt = self.skip_statement("");
+                    items.push(Item::Unsupported {
+                        line: token.line,
+                        text: stmt.text,
+                        reason: "Processing libraries aren't available; look for the matching Synthesis module".to_string(),
+                    });
+                }
+                _ => items.push(Item::Statement(self.statement_or_skip())),
+            }
+        }
+        Ok(items)
+    }
+
+    /// `void name(...) {`, `float name(...) {` or `function name(...) {`; returns the name and the `{` index
+    fn function_header(&self) -> Option<(String, usize)> {
+        let mut i = self.pos;
+        while matches!(self.tok(i), Some(Tok::Ident(word)) if MODIFIERS.contains(&word.as_str())) {
+            i += 1;
+        }
+        let name = match (self.tok(i), self.tok(i + 1), self.tok(i + 2)) {
+            (Some(Tok::Ident(_)), Some(Tok::Ident(name)), Some(Tok::Punct("("))) => name.clone(),
+            _ => return None,
+        };
+        let mut depth = 0;
+        for j in i + 2..self.tokens.len() {
+            match self.tok(j) {
+                Some(Tok::Punct("(")) => depth += 1,
+                Some(Tok::Punct(")")) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return (self.tok(j + 1) == Some(&Tok::Punct("{"))).then_some((name, j + 1));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn matching_brace(&self, open: usize) -> crate::Result<usize> {
+        let mut depth = 0;
+        for i in open..self.tokens.len() {
+            match self.tok(i) {
+                Some(Tok::Punct("{")) => depth += 1,
+                Some(Tok::Punct("}")) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(import_error(self.tokens[open].line, "🔁 This { is never closed", "Check the sketch's braces match up"))
+    }
+
+    fn tok(&self, i: usize) -> Option<&Tok> {
+        self.tokens.get(i).map(|t| &t.tok)
+    }
+
+    /// Current token, skipping comments; comments inside expressions are dropped
+    fn peek(&mut self) -> Option<&Tok> {
+        while matches!(self.tok(self.pos), Some(Tok::Comment(_))) {
+            self.pos += 1;
+        }
+        self.tok(self.pos)
+    }
+
+    fn peek_at(&mut self, offset: usize) -> Option<&Tok> {
+        self.peek();
+        self.tok(self.pos + offset)
+    }
+
+    fn is_punct(&mut self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Punct(p)) if *p == punct)
+    }
+
+    fn is_word(&mut self, word: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(w)) if w == word)
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let matched = self.is_punct(punct);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expect(&mut self, punct: &str) -> crate::Result<()> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        let line = self.line();
+        Err(import_error(line, format!("🔁 Expected '{}' here", punct), "Check the sketch runs in Processing or p5.js first"))
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |t| t.line)
+    }
+
+    /// Source text of the tokens from `start` up to the current position
+    fn text_from(&self, start: usize) -> String {
+        let first = self.tokens.get(start).map_or(self.source.len(), |t| t.start);
+        let last = self.tokens.get(self.pos.saturating_sub(1)).map_or(first, |t| t.end);
+        self.source[first..last.max(first)].to_string()
+    }
+
+    /// A `;`, or for p5 sketches that leave them out, the end of the line
+    fn end_statement(&mut self) -> crate::Result<()> {
+        if self.eat(";") || self.is_punct("}") || self.peek().is_none() {
+            return Ok(());
+        }
+        if self.tokens[self.pos].line > self.tokens[self.pos - 1].line {
+            return Ok(());
+        }
+        self.expect(";")
+    }
+
+    fn block(&mut self) -> crate::Result<Vec<Stmt>> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while self.tok(self.pos).is_some() && self.tok(self.pos) != Some(&Tok::Punct("}")) {
+            stmts.push(self.statement_or_skip());
+        }
+        self.expect("}")?;
+        Ok(stmts)
+    }
+
+    /// A block, or the single statement an `if` or loop allows without braces
+    fn body(&mut self) -> crate::Result<Vec<Stmt>> {
+        if self.is_punct("{") {
+            self.block()
+        } else {
+            self.statement().map(|stmt| vec![stmt])
+        }
+    }
+
+    /// Statements this converter can't read are skipped and kept as text
+    fn statement_or_skip(&mut self) -> Stmt {
+        let start = self.pos;
+        match self.statement() {
+            Ok(stmt) => stmt,
+            Err(error) => {
+                self.pos = start;
+                self.skip_statement(&reason(error))
+            }
+        }
+    }
+
+    /// Consume up to the end of the statement: a `;` or the close of a block it opened
+    fn skip_statement(&mut self, reason: &str) -> Stmt {
+        self.peek();
+        let (start, line) = (self.pos, self.line());
+        let mut depth = 0;
+        while let Some(tok) = self.tok(self.pos) {
+            match tok {
+                Tok::Punct("(" | "{" | "[") => depth += 1,
+                Tok::Punct(")" | "]") => depth -= 1,
+                // The enclosing block's `}` is left for the caller
+                Tok::Punct("}") if depth == 0 && self.pos > start => break,
+                Tok::Punct("}") => {
+                    depth -= 1;
+                    let continues = matches!(self.tok(self.pos + 1), Some(Tok::Ident(word)) if word == "else");
+                    if depth <= 0 && !continues {
+                        self.pos += 1;
+                        break;
+                    }
+                }
+                Tok::Punct(";") if depth <= 0 => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        Stmt { line, text: self.text_from(start), kind: StmtKind::Unsupported(reason.to_string()) }
+    }
+
+    fn statement(&mut self) -> crate::Result<Stmt> {
+        let start = self.pos;
+        let line = self.line();
+        let kind = self.statement_kind()?;
+        Ok(Stmt { line, text: self.text_from(start), kind })
+    }
+
+    fn statement_kind(&mut self) -> crate::Result<StmtKind> {
+        if let Some(Tok::Comment(text)) = self.tok(self.pos) {
+            let text = text.clone();
+            self.pos += 1;
+            return Ok(StmtKind::Comment(text));
+        }
+        let word = match self.peek() {
+            None => return Err(import_error(self.line(), "🔁 The sketch ends in the middle of a statement", "Check the sketch's braces match up")),
+            Some(Tok::Punct("{")) => return self.block().map(StmtKind::Block),
+            Some(Tok::Punct(";")) => {
+                self.pos += 1;
+                return Ok(StmtKind::Block(Vec::new()));
+            }
+            Some(Tok::Ident(word)) => word.clone(),
+            Some(_) => String::new(),
+        };
+
+        match word.as_str() {
+            "if" => {
+                self.pos += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                let then_branch = self.body()?;
+                let else_branch = if self.is_word("else") {
+                    self.pos += 1;
+                    Some(self.body()?)
+                } else {
+                    None
+                };
+                Ok(StmtKind::If { condition, then_branch, else_branch })
+            }
+            "while" => {
+                self.pos += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                let body = self.body()?;
+                Ok(StmtKind::While { condition, body })
+            }
+            "for" => {
+                self.pos += 1;
+                self.expect("(")?;
+                let init = if self.is_punct(";") { Vec::new() } else { self.simple_statements()? };
+                self.expect(";")?;
+                let condition = if self.is_punct(";") { None } else { Some(self.expression()?) };
+                self.expect(";")?;
+                let update = if self.is_punct(")") { Vec::new() } else { self.simple_statements()? };
+                self.expect(")")?;
+                let body = self.body()?;
+                Ok(StmtKind::For { init, condition, update, body })
+            }
+            "switch" | "do" | "return" | "break" | "continue" | "try" | "throw" => {
+                let reason = match word.as_str() {
+                    "return" => "🔁 return only makes sense inside functions, which aren't converted yet",
+                    "break" | "continue" => "🔁 break and continue inside converted loops aren't handled yet",
+                    _ => "🔁 This kind of statement has no Synthesis equivalent yet",
+                };
+                Err(import_error(self.line(), reason, "Convert this part by hand"))
+            }
+            _ => {
+                let stmts = self.simple_statements()?;
+                self.end_statement()?;
+                Ok(match stmts.len() {
+                    1 => stmts.into_iter().next().map(|s| s.kind).unwrap_or(StmtKind::Block(Vec::new())),
+                    _ => StmtKind::Block(stmts),
+                })
+            }
+        }
+    }
+
+    /// A declaration or expression statement, without its `;`, as used in `for (...)` headers too
+    fn simple_statements(&mut self) -> crate::Result<Vec<Stmt>> {
+        let (start, line) = (self.pos, self.line());
+        while matches!(self.peek(), Some(Tok::Ident(word)) if MODIFIERS.contains(&word.as_str())) {
+            self.pos += 1;
+        }
+
+        let is_declaration = match (self.peek_at(0).cloned(), self.peek_at(1).cloned()) {
+            (Some(Tok::Ident(ty)), Some(Tok::Ident(_))) => DECLARATION_TYPES.contains(&ty.as_str()) || ty.starts_with(char::is_uppercase),
+            (Some(Tok::Ident(ty)), Some(Tok::Punct("["))) => DECLARATION_TYPES.contains(&ty.as_str()),
+            _ => false,
+        };
+        if is_declaration {
+            let ty = match self.peek().cloned() {
+                Some(Tok::Ident(ty)) => ty,
+                _ => String::new(),
+            };
+            self.pos += 1;
+            let mut stmts = Vec::new();
+            loop {
+                let decl_start = self.pos;
+                if self.is_punct("[") {
+                    return Err(import_error(line, "🔁 Arrays aren't converted yet", "Rewrite it with separate variables"));
+                }
+                let name = match self.peek().cloned() {
+                    Some(Tok::Ident(name)) => name,
+                    _ => return Err(import_error(line, "🔁 Expected a variable name", "Check the declaration")),
+                };
+                self.pos += 1;
+                if self.is_punct("[") {
+                    return Err(import_error(line, "🔁 Arrays aren't converted yet", "Rewrite it with separate variables"));
+                }
+                let value = if self.eat("=") { Some(self.expression()?) } else { None };
+                stmts.push(Stmt {
+                    line,
+                    text: self.text_from(if stmts.is_empty() { start } else { decl_start }),
+                    kind: StmtKind::Declare { ty: ty.clone(), name, value },
+                });
+                if !self.eat(",") {
+                    return Ok(stmts);
+                }
+            }
+        }
+
+        let mut stmts = Vec::new();
+        loop {
+            let part_start = self.pos;
+            let kind = if self.eat("++") || self.eat("--") {
+                let delta = if self.tok(self.pos - 1) == Some(&Tok::Punct("++")) { 1 } else { -1 };
+                StmtKind::Increment { target: self.postfix()?, delta }
+            } else {
+                let target = self.expression()?;
+                let op = match self.peek() {
+                    Some(Tok::Punct(op @ ("=" | "+=" | "-=" | "*=" | "/=" | "++" | "--"))) => Some(*op),
+                    _ => None,
+                };
+                match op {
+                    Some(op @ ("++" | "--")) => {
+                        self.pos += 1;
+                        StmtKind::Increment { target, delta: if op == "++" { 1 } else { -1 } }
+                    }
+                    Some(op) => {
+                        self.pos += 1;
+                        StmtKind::Assign { target, op, value: self.expression()? }
+                    }
+                    None => StmtKind::Expr(target),
+                }
+            };
+            stmts.push(Stmt { line, text: self.text_from(part_start), kind });
+            if !self.eat(",") {
+                return Ok(stmts);
+            }
+        }
+    }
+
+    fn expression(&mut self) -> crate::Result<Expr> {
+        let condition = self.binary(1)?;
+        if self.eat("?") {
+            let then_value = self.expression()?;
+            self.expect(":")?;
+            let else_value = self.expression()?;
+            return Ok(Expr::Ternary(Box::new(condition), Box::new(then_value), Box::new(else_value)));
+        }
+        Ok(condition)
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> crate::Result<Expr> {
+        let mut left = self.unary()?;
+        while let Some(Tok::Punct(op)) = self.peek() {
+            let op = *op;
+            let precedence = match precedence(op) {
+                Some(p) if p >= min_precedence => p,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.binary(precedence + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> crate::Result<Expr> {
+        if self.eat("-") {
+            return Ok(Expr::Unary("-", Box::new(self.unary()?)));
+        }
+        if self.eat("!") {
+            return Ok(Expr::Unary("!", Box::new(self.unary()?)));
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        // Java casts: (int) x floors, (float) x is a no-op
+        if let (Some(Tok::Punct("(")), Some(Tok::Ident(ty)), Some(Tok::Punct(")"))) =
+            (self.peek_at(0).cloned(), self.peek_at(1).cloned(), self.peek_at(2).cloned())
+        {
+            if DECLARATION_TYPES.contains(&ty.as_str()) {
+                self.pos += 3;
+                let value = self.unary()?;
+                return Ok(Expr::Call(ty, vec![value]));
+            }
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> crate::Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat("(") {
+                let args = self.arguments()?;
+                expr = match expr {
+                    Expr::Ident(name) => Expr::Call(name, args),
+                    Expr::Member(object, name) => Expr::MethodCall(object, name, args),
+                    other => other,
+                };
+            } else if self.eat(".") {
+                match self.peek().cloned() {
+                    Some(Tok::Ident(name)) => {
+                        self.pos += 1;
+                        expr = Expr::Member(Box::new(expr), name);
+                    }
+                    _ => return Err(import_error(self.line(), "🔁 Expected a name after '.'", "Check the sketch")),
+                }
+            } else if self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> crate::Result<Vec<Expr>> {
+        let mut args = Vec::new();
+        if self.eat(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            if self.eat(")") {
+                return Ok(args);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn primary(&mut self) -> crate::Result<Expr> {
+        let line = self.line();
+        let tok = self.peek().cloned()
+            .ok_or_else(|| import_error(line, "🔁 The sketch ends in the middle of an expression", "Check the sketch"))?;
+        self.pos += 1;
+        match tok {
+            Tok::Number(n) => Ok(Expr::Number(n)),
+            Tok::Str(s) => Ok(Expr::Str(s)),
+            Tok::Color(c) => Ok(Expr::Number(c.to_string())),
+            Tok::Ident(word) if word == "true" || word == "false" => Ok(Expr::Bool(word == "true")),
+            Tok::Ident(word) if word == "new" => {
+                let constructed = self.postfix()?;
+                let what = match constructed {
+                    Expr::Call(name, _) => name,
+                    _ => "objects".to_string(),
+                };
+                Ok(Expr::Unsupported(format!("creating objects (new {}) isn't converted yet", what)))
+            }
+            Tok::Ident(word) => Ok(Expr::Ident(word)),
+            Tok::Punct("(") => {
+                let inner = self.expression()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Tok::Punct("[") => Err(import_error(line, "🔁 Arrays aren't converted yet", "Rewrite it with separate variables")),
+            _ => Err(import_error(line, "🔁 Couldn't read this expression", "Check the sketch runs in Processing or p5.js first")),
+        }
+    }
+}
+
+fn precedence(op: &str) -> Option<u8> {
+    match op {
+        "||" => Some(1),
+        "&&" => Some(2),
+        "==" | "!=" | "===" | "!==" => Some(3),
+        "<" | ">" | "<=" | ">=" => Some(4),
+        "+" | "-" => Some(5),
+        "*" | "/" | "%" => Some(6),
+        _ => None,
+    }
+}
+
+/// Synthesis spelling of a sketch's variable name: snake_case, clear of keyword prefixes
+fn rename(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous_lower = false;
+    for ch in name.chars() {
+        if ch.is_ascii_uppercase() && previous_lower {
+            snake.push('_');
+        }
+        previous_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+        snake.push(if ch == '$' { '_' } else { ch.to_ascii_lowercase() });
+    }
+    let snake = snake.trim_start_matches('_').to_string();
+    if snake.is_empty() || KEYWORD_PREFIXES.iter().any(|k| snake.starts_with(k)) {
+        format!("v_{}", snake)
+    } else {
+        snake
+    }
+}
+
+fn render(expr: &Expr) -> String {
+    render_within(expr, 0)
+}
+
+/// Render with just the parentheses Synthesis' precedence needs
+fn render_within(expr: &Expr, parent: u8) -> String {
+    match expr {
+        Expr::Number(n) => n.clone(),
+        Expr::Str(s) => format!("\"{}\"", s),
+        Expr::Bool(b) => b.to_string(),
+        Expr::Ident(name) => name.clone(),
+        Expr::Call(name, args) => format!("{}({})", name, args.iter().map(render).collect::<Vec<_>>().join(", ")),
+        Expr::Binary(op, left, right) => {
+            let p = precedence(op).unwrap_or(0);
+            let text = format!("{} {} {}", render_within(left, p), op, render_within(right, p + 1));
+            if p < parent { format!("({})", text) } else { text }
+        }
+        // Everything else is turned into the forms above before rendering
+        Expr::Member(..) | Expr::MethodCall(..) | Expr::Index(..) | Expr::Unary(..) | Expr::Ternary(..) | Expr::Unsupported(_) => String::new(),
+    }
+}
+
+fn number(value: f64) -> Expr {
+    Expr::Number(format_number(value))
+}
+
+fn literal(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Number(n) => n.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Build `left op right`, folding what `map()` and friends expand to when the inputs are literal
+fn binary(op: &'static str, left: Expr, right: Expr) -> Expr {
+    match (op, literal(&left), literal(&right)) {
+        // Negative results stay unfolded; Synthesis has no negative literals
+        ("+", Some(l), Some(r)) if l + r >= 0.0 => number(l + r),
+        ("-", Some(l), Some(r)) if l - r >= 0.0 => number(l - r),
+        ("*", Some(l), Some(r)) if l * r >= 0.0 => number(l * r),
+        ("+", Some(0.0), _) | ("*", Some(1.0), _) => right,
+        ("+" | "-", _, Some(0.0)) | ("*" | "/", _, Some(1.0)) => left,
+        _ => Expr::Binary(op, Box::new(left), Box::new(right)),
+    }
+}
+
+fn call(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::Call(name.to_string(), args)
+}
+
+#[derive(Default)]
+struct Converter {
+    warnings: Vec<ImportWarning>,
+    warned_once: HashSet<&'static str>,
+    line: usize,
+    /// Prelude variables the converted code relies on
+    uses: HashSet<&'static str>,
+    /// Variables declared as `color`, which fill() and friends take as-is
+    color_vars: HashSet<String>,
+    functions: HashSet<String>,
+    canvas: Option<(String, String)>,
+    fps: Option<String>,
+    no_loop: bool,
+}
+
+/// Why a statement or expression has to stay a comment
+type Unconvertible = String;
+
+impl Converter {
+    fn convert(mut self, items: &[Item], name: &str) -> SketchImport {
+        self.functions = items.iter()
+            .filter_map(|item| match item {
+                Item::Function { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        let function = |wanted: &str| items.iter().find_map(|item| match item {
+            Item::Function { name, body: Some(body), .. } if name == wanted => Some(body.clone()),
+            _ => None,
+        });
+
+        let mut globals = Vec::new();
+        for item in items {
+            if let Item::Statement(stmt) = item {
+                self.statement(stmt, 0, &mut globals);
+            }
+        }
+
+        let mut setup_lines = Vec::new();
+        if let Some(setup) = function("setup") {
+            let body = self.hoist_setup(setup);
+            self.statements(&body, 0, &mut setup_lines);
+        }
+
+        let mut draw_lines = Vec::new();
+        if let Some(draw) = function("draw") {
+            let indent = if self.no_loop { 0 } else { 1 };
+            self.statements(&draw, indent, &mut draw_lines);
+        }
+
+        let mut leftovers = Vec::new();
+        for item in items {
+            match item {
+                Item::Function { name, line, body: None, text } => {
+                    let reason = if is_event_handler(name) {
+                        format!("{}() is an input event, and Synthesis has no mouse or keyboard events yet", name)
+                    } else {
+                        format!("{}() is a user function, and functions aren't converted yet", name)
+                    };
+                    self.warn(*line, reason);
+                    leftovers.push(String::new());
+                    leftovers.extend(text.lines().map(|l| format!("// {}", l).trim_end().to_string()));
+                }
+                Item::Unsupported { line, text, reason } => {
+                    self.warn(*line, reason.clone());
+                    leftovers.extend(text.lines().map(|l| format!("// {}", l).trim_end().to_string()));
+                }
+                _ => {}
+            }
+        }
+
+        let mut out = vec![format!("// Converted from {} by `synthesis import`", name)];
+        if let Some(fps) = &self.fps {
+            out.push(String::new());
+            out.push(format!("config {{ fps: {} }}", fps));
+        }
+
+        let mut prelude = Vec::new();
+        if self.canvas.is_some() || self.uses.contains("width") {
+            let (width, height) = self.canvas.clone()
+                .unwrap_or((DEFAULT_CANVAS.0.to_string(), DEFAULT_CANVAS.1.to_string()));
+            prelude.push(format!("width = {}", width));
+            prelude.push(format!("height = {}", height));
+        }
+        if self.uses.contains("fill_color") {
+            prelude.push(format!("fill_color = {}", DEFAULT_FILL));
+        }
+        if self.uses.contains("stroke_color") {
+            prelude.push(format!("stroke_color = {}", DEFAULT_STROKE));
+        }
+        if self.uses.contains("text_size") {
+            prelude.push(format!("text_size = {}", DEFAULT_TEXT_SIZE));
+        }
+        if self.uses.contains("mouse") {
+            prelude.push("mouse_x = width / 2".to_string());
+            prelude.push("mouse_y = height / 2".to_string());
+        }
+        if self.uses.contains("frame_count") {
+            prelude.push("frame_count = 0".to_string());
+        }
+
+        for section in [prelude, globals, setup_lines] {
+            if !section.is_empty() {
+                out.push(String::new());
+                out.extend(section);
+            }
+        }
+        if function("draw").is_some() {
+            out.push(String::new());
+            let counter = if self.no_loop { "" } else { "    " };
+            if !self.no_loop {
+                out.push("loop {".to_string());
+            }
+            if self.uses.contains("frame_count") {
+                out.push(format!("{}frame_count = frame_count + 1", counter));
+            }
+            out.extend(draw_lines);
+            if !self.no_loop {
+                out.push("}".to_string());
+            }
+        }
+        out.extend(leftovers);
+
+        let mut source = out.join("\n");
+        source.push('\n');
+        SketchImport { source, warnings: self.warnings }
+    }
+
+    /// Pull canvas size, frame rate and noLoop() out of setup() into the prelude
+    fn hoist_setup(&mut self, body: Vec<Stmt>) -> Vec<Stmt> {
+        body.into_iter()
+            .filter(|stmt| {
+                let StmtKind::Expr(Expr::Call(name, args)) = &stmt.kind else { return true };
+                match (name.as_str(), args.as_slice()) {
+                    ("size" | "createCanvas", [width, height, ..]) => {
+                        let dimension = |expr: &Expr, window: &str| match expr {
+                            Expr::Number(n) => Some(n.clone()),
+                            Expr::Ident(name) if name.starts_with("window") || name.starts_with("display") => Some(window.to_string()),
+                            _ => None,
+                        };
+                        match (dimension(width, WINDOW_SIZE.0), dimension(height, WINDOW_SIZE.1)) {
+                            (Some(w), Some(h)) => {
+                                self.canvas = Some((w, h));
+                                false
+                            }
+                            _ => true,
+                        }
+                    }
+                    ("frameRate", [Expr::Number(fps)]) => {
+                        self.fps = Some(fps.clone());
+                        false
+                    }
+                    ("noLoop", []) => {
+                        self.no_loop = true;
+                        false
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+
+    fn warn(&mut self, line: usize, message: impl Into<String>) {
+        self.warnings.push(ImportWarning { line, message: message.into() });
+    }
+
+    fn warn_once(&mut self, key: &'static str, message: &str) {
+        if self.warned_once.insert(key) {
+            let line = self.line;
+            self.warn(line, message);
+        }
+    }
+
+    fn statements(&mut self, stmts: &[Stmt], indent: usize, out: &mut Vec<String>) {
+        for stmt in stmts {
+            self.statement(stmt, indent, out);
+        }
+    }
+
+    /// Convert one statement, or keep it as a comment with a warning if it can't be
+    fn statement(&mut self, stmt: &Stmt, indent: usize, out: &mut Vec<String>) {
+        self.line = stmt.line;
+        let mut lines = Vec::new();
+        match self.try_statement(stmt, indent, &mut lines) {
+            Ok(()) => out.extend(lines),
+            Err(reason) => {
+                self.warn(stmt.line, reason);
+                out.extend(original_as_comment(stmt, indent));
+            }
+        }
+    }
+
+    fn try_statement(&mut self, stmt: &Stmt, indent: usize, out: &mut Vec<String>) -> Result<(), Unconvertible> {
+        let pad = "    ".repeat(indent);
+        match &stmt.kind {
+            StmtKind::Comment(text) => {
+                out.extend(text.lines().map(|line| format!("{}// {}", pad, line).trim_end().to_string()));
+                if text.is_empty() {
+                    out.push(format!("{}//", pad));
+                }
+            }
+            StmtKind::Block(stmts) => self.statements(stmts, indent, out),
+            StmtKind::Unsupported(reason) => return Err(reason.clone()),
+            StmtKind::Declare { ty, name, value } => {
+                if ty == "color" {
+                    self.color_vars.insert(name.clone());
+                }
+                let value = match value {
+                    Some(value) => value.clone(),
+                    None => match ty.as_str() {
+                        "boolean" => Expr::Bool(false),
+                        "String" => Expr::Str(String::new()),
+                        "float" | "double" => Expr::Number("0.0".to_string()),
+                        "int" | "long" | "short" | "byte" | "color" | "let" | "var" => Expr::Number("0".to_string()),
+                        _ => return Err(format!("objects like {} aren't converted yet", ty)),
+                    },
+                };
+                self.assign(&rename(name), value, &pad, out)?;
+            }
+            StmtKind::Assign { target, op, value } => {
+                let name = self.target(target)?;
+                let value = match *op {
+                    "=" => value.clone(),
+                    _ => {
+                        let op = match *op { "+=" => "+", "-=" => "-", "*=" => "*", _ => "/" };
+                        binary(op, target.clone(), value.clone())
+                    }
+                };
+                self.assign(&name, value, &pad, out)?;
+            }
+            StmtKind::Increment { target, delta } => {
+                let name = self.target(target)?;
+                let op = if *delta > 0 { "+" } else { "-" };
+                out.push(format!("{}{} = {} {} 1", pad, name, name, op));
+            }
+            StmtKind::Expr(Expr::Call(name, args)) => {
+                for line in self.call_statement(name, args)? {
+                    out.push(format!("{}{}", pad, line));
+                }
+            }
+            StmtKind::Expr(expr) => {
+                let expr = self.expr(expr)?;
+                out.push(format!("{}{}", pad, render(&expr)));
+            }
+            StmtKind::If { condition, then_branch, else_branch } => {
+                let condition = self.expr(condition)?;
+                out.push(format!("{}if {} {{", pad, render(&condition)));
+                self.statements(then_branch, indent + 1, out);
+                if let Some(else_branch) = else_branch {
+                    out.push(format!("{}}} else {{", pad));
+                    self.statements(else_branch, indent + 1, out);
+                }
+                out.push(format!("{}}}", pad));
+            }
+            StmtKind::While { condition, body } => {
+                let condition = self.expr(condition)?;
+                out.push(format!("{}while {} {{", pad, render(&condition)));
+                self.statements(body, indent + 1, out);
+                out.push(format!("{}}}", pad));
+            }
+            StmtKind::For { init, condition, update, body } => {
+                let condition = match condition {
+                    Some(condition) => self.expr(condition)?,
+                    None => Expr::Bool(true),
+                };
+                self.statements(init, indent, out);
+                out.push(format!("{}while {} {{", pad, render(&condition)));
+                self.statements(body, indent + 1, out);
+                self.statements(update, indent + 1, out);
+                out.push(format!("{}}}", pad));
+            }
+        }
+        Ok(())
+    }
+
+    /// `name = value`, with `c ? a : b` spelled out as an if/else
+    fn assign(&mut self, name: &str, value: Expr, pad: &str, out: &mut Vec<String>) -> Result<(), Unconvertible> {
+        if let Expr::Ternary(condition, then_value, else_value) = value {
+            let condition = self.expr(&condition)?;
+            let (then_value, else_value) = (self.expr(&then_value)?, self.expr(&else_value)?);
+            out.push(format!("{}if {} {{", pad, render(&condition)));
+            out.push(format!("{}    {} = {}", pad, name, render(&then_value)));
+            out.push(format!("{}}} else {{", pad));
+            out.push(format!("{}    {} = {}", pad, name, render(&else_value)));
+            out.push(format!("{}}}", pad));
+            return Ok(());
+        }
+        let value = self.expr(&value)?;
+        out.push(format!("{}{} = {}", pad, name, render(&value)));
+        Ok(())
+    }
+
+    fn target(&mut self, target: &Expr) -> Result<String, Unconvertible> {
+        match self.expr(target)? {
+            Expr::Ident(name) => Ok(name),
+            _ => Err("only plain variables can be assigned to; fields and array items aren't converted yet".to_string()),
+        }
+    }
+
+    /// Calls used as statements: drawing, style state and canvas setup
+    fn call_statement(&mut self, name: &str, args: &[Expr]) -> Result<Vec<String>, Unconvertible> {
+        let converted = args.iter().map(|arg| self.expr(arg)).collect::<Result<Vec<_>, _>>()?;
+        let arity = |range: std::ops::RangeInclusive<usize>| {
+            if range.contains(&args.len()) {
+                Ok(())
+            } else {
+                Err(format!("{}() expects {} to {} arguments", name, range.start(), range.end()))
+            }
+        };
+
+        let line = match name {
+            "background" => format!("Graphics.clear({})", render(&self.color(args)?)),
+            "fill" | "stroke" => {
+                let variable = if name == "fill" { "fill_color" } else { "stroke_color" };
+                self.uses.insert(variable);
+                format!("{} = {}", variable, render(&self.color(args)?))
+            }
+            "ellipse" | "circle" => {
+                arity(3..=4)?;
+                if converted.len() == 4 && converted[2] != converted[3] {
+                    self.warn(self.line, "ellipse() with different width and height is drawn as a circle");
+                }
+                self.uses.insert("fill_color");
+                let radius = match literal(&converted[2]) {
+                    Some(diameter) => number(diameter / 2.0),
+                    None => binary("/", converted[2].clone(), number(2.0)),
+                };
+                format!("Graphics.circle({}, {}, {}, fill_color)", render(&converted[0]), render(&converted[1]), render(&radius))
+            }
+            "rect" | "square" => {
+                let (width, height) = match (name, converted.as_slice()) {
+                    ("square", [_, _, side, ..]) => (side, side),
+                    ("rect", [_, _, width, height, ..]) => (width, height),
+                    _ => return Err(format!("{}() needs a position and a size", name)),
+                };
+                self.uses.insert("fill_color");
+                format!("Graphics.rect({}, {}, {}, {}, fill_color)", render(&converted[0]), render(&converted[1]), render(width), render(height))
+            }
+            "line" => {
+                arity(4..=4)?;
+                self.uses.insert("stroke_color");
+                let coords = converted.iter().map(render).collect::<Vec<_>>().join(", ");
+                format!("Graphics.line({}, stroke_color)", coords)
+            }
+            "point" => {
+                arity(2..=2)?;
+                self.uses.insert("stroke_color");
+                format!("Graphics.rect({}, {}, 1, 1, stroke_color)", render(&converted[0]), render(&converted[1]))
+            }
+            "text" => {
+                arity(3..=5)?;
+                self.uses.insert("fill_color");
+                self.uses.insert("text_size");
+                format!("Graphics.text({}, {}, {}, fill_color, text_size)", render(&converted[0]), render(&converted[1]), render(&converted[2]))
+            }
+            "textSize" => {
+                arity(1..=1)?;
+                self.uses.insert("text_size");
+                format!("text_size = {}", render(&converted[0]))
+            }
+            "size" | "createCanvas" => {
+                arity(2..=3)?;
+                self.uses.insert("width");
+                return Ok(vec![
+                    format!("width = {}", render(&converted[0])),
+                    format!("height = {}", render(&converted[1])),
+                ]);
+            }
+            "frameRate" => return Err("frameRate() only converts as a number in setup(); set config { fps: ... } instead".to_string()),
+            "noLoop" => return Err("noLoop() only converts at the top of setup()".to_string()),
+            "noFill" | "noStroke" => return Err(format!("{}() has no equivalent; Synthesis shapes always fill with a color", name)),
+            "translate" | "rotate" | "scale" | "pushMatrix" | "popMatrix" | "push" | "pop" | "resetMatrix" =>
+                return Err("transforms aren't converted; offset the coordinates instead".to_string()),
+            "strokeWeight" | "smooth" | "noSmooth" | "colorMode" | "ellipseMode" | "rectMode" | "textAlign" | "textFont" | "blendMode" =>
+                return Err(format!("{}() has no Synthesis equivalent yet", name)),
+            "print" | "println" => return Err("Synthesis has no print yet".to_string()),
+            _ => render(&self.expr(&Expr::Call(name.to_string(), args.to_vec()))?),
+        };
+        Ok(vec![line])
+    }
+
+    /// Turn a sketch expression into one Synthesis can evaluate
+    fn expr(&mut self, expr: &Expr) -> Result<Expr, Unconvertible> {
+        Ok(match expr {
+            Expr::Number(_) | Expr::Str(_) | Expr::Bool(_) => expr.clone(),
+            Expr::Ident(name) => self.identifier(name)?,
+            Expr::Call(name, args) => self.call(name, args)?,
+            Expr::Member(object, name) => match (object.as_ref(), name.as_str()) {
+                (Expr::Ident(math), "PI") if math == "Math" => number(std::f64::consts::PI),
+                (Expr::Ident(math), "E") if math == "Math" => number(std::f64::consts::E),
+                _ => return Err("object fields aren't converted yet".to_string()),
+            },
+            Expr::MethodCall(object, name, args) => match object.as_ref() {
+                // p5 sketches reach for JavaScript's Math as often as the p5 globals
+                Expr::Ident(math) if math == "Math" => self.call(name, args)?,
+                Expr::Ident(console) if console == "console" => return Err("Synthesis has no print yet".to_string()),
+                _ => return Err("methods on objects aren't converted yet".to_string()),
+            },
+            Expr::Index(..) => return Err("arrays aren't converted yet".to_string()),
+            Expr::Unary(op, value) => {
+                let value = self.expr(value)?;
+                match *op {
+                    "-" => match literal(&value) {
+                        Some(0.0) => value,
+                        _ => binary("-", number(0.0), value),
+                    },
+                    _ => binary("==", value, Expr::Bool(false)),
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (self.expr(left)?, self.expr(right)?);
+                match *op {
+                    // Floored rather than truncated, which only differs for negative numbers
+                    "%" => binary("-", left.clone(), binary("*", call("Math.floor", vec![binary("/", left, right.clone())]), right)),
+                    "===" => binary("==", left, right),
+                    "!==" => binary("!=", left, right),
+                    op => binary(op, left, right),
+                }
+            }
+            Expr::Ternary(..) => return Err("?: only converts when its result is assigned to a variable".to_string()),
+            Expr::Unsupported(reason) => return Err(reason.clone()),
+        })
+    }
+
+    fn identifier(&mut self, name: &str) -> Result<Expr, Unconvertible> {
+        Ok(match name {
+            "PI" => number(std::f64::consts::PI),
+            "TWO_PI" | "TAU" => number(std::f64::consts::TAU),
+            "HALF_PI" => number(std::f64::consts::FRAC_PI_2),
+            "QUARTER_PI" => number(std::f64::consts::FRAC_PI_4),
+            "frameCount" => {
+                self.uses.insert("frame_count");
+                Expr::Ident("frame_count".to_string())
+            }
+            "width" | "height" => {
+                self.uses.insert("width");
+                Expr::Ident(name.to_string())
+            }
+            "windowWidth" | "displayWidth" | "windowHeight" | "displayHeight" => {
+                self.warn_once("window", "windowWidth/windowHeight are fixed at 1280x720");
+                Expr::Number(if name.ends_with("Width") { WINDOW_SIZE.0 } else { WINDOW_SIZE.1 }.to_string())
+            }
+            "mouseX" | "mouseY" | "pmouseX" | "pmouseY" => {
+                self.uses.insert("mouse");
+                self.uses.insert("width");
+                self.warn_once("mouse", "mouseX/mouseY are fixed at the centre of the canvas; a GUI.slider is one way to steer them");
+                Expr::Ident(if name.ends_with('X') { "mouse_x" } else { "mouse_y" }.to_string())
+            }
+            "mousePressed" | "keyPressed" | "key" | "keyCode" | "mouseButton" =>
+                return Err("mouse and keyboard state aren't available in Synthesis yet".to_string()),
+            "null" | "undefined" => return Err("Synthesis has no null value for sketches to assign".to_string()),
+            _ => Expr::Ident(rename(name)),
+        })
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<Expr, Unconvertible> {
+        if name == "color" {
+            return self.color(args);
+        }
+        let args = args.iter().map(|arg| self.expr(arg)).collect::<Result<Vec<_>, _>>()?;
+        let arg = |i: usize| args.get(i).cloned().ok_or_else(|| format!("{}() is missing an argument", name));
+
+        Ok(match name {
+            "sin" | "cos" | "tan" | "sqrt" | "abs" | "floor" | "ceil" | "round" | "pow" | "log" | "exp" | "min" | "max" =>
+                call(&format!("Math.{}", name), args),
+            "constrain" => call("Math.clamp", args),
+            "lerp" => call("Math.lerp", args),
+            "int" => call("Math.floor", args),
+            "float" => arg(0)?,
+            "sq" => binary("*", arg(0)?, arg(0)?),
+            "mag" => call("Math.sqrt", vec![binary("+", binary("*", arg(0)?, arg(0)?), binary("*", arg(1)?, arg(1)?))]),
+            "dist" => {
+                let dx = binary("-", arg(2)?, arg(0)?);
+                let dy = binary("-", arg(3)?, arg(1)?);
+                call("Math.sqrt", vec![binary("+", binary("*", dx.clone(), dx), binary("*", dy.clone(), dy))])
+            }
+            "map" => {
+                let (value, start1, stop1, start2, stop2) = (arg(0)?, arg(1)?, arg(2)?, arg(3)?, arg(4)?);
+                let scaled = binary("/", binary("*", binary("-", value, start1.clone()), binary("-", stop2, start2.clone())), binary("-", stop1, start1));
+                binary("+", start2, scaled)
+            }
+            "radians" => binary("*", arg(0)?, number(std::f64::consts::PI / 180.0)),
+            "degrees" => binary("*", arg(0)?, number(180.0 / std::f64::consts::PI)),
+            "noise" => {
+                // Processing's noise() is 0..1, Synthesis' Perlin noise is -1..1
+                let coords = (0..3).map(|i| args.get(i).cloned().unwrap_or(number(0.0))).collect();
+                binary("+", binary("*", call("Generate.perlin_noise", coords), number(0.5)), number(0.5))
+            }
+            _ if self.functions.contains(name) => return Err(format!("{}() is a user function, and functions aren't converted yet", name)),
+            _ => return Err(format!("{}() has no Synthesis equivalent yet", name)),
+        })
+    }
+
+    /// Processing color arguments (gray, gray+alpha, rgb, rgba, #hex, "css") as a 0xRRGGBB number
+    fn color(&mut self, args: &[Expr]) -> Result<Expr, Unconvertible> {
+        if args.len() == 2 || args.len() == 4 {
+            self.warn(self.line, "transparency isn't supported; the alpha value is dropped");
+        }
+        match args {
+            [Expr::Ident(name)] if self.color_vars.contains(name) => Ok(Expr::Ident(rename(name))),
+            [Expr::Call(name, inner)] if name == "color" => self.color(inner),
+            [Expr::Str(css)] => css_color(css)
+                .map(|c| number(c as f64))
+                .ok_or_else(|| format!("the color \"{}\" isn't one the converter knows", css)),
+            // Hex and #RRGGBB literals arrive as plain numbers
+            [Expr::Number(n)] if n.parse::<i64>().is_ok_and(|n| n > 255) => Ok(number((n.parse::<i64>().unwrap_or(0) & 0xFFFFFF) as f64)),
+            [gray] | [gray, _] => {
+                let gray = self.expr(gray)?;
+                Ok(match literal(&gray) {
+                    Some(g) => number(channel(g) as f64 * 65793.0),
+                    None => binary("*", call("Math.floor", vec![gray]), number(65793.0)),
+                })
+            }
+            [r, g, b] | [r, g, b, _] => {
+                let channels = [self.expr(r)?, self.expr(g)?, self.expr(b)?];
+                if let [Some(r), Some(g), Some(b)] = channels.each_ref().map(literal) {
+                    return Ok(number((channel(r) * 65536 + channel(g) * 256 + channel(b)) as f64));
+                }
+                let [r, g, b] = channels.map(|c| match literal(&c) {
+                    Some(n) => number(channel(n) as f64),
+                    None => call("Math.floor", vec![c]),
+                });
+                Ok(binary("+", binary("+", binary("*", r, number(65536.0)), binary("*", g, number(256.0))), b))
+            }
+            _ => Err("color() expects 1 to 4 arguments".to_string()),
+        }
+    }
+}
+
+fn channel(value: f64) -> i64 {
+    value.round().clamp(0.0, 255.0) as i64
+}
+
+fn css_color(css: &str) -> Option<i64> {
+    if let Some(hex) = css.strip_prefix('#') {
+        return match hex.len() {
+            6 => i64::from_str_radix(hex, 16).ok(),
+            3 => i64::from_str_radix(&hex.chars().flat_map(|c| [c, c]).collect::<String>(), 16).ok(),
+            _ => None,
+        };
+    }
+    Some(match css.to_ascii_lowercase().as_str() {
+        "black" => 0x000000,
+        "white" => 0xFFFFFF,
+        "red" => 0xFF0000,
+        "green" => 0x008000,
+        "blue" => 0x0000FF,
+        "yellow" => 0xFFFF00,
+        "cyan" => 0x00FFFF,
+        "magenta" => 0xFF00FF,
+        "orange" => 0xFFA500,
+        "purple" => 0x800080,
+        "pink" => 0xFFC0CB,
+        "gray" | "grey" => 0x808080,
+        _ => return None,
+    })
+}
+
+fn is_event_handler(name: &str) -> bool {
+    name.starts_with("mouse") || name.starts_with("key") || name.starts_with("touch") || name == "windowResized"
+}
+
+fn original_as_comment(stmt: &Stmt, indent: usize) -> Vec<String> {
+    let pad = "    ".repeat(indent);
+    stmt.text.lines().map(|line| format!("{}// {}", pad, line.trim())).collect()
+}
@@ -0,0 +1,277 @@
+/// Structured output for headless runs: rendered frames, audio clips and run metrics as
+/// one JSON document, so playgrounds and docs don't have to scrape stdout
+///
+/// ```json
+/// { "format": "synthesis-artifacts", "version": 1, "status": "ok",
+///   "frames": [{ "frame": 0, "image": { "mime": "image/png", "encoding": "base64", "data": "..." } }],
+///   "audio": [...], "metrics": {...}, "variables": {...}, "error": null }
+/// ```
+///
+/// Frames are rasterized on the CPU from the recorded draw calls (clear, rect, circle, line);
+/// effects without a software path are listed in `draws` but don't appear in the image.
+use crate::runtime::{value_to_json, RecordedCall, SandboxReport, Value};
+use base64::Engine;
+
+pub const ARTIFACT_FORMAT: &str = "synthesis-artifacts";
+pub const ARTIFACT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct ArtifactOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Encode every nth frame as an image
+    pub capture_every: u64,
+    /// Stop encoding images after this many, to keep documents small
+    pub max_images: usize,
+}
+
+impl Default for ArtifactOptions {
+    fn default() -> Self {
+        Self { width: 320, height: 180, capture_every: 30, max_images: 8 }
+    }
+}
+
+/// RGBA software canvas for headless frames
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut canvas = Self { width, height, pixels: vec![0; (width * height * 4) as usize] };
+        canvas.clear(0x000000);
+        canvas
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    fn put(&mut self, x: i64, y: i64, color: i64) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let i = ((y as u32 * self.width + x as u32) * 4) as usize;
+        // A translucent colour says how transparent it is above 0xRRGGBB; blend it over what's there
+        let opacity = 1.0 - (color >> 24 & 0xFF) as f64 / 255.0;
+        for (channel, shift) in [16, 8, 0].into_iter().enumerate() {
+            let source = (color >> shift & 0xFF) as f64;
+            let target = &mut self.pixels[i + channel];
+            *target = (source * opacity + *target as f64 * (1.0 - opacity)).round() as u8;
+        }
+        self.pixels[i + 3] = 0xFF;
+    }
+
+    pub fn clear(&mut self, color: i64) {
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                self.put(x, y, color);
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: i64) {
+        for py in y.round() as i64..(y + height).round() as i64 {
+            for px in x.round() as i64..(x + width).round() as i64 {
+                self.put(px, py, color);
+            }
+        }
+    }
+
+    pub fn fill_circle(&mut self, cx: f64, cy: f64, radius: f64, color: i64) {
+        let r2 = radius * radius;
+        for py in (cy - radius).floor() as i64..=(cy + radius).ceil() as i64 {
+            for px in (cx - radius).floor() as i64..=(cx + radius).ceil() as i64 {
+                let (dx, dy) = (px as f64 + 0.5 - cx, py as f64 + 0.5 - cy);
+                if dx * dx + dy * dy <= r2 {
+                    self.put(px, py, color);
+                }
+            }
+        }
+    }
+
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, color: i64) {
+        let steps = (x2 - x1).abs().max((y2 - y1).abs()).ceil().max(1.0) as i64;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            self.put((x1 + (x2 - x1) * t).round() as i64, (y1 + (y2 - y1) * t).round() as i64, color);
+        }
+    }
+
+    /// Resolution-independent coordinates: floats between 0 and 1 (percentages) scale to the canvas
+    fn coord(&self, value: Option<&Value>, extent: u32) -> Option<f64> {
+        match value {
+            Some(Value::Float(f)) if (0.0..=1.0).contains(f) => Some(f * extent as f64),
+            Some(value) => value.as_number(),
+            None => None,
+        }
+    }
+
+    /// Replay one recorded Graphics call; returns false if it has no software path
+    pub fn draw(&mut self, call: &RecordedCall) -> bool {
+        let (w, h) = (self.width, self.height);
+        let arg = |i: usize| call.args.get(i);
+        // The colour comes after the shape's numbers, or as `color:`
+        let color = |i: usize, default: i64| call.named.get("color").or_else(|| arg(i)).and_then(|v| v.as_number()).map(|c| c as i64).unwrap_or(default);
+
+        match call.function.as_str() {
+            "clear" => {
+                self.clear(color(0, 0x000000));
+                true
+            }
+            "rect" => match (self.coord(arg(0), w), self.coord(arg(1), h), self.coord(arg(2), w), self.coord(arg(3), h)) {
+                (Some(x), Some(y), Some(width), Some(height)) => {
+                    self.fill_rect(x, y, width, height, color(4, 0xFFFFFF));
+                    true
+                }
+                _ => false,
+            },
+            "circle" => match (self.coord(arg(0), w), self.coord(arg(1), h), self.coord(arg(2), w.min(h))) {
+                (Some(x), Some(y), Some(radius)) => {
+                    self.fill_circle(x, y, radius, color(3, 0xFFFFFF));
+                    true
+                }
+                _ => false,
+            },
+            "line" => match (self.coord(arg(0), w), self.coord(arg(1), h), self.coord(arg(2), w), self.coord(arg(3), h)) {
+                (Some(x1), Some(y1), Some(x2), Some(y2)) => {
+                    self.line(x1, y1, x2, y2, color(4, 0xFFFFFF));
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn to_png(&self) -> std::io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(std::io::Error::other)?;
+        writer.write_image_data(&self.pixels).map_err(std::io::Error::other)?;
+        writer.finish().map_err(std::io::Error::other)?;
+        Ok(bytes)
+    }
+}
+
+/// 16-bit mono WAV, for clips a script rendered as sample arrays
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+    wav
+}
+
+fn base64_blob(mime: &str, bytes: &[u8]) -> serde_json::Value {
+    serde_json::json!({
+        "mime": mime,
+        "encoding": "base64",
+        "data": base64::engine::general_purpose::STANDARD.encode(bytes),
+    })
+}
+
+fn audio_clip(call: &RecordedCall) -> serde_json::Value {
+    let source = call.args.first().unwrap_or(&call.result);
+    match source {
+        Value::Stream(stream) => serde_json::json!({
+            "function": call.function,
+            "frame": call.frame,
+            "stream": stream.name,
+            "sample_rate": stream.sample_rate,
+            "clip": null,
+        }),
+        Value::Array(samples) => {
+            let samples: Vec<f32> = samples.iter().filter_map(|s| s.as_number()).map(|s| s as f32).collect();
+            let sample_rate = call.args.get(1).and_then(|v| v.as_number()).unwrap_or(44100.0) as u32;
+            serde_json::json!({
+                "function": call.function,
+                "frame": call.frame,
+                "sample_rate": sample_rate,
+                "duration": samples.len() as f64 / sample_rate.max(1) as f64,
+                "clip": base64_blob("audio/wav", &encode_wav(&samples, sample_rate)),
+            })
+        }
+        other => serde_json::json!({
+            "function": call.function,
+            "frame": call.frame,
+            "value": value_to_json(other),
+            "clip": null,
+        }),
+    }
+}
+
+/// Build the artifact document for a finished headless run
+pub fn artifact_document(report: &SandboxReport, options: &ArtifactOptions) -> serde_json::Value {
+    let mut canvas = Canvas::new(options.width, options.height);
+    let mut frames = Vec::new();
+
+    // The canvas persists between frames like a real framebuffer, so scripts that skip clear() accumulate
+    for (frame, calls) in report.frames() {
+        let mut skipped = Vec::new();
+        for call in &calls {
+            if !canvas.draw(call) {
+                skipped.push(call.function.clone());
+            }
+        }
+        let captured = frames.len() < options.max_images && frame.is_multiple_of(options.capture_every.max(1));
+        if !captured {
+            continue;
+        }
+        let image = match canvas.to_png() {
+            Ok(png) => base64_blob("image/png", &png),
+            Err(error) => serde_json::json!({ "error": error.to_string() }),
+        };
+        frames.push(serde_json::json!({
+            "frame": frame,
+            "width": options.width,
+            "height": options.height,
+            "image": image,
+            "draws": calls.len(),
+            "not_rendered": skipped,
+        }));
+    }
+
+    let error = report.error.as_ref().map(|error| serde_json::json!({
+        "kind": format!("{:?}", error.kind),
+        "message": error.message,
+        "suggestions": error.suggestions,
+    }));
+
+    serde_json::json!({
+        "format": ARTIFACT_FORMAT,
+        "version": ARTIFACT_VERSION,
+        "status": if report.succeeded() { "ok" } else { "error" },
+        "error": error,
+        "frames": frames,
+        "audio": report.audio().into_iter().map(audio_clip).collect::<Vec<_>>(),
+        "metrics": {
+            "elapsed_ms": report.elapsed.as_secs_f64() * 1000.0,
+            "frames_run": report.frames_run,
+            "instructions": report.instructions,
+            "peak_memory_bytes": report.peak_memory,
+            "dropped_calls": report.dropped_calls,
+        },
+        "variables": report.variables.iter()
+            .map(|(name, value)| (name.clone(), value_to_json(value)))
+            .collect::<serde_json::Map<_, _>>(),
+    })
+}
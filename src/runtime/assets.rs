@@ -0,0 +1,475 @@
+/// Central asset loading: images, audio files, shaders, fonts and models are read on a
+/// background thread and cached by path hash, so a big file never stalls the first frame
+/// or the audio callback. Scripts get a placeholder until the real data arrives.
+use crate::runtime::resources::{self, ResourceId, ResourceKind};
+use crate::runtime::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+/// How often the watcher checks modification times
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Shared manager for stateless module functions like Assets.load()
+static ASSETS: OnceLock<AssetManager> = OnceLock::new();
+
+pub fn asset_manager() -> &'static AssetManager {
+    ASSETS.get_or_init(AssetManager::new)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssetHandle(pub u64);
+
+impl AssetHandle {
+    pub fn for_path(path: &Path) -> Self {
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        AssetHandle(hasher.finish())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssetKind {
+    Image,
+    Audio,
+    Shader,
+    Font,
+    Model,
+    Data,
+}
+
+impl AssetKind {
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tga" | "hdr" | "exr" | "webp" => AssetKind::Image,
+            "wav" | "mp3" | "ogg" | "flac" | "aif" | "aiff" | "opus" => AssetKind::Audio,
+            "wgsl" | "glsl" | "frag" | "vert" | "fs" | "vs" | "isf" => AssetKind::Shader,
+            "ttf" | "otf" | "woff" | "woff2" => AssetKind::Font,
+            "obj" | "gltf" | "glb" | "stl" | "ply" | "fbx" => AssetKind::Model,
+            _ => AssetKind::Data,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AssetKind::Image => "image",
+            AssetKind::Audio => "audio",
+            AssetKind::Shader => "shader",
+            AssetKind::Font => "font",
+            AssetKind::Model => "model",
+            AssetKind::Data => "data",
+        }
+    }
+
+    /// What the loaded data occupies once it's uploaded or decoded
+    pub fn resource_kind(&self) -> ResourceKind {
+        match self {
+            AssetKind::Image => ResourceKind::Texture,
+            AssetKind::Audio => ResourceKind::AudioBuffer,
+            _ => ResourceKind::Buffer,
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, AssetKind::Shader | AssetKind::Data)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssetState {
+    Loading,
+    Ready,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    pub handle: AssetHandle,
+    pub path: PathBuf,
+    pub kind: AssetKind,
+    pub state: AssetState,
+    pub data: Arc<Vec<u8>>,
+    pub modified: Option<SystemTime>,
+    /// Bumped on every successful (re)load so scripts can notice hot reloads
+    pub version: u32,
+    /// Entry in the resource tracker once data is resident
+    pub resource: Option<ResourceId>,
+}
+
+impl Asset {
+    fn pending(handle: AssetHandle, path: PathBuf) -> Self {
+        Self {
+            handle,
+            kind: AssetKind::from_path(&path),
+            path,
+            state: AssetState::Loading,
+            data: Arc::new(Vec::new()),
+            modified: None,
+            version: 0,
+            resource: None,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == AssetState::Ready
+    }
+
+    /// Script view of the asset; stands in with a placeholder until loading finishes
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("type".to_string(), Value::String("asset".to_string()));
+        fields.insert("handle".to_string(), Value::String(format!("{:016x}", self.handle.0)));
+        fields.insert("path".to_string(), Value::String(self.path.display().to_string()));
+        fields.insert("kind".to_string(), Value::String(self.kind.name().to_string()));
+        fields.insert("version".to_string(), Value::Integer(self.version as i64));
+        fields.insert("bytes".to_string(), Value::Integer(self.data.len() as i64));
+        fields.insert("ready".to_string(), Value::Boolean(self.is_ready()));
+
+        let state = match &self.state {
+            AssetState::Loading => "loading",
+            AssetState::Ready => "ready",
+            AssetState::Failed(error) => {
+                fields.insert("error".to_string(), Value::String(error.clone()));
+                "failed"
+            }
+        };
+        fields.insert("state".to_string(), Value::String(state.to_string()));
+
+        if self.is_ready() && self.kind.is_text() {
+            fields.insert("source".to_string(), Value::String(String::from_utf8_lossy(&self.data).into_owned()));
+        } else if !self.is_ready() {
+            fields.insert("placeholder".to_string(), Value::Boolean(true));
+            match self.kind {
+                // A single magenta pixel is hard to miss if something never finishes loading
+                AssetKind::Image => {
+                    fields.insert("width".to_string(), Value::Integer(1));
+                    fields.insert("height".to_string(), Value::Integer(1));
+                    fields.insert("color".to_string(), Value::String("#ff00ff".to_string()));
+                }
+                AssetKind::Audio => {
+                    fields.insert("duration".to_string(), Value::Float(0.0));
+                }
+                AssetKind::Shader | AssetKind::Data => {
+                    fields.insert("source".to_string(), Value::String(String::new()));
+                }
+                AssetKind::Font | AssetKind::Model => {}
+            }
+        }
+
+        Value::Object(fields)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LoadProgress {
+    pub total: usize,
+    pub ready: usize,
+    pub failed: usize,
+    pub bytes: usize,
+}
+
+impl LoadProgress {
+    /// Fraction of assets finished (loaded or failed), 1.0 when nothing is pending
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.ready + self.failed) as f32 / self.total as f32
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("total".to_string(), Value::Integer(self.total as i64));
+        fields.insert("ready".to_string(), Value::Integer(self.ready as i64));
+        fields.insert("failed".to_string(), Value::Integer(self.failed as i64));
+        fields.insert("loading".to_string(), Value::Integer((self.total - self.ready - self.failed) as i64));
+        fields.insert("bytes".to_string(), Value::Integer(self.bytes as i64));
+        fields.insert("progress".to_string(), Value::Float(self.fraction() as f64));
+        Value::Object(fields)
+    }
+}
+
+type AssetTable = Arc<Mutex<HashMap<AssetHandle, Asset>>>;
+
+pub struct AssetManager {
+    assets: AssetTable,
+    loader: Mutex<Sender<AssetHandle>>,
+    changed: Arc<Mutex<Vec<AssetHandle>>>,
+    watching: Arc<Mutex<bool>>,
+}
+
+impl AssetManager {
+    pub fn new() -> Self {
+        let assets: AssetTable = Arc::new(Mutex::new(HashMap::new()));
+        let (sender, receiver) = channel::<AssetHandle>();
+
+        let table = assets.clone();
+        std::thread::Builder::new()
+            .name("synthesis-assets".to_string())
+            .spawn(move || {
+                while let Ok(handle) = receiver.recv() {
+                    Self::load_now(&table, handle);
+                }
+            })
+            .ok();
+
+        Self {
+            assets,
+            loader: Mutex::new(sender),
+            changed: Arc::new(Mutex::new(Vec::new())),
+            watching: Arc::new(Mutex::new(false)),
+        }
+    }
+
+    /// Queue a file for loading and return immediately; repeated loads hit the cache
+    pub fn load(&self, path: impl AsRef<Path>) -> AssetHandle {
+        let path = path.as_ref().to_path_buf();
+        let handle = AssetHandle::for_path(&path);
+
+        let queued = match self.assets.lock() {
+            Ok(mut assets) => match assets.entry(handle) {
+                Entry::Occupied(_) => false,
+                Entry::Vacant(entry) => {
+                    entry.insert(Asset::pending(handle, path));
+                    true
+                }
+            },
+            Err(_) => false,
+        };
+
+        if queued {
+            self.queue(handle);
+        }
+        handle
+    }
+
+    /// Load on the calling thread; for tools and tests that need the data right away
+    pub fn load_blocking(&self, path: impl AsRef<Path>) -> Option<Asset> {
+        let path = path.as_ref().to_path_buf();
+        let handle = AssetHandle::for_path(&path);
+        if let Ok(mut assets) = self.assets.lock() {
+            assets.entry(handle).or_insert_with(|| Asset::pending(handle, path));
+        }
+        Self::load_now(&self.assets, handle);
+        self.get(handle)
+    }
+
+    pub fn get(&self, handle: AssetHandle) -> Option<Asset> {
+        let asset = self.assets.lock().ok()?.get(&handle).cloned()?;
+        if let Some(resource) = asset.resource {
+            resources::touch_resource(resource);
+        }
+        Some(asset)
+    }
+
+    pub fn reload(&self, handle: AssetHandle) {
+        if Self::mark_loading(&self.assets, handle) {
+            self.queue(handle);
+        }
+    }
+
+    pub fn unload(&self, handle: AssetHandle) -> bool {
+        let removed = self.assets.lock().ok().and_then(|mut assets| assets.remove(&handle));
+        match removed {
+            Some(asset) => {
+                if let Some(resource) = asset.resource {
+                    resources::release_resource(resource);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop assets that haven't been used for `idle`; they reload on next use
+    pub fn unload_unused(&self, idle: Duration) -> Vec<PathBuf> {
+        let unused: Vec<(AssetHandle, PathBuf)> = match self.assets.lock() {
+            Ok(assets) => assets
+                .values()
+                .filter(|asset| {
+                    asset.resource
+                        .and_then(resources::resource_idle_time)
+                        .map(|idle_time| idle_time >= idle)
+                        .unwrap_or(false)
+                })
+                .map(|asset| (asset.handle, asset.path.clone()))
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+
+        unused
+            .into_iter()
+            .filter(|(handle, _)| self.unload(*handle))
+            .map(|(_, path)| path)
+            .collect()
+    }
+
+    pub fn progress(&self) -> LoadProgress {
+        let assets = match self.assets.lock() {
+            Ok(assets) => assets,
+            Err(_) => return LoadProgress::default(),
+        };
+
+        let mut progress = LoadProgress { total: assets.len(), ..Default::default() };
+        for asset in assets.values() {
+            match asset.state {
+                AssetState::Ready => progress.ready += 1,
+                AssetState::Failed(_) => progress.failed += 1,
+                AssetState::Loading => {}
+            }
+            progress.bytes += asset.data.len();
+        }
+        progress
+    }
+
+    /// Assets reloaded by the watcher since the last call
+    pub fn take_changed(&self) -> Vec<AssetHandle> {
+        self.changed.lock().map(|mut changed| std::mem::take(&mut *changed)).unwrap_or_default()
+    }
+
+    /// Check every loaded file once and queue the ones modified on disk
+    pub fn check_for_changes(&self) -> Vec<AssetHandle> {
+        let stale = Self::stale_assets(&self.assets);
+        for handle in &stale {
+            self.reload(*handle);
+        }
+        stale
+    }
+
+    /// Start polling loaded files for changes in the background
+    pub fn watch(&self) {
+        {
+            let mut watching = match self.watching.lock() {
+                Ok(watching) => watching,
+                Err(_) => return,
+            };
+            if *watching {
+                return;
+            }
+            *watching = true;
+        }
+
+        let assets = self.assets.clone();
+        let changed = self.changed.clone();
+        let watching = self.watching.clone();
+        let loader = match self.loader.lock() {
+            Ok(loader) => loader.clone(),
+            Err(_) => return,
+        };
+
+        std::thread::Builder::new()
+            .name("synthesis-asset-watch".to_string())
+            .spawn(move || {
+                while watching.lock().map(|w| *w).unwrap_or(false) {
+                    for handle in Self::stale_assets(&assets) {
+                        if !Self::mark_loading(&assets, handle) {
+                            continue;
+                        }
+                        println!("🔄 Reloading changed asset {:016x}", handle.0);
+                        if let Ok(mut changed) = changed.lock() {
+                            changed.push(handle);
+                        }
+                        let _ = loader.send(handle);
+                    }
+                    std::thread::sleep(WATCH_INTERVAL);
+                }
+            })
+            .ok();
+    }
+
+    pub fn stop_watching(&self) {
+        if let Ok(mut watching) = self.watching.lock() {
+            *watching = false;
+        }
+    }
+
+    fn queue(&self, handle: AssetHandle) {
+        if let Ok(loader) = self.loader.lock() {
+            let _ = loader.send(handle);
+        }
+    }
+
+    fn mark_loading(assets: &AssetTable, handle: AssetHandle) -> bool {
+        match assets.lock() {
+            Ok(mut assets) => match assets.get_mut(&handle) {
+                Some(asset) => {
+                    asset.state = AssetState::Loading;
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    fn stale_assets(assets: &AssetTable) -> Vec<AssetHandle> {
+        let snapshot: Vec<(AssetHandle, PathBuf, Option<SystemTime>)> = match assets.lock() {
+            Ok(assets) => assets
+                .values()
+                .filter(|asset| asset.state != AssetState::Loading)
+                .map(|asset| (asset.handle, asset.path.clone(), asset.modified))
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+
+        // Stat files without holding the lock, so loads aren't held up by slow disks
+        snapshot
+            .into_iter()
+            .filter(|(_, path, modified)| {
+                let on_disk = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                on_disk.is_some() && on_disk != *modified
+            })
+            .map(|(handle, _, _)| handle)
+            .collect()
+    }
+
+    fn load_now(assets: &AssetTable, handle: AssetHandle) {
+        let path = match assets.lock().ok().and_then(|assets| assets.get(&handle).map(|a| a.path.clone())) {
+            Some(path) => path,
+            None => return, // unloaded while queued
+        };
+
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let result = std::fs::read(&path);
+
+        if let Ok(mut assets) = assets.lock() {
+            if let Some(asset) = assets.get_mut(&handle) {
+                asset.modified = modified;
+                match result {
+                    Ok(data) => {
+                        match asset.resource {
+                            Some(resource) => resources::resize_resource(resource, data.len()),
+                            None => {
+                                let label = asset.path.display().to_string();
+                                asset.resource = Some(resources::track_resource(asset.kind.resource_kind(), label, data.len()));
+                            }
+                        }
+                        asset.data = Arc::new(data);
+                        asset.state = AssetState::Ready;
+                        asset.version += 1;
+                    }
+                    // Keep serving the previous version if a hot reload fails mid-save
+                    Err(error) => asset.state = AssetState::Failed(error.to_string()),
+                }
+            }
+        }
+    }
+}
+
+impl Default for AssetManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,135 @@
+/// Where the sun and moon are, for installations that follow the day outside
+///
+/// The sun uses NOAA's low-precision formulas, good to a minute or so for sunrise and sunset
+/// between the polar circles; the moon's phase counts synodic months from a known new moon.
+/// Times are Unix seconds, angles degrees, latitude north and longitude east positive.
+use std::f64::consts::PI;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+/// Julian day of the Unix epoch
+const UNIX_EPOCH_JULIAN: f64 = 2_440_587.5;
+/// Julian day of J2000.0, which the orbital elements are measured from
+const J2000: f64 = 2_451_545.0;
+/// Where the sun's centre is at sunrise, allowing for refraction and its radius
+const SUNRISE_ELEVATION: f64 = -0.833;
+/// Days from new moon to new moon
+pub const SYNODIC_MONTH: f64 = 29.530_588_853;
+/// A new moon (2000-01-06 18:14 UTC) to count phases from
+const KNOWN_NEW_MOON: f64 = 2_451_550.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunPosition {
+    /// Above the horizon, negative below it
+    pub elevation: f64,
+    /// Clockwise from north
+    pub azimuth: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunTimes {
+    /// None during polar night or midnight sun
+    pub sunrise: Option<f64>,
+    pub noon: f64,
+    pub sunset: Option<f64>,
+    /// Hours the sun is up: 0 in polar night, 24 under the midnight sun
+    pub day_length: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoonPhase {
+    /// 0 is new, 0.5 full, and back towards 1 as it wanes
+    pub phase: f64,
+    /// How much of the disc is lit, 0 to 1
+    pub illumination: f64,
+    /// Days since the last new moon
+    pub age: f64,
+}
+
+impl MoonPhase {
+    pub fn name(&self) -> &'static str {
+        const NAMES: [&str; 8] = [
+            "new moon", "waxing crescent", "first quarter", "waxing gibbous",
+            "full moon", "waning gibbous", "last quarter", "waning crescent",
+        ];
+        NAMES[((self.phase * 8.0 + 0.5) as usize) % 8]
+    }
+}
+
+fn julian_day(unix: f64) -> f64 {
+    unix / SECONDS_PER_DAY + UNIX_EPOCH_JULIAN
+}
+
+/// The sun's declination and the equation of time in minutes
+fn solar_coordinates(unix: f64) -> (f64, f64) {
+    let n = julian_day(unix) - J2000;
+    let mean_anomaly = (357.529 + 0.985_600_28 * n).to_radians();
+    let mean_longitude = 280.459 + 0.985_647_36 * n;
+    let longitude = (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin()).to_radians();
+    let obliquity = (23.439 - 0.000_000_36 * n).to_radians();
+
+    let right_ascension = (obliquity.cos() * longitude.sin()).atan2(longitude.cos()).to_degrees();
+    let declination = (obliquity.sin() * longitude.sin()).asin();
+    let equation_of_time = 4.0 * ((mean_longitude - right_ascension + 180.0).rem_euclid(360.0) - 180.0);
+    (declination, equation_of_time)
+}
+
+pub fn sun_position(unix: f64, latitude: f64, longitude: f64) -> SunPosition {
+    let (declination, equation_of_time) = solar_coordinates(unix);
+    let minutes = unix.rem_euclid(SECONDS_PER_DAY) / 60.0;
+    let solar_minutes = minutes + equation_of_time + 4.0 * longitude;
+    let hour_angle = (solar_minutes / 4.0 - 180.0).to_radians();
+    let latitude = latitude.to_radians();
+
+    let elevation = (latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos())
+        .clamp(-1.0, 1.0)
+        .asin();
+    let azimuth = hour_angle.sin().atan2(hour_angle.cos() * latitude.sin() - declination.tan() * latitude.cos());
+    SunPosition {
+        elevation: elevation.to_degrees(),
+        azimuth: (azimuth.to_degrees() + 180.0).rem_euclid(360.0),
+    }
+}
+
+/// Sunrise, solar noon and sunset on the day `unix` falls in at `longitude`
+pub fn sun_times(unix: f64, latitude: f64, longitude: f64) -> SunTimes {
+    // The solar day, so a place far from Greenwich gets its own date's noon
+    let day = ((unix + longitude / 15.0 * 3600.0) / SECONDS_PER_DAY).floor() * SECONDS_PER_DAY;
+    let mut noon = day + (720.0 - 4.0 * longitude) * 60.0;
+    let (declination, equation_of_time) = solar_coordinates(noon);
+    noon -= equation_of_time * 60.0;
+
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = (SUNRISE_ELEVATION.to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if cos_hour_angle >= 1.0 {
+        return SunTimes { sunrise: None, noon, sunset: None, day_length: 0.0 };
+    }
+    if cos_hour_angle <= -1.0 {
+        return SunTimes { sunrise: None, noon, sunset: None, day_length: 24.0 };
+    }
+    // Four minutes per degree of hour angle
+    let half_day = cos_hour_angle.acos().to_degrees() * 4.0 * 60.0;
+    SunTimes {
+        sunrise: Some(noon - half_day),
+        noon,
+        sunset: Some(noon + half_day),
+        day_length: 2.0 * half_day / 3600.0,
+    }
+}
+
+pub fn moon_phase(unix: f64) -> MoonPhase {
+    let age = (julian_day(unix) - KNOWN_NEW_MOON).rem_euclid(SYNODIC_MONTH);
+    let phase = age / SYNODIC_MONTH;
+    MoonPhase {
+        phase,
+        illumination: (1.0 - (2.0 * PI * phase).cos()) / 2.0,
+        age,
+    }
+}
+
+/// How light it is outside from the sun's elevation: 0 once civil twilight ends, 1 once the sun
+/// is a few degrees up, easing in between
+pub fn daylight(elevation: f64) -> f64 {
+    let t = ((elevation + 6.0) / 12.0).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,232 @@
+/// Capabilities a script declares up front and the runtime enforces
+///
+/// ```text
+/// capabilities { network, camera, filesystem("assets/") }
+/// ```
+///
+/// Scripts without a declaration keep working as before. Once a script declares its
+/// capabilities, or runs under `--sandbox`, anything it didn't ask for is refused.
+use crate::errors::{synthesis_error, ErrorKind, SynthesisError};
+use crate::parser::ast::CapabilityRequest;
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Capability {
+    Network,
+    Camera,
+    Microphone,
+    Midi,
+    Location,
+    /// Read files under this directory; an empty scope means anywhere
+    Filesystem(PathBuf),
+}
+
+impl Capability {
+    pub fn parse(name: &str, scope: Option<&str>) -> crate::Result<Self> {
+        match (name, scope) {
+            ("network", None) => Ok(Capability::Network),
+            ("camera", None) => Ok(Capability::Camera),
+            ("microphone", None) => Ok(Capability::Microphone),
+            ("midi", None) => Ok(Capability::Midi),
+            ("location", None) => Ok(Capability::Location),
+            ("filesystem", None) => Ok(Capability::Filesystem(PathBuf::new())),
+            ("filesystem", Some(scope)) => match normalize(Path::new(scope)) {
+                Some(scope) => Ok(Capability::Filesystem(scope)),
+                None => Err(synthesis_error(
+                    ErrorKind::PermissionDenied,
+                    format!("🔒 filesystem(\"{}\") points outside the project folder", scope)
+                )
+                .with_suggestion("Use a folder next to your script, like filesystem(\"assets/\")")
+                .with_docs("https://synthesis-lang.org/docs/capabilities")),
+            },
+            ("network" | "camera" | "microphone" | "midi" | "location", Some(_)) => Err(synthesis_error(
+                ErrorKind::SyntaxError,
+                format!("🔒 The {} capability doesn't take a folder", name)
+            )
+            .with_suggestion(format!("Write it on its own: capabilities {{ {} }}", name))
+            .with_docs("https://synthesis-lang.org/docs/capabilities")),
+            _ => Err(synthesis_error(
+                ErrorKind::SyntaxError,
+                format!("🔒 Unknown capability '{}'", name)
+            )
+            .with_suggestion("Available: network, camera, microphone, midi, location, filesystem(\"folder/\")")
+            .with_docs("https://synthesis-lang.org/docs/capabilities")),
+        }
+    }
+
+    /// Parse a command line list like `camera,microphone,filesystem:assets/`
+    pub fn parse_list(list: &str) -> crate::Result<Vec<Self>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once(':') {
+                Some((name, scope)) => Self::parse(name, Some(scope)),
+                None => Self::parse(entry, None),
+            })
+            .collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Network => "network",
+            Capability::Camera => "camera",
+            Capability::Microphone => "microphone",
+            Capability::Midi => "midi",
+            Capability::Location => "location",
+            Capability::Filesystem(_) => "filesystem",
+        }
+    }
+
+    /// Whether holding `self` is enough for `required`
+    pub fn covers(&self, required: &Capability) -> bool {
+        match (self, required) {
+            (Capability::Filesystem(scope), Capability::Filesystem(path)) => path.starts_with(scope),
+            _ => self == required,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Capability::Filesystem(scope) if scope.as_os_str().is_empty() => "filesystem".to_string(),
+            Capability::Filesystem(scope) => format!("filesystem(\"{}\")", scope.display()),
+            other => other.name().to_string(),
+        }
+    }
+}
+
+/// Resolve `.` and `..` without touching the disk; `None` if the path climbs above its start
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+/// What reading or writing `path` needs
+pub fn file_capability(path: &Path) -> Capability {
+    // A path that escapes the project can never be covered by a scope
+    Capability::Filesystem(normalize(path).unwrap_or_else(|| PathBuf::from("..")))
+}
+
+/// Which capability a module call needs, if any
+pub fn required_capability(module: &str, function: &str, args: &[Value], named: &HashMap<String, Value>) -> Option<Capability> {
+    match (module, function) {
+        ("Web", _) | ("Environment", "weather") | ("Stream", "receive" | "send_to") => Some(Capability::Network),
+        ("MIDI", _) => Some(Capability::Midi),
+        ("GPS", "open") => Some(Capability::Location),
+        // Loopback hears calls and anything else the machine plays, so it asks like a mic does
+        ("Audio", "mic_input" | "loopback") => Some(Capability::Microphone),
+        // A screen grab can reveal as much as a camera
+        ("Graphics", "camera_layer" | "capture_screen" | "capture_window") | ("Depth", "open") => Some(Capability::Camera),
+        ("Audio", "load_file") | ("Assets", "load") | ("Assets", "get") | ("Assets", "reload") | ("Graphics", "isf")
+        | ("Text", "load_lyrics" | "lyric" | "karaoke") | ("Stream", "tap") => {
+            let path = match args.first() {
+                Some(Value::String(path)) => path.clone(),
+                Some(Value::Object(fields)) => match fields.get("path") {
+                    Some(Value::String(path)) => path.clone(),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            Some(file_capability(Path::new(&path)))
+        }
+        ("Audio", "record_session") => {
+            let path = match named.get("path") {
+                Some(Value::String(path)) => path.clone(),
+                _ => crate::audio::recorder::DEFAULT_RECORDING_DIR.to_string(),
+            };
+            Some(file_capability(Path::new(&path)))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityPolicy {
+    /// What the script asked for in its `capabilities { }` block
+    declared: Option<Vec<Capability>>,
+    /// What the host allows (`--sandbox --allow ...`); `None` means no sandbox
+    sandbox: Option<Vec<Capability>>,
+}
+
+impl CapabilityPolicy {
+    /// Refuse everything the script doesn't declare, and any declaration beyond `allowed`
+    pub fn sandboxed(allowed: Vec<Capability>) -> Self {
+        Self { declared: None, sandbox: Some(allowed) }
+    }
+
+    pub fn is_enforced(&self) -> bool {
+        self.declared.is_some() || self.sandbox.is_some()
+    }
+
+    pub fn declared(&self) -> &[Capability] {
+        self.declared.as_deref().unwrap_or(&[])
+    }
+
+    /// Handle a script's `capabilities { }` block
+    pub fn declare(&mut self, requests: &[CapabilityRequest]) -> crate::Result<()> {
+        let mut declared = self.declared.clone().unwrap_or_default();
+        for request in requests {
+            let capability = Capability::parse(&request.name, request.scope.as_deref())?;
+            if let Some(allowed) = &self.sandbox {
+                if !allowed.iter().any(|a| a.covers(&capability)) {
+                    return Err(synthesis_error(
+                        ErrorKind::PermissionDenied,
+                        format!("🔒 This sketch asks for {}, which this sandbox doesn't allow", capability.describe())
+                    )
+                    .with_suggestion("Run it locally, or start the sandbox with --allow to grant it")
+                    .with_docs("https://synthesis-lang.org/docs/capabilities"));
+                }
+            }
+            if !declared.contains(&capability) {
+                declared.push(capability);
+            }
+        }
+        self.declared = Some(declared);
+        Ok(())
+    }
+
+    pub fn check(&self, required: &Capability) -> crate::Result<()> {
+        if !self.is_enforced() || self.declared().iter().any(|c| c.covers(required)) {
+            return Ok(());
+        }
+        Err(self.denied(required))
+    }
+
+    /// Check a module call before it runs
+    pub fn check_call(&self, module: &str, function: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<()> {
+        match required_capability(module, function, args, named) {
+            Some(required) => self.check(&required).map_err(|error| {
+                error.with_suggestion(format!("{}.{}() needs this capability", module, function))
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn denied(&self, required: &Capability) -> SynthesisError {
+        let (message, suggestion) = match required {
+            Capability::Filesystem(path) => (
+                format!("🔒 Accessing '{}' isn't covered by this script's capabilities", path.display()),
+                "Declare the folder it lives in: capabilities { filesystem(\"assets/\") }".to_string(),
+            ),
+            other => (
+                format!("🔒 This script didn't declare the {} capability", other.name()),
+                format!("Add it to the top of your script: capabilities {{ {} }}", other.name()),
+            ),
+        };
+        synthesis_error(ErrorKind::PermissionDenied, message)
+            .with_suggestion(suggestion)
+            .with_docs("https://synthesis-lang.org/docs/capabilities")
+    }
+}
@@ -0,0 +1,138 @@
+/// Complex numbers and polynomials, for prototyping filters and drawing fractals
+///
+/// A complex number is a `Complex { re, im }` struct in scripts and arithmetic works on it like on
+/// any number, so a Mandelbrot step is just `z = z * z + c`. Polynomials are lists of coefficients,
+/// highest power first like `Math.poly(1, 0, -1)` for x² - 1. Transfer functions take their
+/// numerator and denominator the way filter designs print them: `b0 + b1·z⁻¹ + b2·z⁻² ...`.
+use crate::parser::ast::{BinaryOperator, Expression, Field, Literal, StructDef, TypeAnnotation};
+use crate::runtime::Value;
+use num_complex::Complex64;
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::sync::Arc;
+
+/// Durand-Kerner gives up after this many rounds; well-behaved polynomials settle in a few dozen
+const MAX_ROOT_ITERATIONS: usize = 500;
+const TOLERANCE: f64 = 1e-12;
+
+pub fn from_value(value: &Value) -> Option<Complex64> {
+    match value {
+        Value::Struct { name, fields } if name == "Complex" && fields.len() == 2 => Some(Complex64::new(
+            fields.get("re")?.as_number()?,
+            fields.get("im")?.as_number()?,
+        )),
+        _ => None,
+    }
+}
+
+/// A complex number, or a plain number as one on the real line
+pub fn from_number(value: &Value) -> Option<Complex64> {
+    from_value(value).or_else(|| value.as_number().map(|re| Complex64::new(re, 0.0)))
+}
+
+pub fn to_value(z: Complex64) -> Value {
+    let fields = HashMap::from([
+        ("re".to_string(), Value::Float(z.re)),
+        ("im".to_string(), Value::Float(z.im)),
+    ]);
+    Value::Struct { name: "Complex".to_string(), fields }
+}
+
+/// `Complex { re: 1 }` with zero defaults, when the script hasn't defined its own
+pub fn builtin_struct(name: &str) -> Option<Arc<StructDef>> {
+    if name != "Complex" {
+        return None;
+    }
+    let fields = ["re", "im"].iter().map(|field| Field {
+        name: field.to_string(),
+        type_annotation: TypeAnnotation::Simple("Float".to_string()),
+        default_value: Some(Expression::Literal(Literal::Float(0.0))),
+    }).collect();
+    Some(Arc::new(StructDef { name: name.to_string(), fields, methods: Vec::new() }))
+}
+
+/// Arithmetic and equality with a complex number on either side and a complex or plain number
+/// on the other. None when neither side is complex
+pub fn binary_op(left: &Value, op: &BinaryOperator, right: &Value) -> Option<crate::Result<Value>> {
+    if from_value(left).is_none() && from_value(right).is_none() {
+        return None;
+    }
+    let (Some(a), Some(b)) = (from_number(left), from_number(right)) else {
+        return Some(Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🌀 Complex numbers work with numbers, not {}", if from_value(left).is_some() { right.type_name() } else { left.type_name() })
+        )
+        .with_suggestion("Try: z * z + c, or z * 2")));
+    };
+    let result = match op {
+        BinaryOperator::Add => to_value(a + b),
+        BinaryOperator::Subtract => to_value(a - b),
+        BinaryOperator::Multiply => to_value(a * b),
+        BinaryOperator::Divide if b.norm_sqr() == 0.0 => return Some(Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::InvalidExpression,
+            "🌀 Can't divide a complex number by zero"
+        )
+        .with_suggestion("Check Math.abs(z) > 0 before dividing"))),
+        BinaryOperator::Divide => to_value(a / b),
+        BinaryOperator::Equal => Value::Boolean((a - b).norm() < TOLERANCE),
+        BinaryOperator::NotEqual => Value::Boolean((a - b).norm() >= TOLERANCE),
+        _ => return Some(Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            "🌀 Complex numbers have no order, so they can't be compared with < or >"
+        )
+        .with_suggestion("Compare their sizes instead: Math.abs(z) > 2"))),
+    };
+    Some(Ok(result))
+}
+
+/// The polynomial with `coefficients` (highest power first) at `x`, by Horner's method
+pub fn polyval(coefficients: &[Complex64], x: Complex64) -> Complex64 {
+    coefficients.iter().fold(Complex64::new(0.0, 0.0), |sum, c| sum * x + c)
+}
+
+/// Every root of the polynomial, found all at once by Durand-Kerner iteration
+pub fn roots(coefficients: &[Complex64]) -> Vec<Complex64> {
+    // Leading zeros don't change the polynomial, only its apparent degree
+    let start = coefficients.iter().position(|c| c.norm() > 0.0).unwrap_or(coefficients.len());
+    let coefficients = &coefficients[start..];
+    let Some((leading, _)) = coefficients.split_first() else {
+        return Vec::new();
+    };
+    let monic: Vec<Complex64> = coefficients.iter().map(|c| c / leading).collect();
+    let degree = monic.len() - 1;
+    // Starting points spread around a circle, off the real axis so symmetric roots separate
+    let seed = Complex64::new(0.4, 0.9);
+    let mut estimates: Vec<Complex64> = (0..degree).map(|k| seed.powu(k as u32)).collect();
+    for _ in 0..MAX_ROOT_ITERATIONS {
+        let mut largest_step: f64 = 0.0;
+        for i in 0..degree {
+            let others = (0..degree)
+                .filter(|&j| j != i)
+                .fold(Complex64::new(1.0, 0.0), |product, j| product * (estimates[i] - estimates[j]));
+            if others.norm() == 0.0 {
+                estimates[i] += Complex64::new(TOLERANCE.sqrt(), 0.0);
+                continue;
+            }
+            let step = polyval(&monic, estimates[i]) / others;
+            estimates[i] -= step;
+            largest_step = largest_step.max(step.norm());
+        }
+        if largest_step < TOLERANCE {
+            break;
+        }
+    }
+    estimates
+}
+
+/// The `n` complex numbers whose nth power is 1, evenly spaced around the unit circle from 1
+pub fn roots_of_unity(n: usize) -> Vec<Complex64> {
+    (0..n).map(|k| Complex64::from_polar(1.0, TAU * k as f64 / n as f64)).collect()
+}
+
+/// H(z) = B(z) / A(z) at `frequency` Hz, with both in ascending powers of z⁻¹; the magnitude is
+/// the filter's gain there and the argument its phase shift
+pub fn transfer(numerator: &[Complex64], denominator: &[Complex64], frequency: f64, sample_rate: f64) -> Complex64 {
+    let z_inverse = Complex64::from_polar(1.0, -TAU * frequency / sample_rate);
+    let evaluate = |coefficients: &[Complex64]| coefficients.iter().rev().fold(Complex64::new(0.0, 0.0), |sum, c| sum * z_inverse + c);
+    evaluate(numerator) / evaluate(denominator)
+}
@@ -0,0 +1,78 @@
+/// Suspendable `every`, `after` and `async` blocks
+///
+/// Each block runs as a coroutine: it goes until it reaches `wait(seconds)` or its end, and the
+/// interpreter picks it up again once that much frame time has passed. Any number of blocks can
+/// be part way through at once, all moved along inside the same `loop {}` update and none of them
+/// sleeping, so nothing a script waits for holds up a frame or the audio thread. A coroutine keeps
+/// its own copy of the statements it's in the middle of, so it stays valid across hot reloads.
+use crate::parser::ast::{Expression, Statement};
+use crate::runtime::Value;
+use std::sync::Arc;
+
+/// Where a coroutine is up to in one of the blocks it's inside, innermost last
+#[derive(Debug, Clone)]
+pub enum Frame {
+    /// Running `body` from statement `next`
+    Block { body: Arc<Vec<Statement>>, next: usize },
+    /// A `while` whose body is the block above it; the condition is checked again when that ends
+    While { condition: Expression, body: Arc<Vec<Statement>> },
+    /// A `for` with the items it hasn't reached yet
+    For { variable: String, items: std::vec::IntoIter<Value>, body: Arc<Vec<Statement>> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Coroutine {
+    pub body: Arc<Vec<Statement>>,
+    /// Empty between runs of an `every`, and once any other block is done
+    pub frames: Vec<Frame>,
+    /// Seconds until it carries on; below zero when it's overdue
+    pub wait: f64,
+    /// `every`: how long from one start of the body to the next, evaluated at each start
+    pub period: Option<Expression>,
+    /// Seconds since the body last started, so a body that waits doesn't push the next start back
+    pub elapsed: f64,
+}
+
+impl Coroutine {
+    pub fn new(body: Vec<Statement>, wait: f64, period: Option<Expression>) -> Self {
+        Self {
+            body: Arc::new(body),
+            frames: Vec::new(),
+            wait,
+            period,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Start the body from the top, counting any time it's overdue as already elapsed
+    pub fn restart(&mut self) {
+        self.frames = vec![Frame::Block { body: self.body.clone(), next: 0 }];
+        self.elapsed = (-self.wait).max(0.0);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_empty() && self.period.is_none()
+    }
+}
+
+/// The arguments of a `wait(...)` on its own line
+pub fn wait_call(statement: &Statement) -> Option<&[Expression]> {
+    match statement {
+        Statement::Expression(Expression::FunctionCall { module: None, name, args, .. }) if name == "wait" => Some(args),
+        _ => None,
+    }
+}
+
+/// Whether running `statement` can reach a `wait()` it has to pause at; everything else runs in one go
+pub fn contains_wait(statement: &Statement) -> bool {
+    if wait_call(statement).is_some() {
+        return true;
+    }
+    match statement {
+        Statement::If { then_branch, else_branch, .. } => {
+            then_branch.iter().chain(else_branch.iter().flatten()).any(contains_wait)
+        }
+        Statement::While { body, .. } | Statement::For { body, .. } => body.iter().any(contains_wait),
+        _ => false,
+    }
+}
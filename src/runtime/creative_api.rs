@@ -1,9 +1,8 @@
 use crate::runtime::stream_composition::{StreamCompositionEngine, StreamConnection, ConnectionType, StreamTransform};
 use crate::runtime::streams::StreamManager;
-use crate::runtime::types::{Value, DataType};
+use crate::runtime::types::Value;
 use crate::errors::ErrorKind;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 
 /// Creative-friendly API layer for stream composition
 /// This hides technical complexity behind musical and artistic terminology
@@ -118,7 +117,7 @@ impl CreativeComposer {
     
     fn harmonize_internal(&mut self, streams: Vec<String>, output: String) -> crate::Result<()> {
         if streams.len() < 2 {
-            return Err(crate::SynthesisError::new(ErrorKind::InvalidArgument,
+            return Err(crate::SynthesisError::new(ErrorKind::InvalidExpression,
                 "Need at least 2 streams to create harmony".to_string()));
         }
         
@@ -153,7 +152,7 @@ impl CreativeComposer {
     }
     
     fn layer_internal(&mut self, base: String, layers: Vec<(String, String)>, output: String) -> crate::Result<()> {
-        let mut all_streams = vec![base];
+        let mut all_streams = vec![base.clone()];
         
         // Create parallel processing for each layer
         for (layer_name, effect_type) in layers {
@@ -201,7 +200,7 @@ impl CreativeComposer {
             };
             
             // Add layer connection with transform
-            let connection = StreamConnection {
+            let _connection = StreamConnection {
                 source: base.clone(),
                 destination: layer_name.clone(),
                 connection_type: ConnectionType::Parallel,
@@ -240,12 +239,12 @@ impl CreativeComposer {
     
     fn spread_internal(&mut self, input: String, left: String, right: String, width: f32, movement_speed: f32) -> crate::Result<()> {
         // Create left channel with slight delay and filtering
-        let left_transform = StreamTransform {
+        let _left_transform = StreamTransform {
             transform_id: "stereo_left".to_string(),
             parameters: {
                 let mut params = HashMap::new();
-                params.insert("delay".to_string(), Value::Float(movement_speed * 0.001)); // Convert to seconds
-                params.insert("gain".to_string(), Value::Float(0.7 * width));
+                params.insert("delay".to_string(), Value::Float(movement_speed as f64 * 0.001)); // Convert to seconds
+                params.insert("gain".to_string(), Value::Float(0.7 * width as f64));
                 params.insert("highcut".to_string(), Value::Float(8000.0));
                 params
             },
@@ -253,12 +252,12 @@ impl CreativeComposer {
         };
         
         // Create right channel with different characteristics
-        let right_transform = StreamTransform {
+        let _right_transform = StreamTransform {
             transform_id: "stereo_right".to_string(),
             parameters: {
                 let mut params = HashMap::new();
-                params.insert("delay".to_string(), Value::Float(movement_speed * 0.002));
-                params.insert("gain".to_string(), Value::Float(0.7 * width));
+                params.insert("delay".to_string(), Value::Float(movement_speed as f64 * 0.002));
+                params.insert("gain".to_string(), Value::Float(0.7 * width as f64));
                 params.insert("lowcut".to_string(), Value::Float(100.0));
                 params
             },
@@ -302,7 +301,7 @@ impl CreativeComposer {
             
             // This would integrate with a tempo/timing system
             // For now, just create a placeholder connection
-            let connection = StreamConnection {
+            let _connection = StreamConnection {
                 source: stream.clone(),
                 destination: format!("{}_synced", stream),
                 connection_type: ConnectionType::Direct,
@@ -353,7 +352,7 @@ impl CreativeComposer {
             bypass: false,
         };
         
-        let connection = StreamConnection {
+        let _connection = StreamConnection {
             source: audio,
             destination: visual,
             connection_type: ConnectionType::Direct,
@@ -367,7 +366,7 @@ impl CreativeComposer {
     /// Set the musical context for all operations
     pub fn set_musical_context(&mut self, context: MusicalContext) {
         self.current_tempo = context.tempo_bpm;
-        self.current_key = context.key;
+        self.current_key = context.key.clone();
         self.current_time_signature = context.time_signature;
         
         self.update_health(HealthStatus::Perfect, 
@@ -418,7 +417,7 @@ impl CreativeComposer {
     /// Translate technical Rust errors into creative-friendly messages
     fn translate_technical_error(&self, error: &crate::SynthesisError) -> String {
         match error.kind {
-            ErrorKind::InvalidArgument => {
+            ErrorKind::InvalidExpression => {
                 "🎭 Creative challenge! The combination you're trying isn't quite working. Try adjusting the parameters or using fewer elements.".to_string()
             }
             ErrorKind::UnknownModule => {
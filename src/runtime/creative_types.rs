@@ -1,6 +1,5 @@
 use crate::runtime::types::{Value, DataType};
-use crate::runtime::units::UnitValue;
-use crate::errors::ErrorKind;
+use crate::runtime::units::Unit;
 use std::collections::HashMap;
 use std::fmt;
 
@@ -11,6 +10,8 @@ pub struct CreativeTypeSystem {
     pub coercion_rules: Vec<CoercionRule>,
     pub creative_contexts: HashMap<String, CreativeContext>,
     pub type_inference_enabled: bool,
+    /// What inference found each variable to hold, so coercing one doesn't guess from its value
+    pub variable_types: HashMap<String, CreativeType>,
 }
 
 /// Rules for automatic type coercion in creative contexts
@@ -244,6 +245,7 @@ impl CreativeTypeSystem {
             coercion_rules: Vec::new(),
             creative_contexts: HashMap::new(),
             type_inference_enabled: true,
+            variable_types: HashMap::new(),
         };
         
         system.register_default_coercion_rules();
@@ -262,7 +264,7 @@ impl CreativeTypeSystem {
         
         // Find applicable coercion rule
         for rule in &self.coercion_rules {
-            if self.rule_applies(&rule, &source_type, target_type, context) {
+            if self.rule_applies(rule, &source_type, target_type, context) {
                 return self.apply_coercion_rule(value, rule, context);
             }
         }
@@ -271,8 +273,44 @@ impl CreativeTypeSystem {
         self.apply_default_coercion(value, target_type, context)
     }
     
+    /// Take in the variable types a program's inference pass found
+    pub fn learn(&mut self, inference: &crate::parser::inference::Inference) {
+        for name in inference.variables.keys() {
+            if let Some(creative_type) = inference.creative_type(name) {
+                self.variable_types.insert(name.clone(), creative_type);
+            }
+        }
+    }
+    
+    /// Coerce a variable's value, going by the type inference found for it rather than by the
+    /// value itself; 440.0 stays a number if that's what the variable holds
+    pub fn coerce_variable(&self, name: &str, value: &Value, target_type: &CreativeType, context: Option<&str>) -> Result<Value, String> {
+        let Some(source_type) = self.variable_types.get(name).filter(|_| self.type_inference_enabled) else {
+            return self.coerce_value(value, target_type, context);
+        };
+        if self.types_compatible(source_type, target_type) {
+            return Ok(value.clone());
+        }
+        match self.coercion_between(source_type, target_type) {
+            Some(rule) => self.apply_coercion_rule(value, rule, context),
+            None => self.apply_default_coercion(value, target_type, context),
+        }
+    }
+    
+    /// The rule that turns exactly this type into that one, if there is one
+    pub fn coercion_between(&self, from: &CreativeType, to: &CreativeType) -> Option<&CoercionRule> {
+        let matches = |rule_type: &CreativeType, actual: &CreativeType| match (rule_type, actual) {
+            // Color rules hold a placeholder color; any color matches
+            (CreativeType::Color(_), CreativeType::Color(_)) => true,
+            (a, b) => a == b,
+        };
+        self.coercion_rules.iter()
+            .filter(|rule| matches(&rule.from_type, from) && matches(&rule.to_type, to))
+            .max_by_key(|rule| rule.priority)
+    }
+    
     /// Infer the creative type from a Value
-    pub fn infer_creative_type(&self, value: &Value, context: Option<&str>) -> CreativeType {
+    pub fn infer_creative_type(&self, value: &Value, _context: Option<&str>) -> CreativeType {
         match value {
             Value::Integer(n) => {
                 if *n >= 0 && *n <= 127 {
@@ -304,12 +342,12 @@ impl CreativeTypeSystem {
             }
             Value::Boolean(_) => CreativeType::Boolean(BooleanType::Switch),
             Value::UnitValue(unit_val) => {
-                match unit_val.unit.to_string().as_str() {
-                    "hz" => CreativeType::Frequency(FrequencyType::Hertz),
-                    "%" => CreativeType::Number(NumberType::Percentage),
-                    "s" => CreativeType::Duration(DurationType::Seconds),
-                    "ms" => CreativeType::Duration(DurationType::Milliseconds),
-                    "db" => CreativeType::Number(NumberType::Decibel),
+                match unit_val.unit {
+                    Unit::Hertz | Unit::Kilohertz => CreativeType::Frequency(FrequencyType::Hertz),
+                    Unit::Percent => CreativeType::Number(NumberType::Percentage),
+                    Unit::Second => CreativeType::Duration(DurationType::Seconds),
+                    Unit::Millisecond => CreativeType::Duration(DurationType::Milliseconds),
+                    Unit::Note | Unit::Beat | Unit::Bar => CreativeType::Duration(DurationType::Beats),
                     _ => CreativeType::Number(NumberType::Float),
                 }
             }
@@ -357,7 +395,7 @@ impl CreativeTypeSystem {
         
         // Check context compatibility
         if let Some(ctx) = context {
-            if let Some(creative_ctx) = self.creative_contexts.get(ctx) {
+            if let Some(_creative_ctx) = self.creative_contexts.get(ctx) {
                 // Context-specific logic would go here
                 return true;
             }
@@ -373,8 +411,8 @@ impl CreativeTypeSystem {
             
             CoercionFunction::Scale(from_max, to_max) => {
                 if let Some(num) = value.as_number() {
-                    let normalized = num / from_max;
-                    let scaled = normalized * to_max;
+                    let normalized = num / *from_max as f64;
+                    let scaled = normalized * *to_max as f64;
                     Ok(Value::Float(scaled))
                 } else {
                     Err("🎨 Can't scale non-numeric value".to_string())
@@ -401,7 +439,7 @@ impl CreativeTypeSystem {
             CreativeType::Number(NumberType::Percentage) => {
                 if let Some(num) = value.as_number() {
                     // Assume input is 0-1, convert to percentage
-                    Ok(Value::Float((num * 100.0).min(100.0).max(0.0)))
+                    Ok(Value::Float((num * 100.0).clamp(0.0, 100.0)))
                 } else {
                     Err("🎨 Can't convert to percentage - need a number".to_string())
                 }
@@ -413,7 +451,7 @@ impl CreativeTypeSystem {
                     if num > 1.0 {
                         Ok(Value::Float(num / 100.0)) // Assume percentage
                     } else {
-                        Ok(Value::Float(num.min(1.0).max(0.0)))
+                        Ok(Value::Float(num.clamp(0.0, 1.0)))
                     }
                 } else {
                     Err("🎨 Can't normalize non-numeric value".to_string())
@@ -424,14 +462,14 @@ impl CreativeTypeSystem {
                 match value {
                     Value::String(note) => {
                         if let Some(freq) = self.note_to_frequency(note) {
-                            Ok(Value::Float(freq))
+                            Ok(Value::Float(freq as f64))
                         } else {
                             Err(format!("🎵 Don't recognize the note '{}'", note))
                         }
                     }
                     Value::Integer(midi_note) => {
                         let freq = self.midi_to_frequency(*midi_note as u8);
-                        Ok(Value::Float(freq))
+                        Ok(Value::Float(freq as f64))
                     }
                     _ => Err("🎵 Need a note name or MIDI number to get frequency".to_string())
                 }
@@ -447,7 +485,7 @@ impl CreativeTypeSystem {
             MusicalConversion::NoteToFrequency => {
                 if let Value::String(note) = value {
                     if let Some(freq) = self.note_to_frequency(note) {
-                        Ok(Value::Float(freq))
+                        Ok(Value::Float(freq as f64))
                     } else {
                         Err(format!("🎵 '{}' isn't a note I recognize", note))
                     }
@@ -459,7 +497,7 @@ impl CreativeTypeSystem {
             MusicalConversion::MIDIToFrequency => {
                 if let Some(midi) = value.as_number() {
                     let freq = self.midi_to_frequency(midi as u8);
-                    Ok(Value::Float(freq))
+                    Ok(Value::Float(freq as f64))
                 } else {
                     Err("🎵 Need a MIDI note number (0-127)".to_string())
                 }
@@ -468,7 +506,7 @@ impl CreativeTypeSystem {
             MusicalConversion::ChordToFrequencies => {
                 if let Value::String(chord) = value {
                     let frequencies = self.chord_to_frequencies(chord)?;
-                    Ok(Value::Array(frequencies.into_iter().map(Value::Float).collect()))
+                    Ok(Value::Array(frequencies.into_iter().map(|f| Value::Float(f as f64)).collect()))
                 } else {
                     Err("🎵 Need a chord name like 'Cmaj7' or 'Am'".to_string())
                 }
@@ -540,6 +578,16 @@ impl CreativeTypeSystem {
             priority: 10,
         });
         
+        // 50% where a 0-1 level goes
+        self.coercion_rules.push(CoercionRule {
+            name: "percentage_to_normalized".to_string(),
+            from_type: CreativeType::Number(NumberType::Percentage),
+            to_type: CreativeType::Number(NumberType::Normalized),
+            coercion_fn: CoercionFunction::Scale(100.0, 1.0),
+            context: CoercionContext::Generic,
+            priority: 10,
+        });
+        
         // Note name to frequency
         self.coercion_rules.push(CoercionRule {
             name: "note_to_frequency".to_string(),
@@ -0,0 +1,89 @@
+/// Event streams: timestamped notes, triggers and messages, kept apart from continuous streams
+///
+/// Audio and control streams are blocks of f32 samples, so anything discrete pushed through them
+/// lands on a block boundary and loses what it carried. An event stream keeps each event's time
+/// in seconds and its value as it is. `delay`, `quantize`, `filter` and `merge` move or pick events
+/// without rounding them to frames, and `block()` turns times into sample offsets within the
+/// audio block being rendered, so a note starts on the sample it was meant to.
+use crate::runtime::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Seconds since the sketch started, the clock patterns run on
+    pub time: f64,
+    pub value: Value,
+}
+
+/// Events in time order; events at the same time keep the order they were added in
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventStream {
+    events: Vec<Event>,
+}
+
+impl EventStream {
+    pub fn new(mut events: Vec<Event>) -> Self {
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { events }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Every event `seconds` later, or earlier for a negative delay
+    pub fn delay(&self, seconds: f64) -> Self {
+        let events = self.events.iter().map(|event| Event { time: event.time + seconds, value: event.value.clone() }).collect();
+        Self::new(events)
+    }
+
+    /// Each event moved `strength` (0 to 1) of the way to the nearest multiple of `grid` seconds
+    pub fn quantize(&self, grid: f64, strength: f64) -> Self {
+        if grid <= 0.0 {
+            return self.clone();
+        }
+        let strength = strength.clamp(0.0, 1.0);
+        let events = self.events.iter()
+            .map(|event| {
+                let target = (event.time / grid).round() * grid;
+                Event { time: event.time + (target - event.time) * strength, value: event.value.clone() }
+            })
+            .collect();
+        Self::new(events)
+    }
+
+    pub fn filter(&self, keep: impl Fn(&Event) -> bool) -> Self {
+        Self { events: self.events.iter().filter(|event| keep(event)).cloned().collect() }
+    }
+
+    /// All the events of every stream; at the same time, earlier streams' events come first
+    pub fn merge<'a>(streams: impl IntoIterator<Item = &'a EventStream>) -> Self {
+        Self::new(streams.into_iter().flat_map(|stream| stream.events.iter().cloned()).collect())
+    }
+
+    /// Events from `from` up to but not including `to`
+    pub fn between(&self, from: f64, to: f64) -> &[Event] {
+        let start = self.events.partition_point(|event| event.time < from);
+        let end = self.events.partition_point(|event| event.time < to).max(start);
+        &self.events[start..end]
+    }
+
+    /// The events inside an audio block of `frames` samples starting at `start` seconds, each
+    /// with the sample it falls on
+    pub fn block(&self, start: f64, frames: usize, sample_rate: f64) -> Vec<(usize, &Event)> {
+        if frames == 0 || sample_rate <= 0.0 {
+            return Vec::new();
+        }
+        self.between(start, start + frames as f64 / sample_rate)
+            .iter()
+            .map(|event| ((((event.time - start) * sample_rate).round() as usize).min(frames - 1), event))
+            .collect()
+    }
+}
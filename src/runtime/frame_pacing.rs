@@ -0,0 +1,283 @@
+/// Frame pacing: target frame rate, vsync/buffering choice and fixed-rate script updates
+/// Keeps `loop { }` blocks from spinning flat out and reports frames that miss their budget
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Upper bound on catch-up updates per rendered frame, so a slow frame can't snowball
+const MAX_UPDATES_PER_FRAME: u32 = 4;
+
+// Latest frame timing, readable from stateless module functions like Timeline.delta_time()
+static FRAME_STATE: OnceLock<Mutex<FrameTick>> = OnceLock::new();
+
+fn frame_state() -> &'static Mutex<FrameTick> {
+    FRAME_STATE.get_or_init(|| Mutex::new(FrameTick::default()))
+}
+
+/// Timing of the most recently paced frame
+pub fn current_frame() -> FrameTick {
+    frame_state().lock().map(|tick| *tick).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramePacingConfig {
+    /// Render rate; None runs unthrottled
+    pub fps: Option<f32>,
+    pub vsync: bool,
+    pub triple_buffering: bool,
+    /// Script update rate; None updates once per rendered frame
+    pub update_rate: Option<f32>,
+}
+
+impl FramePacingConfig {
+    pub fn new() -> Self {
+        Self {
+            fps: Some(60.0),
+            vsync: true,
+            triple_buffering: false,
+            update_rate: None,
+        }
+    }
+
+    /// Apply the fields of a script `config { fps: 30, vsync: false }` block
+    pub fn apply_value(&mut self, value: &Value) -> crate::Result<()> {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            _ => return Ok(()),
+        };
+
+        for (key, value) in fields {
+            match key.as_str() {
+                "fps" => self.fps = Self::rate(key, value)?,
+                "update_rate" => self.update_rate = Self::rate(key, value)?,
+                "vsync" => self.vsync = value.is_truthy(),
+                "triple_buffering" => self.triple_buffering = value.is_truthy(),
+                _ => {} // other config keys belong to other subsystems
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rate(key: &str, value: &Value) -> crate::Result<Option<f32>> {
+        match value {
+            Value::Null => Ok(None),
+            Value::String(s) if s == "unlimited" => Ok(None),
+            _ => match value.as_number() {
+                Some(rate) if rate > 0.0 => Ok(Some(rate as f32)),
+                _ => Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    format!("🎬 config {} must be a positive number of frames per second", key)
+                )
+                .with_suggestion(format!("Try: config {{ {}: 30 }}", key))
+                .with_suggestion("Use \"unlimited\" to run as fast as possible")),
+            },
+        }
+    }
+
+    pub fn frame_budget(&self) -> Option<Duration> {
+        self.fps.map(|fps| Duration::from_secs_f32(1.0 / fps))
+    }
+
+    /// Frames the swapchain may queue: triple buffering trades a frame of latency for smoothness
+    pub fn max_frame_latency(&self) -> u32 {
+        if self.triple_buffering { 3 } else { 2 }
+    }
+
+    pub fn to_value(&self) -> Value {
+        let rate = |rate: Option<f32>| rate.map(|r| Value::Float(r as f64)).unwrap_or(Value::String("unlimited".to_string()));
+        let mut fields = HashMap::new();
+        fields.insert("fps".to_string(), rate(self.fps));
+        fields.insert("update_rate".to_string(), rate(self.update_rate));
+        fields.insert("vsync".to_string(), Value::Boolean(self.vsync));
+        fields.insert("triple_buffering".to_string(), Value::Boolean(self.triple_buffering));
+        Value::Object(fields)
+    }
+}
+
+impl Default for FramePacingConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTick {
+    pub frame: u64,
+    /// Script updates to run before this frame is rendered
+    pub updates: u32,
+    /// Blend factor between the previous and current update for interpolated rendering
+    pub alpha: f32,
+    pub delta_time: f32,
+    /// Seconds since the first frame, summed from frame deltas so patterns stay on the frame clock
+    pub elapsed: f64,
+    pub fps: f32,
+    pub dropped: u64,
+}
+
+impl Default for FrameTick {
+    fn default() -> Self {
+        Self {
+            frame: 0,
+            updates: 1,
+            alpha: 1.0,
+            delta_time: 1.0 / 60.0,
+            elapsed: 0.0,
+            fps: 60.0,
+            dropped: 0,
+        }
+    }
+}
+
+pub struct FramePacer {
+    config: FramePacingConfig,
+    frame: u64,
+    frame_start: Option<Instant>,
+    next_deadline: Option<Instant>,
+    update_accumulator: f32,
+    smoothed_fps: f32,
+    dropped_frames: u64,
+    elapsed: f64,
+}
+
+impl FramePacer {
+    pub fn new(config: FramePacingConfig) -> Self {
+        Self {
+            config,
+            frame: 0,
+            frame_start: None,
+            next_deadline: None,
+            update_accumulator: 0.0,
+            smoothed_fps: 60.0,
+            dropped_frames: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn config(&self) -> &FramePacingConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: FramePacingConfig) {
+        self.config = config;
+        self.next_deadline = None;
+    }
+
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Start a frame: measures the previous one and decides how many updates to run
+    pub fn begin_frame(&mut self) -> FrameTick {
+        let now = Instant::now();
+        let delta_time = self.frame_start
+            .map(|start| now.duration_since(start).as_secs_f32())
+            .unwrap_or_else(|| self.config.frame_budget().map(|b| b.as_secs_f32()).unwrap_or(1.0 / 60.0));
+        self.frame_start = Some(now);
+
+        let mut dropped = 0;
+        if let Some(budget) = self.config.frame_budget() {
+            // A frame that took more than one and a half budgets skipped at least one refresh
+            let budget = budget.as_secs_f32();
+            if self.frame > 0 && delta_time > budget * 1.5 {
+                dropped = ((delta_time / budget).round() as u64).saturating_sub(1).max(1);
+                self.dropped_frames += dropped;
+            }
+        }
+
+        // The first frame starts the clock; later frames add the time since the one before
+        if self.frame > 0 {
+            self.elapsed += delta_time as f64;
+        }
+
+        if delta_time > 0.0 {
+            self.smoothed_fps = self.smoothed_fps * 0.9 + (1.0 / delta_time) * 0.1;
+        }
+
+        let (updates, alpha) = match self.config.update_rate {
+            Some(rate) => {
+                let step = 1.0 / rate;
+                self.update_accumulator += delta_time;
+                let mut updates = (self.update_accumulator / step) as u32;
+                self.update_accumulator -= updates as f32 * step;
+                if updates > MAX_UPDATES_PER_FRAME {
+                    updates = MAX_UPDATES_PER_FRAME;
+                    self.update_accumulator = 0.0;
+                }
+                (updates, (self.update_accumulator / step).clamp(0.0, 1.0))
+            }
+            None => (1, 1.0),
+        };
+
+        let tick = FrameTick {
+            frame: self.frame,
+            updates,
+            alpha,
+            delta_time,
+            elapsed: self.elapsed,
+            fps: self.smoothed_fps,
+            dropped,
+        };
+        self.frame += 1;
+
+        if let Ok(mut state) = frame_state().lock() {
+            *state = tick;
+        }
+
+        tick
+    }
+
+    /// Sleep until the next frame is due (vsync does this for us when presenting)
+    /// Returns the time the frame spent working, excluding the sleep
+    pub fn end_frame(&mut self) -> f32 {
+        let now = Instant::now();
+        let work_time = self.frame_start
+            .map(|start| now.duration_since(start).as_secs_f32())
+            .unwrap_or(0.0);
+
+        let budget = match self.config.frame_budget() {
+            Some(budget) => budget,
+            None => return work_time,
+        };
+
+        let deadline = self.next_deadline.unwrap_or(now) + budget;
+
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+            self.next_deadline = Some(deadline);
+        } else {
+            // We're late: re-anchor instead of rushing to catch up
+            self.next_deadline = Some(now);
+        }
+
+        work_time
+    }
+}
+
+/// Blend two script values for interpolated rendering between fixed-rate updates
+pub fn interpolate_value(previous: &Value, current: &Value, alpha: f32) -> Value {
+    let alpha = alpha.clamp(0.0, 1.0) as f64;
+    match (previous, current) {
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            Value::Array(a.iter().zip(b).map(|(a, b)| interpolate_value(a, b, alpha as f32)).collect())
+        }
+        (Value::Object(a), Value::Object(b)) => Value::Object(
+            b.iter()
+                .map(|(key, b_value)| {
+                    let value = match a.get(key) {
+                        Some(a_value) => interpolate_value(a_value, b_value, alpha as f32),
+                        None => b_value.clone(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+            let a = previous.as_number().unwrap_or(0.0);
+            let b = current.as_number().unwrap_or(0.0);
+            Value::Float(a + (b - a) * alpha)
+        }
+        _ => current.clone(),
+    }
+}
@@ -0,0 +1,82 @@
+/// Example programs bundled into the binary, so `synthesis examples` works from any directory
+///
+/// Each file starts with a one-line description and its category:
+///
+/// ```text
+/// // Beat pulse: a circle that jumps on every beat
+/// // category: audio-reactive
+/// ```
+use crate::runtime::{CapabilityPolicy, SandboxLimits, SandboxReport, SandboxedEngine};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+macro_rules! example {
+    ($name:literal) => {
+        Example { name: $name, source: include_str!(concat!("../../examples/gallery/", $name, ".syn")) }
+    };
+}
+
+const EXAMPLES: &[Example] = &[
+    example!("beat_pulse"),
+    example!("spectrum_glow"),
+    example!("lissajous"),
+    example!("noise_field"),
+    example!("rhythm_grid"),
+    example!("slider_orbit"),
+];
+
+impl Example {
+    fn header(&self, key: &str) -> Option<&'static str> {
+        self.source
+            .lines()
+            .take_while(|line| line.starts_with("//"))
+            .filter_map(|line| line.trim_start_matches('/').trim().strip_prefix(key))
+            .map(str::trim)
+            .next()
+    }
+
+    pub fn category(&self) -> &'static str {
+        self.header("category:").unwrap_or("misc")
+    }
+
+    pub fn description(&self) -> &'static str {
+        self.source
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("//"))
+            .map(|line| line.split_once(':').map(|(_, rest)| rest).unwrap_or(line).trim())
+            .unwrap_or("")
+    }
+
+    /// Run a few headless frames; examples may use the mic or camera, so devices stay allowed
+    pub fn check(&self, frames: u64) -> SandboxReport {
+        SandboxedEngine::new(SandboxLimits { max_frames: frames, ..SandboxLimits::default() })
+            .with_capabilities(CapabilityPolicy::default())
+            .run(self.source)
+    }
+}
+
+pub fn examples() -> &'static [Example] {
+    EXAMPLES
+}
+
+pub fn find_example(name: &str) -> Option<&'static Example> {
+    let name = name.trim_end_matches(".syn");
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+/// Examples grouped by category, in catalog order
+pub fn examples_by_category() -> Vec<(&'static str, Vec<&'static Example>)> {
+    let mut groups: Vec<(&'static str, Vec<&'static Example>)> = Vec::new();
+    for example in EXAMPLES {
+        match groups.iter_mut().find(|(category, _)| *category == example.category()) {
+            Some((_, members)) => members.push(example),
+            None => groups.push((example.category(), vec![example])),
+        }
+    }
+    groups
+}
@@ -0,0 +1,201 @@
+/// Gestures picked out of control values, so interaction logic doesn't need hand-rolled thresholds
+///
+/// `pad.gesture()` turns a pressure or velocity into `"tap"`, `"double_tap"` and `"hold"` events,
+/// `accel.shake()` is true on the frame a shake finishes, and `finger.swipe()` gives the direction
+/// of a touch that moved quickly and let go. Each call site keeps its own detector, and times come
+/// from frame deltas like the other helpers.
+use crate::runtime::vectors::Linear;
+use crate::runtime::{helper_numbers, Value};
+use std::collections::VecDeque;
+
+/// Methods on numbers, lists, vectors and streams that report gestures
+pub const GESTURE_METHODS: [&str; 3] = ["gesture", "shake", "swipe"];
+/// Pressing for longer than this is a hold rather than a tap
+pub const HOLD_SECONDS: f64 = 0.5;
+/// A second tap starting this soon after the first ends makes a double tap
+pub const DOUBLE_TAP_SECONDS: f64 = 0.3;
+/// Jolts that have to land within `SHAKE_SECONDS` of each other to count as a shake
+pub const SHAKE_JOLTS: usize = 3;
+pub const SHAKE_SECONDS: f64 = 1.0;
+/// Touches that take longer than this are drags, not swipes
+pub const SWIPE_SECONDS: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Tap,
+    DoubleTap,
+    Hold,
+}
+
+impl Gesture {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Gesture::Tap => "tap",
+            Gesture::DoubleTap => "double_tap",
+            Gesture::Hold => "hold",
+        }
+    }
+}
+
+/// Taps, double taps and holds from a value that goes over `threshold` while pressed
+///
+/// A tap is reported as soon as it's released, so the first of a double tap is also a tap
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TapDetector {
+    now: f64,
+    pressed_at: Option<f64>,
+    held: bool,
+    /// When the last tap ended, while a second one could still make it a double
+    last_tap: Option<f64>,
+}
+
+impl TapDetector {
+    pub fn step(&mut self, value: f64, threshold: f64, dt: f64) -> Option<Gesture> {
+        self.now += dt.max(0.0);
+        match (self.pressed_at, value >= threshold) {
+            (None, true) => {
+                self.pressed_at = Some(self.now);
+                self.held = false;
+                None
+            }
+            (Some(pressed_at), true) if !self.held && self.now - pressed_at >= HOLD_SECONDS => {
+                self.held = true;
+                self.last_tap = None;
+                Some(Gesture::Hold)
+            }
+            (Some(_), false) => {
+                self.pressed_at = None;
+                if std::mem::take(&mut self.held) {
+                    return None;
+                }
+                match self.last_tap.take() {
+                    Some(last) if self.now - last <= DOUBLE_TAP_SECONDS => Some(Gesture::DoubleTap),
+                    _ => {
+                        self.last_tap = Some(self.now);
+                        Some(Gesture::Tap)
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Shakes from an accelerometer: several sharp changes in quick succession
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShakeDetector {
+    now: f64,
+    previous: Option<Vec<f64>>,
+    jolts: VecDeque<f64>,
+}
+
+impl ShakeDetector {
+    /// True on the frame the shake is recognised; `threshold` is how far the reading has to
+    /// jump in one frame to count as a jolt
+    pub fn step(&mut self, reading: &[f64], threshold: f64, dt: f64) -> bool {
+        self.now += dt.max(0.0);
+        let change = match self.previous.replace(reading.to_vec()) {
+            Some(previous) if previous.len() == reading.len() => {
+                previous.iter().zip(reading).map(|(a, b)| (b - a).powi(2)).sum::<f64>().sqrt()
+            }
+            _ => return false,
+        };
+        while self.jolts.front().is_some_and(|at| self.now - at > SHAKE_SECONDS) {
+            self.jolts.pop_front();
+        }
+        if change < threshold {
+            return false;
+        }
+        self.jolts.push_back(self.now);
+        if self.jolts.len() < SHAKE_JOLTS {
+            return false;
+        }
+        self.jolts.clear();
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Swipe {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Swipe {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Swipe::Left => "left",
+            Swipe::Right => "right",
+            Swipe::Up => "up",
+            Swipe::Down => "down",
+        }
+    }
+}
+
+/// Swipes from a touch position that's null while nothing touches, with y growing downwards
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SwipeDetector {
+    now: f64,
+    touch: Option<SwipeTouch>,
+}
+
+/// Where and when a touch started, and where it was last seen
+#[derive(Debug, Clone, PartialEq)]
+struct SwipeTouch {
+    from: (f64, f64),
+    started: f64,
+    last: (f64, f64),
+}
+
+impl SwipeDetector {
+    /// The direction once a quick touch that covered `distance` lets go
+    pub fn step(&mut self, position: Option<(f64, f64)>, distance: f64, dt: f64) -> Option<Swipe> {
+        self.now += dt.max(0.0);
+        match (position, &mut self.touch) {
+            (Some(position), Some(touch)) => {
+                touch.last = position;
+                None
+            }
+            (Some(position), None) => {
+                self.touch = Some(SwipeTouch { from: position, started: self.now, last: position });
+                None
+            }
+            (None, Some(_)) => {
+                let touch = self.touch.take()?;
+                let (dx, dy) = (touch.last.0 - touch.from.0, touch.last.1 - touch.from.1);
+                if self.now - touch.started > SWIPE_SECONDS || dx.hypot(dy) < distance {
+                    return None;
+                }
+                Some(match (dx.abs() >= dy.abs(), dx > 0.0, dy > 0.0) {
+                    (true, true, _) => Swipe::Right,
+                    (true, false, _) => Swipe::Left,
+                    (false, _, true) => Swipe::Down,
+                    (false, _, false) => Swipe::Up,
+                })
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// x and y of a touch point, `Vec2`, `{ x, y }` object or two-number list
+pub fn position(value: &Value) -> Option<(f64, f64)> {
+    match value {
+        Value::Object(fields) | Value::Struct { fields, .. } => Some((fields.get("x")?.as_number()?, fields.get("y")?.as_number()?)),
+        Value::Array(items) if items.len() == 2 => Some((items[0].as_number()?, items[1].as_number()?)),
+        _ => None,
+    }
+}
+
+/// An accelerometer reading as its axes: a vector, `{ x, y, z }` object, list or single number
+pub fn reading(value: &Value) -> Option<Vec<f64>> {
+    if let Some(vector) = Linear::from_value(value).filter(Linear::is_vector) {
+        return Some(vector.components().to_vec());
+    }
+    match value {
+        Value::Object(fields) => ["x", "y", "z"].iter().map(|axis| fields.get(*axis)?.as_number()).collect(),
+        other => helper_numbers(other).map(|(numbers, _)| numbers),
+    }
+}
@@ -0,0 +1,209 @@
+/// Importing other `.syn` files: `import lib/drums` runs `lib/drums.syn` and makes its functions
+/// and top-level variables available as `drums.kick()` and `drums.tempo`.
+///
+/// Each file runs once, in an interpreter of its own so its functions see its own variables, and is
+/// cached by path: a file imported from several places shares one copy of its state. Paths are
+/// relative to the folder of the file doing the importing.
+///
+/// Importing a folder imports it as a package: `import mylib` brings in each file and folder of
+/// scripts inside it under its own name, so `mylib/chords.syn` is used as `mylib.chords.make()`. A
+/// package says which version it is in its `synthesis.toml`, and the nearest `synthesis.toml` above
+/// the importing file can ask for a range of versions:
+///
+/// ```toml
+/// [package]
+/// name = "mylib"
+/// version = "1.2.0"
+///
+/// [dependencies]
+/// chords = "^0.3"
+/// ```
+use crate::parser::ast::Program;
+use crate::parser::{lexer, Parser};
+use crate::runtime::Interpreter;
+use semver::{Version, VersionReq};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const SCRIPT_EXTENSION: &str = "syn";
+pub const MANIFEST_FILE: &str = "synthesis.toml";
+
+/// An imported file, running in its own interpreter
+pub type ImportedModule = Arc<Mutex<Interpreter>>;
+
+/// Imported files by path, shared by a script and everything it imports
+#[derive(Default)]
+pub struct ImportCache {
+    modules: HashMap<PathBuf, ImportedModule>,
+    /// Files partway through running their top level, outermost first
+    loading: Vec<PathBuf>,
+}
+
+impl ImportCache {
+    pub fn get(&self, path: &Path) -> Option<ImportedModule> {
+        self.modules.get(path).cloned()
+    }
+
+    /// Mark `path` as loading, or explain the cycle if it already is
+    pub fn begin(&mut self, path: &Path) -> crate::Result<()> {
+        if let Some(start) = self.loading.iter().position(|loading| loading == path) {
+            let cycle: Vec<String> = self.loading[start..].iter()
+                .chain(std::iter::once(&path.to_path_buf()))
+                .map(|path| path.display().to_string())
+                .collect();
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("📦 These files import each other in a circle: {}", cycle.join(" → "))
+            )
+            .with_suggestion("Move what they share into a third file that both import"));
+        }
+        self.loading.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Done loading `path`; a module that failed is forgotten so fixing it and importing again works
+    pub fn finish(&mut self, path: &Path, module: Option<ImportedModule>) {
+        self.loading.retain(|loading| loading != path);
+        if let Some(module) = module {
+            self.modules.insert(path.to_path_buf(), module);
+        }
+    }
+}
+
+/// Where `import lib/drums` looks from a file in `dir`
+pub fn import_path(dir: &Path, module: &str) -> PathBuf {
+    let path = dir.join(module);
+    match path.extension() {
+        Some(extension) if extension == SCRIPT_EXTENSION => path,
+        _ => path.with_extension(SCRIPT_EXTENSION),
+    }
+}
+
+/// The name an import's contents go under: its file name, without folder or extension
+pub fn namespace(module: &str) -> String {
+    let name = module.rsplit(['/', '\\']).next().unwrap_or(module);
+    name.strip_suffix(".syn").unwrap_or(name).to_string()
+}
+
+/// Read and parse a file for importing, with errors pointing into that file
+pub fn parse_file(path: &Path) -> crate::Result<Program> {
+    let source = std::fs::read_to_string(path).map_err(|error| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::FileNotFound,
+        format!("📦 Couldn't read {}: {}", path.display(), error)
+    ))?;
+    let filename = path.display().to_string();
+    let (_, (tokens, spans)) = lexer::tokenize_with_spans(&source).map_err(|error| lexer::literal_error(&source, &error, &filename).unwrap_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::SyntaxError,
+        format!("📦 There's something unusual in {}", filename)
+    )
+    .with_suggestion("Check it for typos, missing quotes, or unusual characters")))?;
+    Parser::with_spans(&tokens, &spans, &filename).parse()
+}
+
+/// The files and folders of scripts a package folder holds, by the name each is imported under
+pub fn package_contents(dir: &Path) -> crate::Result<Vec<String>> {
+    let entries = std::fs::read_dir(dir).map_err(|error| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::FileNotFound,
+        format!("📦 Couldn't read the package {}: {}", dir.display(), error)
+    ))?;
+    let mut names: Vec<String> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let script = path.is_file() && path.extension().is_some_and(|extension| extension == SCRIPT_EXTENSION);
+            script || (path.is_dir() && is_package(path))
+        })
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Folders with scripts or a `synthesis.toml` in them; others, like `assets`, aren't imported
+fn is_package(dir: &Path) -> bool {
+    dir.join(MANIFEST_FILE).is_file() || std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().any(|entry| entry.path().extension().is_some_and(|extension| extension == SCRIPT_EXTENSION))
+    })
+}
+
+/// A `synthesis.toml`: what the package in its folder is called and which version it is, and the
+/// versions the scripts around it need of the packages they import
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    pub path: PathBuf,
+    pub name: Option<String>,
+    pub version: Option<Version>,
+    pub dependencies: BTreeMap<String, VersionReq>,
+}
+
+impl Manifest {
+    pub fn parse(text: &str, path: &Path) -> crate::Result<Self> {
+        let invalid = |reason: String| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::SyntaxError,
+            format!("📦 {} isn't a valid manifest: {}", path.display(), reason)
+        )
+        .with_suggestion("It holds a [package] table with a name and a version like \"1.2.0\", and a [dependencies] table like mylib = \"^1.2\"");
+        let table = text.parse::<toml::Table>().map_err(|error| invalid(error.message().to_string()))?;
+        let package = table.get("package").and_then(|package| package.as_table());
+        let text_field = |field: &str| package.and_then(|package| package.get(field)).and_then(|value| value.as_str());
+        let version = text_field("version")
+            .map(|version| Version::parse(version).map_err(|error| invalid(format!("version \"{}\": {}", version, error))))
+            .transpose()?;
+        let mut dependencies = BTreeMap::new();
+        for (name, requirement) in table.get("dependencies").and_then(|dependencies| dependencies.as_table()).into_iter().flatten() {
+            let Some(requirement) = requirement.as_str() else {
+                return Err(invalid(format!("the version {} needs is written as text, like \"^1.2\"", name)));
+            };
+            let requirement = VersionReq::parse(requirement)
+                .map_err(|error| invalid(format!("{} = \"{}\": {}", name, requirement, error)))?;
+            dependencies.insert(name.clone(), requirement);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            name: text_field("name").map(str::to_string),
+            version,
+            dependencies,
+        })
+    }
+
+    /// The `synthesis.toml` in `dir`, if it has one
+    pub fn in_dir(dir: &Path) -> crate::Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, &path).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// The manifest scripts in `dir` go by: the nearest `synthesis.toml` in it or a folder above
+    pub fn nearest(dir: &Path) -> crate::Result<Option<Self>> {
+        for dir in dir.ancestors() {
+            if let Some(manifest) = Self::in_dir(dir)? {
+                return Ok(Some(manifest));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check an imported package against the versions this manifest asks for; `name` is the
+    /// package's own name, or its folder's when it doesn't give one
+    pub fn check_dependency(&self, name: &str, package: Option<&Manifest>) -> crate::Result<()> {
+        let Some(requirement) = self.dependencies.get(name) else {
+            return Ok(());
+        };
+        match package.and_then(|package| package.version.as_ref()) {
+            Some(version) if requirement.matches(version) => Ok(()),
+            Some(version) => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownModule,
+                format!("📦 {} is version {}, but {} asks for {}", name, version, self.path.display(), requirement)
+            )
+            .with_suggestion(format!("Update {}, or change the version {} asks for", name, self.path.display()))),
+            None => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownModule,
+                format!("📦 {} asks for {} {}, but the package doesn't say which version it is", self.path.display(), name, requirement)
+            )
+            .with_suggestion(format!("Add a synthesis.toml to {} with [package] and a version = \"...\"", name))),
+        }
+    }
+}
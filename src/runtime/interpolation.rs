@@ -0,0 +1,117 @@
+/// State behind `smooth()`, `spring()` and `history()`
+///
+/// Each call site in a script keeps its own state, so `smooth(fft[0], 0.2)` and
+/// `smooth(fft[1], 0.2)` on separate lines don't interfere. Smoothing and springs are
+/// frame-rate independent: times are in seconds and every step takes the frame's delta.
+use crate::runtime::{PeakHold, RollingWindow, ShakeDetector, SwipeDetector, TapDetector, Value};
+use std::collections::VecDeque;
+
+/// Delta used outside the frame loop, where there's no previous frame to measure
+pub const DEFAULT_FRAME_DELTA: f64 = 1.0 / 60.0;
+/// Most frames `history()` can reach back (ten seconds at 60fps)
+pub const MAX_HISTORY: usize = 600;
+
+/// Exponential smoothing; `time_constant` is roughly how long it takes to cover 63% of a jump
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Smoother {
+    value: Option<Vec<f64>>,
+}
+
+impl Smoother {
+    pub fn step(&mut self, input: &[f64], time_constant: f64, dt: f64) -> Vec<f64> {
+        let next = match &self.value {
+            Some(previous) if previous.len() == input.len() && time_constant > 0.0 => {
+                let alpha = 1.0 - (-dt.max(0.0) / time_constant).exp();
+                previous.iter().zip(input).map(|(p, i)| p + (i - p) * alpha).collect()
+            }
+            // First call, a disabled filter, or a list that changed length: jump straight there
+            _ => input.to_vec(),
+        };
+        self.value = Some(next.clone());
+        next
+    }
+}
+
+/// A critically damped spring: reaches the target in about `smooth_time` seconds without overshoot
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spring {
+    position: Option<Vec<f64>>,
+    velocity: Vec<f64>,
+}
+
+impl Spring {
+    pub fn step(&mut self, target: &[f64], smooth_time: f64, dt: f64) -> Vec<f64> {
+        let position = match self.position.take() {
+            Some(position) if position.len() == target.len() && smooth_time > 0.0 => position,
+            _ => {
+                self.velocity = vec![0.0; target.len()];
+                self.position = Some(target.to_vec());
+                return target.to_vec();
+            }
+        };
+
+        let omega = 2.0 / smooth_time;
+        let x = omega * dt.max(0.0);
+        let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+        let next: Vec<f64> = position.iter()
+            .zip(target)
+            .zip(self.velocity.iter_mut())
+            .map(|((position, target), velocity)| {
+                let change = position - target;
+                let temp = (*velocity + omega * change) * dt;
+                *velocity = (*velocity - omega * temp) * decay;
+                target + (change + temp) * decay
+            })
+            .collect();
+        self.position = Some(next.clone());
+        next
+    }
+}
+
+/// The last few values seen at a call site, newest last
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct History {
+    values: VecDeque<Value>,
+}
+
+impl History {
+    /// Record this frame's value and return the one from `frames_ago` frames back
+    pub fn step(&mut self, value: Value, frames_ago: usize) -> Value {
+        let frames_ago = frames_ago.min(MAX_HISTORY);
+        self.values.push_back(value);
+        while self.values.len() > frames_ago + 1 {
+            self.values.pop_front();
+        }
+        // Until enough frames have passed, the oldest value stands in
+        let index = self.values.len().saturating_sub(frames_ago + 1);
+        self.values[index].clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HelperState {
+    Smooth(Smoother),
+    Spring(Spring),
+    History(History),
+    Rolling(RollingWindow),
+    PeakHold(PeakHold),
+    Tap(TapDetector),
+    Shake(ShakeDetector),
+    Swipe(SwipeDetector),
+}
+
+/// Numbers out of a number or a list of numbers, remembering which it was
+pub fn helper_numbers(value: &Value) -> Option<(Vec<f64>, bool)> {
+    match value {
+        Value::Array(items) => items.iter().map(|item| item.as_number()).collect::<Option<Vec<_>>>().map(|n| (n, true)),
+        other => other.as_number().map(|n| (vec![n], false)),
+    }
+}
+
+pub fn helper_value(numbers: Vec<f64>, is_list: bool) -> Value {
+    if is_list {
+        Value::Array(numbers.into_iter().map(Value::Float).collect())
+    } else {
+        Value::Float(numbers.first().copied().unwrap_or(0.0))
+    }
+}
@@ -1,6 +1,32 @@
 use crate::parser::ast::*;
-use crate::runtime::{StreamManager, Value};
-use std::collections::HashMap;
+use crate::parser::lexer::Span;
+use crate::runtime::{CapabilityPolicy, FramePacer, FramePacingConfig, QualityChange, QualityGovernor, SandboxSession, StreamManager, StreamTap, SupervisedRun, Value, DEFAULT_TAP_RATE};
+use crate::runtime::{helper_numbers, helper_value, HelperState, History, Smoother, Spring, DEFAULT_FRAME_DELTA};
+use crate::runtime::{PeakHold, RollingWindow, Statistic, DEFAULT_WINDOW, STATISTIC_METHODS};
+use crate::runtime::{gestures, ShakeDetector, SwipeDetector, TapDetector, GESTURE_METHODS};
+use crate::runtime::{ImportCache, ImportedModule, Manifest};
+use crate::runtime::{Router, StreamCompositionEngine};
+use crate::runtime::coroutines::{contains_wait, wait_call, Coroutine, Frame};
+use crate::runtime::{network_receivers, NetworkSend};
+use crate::runtime::plugins::SynthesisModule;
+use crate::runtime::wasm_extensions::wasm_extensions;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Nested `func` calls allowed before a runaway recursion is stopped
+pub const MAX_CALL_DEPTH: usize = 128;
+
+/// Native stack a `func` call wants left before it carries on in a fresh segment of `CALL_STACK_SEGMENT` bytes;
+/// one level of script recursion runs through several large interpreter frames
+const CALL_STACK_RED_ZONE: usize = 1024 * 1024;
+const CALL_STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+/// Frames between `--profile` reports (about two seconds at 60fps)
+const PROFILE_REPORT_INTERVAL: u64 = 120;
+
+/// Frames between sweeps for unused assets (about ten seconds at 60fps)
+const RESOURCE_SWEEP_INTERVAL: u64 = 600;
 
 #[derive(Debug, Clone)]
 pub enum ControlFlow {
@@ -14,6 +40,61 @@ pub struct Interpreter {
     pub variables: HashMap<String, Value>,
     pub stream_manager: StreamManager,
     pub modules: HashMap<String, Module>,
+    pub frame_pacing: FramePacingConfig,
+    frame_pacing_overrides: HashMap<String, Value>,
+    pub quality_governor: QualityGovernor,
+    /// Print the per-pass frame profile periodically (`--profile`)
+    pub profiling: bool,
+    /// Unload assets idle for this long (`config { unload_unused_after: 10 }`, in minutes)
+    pub unload_unused_after: Option<std::time::Duration>,
+    /// Heartbeats and snapshots when running under `synthesis supervise`
+    supervised: Option<SupervisedRun>,
+    /// Variables exposed to the remote control API (`config { remote: ["speed", "hue"] }`)
+    pub remote_variables: Vec<String>,
+    /// Declared capabilities, checked before module calls that touch files or devices
+    pub capabilities: CapabilityPolicy,
+    /// Instruction, time and memory limits plus call recording for `SandboxedEngine` runs
+    pub sandbox: Option<SandboxSession>,
+    /// CSV loggers from `value.tap("file.csv", rate: 100.hz)`, sampled once per frame
+    pub taps: Vec<StreamTap>,
+    /// Values and streams sent to other machines with `value.send_to("host:port")`, once per frame
+    pub sends: Vec<NetworkSend>,
+    /// State for `smooth()`, `spring()` and `history()`, keyed by call site
+    helpers: HashMap<usize, HelperState>,
+    /// Switches, gates and crossbars between streams, moved along once per frame
+    pub composition: StreamCompositionEngine,
+    /// The router each `switch()`, `gate()` and `crossbar()` call site drives
+    routers: HashMap<usize, String>,
+    /// Seconds covered by the current update, for frame-rate independent helpers
+    frame_delta: f64,
+    /// The fluid step the solver thread is working on, and the seconds gone by since it was asked for
+    fluid_step: Option<std::sync::mpsc::Receiver<Vec<crate::graphics::FluidFrame>>>,
+    fluid_seconds: f32,
+    /// `every`, `after` and `async` blocks that are waiting or part way through, resumed once per update
+    coroutines: Vec<Coroutine>,
+    /// `every` and `after` statements that have started their coroutine; each only ever starts one
+    scheduled: HashSet<usize>,
+    /// Names declared with `const` at the top level, with where each was declared; only that
+    /// declaration may run again. Those inside a call live in its scope
+    constants: HashMap<String, Span>,
+    /// `func` definitions; shared so helper state keyed by call site survives across calls
+    pub functions: HashMap<String, Arc<FunctionDef>>,
+    /// `struct` definitions; defaults are evaluated each time an instance is built
+    pub structs: HashMap<String, Arc<StructDef>>,
+    /// `class` definitions; instances are `Value::Struct`s named after their class
+    pub classes: HashMap<String, Arc<ClassDef>>,
+    /// One scope per active `func` call; assignments inside a call stay in its scope
+    locals: Vec<Scope>,
+    /// Folder of the running script; `import` paths start here
+    pub script_dir: PathBuf,
+    /// Files brought in with `import`, by the name they're used under
+    imports: HashMap<String, ImportedModule>,
+    /// Functions picked out with `import Audio.{analyze_fft}` or `import lib/drums.{kick}`, and where they come from
+    imported_functions: HashMap<String, String>,
+    /// `import Graphics as G`: short names for built-in modules
+    module_aliases: HashMap<String, String>,
+    /// Shared with imported files, so each file runs once however many import it
+    import_cache: Arc<Mutex<ImportCache>>,
 }
 
 #[derive(Debug, Clone)]
@@ -22,10 +103,34 @@ pub struct Module {
     pub functions: HashMap<String, ModuleFunction>,
 }
 
+/// `callback` gets the positional arguments in order and the named ones by name
 #[derive(Debug, Clone)]
 pub struct ModuleFunction {
     pub name: String,
-    pub callback: fn(&[Value]) -> crate::Result<Value>,
+    pub callback: fn(&[Value], &HashMap<String, Value>) -> crate::Result<Value>,
+}
+
+/// A `func` call's variables, and which of them it declared with `const` and where
+#[derive(Debug, Default)]
+struct Scope {
+    variables: HashMap<String, Value>,
+    constants: HashMap<String, Span>,
+}
+
+/// Where a method lives: any class, or a struct that defines some (usually operators)
+#[derive(Debug, Clone)]
+enum Methods {
+    Class(Arc<ClassDef>),
+    Struct(Arc<StructDef>),
+}
+
+impl Methods {
+    fn methods(&self) -> &[FunctionDef] {
+        match self {
+            Methods::Class(class) => &class.methods,
+            Methods::Struct(definition) => &definition.methods,
+        }
+    }
 }
 
 impl Interpreter {
@@ -34,13 +139,117 @@ impl Interpreter {
             variables: HashMap::new(),
             stream_manager: StreamManager::new(),
             modules: HashMap::new(),
+            frame_pacing: FramePacingConfig::default(),
+            frame_pacing_overrides: HashMap::new(),
+            quality_governor: QualityGovernor::default(),
+            profiling: false,
+            unload_unused_after: None,
+            supervised: SupervisedRun::from_env(),
+            remote_variables: Vec::new(),
+            capabilities: CapabilityPolicy::default(),
+            sandbox: None,
+            taps: Vec::new(),
+            sends: Vec::new(),
+            helpers: HashMap::new(),
+            composition: StreamCompositionEngine::new(),
+            routers: HashMap::new(),
+            frame_delta: DEFAULT_FRAME_DELTA,
+            fluid_step: None,
+            fluid_seconds: 0.0,
+            coroutines: Vec::new(),
+            scheduled: HashSet::new(),
+            constants: HashMap::new(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            classes: HashMap::new(),
+            locals: Vec::new(),
+            script_dir: PathBuf::new(),
+            imports: HashMap::new(),
+            imported_functions: HashMap::new(),
+            module_aliases: HashMap::new(),
+            import_cache: Arc::new(Mutex::new(ImportCache::default())),
         };
         
         interpreter.register_builtin_modules();
+        for plugin in crate::runtime::plugins::registered_plugins() {
+            if let Err(error) = interpreter.register_module(plugin.as_ref()) {
+                tracing::warn!(target: "interpreter", module = plugin.name(), %error, "🧩 plugin not registered");
+            }
+        }
         interpreter
     }
     
+    /// Frame pacing settings from the command line; these win over a script's `config` block
+    pub fn set_frame_pacing_overrides(&mut self, overrides: HashMap<String, Value>) -> crate::Result<()> {
+        self.frame_pacing.apply_value(&Value::Object(overrides.clone()))?;
+        self.frame_pacing_overrides = overrides;
+        Ok(())
+    }
+    
+    fn apply_config(&mut self, config: &Value) -> crate::Result<()> {
+        self.frame_pacing.apply_value(config)?;
+        let overrides = Value::Object(self.frame_pacing_overrides.clone());
+        self.frame_pacing.apply_value(&overrides)?;
+        
+        self.quality_governor.apply_value(config);
+        self.quality_governor.set_target_fps(self.frame_pacing.fps.unwrap_or(60.0));
+        
+        if let Value::Object(fields) = config {
+            if let Some(Value::Array(names)) = fields.get("remote") {
+                self.remote_variables = names.iter()
+                    .filter_map(|name| match name {
+                        Value::String(name) => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+            }
+            if let Some(keys) = fields.get("keys") {
+                crate::hardware::keyboard().lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .apply_config(keys)?;
+            }
+            if let Some(limiter) = fields.get("limiter") {
+                crate::audio::master::master_bus().lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .apply_limiter_config(limiter)?;
+            }
+            match fields.get("unload_unused_after") {
+                Some(Value::Null) => self.unload_unused_after = None,
+                Some(minutes) => {
+                    self.unload_unused_after = minutes.as_number()
+                        .filter(|m| *m > 0.0)
+                        .map(|m| std::time::Duration::from_secs_f64(m * 60.0));
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+    
     pub fn execute(&mut self, program: &Program) -> crate::Result<()> {
+        let result = self.execute_items(program);
+        if let Err(error) = &result {
+            crate::runtime::record_script_error(error);
+        }
+        result
+    }
+    
+    fn execute_items(&mut self, program: &Program) -> crate::Result<()> {
+        // Functions, structs and classes can be used from anywhere in the script, including above their definition
+        for item in &program.items {
+            match item {
+                Item::Function(function) => {
+                    self.functions.insert(function.name.clone(), Arc::new(function.clone()));
+                }
+                Item::Struct(struct_def) => {
+                    self.structs.insert(struct_def.name.clone(), Arc::new(struct_def.clone()));
+                }
+                Item::Class(class_def) => {
+                    self.classes.insert(class_def.name.clone(), Arc::new(class_def.clone()));
+                }
+                _ => {}
+            }
+        }
         for item in &program.items {
             match item {
                 Item::Import(import) => self.execute_import(import)?,
@@ -48,46 +257,599 @@ impl Interpreter {
                     self.execute_statement(stmt)?;
                 }
                 Item::Loop(loop_block) => {
-                    loop {
-                        let mut should_break = false;
-                        for stmt in &loop_block.body {
-                            match self.execute_statement_with_control(stmt)? {
-                                ControlFlow::Break => {
-                                    should_break = true;
-                                    break;
-                                }
-                                ControlFlow::Continue => {
-                                    break; // break inner loop to continue outer loop
+                    // Setup has run by now, so restored state wins over initial values
+                    if let Some(supervised) = self.supervised.as_mut() {
+                        supervised.restore(&mut self.variables);
+                    }
+                    let mut pacer = FramePacer::new(self.frame_pacing.clone());
+                    'frames: loop {
+                        let tick = pacer.begin_frame();
+                        if let Some(sandbox) = self.sandbox.as_mut() {
+                            if !sandbox.begin_frame(tick.frame)? {
+                                break 'frames;
+                            }
+                        }
+                        let _frame = tracing::trace_span!(target: "interpreter", "frame", frame = tick.frame).entered();
+                        if tick.dropped > 0 {
+                            tracing::debug!(target: "interpreter", dropped = tick.dropped, delta_time = tick.delta_time, "dropped frames");
+                        }
+                        self.stream_manager.record_frame(&tick);
+                        let key_actions = crate::hardware::keyboard().lock()
+                            .map(|mut keyboard| keyboard.begin_frame())
+                            .unwrap_or_default();
+                        for action in key_actions {
+                            self.run_key_action(action);
+                        }
+                        if let Ok(mut touch) = crate::hardware::touch().lock() {
+                            touch.begin_frame();
+                        }
+                        self.publish_input_streams();
+                        self.publish_depth_streams();
+                        self.publish_capture_streams();
+                        self.publish_vision_streams();
+                        self.publish_network_streams();
+                        self.publish_attractor_streams(tick.delta_time as f64);
+                        if !self.composition.routers.is_empty() {
+                            if let Err(error) = self.composition.process_composition(&mut self.stream_manager) {
+                                tracing::warn!(target: "interpreter", %error, "🔀 routing failed");
+                            }
+                        }
+                        crate::audio::synth::advance_synths(tick.delta_time as f64);
+                        crate::runtime::record_runtime_metrics(&self.stream_manager.get_performance_metrics(), &tick);
+                        
+                        // Remote changes land between frames so the body sees a consistent state
+                        if !self.remote_variables.is_empty() {
+                            for (name, value) in crate::runtime::take_variable_writes() {
+                                self.variables.insert(name, value);
+                            }
+                        }
+                        for (name, value) in crate::runtime::take_web_writes() {
+                            self.variables.insert(name, value);
+                        }
+                        
+                        // With a fixed update rate the body may run zero or several times per frame
+                        if tick.frame > 0 && tick.updates > 0 {
+                            self.frame_delta = tick.delta_time as f64 / tick.updates as f64;
+                        }
+                        let script_start = std::time::Instant::now();
+                        for _ in 0..tick.updates {
+                            self.resume_coroutines()?;
+                            let mut should_break = false;
+                            for stmt in &loop_block.body {
+                                match self.execute_statement_with_control(stmt)? {
+                                    ControlFlow::Break => {
+                                        should_break = true;
+                                        break;
+                                    }
+                                    ControlFlow::Continue => {
+                                        break; // break inner loop to continue outer loop
+                                    }
+                                    ControlFlow::Return(val) => {
+                                        return Err(anyhow::anyhow!("Return from loop not yet supported: {:?}", val).into());
+                                    }
+                                    ControlFlow::None => {}
                                 }
-                                ControlFlow::Return(val) => {
-                                    return Err(anyhow::anyhow!("Return from loop not yet supported: {:?}", val).into());
+                            }
+                            if should_break {
+                                break 'frames;
+                            }
+                        }
+                        crate::graphics::record_cpu_pass("script", script_start.elapsed());
+                        // After the body, so pushes made this frame already move the fluid
+                        self.publish_fluid_layers(tick.delta_time);
+                        self.sample_taps(tick.elapsed);
+                        self.send_network_streams();
+                        if !self.remote_variables.is_empty() {
+                            crate::runtime::publish_variables(&self.variables, &self.remote_variables);
+                        }
+                        crate::runtime::publish_web_values(&self.variables);
+                        if let Some(supervised) = self.supervised.as_mut() {
+                            supervised.heartbeat(tick.frame);
+                            supervised.maybe_snapshot(&self.variables);
+                        }
+                        if let Some(idle) = self.unload_unused_after {
+                            if tick.frame > 0 && tick.frame.is_multiple_of(RESOURCE_SWEEP_INTERVAL) {
+                                let unloaded = crate::runtime::asset_manager().unload_unused(idle);
+                                if !unloaded.is_empty() {
+                                    tracing::info!(target: "assets", count = unloaded.len(), "🧹 unloaded unused assets");
                                 }
-                                ControlFlow::None => {}
                             }
                         }
-                        if should_break {
-                            break;
+                        if self.profiling && tick.frame > 0 && tick.frame.is_multiple_of(PROFILE_REPORT_INTERVAL) {
+                            print!("{}", crate::graphics::profile_report());
+                        }
+                        
+                        // Pick up `config` changes made inside the loop
+                        if *pacer.config() != self.frame_pacing {
+                            pacer.set_config(self.frame_pacing.clone());
+                        }
+                        let work_time = pacer.end_frame();
+                        
+                        match self.quality_governor.record_frame(work_time) {
+                            Some(QualityChange::Reduced(settings)) => {
+                                tracing::info!(target: "quality", tier = settings.tier.name(), level = settings.level,
+                                    "⚡ frame budget exceeded, lowering quality");
+                            }
+                            Some(QualityChange::Restored(settings)) => {
+                                tracing::info!(target: "quality", tier = settings.tier.name(), level = settings.level,
+                                    "✨ headroom available, raising quality");
+                            }
+                            None => {}
+                        }
+                    }
+                    self.flush_taps();
+                    // A take can outlive the loop, but what's on disk should be playable now
+                    if let Some(session) = crate::audio::recorder::session_recorder().lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .as_mut()
+                    {
+                        if let Err(error) = session.checkpoint() {
+                            tracing::warn!(target: "interpreter", dir = %session.dir.display(), %error, "📼 recording flush failed");
                         }
                     }
                 }
-                Item::Function(_func_def) => {
-                    // TODO: Implement function definition handling
-                    // For now, skip function definitions in the interpreter
-                }
-                Item::Class(_class_def) => {
-                    // TODO: Implement class definition handling  
-                    // For now, skip class definitions in the interpreter
+                Item::Function(_) | Item::Struct(_) | Item::Class(_) => {
+                    // Registered before anything runs
                 }
-                Item::Struct(_struct_def) => {
-                    // TODO: Implement struct definition handling
-                    // For now, skip struct definitions in the interpreter
+                Item::Synth(synth_def) => self.define_synth(synth_def)?,
+            }
+        }
+        Ok(())
+    }
+    
+    /// `import lib/drums` runs `lib/drums.syn` (once, however many files import it) and makes its
+    /// functions and variables available as `drums.kick()` and `drums.tempo`, or under another name
+    /// with `as`; its structs and classes keep their own names. `import mylib` imports the folder
+    /// `mylib` as a package, checked against the versions `synthesis.toml` asks for. `import Audio`
+    /// names a built-in module
+    fn execute_import(&mut self, import: &ImportItem) -> crate::Result<()> {
+        let folder = self.script_dir.join(&import.module);
+        let path = if folder.is_dir() {
+            folder
+        } else {
+            crate::runtime::imports::import_path(&self.script_dir, &import.module)
+        };
+        if !path.exists() {
+            let builtin = self.modules.contains_key(&import.module)
+                || (import.module.starts_with(char::is_uppercase) && !import.module.contains(['/', '\\', '.']));
+            if builtin {
+                return self.import_builtin(import);
+            }
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::FileNotFound,
+                format!("📦 Can't find {} to import", path.display())
+            )
+            .with_suggestion("Imports are relative to the importing file: import lib/drums reads lib/drums.syn next to it"));
+        }
+        self.capabilities.check(&crate::runtime::capabilities::file_capability(&path))?;
+        
+        let module = self.load_module(&path)?;
+        let namespace = import.alias.clone().unwrap_or_else(|| crate::runtime::imports::namespace(&import.module));
+        if path.is_dir() {
+            let package = Manifest::in_dir(&path)?;
+            let name = package.as_ref().and_then(|package| package.name.clone())
+                .unwrap_or_else(|| crate::runtime::imports::namespace(&import.module));
+            if let Some(manifest) = Manifest::nearest(&self.script_dir)? {
+                manifest.check_dependency(&name, package.as_ref())?;
+            }
+        }
+        self.claim_namespace(&namespace, Some(&module), &import.module)?;
+        {
+            let module = module.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            for (name, definition) in &module.structs {
+                self.structs.entry(name.clone()).or_insert_with(|| definition.clone());
+            }
+            for (name, definition) in &module.classes {
+                self.classes.entry(name.clone()).or_insert_with(|| definition.clone());
+            }
+            // Picked-out functions are called by their own name; picked-out variables are copied as they are now
+            for item in import.items.iter().flatten() {
+                if module.functions.contains_key(item) {
+                    self.import_function(item, &namespace)?;
+                } else if let Some(value) = module.variables.get(item) {
+                    self.variables.insert(item.clone(), value.clone());
+                } else {
+                    return Err(crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::UnknownFunction,
+                        format!("📦 {} has nothing called {}", path.display(), item)
+                    )
+                    .with_suggestion(format!("Check the spelling, or import the whole file and use {}.{}", namespace, item)));
                 }
             }
         }
+        self.imports.insert(namespace, module);
+        Ok(())
+    }
+    
+    /// `import Audio.{analyze_fft}` lets `analyze_fft()` stand for `Audio.analyze_fft()`, and
+    /// `import Graphics as G` lets `G.clear()` stand for `Graphics.clear()`
+    fn import_builtin(&mut self, import: &ImportItem) -> crate::Result<()> {
+        if let Some(alias) = &import.alias {
+            self.claim_namespace(alias, None, &import.module)?;
+            self.module_aliases.insert(alias.clone(), import.module.clone());
+        }
+        // Modules this build doesn't have, like ML, leave their names unbound
+        let Some(module) = self.modules.get(&import.module) else {
+            return Ok(());
+        };
+        let (found, missing): (Vec<&String>, Vec<&String>) = import.items.iter().flatten()
+            .partition(|item| module.functions.contains_key(*item));
+        for item in missing {
+            tracing::warn!(target: "interpreter", module = import.module.as_str(), name = item.as_str(), "📦 imported name isn't in this module");
+        }
+        for item in found {
+            self.import_function(item, &import.module)?;
+        }
+        Ok(())
+    }
+    
+    /// Check nothing else is already used as `namespace`: a built-in or plugin module, an
+    /// extension, an alias, or a different import. Importing the same file twice is fine
+    fn claim_namespace(&self, namespace: &str, module: Option<&ImportedModule>, source: &str) -> crate::Result<()> {
+        let taken_by = if self.modules.contains_key(namespace) {
+            Some(format!("the {} module", namespace))
+        } else if wasm_extensions().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(namespace) {
+            Some(format!("the {} extension", namespace))
+        } else if let Some(aliased) = self.module_aliases.get(namespace).filter(|aliased| aliased.as_str() != source) {
+            Some(format!("import {} as {}", aliased, namespace))
+        } else {
+            self.imports.get(namespace)
+                .filter(|imported| module.is_none_or(|module| !Arc::ptr_eq(imported, module)))
+                .map(|_| format!("another import called {}", namespace))
+        };
+        match taken_by {
+            Some(taken_by) => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("📦 import {} would be called {}, but that name is already {}", source, namespace, taken_by)
+            )
+            .with_suggestion(format!("Give it a name of its own: import {} as {}_lib", source, namespace.to_lowercase()))),
+            None => Ok(()),
+        }
+    }
+    
+    /// `import Audio.{analyze_fft}`: call `analyze_fft()` by itself, as long as no other module has
+    /// already put a function by that name here. A `func` of the script's own still comes first
+    fn import_function(&mut self, name: &str, source: &str) -> crate::Result<()> {
+        if let Some(existing) = self.imported_functions.get(name).filter(|existing| existing.as_str() != source) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("📦 {}() is imported from both {} and {}", name, existing, source)
+            )
+            .with_suggestion(format!("Import it by name from one of them, and call the other as {}.{}()", source, name)));
+        }
+        if self.functions.contains_key(name) {
+            tracing::warn!(target: "interpreter", module = source, name, "📦 imported function is hidden by a func of the same name");
+        }
+        self.imported_functions.insert(name.to_string(), source.to_string());
         Ok(())
     }
     
-    fn execute_import(&mut self, _import: &ImportItem) -> crate::Result<()> {
+    /// Run an imported file's top level in an interpreter of its own, or reuse it if it already ran
+    fn load_module(&mut self, path: &Path) -> crate::Result<ImportedModule> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut cache = self.import_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(module) = cache.get(&key) {
+            return Ok(module);
+        }
+        cache.begin(&key)?;
+        drop(cache);
+        
+        let mut module = Interpreter::new();
+        module.import_cache = self.import_cache.clone();
+        module.capabilities = self.capabilities.clone();
+        let loaded = if path.is_dir() {
+            // A package is its files and folders of scripts, each imported under its own name
+            module.script_dir = path.to_path_buf();
+            crate::runtime::imports::package_contents(path).and_then(|contents| {
+                for name in contents {
+                    module.execute_import(&ImportItem { module: name, items: None, alias: None })?;
+                }
+                Ok(Arc::new(Mutex::new(module)))
+            })
+        } else {
+            module.script_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            crate::runtime::imports::parse_file(path).and_then(|mut program| {
+                // A file's `loop` belongs to it being run on its own, not to it being imported
+                program.items.retain(|item| !matches!(item, Item::Loop(_)));
+                module.execute_items(&program)?;
+                Ok(Arc::new(Mutex::new(module)))
+            })
+        };
+        let mut cache = self.import_cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.finish(&key, loaded.as_ref().ok().cloned());
+        loaded
+    }
+    
+    /// `drums.kick(1)`: run a function from an imported file, inside that file's interpreter
+    fn call_imported(&mut self, namespace: &str, name: &str, args: Vec<Value>, named: HashMap<String, Value>) -> crate::Result<Value> {
+        let Some(module) = self.imports.get(namespace).cloned() else {
+            return Err(crate::errors::synthesis_error(crate::errors::ErrorKind::UnknownFunction, format!("📦 Nothing is imported as {}", namespace)));
+        };
+        call_in_module(&module, namespace, name, args, named)
+    }
+    
+    /// `drums.tempo`: a top-level variable of an imported file, as it is now
+    fn read_imported(&self, namespace: &str, name: &str) -> Option<crate::Result<Value>> {
+        let module = self.imports.get(namespace)?;
+        Some(read_in_module(module, namespace, name))
+    }
+    
+    /// `mylib.chords`: a file inside an imported package, found through the package's own imports,
+    /// with the path it was reached by
+    fn nested_import(&self, expression: &Expression) -> Option<(String, ImportedModule)> {
+        match expression {
+            Expression::Identifier(namespace) if self.lookup(namespace).is_none() => {
+                self.imports.get(namespace).map(|module| (namespace.clone(), module.clone()))
+            }
+            Expression::MethodCall { object, method, args, named_args, optional: false } if args.is_empty() && named_args.is_empty() => {
+                let (path, package) = self.nested_import(object)?;
+                let module = package.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).imports.get(method).cloned()?;
+                Some((format!("{}.{}", path, method), module))
+            }
+            _ => None,
+        }
+    }
+    
+    /// HID fields, the pen tablet and GPS fixes become control streams, created the first time they report
+    fn publish_input_streams(&mut self) {
+        let mut values = crate::hardware::hid_input().lock()
+            .map(|mut hid| hid.poll())
+            .unwrap_or_default();
+        values.extend(crate::hardware::tablet().lock().map(|tablet| tablet.poll()).unwrap_or_default());
+        values.extend(crate::hardware::gps().lock().map(|mut gps| gps.poll()).unwrap_or_default());
+        for (name, value) in values {
+            if self.stream_manager.get_stream(&name).is_none() {
+                if let Err(error) = self.stream_manager.create_control_stream(name.clone()) {
+                    tracing::warn!(target: "hardware", stream = name.as_str(), %error, "🕹️ couldn't create input stream");
+                    continue;
+                }
+            }
+            // Readers only want the latest value, so a full buffer drops its oldest sample
+            if let Some(stream) = self.stream_manager.get_stream(&name) {
+                if let Ok(mut data) = stream.try_write() {
+                    if data.buffer.len() >= data.max_buffer_size {
+                        data.buffer.pop_front();
+                    }
+                }
+            }
+            let _ = self.stream_manager.write_to_stream(&name, vec![value as f32]);
+        }
+    }
+    
+    /// Step each running attractor through the frame's time and stream the points it went through
+    fn publish_attractor_streams(&mut self, seconds: f64) {
+        let published: Vec<(String, Vec<[f64; 3]>, Vec<f32>)> = {
+            let mut running = crate::modules::generate::attractors().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let sample = |stream: &str| self.stream_manager.latest_sample(stream).map(f64::from);
+            running.iter_mut()
+                .map(|(name, attractor)| {
+                    let points = attractor.advance(seconds, sample);
+                    let pitches = points.iter().map(|&point| attractor.frequency(point, sample) as f32).collect();
+                    (name.clone(), points, pitches)
+                })
+                .collect()
+        };
+        for (name, points, pitches) in published {
+            if points.is_empty() {
+                continue;
+            }
+            let count = points.len();
+            let data = points.into_iter().flatten().map(|v| v as f32).collect();
+            self.publish_frame(&format!("{}.points", name), crate::runtime::types::DataType::Generic, count, 1, data);
+            
+            let frequency = format!("{}.frequency", name);
+            if self.stream_manager.get_stream(&frequency).is_none() {
+                if let Err(error) = self.stream_manager.create_control_stream(frequency.clone()) {
+                    tracing::warn!(target: "interpreter", stream = frequency.as_str(), %error, "🌀 couldn't create attractor stream");
+                    continue;
+                }
+            }
+            // Listeners want the newest pitches, so a full buffer drops its oldest
+            if let Some(stream) = self.stream_manager.get_stream(&frequency) {
+                if let Ok(mut data) = stream.try_write() {
+                    while !data.buffer.is_empty() && data.buffer.len() + pitches.len() > data.max_buffer_size {
+                        data.buffer.pop_front();
+                    }
+                }
+            }
+            let _ = self.stream_manager.write_to_stream(&frequency, pitches);
+        }
+    }
+    
+    /// The depth camera's newest frame as texture, mask and point cloud streams, each holding one frame
+    fn publish_depth_streams(&mut self) {
+        let frames = {
+            let mut camera = crate::hardware::depth_camera().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let Some(camera) = camera.as_mut() else {
+                return;
+            };
+            match camera.poll() {
+                Ok(true) => camera.stream_frames(),
+                Ok(false) => return,
+                Err(error) => {
+                    tracing::warn!(target: "hardware", %error, "📡 depth camera read failed");
+                    return;
+                }
+            }
+        };
+        for frame in frames {
+            self.publish_frame(&frame.name, frame.data_type, frame.width, frame.height, frame.data);
+        }
+    }
+    
+    /// Ask the solver thread to step the running fluids and publish each one's dye, as interleaved
+    /// RGBA, for the compositor to draw as its layer. A live run publishes the step it asked for on
+    /// an earlier frame and lets the time pile up while the solver is busy; a sandboxed run waits for
+    /// each step, so what it renders doesn't depend on how fast the machine is
+    fn publish_fluid_layers(&mut self, seconds: f32) {
+        use std::sync::mpsc::TryRecvError;
+        
+        self.fluid_seconds += seconds.max(0.0);
+        let mut frames = match self.fluid_step.as_ref().map(|step| step.try_recv()) {
+            Some(Err(TryRecvError::Empty)) => return,
+            Some(Ok(frames)) => frames,
+            Some(Err(TryRecvError::Disconnected)) | None => Vec::new(),
+        };
+        self.fluid_step = None;
+        
+        let (running, levels) = {
+            let fluids = crate::graphics::fluid_layers().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let levels: HashMap<String, f32> = fluids.values()
+                .flat_map(|fluid| fluid.emitters.iter().filter_map(|(_, source)| source.as_ref()))
+                .filter_map(|stream| Some((stream.clone(), self.stream_manager.latest_sample(stream)?)))
+                .collect();
+            (!fluids.is_empty(), levels)
+        };
+        if running && self.fluid_seconds > 0.0 {
+            let step = crate::graphics::step_fluids(std::mem::take(&mut self.fluid_seconds), levels);
+            if self.sandbox.is_some() {
+                frames = step.recv().unwrap_or_default();
+            } else {
+                self.fluid_step = Some(step);
+            }
+        }
+        
+        for frame in frames {
+            let texture = format!("{}.texture", frame.name);
+            let data = frame.dye.iter().flat_map(|color| color.to_array()).collect();
+            if self.publish_frame(&texture, crate::runtime::types::DataType::Visual, frame.width, frame.height, data) {
+                let _ = self.stream_manager.set_metadata(&texture, "channels".to_string(), Value::Integer(4));
+                let _ = self.stream_manager.set_metadata(&texture, "layer".to_string(), Value::Integer(frame.layer));
+            }
+        }
+    }
+    
+    /// Each screen or window capture's newest grab, as interleaved RGB in its own stream
+    fn publish_capture_streams(&mut self) {
+        let textures: Vec<_> = {
+            let captures = crate::hardware::screen_captures().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            captures.values().filter_map(|capture| capture.poll()).collect()
+        };
+        for texture in textures {
+            if self.publish_frame(&texture.name, crate::runtime::types::DataType::Visual, texture.width, texture.height, texture.data) {
+                let _ = self.stream_manager.set_metadata(&texture.name, "channels".to_string(), Value::Integer(3));
+            }
+        }
+    }
+    
+    /// Analyse the newest frame of each stream given to Vision.analyze() and publish the results
+    fn publish_vision_streams(&mut self) {
+        use crate::modules::vision::{analyze, vision_watches, Image};
+        
+        let watched: Vec<_> = {
+            let watching = vision_watches().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            watching.iter().map(|(name, watch)| (name.clone(), watch.source.clone(), watch.settings)).collect()
+        };
+        for (name, source, settings) in watched {
+            let image = self.stream_manager.get_stream(&source)
+                .and_then(|stream| stream.read().ok().and_then(|data| Image::from_stream(&data)));
+            let Some(image) = image else {
+                continue;
+            };
+            let analysis = analyze(&image, &settings);
+            for (suffix, value) in [("brightness", analysis.brightness), ("edges", analysis.edges)] {
+                let stream = format!("{}.{}", name, suffix);
+                if self.stream_manager.get_stream(&stream).is_none() {
+                    if let Err(error) = self.stream_manager.create_control_stream(stream.clone()) {
+                        tracing::warn!(target: "interpreter", stream = stream.as_str(), %error, "👁️ couldn't create vision stream");
+                        continue;
+                    }
+                }
+                // Listeners only want the latest value, so a full buffer drops its oldest
+                if let Some(stream) = self.stream_manager.get_stream(&stream) {
+                    if let Ok(mut data) = stream.try_write() {
+                        if data.buffer.len() >= data.max_buffer_size {
+                            data.buffer.pop_front();
+                        }
+                    }
+                }
+                let _ = self.stream_manager.write_to_stream(&stream, vec![value]);
+            }
+            self.publish_frame(&format!("{}.palette", name), crate::runtime::types::DataType::Generic, 4, analysis.palette.len(), analysis.palette_frame());
+            let columns = analysis.columns();
+            self.publish_frame(&format!("{}.regions", name), crate::runtime::types::DataType::Generic, columns, analysis.regions.len() / columns, analysis.region_frame());
+            
+            let mut watching = vision_watches().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(watch) = watching.get_mut(&name) {
+                watch.latest = Some(analysis);
+            }
+        }
+    }
+    
+    /// Replace a stream's contents with one frame of `width` × `height`; false if the stream couldn't be made
+    fn publish_frame(&mut self, name: &str, data_type: crate::runtime::types::DataType, width: usize, height: usize, data: Vec<f32>) -> bool {
+        let fits = self.stream_manager.get_stream(name)
+            .and_then(|stream| stream.read().ok().map(|buffer| buffer.max_buffer_size >= data.len()));
+        // A bigger frame (a smaller step, a wider capture) needs a bigger buffer
+        if fits != Some(true) {
+            self.stream_manager.destroy_stream(name);
+            let size = data.len().max(1);
+            if let Err(error) = self.stream_manager.create_realtime_stream(name.to_string(), data_type, None, Some(size)) {
+                tracing::warn!(target: "hardware", stream = name, %error, "couldn't create stream");
+                return false;
+            }
+        }
+        if let Some(stream) = self.stream_manager.get_stream(name) {
+            if let Ok(mut buffer) = stream.try_write() {
+                buffer.buffer.clear();
+            }
+        }
+        let _ = self.stream_manager.set_metadata(name, "width".to_string(), Value::Integer(width as i64));
+        let _ = self.stream_manager.set_metadata(name, "height".to_string(), Value::Integer(height as i64));
+        let _ = self.stream_manager.write_to_realtime_stream(name, data);
+        true
+    }
+    
+    /// Global hotkeys; fullscreen is picked up by the renderer from the keyboard state
+    fn run_key_action(&mut self, action: crate::hardware::KeyAction) {
+        match action {
+            crate::hardware::KeyAction::Transport => {
+                let mut transport = crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                transport.toggle();
+                tracing::info!(target: "interpreter", playing = transport.is_playing, "⏯️ transport toggled");
+            }
+            crate::hardware::KeyAction::Panic => {
+                crate::runtime::panic::trigger_panic();
+            }
+            crate::hardware::KeyAction::Undo => {
+                if let Some(change) = crate::runtime::undo::undo_last(None) {
+                    tracing::info!(target: "interpreter", name = change.target.name(), value = %change.before, "↩️ undo");
+                }
+            }
+            crate::hardware::KeyAction::Redo => {
+                if let Some(change) = crate::runtime::undo::redo_last() {
+                    tracing::info!(target: "interpreter", name = change.target.name(), value = %change.after, "↪️ redo");
+                }
+            }
+            crate::hardware::KeyAction::Fullscreen => {
+                tracing::info!(target: "interpreter", "🖥️ fullscreen toggled");
+            }
+        }
+    }
+    
+    /// Compile a `synth` block and make it playable with `Synth.play`
+    fn define_synth(&mut self, synth_def: &SynthDef) -> crate::Result<()> {
+        let mut params = Vec::with_capacity(synth_def.parameters.len());
+        for param in &synth_def.parameters {
+            let default = match &param.default_value {
+                Some(expr) => {
+                    let value = self.evaluate_expression(expr)?;
+                    crate::modules::synth::synth_value(&value).ok_or_else(|| crate::SynthesisError::new(
+                        crate::ErrorKind::TypeMismatch,
+                        format!("🎛️ The default for {} in synth {} should be a number or a note name", param.name, synth_def.name)
+                    )
+                    .with_suggestion("Try: note = \"A3\" or amp = 0.5"))?
+                }
+                None => 0.0,
+            };
+            params.push(crate::audio::synth::SynthParam { name: param.name.clone(), default });
+        }
+        
+        let graph = crate::audio::synth::VoiceGraph::compile(&synth_def.name, params, &synth_def.body)?;
+        crate::audio::synth::synth_engine()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("synth engine lock poisoned"))?
+            .define(graph);
         Ok(())
     }
     
@@ -103,6 +865,12 @@ impl Interpreter {
                 };
                 Ok(ControlFlow::Return(value))
             }
+            Statement::If { .. } | Statement::Match { .. } | Statement::While { .. } | Statement::For { .. } | Statement::Try { .. } => {
+                if let Some(sandbox) = self.sandbox.as_mut() {
+                    sandbox.step(&self.variables)?;
+                }
+                self.execute_branching(stmt)
+            }
             _ => {
                 self.execute_statement(stmt)?;
                 Ok(ControlFlow::None)
@@ -110,111 +878,423 @@ impl Interpreter {
         }
     }
     
-    fn execute_statement(&mut self, stmt: &Statement) -> crate::Result<Value> {
-        match stmt {
-            Statement::Assignment { name, value } => {
-                let val = self.evaluate_expression(value)?;
-                self.variables.insert(name.clone(), val.clone());
-                Ok(val)
+    /// Run statements until one of them breaks, continues or returns
+    fn execute_block(&mut self, body: &[Statement]) -> crate::Result<ControlFlow> {
+        for stmt in body {
+            let flow = self.execute_statement_with_control(stmt)?;
+            if !matches!(flow, ControlFlow::None) {
+                return Ok(flow);
             }
-            Statement::Expression(expr) => self.evaluate_expression(expr),
+        }
+        Ok(ControlFlow::None)
+    }
+    
+    /// `if`, `match`, `while`, `for` and `try`, passing a `return` inside them out to the enclosing function
+    fn execute_branching(&mut self, stmt: &Statement) -> crate::Result<ControlFlow> {
+        match stmt {
             Statement::If { condition, then_branch, else_branch } => {
                 let cond_value = self.evaluate_expression(condition)?;
                 
                 if cond_value.is_truthy() {
-                    for stmt in then_branch {
-                        self.execute_statement(stmt)?;
-                    }
+                    self.execute_block(then_branch)
                 } else if let Some(else_stmts) = else_branch {
-                    for stmt in else_stmts {
-                        self.execute_statement(stmt)?;
-                    }
+                    self.execute_block(else_stmts)
+                } else {
+                    Ok(ControlFlow::None)
                 }
-                
-                Ok(Value::Null)
             }
             Statement::Match { expression, arms } => {
                 let expr_value = self.evaluate_expression(expression)?;
                 
                 for arm in arms {
-                    if self.pattern_matches(&arm.pattern, &expr_value)? {
-                        for stmt in &arm.body {
-                            self.execute_statement(stmt)?;
+                    let mut bindings = Vec::new();
+                    if !self.pattern_matches(&arm.pattern, &expr_value, &mut bindings)? {
+                        continue;
+                    }
+                    for (name, value) in bindings {
+                        self.assign(&name, value);
+                    }
+                    if let Some(guard) = &arm.guard {
+                        if !self.evaluate_expression(guard)?.is_truthy() {
+                            continue;
                         }
-                        break;
                     }
+                    return self.execute_block(&arm.body);
                 }
                 
-                Ok(Value::Null)
-            }
-            Statement::Every { duration, body } => {
-                // For now, just execute once - full temporal logic would need runtime support
-                let _duration_val = self.evaluate_expression(duration)?;
-                for stmt in body {
-                    self.execute_statement(stmt)?;
-                }
-                Ok(Value::Null)
-            }
-            Statement::After { duration, body } => {
-                // For now, just execute once - full temporal logic would need runtime support
-                let _duration_val = self.evaluate_expression(duration)?;
-                for stmt in body {
-                    self.execute_statement(stmt)?;
-                }
-                Ok(Value::Null)
+                Ok(ControlFlow::None)
             }
             Statement::While { condition, body } => {
                 while self.evaluate_expression(condition)?.is_truthy() {
-                    for stmt in body {
-                        self.execute_statement(stmt)?;
+                    match self.execute_block(body)? {
+                        ControlFlow::Break => break,
+                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+                        ControlFlow::Continue | ControlFlow::None => {}
                     }
                 }
-                Ok(Value::Null)
+                Ok(ControlFlow::None)
             }
-            Statement::For { variable: _variable, iterable: _iterable, body: _body } => {
-                // TODO: Implement for-in loops
-                // For now, return null
-                Ok(Value::Null)
+            Statement::For { variable, iterable, body } => {
+                let iterable = self.evaluate_expression(iterable)?;
+                // The loop variable lives for the loop only; whatever it shadowed comes back after
+                let shadowed = self.scope().get(variable).cloned();
+                let flow = match iterable {
+                    Value::Range { start, end, inclusive } => self.iterate(variable, range_values(start, end, inclusive), body),
+                    Value::Array(items) => self.iterate(variable, items.into_iter(), body),
+                    other => Err(crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::TypeMismatch,
+                        format!("🔁 Can't loop over a {}", other.type_name())
+                    )
+                    .with_suggestion(format!("for loops go over ranges and arrays: for i in 0..10 {{ ... }} or for {} in list {{ ... }}", variable))),
+                };
+                match shadowed {
+                    Some(value) => self.scope().insert(variable.clone(), value),
+                    None => self.scope().remove(variable),
+                };
+                flow
             }
-            Statement::Let { name, type_annotation: _type_annotation, value } => {
-                // Variable declaration with optional initialization
-                let val = if let Some(expr) = value {
-                    self.evaluate_expression(expr)?
-                } else {
-                    Value::Null
+            Statement::Try { body, error, handler } => {
+                let caught = match self.execute_block(body) {
+                    Ok(flow) => return Ok(flow),
+                    // Sandbox limits stop the script; catching them would let it run on forever
+                    Err(err) if self.sandbox.is_some() && matches!(
+                        err.kind,
+                        crate::errors::ErrorKind::PerformanceConstraintViolation | crate::errors::ErrorKind::OutOfMemory
+                    ) => return Err(err),
+                    Err(err) => err,
                 };
-                self.variables.insert(name.clone(), val.clone());
-                Ok(val)
+                let Some(error) = error else {
+                    return self.execute_block(handler);
+                };
+                // Like a loop variable, the error is only there inside the catch block
+                let shadowed = self.scope().get(error).cloned();
+                self.assign(error, error_value(&caught));
+                let flow = self.execute_block(handler);
+                match shadowed {
+                    Some(value) => self.scope().insert(error.clone(), value),
+                    None => self.scope().remove(error),
+                };
+                flow
             }
-            Statement::Return(_) | Statement::Break | Statement::Continue => {
-                // These are now handled by execute_statement_with_control
-                // In non-control contexts, they just return null  
-                Ok(Value::Null)
+            _ => {
+                self.execute_statement(stmt)?;
+                Ok(ControlFlow::None)
             }
         }
     }
     
-    fn evaluate_expression(&mut self, expr: &Expression) -> crate::Result<Value> {
-        match expr {
-            Expression::Literal(lit) => Ok(self.evaluate_literal(lit)),
-            Expression::Identifier(name) => {
-                // Check for module constants like Graphics.black
-                if name.contains('.') {
-                    let parts: Vec<&str> = name.split('.').collect();
-                    if parts.len() == 2 {
-                        let module_name = parts[0];
-                        let constant_name = parts[1];
-                        
-                        match (module_name, constant_name) {
-                            ("Graphics", "black") => return Ok(Value::Integer(0x000000)),
-                            ("Graphics", "white") => return Ok(Value::Integer(0xFFFFFF)),
-                            ("Graphics", "neon") => return Ok(Value::String("neon".to_string())),
-                            _ => {}
-                        }
-                    }
-                }
-                
-                Ok(self.variables.get(name)
+    /// A length of time in seconds for `every`, `after` or `wait`; beats and bars follow the tempo
+    fn seconds(&mut self, duration: &Expression, what: &str) -> crate::Result<f64> {
+        let value = self.evaluate_expression(duration)?;
+        let seconds = crate::modules::time::transport().lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .seconds(&value);
+        match seconds {
+            Some(seconds) if seconds >= 0.0 => Ok(seconds),
+            _ => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("⏳ {}() needs a length of time, not {}", what, value)
+            )
+            .with_suggestion(format!("Try: {}(0.5.seconds) or {}(1.beats)", what, what))),
+        }
+    }
+    
+    /// Run a new coroutine until its first `wait()` if it's due now, and keep it if it isn't done
+    fn spawn(&mut self, mut coroutine: Coroutine) -> crate::Result<()> {
+        coroutine.restart();
+        if coroutine.wait <= 0.0 {
+            self.run_coroutine(&mut coroutine)?;
+        }
+        if !coroutine.is_finished() {
+            self.coroutines.push(coroutine);
+        }
+        Ok(())
+    }
+    
+    /// Move every coroutine along by one update; blocks started meanwhile wait for the next one
+    fn resume_coroutines(&mut self) -> crate::Result<()> {
+        if self.coroutines.is_empty() {
+            return Ok(());
+        }
+        let mut coroutines = std::mem::take(&mut self.coroutines);
+        for coroutine in &mut coroutines {
+            coroutine.wait -= self.frame_delta;
+            coroutine.elapsed += self.frame_delta;
+            if coroutine.wait > 0.0 {
+                continue;
+            }
+            if coroutine.frames.is_empty() {
+                coroutine.restart();
+            }
+            self.run_coroutine(coroutine)?;
+        }
+        coroutines.retain(|coroutine| !coroutine.is_finished());
+        coroutines.append(&mut self.coroutines);
+        self.coroutines = coroutines;
+        Ok(())
+    }
+    
+    /// Run a coroutine until it reaches a `wait()` or the end of its body. Statements that can't
+    /// reach a wait run in one go; `if`, `while` and `for` around one are stepped through here
+    fn run_coroutine(&mut self, coroutine: &mut Coroutine) -> crate::Result<()> {
+        loop {
+            let Some(frame) = coroutine.frames.last_mut() else {
+                // The body is done; an `every` starts again a period after it last started
+                if let Some(period) = coroutine.period.clone() {
+                    let period = self.seconds(&period, "every")?;
+                    if period <= 0.0 {
+                        return Err(crate::errors::synthesis_error(
+                            crate::errors::ErrorKind::InvalidExpression,
+                            "⏳ every() needs a time longer than zero"
+                        )
+                        .with_suggestion("Put code that should run every frame straight in the loop"));
+                    }
+                    coroutine.wait = period - coroutine.elapsed;
+                }
+                return Ok(());
+            };
+            let (body, index) = match frame {
+                Frame::Block { body, next } => {
+                    *next += 1;
+                    (body.clone(), *next - 1)
+                }
+                Frame::While { condition, body } => {
+                    let body = body.clone();
+                    if self.evaluate_expression(condition)?.is_truthy() {
+                        coroutine.frames.push(Frame::Block { body, next: 0 });
+                    } else {
+                        coroutine.frames.pop();
+                    }
+                    continue;
+                }
+                Frame::For { variable, items, body } => {
+                    match items.next() {
+                        Some(item) => {
+                            let (variable, body) = (variable.clone(), body.clone());
+                            self.assign(&variable, item);
+                            coroutine.frames.push(Frame::Block { body, next: 0 });
+                        }
+                        None => {
+                            coroutine.frames.pop();
+                        }
+                    }
+                    continue;
+                }
+            };
+            let Some(stmt) = body.get(index) else {
+                coroutine.frames.pop();
+                continue;
+            };
+            
+            if let Some(args) = wait_call(stmt) {
+                let seconds = match args.first() {
+                    Some(duration) => self.seconds(duration, "wait")?,
+                    None => 0.0,
+                };
+                // Time it was overdue by counts toward the wait, but it always lasts until the next update
+                coroutine.wait = coroutine.wait.min(0.0) + seconds;
+                return Ok(());
+            }
+            if contains_wait(stmt) {
+                match stmt {
+                    Statement::If { condition, then_branch, else_branch } => {
+                        let branch = if self.evaluate_expression(condition)?.is_truthy() {
+                            Some(then_branch)
+                        } else {
+                            else_branch.as_ref()
+                        };
+                        if let Some(branch) = branch {
+                            coroutine.frames.push(Frame::Block { body: Arc::new(branch.clone()), next: 0 });
+                        }
+                    }
+                    Statement::While { condition, body } => {
+                        coroutine.frames.push(Frame::While { condition: condition.clone(), body: Arc::new(body.clone()) });
+                    }
+                    Statement::For { variable, iterable, body } => {
+                        let items = match self.evaluate_expression(iterable)? {
+                            Value::Range { start, end, inclusive } => range_values(start, end, inclusive).collect(),
+                            Value::Array(items) => items,
+                            other => return Err(crate::errors::synthesis_error(
+                                crate::errors::ErrorKind::TypeMismatch,
+                                format!("🔁 Can't loop over a {}", other.type_name())
+                            )),
+                        };
+                        self.check_not_constant(variable)?;
+                        coroutine.frames.push(Frame::For {
+                            variable: variable.clone(),
+                            items: items.into_iter(),
+                            body: Arc::new(body.clone()),
+                        });
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            
+            match self.execute_statement_with_control(stmt)? {
+                ControlFlow::None => {}
+                ControlFlow::Break => {
+                    // Out of the innermost loop, or out of the block if it isn't in one
+                    while let Some(frame) = coroutine.frames.pop() {
+                        if matches!(frame, Frame::While { .. } | Frame::For { .. }) {
+                            break;
+                        }
+                    }
+                }
+                ControlFlow::Continue => {
+                    while matches!(coroutine.frames.last(), Some(Frame::Block { .. })) {
+                        coroutine.frames.pop();
+                    }
+                }
+                ControlFlow::Return(_) => coroutine.frames.clear(),
+            }
+        }
+    }
+    
+    fn iterate(&mut self, variable: &str, items: impl Iterator<Item = Value>, body: &[Statement]) -> crate::Result<ControlFlow> {
+        self.check_not_constant(variable)?;
+        for item in items {
+            self.assign(variable, item);
+            match self.execute_block(body)? {
+                ControlFlow::Break => break,
+                ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+                ControlFlow::Continue | ControlFlow::None => {}
+            }
+        }
+        Ok(ControlFlow::None)
+    }
+    
+    fn execute_statement(&mut self, stmt: &Statement) -> crate::Result<Value> {
+        if let Some(sandbox) = self.sandbox.as_mut() {
+            sandbox.step(&self.variables)?;
+        }
+        match stmt {
+            Statement::Assignment { name, value } => {
+                self.check_reassignable(name)?;
+                let val = self.evaluate_expression(value)?;
+                if name == "config" {
+                    self.apply_config(&val)?;
+                }
+                self.reassign(name, val.clone());
+                Ok(val)
+            }
+            Statement::FieldAssignment { target, path, value } => {
+                self.check_reassignable(target)?;
+                let val = self.evaluate_expression(value)?;
+                let mut current = self.lookup(target).cloned().ok_or_else(|| crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::UnknownFunction,
+                    format!("Undefined variable: {}", target)
+                ))?;
+                set_field(&mut current, path, val.clone())?;
+                self.reassign(target, current);
+                Ok(val)
+            }
+            Statement::Expression(expr) => self.evaluate_expression(expr),
+            Statement::If { .. } | Statement::Match { .. } | Statement::While { .. } | Statement::For { .. } | Statement::Try { .. } => {
+                self.execute_branching(stmt)?;
+                Ok(Value::Null)
+            }
+            Statement::Every { duration, body } => {
+                // Reached every frame inside a loop, but one coroutine keeps the rhythm
+                if self.scheduled.insert(stmt as *const Statement as usize) {
+                    self.spawn(Coroutine::new(body.clone(), 0.0, Some(duration.clone())))?;
+                }
+                Ok(Value::Null)
+            }
+            Statement::After { duration, body } => {
+                if self.scheduled.insert(stmt as *const Statement as usize) {
+                    let delay = self.seconds(duration, "after")?;
+                    self.spawn(Coroutine::new(body.clone(), delay, None))?;
+                }
+                Ok(Value::Null)
+            }
+            Statement::Async { body } => {
+                self.spawn(Coroutine::new(body.clone(), 0.0, None))?;
+                Ok(Value::Null)
+            }
+            Statement::Let { name, type_annotation: _type_annotation, value } => {
+                self.check_not_constant(name)?;
+                // Variable declaration with optional initialization
+                let val = if let Some(expr) = value {
+                    self.evaluate_expression(expr)?
+                } else {
+                    Value::Null
+                };
+                self.assign(name, val.clone());
+                Ok(val)
+            }
+            Statement::Const { name, value, span } => {
+                // A declaration inside a loop runs every frame, so only another one is a mistake
+                if self.scope_constants().get(name).is_some_and(|declared| declared != span) {
+                    self.check_not_constant(name)?;
+                }
+                let val = self.evaluate_expression(value)?;
+                self.scope_constants().insert(name.clone(), *span);
+                self.assign(name, val.clone());
+                Ok(val)
+            }
+            Statement::LetDestructure { pattern, value } => {
+                let names: Vec<&String> = match pattern {
+                    Destructure::Array(names) => names.iter().flatten().collect(),
+                    Destructure::Object(bindings) => bindings.iter().map(|(_, name)| name).collect(),
+                };
+                for name in names {
+                    self.check_not_constant(name)?;
+                }
+                let val = self.evaluate_expression(value)?;
+                match (pattern, &val) {
+                    (Destructure::Array(names), Value::Array(items)) => {
+                        for (index, name) in names.iter().enumerate() {
+                            if let Some(name) = name {
+                                self.assign(name, items.get(index).cloned().unwrap_or(Value::Null));
+                            }
+                        }
+                    }
+                    (Destructure::Object(bindings), Value::Object(fields) | Value::Struct { fields, .. }) => {
+                        for (field, name) in bindings {
+                            self.assign(name, fields.get(field).cloned().unwrap_or(Value::Null));
+                        }
+                    }
+                    (Destructure::Array(_), other) => return Err(crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::TypeMismatch,
+                        format!("📦 let [...] takes apart a list, not a {}", other.type_name())
+                    )
+                    .with_suggestion("Use let {...} to take fields out of an object")),
+                    (Destructure::Object(_), other) => return Err(crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::TypeMismatch,
+                        format!("📦 let {{...}} takes fields out of an object, not a {}", other.type_name())
+                    )
+                    .with_suggestion("Use let [...] to take apart a list")),
+                }
+                Ok(val)
+            }
+            Statement::Return(_) | Statement::Break | Statement::Continue => {
+                // These are now handled by execute_statement_with_control
+                // In non-control contexts, they just return null  
+                Ok(Value::Null)
+            }
+            Statement::Capabilities(requests) => {
+                self.capabilities.declare(requests)?;
+                Ok(Value::Null)
+            }
+        }
+    }
+    
+    fn evaluate_expression(&mut self, expr: &Expression) -> crate::Result<Value> {
+        if let Some(sandbox) = self.sandbox.as_mut() {
+            sandbox.step(&self.variables)?;
+        }
+        match expr {
+            Expression::Literal(lit) => Ok(self.evaluate_literal(lit)),
+            Expression::Identifier(name) => {
+                // Check for module constants like Graphics.black
+                if let Some((module_name, constant_name)) = name.split_once('.') {
+                    if let Some(value) = module_constant(module_name, constant_name) {
+                        return Ok(value);
+                    }
+                }
+                
+                Ok(self.lookup(name)
                     .cloned()
                     .or_else(|| Some(self.stream_manager.get_stream_value(name)))
                     .ok_or_else(|| crate::errors::synthesis_error(crate::errors::ErrorKind::UnknownFunction, format!("Undefined variable: {}", name)))?)
@@ -224,9 +1304,24 @@ impl Interpreter {
             }
             Expression::BinaryOp { left, op, right } => {
                 let left_val = self.evaluate_expression(left)?;
+                // The default is only worked out when it's needed
+                if *op == BinaryOperator::NullCoalesce && !matches!(left_val, Value::Null) {
+                    return Ok(left_val);
+                }
                 let right_val = self.evaluate_expression(right)?;
+                if let Some(result) = self.overloaded_op(&left_val, op, &right_val)? {
+                    return Ok(result);
+                }
+                if let Some(result) = crate::runtime::vectors::binary_op(&left_val, op, &right_val)
+                    .or_else(|| crate::runtime::complex::binary_op(&left_val, op, &right_val)) {
+                    return result;
+                }
                 self.evaluate_binary_op(&left_val, op, &right_val)
             }
+            Expression::UnaryOp { op, operand } => {
+                let value = self.evaluate_expression(operand)?;
+                self.evaluate_unary_op(op, &value)
+            }
             Expression::Block { fields } => {
                 let mut object = HashMap::new();
                 for (key, value_expr) in fields {
@@ -247,9 +1342,9 @@ impl Interpreter {
                         } else {
                             Err(crate::SynthesisError::new(
                                 crate::ErrorKind::InvalidExpression,
-                                &format!("🗺️ List index {} is out of range (list has {} items)", idx, arr.len())
+                                format!("🗺️ List index {} is out of range (list has {} items)", idx, arr.len())
                             )
-                            .with_suggestion(&format!("Try an index between 0 and {} (lists start at 0)", arr.len().saturating_sub(1)))
+                            .with_suggestion(format!("Try an index between 0 and {} (lists start at 0)", arr.len().saturating_sub(1)))
                             .with_suggestion("Check your list size with list.length() first"))
                         }
                     }
@@ -269,7 +1364,7 @@ impl Interpreter {
                 match (&left_val, &right_val) {
                     (Value::Stream(stream), _) => {
                         // Apply processing to stream
-                        println!("Piping stream '{}' through operation", stream.name);
+                        tracing::debug!(target: "streams", stream = %stream.name, "piping stream through operation");
                         Ok(right_val)
                     }
                     _ => Ok(right_val),
@@ -282,12 +1377,10 @@ impl Interpreter {
                 // Create bidirectional connection between streams
                 match (&left_val, &right_val) {
                     (Value::Stream(left_stream), Value::Stream(right_stream)) => {
-                        println!("Creating bidirectional connection: '{}' <> '{}'", 
-                                left_stream.name, right_stream.name);
-                        
                         // Connect both directions - this needs to be moved to a helper method
                         // since we can't mutably borrow stream_manager in evaluate_expression
-                        println!("Would connect: {} <-> {}", left_stream.name, right_stream.name);
+                        tracing::debug!(target: "streams", left = %left_stream.name, right = %right_stream.name,
+                            "bidirectional connection requested");
                         
                         Ok(left_val)
                     }
@@ -304,12 +1397,11 @@ impl Interpreter {
                 
                 match stream_val {
                     Value::Stream(stream) => {
-                        println!("Branching stream '{}' into {} outputs", stream.name, count);
-                        
                         // Create branch streams - placeholder for now
+                        let _span = tracing::debug_span!(target: "streams", "branch", stream = %stream.name, count).entered();
                         for i in 0..*count {
                             let branch_name = format!("{}_branch_{}", stream.name, i + 1);
-                            println!("Would create branch stream: {}", branch_name);
+                            tracing::debug!(target: "streams", branch = %branch_name, "branch stream requested");
                         }
                         
                         Ok(Value::Stream(stream))
@@ -334,7 +1426,7 @@ impl Interpreter {
                 }
                 
                 if !stream_names.is_empty() {
-                    println!("Merging {} streams into '{}'", stream_names.len(), output_name);
+                    tracing::debug!(target: "streams", inputs = ?stream_names, output = %output_name, "merging streams");
                     // Placeholder - actual merge would happen at execution level
                     
                     Ok(Value::Stream(crate::runtime::types::Stream {
@@ -366,25 +1458,90 @@ impl Interpreter {
                     _ => Err(anyhow::anyhow!("Unit values must be numeric, got {:?}", val.type_name()).into()),
                 }
             }
-            Expression::ArrayLiteral(elements) => {
-                let mut values = Vec::new();
-                for element in elements {
-                    values.push(self.evaluate_expression(element)?);
-                }
-                Ok(Value::Array(values))
-            }
-            Expression::Range { start, end, inclusive: _ } => {
-                let start_val = self.evaluate_expression(start)?;
-                let end_val = self.evaluate_expression(end)?;
-                // For now, create a simple range representation
-                Ok(Value::String(format!("{}..{}", start_val, end_val)))
+            Expression::ArrayLiteral(elements) => Ok(Value::Array(self.evaluate_spread(elements)?)),
+            Expression::Spread(_) => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                "📋 ... only works inside [ ] or a call's arguments"
+            )
+            .with_suggestion("Try: all = [...bass, ...lead] or Graphics.rect(...params)")),
+            Expression::Range { start, end, inclusive } => {
+                let start = self.range_bound(start)?;
+                let end = self.range_bound(end)?;
+                Ok(Value::Range { start, end, inclusive: *inclusive })
             }
             Expression::Lambda { parameters: _, body: _ } => {
                 // TODO: Implement lambda expressions
                 Ok(Value::String("<lambda>".to_string()))
             }
-            Expression::MethodCall { object, method, args, named_args: _ } => {
+            Expression::MethodCall { object, method, args, named_args, optional } => {
+                if let Expression::Identifier(namespace) = object.as_ref() {
+                    if args.is_empty() && named_args.is_empty() && self.lookup(namespace).is_none() {
+                        if let Some(value) = self.read_imported(namespace, method) {
+                            return value;
+                        }
+                        // `Graphics.black` parses as a call without parentheses
+                        if let Some(value) = module_constant(namespace, method) {
+                            return Ok(value);
+                        }
+                    }
+                }
+                // `mylib.chords.make("Cmaj")` and `mylib.chords.tempo` reach into an imported package
+                if let Some((namespace, module)) = matches!(object.as_ref(), Expression::MethodCall { .. }).then(|| self.nested_import(object)).flatten() {
+                    let has_function = module.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).functions.contains_key(method);
+                    if args.is_empty() && named_args.is_empty() && !has_function {
+                        return read_in_module(&module, &namespace, method);
+                    }
+                    let arg_values = self.evaluate_spread(args)?;
+                    let mut named = HashMap::new();
+                    for (param, expr) in named_args {
+                        named.insert(param.clone(), self.evaluate_expression(expr)?);
+                    }
+                    return call_in_module(&module, &namespace, method, arg_values, named);
+                }
                 let obj_val = self.evaluate_expression(object)?;
+                if *optional && matches!(obj_val, Value::Null) {
+                    return Ok(Value::Null);
+                }
+                if let Some(chain) = crate::graphics::HydraChain::from_value(&obj_val) {
+                    let arg_values = self.evaluate_spread(args)?;
+                    let named = self.evaluate_named(named_args)?;
+                    return crate::modules::graphics::hydra_method(chain, method, &arg_values, &named);
+                }
+                if let Value::Events(events) = &obj_val {
+                    let arg_values = self.evaluate_spread(args)?;
+                    let named = self.evaluate_named(named_args)?;
+                    return crate::modules::events::events_method(events, method, &arg_values, &named);
+                }
+                if let Some((class, index)) = self.class_method(&obj_val, method)? {
+                    let arg_values = self.evaluate_spread(args)?;
+                    let mut named = HashMap::new();
+                    for (name, expr) in named_args {
+                        named.insert(name.clone(), self.evaluate_expression(expr)?);
+                    }
+                    let (result, instance) = self.call_method(obj_val, &class.methods()[index], arg_values, named)?;
+                    // `game.player.jump()` changes the player inside `game`
+                    if let Some((root, path)) = field_path(object) {
+                        if let Some(mut value) = self.lookup(&root).cloned() {
+                            set_field(&mut value, &path, instance)?;
+                            self.reassign(&root, value);
+                        }
+                    }
+                    return Ok(result);
+                }
+                if args.is_empty() && named_args.is_empty() {
+                    match &obj_val {
+                        Value::Struct { name, fields } => {
+                            return fields.get(method).cloned().ok_or_else(|| unknown_field(name, method, fields.keys()));
+                        }
+                        Value::Object(fields) if fields.contains_key(method) => return Ok(fields[method].clone()),
+                        _ => {}
+                    }
+                }
+                if STATISTIC_METHODS.contains(&method.as_str()) || GESTURE_METHODS.contains(&method.as_str()) {
+                    let arg_values = self.evaluate_spread(args)?;
+                    let named = self.evaluate_named(named_args)?;
+                    return self.call_value_helper(named_args as *const _ as usize, method, obj_val, arg_values, &named);
+                }
                 // For now, handle basic method calls
                 match method.as_str() {
                     "map" | "push" | "length" => {
@@ -394,6 +1551,7 @@ impl Interpreter {
                     _ => Ok(Value::Null)
                 }
             }
+            Expression::StructLiteral { name, fields } => self.construct_struct(name, fields),
             Expression::InterpolatedString(parts) => {
                 let mut result = String::new();
                 for part in parts {
@@ -428,11 +1586,43 @@ impl Interpreter {
         }
     }
     
+    /// List elements or call arguments, with each `...list` replaced by the list's items
+    fn evaluate_spread(&mut self, expressions: &[Expression]) -> crate::Result<Vec<Value>> {
+        let mut values = Vec::new();
+        for expr in expressions {
+            let Expression::Spread(inner) = expr else {
+                values.push(self.evaluate_expression(expr)?);
+                continue;
+            };
+            match self.evaluate_expression(inner)? {
+                Value::Array(items) => values.extend(items),
+                Value::Range { start, end, inclusive } => values.extend(range_values(start, end, inclusive)),
+                Value::Null => {}
+                other => return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("📋 Can't spread a {}; ... takes the items out of a list", other.type_name())
+                )
+                .with_suggestion("Wrap a single value in a list instead: [...notes, 60]")),
+            }
+        }
+        Ok(values)
+    }
+    
+    /// A call's named arguments, by name
+    fn evaluate_named(&mut self, named_args: &HashMap<String, Expression>) -> crate::Result<HashMap<String, Value>> {
+        let mut named = HashMap::new();
+        for (param, expr) in named_args {
+            named.insert(param.clone(), self.evaluate_expression(expr)?);
+        }
+        Ok(named)
+    }
+    
     fn evaluate_literal(&self, lit: &Literal) -> Value {
         match lit {
-            Literal::Integer(n) => Value::Integer(*n),
-            Literal::Float(f) => Value::Float(*f),
+            Literal::Integer(n) | Literal::BasedInteger(n, _) => Value::Integer(*n),
+            Literal::Float(f) | Literal::Percentage(f) => Value::Float(*f),
             Literal::String(s) => Value::String(s.clone()),
+            Literal::Color(rgba) => Value::Color(*rgba),
             Literal::Boolean(b) => Value::Boolean(*b),
         }
     }
@@ -442,64 +1632,975 @@ impl Interpreter {
         module: Option<&String>,
         name: &str,
         args: &[Expression],
-        _named_args: &std::collections::HashMap<String, Expression>,
+        named_args: &std::collections::HashMap<String, Expression>,
     ) -> crate::Result<Value> {
-        let arg_values: Result<Vec<_>, _> = args.iter()
-            .map(|arg| self.evaluate_expression(arg))
-            .collect();
-        let arg_values = arg_values?;
+        let mut arg_values = self.evaluate_spread(args)?;
+        
+        // `G.clear()` after `import Graphics as G`, or `analyze_fft()` after `import Audio.{analyze_fft}`
+        let resolved = match module {
+            Some(m) if !self.modules.contains_key(m) && self.lookup(m).is_none() => self.module_aliases.get(m).cloned(),
+            None if !self.functions.contains_key(name) => self.imported_functions.get(name).cloned(),
+            _ => None,
+        };
+        let module = resolved.as_ref().or(module);
+        
+        // `drums.kick()` runs in the file it was imported from, unless a variable has taken the name
+        let imported = module
+            .filter(|m| !self.modules.contains_key(m.as_str()) && self.lookup(m).is_none() && self.imports.contains_key(m.as_str()))
+            .cloned();
+        if let Some(namespace) = imported {
+            let mut named = HashMap::new();
+            for (param, expr) in named_args {
+                named.insert(param.clone(), self.evaluate_expression(expr)?);
+            }
+            return self.call_imported(&namespace, name, arg_values, named);
+        }
+        
+        // `seq.next()` on a class instance runs the method with `self` bound, then keeps the changes
+        let instance = module
+            .filter(|m| !self.modules.contains_key(m.as_str()))
+            .and_then(|m| self.lookup(m))
+            .filter(|value| matches!(value, Value::Struct { .. }))
+            .cloned();
+        if let Some(instance) = instance {
+            if let Some((class, index)) = self.class_method(&instance, name)? {
+                let mut named = HashMap::new();
+                for (param, expr) in named_args {
+                    named.insert(param.clone(), self.evaluate_expression(expr)?);
+                }
+                let (result, instance) = self.call_method(instance, &class.methods()[index], arg_values, named)?;
+                if let Some(receiver) = module {
+                    self.assign(receiver, instance);
+                }
+                return Ok(result);
+            }
+        }
+        
+        // `level.tap(...)` on a variable, stream or synth rather than a module
+        let receiver = module
+            .filter(|m| !self.modules.contains_key(m.as_str()))
+            .filter(|m| self.lookup(m).is_some() || self.stream_manager.get_stream(m).is_some()
+                || crate::audio::synth::synth_engine().lock().map(|engine| engine.graph(m).is_some()).unwrap_or(false));
+        
+        let named = self.evaluate_named(named_args)?;
+        
+        if let Some(receiver) = receiver {
+            if STATISTIC_METHODS.contains(&name) || GESTURE_METHODS.contains(&name) {
+                let input = self.lookup(receiver).cloned().unwrap_or_else(|| self.tap_value(receiver));
+                // The named arguments live as long as the program too, and exist even when there are no positional ones
+                return self.call_value_helper(named_args as *const _ as usize, name, input, arg_values, &named);
+            }
+            return self.call_value_method(receiver, name, &arg_values, &named);
+        }
+        
+        // `Math.map(level, ...)` on a stream works on its newest sample, and so do extensions
+        let extension = module.filter(|m| !self.modules.contains_key(m.as_str()))
+            .filter(|m| wasm_extensions().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(m));
+        if module.map(String::as_str) == Some("Math") || extension.is_some() {
+            for value in arg_values.iter_mut() {
+                if let Value::Stream(stream) = value {
+                    *value = self.stream_manager.latest_sample(&stream.name)
+                        .map(|s| Value::Float(s as f64))
+                        .unwrap_or(Value::Null);
+                }
+            }
+        }
+        // `Graphics.circle(position, 20)` takes a Vec2 wherever an x and y go
+        if module.map(String::as_str) == Some("Graphics") {
+            arg_values = crate::runtime::vectors::spread_vectors(arg_values);
+        }
+        
+        // Extensions run in their own sandbox, so there are no capabilities to check
+        if let Some(extension) = extension {
+            crate::modules::check_named(&named, &format!("{}.{}", extension, name), &[])?;
+            let result = wasm_extensions().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).call(extension, name, &arg_values)?;
+            if let Some(sandbox) = self.sandbox.as_mut() {
+                sandbox.record_call(extension, name, &arg_values, &named, &result)?;
+            }
+            return Ok(result);
+        }
         
         if let Some(module_name) = module {
             if let Some(module) = self.modules.get(module_name) {
                 if let Some(function) = module.functions.get(name) {
-                    return (function.callback)(&arg_values);
+                    self.capabilities.check_call(module_name, name, &arg_values, &named)?;
+                    let result = (function.callback)(&arg_values, &named)?;
+                    if let Some(sandbox) = self.sandbox.as_mut() {
+                        sandbox.record_call(module_name, name, &arg_values, &named, &result)?;
+                    }
+                    return Ok(result);
                 }
             }
             return Err(crate::SynthesisError::new(
                 crate::ErrorKind::UnknownFunction,
-                &format!("🎹 {}.{}() function doesn't exist", module_name, name)
+                format!("🎹 {}.{}() function doesn't exist", module_name, name)
             )
-            .with_suggestion(&format!("Check available functions in {} module", module_name))
+            .with_suggestion(format!("Check available functions in {} module", module_name))
             .with_suggestion("Try using autocomplete or check the documentation"));
         }
         
+        if let Some(function) = self.functions.get(name).cloned() {
+            return self.call_user_function(&function, arg_values, named);
+        }
+        
+        if let Some(class) = self.classes.get(name).cloned() {
+            return self.instantiate(&class, arg_values, named);
+        }
+        
+        if matches!(name, "smooth" | "spring" | "history") {
+            // The argument list lives as long as the program, so its address identifies the call site
+            return self.call_helper(args.as_ptr() as usize, name, &arg_values, &named);
+        }
+        
+        if matches!(name, "switch" | "gate" | "crossbar") {
+            crate::modules::check_named(&named, name, &[])?;
+            return self.call_router(args.as_ptr() as usize, name, &arg_values);
+        }
+        
+        if name == "wait" {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                "⏳ wait() only pauses an every, after or async block, on a line of its own"
+            )
+            .with_suggestion("Try: async { flash = 1\n    wait(0.1)\n    flash = 0 }")
+            .with_docs("https://synthesis-lang.org/docs/time"));
+        }
+        
         Err(crate::SynthesisError::new(
             crate::ErrorKind::UnknownFunction,
-            &format!("🎹 {}() function doesn't exist", name)
+            format!("🎹 {}() function doesn't exist", name)
         )
         .with_suggestion("Check if you need a module prefix like Math.{} or Audio.{}")
         .with_suggestion("Try using autocomplete or check the documentation"))
     }
     
-    fn evaluate_binary_op(
-        &self,
-        left: &Value,
-        op: &BinaryOperator,
-        right: &Value,
-    ) -> crate::Result<Value> {
-        match op {
-            BinaryOperator::Add => match (left, right) {
-                (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
-                (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f64 + b)),
-                (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a + *b as f64)),
-                (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
-                (Value::UnitValue(a), Value::UnitValue(b)) => {
-                    if let Some(result) = a.add(b) {
-                        Ok(Value::UnitValue(result))
-                    } else {
-                        Err(anyhow::anyhow!("Cannot add incompatible units: {} and {}", a.unit.to_string(), b.unit.to_string()).into())
-                    }
-                }
-                _ => Err(anyhow::anyhow!("Cannot add {:?} and {:?}", left.type_name(), right.type_name()).into()),
+    /// A variable as seen from the current scope: the innermost call's locals, then globals
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        self.locals.last()
+            .and_then(|scope| scope.variables.get(name))
+            .or_else(|| self.variables.get(name))
+    }
+    
+    /// The variables assignments go to right now: the current call's locals, or the globals
+    fn scope(&mut self) -> &mut HashMap<String, Value> {
+        match self.locals.last_mut() {
+            Some(scope) => &mut scope.variables,
+            None => &mut self.variables,
+        }
+    }
+    
+    /// The constants declared in the scope assignments go to right now
+    fn scope_constants(&mut self) -> &mut HashMap<String, Span> {
+        match self.locals.last_mut() {
+            Some(scope) => &mut scope.constants,
+            None => &mut self.constants,
+        }
+    }
+    
+    fn range_bound(&mut self, expr: &Expression) -> crate::Result<i64> {
+        match self.evaluate_expression(expr)? {
+            Value::Integer(n) => Ok(n),
+            Value::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Ok(f as i64),
+            other => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🔁 Ranges count in whole numbers, not {}", other)
+            )
+            .with_suggestion("Try: 0..10, or 0..count for a computed end")),
+        }
+    }
+    
+    /// Setting a name declared with `const` again, by assignment, `let` or a loop, is an error
+    fn check_not_constant(&self, name: &str) -> crate::Result<()> {
+        let constants = self.locals.last().map_or(&self.constants, |scope| &scope.constants);
+        if !constants.contains_key(name) {
+            return Ok(());
+        }
+        Err(constant_error(name))
+    }
+    
+    /// Like `check_not_constant`, for `name = ...`, which can reach a global from inside a `func`
+    fn check_reassignable(&self, name: &str) -> crate::Result<()> {
+        if self.assigns_global(name) && self.constants.contains_key(name) {
+            return Err(constant_error(name));
+        }
+        self.check_not_constant(name)
+    }
+    
+    /// Whether `name = ...` inside a `func` changes a global: there is one, and the call has no
+    /// local of that name
+    fn assigns_global(&self, name: &str) -> bool {
+        self.locals.last().is_some_and(|scope| !scope.variables.contains_key(name)) && self.variables.contains_key(name)
+    }
+    
+    /// `let`, parameters and loop variables inside a `func` stay local to that call; at the top
+    /// level they're global
+    fn assign(&mut self, name: &str, value: Value) {
+        self.scope().insert(name.to_string(), value);
+    }
+    
+    /// `name = ...` changes the call's local of that name, or else the global if there is one;
+    /// otherwise it makes a new local
+    fn reassign(&mut self, name: &str, value: Value) {
+        if self.assigns_global(name) {
+            self.variables.insert(name.to_string(), value);
+        } else {
+            self.assign(name, value);
+        }
+    }
+    
+    /// Build a `struct` instance: every field needs a value, given here or by the definition's default
+    fn construct_struct(&mut self, name: &str, values: &HashMap<String, Expression>) -> crate::Result<Value> {
+        let definition = match self.structs.get(name).cloned()
+            .or_else(|| crate::runtime::vectors::builtin_struct(name))
+            .or_else(|| crate::runtime::complex::builtin_struct(name)) {
+            Some(definition) => definition,
+            None if self.classes.contains_key(name) => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🧱 {} is a class, so it's created by calling it", name)
+            )
+            .with_suggestion(format!("Try: {}()", name))),
+            None => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownFunction,
+                format!("🧱 There's no struct called {}", name)
+            )
+            .with_suggestion(format!("Define it first, like: struct {} {{ x = 0, y = 0 }}", name))),
+        };
+        let names = || definition.fields.iter().map(|field| &field.name);
+        if let Some(unknown) = values.keys().find(|key| !names().any(|name| name == *key)) {
+            return Err(unknown_field(name, unknown, names()));
+        }
+        
+        let mut fields = HashMap::new();
+        for field in &definition.fields {
+            let value = match (values.get(&field.name), &field.default_value) {
+                (Some(expr), _) | (None, Some(expr)) => self.evaluate_expression(expr)?,
+                (None, None) => return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🧱 {} is missing {}", name, field.name)
+                )
+                .with_suggestion(format!("Give it a value: {} {{ {}: ... }}, or a default in the struct", name, field.name))),
+            };
+            fields.insert(field.name.clone(), value);
+        }
+        Ok(Value::Struct { name: name.to_string(), fields })
+    }
+    
+    /// `Sequencer(steps: 8)`: fields start at their defaults, or null, then `init` runs if the class has one;
+    /// without it, named arguments set fields directly
+    fn instantiate(&mut self, class: &ClassDef, args: Vec<Value>, named: HashMap<String, Value>) -> crate::Result<Value> {
+        let mut fields = HashMap::new();
+        for field in &class.fields {
+            let value = match &field.default_value {
+                Some(default) => self.evaluate_expression(default)?,
+                None => Value::Null,
+            };
+            fields.insert(field.name.clone(), value);
+        }
+        if let Some(init) = class.methods.iter().find(|method| method.name == "init") {
+            let instance = Value::Struct { name: class.name.clone(), fields };
+            return self.call_method(instance, init, args, named).map(|(_, instance)| instance);
+        }
+        if !args.is_empty() {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🧱 {} has no init(), so its fields are set by name", class.name)
+            )
+            .with_suggestion(format!("Try: {}({}: ...), or add a func init(...) to the class",
+                class.name, class.fields.first().map_or("field", |field| field.name.as_str()))));
+        }
+        for (field, value) in named {
+            if !fields.contains_key(&field) {
+                return Err(unknown_field(&class.name, &field, fields.keys()));
+            }
+            fields.insert(field, value);
+        }
+        Ok(Value::Struct { name: class.name.clone(), fields })
+    }
+    
+    /// The type of `value` and the index of its method `name`, if `value` is a class instance or a
+    /// struct with that method; an error if a class lacks it. Methods stay inside the shared definition
+    /// so helper state keyed by call site survives across calls
+    fn class_method(&self, value: &Value, name: &str) -> crate::Result<Option<(Methods, usize)>> {
+        let class = match value {
+            Value::Struct { name: class, .. } => match (self.classes.get(class), self.structs.get(class)) {
+                (Some(class), _) => class,
+                // A struct's fields are all it has besides its methods, so anything else is a field read
+                (None, Some(definition)) => return Ok(definition.methods.iter()
+                    .position(|method| method.name == name)
+                    .map(|index| (Methods::Struct(definition.clone()), index))),
+                _ => return Ok(None),
             },
-            BinaryOperator::Subtract => match (left, right) {
-                (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a - b)),
-                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
-                (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f64 - b)),
-                (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a - *b as f64)),
-                (Value::UnitValue(a), Value::UnitValue(b)) => {
-                    if let Some(result) = a.subtract(b) {
+            _ => return Ok(None),
+        };
+        match class.methods.iter().position(|method| method.name == name) {
+            Some(index) => Ok(Some((Methods::Class(class.clone()), index))),
+            // Fields are read with the same dot, so those fall through to field access
+            None if class.fields.iter().any(|field| field.name == name) => Ok(None),
+            None => {
+                let mut methods: Vec<&str> = class.methods.iter().map(|method| method.name.as_str()).collect();
+                methods.sort_unstable();
+                Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::UnknownFunction,
+                    format!("🧱 {} has no method called {}", class.name, name)
+                )
+                .with_suggestion(if methods.is_empty() {
+                    format!("Add one to the class: func {}() {{ ... }}", name)
+                } else {
+                    format!("Its methods are: {}", methods.join(", "))
+                }))
+            }
+        }
+    }
+    
+    /// `a + b` where `a` is a struct or class instance with an `add` method, and likewise `subtract`,
+    /// `multiply` and `divide`; `equals` answers `==` and `!=`, and `compare` the orderings with a number
+    /// below, at or above zero. With a plain value on the left, `+`, `*`, comparisons and equality
+    /// use the right side's method instead, as if the operands were the other way round
+    fn overloaded_op(&mut self, left: &Value, op: &BinaryOperator, right: &Value) -> crate::Result<Option<Value>> {
+        let method = match op {
+            BinaryOperator::Add => "add",
+            BinaryOperator::Subtract => "subtract",
+            BinaryOperator::Multiply => "multiply",
+            BinaryOperator::Divide => "divide",
+            BinaryOperator::Equal | BinaryOperator::NotEqual => "equals",
+            BinaryOperator::LessThan | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan | BinaryOperator::GreaterThanOrEqual => "compare",
+            _ => return Ok(None),
+        };
+        let operator = |value: &Value| match value {
+            Value::Struct { .. } => self.class_method(value, method).ok().flatten(),
+            _ => None,
+        };
+        let swappable = !matches!(op, BinaryOperator::Subtract | BinaryOperator::Divide);
+        let (receiver, argument, swapped, (owner, index)) = match (operator(left), operator(right)) {
+            (Some(found), _) => (left, right, false, found),
+            (None, Some(found)) if swappable => (right, left, true, found),
+            _ => return Ok(None),
+        };
+        let (result, _) = self.call_method(receiver.clone(), &owner.methods()[index], vec![argument.clone()], HashMap::new())?;
+        
+        let type_name = match receiver {
+            Value::Struct { name, .. } => name.as_str(),
+            _ => "",
+        };
+        Ok(Some(match op {
+            BinaryOperator::Equal => Value::Boolean(result.is_truthy()),
+            BinaryOperator::NotEqual => Value::Boolean(!result.is_truthy()),
+            BinaryOperator::LessThan | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan | BinaryOperator::GreaterThanOrEqual => {
+                let order = result.as_number().ok_or_else(|| crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🧱 {}.compare() returned {}, not a number", type_name, result)
+                )
+                .with_suggestion("Return below zero for less, zero for equal and above zero for greater, like: return self.size - other.size"))?;
+                let order = if swapped { -order } else { order };
+                Value::Boolean(match op {
+                    BinaryOperator::LessThan => order < 0.0,
+                    BinaryOperator::LessThanOrEqual => order <= 0.0,
+                    BinaryOperator::GreaterThan => order > 0.0,
+                    _ => order >= 0.0,
+                })
+            }
+            _ => result,
+        }))
+    }
+    
+    /// `!x` is whether `x` is falsy; `-x` negates numbers, units, vectors and complex numbers, and calls `negate()` on structs and classes that have one
+    fn evaluate_unary_op(&mut self, op: &UnaryOperator, value: &Value) -> crate::Result<Value> {
+        match (op, value) {
+            (UnaryOperator::Not, _) => Ok(Value::Boolean(!value.is_truthy())),
+            (UnaryOperator::Negate, Value::Integer(n)) => Ok(Value::Integer(n.wrapping_neg())),
+            (UnaryOperator::Negate, Value::Float(n)) => Ok(Value::Float(-n)),
+            (UnaryOperator::Negate, Value::UnitValue(unit)) => Ok(Value::UnitValue(unit.multiply(-1.0))),
+            (UnaryOperator::Negate, Value::Struct { name, .. }) => match self.class_method(value, "negate")? {
+                Some((owner, index)) => self.call_method(value.clone(), &owner.methods()[index], Vec::new(), HashMap::new()).map(|(result, _)| result),
+                None => match (crate::runtime::vectors::Linear::from_value(value), crate::runtime::complex::from_value(value)) {
+                    (Some(v), _) => Ok(v.map(|x| -x).to_value()),
+                    (_, Some(z)) => Ok(crate::runtime::complex::to_value(-z)),
+                    _ => Err(crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::TypeMismatch,
+                        format!("🧱 {} can't be made negative", name)
+                    )
+                    .with_suggestion(format!("Give {} a method: func negate() {{ ... }}", name))),
+                },
+            },
+            (UnaryOperator::Negate, _) => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🔢 Only numbers can be made negative, not {}", value.type_name())
+            )
+            .with_suggestion("Did you mean to subtract? Try: a - b")),
+        }
+    }
+    
+    /// Run a method with `self` bound to `instance`; returns its result and `self` as the method left it
+    fn call_method(&mut self, instance: Value, method: &FunctionDef, args: Vec<Value>, named: HashMap<String, Value>) -> crate::Result<(Value, Value)> {
+        let mut scope = HashMap::new();
+        scope.insert("self".to_string(), instance);
+        let (result, mut scope) = self.call_function_in(method, args, named, scope)?;
+        Ok((result, scope.remove("self").unwrap_or(Value::Null)))
+    }
+    
+    /// Call a script `func`: positional arguments first, then named ones, then defaults
+    fn call_user_function(&mut self, function: &FunctionDef, args: Vec<Value>, named: HashMap<String, Value>) -> crate::Result<Value> {
+        self.call_function_in(function, args, named, HashMap::new()).map(|(result, _)| result)
+    }
+    
+    /// `call_user_function` starting from `scope` rather than an empty one, handing the scope back afterwards
+    fn call_function_in(&mut self, function: &FunctionDef, mut args: Vec<Value>, mut named: HashMap<String, Value>, mut scope: HashMap<String, Value>) -> crate::Result<(Value, HashMap<String, Value>)> {
+        let usage = format!(
+            "{}({})",
+            function.name,
+            function.parameters.iter()
+                .map(|p| p.name.clone())
+                .chain(function.rest.iter().map(|rest| format!("...{}", rest)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if self.locals.len() >= MAX_CALL_DEPTH {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::PerformanceConstraintViolation,
+                format!("🧩 {}() called itself more than {} levels deep", function.name, MAX_CALL_DEPTH)
+            )
+            .with_suggestion("Make sure the recursion has a case that returns without calling again"));
+        }
+        if args.len() > function.parameters.len() && function.rest.is_none() {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🧩 {}() takes {} arguments but got {}", function.name, function.parameters.len(), args.len())
+            )
+            .with_suggestion(format!("Call it as {}", usage)));
+        }
+        if let Some(unknown) = named.keys().find(|name| !function.parameters.iter().any(|p| &p.name == *name)) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("🧩 {}() has no parameter called {}", function.name, unknown)
+            )
+            .with_suggestion(format!("Call it as {}", usage)));
+        }
+        
+        let extra = args.split_off(function.parameters.len().min(args.len()));
+        if let Some(rest) = &function.rest {
+            scope.insert(rest.clone(), Value::Array(extra));
+        }
+        for (param, value) in function.parameters.iter().zip(args) {
+            if named.contains_key(&param.name) {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🧩 {} was given to {}() twice, by position and by name", param.name, function.name)
+                )
+                .with_suggestion(format!("Call it as {}", usage)));
+            }
+            scope.insert(param.name.clone(), value);
+        }
+        self.locals.push(Scope { variables: scope, constants: HashMap::new() });
+        // Spawned threads only get 2 MB, so deep recursion moves onto the heap rather than overflowing
+        let result = stacker::maybe_grow(CALL_STACK_RED_ZONE, CALL_STACK_SEGMENT, || {
+            self.run_function_body(function, &mut named, &usage)
+        });
+        let scope = self.locals.pop().unwrap_or_default();
+        result.map(|result| (result, scope.variables))
+    }
+    
+    fn run_function_body(&mut self, function: &FunctionDef, named: &mut HashMap<String, Value>, usage: &str) -> crate::Result<Value> {
+        // Defaults are evaluated inside the call, so they can use earlier parameters
+        for param in &function.parameters {
+            if self.locals.last().is_some_and(|scope| scope.variables.contains_key(&param.name)) {
+                continue;
+            }
+            let value = match (named.remove(&param.name), &param.default_value) {
+                (Some(value), _) => value,
+                (None, Some(default)) => self.evaluate_expression(default)?,
+                (None, None) => return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🧩 {}() is missing {}", function.name, param.name)
+                )
+                .with_suggestion(format!("Call it as {}", usage))),
+            };
+            self.assign(&param.name, value);
+        }
+        
+        // Without a `return`, a trailing expression is the result
+        for (index, stmt) in function.body.iter().enumerate() {
+            if let (true, Statement::Expression(expr)) = (index + 1 == function.body.len(), stmt) {
+                return self.evaluate_expression(expr);
+            }
+            match self.execute_statement_with_control(stmt)? {
+                ControlFlow::Return(value) => return Ok(value),
+                ControlFlow::Break | ControlFlow::Continue => break,
+                ControlFlow::None => {}
+            }
+        }
+        Ok(Value::Null)
+    }
+    
+    /// `level.rolling_mean(1.seconds)` or `pad.gesture()` on a value or stream; null until a stream
+    /// has a sample, except for `swipe()` where null means nothing is touching
+    fn call_value_helper(&mut self, site: usize, name: &str, input: Value, args: Vec<Value>, named: &HashMap<String, Value>) -> crate::Result<Value> {
+        let input = match input {
+            Value::Stream(stream) => self.stream_manager.latest_sample(&stream.name)
+                .map(|s| Value::Float(s as f64))
+                .unwrap_or(Value::Null),
+            other => other,
+        };
+        if matches!(input, Value::Null) && name != "swipe" {
+            return Ok(Value::Null);
+        }
+        let mut helper_args = vec![input];
+        helper_args.extend(args);
+        self.call_helper(site, name, &helper_args, named)
+    }
+    
+    fn call_value_method(&mut self, receiver: &str, name: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+        if let Some(Value::Events(events)) = self.lookup(receiver) {
+            return crate::modules::events::events_method(events, name, args, named);
+        }
+        let chain = self.lookup(receiver).and_then(crate::graphics::HydraChain::from_value);
+        match (name, chain) {
+            ("tap", _) => self.tap(receiver, args, named),
+            ("send_to", _) => self.send_to(receiver, args, named),
+            ("cue", _) => self.cue(receiver, args, named),
+            (_, Some(chain)) => crate::modules::graphics::hydra_method(chain, name, args, named),
+            _ => Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownFunction,
+                format!("🎹 {}.{}() isn't something a value can do", receiver, name)
+            )
+            .with_suggestion(format!("Try: {}.tap(\"{}.csv\", rate: 100.hz) to log it", receiver, receiver))),
+        }
+    }
+    
+    /// `pad.cue(true)`: pre-listen a synth on the cue bus, held out of the main mix until `pad.cue(false)`
+    fn cue(&mut self, source: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+        crate::modules::check_named(named, &format!("{}.cue", source), &["main"])?;
+        // A variable holding a synth's name cues that synth
+        let synth = match self.lookup(source) {
+            Some(Value::String(name)) => name.clone(),
+            _ => source.to_string(),
+        };
+        let on = args.first().map(|v| v.is_truthy()).unwrap_or(true);
+        let keep_in_main = named.get("main").map(|v| v.is_truthy()).unwrap_or(false);
+        let mode = on.then_some(if keep_in_main { crate::audio::cue::CueMode::Listen } else { crate::audio::cue::CueMode::Preview });
+        crate::audio::cue::cue_bus().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).set(&synth, mode);
+        let routed = crate::audio::aggregate::aggregate_device().lock().map(|aggregate| aggregate.cue_output.is_some()).unwrap_or(false);
+        if on && !routed {
+            tracing::warn!(target: "audio", synth = %synth, "🎧 cueing with no cue output: pick one with Audio.cue_output(\"Headphones\")");
+        }
+        Ok(Value::Boolean(on))
+    }
+    
+    /// Start (or retarget) a CSV log of a variable or stream; returns the value so it can sit inline
+    fn tap(&mut self, source: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+        crate::modules::check_named(named, &format!("{}.tap", source), &["rate"])?;
+        let path = match args.first() {
+            Some(Value::String(path)) => path.clone(),
+            _ => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("📝 {}.tap() needs a file name", source)
+            )
+            .with_suggestion(format!("Try: {}.tap(\"{}.csv\", rate: 100.hz)", source, source))),
+        };
+        let rate = named.get("rate").and_then(|v| v.as_number()).unwrap_or(DEFAULT_TAP_RATE);
+        self.capabilities.check_call("Stream", "tap", &args[..1], &HashMap::new())?;
+        
+        // Taps are usually written inside the loop, so only the first call opens the file
+        let existing = self.taps.iter().position(|tap| tap.source == source && tap.path == std::path::Path::new(&path));
+        match existing {
+            Some(index) => self.taps[index].rate = rate,
+            None => self.taps.push(StreamTap::open(source, &path, rate)?),
+        }
+        Ok(self.tap_value(source))
+    }
+    
+    /// Current value of a tapped name, reading streams without draining them
+    fn tap_value(&self, source: &str) -> Value {
+        match self.variables.get(source) {
+            Some(Value::Stream(stream)) => self.stream_manager.latest_sample(&stream.name)
+                .map(|s| Value::Float(s as f64))
+                .unwrap_or(Value::Null),
+            Some(value) => value.clone(),
+            None => self.stream_manager.latest_sample(source)
+                .map(|s| Value::Float(s as f64))
+                .unwrap_or(Value::Null),
+        }
+    }
+    
+    fn sample_taps(&mut self, time: f64) {
+        if self.taps.is_empty() {
+            return;
+        }
+        let values: Vec<Value> = self.taps.iter().map(|tap| self.tap_value(&tap.source)).collect();
+        for (tap, value) in self.taps.iter_mut().zip(values) {
+            if let Err(error) = tap.sample(time, &value) {
+                tracing::warn!(target: "interpreter", path = %tap.path.display(), %error, "📝 tap write failed");
+            }
+        }
+    }
+    
+    /// Start sending a variable or stream to another machine; returns the value so it can sit inline
+    fn send_to(&mut self, source: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+        crate::modules::check_named(named, &format!("{}.send_to", source), &["name", "codec", "bitrate", "fec", "loss"])?;
+        let address = match args.first() {
+            Some(Value::String(address)) => address.clone(),
+            _ => return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::TypeMismatch,
+                format!("📡 {}.send_to() needs an address", source)
+            )
+            .with_suggestion(format!("Try: {}.send_to(\"192.168.1.20:7000\")", source))),
+        };
+        let stream = match named.get("name") {
+            Some(Value::String(name)) => name.clone(),
+            _ => source.to_string(),
+        };
+        let opus = crate::modules::network::opus_settings(named)?;
+        self.capabilities.check_call("Stream", "send_to", &args[..1], &HashMap::new())?;
+        
+        // Like taps, sends are usually written inside the loop, so only the first call opens a socket
+        if !self.sends.iter().any(|send| send.source == source && send.address == address) {
+            let send = NetworkSend::open(source, &stream, &address)?;
+            self.sends.push(match opus {
+                Some(settings) => send.with_opus(settings),
+                None => send,
+            });
+        }
+        Ok(self.tap_value(source))
+    }
+    
+    /// This frame's samples for a send: everything an audio stream has buffered, the newest
+    /// sample of any other stream, and the numbers in a variable
+    fn send_samples(&self, source: &str) -> (Vec<f32>, crate::runtime::types::DataType, Option<f32>) {
+        use crate::runtime::types::DataType;
+        let stream = match self.variables.get(source) {
+            Some(Value::Stream(stream)) => Some(stream.clone()),
+            Some(value) => {
+                let samples = helper_numbers(value).map(|(numbers, _)| numbers).unwrap_or_default();
+                return (samples.into_iter().map(|n| n as f32).collect(), DataType::Control, None);
+            }
+            None => self.stream_manager.get_stream(source).map(|_| crate::runtime::types::Stream {
+                name: source.to_string(),
+                data_type: DataType::Control,
+                sample_rate: None,
+            }),
+        };
+        let Some(stream) = stream else {
+            return (Vec::new(), DataType::Control, None);
+        };
+        let samples = match stream.data_type {
+            DataType::Audio => {
+                let buffered = self.stream_manager.get_stream(&stream.name)
+                    .and_then(|data| data.read().ok().map(|data| data.buffer.len()))
+                    .unwrap_or(0);
+                if buffered == 0 {
+                    return (Vec::new(), stream.data_type, stream.sample_rate);
+                }
+                self.stream_manager.read_from_stream(&stream.name, buffered).unwrap_or_default()
+            }
+            _ => self.stream_manager.latest_sample(&stream.name).into_iter().collect(),
+        };
+        (samples, stream.data_type, stream.sample_rate)
+    }
+    
+    fn send_network_streams(&mut self) {
+        if self.sends.is_empty() {
+            return;
+        }
+        let frames: Vec<_> = self.sends.iter().map(|send| self.send_samples(&send.source)).collect();
+        for (send, (samples, kind, sample_rate)) in self.sends.iter_mut().zip(frames) {
+            if samples.is_empty() {
+                continue;
+            }
+            if let Err(error) = send.send(&samples, &kind, sample_rate) {
+                tracing::warn!(target: "network", address = %send.address, %error, "📡 stream send failed");
+            }
+        }
+    }
+    
+    /// Packets from `Stream.receive()` ports that have waited out their jitter buffer, written
+    /// to streams of the kind they were sent as
+    fn publish_network_streams(&mut self) {
+        let packets = network_receivers().lock()
+            .map(|mut receivers| receivers.poll())
+            .unwrap_or_default();
+        for (names, packet) in packets {
+            for name in names {
+                if self.stream_manager.get_stream(&name).is_none() {
+                    if let Err(error) = self.stream_manager.create_stream(name.clone(), packet.kind.clone(), packet.sample_rate) {
+                        tracing::warn!(target: "network", stream = name.as_str(), %error, "📡 couldn't create received stream");
+                        continue;
+                    }
+                }
+                // Newer samples matter more than ones nobody has read, so a full buffer drops its oldest
+                if let Some(stream) = self.stream_manager.get_stream(&name) {
+                    if let Ok(mut data) = stream.try_write() {
+                        while !data.buffer.is_empty() && data.buffer.len() + packet.samples().len() > data.max_buffer_size {
+                            data.buffer.pop_front();
+                        }
+                    }
+                }
+                let _ = self.stream_manager.write_to_stream(&name, packet.samples().to_vec());
+            }
+        }
+    }
+    
+    fn flush_taps(&mut self) {
+        for tap in &mut self.taps {
+            if let Err(error) = tap.flush() {
+                tracing::warn!(target: "interpreter", path = %tap.path.display(), %error, "📝 tap flush failed");
+            }
+        }
+    }
+    
+    /// `switch(selector, [a, b, c])`, `gate(x, open)` and `crossbar(inputs, outputs, matrix)`.
+    /// Given streams, each call site drives a router in the composition engine and returns its
+    /// output streams; given plain values, it routes them straight away
+    fn call_router(&mut self, site: usize, name: &str, args: &[Value]) -> crate::Result<Value> {
+        let usage = match name {
+            "switch" => "switch(selector, [a, b, c])",
+            "gate" => "gate(signal, open)",
+            _ => "crossbar([a, b], [\"left\", \"right\"], [[1, 0], [0.5, 0.5]])",
+        };
+        let invalid = |message: String| crate::errors::synthesis_error(crate::errors::ErrorKind::InvalidExpression, message)
+            .with_suggestion(format!("Try: {}", usage));
+        let list = |value: Option<&Value>, what: &str| match value {
+            Some(Value::Array(items)) => Ok(items.clone()),
+            Some(other) => Err(invalid(format!("🔀 {}() needs a list of {}, not {}", name, what, other.type_name()))),
+            None => Err(invalid(format!("🔀 {}() needs a list of {}", name, what))),
+        };
+        let streams = |items: &[Value]| -> Option<Vec<crate::runtime::types::Stream>> {
+            items.iter().map(|item| match item {
+                Value::Stream(stream) => Some(stream.clone()),
+                _ => None,
+            }).collect()
+        };
+        
+        match name {
+            "switch" => {
+                let options = list(args.get(1), "inputs")?;
+                // Booleans pick the first or second input; past the end picks the last
+                let selected = match args.first() {
+                    Some(Value::Boolean(on)) => *on as usize,
+                    Some(selector) => selector.as_number().ok_or_else(|| invalid(
+                        format!("🔀 switch() needs a number to select with, not {}", selector.type_name())))?.max(0.0) as usize,
+                    None => return Err(invalid("🔀 switch() needs a selector and a list of inputs".to_string())),
+                };
+                let Some(last) = options.len().checked_sub(1) else {
+                    return Ok(Value::Null);
+                };
+                let selected = selected.min(last);
+                match streams(&options) {
+                    Some(inputs) => {
+                        let router = Router::Switch { inputs: inputs.iter().map(|s| s.name.clone()).collect(), selected };
+                        self.drive_router(site, name, router, &inputs[0]).map(|mut outputs| outputs.remove(0))
+                    }
+                    None => Ok(options[selected].clone()),
+                }
+            }
+            "gate" => {
+                let (Some(input), Some(condition)) = (args.first(), args.get(1)) else {
+                    return Err(invalid("🔀 gate() needs a signal and a condition".to_string()));
+                };
+                let open = condition.is_truthy();
+                match input {
+                    Value::Stream(stream) => {
+                        let router = Router::Gate { input: stream.name.clone(), open };
+                        self.drive_router(site, name, router, stream).map(|mut outputs| outputs.remove(0))
+                    }
+                    other if open => Ok(other.clone()),
+                    _ => Ok(Value::Null),
+                }
+            }
+            _ => {
+                let inputs = list(args.first(), "inputs")?;
+                let outputs = list(args.get(1), "outputs")?;
+                let matrix = list(args.get(2), "gain rows, one per output")?.iter()
+                    .map(|row| match row {
+                        Value::Array(gains) => gains.iter().map(|gain| gain.as_number()).collect::<Option<Vec<f64>>>(),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .filter(|rows| rows.len() == outputs.len() && rows.iter().all(|row| row.len() == inputs.len()))
+                    .ok_or_else(|| invalid(format!(
+                        "🔀 crossbar() needs {} rows of {} gains, one row per output and one gain per input", outputs.len(), inputs.len()
+                    )))?;
+                match streams(&inputs) {
+                    Some(input_streams) if !input_streams.is_empty() => {
+                        // Outputs are named by strings, or by streams to write into
+                        let output_names = outputs.iter().map(|output| match output {
+                            Value::String(name) => Ok(name.clone()),
+                            Value::Stream(stream) => Ok(stream.name.clone()),
+                            other => Err(invalid(format!("🔀 crossbar() outputs are stream names, not {}", other.type_name()))),
+                        }).collect::<crate::Result<Vec<_>>>()?;
+                        let router = Router::Crossbar {
+                            inputs: input_streams.iter().map(|s| s.name.clone()).collect(),
+                            outputs: output_names,
+                            matrix: matrix.iter().map(|row| row.iter().map(|gain| *gain as f32).collect()).collect(),
+                        };
+                        self.drive_router(site, name, router, &input_streams[0]).map(Value::Array)
+                    }
+                    _ => {
+                        let values = inputs.iter().map(|input| input.as_number().ok_or_else(|| invalid(
+                            format!("🔀 crossbar() mixes numbers or streams, not {}", input.type_name())))).collect::<crate::Result<Vec<_>>>()?;
+                        Ok(Value::Array(matrix.iter()
+                            .map(|gains| Value::Float(gains.iter().zip(&values).map(|(gain, value)| gain * value).sum()))
+                            .collect()))
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Point this call site's router at `router`, creating its output streams like `like`,
+    /// and return them
+    fn drive_router(&mut self, site: usize, kind: &str, router: Router, like: &crate::runtime::types::Stream) -> crate::Result<Vec<Value>> {
+        let count = self.routers.len();
+        let name = self.routers.entry(site).or_insert_with(|| format!("{}.{}", kind, count + 1)).clone();
+        let outputs = router.outputs(&name);
+        for output in &outputs {
+            if self.stream_manager.get_stream(output).is_none() {
+                self.stream_manager.create_stream(output.clone(), like.data_type.clone(), like.sample_rate)?;
+            }
+        }
+        self.composition.set_router(name, router)?;
+        Ok(outputs.into_iter().map(|output| Value::Stream(crate::runtime::types::Stream {
+            name: output,
+            data_type: like.data_type.clone(),
+            sample_rate: like.sample_rate,
+        })).collect())
+    }
+    
+    /// `smooth(x, 0.2)`, `spring(target, 0.3)` and `history(x, 5)`, and the statistics and gesture
+    /// methods like `level.rolling_mean(1.seconds)` with the value they're called on first
+    fn call_helper(&mut self, site: usize, name: &str, args: &[Value], named: &HashMap<String, Value>) -> crate::Result<Value> {
+        let accepted: &[&str] = match name {
+            "percentile" => &["window"],
+            "gesture" | "shake" => &["threshold"],
+            "swipe" => &["distance"],
+            _ => &[],
+        };
+        crate::modules::check_named(named, name, accepted)?;
+        let Some(input) = args.first().cloned() else {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🌊 {}() needs a value to follow", name)
+            )
+            .with_suggestion(format!("Try: {}(fft[0], 0.2)", name)));
+        };
+        let numbers = |input: &Value| helper_numbers(input).ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🌊 {}() needs a number or a list of numbers, not {}", name, input.type_name())
+        )
+        .with_suggestion(format!("Try: {}(fft[0], 0.2)", name)));
+        let amount = args.get(1).and_then(|v| v.as_number());
+        let dt = self.frame_delta;
+        let statistic = match name {
+            "rolling_variance" => Statistic::Variance,
+            "rolling_stddev" => Statistic::StdDev,
+            "rolling_min" => Statistic::Min,
+            "rolling_max" => Statistic::Max,
+            "percentile" => match amount {
+                Some(percent) if (0.0..=100.0).contains(&percent) => Statistic::Percentile(percent),
+                _ => return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::InvalidExpression,
+                    "🌊 percentile() needs a percentage from 0 to 100"
+                )
+                .with_suggestion("Try: level.percentile(95, window: 2.seconds)")),
+            },
+            _ => Statistic::Mean,
+        };
+        let option = |key: &str| named.get(key).and_then(|v| v.as_number());
+        // `percentile(95)` has its window as an option, the rolling statistics as the argument
+        let window = match name {
+            "percentile" => option("window"),
+            _ => amount,
+        };
+        
+        let state = self.helpers.entry(site).or_insert_with(|| match name {
+            "smooth" => HelperState::Smooth(Smoother::default()),
+            "spring" => HelperState::Spring(Spring::default()),
+            "history" => HelperState::History(History::default()),
+            "peak_hold" => HelperState::PeakHold(PeakHold::default()),
+            "gesture" => HelperState::Tap(TapDetector::default()),
+            "shake" => HelperState::Shake(ShakeDetector::default()),
+            "swipe" => HelperState::Swipe(SwipeDetector::default()),
+            _ => HelperState::Rolling(RollingWindow::default()),
+        });
+        match state {
+            HelperState::Smooth(smoother) => {
+                let (values, is_list) = numbers(&input)?;
+                Ok(helper_value(smoother.step(&values, amount.unwrap_or(0.1), dt), is_list))
+            }
+            HelperState::Spring(spring) => {
+                let (values, is_list) = numbers(&input)?;
+                Ok(helper_value(spring.step(&values, amount.unwrap_or(0.3), dt), is_list))
+            }
+            HelperState::History(history) => Ok(history.step(input, amount.unwrap_or(1.0).max(0.0) as usize)),
+            HelperState::Rolling(rolling) => {
+                let (values, is_list) = numbers(&input)?;
+                Ok(helper_value(rolling.step(&values, window.unwrap_or(DEFAULT_WINDOW), dt, statistic), is_list))
+            }
+            HelperState::PeakHold(peak) => {
+                let (values, is_list) = numbers(&input)?;
+                Ok(helper_value(peak.step(&values, amount.unwrap_or(0.5), dt), is_list))
+            }
+            HelperState::Tap(taps) => {
+                let value = match &input {
+                    Value::Boolean(pressed) => if *pressed { 1.0 } else { 0.0 },
+                    other => numbers(other)?.0.first().copied().unwrap_or(0.0),
+                };
+                let threshold = amount.or_else(|| option("threshold")).unwrap_or(0.5);
+                Ok(taps.step(value, threshold, dt).map(|gesture| Value::String(gesture.name().to_string())).unwrap_or(Value::Null))
+            }
+            HelperState::Shake(shake) => {
+                let reading = gestures::reading(&input).ok_or_else(|| crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("👋 shake() needs an accelerometer reading, not {}", input.type_name())
+                )
+                .with_suggestion("Try: accel.shake(1.5) with a Vec3, { x, y, z } or [x, y, z]"))?;
+                let threshold = amount.or_else(|| option("threshold")).unwrap_or(1.0);
+                Ok(Value::Boolean(shake.step(&reading, threshold, dt)))
+            }
+            HelperState::Swipe(swipe) => {
+                let position = match &input {
+                    Value::Null => None,
+                    other => Some(gestures::position(other).ok_or_else(|| crate::errors::synthesis_error(
+                        crate::errors::ErrorKind::TypeMismatch,
+                        format!("👋 swipe() needs a position with x and y, or null when nothing touches, not {}", other.type_name())
+                    )
+                    .with_suggestion("Try: finger = Touch.active()[0] ?? null, then finger.swipe()"))?),
+                };
+                let distance = amount.or_else(|| option("distance")).unwrap_or(0.1);
+                Ok(swipe.step(position, distance, dt).map(|swipe| Value::String(swipe.name().to_string())).unwrap_or(Value::Null))
+            }
+        }
+    }
+    
+    fn evaluate_binary_op(
+        &self,
+        left: &Value,
+        op: &BinaryOperator,
+        right: &Value,
+    ) -> crate::Result<Value> {
+        match op {
+            BinaryOperator::Add => match (left, right) {
+                (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
+                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
+                (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f64 + b)),
+                (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a + *b as f64)),
+                (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+                (Value::UnitValue(a), Value::UnitValue(b)) => {
+                    if let Some(result) = a.add(b) {
+                        Ok(Value::UnitValue(result))
+                    } else {
+                        Err(anyhow::anyhow!("Cannot add incompatible units: {} and {}", a.unit.to_string(), b.unit.to_string()).into())
+                    }
+                }
+                _ => Err(anyhow::anyhow!("Cannot add {:?} and {:?}", left.type_name(), right.type_name()).into()),
+            },
+            BinaryOperator::Subtract => match (left, right) {
+                (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a - b)),
+                (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
+                (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f64 - b)),
+                (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a - *b as f64)),
+                (Value::UnitValue(a), Value::UnitValue(b)) => {
+                    if let Some(result) = a.subtract(b) {
                         Ok(Value::UnitValue(result))
                     } else {
                         Err(anyhow::anyhow!("Cannot subtract incompatible units: {} and {}", a.unit.to_string(), b.unit.to_string()).into())
@@ -563,6 +2664,14 @@ impl Interpreter {
                         Err(anyhow::anyhow!("Division by zero").into())
                     }
                 },
+                // 1/8.note reads the way musicians say it: one eighth of a note, not an inverse note
+                (Value::Integer(_) | Value::Float(_), Value::UnitValue(b)) if b.unit.is_tempo_relative() => {
+                    if b.value == 0.0 {
+                        return Err(anyhow::anyhow!("Division by zero").into());
+                    }
+                    let a = left.as_number().unwrap_or(0.0);
+                    Ok(Value::UnitValue(crate::runtime::units::UnitValue::new(a / b.value, b.unit.clone())))
+                },
                 _ => Err(anyhow::anyhow!("Cannot divide {:?} and {:?}", left.type_name(), right.type_name()).into()),
             },
             BinaryOperator::Equal => Ok(Value::Boolean(self.values_equal(left, right))),
@@ -617,6 +2726,10 @@ impl Interpreter {
                     Ok(right.clone())
                 }
             },
+            BinaryOperator::NullCoalesce => match left {
+                Value::Null => Ok(right.clone()),
+                _ => Ok(left.clone()),
+            },
         }
     }
     
@@ -626,25 +2739,24 @@ impl Interpreter {
             (Value::Float(a), Value::Float(b)) => (a - b).abs() < f64::EPSILON,
             (Value::String(a), Value::String(b)) => a == b,
             (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Color(a), Value::Color(b)) => a == b,
             (Value::Null, Value::Null) => true,
             (Value::Integer(a), Value::Float(b)) => (*a as f64 - b).abs() < f64::EPSILON,
             (Value::Float(a), Value::Integer(b)) => (a - *b as f64).abs() < f64::EPSILON,
-            (Value::UnitValue(a), Value::UnitValue(b)) => {
-                if a.unit.is_compatible(&b.unit) {
+            (Value::UnitValue(a), Value::UnitValue(b))
+                if a.unit.is_compatible(&b.unit) => {
                     if let Some(converted) = b.convert_to(&a.unit) {
                         (a.value - converted.value).abs() < f64::EPSILON
                     } else {
                         false
                     }
-                } else {
-                    false
                 }
-            }
             _ => false,
         }
     }
     
-    fn pattern_matches(&self, pattern: &crate::parser::ast::Pattern, value: &Value) -> crate::Result<bool> {
+    /// Whether `value` fits `pattern`, collecting the names it binds along the way
+    fn pattern_matches(&self, pattern: &crate::parser::ast::Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> crate::Result<bool> {
         use crate::parser::ast::Pattern;
         
         match pattern {
@@ -653,6 +2765,44 @@ impl Interpreter {
                 let pattern_val = self.evaluate_literal(lit);
                 Ok(self.values_equal(&pattern_val, value))
             }
+            Pattern::Binding(name) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Array { items: patterns, rest } => match value {
+                Value::Array(items) if items.len() == patterns.len() || (*rest && items.len() >= patterns.len()) => {
+                    for (pattern, item) in patterns.iter().zip(items) {
+                        if !self.pattern_matches(pattern, item, bindings)? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+            Pattern::Object(patterns) => match value {
+                Value::Object(fields) | Value::Struct { fields, .. } => {
+                    // Every field named has to be there, even when it's only bound
+                    for (field, pattern) in patterns {
+                        match fields.get(field) {
+                            Some(item) if self.pattern_matches(pattern, item, bindings)? => {}
+                            _ => return Ok(false),
+                        }
+                    }
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+            Pattern::Range { start, end, inclusive } => {
+                let (Some(start), Some(end)) = (self.evaluate_literal(start).as_number(), self.evaluate_literal(end).as_number()) else {
+                    return Ok(false);
+                };
+                Ok(match value.as_number() {
+                    Some(n) if *inclusive => (start..=end).contains(&n),
+                    Some(n) => (start..end).contains(&n),
+                    None => false,
+                })
+            }
             Pattern::Identifier(name) => {
                 // For now, treat identifiers as enum variant names
                 match value {
@@ -670,6 +2820,31 @@ impl Interpreter {
         }
     }
     
+    /// Add a native module's functions. A plugin can add to a built-in module, like `Audio`,
+    /// but not replace what's already there
+    pub fn register_module(&mut self, plugin: &dyn SynthesisModule) -> crate::Result<()> {
+        let name = plugin.name().to_string();
+        let functions = plugin.functions();
+        let module = self.modules.entry(name.clone()).or_insert_with(|| Module {
+            name: name.clone(),
+            functions: HashMap::new(),
+        });
+        if let Some((taken, _)) = functions.iter().find(|(function, _)| module.functions.contains_key(*function)) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("🧩 {}.{}() already exists, so a plugin can't add another", name, taken)
+            )
+            .with_suggestion("Give the plugin's function a name of its own, or put it in a module of its own"));
+        }
+        for (function, callback) in functions {
+            module.functions.insert(function.to_string(), ModuleFunction {
+                name: function.to_string(),
+                callback,
+            });
+        }
+        Ok(())
+    }
+    
     fn register_builtin_modules(&mut self) {
         // Graphics module
         let mut graphics_module = Module {
@@ -712,324 +2887,1436 @@ impl Interpreter {
             callback: crate::modules::graphics::line,
         });
         
-        graphics_module.functions.insert("text".to_string(), ModuleFunction {
-            name: "text".to_string(),
-            callback: crate::modules::graphics::text,
+        graphics_module.functions.insert("path".to_string(), ModuleFunction {
+            name: "path".to_string(),
+            callback: crate::modules::graphics::path,
+        });
+        
+        graphics_module.functions.insert("text".to_string(), ModuleFunction {
+            name: "text".to_string(),
+            callback: crate::modules::graphics::text,
+        });
+        
+        // Advanced effects
+        graphics_module.functions.insert("particle_system".to_string(), ModuleFunction {
+            name: "particle_system".to_string(),
+            callback: crate::modules::graphics::particle_system,
+        });
+        
+        graphics_module.functions.insert("bloom_effect".to_string(), ModuleFunction {
+            name: "bloom_effect".to_string(),
+            callback: crate::modules::graphics::bloom_effect,
+        });
+        
+        graphics_module.functions.insert("depth_of_field".to_string(), ModuleFunction {
+            name: "depth_of_field".to_string(),
+            callback: crate::modules::graphics::depth_of_field,
+        });
+        
+        graphics_module.functions.insert("screen_shake".to_string(), ModuleFunction {
+            name: "screen_shake".to_string(),
+            callback: crate::modules::graphics::screen_shake,
+        });
+        
+        graphics_module.functions.insert("wind_effect".to_string(), ModuleFunction {
+            name: "wind_effect".to_string(),
+            callback: crate::modules::graphics::wind_effect,
+        });
+        
+        graphics_module.functions.insert("flash".to_string(), ModuleFunction {
+            name: "flash".to_string(),
+            callback: crate::modules::graphics::flash,
+        });
+        
+        graphics_module.functions.insert("lightning_strike".to_string(), ModuleFunction {
+            name: "lightning_strike".to_string(),
+            callback: crate::modules::graphics::lightning_strike,
+        });
+        
+        graphics_module.functions.insert("rainbow_arc".to_string(), ModuleFunction {
+            name: "rainbow_arc".to_string(),
+            callback: crate::modules::graphics::rainbow_arc,
+        });
+        
+        graphics_module.functions.insert("rain_effect".to_string(), ModuleFunction {
+            name: "rain_effect".to_string(),
+            callback: crate::modules::graphics::rain_effect,
+        });
+        
+        // Stylization effects
+        graphics_module.functions.insert("ascii_art".to_string(), ModuleFunction {
+            name: "ascii_art".to_string(),
+            callback: crate::modules::graphics::ascii_art,
+        });
+        
+        graphics_module.functions.insert("halftone".to_string(), ModuleFunction {
+            name: "halftone".to_string(),
+            callback: crate::modules::graphics::halftone,
+        });
+        
+        graphics_module.functions.insert("dither".to_string(), ModuleFunction {
+            name: "dither".to_string(),
+            callback: crate::modules::graphics::dither,
+        });
+        
+        graphics_module.functions.insert("pixelate".to_string(), ModuleFunction {
+            name: "pixelate".to_string(),
+            callback: crate::modules::graphics::pixelate,
+        });
+        
+        // Signed-distance-field shapes
+        graphics_module.functions.insert("sdf_circle".to_string(), ModuleFunction {
+            name: "sdf_circle".to_string(),
+            callback: crate::modules::graphics::sdf_circle,
+        });
+        
+        graphics_module.functions.insert("sdf_box".to_string(), ModuleFunction {
+            name: "sdf_box".to_string(),
+            callback: crate::modules::graphics::sdf_box,
+        });
+        
+        graphics_module.functions.insert("sdf_ring".to_string(), ModuleFunction {
+            name: "sdf_ring".to_string(),
+            callback: crate::modules::graphics::sdf_ring,
+        });
+        
+        graphics_module.functions.insert("sdf_segment".to_string(), ModuleFunction {
+            name: "sdf_segment".to_string(),
+            callback: crate::modules::graphics::sdf_segment,
+        });
+        
+        graphics_module.functions.insert("sdf_union".to_string(), ModuleFunction {
+            name: "sdf_union".to_string(),
+            callback: crate::modules::graphics::sdf_union,
+        });
+        
+        graphics_module.functions.insert("sdf_subtract".to_string(), ModuleFunction {
+            name: "sdf_subtract".to_string(),
+            callback: crate::modules::graphics::sdf_subtract,
+        });
+        
+        graphics_module.functions.insert("sdf_intersect".to_string(), ModuleFunction {
+            name: "sdf_intersect".to_string(),
+            callback: crate::modules::graphics::sdf_intersect,
+        });
+        
+        graphics_module.functions.insert("sdf_render".to_string(), ModuleFunction {
+            name: "sdf_render".to_string(),
+            callback: crate::modules::graphics::sdf_render,
+        });
+        
+        // Fluid simulation
+        graphics_module.functions.insert("fluid".to_string(), ModuleFunction {
+            name: "fluid".to_string(),
+            callback: crate::modules::graphics::fluid,
+        });
+        
+        graphics_module.functions.insert("fluid_emitter".to_string(), ModuleFunction {
+            name: "fluid_emitter".to_string(),
+            callback: crate::modules::graphics::fluid_emitter,
+        });
+        
+        graphics_module.functions.insert("fluid_force".to_string(), ModuleFunction {
+            name: "fluid_force".to_string(),
+            callback: crate::modules::graphics::fluid_force,
+        });
+        
+        // Kinetic typography
+        graphics_module.functions.insert("text_glyphs".to_string(), ModuleFunction {
+            name: "text_glyphs".to_string(),
+            callback: crate::modules::graphics::text_glyphs,
+        });
+        
+        graphics_module.functions.insert("text_on_path".to_string(), ModuleFunction {
+            name: "text_on_path".to_string(),
+            callback: crate::modules::graphics::text_on_path,
+        });
+        
+        graphics_module.functions.insert("text_on_circle".to_string(), ModuleFunction {
+            name: "text_on_circle".to_string(),
+            callback: crate::modules::graphics::text_on_circle,
+        });
+        
+        graphics_module.functions.insert("animate_glyphs".to_string(), ModuleFunction {
+            name: "animate_glyphs".to_string(),
+            callback: crate::modules::graphics::animate_glyphs,
+        });
+        
+        // Camera compositing
+        graphics_module.functions.insert("camera_layer".to_string(), ModuleFunction {
+            name: "camera_layer".to_string(),
+            callback: crate::modules::graphics::camera_layer,
+        });
+        
+        graphics_module.functions.insert("chroma_key".to_string(), ModuleFunction {
+            name: "chroma_key".to_string(),
+            callback: crate::modules::graphics::chroma_key,
+        });
+        
+        graphics_module.functions.insert("luma_key".to_string(), ModuleFunction {
+            name: "luma_key".to_string(),
+            callback: crate::modules::graphics::luma_key,
+        });
+        
+        graphics_module.functions.insert("color_grade".to_string(), ModuleFunction {
+            name: "color_grade".to_string(),
+            callback: crate::modules::graphics::color_grade,
+        });
+        
+        // Screen capture
+        graphics_module.functions.insert("capture_screen".to_string(), ModuleFunction {
+            name: "capture_screen".to_string(),
+            callback: crate::modules::graphics::capture_screen,
+        });
+        
+        graphics_module.functions.insert("capture_window".to_string(), ModuleFunction {
+            name: "capture_window".to_string(),
+            callback: crate::modules::graphics::capture_window,
+        });
+        
+        graphics_module.functions.insert("stop_capture".to_string(), ModuleFunction {
+            name: "stop_capture".to_string(),
+            callback: crate::modules::graphics::stop_capture,
+        });
+        
+        // Adaptive quality
+        graphics_module.functions.insert("quality".to_string(), ModuleFunction {
+            name: "quality".to_string(),
+            callback: crate::modules::graphics::quality,
+        });
+        
+        graphics_module.functions.insert("set_quality".to_string(), ModuleFunction {
+            name: "set_quality".to_string(),
+            callback: crate::modules::graphics::set_quality,
+        });
+        
+        // Frame profiling
+        graphics_module.functions.insert("profile".to_string(), ModuleFunction {
+            name: "profile".to_string(),
+            callback: crate::modules::graphics::profile,
+        });
+        
+        graphics_module.functions.insert("debug_hud".to_string(), ModuleFunction {
+            name: "debug_hud".to_string(),
+            callback: crate::modules::graphics::debug_hud,
+        });
+        
+        graphics_module.functions.insert("isf".to_string(), ModuleFunction {
+            name: "isf".to_string(),
+            callback: crate::modules::graphics::isf,
+        });
+        
+        graphics_module.functions.insert("osc".to_string(), ModuleFunction {
+            name: "osc".to_string(),
+            callback: crate::modules::graphics::hydra_osc,
+        });
+        
+        graphics_module.functions.insert("noise".to_string(), ModuleFunction {
+            name: "noise".to_string(),
+            callback: crate::modules::graphics::hydra_noise,
+        });
+        
+        graphics_module.functions.insert("voronoi".to_string(), ModuleFunction {
+            name: "voronoi".to_string(),
+            callback: crate::modules::graphics::hydra_voronoi,
+        });
+        
+        graphics_module.functions.insert("shape".to_string(), ModuleFunction {
+            name: "shape".to_string(),
+            callback: crate::modules::graphics::hydra_shape,
+        });
+        
+        graphics_module.functions.insert("gradient".to_string(), ModuleFunction {
+            name: "gradient".to_string(),
+            callback: crate::modules::graphics::hydra_gradient,
+        });
+        
+        graphics_module.functions.insert("solid".to_string(), ModuleFunction {
+            name: "solid".to_string(),
+            callback: crate::modules::graphics::hydra_solid,
+        });
+        
+        self.modules.insert("Graphics".to_string(), graphics_module);
+        
+        // Audio module
+        let mut audio_module = Module {
+            name: "Audio".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        audio_module.functions.insert("mic_input".to_string(), ModuleFunction {
+            name: "mic_input".to_string(),
+            callback: crate::modules::audio::mic_input,
+        });
+        
+        audio_module.functions.insert("analyze_fft".to_string(), ModuleFunction {
+            name: "analyze_fft".to_string(),
+            callback: crate::modules::audio::analyze_fft,
+        });
+        
+        audio_module.functions.insert("beat_detect".to_string(), ModuleFunction {
+            name: "beat_detect".to_string(),
+            callback: crate::modules::audio::beat_detect,
+        });
+        
+        audio_module.functions.insert("load_file".to_string(), ModuleFunction {
+            name: "load_file".to_string(),
+            callback: crate::modules::audio::load_file,
+        });
+        
+        audio_module.functions.insert("play".to_string(), ModuleFunction {
+            name: "play".to_string(),
+            callback: crate::modules::audio::play,
+        });
+        
+        audio_module.functions.insert("volume".to_string(), ModuleFunction {
+            name: "volume".to_string(),
+            callback: crate::modules::audio::volume,
+        });
+        
+        // Audio classification functions
+        audio_module.functions.insert("classify_beat".to_string(), ModuleFunction {
+            name: "classify_beat".to_string(),
+            callback: crate::modules::audio::classify_beat,
+        });
+        
+        audio_module.functions.insert("classify_mood".to_string(), ModuleFunction {
+            name: "classify_mood".to_string(),
+            callback: crate::modules::audio::classify_mood,
+        });
+        
+        audio_module.functions.insert("onset_detection".to_string(), ModuleFunction {
+            name: "onset_detection".to_string(),
+            callback: crate::modules::audio::onset_detection,
+        });
+        
+        audio_module.functions.insert("tempo_detection".to_string(), ModuleFunction {
+            name: "tempo_detection".to_string(),
+            callback: crate::modules::audio::tempo_detection,
+        });
+        
+        audio_module.functions.insert("spectral_centroid".to_string(), ModuleFunction {
+            name: "spectral_centroid".to_string(),
+            callback: crate::modules::audio::spectral_centroid,
+        });
+        
+        audio_module.functions.insert("panic".to_string(), ModuleFunction {
+            name: "panic".to_string(),
+            callback: crate::modules::audio::audio_panic,
+        });
+        
+        audio_module.functions.insert("resume".to_string(), ModuleFunction {
+            name: "resume".to_string(),
+            callback: crate::modules::audio::audio_resume,
+        });
+        
+        audio_module.functions.insert("loudness".to_string(), ModuleFunction {
+            name: "loudness".to_string(),
+            callback: crate::modules::audio::audio_loudness,
+        });
+        
+        audio_module.functions.insert("record_session".to_string(), ModuleFunction {
+            name: "record_session".to_string(),
+            callback: crate::modules::audio::audio_record_session,
+        });
+        
+        audio_module.functions.insert("stop_recording".to_string(), ModuleFunction {
+            name: "stop_recording".to_string(),
+            callback: crate::modules::audio::audio_stop_recording,
+        });
+        
+        audio_module.functions.insert("latency".to_string(), ModuleFunction {
+            name: "latency".to_string(),
+            callback: crate::modules::audio::audio_latency,
+        });
+        
+        audio_module.functions.insert("configure".to_string(), ModuleFunction {
+            name: "configure".to_string(),
+            callback: crate::modules::audio::audio_configure,
+        });
+        
+        audio_module.functions.insert("open_output".to_string(), ModuleFunction {
+            name: "open_output".to_string(),
+            callback: crate::modules::audio::audio_open_output,
+        });
+        
+        audio_module.functions.insert("open_input".to_string(), ModuleFunction {
+            name: "open_input".to_string(),
+            callback: crate::modules::audio::audio_open_input,
+        });
+        
+        audio_module.functions.insert("loopback".to_string(), ModuleFunction {
+            name: "loopback".to_string(),
+            callback: crate::modules::audio::audio_loopback,
+        });
+        
+        audio_module.functions.insert("close_device".to_string(), ModuleFunction {
+            name: "close_device".to_string(),
+            callback: crate::modules::audio::audio_close_device,
+        });
+        
+        audio_module.functions.insert("devices".to_string(), ModuleFunction {
+            name: "devices".to_string(),
+            callback: crate::modules::audio::audio_devices,
+        });
+        
+        audio_module.functions.insert("device_input".to_string(), ModuleFunction {
+            name: "device_input".to_string(),
+            callback: crate::modules::audio::audio_device_input,
+        });
+        
+        audio_module.functions.insert("cue_output".to_string(), ModuleFunction {
+            name: "cue_output".to_string(),
+            callback: crate::modules::audio::audio_cue_output,
+        });
+        
+        audio_module.functions.insert("cues".to_string(), ModuleFunction {
+            name: "cues".to_string(),
+            callback: crate::modules::audio::audio_cues,
+        });
+        
+        audio_module.functions.insert("delay".to_string(), ModuleFunction {
+            name: "delay".to_string(),
+            callback: crate::modules::audio::audio_delay,
+        });
+        
+        audio_module.functions.insert("apply_reverb".to_string(), ModuleFunction {
+            name: "apply_reverb".to_string(),
+            callback: crate::modules::audio::audio_apply_reverb,
+        });
+        
+        self.modules.insert("Audio".to_string(), audio_module);
+        
+        // Math module
+        let mut math_module = Module {
+            name: "Math".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        math_module.functions.insert("sin".to_string(), ModuleFunction {
+            name: "sin".to_string(),
+            callback: crate::modules::math::sin,
+        });
+        
+        math_module.functions.insert("cos".to_string(), ModuleFunction {
+            name: "cos".to_string(),
+            callback: crate::modules::math::cos,
+        });
+        
+        math_module.functions.insert("sqrt".to_string(), ModuleFunction {
+            name: "sqrt".to_string(),
+            callback: crate::modules::math::sqrt,
+        });
+        
+        math_module.functions.insert("abs".to_string(), ModuleFunction {
+            name: "abs".to_string(),
+            callback: crate::modules::math::abs,
+        });
+        
+        math_module.functions.insert("min".to_string(), ModuleFunction {
+            name: "min".to_string(),
+            callback: crate::modules::math::min,
+        });
+        
+        math_module.functions.insert("max".to_string(), ModuleFunction {
+            name: "max".to_string(),
+            callback: crate::modules::math::max,
+        });
+        
+        math_module.functions.insert("floor".to_string(), ModuleFunction {
+            name: "floor".to_string(),
+            callback: crate::modules::math::floor,
+        });
+        
+        math_module.functions.insert("ceil".to_string(), ModuleFunction {
+            name: "ceil".to_string(),
+            callback: crate::modules::math::ceil,
+        });
+        
+        math_module.functions.insert("round".to_string(), ModuleFunction {
+            name: "round".to_string(),
+            callback: crate::modules::math::round,
+        });
+        
+        math_module.functions.insert("pow".to_string(), ModuleFunction {
+            name: "pow".to_string(),
+            callback: crate::modules::math::pow,
+        });
+        
+        math_module.functions.insert("log".to_string(), ModuleFunction {
+            name: "log".to_string(),
+            callback: crate::modules::math::log,
+        });
+        
+        math_module.functions.insert("exp".to_string(), ModuleFunction {
+            name: "exp".to_string(),
+            callback: crate::modules::math::exp,
+        });
+        
+        math_module.functions.insert("tan".to_string(), ModuleFunction {
+            name: "tan".to_string(),
+            callback: crate::modules::math::tan,
+        });
+        
+        math_module.functions.insert("clamp".to_string(), ModuleFunction {
+            name: "clamp".to_string(),
+            callback: crate::modules::math::clamp,
+        });
+        
+        math_module.functions.insert("lerp".to_string(), ModuleFunction {
+            name: "lerp".to_string(),
+            callback: crate::modules::math::lerp,
+        });
+        
+        math_module.functions.insert("map".to_string(), ModuleFunction {
+            name: "map".to_string(),
+            callback: crate::modules::math::map,
+        });
+        
+        math_module.functions.insert("normalize".to_string(), ModuleFunction {
+            name: "normalize".to_string(),
+            callback: crate::modules::math::normalize,
+        });
+        
+        math_module.functions.insert("clip".to_string(), ModuleFunction {
+            name: "clip".to_string(),
+            callback: crate::modules::math::clip,
+        });
+        
+        math_module.functions.insert("deadzone".to_string(), ModuleFunction {
+            name: "deadzone".to_string(),
+            callback: crate::modules::math::deadzone,
+        });
+        
+        math_module.functions.insert("vec2".to_string(), ModuleFunction {
+            name: "vec2".to_string(),
+            callback: crate::modules::math::vec2,
+        });
+        
+        math_module.functions.insert("vec3".to_string(), ModuleFunction {
+            name: "vec3".to_string(),
+            callback: crate::modules::math::vec3,
+        });
+        
+        math_module.functions.insert("mat3".to_string(), ModuleFunction {
+            name: "mat3".to_string(),
+            callback: crate::modules::math::mat3,
+        });
+        
+        math_module.functions.insert("mat4".to_string(), ModuleFunction {
+            name: "mat4".to_string(),
+            callback: crate::modules::math::mat4,
+        });
+        
+        math_module.functions.insert("dot".to_string(), ModuleFunction {
+            name: "dot".to_string(),
+            callback: crate::modules::math::dot,
+        });
+        
+        math_module.functions.insert("cross".to_string(), ModuleFunction {
+            name: "cross".to_string(),
+            callback: crate::modules::math::cross,
+        });
+        
+        math_module.functions.insert("length".to_string(), ModuleFunction {
+            name: "length".to_string(),
+            callback: crate::modules::math::length,
+        });
+        
+        math_module.functions.insert("distance".to_string(), ModuleFunction {
+            name: "distance".to_string(),
+            callback: crate::modules::math::distance,
+        });
+        
+        math_module.functions.insert("rotation".to_string(), ModuleFunction {
+            name: "rotation".to_string(),
+            callback: crate::modules::math::rotation,
+        });
+        
+        math_module.functions.insert("translation".to_string(), ModuleFunction {
+            name: "translation".to_string(),
+            callback: crate::modules::math::translation,
+        });
+        
+        math_module.functions.insert("scaling".to_string(), ModuleFunction {
+            name: "scaling".to_string(),
+            callback: crate::modules::math::scaling,
+        });
+        
+        math_module.functions.insert("transpose".to_string(), ModuleFunction {
+            name: "transpose".to_string(),
+            callback: crate::modules::math::transpose,
+        });
+        
+        math_module.functions.insert("inverse".to_string(), ModuleFunction {
+            name: "inverse".to_string(),
+            callback: crate::modules::math::inverse,
+        });
+        
+        math_module.functions.insert("complex".to_string(), ModuleFunction {
+            name: "complex".to_string(),
+            callback: crate::modules::math::complex,
+        });
+        
+        math_module.functions.insert("polar".to_string(), ModuleFunction {
+            name: "polar".to_string(),
+            callback: crate::modules::math::polar,
+        });
+        
+        math_module.functions.insert("phase".to_string(), ModuleFunction {
+            name: "phase".to_string(),
+            callback: crate::modules::math::phase,
+        });
+        
+        math_module.functions.insert("conj".to_string(), ModuleFunction {
+            name: "conj".to_string(),
+            callback: crate::modules::math::conj,
+        });
+        
+        math_module.functions.insert("poly".to_string(), ModuleFunction {
+            name: "poly".to_string(),
+            callback: crate::modules::math::poly,
+        });
+        
+        math_module.functions.insert("polyval".to_string(), ModuleFunction {
+            name: "polyval".to_string(),
+            callback: crate::modules::math::polyval,
+        });
+        
+        math_module.functions.insert("roots".to_string(), ModuleFunction {
+            name: "roots".to_string(),
+            callback: crate::modules::math::roots,
+        });
+        
+        math_module.functions.insert("roots_of_unity".to_string(), ModuleFunction {
+            name: "roots_of_unity".to_string(),
+            callback: crate::modules::math::roots_of_unity,
+        });
+        
+        math_module.functions.insert("transfer".to_string(), ModuleFunction {
+            name: "transfer".to_string(),
+            callback: crate::modules::math::transfer,
+        });
+        
+        self.modules.insert("Math".to_string(), math_module);
+        
+        // GUI module
+        let mut gui_module = Module {
+            name: "GUI".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        gui_module.functions.insert("window".to_string(), ModuleFunction {
+            name: "window".to_string(),
+            callback: crate::modules::gui::window,
+        });
+        
+        gui_module.functions.insert("button".to_string(), ModuleFunction {
+            name: "button".to_string(),
+            callback: crate::modules::gui::button,
+        });
+        
+        gui_module.functions.insert("slider".to_string(), ModuleFunction {
+            name: "slider".to_string(),
+            callback: crate::modules::gui::slider,
+        });
+        
+        gui_module.functions.insert("checkbox".to_string(), ModuleFunction {
+            name: "checkbox".to_string(),
+            callback: crate::modules::gui::checkbox,
+        });
+        
+        gui_module.functions.insert("dropdown".to_string(), ModuleFunction {
+            name: "dropdown".to_string(),
+            callback: crate::modules::gui::dropdown,
+        });
+        
+        gui_module.functions.insert("control_group".to_string(), ModuleFunction {
+            name: "control_group".to_string(),
+            callback: crate::modules::gui::control_group,
+        });
+        
+        self.modules.insert("GUI".to_string(), gui_module);
+        
+        // Generate module
+        let mut generate_module = Module {
+            name: "Generate".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        generate_module.functions.insert("l_system".to_string(), ModuleFunction {
+            name: "l_system".to_string(),
+            callback: crate::modules::generate::l_system,
+        });
+        
+        generate_module.functions.insert("perlin_noise".to_string(), ModuleFunction {
+            name: "perlin_noise".to_string(),
+            callback: crate::modules::generate::perlin_noise,
+        });
+        
+        generate_module.functions.insert("euclidean".to_string(), ModuleFunction {
+            name: "euclidean".to_string(),
+            callback: crate::modules::generate::euclidean,
+        });
+        
+        generate_module.functions.insert("fractal_terrain".to_string(), ModuleFunction {
+            name: "fractal_terrain".to_string(),
+            callback: crate::modules::generate::fractal_terrain,
+        });
+        
+        generate_module.functions.insert("voronoi".to_string(), ModuleFunction {
+            name: "voronoi".to_string(),
+            callback: crate::modules::generate::voronoi,
+        });
+        
+        generate_module.functions.insert("delaunay".to_string(), ModuleFunction {
+            name: "delaunay".to_string(),
+            callback: crate::modules::generate::delaunay_triangulation,
+        });
+        
+        generate_module.functions.insert("nearest".to_string(), ModuleFunction {
+            name: "nearest".to_string(),
+            callback: crate::modules::generate::nearest,
+        });
+        
+        generate_module.functions.insert("attractor".to_string(), ModuleFunction {
+            name: "attractor".to_string(),
+            callback: crate::modules::generate::attractor,
+        });
+        
+        self.modules.insert("Generate".to_string(), generate_module);
+        
+        // Timeline module
+        let mut timeline_module = Module {
+            name: "Timeline".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        timeline_module.functions.insert("create".to_string(), ModuleFunction {
+            name: "create".to_string(),
+            callback: crate::modules::time::timeline_create,
+        });
+        
+        timeline_module.functions.insert("sequencer".to_string(), ModuleFunction {
+            name: "sequencer".to_string(),
+            callback: crate::modules::time::sequencer_create,
+        });
+        
+        timeline_module.functions.insert("animation_curve".to_string(), ModuleFunction {
+            name: "animation_curve".to_string(),
+            callback: crate::modules::time::animation_curve_create,
+        });
+        
+        timeline_module.functions.insert("every".to_string(), ModuleFunction {
+            name: "every".to_string(),
+            callback: crate::modules::time::every,
+        });
+        
+        timeline_module.functions.insert("after".to_string(), ModuleFunction {
+            name: "after".to_string(),
+            callback: crate::modules::time::after,
+        });
+        
+        timeline_module.functions.insert("sequence".to_string(), ModuleFunction {
+            name: "sequence".to_string(),
+            callback: crate::modules::time::sequence,
+        });
+        
+        timeline_module.functions.insert("now".to_string(), ModuleFunction {
+            name: "now".to_string(),
+            callback: crate::modules::time::now,
+        });
+        
+        timeline_module.functions.insert("delta_time".to_string(), ModuleFunction {
+            name: "delta_time".to_string(),
+            callback: crate::modules::time::delta_time,
+        });
+        
+        timeline_module.functions.insert("fps".to_string(), ModuleFunction {
+            name: "fps".to_string(),
+            callback: crate::modules::time::fps,
+        });
+        
+        timeline_module.functions.insert("frame_alpha".to_string(), ModuleFunction {
+            name: "frame_alpha".to_string(),
+            callback: crate::modules::time::frame_alpha,
+        });
+        
+        timeline_module.functions.insert("interpolate".to_string(), ModuleFunction {
+            name: "interpolate".to_string(),
+            callback: crate::modules::time::interpolate,
+        });
+        
+        // Timecode sync
+        timeline_module.functions.insert("timecode".to_string(), ModuleFunction {
+            name: "timecode".to_string(),
+            callback: crate::modules::time::timecode_sync,
+        });
+        
+        timeline_module.functions.insert("timecode_format".to_string(), ModuleFunction {
+            name: "timecode_format".to_string(),
+            callback: crate::modules::time::timecode_format,
+        });
+        
+        timeline_module.functions.insert("timecode_parse".to_string(), ModuleFunction {
+            name: "timecode_parse".to_string(),
+            callback: crate::modules::time::timecode_parse,
+        });
+        
+        timeline_module.functions.insert("timecode_overlay".to_string(), ModuleFunction {
+            name: "timecode_overlay".to_string(),
+            callback: crate::modules::time::timecode_overlay,
+        });
+        
+        timeline_module.functions.insert("pattern".to_string(), ModuleFunction {
+            name: "pattern".to_string(),
+            callback: crate::modules::time::pattern,
+        });
+        
+        timeline_module.functions.insert("pattern_events".to_string(), ModuleFunction {
+            name: "pattern_events".to_string(),
+            callback: crate::modules::time::pattern_events,
+        });
+        
+        timeline_module.functions.insert("pattern_hit".to_string(), ModuleFunction {
+            name: "pattern_hit".to_string(),
+            callback: crate::modules::time::pattern_hit,
+        });
+        
+        timeline_module.functions.insert("playing".to_string(), ModuleFunction {
+            name: "playing".to_string(),
+            callback: crate::modules::time::transport_playing,
+        });
+        
+        timeline_module.functions.insert("toggle".to_string(), ModuleFunction {
+            name: "toggle".to_string(),
+            callback: crate::modules::time::transport_toggle,
+        });
+        
+        timeline_module.functions.insert("transport_time".to_string(), ModuleFunction {
+            name: "transport_time".to_string(),
+            callback: crate::modules::time::transport_time,
+        });
+        
+        timeline_module.functions.insert("tempo".to_string(), ModuleFunction {
+            name: "tempo".to_string(),
+            callback: crate::modules::time::timeline_tempo,
+        });
+        
+        timeline_module.functions.insert("seconds".to_string(), ModuleFunction {
+            name: "seconds".to_string(),
+            callback: crate::modules::time::timeline_seconds,
+        });
+        
+        self.modules.insert("Timeline".to_string(), timeline_module);
+        
+        // Synth module: plays `synth` definitions
+        let mut synth_module = Module {
+            name: "Synth".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        synth_module.functions.insert("play".to_string(), ModuleFunction {
+            name: "play".to_string(),
+            callback: crate::modules::synth::synth_play,
+        });
+        
+        synth_module.functions.insert("release".to_string(), ModuleFunction {
+            name: "release".to_string(),
+            callback: crate::modules::synth::synth_release,
+        });
+        
+        synth_module.functions.insert("render".to_string(), ModuleFunction {
+            name: "render".to_string(),
+            callback: crate::modules::synth::synth_render,
+        });
+        
+        synth_module.functions.insert("voices".to_string(), ModuleFunction {
+            name: "voices".to_string(),
+            callback: crate::modules::synth::synth_voices,
+        });
+        
+        self.modules.insert("Synth".to_string(), synth_module);
+        
+        // Signal module: exact-length test tones for checking effect chains
+        let mut signal_module = Module {
+            name: "Signal".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        signal_module.functions.insert("sine".to_string(), ModuleFunction {
+            name: "sine".to_string(),
+            callback: crate::modules::signal::signal_sine,
+        });
+        
+        signal_module.functions.insert("sweep".to_string(), ModuleFunction {
+            name: "sweep".to_string(),
+            callback: crate::modules::signal::signal_sweep,
+        });
+        
+        signal_module.functions.insert("impulse".to_string(), ModuleFunction {
+            name: "impulse".to_string(),
+            callback: crate::modules::signal::signal_impulse,
+        });
+        
+        signal_module.functions.insert("white_noise".to_string(), ModuleFunction {
+            name: "white_noise".to_string(),
+            callback: crate::modules::signal::signal_white_noise,
+        });
+        
+        signal_module.functions.insert("pink_noise".to_string(), ModuleFunction {
+            name: "pink_noise".to_string(),
+            callback: crate::modules::signal::signal_pink_noise,
+        });
+        
+        signal_module.functions.insert("dc".to_string(), ModuleFunction {
+            name: "dc".to_string(),
+            callback: crate::modules::signal::signal_dc,
+        });
+        
+        signal_module.functions.insert("silence".to_string(), ModuleFunction {
+            name: "silence".to_string(),
+            callback: crate::modules::signal::signal_silence,
+        });
+        
+        signal_module.functions.insert("stats".to_string(), ModuleFunction {
+            name: "stats".to_string(),
+            callback: crate::modules::signal::signal_stats,
+        });
+        
+        self.modules.insert("Signal".to_string(), signal_module);
+        
+        // Mix module: crossfades between synths, buffers, graphics layers and scenes
+        let mut mix_module = Module {
+            name: "Mix".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        mix_module.functions.insert("crossfade".to_string(), ModuleFunction {
+            name: "crossfade".to_string(),
+            callback: crate::modules::mix::mix_crossfade,
+        });
+        
+        self.modules.insert("Mix".to_string(), mix_module);
+        
+        // Clips module: the session-view clip grid
+        let mut clips_module = Module {
+            name: "Clips".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        clips_module.functions.insert("set".to_string(), ModuleFunction {
+            name: "set".to_string(),
+            callback: crate::modules::clips::clips_set,
+        });
+        
+        clips_module.functions.insert("launch".to_string(), ModuleFunction {
+            name: "launch".to_string(),
+            callback: crate::modules::clips::clips_launch,
+        });
+        
+        clips_module.functions.insert("scene".to_string(), ModuleFunction {
+            name: "scene".to_string(),
+            callback: crate::modules::clips::clips_scene,
+        });
+        
+        clips_module.functions.insert("stop".to_string(), ModuleFunction {
+            name: "stop".to_string(),
+            callback: crate::modules::clips::clips_stop,
+        });
+        
+        clips_module.functions.insert("clear".to_string(), ModuleFunction {
+            name: "clear".to_string(),
+            callback: crate::modules::clips::clips_clear,
+        });
+        
+        clips_module.functions.insert("quantize".to_string(), ModuleFunction {
+            name: "quantize".to_string(),
+            callback: crate::modules::clips::clips_quantize,
+        });
+        
+        clips_module.functions.insert("grid".to_string(), ModuleFunction {
+            name: "grid".to_string(),
+            callback: crate::modules::clips::clips_grid,
+        });
+        
+        clips_module.functions.insert("pads".to_string(), ModuleFunction {
+            name: "pads".to_string(),
+            callback: crate::modules::clips::clips_pads,
+        });
+        
+        clips_module.functions.insert("controller".to_string(), ModuleFunction {
+            name: "controller".to_string(),
+            callback: crate::modules::clips::clips_controller,
+        });
+        
+        self.modules.insert("Clips".to_string(), clips_module);
+        
+        // Keyboard module: held keys, per-frame events and hotkey bindings
+        let mut keyboard_module = Module {
+            name: "Keyboard".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        keyboard_module.functions.insert("down".to_string(), ModuleFunction {
+            name: "down".to_string(),
+            callback: crate::modules::keyboard::keyboard_down,
+        });
+        
+        keyboard_module.functions.insert("pressed".to_string(), ModuleFunction {
+            name: "pressed".to_string(),
+            callback: crate::modules::keyboard::keyboard_pressed,
+        });
+        
+        keyboard_module.functions.insert("released".to_string(), ModuleFunction {
+            name: "released".to_string(),
+            callback: crate::modules::keyboard::keyboard_released,
+        });
+        
+        keyboard_module.functions.insert("events".to_string(), ModuleFunction {
+            name: "events".to_string(),
+            callback: crate::modules::keyboard::keyboard_events,
+        });
+        
+        keyboard_module.functions.insert("bind".to_string(), ModuleFunction {
+            name: "bind".to_string(),
+            callback: crate::modules::keyboard::keyboard_bind,
+        });
+        
+        keyboard_module.functions.insert("bindings".to_string(), ModuleFunction {
+            name: "bindings".to_string(),
+            callback: crate::modules::keyboard::keyboard_bindings,
+        });
+        
+        self.modules.insert("Keyboard".to_string(), keyboard_module);
+        
+        // Hid module: raw HID controllers read through a field mapping
+        let mut hid_module = Module {
+            name: "Hid".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        hid_module.functions.insert("open".to_string(), ModuleFunction {
+            name: "open".to_string(),
+            callback: crate::modules::hid::hid_open,
+        });
+        
+        hid_module.functions.insert("read".to_string(), ModuleFunction {
+            name: "read".to_string(),
+            callback: crate::modules::hid::hid_read,
+        });
+        
+        hid_module.functions.insert("close".to_string(), ModuleFunction {
+            name: "close".to_string(),
+            callback: crate::modules::hid::hid_close,
+        });
+        
+        hid_module.functions.insert("devices".to_string(), ModuleFunction {
+            name: "devices".to_string(),
+            callback: crate::modules::hid::hid_devices,
+        });
+        
+        self.modules.insert("Hid".to_string(), hid_module);
+        
+        // Tablet module: pen pressure, tilt and proximity
+        let mut tablet_module = Module {
+            name: "Tablet".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        tablet_module.functions.insert("pressure".to_string(), ModuleFunction {
+            name: "pressure".to_string(),
+            callback: crate::modules::tablet::tablet_pressure,
         });
         
-        // Advanced effects
-        graphics_module.functions.insert("particle_system".to_string(), ModuleFunction {
-            name: "particle_system".to_string(),
-            callback: crate::modules::graphics::particle_system,
+        tablet_module.functions.insert("tilt".to_string(), ModuleFunction {
+            name: "tilt".to_string(),
+            callback: crate::modules::tablet::tablet_tilt,
         });
         
-        graphics_module.functions.insert("bloom_effect".to_string(), ModuleFunction {
-            name: "bloom_effect".to_string(),
-            callback: crate::modules::graphics::bloom_effect,
+        tablet_module.functions.insert("proximity".to_string(), ModuleFunction {
+            name: "proximity".to_string(),
+            callback: crate::modules::tablet::tablet_proximity,
         });
         
-        graphics_module.functions.insert("depth_of_field".to_string(), ModuleFunction {
-            name: "depth_of_field".to_string(),
-            callback: crate::modules::graphics::depth_of_field,
+        tablet_module.functions.insert("read".to_string(), ModuleFunction {
+            name: "read".to_string(),
+            callback: crate::modules::tablet::tablet_read,
         });
         
-        graphics_module.functions.insert("screen_shake".to_string(), ModuleFunction {
-            name: "screen_shake".to_string(),
-            callback: crate::modules::graphics::screen_shake,
+        tablet_module.functions.insert("touching".to_string(), ModuleFunction {
+            name: "touching".to_string(),
+            callback: crate::modules::tablet::tablet_touching,
         });
         
-        graphics_module.functions.insert("wind_effect".to_string(), ModuleFunction {
-            name: "wind_effect".to_string(),
-            callback: crate::modules::graphics::wind_effect,
+        self.modules.insert("Tablet".to_string(), tablet_module);
+        
+        // Touch module: every finger on a touchscreen
+        let mut touch_module = Module {
+            name: "Touch".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        touch_module.functions.insert("active".to_string(), ModuleFunction {
+            name: "active".to_string(),
+            callback: crate::modules::touch::touch_active,
         });
         
-        graphics_module.functions.insert("flash".to_string(), ModuleFunction {
-            name: "flash".to_string(),
-            callback: crate::modules::graphics::flash,
+        touch_module.functions.insert("count".to_string(), ModuleFunction {
+            name: "count".to_string(),
+            callback: crate::modules::touch::touch_count,
         });
         
-        graphics_module.functions.insert("lightning_strike".to_string(), ModuleFunction {
-            name: "lightning_strike".to_string(),
-            callback: crate::modules::graphics::lightning_strike,
+        touch_module.functions.insert("get".to_string(), ModuleFunction {
+            name: "get".to_string(),
+            callback: crate::modules::touch::touch_get,
         });
         
-        graphics_module.functions.insert("rainbow_arc".to_string(), ModuleFunction {
-            name: "rainbow_arc".to_string(),
-            callback: crate::modules::graphics::rainbow_arc,
+        touch_module.functions.insert("started".to_string(), ModuleFunction {
+            name: "started".to_string(),
+            callback: crate::modules::touch::touch_started,
         });
         
-        graphics_module.functions.insert("rain_effect".to_string(), ModuleFunction {
-            name: "rain_effect".to_string(),
-            callback: crate::modules::graphics::rain_effect,
+        touch_module.functions.insert("ended".to_string(), ModuleFunction {
+            name: "ended".to_string(),
+            callback: crate::modules::touch::touch_ended,
         });
         
-        self.modules.insert("Graphics".to_string(), graphics_module);
+        self.modules.insert("Touch".to_string(), touch_module);
         
-        // Audio module
-        let mut audio_module = Module {
-            name: "Audio".to_string(),
+        // Depth module: Kinect and RealSense depth, point clouds and people
+        let mut depth_module = Module {
+            name: "Depth".to_string(),
             functions: HashMap::new(),
         };
         
-        audio_module.functions.insert("mic_input".to_string(), ModuleFunction {
-            name: "mic_input".to_string(),
-            callback: crate::modules::audio::mic_input,
+        depth_module.functions.insert("open".to_string(), ModuleFunction {
+            name: "open".to_string(),
+            callback: crate::modules::depth::depth_open,
         });
         
-        audio_module.functions.insert("analyze_fft".to_string(), ModuleFunction {
-            name: "analyze_fft".to_string(),
-            callback: crate::modules::audio::analyze_fft,
+        depth_module.functions.insert("at".to_string(), ModuleFunction {
+            name: "at".to_string(),
+            callback: crate::modules::depth::depth_at,
         });
         
-        audio_module.functions.insert("beat_detect".to_string(), ModuleFunction {
-            name: "beat_detect".to_string(),
-            callback: crate::modules::audio::beat_detect,
+        depth_module.functions.insert("points".to_string(), ModuleFunction {
+            name: "points".to_string(),
+            callback: crate::modules::depth::depth_points,
         });
         
-        audio_module.functions.insert("load_file".to_string(), ModuleFunction {
-            name: "load_file".to_string(),
-            callback: crate::modules::audio::load_file,
+        depth_module.functions.insert("users".to_string(), ModuleFunction {
+            name: "users".to_string(),
+            callback: crate::modules::depth::depth_users,
         });
         
-        audio_module.functions.insert("play".to_string(), ModuleFunction {
-            name: "play".to_string(),
-            callback: crate::modules::audio::play,
+        depth_module.functions.insert("background".to_string(), ModuleFunction {
+            name: "background".to_string(),
+            callback: crate::modules::depth::depth_background,
         });
         
-        audio_module.functions.insert("volume".to_string(), ModuleFunction {
-            name: "volume".to_string(),
-            callback: crate::modules::audio::volume,
+        depth_module.functions.insert("close".to_string(), ModuleFunction {
+            name: "close".to_string(),
+            callback: crate::modules::depth::depth_close,
         });
         
-        // Audio classification functions
-        audio_module.functions.insert("classify_beat".to_string(), ModuleFunction {
-            name: "classify_beat".to_string(),
-            callback: crate::modules::audio::classify_beat,
+        self.modules.insert("Depth".to_string(), depth_module);
+        
+        // Vision module: brightness, palettes, edges and regions of pictures and video
+        let mut vision_module = Module {
+            name: "Vision".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        vision_module.functions.insert("analyze".to_string(), ModuleFunction {
+            name: "analyze".to_string(),
+            callback: crate::modules::vision::vision_analyze,
         });
         
-        audio_module.functions.insert("classify_mood".to_string(), ModuleFunction {
-            name: "classify_mood".to_string(),
-            callback: crate::modules::audio::classify_mood,
+        self.modules.insert("Vision".to_string(), vision_module);
+        
+        // MIDI module: arpeggiators, strummers, scales and humanizing for note events
+        let mut midi_module = Module {
+            name: "MIDI".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        midi_module.functions.insert("arpeggiate".to_string(), ModuleFunction {
+            name: "arpeggiate".to_string(),
+            callback: crate::modules::midi::midi_arpeggiate,
         });
         
-        audio_module.functions.insert("onset_detection".to_string(), ModuleFunction {
-            name: "onset_detection".to_string(),
-            callback: crate::modules::audio::onset_detection,
+        midi_module.functions.insert("strum".to_string(), ModuleFunction {
+            name: "strum".to_string(),
+            callback: crate::modules::midi::midi_strum,
         });
         
-        audio_module.functions.insert("tempo_detection".to_string(), ModuleFunction {
-            name: "tempo_detection".to_string(),
-            callback: crate::modules::audio::tempo_detection,
+        midi_module.functions.insert("quantize_to_scale".to_string(), ModuleFunction {
+            name: "quantize_to_scale".to_string(),
+            callback: crate::modules::midi::midi_quantize_to_scale,
         });
         
-        audio_module.functions.insert("spectral_centroid".to_string(), ModuleFunction {
-            name: "spectral_centroid".to_string(),
-            callback: crate::modules::audio::spectral_centroid,
+        midi_module.functions.insert("humanize".to_string(), ModuleFunction {
+            name: "humanize".to_string(),
+            callback: crate::modules::midi::midi_humanize,
         });
         
-        self.modules.insert("Audio".to_string(), audio_module);
+        self.modules.insert("MIDI".to_string(), midi_module);
         
-        // Math module
-        let mut math_module = Module {
-            name: "Math".to_string(),
+        // GPS module: position, speed and heading from gpsd or an NMEA receiver
+        let mut gps_module = Module {
+            name: "GPS".to_string(),
             functions: HashMap::new(),
         };
         
-        math_module.functions.insert("sin".to_string(), ModuleFunction {
-            name: "sin".to_string(),
-            callback: crate::modules::math::sin,
+        gps_module.functions.insert("open".to_string(), ModuleFunction {
+            name: "open".to_string(),
+            callback: crate::modules::gps::gps_open,
         });
         
-        math_module.functions.insert("cos".to_string(), ModuleFunction {
-            name: "cos".to_string(),
-            callback: crate::modules::math::cos,
+        gps_module.functions.insert("latitude".to_string(), ModuleFunction {
+            name: "latitude".to_string(),
+            callback: crate::modules::gps::gps_latitude,
         });
         
-        math_module.functions.insert("sqrt".to_string(), ModuleFunction {
-            name: "sqrt".to_string(),
-            callback: crate::modules::math::sqrt,
+        gps_module.functions.insert("longitude".to_string(), ModuleFunction {
+            name: "longitude".to_string(),
+            callback: crate::modules::gps::gps_longitude,
         });
         
-        math_module.functions.insert("abs".to_string(), ModuleFunction {
-            name: "abs".to_string(),
-            callback: crate::modules::math::abs,
+        gps_module.functions.insert("speed".to_string(), ModuleFunction {
+            name: "speed".to_string(),
+            callback: crate::modules::gps::gps_speed,
         });
         
-        math_module.functions.insert("min".to_string(), ModuleFunction {
-            name: "min".to_string(),
-            callback: crate::modules::math::min,
+        gps_module.functions.insert("heading".to_string(), ModuleFunction {
+            name: "heading".to_string(),
+            callback: crate::modules::gps::gps_heading,
         });
         
-        math_module.functions.insert("max".to_string(), ModuleFunction {
-            name: "max".to_string(),
-            callback: crate::modules::math::max,
+        gps_module.functions.insert("altitude".to_string(), ModuleFunction {
+            name: "altitude".to_string(),
+            callback: crate::modules::gps::gps_altitude,
         });
         
-        math_module.functions.insert("floor".to_string(), ModuleFunction {
-            name: "floor".to_string(),
-            callback: crate::modules::math::floor,
+        gps_module.functions.insert("position".to_string(), ModuleFunction {
+            name: "position".to_string(),
+            callback: crate::modules::gps::gps_position,
         });
         
-        math_module.functions.insert("ceil".to_string(), ModuleFunction {
-            name: "ceil".to_string(),
-            callback: crate::modules::math::ceil,
+        gps_module.functions.insert("distance".to_string(), ModuleFunction {
+            name: "distance".to_string(),
+            callback: crate::modules::gps::gps_distance,
         });
         
-        math_module.functions.insert("round".to_string(), ModuleFunction {
-            name: "round".to_string(),
-            callback: crate::modules::math::round,
+        gps_module.functions.insert("bearing".to_string(), ModuleFunction {
+            name: "bearing".to_string(),
+            callback: crate::modules::gps::gps_bearing,
         });
         
-        math_module.functions.insert("pow".to_string(), ModuleFunction {
-            name: "pow".to_string(),
-            callback: crate::modules::math::pow,
+        gps_module.functions.insert("close".to_string(), ModuleFunction {
+            name: "close".to_string(),
+            callback: crate::modules::gps::gps_close,
         });
         
-        math_module.functions.insert("log".to_string(), ModuleFunction {
-            name: "log".to_string(),
-            callback: crate::modules::math::log,
+        self.modules.insert("GPS".to_string(), gps_module);
+        
+        // Stream module: streams sent from other machines with `value.send_to("host:port")`
+        let mut stream_module = Module {
+            name: "Stream".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        stream_module.functions.insert("receive".to_string(), ModuleFunction {
+            name: "receive".to_string(),
+            callback: crate::modules::network::stream_receive,
         });
         
-        math_module.functions.insert("exp".to_string(), ModuleFunction {
-            name: "exp".to_string(),
-            callback: crate::modules::math::exp,
+        stream_module.functions.insert("network_stats".to_string(), ModuleFunction {
+            name: "network_stats".to_string(),
+            callback: crate::modules::network::stream_network_stats,
         });
         
-        math_module.functions.insert("tan".to_string(), ModuleFunction {
-            name: "tan".to_string(),
-            callback: crate::modules::math::tan,
+        stream_module.functions.insert("close".to_string(), ModuleFunction {
+            name: "close".to_string(),
+            callback: crate::modules::network::stream_close,
         });
         
-        math_module.functions.insert("clamp".to_string(), ModuleFunction {
-            name: "clamp".to_string(),
-            callback: crate::modules::math::clamp,
+        self.modules.insert("Stream".to_string(), stream_module);
+        
+        // Text module: timed lyrics and subtitles, karaoke style
+        let mut text_module = Module {
+            name: "Text".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        text_module.functions.insert("load_lyrics".to_string(), ModuleFunction {
+            name: "load_lyrics".to_string(),
+            callback: crate::modules::text::text_load_lyrics,
         });
         
-        math_module.functions.insert("lerp".to_string(), ModuleFunction {
-            name: "lerp".to_string(),
-            callback: crate::modules::math::lerp,
+        text_module.functions.insert("lyric".to_string(), ModuleFunction {
+            name: "lyric".to_string(),
+            callback: crate::modules::text::text_lyric,
         });
         
-        self.modules.insert("Math".to_string(), math_module);
+        text_module.functions.insert("karaoke".to_string(), ModuleFunction {
+            name: "karaoke".to_string(),
+            callback: crate::modules::text::text_karaoke,
+        });
         
-        // GUI module
-        let mut gui_module = Module {
-            name: "GUI".to_string(),
+        self.modules.insert("Text".to_string(), text_module);
+        
+        // Events module: timestamped notes and triggers, apart from continuous streams
+        let mut events_module = Module {
+            name: "Events".to_string(),
             functions: HashMap::new(),
         };
         
-        gui_module.functions.insert("window".to_string(), ModuleFunction {
-            name: "window".to_string(),
-            callback: crate::modules::gui::window,
+        events_module.functions.insert("from".to_string(), ModuleFunction {
+            name: "from".to_string(),
+            callback: crate::modules::events::events_from,
         });
         
-        gui_module.functions.insert("button".to_string(), ModuleFunction {
-            name: "button".to_string(),
-            callback: crate::modules::gui::button,
+        events_module.functions.insert("merge".to_string(), ModuleFunction {
+            name: "merge".to_string(),
+            callback: crate::modules::events::events_merge,
         });
         
-        gui_module.functions.insert("slider".to_string(), ModuleFunction {
-            name: "slider".to_string(),
-            callback: crate::modules::gui::slider,
+        self.modules.insert("Events".to_string(), events_module);
+        
+        // Web module: the script's own endpoints
+        let mut web_module = Module {
+            name: "Web".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        web_module.functions.insert("serve".to_string(), ModuleFunction {
+            name: "serve".to_string(),
+            callback: crate::modules::web::web_serve,
         });
         
-        gui_module.functions.insert("checkbox".to_string(), ModuleFunction {
-            name: "checkbox".to_string(),
-            callback: crate::modules::gui::checkbox,
+        web_module.functions.insert("export_webapp".to_string(), ModuleFunction {
+            name: "export_webapp".to_string(),
+            callback: crate::modules::web::export_webapp,
         });
         
-        gui_module.functions.insert("dropdown".to_string(), ModuleFunction {
-            name: "dropdown".to_string(),
-            callback: crate::modules::gui::dropdown,
+        self.modules.insert("Web".to_string(), web_module);
+        
+        // Environment module: the day, the sky and the weather outside
+        let mut environment_module = Module {
+            name: "Environment".to_string(),
+            functions: HashMap::new(),
+        };
+        
+        environment_module.functions.insert("location".to_string(), ModuleFunction {
+            name: "location".to_string(),
+            callback: crate::modules::environment::environment_location,
         });
         
-        gui_module.functions.insert("control_group".to_string(), ModuleFunction {
-            name: "control_group".to_string(),
-            callback: crate::modules::gui::control_group,
+        environment_module.functions.insert("sun".to_string(), ModuleFunction {
+            name: "sun".to_string(),
+            callback: crate::modules::environment::environment_sun,
         });
         
-        self.modules.insert("GUI".to_string(), gui_module);
+        environment_module.functions.insert("moon".to_string(), ModuleFunction {
+            name: "moon".to_string(),
+            callback: crate::modules::environment::environment_moon,
+        });
         
-        // Generate module
-        let mut generate_module = Module {
-            name: "Generate".to_string(),
+        environment_module.functions.insert("time_of_day".to_string(), ModuleFunction {
+            name: "time_of_day".to_string(),
+            callback: crate::modules::environment::environment_time_of_day,
+        });
+        
+        environment_module.functions.insert("weather".to_string(), ModuleFunction {
+            name: "weather".to_string(),
+            callback: crate::modules::environment::environment_weather,
+        });
+        
+        self.modules.insert("Environment".to_string(), environment_module);
+        
+        // Undo module: take back live parameter changes
+        let mut undo_module = Module {
+            name: "Undo".to_string(),
             functions: HashMap::new(),
         };
         
-        generate_module.functions.insert("l_system".to_string(), ModuleFunction {
-            name: "l_system".to_string(),
-            callback: crate::modules::generate::l_system,
+        undo_module.functions.insert("last".to_string(), ModuleFunction {
+            name: "last".to_string(),
+            callback: crate::modules::undo::undo_last_change,
         });
         
-        generate_module.functions.insert("perlin_noise".to_string(), ModuleFunction {
-            name: "perlin_noise".to_string(),
-            callback: crate::modules::generate::perlin_noise,
+        undo_module.functions.insert("redo".to_string(), ModuleFunction {
+            name: "redo".to_string(),
+            callback: crate::modules::undo::undo_redo,
         });
         
-        generate_module.functions.insert("euclidean".to_string(), ModuleFunction {
-            name: "euclidean".to_string(),
-            callback: crate::modules::generate::euclidean,
+        undo_module.functions.insert("history".to_string(), ModuleFunction {
+            name: "history".to_string(),
+            callback: crate::modules::undo::undo_entries,
         });
         
-        generate_module.functions.insert("fractal_terrain".to_string(), ModuleFunction {
-            name: "fractal_terrain".to_string(),
-            callback: crate::modules::generate::fractal_terrain,
+        undo_module.functions.insert("clear".to_string(), ModuleFunction {
+            name: "clear".to_string(),
+            callback: crate::modules::undo::undo_clear,
         });
         
-        self.modules.insert("Generate".to_string(), generate_module);
+        self.modules.insert("Undo".to_string(), undo_module);
         
-        // Timeline module
-        let mut timeline_module = Module {
-            name: "Timeline".to_string(),
+        // Assets module
+        let mut assets_module = Module {
+            name: "Assets".to_string(),
             functions: HashMap::new(),
         };
         
-        timeline_module.functions.insert("create".to_string(), ModuleFunction {
-            name: "create".to_string(),
-            callback: crate::modules::time::timeline_create,
+        assets_module.functions.insert("load".to_string(), ModuleFunction {
+            name: "load".to_string(),
+            callback: crate::modules::assets::load,
         });
         
-        timeline_module.functions.insert("sequencer".to_string(), ModuleFunction {
-            name: "sequencer".to_string(),
-            callback: crate::modules::time::sequencer_create,
+        assets_module.functions.insert("get".to_string(), ModuleFunction {
+            name: "get".to_string(),
+            callback: crate::modules::assets::get,
         });
         
-        timeline_module.functions.insert("animation_curve".to_string(), ModuleFunction {
-            name: "animation_curve".to_string(),
-            callback: crate::modules::time::animation_curve_create,
+        assets_module.functions.insert("reload".to_string(), ModuleFunction {
+            name: "reload".to_string(),
+            callback: crate::modules::assets::reload,
         });
         
-        timeline_module.functions.insert("every".to_string(), ModuleFunction {
-            name: "every".to_string(),
-            callback: crate::modules::time::every,
+        assets_module.functions.insert("unload".to_string(), ModuleFunction {
+            name: "unload".to_string(),
+            callback: crate::modules::assets::unload,
         });
         
-        timeline_module.functions.insert("after".to_string(), ModuleFunction {
-            name: "after".to_string(),
-            callback: crate::modules::time::after,
+        assets_module.functions.insert("progress".to_string(), ModuleFunction {
+            name: "progress".to_string(),
+            callback: crate::modules::assets::progress,
         });
         
-        timeline_module.functions.insert("sequence".to_string(), ModuleFunction {
-            name: "sequence".to_string(),
-            callback: crate::modules::time::sequence,
+        assets_module.functions.insert("watch".to_string(), ModuleFunction {
+            name: "watch".to_string(),
+            callback: crate::modules::assets::watch,
         });
         
-        timeline_module.functions.insert("now".to_string(), ModuleFunction {
-            name: "now".to_string(),
-            callback: crate::modules::time::now,
+        assets_module.functions.insert("changed".to_string(), ModuleFunction {
+            name: "changed".to_string(),
+            callback: crate::modules::assets::changed,
         });
         
-        timeline_module.functions.insert("delta_time".to_string(), ModuleFunction {
-            name: "delta_time".to_string(),
-            callback: crate::modules::time::delta_time,
+        // Resource cleanup
+        assets_module.functions.insert("stats".to_string(), ModuleFunction {
+            name: "stats".to_string(),
+            callback: crate::modules::assets::stats,
         });
         
-        timeline_module.functions.insert("fps".to_string(), ModuleFunction {
-            name: "fps".to_string(),
-            callback: crate::modules::time::fps,
+        assets_module.functions.insert("cleanup".to_string(), ModuleFunction {
+            name: "cleanup".to_string(),
+            callback: crate::modules::assets::cleanup,
         });
         
-        self.modules.insert("Timeline".to_string(), timeline_module);
+        self.modules.insert("Assets".to_string(), assets_module);
     }
 }
 
@@ -1037,4 +4324,121 @@ impl Default for Interpreter {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// Named values modules provide, like `Graphics.black`
+fn module_constant(module: &str, name: &str) -> Option<Value> {
+    match (module, name) {
+        ("Graphics", "black") => Some(Value::Integer(0x000000)),
+        ("Graphics", "white") => Some(Value::Integer(0xFFFFFF)),
+        ("Graphics", "neon") => Some(Value::String("neon".to_string())),
+        _ => None,
+    }
+}
+
+/// The integers a range counts through. An inclusive one ends on `end` itself rather than
+/// stopping before `end + 1`, which wouldn't fit for `..=i64::MAX`
+fn range_values(start: i64, end: i64, inclusive: bool) -> impl Iterator<Item = Value> {
+    let last = Some(end).filter(|&end| inclusive && start <= end);
+    (start..end).chain(last).map(Value::Integer)
+}
+
+/// Setting a name declared with `const` again
+fn constant_error(name: &str) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::InvalidExpression,
+        format!("📌 '{}' is a constant, so it can't be changed after it's set", name)
+    )
+    .with_suggestion(format!("If it needs to change, declare it without const: {} = ...", name))
+    .with_suggestion("Or give the changed value a name of its own")
+    .with_docs("https://synthesis-lang.org/docs/variables#mutability")
+}
+
+fn unknown_field<'a>(name: &str, field: &str, known: impl Iterator<Item = &'a String>) -> crate::errors::SynthesisError {
+    let mut known: Vec<&str> = known.map(String::as_str).collect();
+    known.sort_unstable();
+    crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("🧱 {} has no field called {}", name, field)
+    )
+    .with_suggestion(format!("Its fields are: {}", known.join(", ")))
+}
+
+/// `player.position` as `("player", ["position"])`, for writing a changed value back where it came from
+fn field_path(expr: &Expression) -> Option<(String, Vec<String>)> {
+    match expr {
+        Expression::Identifier(name) => Some((name.clone(), Vec::new())),
+        Expression::MethodCall { object, method, args, named_args, .. } if args.is_empty() && named_args.is_empty() => {
+            let (root, mut path) = field_path(object)?;
+            path.push(method.clone());
+            Some((root, path))
+        }
+        _ => None,
+    }
+}
+
+/// What a `catch (err)` block sees: `err.message`, `err.kind` and `err.suggestions`
+fn error_value(error: &crate::errors::SynthesisError) -> Value {
+    let mut fields = HashMap::new();
+    fields.insert("message".to_string(), Value::String(error.message.clone()));
+    fields.insert("kind".to_string(), Value::String(format!("{:?}", error.kind)));
+    fields.insert("suggestions".to_string(), Value::Array(error.suggestions.iter().cloned().map(Value::String).collect()));
+    Value::Object(fields)
+}
+
+/// Set the field at the end of `path`; objects grow new fields, structs only have the ones they were defined with
+fn set_field(value: &mut Value, path: &[String], new_value: Value) -> crate::Result<()> {
+    let Some((field, rest)) = path.split_first() else {
+        *value = new_value;
+        return Ok(());
+    };
+    let slot = match value {
+        Value::Struct { name, fields } if !fields.contains_key(field) => return Err(unknown_field(name, field, fields.keys())),
+        Value::Struct { fields, .. } | Value::Object(fields) => fields.entry(field.clone()).or_insert(Value::Null),
+        other => return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🧱 Can't set .{} on a {}", field, other.type_name())
+        )
+        .with_suggestion("Only structs and objects have fields")),
+    };
+    set_field(slot, rest, new_value)
+}
+
+/// Run a function of an imported file inside its own interpreter; `namespace` names it in errors
+fn call_in_module(module: &ImportedModule, namespace: &str, name: &str, args: Vec<Value>, named: HashMap<String, Value>) -> crate::Result<Value> {
+    let mut module = module.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match module.functions.get(name).cloned() {
+        Some(function) => module.call_user_function(&function, args, named),
+        None if module.functions.is_empty() && !module.imports.is_empty() => {
+            let files = sorted_names(module.imports.keys());
+            let first = files.split(", ").next().unwrap_or_default();
+            Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownFunction,
+                format!("📦 {} is a package, so {}() is called on one of its files", namespace, name)
+            )
+            .with_suggestion(format!("It holds {}: try {}.{}.{}()", files, namespace, first, name)))
+        }
+        None => Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::UnknownFunction,
+            format!("📦 {}.{}() isn't defined in the imported file", namespace, name)
+        )
+        .with_suggestion(format!("It has: {}", sorted_names(module.functions.keys())))),
+    }
+}
+
+/// A top-level variable of an imported file, as it is now
+fn read_in_module(module: &ImportedModule, namespace: &str, name: &str) -> crate::Result<Value> {
+    let module = module.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    module.variables.get(name).cloned().ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::UnknownFunction,
+        format!("📦 {} has no variable called {}", namespace, name)
+    )
+    .with_suggestion(format!("It has: {}", sorted_names(module.variables.keys()))))
+}
+
+/// Names for a suggestion, alphabetically
+fn sorted_names<'a>(names: impl Iterator<Item = &'a String>) -> String {
+    let mut names: Vec<&str> = names.map(String::as_str).collect();
+    names.sort_unstable();
+    names.join(", ")
+}
@@ -0,0 +1,66 @@
+/// Structured logging for the runtime, built on `tracing`
+///
+/// Levels are set per subsystem with `SYNTHESIS_LOG`, using the usual filter syntax:
+///
+/// ```text
+/// SYNTHESIS_LOG=streams=debug,audio=info
+/// SYNTHESIS_LOG=debug
+/// ```
+///
+/// `SYNTHESIS_LOG_FORMAT=json` switches to one JSON object per line for log shippers.
+/// Targets in use: `interpreter`, `streams`, `scheduler`, `audio`, `assets`, `quality`, `metrics`, `remote`.
+use tracing_subscriber::{fmt, EnvFilter};
+
+pub const LOG_ENV: &str = "SYNTHESIS_LOG";
+pub const LOG_FORMAT_ENV: &str = "SYNTHESIS_LOG_FORMAT";
+
+/// Warnings everywhere, plus the runtime's own notices (quality changes, cleanup)
+const DEFAULT_FILTER: &str = "warn,interpreter=info,quality=info,assets=info,metrics=info,remote=info";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Self {
+        match std::env::var(LOG_FORMAT_ENV) {
+            Ok(format) if format.eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Pretty,
+        }
+    }
+}
+
+/// Build the filter from `SYNTHESIS_LOG`, falling back to the defaults if it's unset or invalid
+pub fn log_filter(spec: Option<&str>) -> EnvFilter {
+    match spec {
+        Some(spec) if !spec.trim().is_empty() => EnvFilter::try_new(spec).unwrap_or_else(|error| {
+            eprintln!("🪵 Ignoring {}={}: {}", LOG_ENV, spec, error);
+            EnvFilter::new(DEFAULT_FILTER)
+        }),
+        _ => EnvFilter::new(DEFAULT_FILTER),
+    }
+}
+
+/// Install the global subscriber; safe to call more than once (later calls are ignored)
+pub fn init_logging() {
+    let spec = std::env::var(LOG_ENV).ok();
+    let filter = log_filter(spec.as_deref());
+
+    // Logs go to stderr so they never mix with a script's own output
+    let result = match LogFormat::from_env() {
+        LogFormat::Json => fmt()
+            .json()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .with_current_span(true)
+            .try_init(),
+        LogFormat::Pretty => fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .with_target(true)
+            .try_init(),
+    };
+    let _ = result;
+}
@@ -0,0 +1,176 @@
+/// Embedded monitoring endpoint: Prometheus metrics on `/metrics` and a liveness check on
+/// `/health`, so galleries can watch Synthesis instances with their existing monitoring
+use crate::runtime::frame_pacing::FrameTick;
+use crate::runtime::streams::PerformanceMetrics;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// No frame for this long and /health reports the instance as stalled
+const STALL_THRESHOLD: Duration = Duration::from_secs(5);
+
+// Latest runtime numbers, written by the frame loop and read by the HTTP thread
+static METRICS: OnceLock<Mutex<MetricsSnapshot>> = OnceLock::new();
+
+fn metrics_state() -> &'static Mutex<MetricsSnapshot> {
+    METRICS.get_or_init(|| Mutex::new(MetricsSnapshot::new()))
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsSnapshot {
+    pub started: Instant,
+    pub last_frame: Option<Instant>,
+    pub fps: f32,
+    pub frames_rendered: u64,
+    pub dropped_frames: u64,
+    pub buffer_underruns: u64,
+    pub buffer_overruns: u64,
+    pub streams_processed: u64,
+    pub processing_time_avg_us: f64,
+    pub processing_time_max_us: u64,
+    pub quality_level: f32,
+    pub script_errors: u64,
+    pub last_error: Option<String>,
+}
+
+impl MetricsSnapshot {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_frame: None,
+            fps: 0.0,
+            frames_rendered: 0,
+            dropped_frames: 0,
+            buffer_underruns: 0,
+            buffer_overruns: 0,
+            streams_processed: 0,
+            processing_time_avg_us: 0.0,
+            processing_time_max_us: 0,
+            quality_level: 1.0,
+            script_errors: 0,
+            last_error: None,
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.last_frame.map(|t| t.elapsed() > STALL_THRESHOLD).unwrap_or(false)
+    }
+
+    /// Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value));
+        };
+
+        metric("synthesis_uptime_seconds", "gauge", "Seconds since the runtime started", format!("{:.3}", self.uptime().as_secs_f64()));
+        metric("synthesis_fps", "gauge", "Smoothed frames per second", format!("{:.2}", self.fps));
+        metric("synthesis_frames_rendered_total", "counter", "Frames rendered", self.frames_rendered.to_string());
+        metric("synthesis_dropped_frames_total", "counter", "Frames that missed their budget", self.dropped_frames.to_string());
+        metric("synthesis_buffer_underruns_total", "counter", "Stream buffer underruns", self.buffer_underruns.to_string());
+        metric("synthesis_buffer_overruns_total", "counter", "Stream buffer overruns", self.buffer_overruns.to_string());
+        metric("synthesis_streams_processed_total", "counter", "Stream tasks processed", self.streams_processed.to_string());
+        metric("synthesis_processing_time_avg_microseconds", "gauge", "Average stream processing time", format!("{:.1}", self.processing_time_avg_us));
+        metric("synthesis_processing_time_max_microseconds", "gauge", "Worst stream processing time", self.processing_time_max_us.to_string());
+        metric("synthesis_quality_level", "gauge", "Adaptive quality level (0-1)", format!("{:.2}", self.quality_level));
+        metric("synthesis_script_errors_total", "counter", "Errors raised by the running script", self.script_errors.to_string());
+        out
+    }
+
+    pub fn health_json(&self) -> String {
+        let status = if self.is_stalled() { "stalled" } else { "ok" };
+        let last_error = match &self.last_error {
+            Some(error) => format!("\"{}\"", error.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"status\":\"{}\",\"uptime_seconds\":{:.1},\"fps\":{:.1},\"frames\":{},\"script_errors\":{},\"last_error\":{}}}",
+            status, self.uptime().as_secs_f64(), self.fps, self.frames_rendered, self.script_errors, last_error
+        )
+    }
+}
+
+pub fn metrics_snapshot() -> MetricsSnapshot {
+    metrics_state().lock().map(|m| m.clone()).unwrap_or_else(|_| MetricsSnapshot::new())
+}
+
+/// Called once per frame from the interpreter loop
+pub fn record_runtime_metrics(performance: &PerformanceMetrics, tick: &FrameTick) {
+    if let Ok(mut m) = metrics_state().lock() {
+        m.last_frame = Some(Instant::now());
+        m.fps = tick.fps;
+        m.frames_rendered = performance.frames_rendered;
+        m.dropped_frames = performance.dropped_frames;
+        m.buffer_underruns = performance.buffer_underruns;
+        m.buffer_overruns = performance.buffer_overruns;
+        m.streams_processed = performance.streams_processed;
+        m.processing_time_avg_us = performance.processing_time_avg_us;
+        m.processing_time_max_us = performance.processing_time_max_us;
+        m.quality_level = crate::runtime::quality_governor::current_quality().level;
+    }
+}
+
+pub fn record_script_error(error: &crate::errors::SynthesisError) {
+    if let Ok(mut m) = metrics_state().lock() {
+        m.script_errors += 1;
+        m.last_error = Some(error.message.clone());
+    }
+}
+
+/// Serves /metrics and /health on a background thread for the life of the process
+pub struct MetricsServer {
+    address: SocketAddr,
+}
+
+impl MetricsServer {
+    /// Bind to a port (localhost only) or a full address like `0.0.0.0:9100`
+    pub fn start(bind: &str) -> std::io::Result<Self> {
+        let address = if bind.contains(':') { bind.to_string() } else { format!("127.0.0.1:{}", bind) };
+        let listener = TcpListener::bind(&address)?;
+        let address = listener.local_addr()?;
+
+        std::thread::Builder::new()
+            .name("synthesis-metrics".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    if let Err(error) = Self::handle(stream) {
+                        tracing::debug!(target: "metrics", %error, "metrics request failed");
+                    }
+                }
+            })?;
+
+        tracing::info!(target: "metrics", %address, "📈 metrics endpoint listening on /metrics and /health");
+        Ok(Self { address })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    fn handle(mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let snapshot = metrics_snapshot();
+        let (status, content_type, body) = match path {
+            "/metrics" => ("200 OK", "text/plain; version=0.0.4", snapshot.to_prometheus()),
+            // Load balancers and uptime checks only look at the status code
+            "/health" if snapshot.is_stalled() => ("503 Service Unavailable", "application/json", snapshot.health_json()),
+            "/health" => ("200 OK", "application/json", snapshot.health_json()),
+            _ => ("404 Not Found", "text/plain", "Try /metrics or /health\n".to_string()),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status, content_type, body.len(), body
+        )
+    }
+}
@@ -7,6 +7,36 @@ pub mod realtime_optimizations;
 pub mod stream_composition;
 pub mod creative_api;
 pub mod creative_types;
+pub mod frame_pacing;
+pub mod quality_governor;
+pub mod assets;
+pub mod resources;
+pub mod supervisor;
+pub mod logging;
+pub mod metrics;
+pub mod remote_control;
+pub mod capabilities;
+pub mod sandbox;
+pub mod artifacts;
+pub mod gallery;
+pub mod tutorial;
+pub mod stream_tap;
+pub mod interpolation;
+pub mod rolling;
+pub mod events;
+pub mod gestures;
+pub mod network_streams;
+pub mod plugins;
+pub mod wasm_extensions;
+pub mod panic;
+pub mod undo;
+pub mod web_server;
+pub mod imports;
+pub mod astronomy;
+pub mod weather;
+pub mod vectors;
+pub mod complex;
+pub mod coroutines;
 
 #[cfg(test)]
 mod stream_primitives_test;
@@ -17,9 +47,6 @@ mod realtime_buffer_test;
 #[cfg(test)]
 mod performance_test;
 
-#[cfg(test)]
-mod realtime_safety_tests;
-
 pub use interpreter::*;
 pub use streams::*;
 pub use types::*;
@@ -27,4 +54,34 @@ pub use units::*;
 pub use realtime_buffer::*;
 pub use stream_composition::*;
 pub use creative_api::*;
-pub use creative_types::*;
\ No newline at end of file
+pub use creative_types::*;
+// Both define a `ScaleType`; the composer's keeps the root note out of the scale
+pub use creative_api::ScaleType;
+pub use frame_pacing::*;
+pub use quality_governor::*;
+pub use assets::*;
+pub use resources::*;
+pub use supervisor::*;
+pub use logging::*;
+pub use metrics::*;
+pub use remote_control::*;
+pub use capabilities::*;
+pub use sandbox::*;
+pub use artifacts::*;
+pub use gallery::*;
+pub use tutorial::*;
+pub use stream_tap::*;
+pub use interpolation::*;
+pub use rolling::*;
+pub use events::{Event, EventStream};
+pub use gestures::{Gesture, ShakeDetector, Swipe, SwipeDetector, TapDetector, GESTURE_METHODS};
+pub use network_streams::{network_receivers, JitterBuffer, NetworkSend, NetworkStats, Payload, StreamPacket};
+pub use plugins::{load_plugin, register_plugin, NativeFunction, SynthesisModule};
+pub use wasm_extensions::{load_extension, wasm_extensions, WasmExtensions};
+pub use panic::*;
+pub use undo::*;
+pub use web_server::*;
+pub use imports::*;
+pub use astronomy::*;
+pub use weather::*;
+pub use coroutines::{Coroutine, Frame};
\ No newline at end of file
@@ -0,0 +1,474 @@
+/// Streams sent between machines over UDP, so a sensor box can feed the main render machine
+///
+/// `level.send_to("192.168.1.20:7000")` sends a value or stream every frame, and
+/// `Stream.receive(7000)` on the other machine turns what arrives into a stream of its own.
+/// Packets are small and describe themselves: the stream's name and kind, a sequence number and
+/// the sender's clock, then the samples as little-endian f32s. A jitter buffer holds packets for a
+/// moment so ones that arrive out of order go back in order; late and duplicate ones are dropped.
+/// With `codec: "opus"` audio goes compressed instead, one 10ms Opus frame per packet, and lost
+/// frames are rebuilt from the next packet's error correction where it has some.
+use crate::audio::opus::{OpusDecoder, OpusEncoder, OpusSettings, MAX_CONCEALED_FRAMES};
+use crate::runtime::types::DataType;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::net::UdpSocket;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const PACKET_MAGIC: [u8; 2] = *b"SY";
+const PACKET_VERSION: u8 = 2;
+/// Samples per packet, keeping packets inside a 1500-byte Ethernet frame
+pub const MAX_PACKET_SAMPLES: usize = 320;
+/// How long packets wait for stragglers before they play
+pub const DEFAULT_JITTER_SECONDS: f64 = 0.02;
+
+/// The stream a port's packets become, or one named stream from it
+pub fn network_stream_name(port: u16, stream: Option<&str>) -> String {
+    match stream {
+        Some(stream) => format!("net.{}.{}", port, stream),
+        None => format!("net.{}", port),
+    }
+}
+
+fn kind_byte(kind: &DataType) -> u8 {
+    match kind {
+        DataType::Audio => 0,
+        DataType::Control => 1,
+        DataType::Visual => 2,
+        DataType::MIDI => 3,
+        DataType::Generic => 4,
+    }
+}
+
+fn kind_from_byte(byte: u8) -> Option<DataType> {
+    Some(match byte {
+        0 => DataType::Audio,
+        1 => DataType::Control,
+        2 => DataType::Visual,
+        3 => DataType::MIDI,
+        4 => DataType::Generic,
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload {
+    Samples(Vec<f32>),
+    /// One Opus frame, decoded back into samples before it reaches a stream
+    Opus(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamPacket {
+    pub stream: String,
+    pub kind: DataType,
+    pub sample_rate: Option<f32>,
+    pub sequence: u32,
+    /// Seconds on the sender's clock when the packet left
+    pub timestamp: f64,
+    pub payload: Payload,
+}
+
+impl StreamPacket {
+    /// The samples carried, or none while they're still Opus
+    pub fn samples(&self) -> &[f32] {
+        match &self.payload {
+            Payload::Samples(samples) => samples,
+            Payload::Opus(_) => &[],
+        }
+    }
+
+    /// Magic, version, kind, codec, sequence, timestamp, sample rate (0 for none), name, then
+    /// the sample count and samples, or the byte count and Opus frame
+    pub fn encode(&self) -> Vec<u8> {
+        let name = &self.stream.as_bytes()[..self.stream.len().min(u8::MAX as usize)];
+        let (codec, count, body) = match &self.payload {
+            Payload::Samples(samples) => (0, samples.len(), samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()),
+            Payload::Opus(frame) => (1, frame.len(), frame.clone()),
+        };
+        let mut bytes = Vec::with_capacity(24 + name.len() + body.len());
+        bytes.extend_from_slice(&PACKET_MAGIC);
+        bytes.push(PACKET_VERSION);
+        bytes.push(kind_byte(&self.kind));
+        bytes.push(codec);
+        bytes.extend_from_slice(&self.sequence.to_le_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.unwrap_or(0.0).to_le_bytes());
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name);
+        bytes.extend_from_slice(&(count as u16).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    /// None for anything that isn't a whole packet of ours
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut at = 0;
+        let mut take = |count: usize| -> Option<&[u8]> {
+            let slice = bytes.get(at..at + count)?;
+            at += count;
+            Some(slice)
+        };
+        if take(2)? != PACKET_MAGIC || take(1)?[0] != PACKET_VERSION {
+            return None;
+        }
+        let kind = kind_from_byte(take(1)?[0])?;
+        let codec = take(1)?[0];
+        let sequence = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        let timestamp = f64::from_le_bytes(take(8)?.try_into().ok()?);
+        let sample_rate = f32::from_le_bytes(take(4)?.try_into().ok()?);
+        let name_length = take(1)?[0] as usize;
+        let stream = String::from_utf8(take(name_length)?.to_vec()).ok()?;
+        let count = u16::from_le_bytes(take(2)?.try_into().ok()?) as usize;
+        let payload = match codec {
+            0 => Payload::Samples(take(count * 4)?
+                .chunks_exact(4)
+                .map(|sample| f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]))
+                .collect()),
+            1 => Payload::Opus(take(count)?.to_vec()),
+            _ => return None,
+        };
+        Some(Self { stream, kind, sample_rate: (sample_rate > 0.0).then_some(sample_rate), sequence, timestamp, payload })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkStats {
+    pub received: u64,
+    /// Packets that never turned up in time
+    pub lost: u64,
+    /// Packets that arrived after later ones had already played, or twice
+    pub late: u64,
+    /// Lost Opus frames rebuilt from the error correction in the packet after them
+    pub recovered: u64,
+    /// Smoothed variation in transit time, in seconds (RFC 3550's estimate)
+    pub jitter: f64,
+}
+
+/// Puts one stream's packets back in order, waiting `delay` seconds for any that are missing
+#[derive(Debug, Clone, Default)]
+pub struct JitterBuffer {
+    pub delay: f64,
+    /// Waiting packets by sequence number, with when each arrived
+    pending: BTreeMap<u32, (f64, StreamPacket)>,
+    next: Option<u32>,
+    last_transit: Option<f64>,
+    pub stats: NetworkStats,
+}
+
+impl JitterBuffer {
+    pub fn new(delay: f64) -> Self {
+        Self { delay: delay.max(0.0), ..Self::default() }
+    }
+
+    /// Take a packet that arrived at `arrived` seconds on this machine's clock
+    pub fn push(&mut self, packet: StreamPacket, arrived: f64) {
+        if self.next.is_some_and(|next| packet.sequence < next) || self.pending.contains_key(&packet.sequence) {
+            self.stats.late += 1;
+            return;
+        }
+        // The clocks differ by an unknown offset, but changes in transit time are still jitter
+        let transit = arrived - packet.timestamp;
+        if let Some(last) = self.last_transit.replace(transit) {
+            self.stats.jitter += ((transit - last).abs() - self.stats.jitter) / 16.0;
+        }
+        self.stats.received += 1;
+        self.pending.insert(packet.sequence, (arrived, packet));
+    }
+
+    /// Packets due by `now`, in order. The next in sequence plays once it has waited out the
+    /// delay; a gap is given up on when the packet after it has waited that long
+    pub fn pop_ready(&mut self, now: f64) -> Vec<StreamPacket> {
+        self.pop_ready_after_losses(now).into_iter().map(|(_, packet)| packet).collect()
+    }
+
+    /// Like `pop_ready()`, with how many packets went missing just before each
+    pub fn pop_ready_after_losses(&mut self, now: f64) -> Vec<(u64, StreamPacket)> {
+        let mut ready = Vec::new();
+        while let Some(entry) = self.pending.first_entry() {
+            let (sequence, (arrived, _)) = (*entry.key(), entry.get());
+            if now - arrived < self.delay {
+                break;
+            }
+            let lost = self.next.map(|next| u64::from(sequence - next)).unwrap_or(0);
+            self.stats.lost += lost;
+            self.next = Some(sequence + 1);
+            ready.push((lost, entry.remove().1));
+        }
+        ready
+    }
+}
+
+/// One `Stream.receive()` port: its socket's packets and a jitter buffer per stream heard on it
+#[derive(Debug)]
+struct PortReceiver {
+    packets: Receiver<(StreamPacket, Instant)>,
+    buffers: HashMap<String, JitterBuffer>,
+    delay: f64,
+    /// Names asked for with `Stream.receive(port, name: ...)`; whatever arrives also goes to `net.<port>`
+    named: Vec<String>,
+    /// Opus decoders for the streams sent compressed, or None if one couldn't be made
+    decoders: HashMap<String, Option<OpusDecoder>>,
+}
+
+/// Turn an Opus packet back into samples, first filling in the `lost` frames before it: the
+/// last one from the packet's error correction, any earlier ones with Opus's best guess
+fn decode_opus(decoder: &mut OpusDecoder, frame: &[u8], lost: u64, stats: &mut NetworkStats) -> crate::Result<Vec<f32>> {
+    let mut samples = Vec::new();
+    if lost > 0 {
+        let guessed = (lost as usize - 1).min(MAX_CONCEALED_FRAMES - 1);
+        for _ in 0..guessed {
+            samples.extend(decoder.decode(None, false)?);
+        }
+        samples.extend(decoder.decode(Some(frame), true)?);
+        stats.recovered += 1;
+    }
+    samples.extend(decoder.decode(Some(frame), false)?);
+    Ok(samples)
+}
+
+#[derive(Debug, Default)]
+pub struct NetworkReceivers {
+    ports: HashMap<u16, PortReceiver>,
+    started: Option<Instant>,
+}
+
+impl NetworkReceivers {
+    /// Listen on `port` from now on; listening again only changes the delay and names
+    pub fn listen(&mut self, port: u16, delay: f64, stream: Option<String>) -> crate::Result<()> {
+        self.started.get_or_insert_with(Instant::now);
+        let receiver = match self.ports.entry(port) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let packets = spawn_packet_reader(port)?;
+                tracing::info!(target: "network", port, "📡 receiving streams");
+                entry.insert(PortReceiver { packets, buffers: HashMap::new(), delay, named: Vec::new(), decoders: HashMap::new() })
+            }
+        };
+        receiver.delay = delay;
+        if let Some(stream) = stream.filter(|stream| !receiver.named.contains(stream)) {
+            receiver.named.push(stream);
+        }
+        Ok(())
+    }
+
+    pub fn close(&mut self, port: u16) -> bool {
+        self.ports.remove(&port).is_some()
+    }
+
+    pub fn stats(&self, port: u16) -> Option<NetworkStats> {
+        let receiver = self.ports.get(&port)?;
+        Some(receiver.buffers.values().fold(NetworkStats::default(), |total, buffer| NetworkStats {
+            received: total.received + buffer.stats.received,
+            lost: total.lost + buffer.stats.lost,
+            late: total.late + buffer.stats.late,
+            recovered: total.recovered + buffer.stats.recovered,
+            jitter: total.jitter.max(buffer.stats.jitter),
+        }))
+    }
+
+    /// Packets that are due, each with the local stream names it goes to
+    pub fn poll(&mut self) -> Vec<(Vec<String>, StreamPacket)> {
+        let Some(started) = self.started else {
+            return Vec::new();
+        };
+        let now = started.elapsed().as_secs_f64();
+        let mut ready = Vec::new();
+        let mut closed = Vec::new();
+        for (&port, receiver) in &mut self.ports {
+            loop {
+                match receiver.packets.try_recv() {
+                    Ok((packet, arrived)) => {
+                        let arrived = arrived.saturating_duration_since(started).as_secs_f64();
+                        let delay = receiver.delay;
+                        receiver.buffers.entry(packet.stream.clone()).or_insert_with(|| JitterBuffer::new(delay)).push(packet, arrived);
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        closed.push(port);
+                        break;
+                    }
+                }
+            }
+            for (stream, buffer) in receiver.buffers.iter_mut() {
+                buffer.delay = receiver.delay;
+                for (lost, mut packet) in buffer.pop_ready_after_losses(now) {
+                    if let Payload::Opus(frame) = &packet.payload {
+                        // A stream that can't be decoded is reported once, then skipped
+                        let decoder = receiver.decoders.entry(stream.clone()).or_insert_with(|| {
+                            OpusDecoder::new(packet.sample_rate.unwrap_or(0.0))
+                                .map_err(|error| tracing::warn!(target: "network", port, stream = stream.as_str(), %error, "📡 can't decode Opus stream"))
+                                .ok()
+                        });
+                        let Some(decoder) = decoder.as_mut() else {
+                            continue;
+                        };
+                        match decode_opus(decoder, frame, lost, &mut buffer.stats) {
+                            Ok(samples) => packet.payload = Payload::Samples(samples),
+                            Err(error) => {
+                                tracing::warn!(target: "network", port, stream = stream.as_str(), %error, "📡 Opus frame didn't decode");
+                                continue;
+                            }
+                        }
+                    }
+                    let mut names = vec![network_stream_name(port, None)];
+                    if receiver.named.contains(&packet.stream) {
+                        names.push(network_stream_name(port, Some(&packet.stream)));
+                    }
+                    ready.push((names, packet));
+                }
+            }
+        }
+        for port in closed {
+            tracing::warn!(target: "network", port, "📡 stream receiver stopped");
+            self.ports.remove(&port);
+        }
+        ready
+    }
+}
+
+/// Decode packets arriving on `port` on a thread of its own, until nobody's listening
+fn spawn_packet_reader(port: u16) -> crate::Result<Receiver<(StreamPacket, Instant)>> {
+    let socket = UdpSocket::bind(("0.0.0.0", port)).map_err(|error| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::StreamConnectionError,
+        format!("📡 Couldn't listen for streams on port {}: {}", port, error)
+    )
+    .with_suggestion("Another program (or another Stream.receive) may have the port; try a different one"))?;
+    socket.set_read_timeout(Some(Duration::from_millis(250)))?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::Builder::new()
+        .name(format!("synthesis-net-{}", port))
+        .spawn(move || {
+            let mut buffer = [0u8; 65536];
+            loop {
+                let size = match socket.recv(&mut buffer) {
+                    Ok(size) => size,
+                    Err(error) if matches!(error.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted) => continue,
+                    Err(error) => {
+                        tracing::warn!(target: "network", port, %error, "📡 stream receive failed");
+                        return;
+                    }
+                };
+                let Some(packet) = StreamPacket::decode(&buffer[..size]) else {
+                    continue;
+                };
+                if sender.send((packet, Instant::now())).is_err() {
+                    return;
+                }
+            }
+        })?;
+    Ok(receiver)
+}
+
+static RECEIVERS: OnceLock<Mutex<NetworkReceivers>> = OnceLock::new();
+
+pub fn network_receivers() -> &'static Mutex<NetworkReceivers> {
+    RECEIVERS.get_or_init(|| Mutex::new(NetworkReceivers::default()))
+}
+
+/// A value or stream sent somewhere every frame, from `level.send_to("host:port")`
+#[derive(Debug)]
+pub struct NetworkSend {
+    /// The variable or stream sent
+    pub source: String,
+    /// The name the other end sees
+    pub stream: String,
+    pub address: String,
+    /// Compress audio with Opus, from `codec: "opus"`
+    pub opus: Option<OpusSettings>,
+    socket: UdpSocket,
+    sequence: u32,
+    started: Instant,
+    encoder: Option<OpusEncoder>,
+    /// Audio waiting to fill an Opus frame
+    pending: Vec<f32>,
+}
+
+impl NetworkSend {
+    pub fn open(source: &str, stream: &str, address: &str) -> crate::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(address).map_err(|error| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::StreamConnectionError,
+            format!("📡 Couldn't send {} to {}: {}", source, address, error)
+        )
+        .with_suggestion("Give a host and port, like \"192.168.1.20:7000\"")
+        .with_suggestion("The other machine listens with Stream.receive(7000)"))?;
+        Ok(Self {
+            source: source.to_string(),
+            stream: stream.to_string(),
+            address: address.to_string(),
+            opus: None,
+            socket,
+            sequence: 0,
+            started: Instant::now(),
+            encoder: None,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Send audio as Opus; other kinds of stream still go as they are
+    pub fn with_opus(mut self, settings: OpusSettings) -> Self {
+        self.opus = Some(settings);
+        self
+    }
+
+    /// Send `samples` in as many packets as they need. Nobody listening isn't an error on UDP,
+    /// so only local failures are reported
+    pub fn send(&mut self, samples: &[f32], kind: &DataType, sample_rate: Option<f32>) -> crate::Result<()> {
+        if *kind == DataType::Audio && self.opus.is_some() {
+            return self.send_opus(samples, sample_rate);
+        }
+        for chunk in samples.chunks(MAX_PACKET_SAMPLES) {
+            self.send_packet(kind, sample_rate, Payload::Samples(chunk.to_vec()))?;
+        }
+        Ok(())
+    }
+
+    /// Audio in whole Opus frames; what's left over waits for the next call
+    fn send_opus(&mut self, samples: &[f32], sample_rate: Option<f32>) -> crate::Result<()> {
+        let rate = sample_rate.unwrap_or(crate::audio::signal::DEFAULT_SIGNAL_RATE as f32);
+        if self.encoder.is_none() {
+            match OpusEncoder::new(rate, &self.opus.clone().unwrap_or_default()) {
+                Ok(encoder) => self.encoder = Some(encoder),
+                Err(error) => {
+                    // Uncompressed audio beats none at all
+                    tracing::warn!(target: "network", address = %self.address, %error, "📡 sending audio uncompressed");
+                    self.opus = None;
+                    return self.send(samples, &DataType::Audio, sample_rate);
+                }
+            }
+        }
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Ok(());
+        };
+        self.pending.extend_from_slice(samples);
+        let frame_size = encoder.frame_size().max(1);
+        let whole = self.pending.len() / frame_size * frame_size;
+        let frames = self.pending.drain(..whole).collect::<Vec<_>>()
+            .chunks(frame_size)
+            .map(|frame| encoder.encode(frame))
+            .collect::<crate::Result<Vec<_>>>()?;
+        for frame in frames {
+            self.send_packet(&DataType::Audio, Some(rate), Payload::Opus(frame))?;
+        }
+        Ok(())
+    }
+
+    fn send_packet(&mut self, kind: &DataType, sample_rate: Option<f32>, payload: Payload) -> crate::Result<()> {
+        let packet = StreamPacket {
+            stream: self.stream.clone(),
+            kind: kind.clone(),
+            sample_rate,
+            sequence: self.sequence,
+            timestamp: self.started.elapsed().as_secs_f64(),
+            payload,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+        match self.socket.send(&packet.encode()) {
+            Ok(_) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::ConnectionRefused => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
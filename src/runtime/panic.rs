@@ -0,0 +1,41 @@
+/// The panic button, shared by `Audio.panic()` and the Esc hotkey
+///
+/// Fades the master bus to silence, stops every clip, sends MIDI all-notes-off, blacks out
+/// window outputs and stops the transport. `release_panic` brings sound and picture back; the
+/// transport stays stopped until it's started again.
+use crate::audio::device::audio_device;
+use crate::audio::master::{master_bus, PANIC_FADE_SECONDS};
+use crate::audio::midi::{all_notes_off_messages, broadcast_midi};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanicReport {
+    pub fade_seconds: f64,
+    /// MIDI messages actually delivered across every output
+    pub midi_messages: usize,
+}
+
+pub fn trigger_panic() -> PanicReport {
+    master_bus().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).fade_to(0.0, PANIC_FADE_SECONDS);
+    // A device switch mid-crossfade must not fade back in afterwards
+    audio_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).resume_to(0.0);
+    crate::audio::clips::clip_grid().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).halt();
+    let midi_messages = broadcast_midi(&all_notes_off_messages());
+    crate::graphics::renderer::set_blackout(true);
+    crate::modules::time::transport().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).stop();
+    tracing::warn!(target: "interpreter", midi_messages, "🛑 panic: audio fading out, video blacked out, transport stopped");
+    PanicReport { fade_seconds: PANIC_FADE_SECONDS, midi_messages }
+}
+
+pub fn release_panic() {
+    master_bus().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).fade_to(1.0, PANIC_FADE_SECONDS);
+    audio_device().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).resume_to(1.0);
+    // Echoes from before the panic shouldn't come back with the sound
+    if let Some(delay) = crate::audio::effects::master_delay().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+        crate::audio::effects::AudioEffect::reset(delay);
+    }
+    if let Some(reverb) = crate::audio::effects::master_reverb().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_mut() {
+        crate::audio::effects::AudioEffect::reset(reverb);
+    }
+    crate::graphics::renderer::set_blackout(false);
+    tracing::info!(target: "interpreter", "🔈 panic released");
+}
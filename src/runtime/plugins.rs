@@ -0,0 +1,123 @@
+/// Native modules from outside the crate, so new effects and hardware drivers don't need a fork
+///
+/// A plugin implements `SynthesisModule`: a name and a list of functions with the same shape as
+/// the built-in ones. It's either compiled in, with `register_plugin(Lights)` before the
+/// interpreter is created, or built as a `cdylib` that declares its modules with
+/// `synthesis::export_plugin!(Lights)` and is loaded with `synthesis run show.syn --plugin
+/// liblights.so`. Rust has no stable ABI, so a loaded plugin has to be built against the same
+/// Synthesis version with the same compiler; the version is checked, the compiler can't be.
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Bumped whenever `SynthesisModule` or `PluginDeclaration` change shape
+pub const PLUGIN_API_VERSION: u32 = 2;
+/// The Synthesis version a plugin was built against has to match the one loading it
+pub const SYNTHESIS_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The symbol `export_plugin!` defines and `load_plugin` looks for
+pub const PLUGIN_SYMBOL: &[u8] = b"SYNTHESIS_PLUGIN\0";
+
+/// What a plugin function looks like: the positional arguments, then the named ones by name
+pub type NativeFunction = fn(&[Value], &HashMap<String, Value>) -> crate::Result<Value>;
+
+pub trait SynthesisModule: Send + Sync {
+    /// What scripts call it by, like `Lights` for `Lights.flash(0.5)`
+    fn name(&self) -> &str;
+
+    /// Each function's name and the code that runs it
+    fn functions(&self) -> Vec<(&str, NativeFunction)>;
+}
+
+/// What a plugin library exports, through `export_plugin!`
+pub struct PluginDeclaration {
+    pub api_version: u32,
+    pub synthesis_version: &'static str,
+    pub modules: fn() -> Vec<Box<dyn SynthesisModule>>,
+}
+
+/// Declare the modules a plugin library provides:
+/// `synthesis::export_plugin!(Lights, Fog);`
+#[macro_export]
+macro_rules! export_plugin {
+    ($($module:expr),+ $(,)?) => {
+        #[no_mangle]
+        pub static SYNTHESIS_PLUGIN: $crate::runtime::plugins::PluginDeclaration = $crate::runtime::plugins::PluginDeclaration {
+            api_version: $crate::runtime::plugins::PLUGIN_API_VERSION,
+            synthesis_version: $crate::runtime::plugins::SYNTHESIS_VERSION,
+            modules: || vec![$(Box::new($module) as Box<dyn $crate::runtime::plugins::SynthesisModule>),+],
+        };
+    };
+}
+
+#[derive(Default)]
+struct PluginRegistry {
+    modules: Vec<Arc<dyn SynthesisModule>>,
+    /// Loaded libraries stay loaded: their modules' code lives in them
+    #[cfg(feature = "libloading")]
+    libraries: Vec<libloading::Library>,
+}
+
+static PLUGINS: OnceLock<Mutex<PluginRegistry>> = OnceLock::new();
+
+fn registry() -> &'static Mutex<PluginRegistry> {
+    PLUGINS.get_or_init(|| Mutex::new(PluginRegistry::default()))
+}
+
+/// Add a module to every interpreter created from now on
+pub fn register_plugin(module: impl SynthesisModule + 'static) {
+    registry().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).modules.push(Arc::new(module));
+}
+
+/// The modules registered or loaded so far, in that order
+pub fn registered_plugins() -> Vec<Arc<dyn SynthesisModule>> {
+    registry().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).modules.clone()
+}
+
+/// Check a plugin library's declaration and take its modules
+pub fn plugin_modules(path: &Path, declaration: &PluginDeclaration) -> crate::Result<Vec<Box<dyn SynthesisModule>>> {
+    if declaration.api_version != PLUGIN_API_VERSION || declaration.synthesis_version != SYNTHESIS_VERSION {
+        return Err(crate::errors::synthesis_error(
+            crate::errors::ErrorKind::UnknownModule,
+            format!(
+                "🧩 {} was built for Synthesis {} (plugin API {}), but this is {} (plugin API {})",
+                path.display(), declaration.synthesis_version, declaration.api_version, SYNTHESIS_VERSION, PLUGIN_API_VERSION
+            )
+        )
+        .with_suggestion("Rebuild the plugin against this version of Synthesis, with the same Rust compiler"));
+    }
+    Ok((declaration.modules)())
+}
+
+/// Load a plugin library and register its modules; returns their names
+#[cfg(feature = "libloading")]
+pub fn load_plugin(path: &Path) -> crate::Result<Vec<String>> {
+    let not_a_plugin = |reason: String| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::UnknownModule,
+        format!("🧩 Couldn't load the plugin {}: {}", path.display(), reason)
+    )
+    .with_suggestion("Plugins are cdylib crates that declare their modules with synthesis::export_plugin!(...)");
+    // Safety: loading runs the library's initialisers, and the declaration is trusted to be what
+    // export_plugin! wrote; that's the deal with native plugins, which is why only paths the
+    // person running the script names get loaded
+    let library = unsafe { libloading::Library::new(path) }.map_err(|error| not_a_plugin(error.to_string()))?;
+    let modules = {
+        let declaration = unsafe { library.get::<*const PluginDeclaration>(PLUGIN_SYMBOL) }
+            .map_err(|error| not_a_plugin(error.to_string()))?;
+        plugin_modules(path, unsafe { &**declaration })?
+    };
+    let names = modules.iter().map(|module| module.name().to_string()).collect();
+    let mut registry = registry().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    registry.modules.extend(modules.into_iter().map(Arc::from));
+    registry.libraries.push(library);
+    Ok(names)
+}
+
+#[cfg(not(feature = "libloading"))]
+pub fn load_plugin(path: &Path) -> crate::Result<Vec<String>> {
+    Err(crate::errors::synthesis_error(
+        crate::errors::ErrorKind::UnknownModule,
+        format!("🧩 This build of Synthesis can't load plugin libraries like {}", path.display())
+    )
+    .with_suggestion("Rebuild with --features libloading, or compile the module in with register_plugin()"))
+}
@@ -0,0 +1,204 @@
+/// Adaptive quality scaling: watches frame times and trades detail for frame rate under load
+/// so an installation degrades gracefully instead of stuttering mid-show
+use crate::runtime::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+// Current settings, readable from stateless module functions (particle counts, blur radius, ...)
+static QUALITY_STATE: OnceLock<Mutex<QualitySettings>> = OnceLock::new();
+
+fn quality_state() -> &'static Mutex<QualitySettings> {
+    QUALITY_STATE.get_or_init(|| Mutex::new(QualitySettings::from_level(1.0)))
+}
+
+/// Quality settings currently in effect
+pub fn current_quality() -> QualitySettings {
+    quality_state().lock().map(|settings| *settings).unwrap_or_else(|_| QualitySettings::from_level(1.0))
+}
+
+/// Pin the quality level from a script; the governor keeps adjusting from here if enabled
+pub fn set_quality_level(level: f32) -> QualitySettings {
+    let settings = QualitySettings::from_level(level);
+    if let Ok(mut state) = quality_state().lock() {
+        *state = settings;
+    }
+    settings
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QualityTier {
+    Ultra,
+    High,
+    Medium,
+    Low,
+    Minimal,
+}
+
+impl QualityTier {
+    pub fn from_level(level: f32) -> Self {
+        match level {
+            l if l >= 0.9 => QualityTier::Ultra,
+            l if l >= 0.7 => QualityTier::High,
+            l if l >= 0.5 => QualityTier::Medium,
+            l if l >= 0.3 => QualityTier::Low,
+            _ => QualityTier::Minimal,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityTier::Ultra => "ultra",
+            QualityTier::High => "high",
+            QualityTier::Medium => "medium",
+            QualityTier::Low => "low",
+            QualityTier::Minimal => "minimal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualitySettings {
+    /// Overall quality, 0.0 (bare minimum) to 1.0 (everything on)
+    pub level: f32,
+    pub tier: QualityTier,
+    /// Multiplier for particle and instance counts
+    pub particle_scale: f32,
+    /// Render resolution relative to the window size
+    pub resolution_scale: f32,
+    /// Multiplier for post-effect sample counts and radii (bloom, blur, depth of field)
+    pub effect_quality: f32,
+}
+
+impl QualitySettings {
+    pub fn from_level(level: f32) -> Self {
+        let level = level.clamp(0.0, 1.0);
+        Self {
+            level,
+            tier: QualityTier::from_level(level),
+            // Particles go first: they're usually the biggest cost and the least noticed
+            particle_scale: (0.1 + 0.9 * level * level).min(1.0),
+            resolution_scale: (0.5 + 0.5 * level).min(1.0),
+            effect_quality: (0.25 + 0.75 * level).min(1.0),
+        }
+    }
+
+    pub fn scale_count(&self, count: f64) -> f64 {
+        (count * self.particle_scale as f64).round().max(1.0)
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("level".to_string(), Value::Float(self.level as f64));
+        fields.insert("tier".to_string(), Value::String(self.tier.name().to_string()));
+        fields.insert("particle_scale".to_string(), Value::Float(self.particle_scale as f64));
+        fields.insert("resolution_scale".to_string(), Value::Float(self.resolution_scale as f64));
+        fields.insert("effect_quality".to_string(), Value::Float(self.effect_quality as f64));
+        Value::Object(fields)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QualityChange {
+    Reduced(QualitySettings),
+    Restored(QualitySettings),
+}
+
+#[derive(Debug, Clone)]
+pub struct QualityGovernor {
+    pub enabled: bool,
+    pub min_level: f32,
+    /// Frame time budget in seconds
+    budget: f32,
+    frame_times: VecDeque<f32>,
+    window: usize,
+    level: f32,
+    frames_since_change: usize,
+    /// Frames to wait after a change before judging its effect
+    cooldown: usize,
+}
+
+impl QualityGovernor {
+    pub fn new(target_fps: f32) -> Self {
+        Self {
+            enabled: false,
+            min_level: 0.2,
+            budget: 1.0 / target_fps.max(1.0),
+            frame_times: VecDeque::with_capacity(30),
+            window: 30,
+            level: current_quality().level,
+            frames_since_change: 0,
+            cooldown: 30,
+        }
+    }
+
+    pub fn set_target_fps(&mut self, fps: f32) {
+        self.budget = 1.0 / fps.max(1.0);
+    }
+
+    /// Apply `config { adaptive_quality: true, min_quality: 0.3 }`
+    pub fn apply_value(&mut self, value: &Value) {
+        if let Value::Object(fields) = value {
+            if let Some(enabled) = fields.get("adaptive_quality") {
+                self.enabled = enabled.is_truthy();
+            }
+            if let Some(min) = fields.get("min_quality").and_then(|v| v.as_number()) {
+                self.min_level = (min as f32).clamp(0.0, 1.0);
+            }
+            if let Some(quality) = fields.get("quality").and_then(|v| v.as_number()) {
+                self.level = set_quality_level(quality as f32).level;
+                self.frames_since_change = 0;
+            }
+        }
+    }
+
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Feed one frame time (seconds); returns a change when the governor steps quality up or down
+    pub fn record_frame(&mut self, frame_time: f32) -> Option<QualityChange> {
+        if !self.enabled {
+            return None;
+        }
+
+        if self.frame_times.len() == self.window {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.frames_since_change += 1;
+
+        if self.frames_since_change < self.cooldown || self.frame_times.len() < self.window {
+            return None;
+        }
+
+        let average = self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32;
+
+        // Asymmetric thresholds: drop quickly when over budget, restore only with clear headroom
+        if average > self.budget * 1.1 && self.level > self.min_level {
+            let overshoot = (average / self.budget - 1.0).min(1.0);
+            self.change_level(self.level - 0.1 - overshoot * 0.2);
+            Some(QualityChange::Reduced(current_quality()))
+        } else if average < self.budget * 0.7 && self.level < 1.0 {
+            self.change_level(self.level + 0.05);
+            // Restore slowly so we don't oscillate around the threshold
+            self.cooldown = self.window * 4;
+            Some(QualityChange::Restored(current_quality()))
+        } else {
+            None
+        }
+    }
+
+    fn change_level(&mut self, level: f32) {
+        self.level = level.clamp(self.min_level, 1.0);
+        set_quality_level(self.level);
+        self.frames_since_change = 0;
+        self.frame_times.clear();
+        self.cooldown = self.window;
+    }
+}
+
+impl Default for QualityGovernor {
+    fn default() -> Self {
+        Self::new(60.0)
+    }
+}
@@ -1,11 +1,11 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 /// Lock-free circular buffer optimized for real-time audio processing
 /// Provides single-producer, single-consumer (SPSC) access pattern
 #[derive(Debug)]
 pub struct RealtimeCircularBuffer {
-    buffer: Vec<f32>,
+    buffer: Box<[AtomicU32]>, // f32 samples stored as bits so writers only need &self
     capacity: usize,
     write_head: AtomicUsize,
     read_head: AtomicUsize,
@@ -16,7 +16,7 @@ pub struct RealtimeCircularBuffer {
 /// Uses compare-and-swap for thread-safe operations at the cost of some performance
 #[derive(Debug)]
 pub struct MpmcRealtimeBuffer {
-    buffer: Vec<f32>,
+    buffer: Box<[AtomicU32]>, // f32 samples stored as bits so writers only need &self
     capacity: usize,
     write_head: AtomicUsize,
     read_head: AtomicUsize,
@@ -52,7 +52,7 @@ impl RealtimeCircularBuffer {
         }
         
         Ok(RealtimeCircularBuffer {
-            buffer: vec![0.0; size],
+            buffer: (0..size).map(|_| AtomicU32::new(0)).collect(),
             capacity: size,
             write_head: AtomicUsize::new(0),
             read_head: AtomicUsize::new(0),
@@ -72,7 +72,7 @@ impl RealtimeCircularBuffer {
         }
         
         // Write the sample
-        self.buffer[write_pos & self.mask] = sample;
+        self.buffer[write_pos & self.mask].store(sample.to_bits(), Ordering::Relaxed);
         
         // Advance write head (this makes the data visible to readers)
         self.write_head.store((write_pos + 1) & self.mask, Ordering::Release);
@@ -80,6 +80,16 @@ impl RealtimeCircularBuffer {
         true
     }
     
+    /// Write a single sample, reporting a full buffer as an error
+    pub fn write_single(&self, sample: f32) -> Result<(), BufferError> {
+        if self.write(sample) { Ok(()) } else { Err(BufferError::BufferFull) }
+    }
+    
+    /// Read a single sample, reporting an empty buffer as an error
+    pub fn read_single(&self) -> Result<f32, BufferError> {
+        self.read().ok_or(BufferError::BufferEmpty)
+    }
+    
     /// Write multiple samples to the buffer
     /// Returns the number of samples actually written
     pub fn write_slice(&self, samples: &[f32]) -> usize {
@@ -107,7 +117,7 @@ impl RealtimeCircularBuffer {
         }
         
         // Read the sample
-        let sample = self.buffer[read_pos & self.mask];
+        let sample = f32::from_bits(self.buffer[read_pos & self.mask].load(Ordering::Relaxed));
         
         // Advance read head
         self.read_head.store((read_pos + 1) & self.mask, Ordering::Release);
@@ -174,7 +184,7 @@ impl MpmcRealtimeBuffer {
         }
         
         Ok(MpmcRealtimeBuffer {
-            buffer: vec![0.0; size],
+            buffer: (0..size).map(|_| AtomicU32::new(0)).collect(),
             capacity: size,
             write_head: AtomicUsize::new(0),
             read_head: AtomicUsize::new(0),
@@ -202,7 +212,7 @@ impl MpmcRealtimeBuffer {
             ) {
                 Ok(_) => {
                     // Successfully claimed position, write the sample
-                    self.buffer[write_pos & self.mask] = sample;
+                    self.buffer[write_pos & self.mask].store(sample.to_bits(), Ordering::Relaxed);
                     return true;
                 }
                 Err(_) => {
@@ -234,7 +244,7 @@ impl MpmcRealtimeBuffer {
             ) {
                 Ok(_) => {
                     // Successfully claimed position, read the sample
-                    let sample = self.buffer[read_pos & self.mask];
+                    let sample = f32::from_bits(self.buffer[read_pos & self.mask].load(Ordering::Relaxed));
                     return Some(sample);
                 }
                 Err(_) => {
@@ -424,7 +434,7 @@ impl RealtimeBufferPool {
     }
     
     pub fn total_capacity(&self) -> usize {
-        self.buffers.len() * self.buffer_size
+        self.buffers.iter().map(|buffer| buffer.capacity()).sum()
     }
     
     pub fn get_aggregate_stats(&self) -> BufferStats {
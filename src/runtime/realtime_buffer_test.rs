@@ -1,8 +1,9 @@
 #[cfg(test)]
 mod realtime_buffer_tests {
-    use super::*;
+    use crate::runtime::realtime_buffer::*;
+    use std::sync::Arc;
     use std::thread;
-    use std::time::{Duration, Instant};
+    use std::time::Instant;
     
     #[test]
     fn test_realtime_buffer_creation() {
@@ -12,7 +12,7 @@ static GLOBAL_BUFFER_POOL: OnceLock<BufferPool> = OnceLock::new();
 
 /// Get the global buffer pool instance (thread-safe singleton)
 pub fn global_buffer_pool() -> &'static BufferPool {
-    GLOBAL_BUFFER_POOL.get_or_init(|| BufferPool::new())
+    GLOBAL_BUFFER_POOL.get_or_init(BufferPool::new)
 }
 
 /// Lock-free optimization strategies for real-time processing
@@ -46,9 +46,6 @@ pub struct BufferPool {
 pub struct ConnectionCache {
     /// Pre-computed routing tables for hot connections
     routing_table: Arc<parking_lot::RwLock<Vec<RoutingEntry>>>,
-    /// Cache hit/miss counters
-    cache_hits: AtomicUsize,
-    cache_misses: AtomicUsize,
 }
 
 #[derive(Debug, Clone)]
@@ -64,7 +61,7 @@ pub struct RoutingEntry {
 #[derive(Debug)]
 pub struct PerformanceMonitor {
     /// Processing time histogram (lock-free)
-    processing_times: Arc<hdrhistogram::Histogram<u64>>,
+    processing_times: Arc<parking_lot::Mutex<hdrhistogram::Histogram<u64>>>,
     /// Buffer underrun counter
     underruns: AtomicUsize,
     /// Buffer overrun counter
@@ -99,7 +96,7 @@ impl RealtimeOptimizer {
     /// Process connections with real-time constraints (must complete in <1ms for audio)
     pub fn process_realtime_connections(
         &self,
-        engine: &StreamCompositionEngine,
+        _engine: &StreamCompositionEngine,
         stream_manager: &mut StreamManager,
         deadline: Instant,
     ) -> crate::Result<ProcessingResults> {
@@ -140,11 +137,11 @@ impl RealtimeOptimizer {
     fn process_cached_connection(
         &self,
         entry: &RoutingEntry,
-        stream_manager: &mut StreamManager,
+        _stream_manager: &mut StreamManager,
         results: &mut ProcessingResults,
     ) -> crate::Result<()> {
         // Get buffer from pool (no allocation)
-        let mut buffer = self.buffer_pool.get_small_buffer();
+        let buffer = self.buffer_pool.get_small_buffer();
         
         // Fast path for simple gain-only connections
         if entry.delay_samples == 0 && entry.transform_id.is_none() {
@@ -161,10 +158,9 @@ impl RealtimeOptimizer {
     
     fn precompute_routing_tables(&self, engine: &StreamCompositionEngine) -> crate::Result<()> {
         let mut routing_entries = Vec::new();
-        let mut source_id = 0u32;
         
         // Convert all connections to cache-friendly format
-        for (source_name, connections) in &engine.connections {
+        for (source_id, connections) in (0u32..).zip(engine.connections.values()) {
             for connection in connections {
                 let entry = RoutingEntry {
                     source_id,
@@ -175,7 +171,6 @@ impl RealtimeOptimizer {
                 };
                 routing_entries.push(entry);
             }
-            source_id += 1;
         }
         
         // Sort by processing priority and cache locality
@@ -200,6 +195,12 @@ impl RealtimeOptimizer {
     }
 }
 
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BufferPool {
     pub fn new() -> Self {
         let small_buffers = crossbeam::queue::SegQueue::new();
@@ -309,8 +310,6 @@ impl ConnectionCache {
     fn new() -> Self {
         Self {
             routing_table: Arc::new(parking_lot::RwLock::new(Vec::new())),
-            cache_hits: AtomicUsize::new(0),
-            cache_misses: AtomicUsize::new(0),
         }
     }
 }
@@ -318,7 +317,7 @@ impl ConnectionCache {
 impl PerformanceMonitor {
     fn new() -> Self {
         Self {
-            processing_times: Arc::new(hdrhistogram::Histogram::new(3).unwrap()),
+            processing_times: Arc::new(parking_lot::Mutex::new(hdrhistogram::Histogram::new(3).unwrap())),
             underruns: AtomicUsize::new(0),
             overruns: AtomicUsize::new(0),
             last_warning: Arc::new(parking_lot::Mutex::new(Instant::now())),
@@ -328,7 +327,7 @@ impl PerformanceMonitor {
     fn record_processing_time(&self, duration: Duration) {
         let micros = duration.as_micros() as u64;
         // Recording can fail if the value is too large, but we don't want to panic in real-time code
-        let _ = self.processing_times.record(micros);
+        let _ = self.processing_times.lock().record(micros);
     }
     
     fn record_overrun(&self) {
@@ -339,19 +338,21 @@ impl PerformanceMonitor {
         // Rate-limit warnings to avoid spam
         let mut last_warning = self.last_warning.lock();
         if last_warning.elapsed() > Duration::from_secs(1) {
-            eprintln!(
-                "⚠️ Stream processing approaching deadline: {:.2}ms (target: <1ms)",
-                processing_time.as_micros() as f64 / 1000.0
+            tracing::warn!(
+                target: "scheduler",
+                processing_ms = processing_time.as_micros() as f64 / 1000.0,
+                "⚠️ stream processing approaching deadline (target: <1ms)"
             );
             *last_warning = Instant::now();
         }
     }
     
     pub fn get_stats(&self) -> PerformanceStats {
+        let processing_times = self.processing_times.lock();
         PerformanceStats {
-            avg_processing_time_us: self.processing_times.mean(),
-            max_processing_time_us: self.processing_times.max(),
-            p99_processing_time_us: self.processing_times.value_at_quantile(0.99),
+            avg_processing_time_us: processing_times.mean(),
+            max_processing_time_us: processing_times.max(),
+            p99_processing_time_us: processing_times.value_at_quantile(0.99),
             underruns: self.underruns.load(Ordering::Relaxed),
             overruns: self.overruns.load(Ordering::Relaxed),
         }
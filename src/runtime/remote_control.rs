@@ -0,0 +1,530 @@
+/// Remote parameter control: GUI controls and variables listed in `config { remote: [...] }`
+/// can be read and changed over REST or a WebSocket while a piece is running, so a tablet at
+/// front of house or a custom dashboard can tweak it without touching the machine.
+///
+/// ```text
+/// GET  /state               everything below in one object
+/// GET  /controls            declared GUI controls with their ranges and values
+/// PUT  /controls/<label>    body: a JSON value, e.g. 0.75
+/// GET  /variables           public variables
+/// PUT  /variables/<name>    body: a JSON value
+/// GET  /ws                  WebSocket: pushes state, accepts {"control"|"variable": name, "value": v}
+/// ```
+///
+/// Every request needs the access token, as `Authorization: Bearer <token>` or `?token=<token>`.
+use crate::runtime::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const CONTROL_TOKEN_ENV: &str = "SYNTHESIS_CONTROL_TOKEN";
+
+/// How often WebSocket clients get a fresh copy of the state when something changed
+const PUSH_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_BODY: usize = 64 * 1024;
+
+// Shared between the script (GUI functions, frame loop) and the HTTP threads
+static REMOTE_STATE: OnceLock<Mutex<RemoteState>> = OnceLock::new();
+
+fn remote_state() -> &'static Mutex<RemoteState> {
+    REMOTE_STATE.get_or_init(|| Mutex::new(RemoteState::default()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteControl {
+    pub label: String,
+    /// "slider", "checkbox", "dropdown" or "button"
+    pub kind: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub options: Vec<String>,
+    /// What the local GUI reports
+    pub value: Value,
+    /// Set from the control API; wins over the local value until cleared
+    pub remote_value: Option<Value>,
+}
+
+impl RemoteControl {
+    pub fn new(label: impl Into<String>, kind: impl Into<String>, value: Value) -> Self {
+        Self {
+            label: label.into(),
+            kind: kind.into(),
+            min: None,
+            max: None,
+            options: Vec::new(),
+            value,
+            remote_value: None,
+        }
+    }
+
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn with_options(mut self, options: Vec<String>) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn current(&self) -> Value {
+        self.remote_value.clone().unwrap_or_else(|| self.value.clone())
+    }
+
+    /// Check a remote value against the control's type and range
+    fn validate(&self, value: Value) -> Result<Value, String> {
+        match self.kind.as_str() {
+            "slider" => {
+                let number = value.as_number().ok_or_else(|| format!("'{}' is a slider and needs a number", self.label))?;
+                let min = self.min.unwrap_or(f64::MIN);
+                let max = self.max.unwrap_or(f64::MAX);
+                Ok(Value::Float(number.clamp(min, max)))
+            }
+            "checkbox" | "button" => Ok(Value::Boolean(value.is_truthy())),
+            "dropdown" => match &value {
+                Value::String(choice) if self.options.contains(choice) => Ok(value),
+                _ => Err(format!("'{}' must be one of {:?}", self.label, self.options)),
+            },
+            _ => Ok(value),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "label": self.label,
+            "kind": self.kind,
+            "min": self.min,
+            "max": self.max,
+            "options": self.options,
+            "value": value_to_json(&self.current()),
+            "remote": self.remote_value.is_some(),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RemoteState {
+    controls: BTreeMap<String, RemoteControl>,
+    /// Names the script made public, with their latest values
+    variables: BTreeMap<String, Value>,
+    pending_writes: Vec<(String, Value)>,
+    /// Bumped on every change so WebSocket clients only get pushes when needed
+    version: u64,
+}
+
+impl RemoteState {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "controls": self.controls.values().map(|c| c.to_json()).collect::<Vec<_>>(),
+            "variables": self.variables.iter().map(|(k, v)| (k.clone(), value_to_json(v))).collect::<serde_json::Map<_, _>>(),
+            "version": self.version,
+        })
+    }
+}
+
+/// Register or refresh a GUI control; returns the value the script should use
+pub fn declare_control(control: RemoteControl) -> Value {
+    let mut state = match remote_state().lock() {
+        Ok(state) => state,
+        Err(_) => return control.value,
+    };
+
+    let entry = state.controls.entry(control.label.clone()).or_insert_with(|| control.clone());
+    let changed = entry.value != control.value;
+    entry.value = control.value;
+    entry.min = control.min;
+    entry.max = control.max;
+    entry.options = control.options;
+    let current = entry.current();
+
+    // A button press is one-shot: consume it once the script has seen it
+    if entry.kind == "button" && entry.remote_value.is_some() {
+        entry.remote_value = None;
+    }
+    if changed {
+        state.version += 1;
+    }
+    current
+}
+
+pub fn set_control(label: &str, value: Value) -> Result<Value, String> {
+    let mut state = remote_state().lock().map_err(|_| "control state unavailable".to_string())?;
+    let control = state.controls.get_mut(label).ok_or_else(|| format!("No control named '{}'", label))?;
+    let before = control.remote_value.clone().unwrap_or(Value::Null);
+
+    let value = if value == Value::Null {
+        control.remote_value = None; // null hands control back to the local GUI
+        control.current()
+    } else {
+        let value = control.validate(value)?;
+        control.remote_value = Some(value.clone());
+        value
+    };
+    // Buttons are one-shot presses, not settings worth undoing
+    if control.kind != "button" {
+        let after = control.remote_value.clone().unwrap_or(Value::Null);
+        crate::runtime::undo::undo_history().lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .record(crate::runtime::undo::ParamTarget::Control(label.to_string()), before, after);
+    }
+    state.version += 1;
+    Ok(value)
+}
+
+/// Update the published variables from the interpreter; only the listed names are exposed
+pub fn publish_variables(variables: &HashMap<String, Value>, public: &[String]) {
+    if let Ok(mut state) = remote_state().lock() {
+        let mut changed = false;
+        for name in public {
+            let value = variables.get(name).cloned().unwrap_or(Value::Null);
+            if state.variables.get(name) != Some(&value) {
+                state.variables.insert(name.clone(), value);
+                changed = true;
+            }
+        }
+        let before = state.variables.len();
+        state.variables.retain(|name, _| public.contains(name));
+        if changed || state.variables.len() != before {
+            state.version += 1;
+        }
+    }
+}
+
+pub fn set_variable(name: &str, value: Value) -> Result<Value, String> {
+    let mut state = remote_state().lock().map_err(|_| "control state unavailable".to_string())?;
+    if !state.variables.contains_key(name) {
+        return Err(format!("'{}' isn't public; list it in config {{ remote: [...] }}", name));
+    }
+    let before = state.variables.insert(name.to_string(), value.clone()).unwrap_or(Value::Null);
+    state.pending_writes.push((name.to_string(), value.clone()));
+    state.version += 1;
+    crate::runtime::undo::undo_history().lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .record(crate::runtime::undo::ParamTarget::Variable(name.to_string()), before, value.clone());
+    Ok(value)
+}
+
+/// Put a parameter back without recording it, for undo and redo
+pub fn restore_param(target: &crate::runtime::undo::ParamTarget, value: Value) {
+    use crate::runtime::undo::ParamTarget;
+    let mut state = remote_state().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match target {
+        ParamTarget::Control(label) => {
+            if let Some(control) = state.controls.get_mut(label) {
+                control.remote_value = (value != Value::Null).then_some(value);
+            }
+        }
+        ParamTarget::Variable(name) => {
+            state.variables.insert(name.clone(), value.clone());
+            state.pending_writes.push((name.clone(), value));
+        }
+    }
+    state.version += 1;
+}
+
+/// Variable changes made remotely since the last frame, applied by the interpreter
+pub fn take_variable_writes() -> Vec<(String, Value)> {
+    remote_state().lock().map(|mut state| std::mem::take(&mut state.pending_writes)).unwrap_or_default()
+}
+
+pub fn remote_state_json() -> serde_json::Value {
+    remote_state().lock().map(|state| state.to_json()).unwrap_or(serde_json::Value::Null)
+}
+
+fn state_version() -> u64 {
+    remote_state().lock().map(|state| state.version).unwrap_or(0)
+}
+
+pub fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Integer(n) => serde_json::Value::from(*n),
+        Value::Float(n) => serde_json::Number::from_f64(*n).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        Value::String(s) => serde_json::Value::String(s.clone()),
+        Value::Boolean(b) => serde_json::Value::Bool(*b),
+        Value::Array(items) => serde_json::Value::Array(items.iter().map(value_to_json).collect()),
+        Value::Object(fields) | Value::Struct { fields, .. } => serde_json::Value::Object(fields.iter().map(|(k, v)| (k.clone(), value_to_json(v))).collect()),
+        Value::UnitValue(unit) => serde_json::json!(unit.value),
+        Value::Range { .. } | Value::Events(_) | Value::Color(_) => serde_json::Value::String(value.to_string()),
+        Value::Stream(stream) => serde_json::Value::String(format!("Stream<{}>", stream.name)),
+        Value::Function(function) => serde_json::Value::String(format!("Function<{}>", function.name)),
+        Value::Null => serde_json::Value::Null,
+    }
+}
+
+pub fn json_to_value(json: &serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Float(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Value::String(s.clone()),
+        serde_json::Value::Array(items) => Value::Array(items.iter().map(json_to_value).collect()),
+        serde_json::Value::Object(fields) => Value::Object(fields.iter().map(|(k, v)| (k.clone(), json_to_value(v))).collect()),
+    }
+}
+
+/// Apply one change: `{"control": "Speed", "value": 0.5}` or `{"variable": "hue", "value": 120}`
+fn apply_message(message: &serde_json::Value) -> Result<Value, String> {
+    let value = json_to_value(message.get("value").unwrap_or(&serde_json::Value::Null));
+    if let Some(label) = message.get("control").and_then(|v| v.as_str()) {
+        set_control(label, value)
+    } else if let Some(name) = message.get("variable").and_then(|v| v.as_str()) {
+        set_variable(name, value)
+    } else {
+        Err("Expected {\"control\": ..., \"value\": ...} or {\"variable\": ..., \"value\": ...}".to_string())
+    }
+}
+
+/// The control API server; runs on background threads for the life of the process
+pub struct ControlServer {
+    address: SocketAddr,
+    token: String,
+}
+
+impl ControlServer {
+    /// Bind to a port (all interfaces, so tablets on the LAN can reach it) or a full address
+    pub fn start(bind: &str, token: Option<String>) -> std::io::Result<Self> {
+        let address = if bind.contains(':') { bind.to_string() } else { format!("0.0.0.0:{}", bind) };
+        let listener = TcpListener::bind(&address)?;
+        let address = listener.local_addr()?;
+        let token = token
+            .or_else(|| std::env::var(CONTROL_TOKEN_ENV).ok())
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(generate_token);
+
+        let server_token = token.clone();
+        std::thread::Builder::new()
+            .name("synthesis-control".to_string())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let token = server_token.clone();
+                    let _ = std::thread::Builder::new()
+                        .name("synthesis-control-client".to_string())
+                        .spawn(move || {
+                            if let Err(error) = handle_connection(stream, &token) {
+                                tracing::debug!(target: "remote", %error, "control connection closed");
+                            }
+                        });
+                }
+            })?;
+
+        tracing::info!(target: "remote", %address, "🎛️ remote control API listening");
+        Ok(Self { address, token })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+fn generate_token() -> String {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    (0..24).map(|_| format!("{:x}", rng.gen_range(0..16u8))).collect()
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query_token: Option<String>,
+    headers: HashMap<String, String>,
+    header_len: usize,
+}
+
+impl Request {
+    fn parse(raw: &str) -> Option<Self> {
+        let header_end = raw.find("\r\n\r\n")?;
+        let mut lines = raw[..header_end].split("\r\n");
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?;
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query_token = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(|t| t.to_string());
+
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+            .collect();
+
+        Some(Self { method, path: path.to_string(), query_token, headers, header_len: header_end + 4 })
+    }
+
+    fn is_authorized(&self, token: &str) -> bool {
+        let offered = self.headers
+            .get("authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(|t| t.to_string())
+            .or_else(|| self.query_token.clone());
+        offered.map(|offered| constant_time_eq(offered.as_bytes(), token.as_bytes())).unwrap_or(false)
+    }
+
+    fn is_websocket(&self) -> bool {
+        self.headers.get("upgrade").map(|u| u.eq_ignore_ascii_case("websocket")).unwrap_or(false)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn handle_connection(mut stream: TcpStream, token: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    // Peek so a WebSocket handshake can be handed to tungstenite untouched
+    let mut peek = vec![0u8; 8192];
+    let mut peeked = 0;
+    for _ in 0..100 {
+        peeked = stream.peek(&mut peek)?;
+        if peeked == 0 {
+            return Ok(()); // closed before sending anything
+        }
+        if peeked == peek.len() || peek[..peeked].windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let head = String::from_utf8_lossy(&peek[..peeked]).into_owned();
+    let request = match Request::parse(&head) {
+        Some(request) => request,
+        None => return respond(&mut stream, "400 Bad Request", &serde_json::json!({"error": "malformed request"})),
+    };
+
+    let authorized = request.is_authorized(token);
+    if authorized && request.is_websocket() && request.path == "/ws" {
+        return serve_websocket(stream);
+    }
+
+    // Read the whole request before answering, so the client never sees a reset connection
+    let mut consumed = vec![0u8; request.header_len];
+    stream.read_exact(&mut consumed)?;
+    let length = request.headers.get("content-length").and_then(|l| l.parse::<usize>().ok()).unwrap_or(0);
+    if length > MAX_BODY {
+        return respond(&mut stream, "413 Payload Too Large", &serde_json::json!({"error": "body too large"}));
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+
+    if request.method == "OPTIONS" {
+        return write_response(&mut stream, "204 No Content", "");
+    }
+    if !authorized {
+        return respond(&mut stream, "401 Unauthorized", &serde_json::json!({"error": "missing or wrong token"}));
+    }
+
+    let (status, response) = route(&request.method, &request.path, &body);
+    respond(&mut stream, status, &response)
+}
+
+fn route(method: &str, path: &str, body: &[u8]) -> (&'static str, serde_json::Value) {
+    let state = remote_state_json();
+    let segments: Vec<&str> = path.trim_matches('/').splitn(2, '/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["state"]) | ("GET", [""]) => ("200 OK", state),
+        ("GET", ["controls"]) => ("200 OK", state["controls"].clone()),
+        ("GET", ["variables"]) => ("200 OK", state["variables"].clone()),
+        ("PUT" | "POST", [collection @ ("controls" | "variables"), name]) => {
+            let name = percent_decode(name);
+            let value = match serde_json::from_slice::<serde_json::Value>(body) {
+                Ok(json) => json_to_value(json.get("value").unwrap_or(&json)),
+                Err(error) => return ("400 Bad Request", serde_json::json!({"error": format!("body must be JSON: {}", error)})),
+            };
+            let result = if *collection == "controls" { set_control(&name, value) } else { set_variable(&name, value) };
+            match result {
+                Ok(value) => ("200 OK", serde_json::json!({"name": name, "value": value_to_json(&value)})),
+                Err(error) => ("404 Not Found", serde_json::json!({"error": error})),
+            }
+        }
+        _ => ("404 Not Found", serde_json::json!({"error": "unknown endpoint", "endpoints": ["/state", "/controls", "/variables", "/ws"]})),
+    }
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%' && i + 3 <= bytes.len())
+            .then(|| std::str::from_utf8(&bytes[i + 1..i + 3]).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &serde_json::Value) -> std::io::Result<()> {
+    write_response(stream, status, &body.to_string())
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Access-Control-Allow-Methods: GET, PUT, POST, OPTIONS\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body
+    )
+}
+
+fn serve_websocket(stream: TcpStream) -> std::io::Result<()> {
+    use tungstenite::Message;
+
+    let to_io = |error: tungstenite::Error| std::io::Error::other(error.to_string());
+    let mut socket = tungstenite::accept(stream).map_err(|e| std::io::Error::other(e.to_string()))?;
+    socket.get_ref().set_read_timeout(Some(PUSH_INTERVAL))?;
+
+    let mut sent_version = None;
+    let mut last_push = Instant::now() - PUSH_INTERVAL;
+
+    loop {
+        if last_push.elapsed() >= PUSH_INTERVAL {
+            let version = state_version();
+            if sent_version != Some(version) {
+                socket.send(Message::Text(remote_state_json().to_string())).map_err(to_io)?;
+                sent_version = Some(version);
+            }
+            last_push = Instant::now();
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = match serde_json::from_str::<serde_json::Value>(&text) {
+                    Ok(message) => match apply_message(&message) {
+                        Ok(value) => serde_json::json!({"ok": true, "value": value_to_json(&value)}),
+                        Err(error) => serde_json::json!({"ok": false, "error": error}),
+                    },
+                    Err(error) => serde_json::json!({"ok": false, "error": error.to_string()}),
+                };
+                socket.send(Message::Text(reply.to_string())).map_err(to_io)?;
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(error) => return Err(to_io(error)),
+        }
+    }
+}
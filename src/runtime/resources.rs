@@ -0,0 +1,227 @@
+/// Lifetime tracking for textures, buffers, decoded audio and streams
+/// Long-running installations use this to spot slow leaks and drop assets nobody touches anymore
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Process-wide tracker: resources are created from many subsystems and threads
+static RESOURCES: OnceLock<Mutex<ResourceTracker>> = OnceLock::new();
+
+fn tracker() -> &'static Mutex<ResourceTracker> {
+    RESOURCES.get_or_init(|| Mutex::new(ResourceTracker::new()))
+}
+
+pub fn track_resource(kind: ResourceKind, label: impl Into<String>, bytes: usize) -> ResourceId {
+    tracker().lock().map(|mut t| t.track(kind, label, bytes)).unwrap_or(ResourceId(0))
+}
+
+pub fn touch_resource(id: ResourceId) {
+    if let Ok(mut t) = tracker().lock() {
+        t.touch(id);
+    }
+}
+
+pub fn resize_resource(id: ResourceId, bytes: usize) {
+    if let Ok(mut t) = tracker().lock() {
+        t.resize(id, bytes);
+    }
+}
+
+pub fn release_resource(id: ResourceId) -> bool {
+    tracker().lock().map(|mut t| t.release(id)).unwrap_or(false)
+}
+
+pub fn resource_idle_time(id: ResourceId) -> Option<Duration> {
+    tracker().lock().ok()?.idle_time(id)
+}
+
+pub fn resource_stats() -> Vec<ResourceStats> {
+    tracker().lock().map(|t| t.stats()).unwrap_or_default()
+}
+
+pub fn leak_report() -> String {
+    tracker().lock().map(|t| t.leak_report()).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    Texture,
+    Buffer,
+    AudioBuffer,
+    Stream,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 4] = [
+        ResourceKind::Texture,
+        ResourceKind::Buffer,
+        ResourceKind::AudioBuffer,
+        ResourceKind::Stream,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourceKind::Texture => "texture",
+            ResourceKind::Buffer => "buffer",
+            ResourceKind::AudioBuffer => "audio",
+            ResourceKind::Stream => "stream",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceRecord {
+    pub id: ResourceId,
+    pub kind: ResourceKind,
+    pub label: String,
+    pub bytes: usize,
+    pub created: Instant,
+    pub last_used: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceStats {
+    pub kind: ResourceKind,
+    pub live: usize,
+    pub created: u64,
+    pub released: u64,
+    pub bytes: usize,
+    pub peak_bytes: usize,
+}
+
+impl ResourceStats {
+    fn empty(kind: ResourceKind) -> Self {
+        Self { kind, live: 0, created: 0, released: 0, bytes: 0, peak_bytes: 0 }
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut fields = HashMap::new();
+        fields.insert("kind".to_string(), Value::String(self.kind.name().to_string()));
+        fields.insert("live".to_string(), Value::Integer(self.live as i64));
+        fields.insert("created".to_string(), Value::Integer(self.created as i64));
+        fields.insert("released".to_string(), Value::Integer(self.released as i64));
+        fields.insert("bytes".to_string(), Value::Integer(self.bytes as i64));
+        fields.insert("peak_bytes".to_string(), Value::Integer(self.peak_bytes as i64));
+        Value::Object(fields)
+    }
+}
+
+#[derive(Debug)]
+pub struct ResourceTracker {
+    next_id: u64,
+    live: HashMap<ResourceId, ResourceRecord>,
+    totals: HashMap<ResourceKind, ResourceStats>,
+}
+
+impl ResourceTracker {
+    pub fn new() -> Self {
+        Self {
+            next_id: 1,
+            live: HashMap::new(),
+            totals: ResourceKind::ALL.iter().map(|kind| (*kind, ResourceStats::empty(*kind))).collect(),
+        }
+    }
+
+    pub fn track(&mut self, kind: ResourceKind, label: impl Into<String>, bytes: usize) -> ResourceId {
+        let id = ResourceId(self.next_id);
+        self.next_id += 1;
+
+        let now = Instant::now();
+        self.live.insert(id, ResourceRecord { id, kind, label: label.into(), bytes, created: now, last_used: now });
+
+        if let Some(stats) = self.totals.get_mut(&kind) {
+            stats.created += 1;
+            stats.live += 1;
+            stats.bytes += bytes;
+            stats.peak_bytes = stats.peak_bytes.max(stats.bytes);
+        }
+        id
+    }
+
+    pub fn touch(&mut self, id: ResourceId) {
+        if let Some(record) = self.live.get_mut(&id) {
+            record.last_used = Instant::now();
+        }
+    }
+
+    /// Update the size of a resource that was reloaded or reallocated in place
+    pub fn resize(&mut self, id: ResourceId, bytes: usize) {
+        if let Some(record) = self.live.get_mut(&id) {
+            if let Some(stats) = self.totals.get_mut(&record.kind) {
+                stats.bytes = stats.bytes - record.bytes + bytes;
+                stats.peak_bytes = stats.peak_bytes.max(stats.bytes);
+            }
+            record.bytes = bytes;
+            record.last_used = Instant::now();
+        }
+    }
+
+    pub fn release(&mut self, id: ResourceId) -> bool {
+        match self.live.remove(&id) {
+            Some(record) => {
+                if let Some(stats) = self.totals.get_mut(&record.kind) {
+                    stats.live -= 1;
+                    stats.released += 1;
+                    stats.bytes -= record.bytes;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn idle_time(&self, id: ResourceId) -> Option<Duration> {
+        self.live.get(&id).map(|record| record.last_used.elapsed())
+    }
+
+    /// Live resources nobody has touched for at least `idle`
+    pub fn unused_for(&self, idle: Duration) -> Vec<ResourceRecord> {
+        self.live.values().filter(|r| r.last_used.elapsed() >= idle).cloned().collect()
+    }
+
+    pub fn stats(&self) -> Vec<ResourceStats> {
+        ResourceKind::ALL.iter().filter_map(|kind| self.totals.get(kind).copied()).collect()
+    }
+
+    /// Everything still alive, oldest first, for `--report-leaks`
+    pub fn leak_report(&self) -> String {
+        let mut report = String::from("🧹 Resource report\n");
+
+        for stats in self.stats() {
+            report.push_str(&format!(
+                "  {:<8} live {:>5}  created {:>6}  released {:>6}  {:>10} bytes (peak {})\n",
+                stats.kind.name(), stats.live, stats.created, stats.released, stats.bytes, stats.peak_bytes
+            ));
+        }
+
+        let mut live: Vec<&ResourceRecord> = self.live.values().collect();
+        if live.is_empty() {
+            report.push_str("  No live resources, nothing leaked\n");
+            return report;
+        }
+
+        live.sort_by_key(|record| record.created);
+        report.push_str(&format!("  {} resource(s) still alive:\n", live.len()));
+        for record in live {
+            report.push_str(&format!(
+                "    {:<8} {:<32} {:>10} bytes  age {:>6.1}s  idle {:>6.1}s\n",
+                record.kind.name(),
+                record.label,
+                record.bytes,
+                record.created.elapsed().as_secs_f32(),
+                record.last_used.elapsed().as_secs_f32()
+            ));
+        }
+        report
+    }
+}
+
+impl Default for ResourceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
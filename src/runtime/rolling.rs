@@ -0,0 +1,102 @@
+/// Windowed statistics for conditioning noisy control values
+///
+/// `level.rolling_mean(1.seconds)`, `.rolling_variance()`, `.percentile(95)` and friends look back
+/// over a window of time, and `.peak_hold(0.5)` jumps up to peaks and falls back slowly. Like
+/// `smooth()`, each call site keeps its own state, and time comes from frame deltas so a
+/// one-second window holds one second of frames at any frame rate.
+use std::collections::VecDeque;
+
+/// Methods on numbers, lists of numbers and streams that keep a window
+pub const STATISTIC_METHODS: [&str; 7] = [
+    "rolling_mean", "rolling_variance", "rolling_stddev", "rolling_min", "rolling_max", "percentile", "peak_hold",
+];
+/// Seconds looked back over when no window is given
+pub const DEFAULT_WINDOW: f64 = 1.0;
+/// Most samples a window keeps however long it is (a minute at 60fps)
+pub const MAX_WINDOW_SAMPLES: usize = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Statistic {
+    Mean,
+    /// Population variance, so a steady signal has none
+    Variance,
+    StdDev,
+    Min,
+    Max,
+    /// 0 to 100, interpolating between the nearest samples
+    Percentile(f64),
+}
+
+impl Statistic {
+    pub fn of(&self, values: &[f64]) -> f64 {
+        if values.is_empty() {
+            return 0.0;
+        }
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        match self {
+            Statistic::Mean => mean,
+            Statistic::Variance => values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count,
+            Statistic::StdDev => Statistic::Variance.of(values).sqrt(),
+            Statistic::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Statistic::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Statistic::Percentile(percent) => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let rank = percent.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+                let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+                sorted[below] + (sorted[above] - sorted[below]) * rank.fract()
+            }
+        }
+    }
+}
+
+/// The values seen over the last `window` seconds, newest last
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RollingWindow {
+    now: f64,
+    samples: VecDeque<(f64, Vec<f64>)>,
+}
+
+impl RollingWindow {
+    /// Record this frame's values and return the statistic of each over the window
+    pub fn step(&mut self, input: &[f64], window: f64, dt: f64, statistic: Statistic) -> Vec<f64> {
+        self.now += dt.max(0.0);
+        // A list that changed length can't be compared with what came before
+        if self.samples.back().is_some_and(|(_, values)| values.len() != input.len()) {
+            self.samples.clear();
+        }
+        self.samples.push_back((self.now, input.to_vec()));
+        while self.samples.len() > MAX_WINDOW_SAMPLES
+            || (self.samples.len() > 1 && self.samples.front().is_some_and(|(at, _)| self.now - at >= window))
+        {
+            self.samples.pop_front();
+        }
+        (0..input.len())
+            .map(|index| {
+                let values: Vec<f64> = self.samples.iter().map(|(_, values)| values[index]).collect();
+                statistic.of(&values)
+            })
+            .collect()
+    }
+}
+
+/// Follows rises at once and falls back with a `decay` time constant in seconds, like a level meter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeakHold {
+    peak: Option<Vec<f64>>,
+}
+
+impl PeakHold {
+    pub fn step(&mut self, input: &[f64], decay: f64, dt: f64) -> Vec<f64> {
+        let next = match &self.peak {
+            Some(peak) if peak.len() == input.len() && decay > 0.0 => {
+                let hold = (-dt.max(0.0) / decay).exp();
+                peak.iter().zip(input).map(|(peak, input)| if input >= peak { *input } else { input + (peak - input) * hold }).collect()
+            }
+            _ => input.to_vec(),
+        };
+        self.peak = Some(next.clone());
+        next
+    }
+}
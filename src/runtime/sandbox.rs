@@ -0,0 +1,293 @@
+/// In-process execution of untrusted scripts for hosted playgrounds and docs
+///
+/// `SandboxedEngine` runs a script headless with an instruction budget, a wall-clock
+/// deadline and a memory quota, with every device and file capability refused. Instead of
+/// printing, the run hands back what the script drew and played as structured values.
+///
+/// Limits are checked between statements and expressions, so a single slow built-in call
+/// can overrun the deadline by its own duration; run the engine on a worker thread if that matters.
+use crate::errors::{synthesis_error, ErrorKind, SynthesisError};
+use crate::parser::{lexer, Parser};
+use crate::runtime::{CapabilityPolicy, Interpreter, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How often (in steps) the clock and memory are checked; both are too costly to do every step
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+const MEMORY_CHECK_INTERVAL: u64 = 4096;
+
+/// Recorded calls beyond this are counted but not kept
+const MAX_RECORDED_CALLS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct SandboxLimits {
+    /// Statements and expressions evaluated, across setup and every frame
+    pub max_instructions: u64,
+    pub deadline: Duration,
+    /// Approximate bytes held by script variables
+    pub memory_quota: usize,
+    /// Headless frames to run before a `loop { }` is stopped
+    pub max_frames: u64,
+}
+
+impl SandboxLimits {
+    /// No budget, just a frame count: for trusted headless runs like `--artifacts`
+    pub fn headless(max_frames: u64) -> Self {
+        Self {
+            max_instructions: u64::MAX,
+            deadline: Duration::MAX,
+            memory_quota: usize::MAX,
+            max_frames,
+        }
+    }
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            max_instructions: 5_000_000,
+            deadline: Duration::from_secs(5),
+            memory_quota: 64 * 1024 * 1024,
+            max_frames: 120,
+        }
+    }
+}
+
+/// A module call made during a sandboxed run, with what it returned
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub frame: u64,
+    pub module: String,
+    pub function: String,
+    pub args: Vec<Value>,
+    pub named: HashMap<String, Value>,
+    pub result: Value,
+}
+
+/// Per-run counters the interpreter updates while a sandbox is active
+#[derive(Debug, Clone)]
+pub struct SandboxSession {
+    limits: SandboxLimits,
+    started: Instant,
+    instructions: u64,
+    peak_memory: usize,
+    frame: u64,
+    frames_run: u64,
+    calls: Vec<RecordedCall>,
+    dropped_calls: u64,
+}
+
+impl SandboxSession {
+    pub fn new(limits: SandboxLimits) -> Self {
+        Self {
+            limits,
+            started: Instant::now(),
+            instructions: 0,
+            peak_memory: 0,
+            frame: 0,
+            frames_run: 0,
+            calls: Vec::new(),
+            dropped_calls: 0,
+        }
+    }
+
+    pub fn limits(&self) -> &SandboxLimits {
+        &self.limits
+    }
+
+    /// Count one statement or expression, failing once a limit is exceeded
+    pub fn step(&mut self, variables: &HashMap<String, Value>) -> crate::Result<()> {
+        self.instructions += 1;
+        if self.instructions > self.limits.max_instructions {
+            return Err(synthesis_error(
+                ErrorKind::PerformanceConstraintViolation,
+                format!("⏱️ Script ran more than {} instructions", self.limits.max_instructions)
+            )
+            .with_suggestion("Look for a while loop that never ends, or do less work per frame")
+            .with_docs("https://synthesis-lang.org/docs/sandbox"));
+        }
+        if self.instructions.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            self.check_deadline()?;
+        }
+        if self.instructions.is_multiple_of(MEMORY_CHECK_INTERVAL) {
+            let used = variables.values().map(value_size).sum();
+            self.check_memory(used)?;
+        }
+        Ok(())
+    }
+
+    pub fn check_deadline(&self) -> crate::Result<()> {
+        if self.started.elapsed() > self.limits.deadline {
+            return Err(synthesis_error(
+                ErrorKind::PerformanceConstraintViolation,
+                format!("⏱️ Script hit the {:.1}s timeout", self.limits.deadline.as_secs_f64())
+            )
+            .with_suggestion("Hosted runs are short; try fewer frames or lighter effects")
+            .with_docs("https://synthesis-lang.org/docs/sandbox"));
+        }
+        Ok(())
+    }
+
+    pub fn check_memory(&mut self, used: usize) -> crate::Result<()> {
+        self.peak_memory = self.peak_memory.max(used);
+        if used > self.limits.memory_quota {
+            return Err(synthesis_error(
+                ErrorKind::OutOfMemory,
+                format!("📦 Script used about {} KB, over the {} KB limit", used / 1024, self.limits.memory_quota / 1024)
+            )
+            .with_suggestion("Use smaller arrays or generate values as you need them")
+            .with_docs("https://synthesis-lang.org/docs/sandbox"));
+        }
+        Ok(())
+    }
+
+    /// Called at the top of each frame; returns false once the frame limit is reached
+    pub fn begin_frame(&mut self, frame: u64) -> crate::Result<bool> {
+        self.check_deadline()?;
+        if frame >= self.limits.max_frames {
+            return Ok(false);
+        }
+        self.frame = frame;
+        self.frames_run = frame + 1;
+        Ok(true)
+    }
+
+    pub fn record_call(&mut self, module: &str, function: &str, args: &[Value], named: &HashMap<String, Value>, result: &Value) -> crate::Result<()> {
+        self.check_memory(value_size(result))?;
+        if self.calls.len() >= MAX_RECORDED_CALLS {
+            self.dropped_calls += 1;
+            return Ok(());
+        }
+        self.calls.push(RecordedCall {
+            frame: self.frame,
+            module: module.to_string(),
+            function: function.to_string(),
+            args: args.to_vec(),
+            named: named.clone(),
+            result: result.clone(),
+        });
+        Ok(())
+    }
+
+    /// Wrap up the run once the interpreter is done
+    pub fn finish(self, variables: HashMap<String, Value>, error: Option<SynthesisError>) -> SandboxReport {
+        SandboxReport {
+            variables,
+            calls: self.calls,
+            dropped_calls: self.dropped_calls,
+            error,
+            instructions: self.instructions,
+            frames_run: self.frames_run,
+            peak_memory: self.peak_memory,
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+/// Rough heap footprint of a value, enough to stop runaway allocations
+pub fn value_size(value: &Value) -> usize {
+    let base = std::mem::size_of::<Value>();
+    match value {
+        Value::String(s) => base + s.len(),
+        Value::Array(items) => base + items.iter().map(value_size).sum::<usize>(),
+        Value::Object(fields) | Value::Struct { fields, .. } => base + fields.iter().map(|(k, v)| k.len() + value_size(v)).sum::<usize>(),
+        Value::Events(events) => base + events.events().iter().map(|event| value_size(&event.value)).sum::<usize>(),
+        _ => base,
+    }
+}
+
+/// What a sandboxed run produced
+#[derive(Debug, Clone)]
+pub struct SandboxReport {
+    pub variables: HashMap<String, Value>,
+    pub calls: Vec<RecordedCall>,
+    /// Calls made after the recording cap was hit
+    pub dropped_calls: u64,
+    pub error: Option<SynthesisError>,
+    pub instructions: u64,
+    pub frames_run: u64,
+    pub peak_memory: usize,
+    pub elapsed: Duration,
+}
+
+impl SandboxReport {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Graphics calls, grouped by frame (setup counts as frame 0)
+    pub fn frames(&self) -> Vec<(u64, Vec<&RecordedCall>)> {
+        let mut frames: Vec<(u64, Vec<&RecordedCall>)> = Vec::new();
+        for call in self.calls.iter().filter(|c| c.module == "Graphics") {
+            match frames.last_mut() {
+                Some((frame, calls)) if *frame == call.frame => calls.push(call),
+                _ => frames.push((call.frame, vec![call])),
+            }
+        }
+        frames
+    }
+
+    pub fn audio(&self) -> Vec<&RecordedCall> {
+        self.calls.iter().filter(|c| c.module == "Audio").collect()
+    }
+}
+
+/// Runs untrusted source in-process with limits and no device access
+#[derive(Debug, Clone)]
+pub struct SandboxedEngine {
+    limits: SandboxLimits,
+    capabilities: CapabilityPolicy,
+}
+
+impl Default for SandboxedEngine {
+    fn default() -> Self {
+        Self::new(SandboxLimits::default())
+    }
+}
+
+impl SandboxedEngine {
+    pub fn new(limits: SandboxLimits) -> Self {
+        Self { limits, capabilities: CapabilityPolicy::sandboxed(Vec::new()) }
+    }
+
+    /// Loosen device access for trusted code, like the bundled examples
+    pub fn with_capabilities(mut self, capabilities: CapabilityPolicy) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn limits(&self) -> &SandboxLimits {
+        &self.limits
+    }
+
+    pub fn run(&self, source: &str) -> SandboxReport {
+        let mut interpreter = Interpreter::new();
+        interpreter.capabilities = self.capabilities.clone();
+        interpreter.sandbox = Some(SandboxSession::new(self.limits.clone()));
+
+        let result = Self::parse(source).and_then(|program| {
+            // Headless frames run back to back; the frame limit and deadline end the loop
+            let mut overrides = HashMap::new();
+            overrides.insert("fps".to_string(), Value::String("unlimited".to_string()));
+            overrides.insert("vsync".to_string(), Value::Boolean(false));
+            interpreter.set_frame_pacing_overrides(overrides)?;
+            interpreter.execute(&program)
+        });
+
+        let session = interpreter.sandbox.take().unwrap_or_else(|| SandboxSession::new(self.limits.clone()));
+        session.finish(std::mem::take(&mut interpreter.variables), result.err())
+    }
+
+    fn parse(source: &str) -> crate::Result<crate::parser::ast::Program> {
+        let (tokens, spans) = match lexer::tokenize_with_spans(source) {
+            Ok((_, spanned)) => spanned,
+            Err(error) => return Err(lexer::literal_error(source, &error, "<sandbox>").unwrap_or_else(|| synthesis_error(
+                ErrorKind::SyntaxError,
+                "🎵 Oops! There's something unusual in your creative code"
+            )
+            .with_suggestion("Check for typos, missing quotes, or unusual characters")
+            .with_docs("https://synthesis-lang.org/docs/syntax-basics"))),
+        };
+        Parser::with_spans(&tokens, &spans, "<sandbox>").parse()
+    }
+}
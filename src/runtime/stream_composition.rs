@@ -1,8 +1,7 @@
 use crate::runtime::types::{DataType, Value};
-use crate::runtime::streams::{StreamManager, StreamTask, TaskType, TaskPriority};
+use crate::runtime::streams::{StreamManager, TaskPriority};
 use crate::errors::ErrorKind;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
 
 /// Stream composition engine for complex routing and processing
 #[derive(Debug)]
@@ -10,6 +9,8 @@ pub struct StreamCompositionEngine {
     pub connections: HashMap<String, Vec<StreamConnection>>,
     pub processing_graph: StreamGraph,
     pub composition_rules: Vec<CompositionRule>,
+    /// Switches, gates and crossbars by name, re-pointed at runtime
+    pub routers: HashMap<String, Router>,
 }
 
 /// Represents a connection between two streams
@@ -127,6 +128,50 @@ pub enum CompositionAction {
     CreateBuffer { size: usize },
 }
 
+/// Routing nodes whose paths change while running
+#[derive(Debug, Clone, PartialEq)]
+pub enum Router {
+    /// One of `inputs` goes through; the rest are drained so they don't burst out when picked
+    Switch { inputs: Vec<String>, selected: usize },
+    /// `input` goes through while `open` and is dropped while closed
+    Gate { input: String, open: bool },
+    /// Every output is a mix of the inputs, `matrix[output][input]` being the gain
+    Crossbar { inputs: Vec<String>, outputs: Vec<String>, matrix: Vec<Vec<f32>> },
+}
+
+impl Router {
+    pub fn inputs(&self) -> Vec<String> {
+        match self {
+            Router::Switch { inputs, .. } | Router::Crossbar { inputs, .. } => inputs.clone(),
+            Router::Gate { input, .. } => vec![input.clone()],
+        }
+    }
+    
+    /// Streams written; switches and gates write to the stream named after the router
+    pub fn outputs(&self, name: &str) -> Vec<String> {
+        match self {
+            Router::Crossbar { outputs, .. } => outputs.clone(),
+            _ => vec![name.to_string()],
+        }
+    }
+    
+    /// What each output gets, given what arrived on each input
+    pub fn route(&self, inputs: &[Vec<f32>]) -> Vec<Vec<f32>> {
+        match self {
+            Router::Switch { selected, .. } => vec![inputs.get(*selected).cloned().unwrap_or_default()],
+            Router::Gate { open, .. } => vec![if *open { inputs.first().cloned().unwrap_or_default() } else { Vec::new() }],
+            Router::Crossbar { matrix, .. } => {
+                let length = inputs.iter().map(Vec::len).max().unwrap_or(0);
+                matrix.iter()
+                    .map(|gains| (0..length)
+                        .map(|k| inputs.iter().zip(gains).map(|(input, gain)| input.get(k).copied().unwrap_or(0.0) * gain).sum())
+                        .collect())
+                    .collect()
+            }
+        }
+    }
+}
+
 /// Comparison operators for conditions
 #[derive(Debug, Clone, PartialEq)]
 pub enum Comparison {
@@ -143,7 +188,44 @@ impl StreamCompositionEngine {
             connections: HashMap::new(),
             processing_graph: StreamGraph::new(),
             composition_rules: Vec::new(),
+            routers: HashMap::new(),
+        }
+    }
+    
+    /// Add a router, or update one; only a change of inputs or outputs rebuilds the graph
+    pub fn set_router(&mut self, name: String, router: Router) -> crate::Result<()> {
+        if let Router::Crossbar { inputs, outputs, matrix } = &router {
+            if matrix.len() != outputs.len() || matrix.iter().any(|row| row.len() != inputs.len()) {
+                return Err(crate::errors::synthesis_error(ErrorKind::InvalidStreamConnection,
+                    format!("🔀 Crossbar matrix must have {} rows of {} gains, one row per output", outputs.len(), inputs.len()))
+                    .with_suggestion(format!("Give each of the {} outputs a row with one gain per input, 0.0 to leave an input out", outputs.len())));
+            }
+        }
+        let rewired = match self.routers.get(&name) {
+            Some(existing) => existing.inputs() != router.inputs() || existing.outputs(&name) != router.outputs(&name),
+            None => true,
+        };
+        let previous = self.routers.insert(name.clone(), router);
+        if rewired {
+            if let Err(error) = self.rebuild_graph() {
+                // Leave the graph as it was rather than half-wired
+                match previous {
+                    Some(previous) => self.routers.insert(name, previous),
+                    None => self.routers.remove(&name),
+                };
+                self.rebuild_graph()?;
+                return Err(error);
+            }
         }
+        Ok(())
+    }
+    
+    pub fn remove_router(&mut self, name: &str) -> crate::Result<bool> {
+        if self.routers.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.rebuild_graph()?;
+        Ok(true)
     }
     
     /// Create a direct connection between two streams
@@ -169,7 +251,7 @@ impl StreamCompositionEngine {
     /// Create a split connection (1:many)
     pub fn connect_split(&mut self, source: String, destinations: Vec<String>, gains: Vec<f32>) -> crate::Result<()> {
         if destinations.len() != gains.len() {
-            return Err(crate::SynthesisError::new(ErrorKind::InvalidArgument,
+            return Err(crate::SynthesisError::new(ErrorKind::InvalidExpression,
                 "Number of destinations must match number of gains".to_string()));
         }
         
@@ -197,7 +279,7 @@ impl StreamCompositionEngine {
     /// Create a merge connection (many:1)
     pub fn connect_merge(&mut self, sources: Vec<String>, destination: String, mix_gains: Vec<f32>) -> crate::Result<()> {
         if sources.len() != mix_gains.len() {
-            return Err(crate::SynthesisError::new(ErrorKind::InvalidArgument,
+            return Err(crate::SynthesisError::new(ErrorKind::InvalidExpression,
                 "Number of sources must match number of mix gains".to_string()));
         }
         
@@ -225,7 +307,7 @@ impl StreamCompositionEngine {
     /// Create a processing chain
     pub fn connect_chain(&mut self, streams: Vec<String>, transforms: Vec<StreamTransform>) -> crate::Result<()> {
         if streams.len() < 2 {
-            return Err(crate::SynthesisError::new(ErrorKind::InvalidArgument,
+            return Err(crate::SynthesisError::new(ErrorKind::InvalidExpression,
                 "Chain requires at least 2 streams".to_string()));
         }
         
@@ -272,7 +354,7 @@ impl StreamCompositionEngine {
         
         self.connections
             .entry(source)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(connection);
         
         Ok(())
@@ -300,11 +382,10 @@ impl StreamCompositionEngine {
         
         // Collect all unique stream names
         let mut all_streams = HashSet::new();
-        for connections in self.connections.values() {
-            for connection in connections {
-                all_streams.insert(connection.source.clone());
-                all_streams.insert(connection.destination.clone());
-            }
+        let router_connections = self.router_connections();
+        for connection in self.connections.values().flatten().chain(&router_connections) {
+            all_streams.insert(connection.source.clone());
+            all_streams.insert(connection.destination.clone());
         }
         
         // Create nodes
@@ -329,7 +410,7 @@ impl StreamCompositionEngine {
         }
         
         // Create edges and update dependencies
-        for connections in self.connections.values() {
+        for connections in self.connections.values().chain(std::iter::once(&router_connections)) {
             for connection in connections {
                 let edge = StreamEdge {
                     from: connection.source.clone(),
@@ -363,8 +444,29 @@ impl StreamCompositionEngine {
         Ok(())
     }
     
+    /// Every path through a router as a conditional connection, so the graph orders them
+    fn router_connections(&self) -> Vec<StreamConnection> {
+        self.routers.iter()
+            .flat_map(|(name, router)| {
+                let inputs = router.inputs();
+                router.outputs(name).into_iter().flat_map(move |output| {
+                    inputs.clone().into_iter().map(move |input| StreamConnection {
+                        source: input,
+                        destination: output.clone(),
+                        connection_type: ConnectionType::Conditional,
+                        transform: None,
+                        routing: RoutingConfig::default(),
+                    })
+                })
+            })
+            .collect()
+    }
+    
     /// Determine the type of a stream node based on its name and connections
     fn determine_node_type(&self, stream_name: &str) -> StreamNodeType {
+        if self.routers.iter().any(|(name, router)| router.outputs(name).iter().any(|output| output == stream_name)) {
+            return StreamNodeType::Router;
+        }
         let has_inputs = self.connections.values()
             .any(|conns| conns.iter().any(|c| c.destination == stream_name));
         
@@ -413,7 +515,7 @@ impl StreamCompositionEngine {
         result: &mut Vec<String>,
     ) -> crate::Result<()> {
         if temp_visited.contains(node) {
-            return Err(crate::SynthesisError::new(ErrorKind::InvalidArgument,
+            return Err(crate::SynthesisError::new(ErrorKind::InvalidExpression,
                 format!("Circular dependency detected involving stream '{}'", node)));
         }
         
@@ -438,7 +540,15 @@ impl StreamCompositionEngine {
     
     /// Process the stream composition graph
     pub fn process_composition(&mut self, stream_manager: &mut StreamManager) -> crate::Result<()> {
+        let mut routed = HashSet::new();
         for stream_name in &self.processing_graph.execution_order.clone() {
+            // A router runs when its first output comes up, after everything feeding its inputs
+            for (name, router) in &self.routers {
+                if !routed.contains(name) && router.outputs(name).contains(stream_name) {
+                    routed.insert(name.clone());
+                    self.process_router(name, router, stream_manager)?;
+                }
+            }
             if let Some(connections) = self.connections.get(stream_name) {
                 for connection in connections {
                     self.process_connection(connection, stream_manager)?;
@@ -512,6 +622,27 @@ impl StreamCompositionEngine {
         Ok(())
     }
     
+    fn process_router(&self, name: &str, router: &Router, stream_manager: &mut StreamManager) -> crate::Result<()> {
+        // Only what has arrived moves on, so control streams don't fill up with silence
+        let inputs: Vec<Vec<f32>> = router.inputs().iter()
+            .map(|input| {
+                let available = stream_manager.get_stream(input)
+                    .and_then(|stream| stream.try_read().ok().map(|data| data.buffer.len()))
+                    .unwrap_or(0);
+                if available == 0 {
+                    return Ok(Vec::new());
+                }
+                stream_manager.read_from_stream(input, available)
+            })
+            .collect::<crate::Result<_>>()?;
+        for (output, data) in router.outputs(name).iter().zip(router.route(&inputs)) {
+            if !data.is_empty() {
+                stream_manager.write_to_stream(output, data)?;
+            }
+        }
+        Ok(())
+    }
+    
     fn process_split_connection(&self, connection: &StreamConnection, stream_manager: &mut StreamManager) -> crate::Result<()> {
         // Split is handled by having multiple connections from the same source
         self.process_direct_connection(connection, stream_manager)
@@ -575,7 +706,7 @@ impl StreamCompositionEngine {
     pub fn add_composition_rule(&mut self, rule: CompositionRule) {
         self.composition_rules.push(rule);
         // Sort by priority (higher priority first)
-        self.composition_rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.composition_rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
     }
     
     /// Apply composition rules
@@ -591,10 +722,10 @@ impl StreamCompositionEngine {
     fn evaluate_condition(&self, condition: &CompositionCondition, stream_manager: &StreamManager) -> crate::Result<bool> {
         match condition {
             CompositionCondition::Always => Ok(true),
-            CompositionCondition::StreamTypeMatch { source_type, destination_type } => {
+            CompositionCondition::StreamTypeMatch { source_type: _, destination_type: _ } => {
                 // Check if any connections match the type pattern
                 Ok(self.connections.values().any(|conns| {
-                    conns.iter().any(|conn| {
+                    conns.iter().any(|_conn| {
                         // In a full implementation, we'd check actual stream types
                         // For now, just return true as a placeholder
                         true
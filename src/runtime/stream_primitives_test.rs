@@ -1,6 +1,5 @@
 #[cfg(test)]
 mod stream_primitives_tests {
-    use super::*;
     use crate::runtime::types::{Value, DataType};
     use crate::runtime::streams::{
         StreamManager, InputSourceType, OutputDestinationType, OutputFormat,
@@ -152,8 +151,9 @@ mod stream_primitives_tests {
             assert_eq!(cutoff, 440.0);
         }
         
+        // Parameters are stored from f32, so compare at f32 precision
         if let Some(Value::Float(resonance)) = manager.get_metadata("filter_transform", "param_resonance") {
-            assert_eq!(resonance, 0.7);
+            assert_eq!(resonance as f32, 0.7);
         }
         
         if let Some(Value::String(filter_type)) = manager.get_metadata("filter_transform", "param_filter_type") {
@@ -178,7 +178,7 @@ mod stream_primitives_tests {
         
         // Check reverb parameters
         if let Some(Value::Float(room_size)) = manager.get_metadata("reverb_transform", "param_room_size") {
-            assert_eq!(room_size, 0.8);
+            assert_eq!(room_size as f32, 0.8);
         }
     }
 
@@ -327,16 +327,18 @@ mod stream_primitives_tests {
         
         // Process the chain
         
+        // Intermediate streams between the stages
+        manager.create_stream("temp_1".to_string(), DataType::Audio, None).unwrap();
+        manager.create_stream("temp_2".to_string(), DataType::Audio, None).unwrap();
+        
         // Step 1: Generate input data
         let input_data = manager.process_input_stream("sine_input").unwrap();
         manager.write_to_stream("temp_1", input_data).unwrap();
         
         // Step 2: Apply gain
-        manager.create_stream("temp_1".to_string(), DataType::Audio, None).unwrap();
         manager.apply_transform_stream("temp_1", "gain", "temp_2").unwrap();
         
         // Step 3: Apply filter  
-        manager.create_stream("temp_2".to_string(), DataType::Audio, None).unwrap();
         manager.apply_transform_stream("temp_2", "filter", "audio_out").unwrap();
         
         // Step 4: Process output
@@ -0,0 +1,123 @@
+/// Stream taps: `level.tap("level.csv", rate: 100.hz)` logs a value to CSV while the sketch runs
+///
+/// Taps are sampled once per frame on the frame clock and decimated to the requested rate, so
+/// a 100 Hz tap on a 60 fps sketch writes one row per frame and a 10 Hz tap every sixth frame.
+/// Arrays become one column each; the files load directly with `pandas.read_csv`.
+use crate::runtime::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_TAP_RATE: f64 = 30.0;
+
+pub struct StreamTap {
+    pub source: String,
+    pub path: PathBuf,
+    pub rate: f64,
+    writer: BufWriter<File>,
+    next_due: Option<f64>,
+    /// Columns after `time`, fixed by the first sample
+    columns: Option<usize>,
+    rows: u64,
+}
+
+impl std::fmt::Debug for StreamTap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamTap")
+            .field("source", &self.source)
+            .field("path", &self.path)
+            .field("rate", &self.rate)
+            .field("rows", &self.rows)
+            .finish()
+    }
+}
+
+impl StreamTap {
+    pub fn open(source: &str, path: impl AsRef<Path>, rate: f64) -> crate::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("📝 {}.tap() rate must be above zero", source)
+            )
+            .with_suggestion("Try: rate: 100.hz"));
+        }
+        let file = File::create(&path).map_err(|error| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::PermissionDenied,
+            format!("📝 Couldn't create {}: {}", path.display(), error)
+        )
+        .with_suggestion("Check the folder exists and you can write to it"))?;
+
+        Ok(Self {
+            source: source.to_string(),
+            path,
+            rate,
+            writer: BufWriter::new(file),
+            next_due: None,
+            columns: None,
+            rows: 0,
+        })
+    }
+
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Log `value` at `time` seconds if a row is due
+    pub fn sample(&mut self, time: f64, value: &Value) -> std::io::Result<bool> {
+        let interval = 1.0 / self.rate;
+        match self.next_due {
+            Some(due) if time + 1e-9 < due => return Ok(false),
+            // Fell more than a row behind (a stall, or a breakpoint): re-anchor rather than burst
+            Some(due) if time - due >= interval => self.next_due = Some(time + interval),
+            Some(due) => self.next_due = Some(due + interval),
+            None => self.next_due = Some(time + interval),
+        }
+
+        let cells = tap_cells(value);
+        let columns = *self.columns.get_or_insert_with(|| cells.len().max(1));
+        if self.rows == 0 {
+            let header: Vec<String> = if columns == 1 {
+                vec![self.source.clone()]
+            } else {
+                (0..columns).map(|i| format!("{}_{}", self.source, i)).collect()
+            };
+            writeln!(self.writer, "time,{}", header.join(","))?;
+        }
+
+        let mut row = format!("{:.6}", time);
+        for i in 0..columns {
+            row.push(',');
+            if let Some(cell) = cells.get(i) {
+                row.push_str(cell);
+            }
+        }
+        writeln!(self.writer, "{}", row)?;
+        self.rows += 1;
+
+        // Flush about once a second so a killed sketch still leaves most of its data behind
+        if self.rows.is_multiple_of(self.rate.ceil().max(1.0) as u64) {
+            self.writer.flush()?;
+        }
+        Ok(true)
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// CSV cells for a value: numbers as-is, booleans as 0/1, arrays spread across columns
+fn tap_cells(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(tap_cells).collect(),
+        Value::Boolean(b) => vec![if *b { "1" } else { "0" }.to_string()],
+        Value::String(s) if s.contains([',', '"', '\n']) => vec![format!("\"{}\"", s.replace('"', "\"\""))],
+        Value::String(s) => vec![s.clone()],
+        Value::Null => vec![String::new()],
+        other => match other.as_number() {
+            Some(n) => vec![n.to_string()],
+            None => vec![String::new()],
+        },
+    }
+}
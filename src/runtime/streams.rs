@@ -1,5 +1,6 @@
 use crate::runtime::types::{DataType, Value};
-use crate::runtime::realtime_buffer::{SharedRealtimeBuffer, BufferError};
+use crate::runtime::realtime_buffer::SharedRealtimeBuffer;
+use crate::runtime::resources::{release_resource, track_resource, ResourceId, ResourceKind};
 use crate::errors::ErrorKind;
 use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex, RwLock};
@@ -25,6 +26,7 @@ pub struct StreamManager {
     processing_scheduler: Option<ProcessingScheduler>,
     real_time_config: RealTimeConfig,
     performance_metrics: Arc<Mutex<PerformanceMetrics>>,
+    resource_ids: HashMap<String, ResourceId>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +46,8 @@ pub struct PerformanceMetrics {
     pub buffer_underruns: u64,
     pub buffer_overruns: u64,
     pub streams_processed: u64,
+    pub frames_rendered: u64,
+    pub dropped_frames: u64,
     pub last_reset: Instant,
 }
 
@@ -240,6 +244,8 @@ impl StreamManager {
             buffer_underruns: 0,
             buffer_overruns: 0,
             streams_processed: 0,
+            frames_rendered: 0,
+            dropped_frames: 0,
             last_reset: Instant::now(),
         }));
         
@@ -249,6 +255,7 @@ impl StreamManager {
             processing_scheduler: None,
             real_time_config: config,
             performance_metrics,
+            resource_ids: HashMap::new(),
         }
     }
     
@@ -315,6 +322,7 @@ impl StreamManager {
             
             if let Some(task) = task {
                 let start_time = Instant::now();
+                let _span = tracing::trace_span!(target: "scheduler", "stream_task", stream = %task.stream_name).entered();
                 
                 // Process task based on priority and type
                 Self::process_task(&task, &config);
@@ -335,8 +343,13 @@ impl StreamManager {
                 
                 // Check if we exceeded our time budget
                 if processing_time > config.max_processing_time_us {
-                    eprintln!("Warning: Stream processing exceeded time budget: {}μs > {}μs", 
-                             processing_time, config.max_processing_time_us);
+                    tracing::warn!(
+                        target: "scheduler",
+                        task = ?task.task_type,
+                        processing_us = processing_time,
+                        budget_us = config.max_processing_time_us,
+                        "stream processing exceeded time budget"
+                    );
                 }
             } else {
                 // No tasks available - yield CPU briefly
@@ -380,10 +393,32 @@ impl StreamManager {
             processing_time_us: 0,
         };
         
+        self.track_stream(&name, max_buffer_size);
         self.streams.insert(name, Arc::new(RwLock::new(stream_data)));
         Ok(())
     }
     
+    /// Remove a stream and its connections, releasing its buffer
+    pub fn destroy_stream(&mut self, name: &str) -> bool {
+        let removed = self.streams.remove(name).is_some();
+        self.connections.remove(name);
+        for destinations in self.connections.values_mut() {
+            destinations.retain(|destination| destination != name);
+        }
+        if let Some(id) = self.resource_ids.remove(name) {
+            release_resource(id);
+        }
+        removed
+    }
+    
+    fn track_stream(&mut self, name: &str, buffer_size: usize) {
+        let id = track_resource(ResourceKind::Stream, name, buffer_size * std::mem::size_of::<f32>());
+        // Replacing a stream of the same name frees the old buffer
+        if let Some(previous) = self.resource_ids.insert(name.to_string(), id) {
+            release_resource(previous);
+        }
+    }
+    
     pub fn connect(&mut self, source: String, destination: String) -> crate::Result<()> {
         if !self.streams.contains_key(&source) {
             return Err(crate::SynthesisError::new(crate::ErrorKind::UnknownModule, format!("Source stream '{}' does not exist", source)));
@@ -395,7 +430,7 @@ impl StreamManager {
         
         self.connections
             .entry(source)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(destination);
         
         Ok(())
@@ -483,9 +518,9 @@ impl StreamManager {
                     }
                     
                     // Read available samples efficiently
-                    for i in 0..available_samples {
+                    for slot in data.iter_mut().take(available_samples) {
                         if let Some(sample) = stream_data.buffer.pop_front() {
-                            data[i] = sample;
+                            *slot = sample;
                         }
                         // Remaining elements already initialized to 0.0
                     }
@@ -524,6 +559,13 @@ impl StreamManager {
         Ok(())
     }
     
+    /// Most recent buffered sample, without consuming it
+    pub fn latest_sample(&self, name: &str) -> Option<f32> {
+        let stream = self.streams.get(name)?;
+        let stream_data = stream.try_read().ok()?;
+        stream_data.buffer.back().copied()
+    }
+    
     pub fn get_stream_value(&self, name: &str) -> Value {
         if let Some(stream) = self.streams.get(name) {
             let stream_data = stream.read().unwrap();
@@ -570,7 +612,7 @@ impl StreamManager {
     
     pub fn process_stream_data(&self, stream_name: &str) -> crate::Result<Vec<f32>> {
         if let Some(stream) = self.streams.get(stream_name) {
-            let mut stream_data = stream.write().unwrap();
+            let stream_data = stream.write().unwrap();
             let mut data = stream_data.buffer.iter().cloned().collect::<Vec<f32>>();
             
             // Apply processing chain
@@ -596,7 +638,7 @@ impl StreamManager {
                 // Simple low-pass filter implementation
                 let mut filtered = Vec::with_capacity(data.len());
                 let mut prev = 0.0;
-                let alpha = (*cutoff).min(1.0).max(0.0);
+                let alpha = cutoff.clamp(0.0, 1.0);
                 
                 for sample in data {
                     let filtered_sample = prev + alpha * (sample - prev);
@@ -674,7 +716,10 @@ impl StreamManager {
             let mut new_stream_data = source_data.clone();
             new_stream_data.name = new_name.clone();
             new_stream_data.timestamp = Instant::now();
+            let buffer_size = new_stream_data.max_buffer_size;
+            drop(source_data);
             
+            self.track_stream(&new_name, buffer_size);
             self.streams.insert(new_name, Arc::new(RwLock::new(new_stream_data)));
             Ok(())
         } else {
@@ -744,6 +789,7 @@ impl StreamManager {
             processing_time_us: 0,
         };
         
+        self.track_stream(&output_name, self.real_time_config.buffer_size);
         self.streams.insert(output_name, Arc::new(RwLock::new(merged_stream_data)));
         Ok(())
     }
@@ -751,13 +797,18 @@ impl StreamManager {
     pub fn get_stream_info(&self, name: &str) -> Option<StreamInfo> {
         if let Some(stream) = self.streams.get(name) {
             let stream_data = stream.read().unwrap();
+            // Audio reaches us a calibrated round trip after it happened in the room
+            let compensation = match stream_data.data_type {
+                DataType::Audio => crate::audio::latency::input_latency(),
+                _ => Duration::ZERO,
+            };
             Some(StreamInfo {
                 name: stream_data.name.clone(),
                 data_type: stream_data.data_type.clone(),
                 sample_rate: stream_data.sample_rate,
                 buffer_size: stream_data.buffer.len(),
                 is_active: stream_data.is_active,
-                age: stream_data.timestamp.elapsed(),
+                age: stream_data.timestamp.elapsed() + compensation,
                 processor_count: stream_data.processing_chain.len(),
             })
         } else {
@@ -785,11 +836,25 @@ impl Default for StreamManager {
     }
 }
 
+impl Drop for StreamManager {
+    fn drop(&mut self) {
+        for (_, id) in self.resource_ids.drain() {
+            release_resource(id);
+        }
+    }
+}
+
 impl StreamManager {
     pub fn get_performance_metrics(&self) -> PerformanceMetrics {
         self.performance_metrics.lock().unwrap().clone()
     }
     
+    pub fn record_frame(&self, tick: &crate::runtime::frame_pacing::FrameTick) {
+        let mut metrics = self.performance_metrics.lock().unwrap();
+        metrics.frames_rendered += 1;
+        metrics.dropped_frames += tick.dropped;
+    }
+    
     pub fn reset_performance_metrics(&mut self) {
         let mut metrics = self.performance_metrics.lock().unwrap();
         *metrics = PerformanceMetrics {
@@ -798,6 +863,8 @@ impl StreamManager {
             buffer_underruns: 0,
             buffer_overruns: 0,
             streams_processed: 0,
+            frames_rendered: 0,
+            dropped_frames: 0,
             last_reset: Instant::now(),
         };
     }
@@ -983,7 +1050,7 @@ impl StreamManager {
     // Core stream primitive implementations
     
     pub fn create_input_stream(&mut self, name: String, source_type: InputSourceType) -> crate::Result<()> {
-        let primitive = StreamPrimitive::Input { 
+        let _primitive = StreamPrimitive::Input { 
             source_type: source_type.clone(), 
             callback: None 
         };
@@ -1223,7 +1290,7 @@ impl StreamManager {
         
         let mut filtered = Vec::with_capacity(data.len());
         let mut prev = 0.0;
-        let alpha = cutoff.min(1.0).max(0.0);
+        let alpha = cutoff.clamp(0.0, 1.0);
         
         for &sample in data {
             let filtered_sample = prev + alpha * (sample - prev);
@@ -1335,26 +1402,26 @@ impl StreamManager {
         match destination_type {
             dest if dest.contains("AudioDevice") => {
                 // Simulate audio output
-                eprintln!("Audio output: {} samples", data.len());
+                tracing::debug!(target: "streams", samples = data.len(), "audio output");
                 Ok(())
             }
             dest if dest.contains("Graphics") => {
                 // Simulate graphics output
-                eprintln!("Graphics output: {} data points", data.len());
+                tracing::debug!(target: "streams", points = data.len(), "graphics output");
                 Ok(())
             }
             dest if dest.contains("MidiDevice") => {
                 // Simulate MIDI output
-                eprintln!("MIDI output: {} values", data.len());
+                tracing::debug!(target: "streams", values = data.len(), "midi output");
                 Ok(())
             }
             dest if dest.contains("OSC") => {
                 // Simulate OSC output
-                eprintln!("OSC output: {} messages", data.len());
+                tracing::debug!(target: "streams", messages = data.len(), "osc output");
                 Ok(())
             }
             _ => {
-                eprintln!("Unknown output destination: {}", destination_type);
+                tracing::warn!(target: "streams", destination = destination_type, "unknown output destination");
                 Ok(())
             }
         }
@@ -0,0 +1,346 @@
+/// Crash watchdog for unattended installations: `synthesis supervise script.syn` runs the
+/// script in a child process, restarts it when it crashes or stops producing frames, and
+/// hands the restarted child the last snapshot of its variables
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Set for the child: file it touches every frame so the supervisor can spot hangs
+pub const HEARTBEAT_ENV: &str = "SYNTHESIS_HEARTBEAT";
+/// Set for the child: where to periodically save variables and restore them from on start
+pub const SNAPSHOT_ENV: &str = "SYNTHESIS_SNAPSHOT";
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(500);
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    /// Restart when no heartbeat arrives for this long
+    pub hang_timeout: Duration,
+    /// Allowance for loading before the first frame
+    pub startup_grace: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// A child that runs this long without incident resets the backoff
+    pub stable_after: Duration,
+    pub incident_log: PathBuf,
+    pub snapshot_path: PathBuf,
+    pub heartbeat_path: PathBuf,
+}
+
+impl SupervisorConfig {
+    pub fn for_script(script: &Path) -> Self {
+        let stem = script.with_extension("");
+        let sibling = |suffix: &str| PathBuf::from(format!("{}.{}", stem.display(), suffix));
+        Self {
+            hang_timeout: Duration::from_secs(10),
+            startup_grace: Duration::from_secs(30),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            stable_after: Duration::from_secs(120),
+            incident_log: sibling("incidents.log"),
+            snapshot_path: sibling("snapshot"),
+            heartbeat_path: std::env::temp_dir().join(format!("synthesis-heartbeat-{}", std::process::id())),
+        }
+    }
+}
+
+/// Doubling restart delay, capped, reset once the child is stable again
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max, current: initial }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incident {
+    Crashed(Option<i32>),
+    Hung(Duration),
+    SpawnFailed(String),
+}
+
+impl std::fmt::Display for Incident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Incident::Crashed(Some(code)) => write!(f, "crashed with exit code {}", code),
+            Incident::Crashed(None) => write!(f, "crashed (killed by signal)"),
+            Incident::Hung(silence) => write!(f, "hung: no frame for {:.1}s", silence.as_secs_f32()),
+            Incident::SpawnFailed(error) => write!(f, "failed to start: {}", error),
+        }
+    }
+}
+
+pub struct Supervisor {
+    config: SupervisorConfig,
+    script: PathBuf,
+    script_args: Vec<String>,
+    backoff: Backoff,
+    pub restarts: u32,
+}
+
+impl Supervisor {
+    pub fn new(script: impl Into<PathBuf>, script_args: Vec<String>) -> Self {
+        let script = script.into();
+        let config = SupervisorConfig::for_script(&script);
+        Self::with_config(script, script_args, config)
+    }
+
+    pub fn with_config(script: impl Into<PathBuf>, script_args: Vec<String>, config: SupervisorConfig) -> Self {
+        Self {
+            backoff: Backoff::new(config.initial_backoff, config.max_backoff),
+            config,
+            script: script.into(),
+            script_args,
+            restarts: 0,
+        }
+    }
+
+    /// Keep the script running until it exits cleanly
+    pub fn run(&mut self) -> crate::Result<()> {
+        println!("🛡️  Supervising {} (hang timeout {}s)", self.script.display(), self.config.hang_timeout.as_secs());
+        println!("   Incidents are logged to {}", self.config.incident_log.display());
+
+        loop {
+            let started = Instant::now();
+            let incident = match self.spawn() {
+                Ok(mut child) => match self.watch(&mut child, started) {
+                    None => {
+                        println!("🛡️  {} exited normally, stopping supervisor", self.script.display());
+                        let _ = std::fs::remove_file(&self.config.heartbeat_path);
+                        return Ok(());
+                    }
+                    Some(incident) => incident,
+                },
+                Err(error) => Incident::SpawnFailed(error.to_string()),
+            };
+
+            if started.elapsed() >= self.config.stable_after {
+                self.backoff.reset();
+            }
+            let delay = self.backoff.next_delay();
+            self.restarts += 1;
+
+            self.log_incident(&incident, delay);
+            eprintln!("🛡️  {} {}, restarting in {:.0}s (restart #{})",
+                self.script.display(), incident, delay.as_secs_f32(), self.restarts);
+            std::thread::sleep(delay);
+        }
+    }
+
+    fn spawn(&self) -> std::io::Result<Child> {
+        let _ = std::fs::remove_file(&self.config.heartbeat_path);
+        Command::new(std::env::current_exe()?)
+            .arg("run")
+            .arg(&self.script)
+            .args(&self.script_args)
+            .env(HEARTBEAT_ENV, &self.config.heartbeat_path)
+            .env(SNAPSHOT_ENV, &self.config.snapshot_path)
+            .spawn()
+    }
+
+    /// Wait for the child to exit or hang; None means it finished successfully
+    fn watch(&self, child: &mut Child, started: Instant) -> Option<Incident> {
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => return None,
+                Ok(Some(status)) => return Some(Incident::Crashed(status.code())),
+                Ok(None) => {}
+                Err(_) => return Some(Incident::Crashed(None)),
+            }
+
+            let silence = match heartbeat_age(&self.config.heartbeat_path) {
+                Some(age) => (age > self.config.hang_timeout).then_some(age),
+                None => {
+                    let waited = started.elapsed();
+                    (waited > self.config.startup_grace + self.config.hang_timeout).then_some(waited)
+                }
+            };
+            if let Some(silence) = silence {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Some(Incident::Hung(silence));
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn log_incident(&self, incident: &Incident, delay: Duration) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let line = format!("{}\t{}\t{}\trestart #{} in {:.0}s\n",
+            timestamp, self.script.display(), incident, self.restarts, delay.as_secs_f32());
+
+        if let Ok(mut log) = OpenOptions::new().create(true).append(true).open(&self.config.incident_log) {
+            let _ = log.write_all(line.as_bytes());
+        }
+    }
+}
+
+fn heartbeat_age(path: &Path) -> Option<Duration> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(SystemTime::now().duration_since(modified).unwrap_or_default())
+}
+
+/// Child side of supervision: heartbeats and periodic snapshots, driven from the frame loop
+pub struct SupervisedRun {
+    heartbeat_path: Option<PathBuf>,
+    snapshot_path: Option<PathBuf>,
+    last_heartbeat: Option<Instant>,
+    last_snapshot: Instant,
+    restored: bool,
+}
+
+impl SupervisedRun {
+    /// Present only when started by `synthesis supervise`
+    pub fn from_env() -> Option<Self> {
+        let heartbeat_path = std::env::var_os(HEARTBEAT_ENV).map(PathBuf::from);
+        let snapshot_path = std::env::var_os(SNAPSHOT_ENV).map(PathBuf::from);
+        if heartbeat_path.is_none() && snapshot_path.is_none() {
+            return None;
+        }
+        Some(Self { heartbeat_path, snapshot_path, last_heartbeat: None, last_snapshot: Instant::now(), restored: false })
+    }
+
+    pub fn heartbeat(&mut self, frame: u64) {
+        let due = self.last_heartbeat.map(|t| t.elapsed() >= HEARTBEAT_INTERVAL).unwrap_or(true);
+        if let (true, Some(path)) = (due, &self.heartbeat_path) {
+            let _ = std::fs::write(path, frame.to_string());
+            self.last_heartbeat = Some(Instant::now());
+        }
+    }
+
+    /// Bring back the variables saved before the last crash; only the first call restores
+    pub fn restore(&mut self, variables: &mut HashMap<String, Value>) -> usize {
+        let restored = match &self.snapshot_path {
+            Some(path) if !self.restored => load_snapshot(path),
+            _ => return 0,
+        };
+        self.restored = true;
+        let count = restored.len();
+        if count > 0 {
+            println!("🛡️  Restored {} variable(s) from the last snapshot", count);
+        }
+        variables.extend(restored);
+        count
+    }
+
+    pub fn maybe_snapshot(&mut self, variables: &HashMap<String, Value>) {
+        if self.last_snapshot.elapsed() < SNAPSHOT_INTERVAL {
+            return;
+        }
+        if let Some(path) = &self.snapshot_path {
+            let _ = save_snapshot(path, variables);
+        }
+        self.last_snapshot = Instant::now();
+    }
+}
+
+/// Write plain-data variables (numbers, text, booleans and flat lists of those)
+/// Streams, functions and module objects can't outlive the process, so they're skipped
+pub fn save_snapshot(path: &Path, variables: &HashMap<String, Value>) -> std::io::Result<()> {
+    let mut lines: Vec<String> = variables
+        .iter()
+        .filter_map(|(name, value)| encode_value(value).map(|encoded| format!("{}\t{}", name, encoded)))
+        .collect();
+    lines.sort();
+
+    // Write then rename, so a crash mid-save never leaves a torn snapshot
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, lines.join("\n"))?;
+    std::fs::rename(&temporary, path)
+}
+
+pub fn load_snapshot(path: &Path) -> HashMap<String, Value> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return HashMap::new(),
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, encoded) = line.split_once('\t')?;
+            Some((name.to_string(), decode_value(encoded)?))
+        })
+        .collect()
+}
+
+fn encode_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Integer(n) => Some(format!("i:{}", n)),
+        Value::Float(n) => Some(format!("f:{}", n)),
+        Value::Boolean(b) => Some(format!("b:{}", b)),
+        Value::Null => Some("n:".to_string()),
+        Value::String(s) => Some(format!("s:{}", escape(s))),
+        Value::Array(items) => {
+            let encoded: Option<Vec<String>> = items
+                .iter()
+                .map(|item| match item {
+                    Value::Array(_) => None,
+                    other => encode_value(other).map(|e| escape(&e)),
+                })
+                .collect();
+            encoded.map(|items| format!("a:{}", items.join("\u{1f}")))
+        }
+        _ => None,
+    }
+}
+
+fn decode_value(encoded: &str) -> Option<Value> {
+    let (tag, body) = encoded.split_once(':')?;
+    match tag {
+        "i" => body.parse().ok().map(Value::Integer),
+        "f" => body.parse().ok().map(Value::Float),
+        "b" => body.parse().ok().map(Value::Boolean),
+        "n" => Some(Value::Null),
+        "s" => Some(Value::String(unescape(body))),
+        "a" if body.is_empty() => Some(Value::Array(Vec::new())),
+        "a" => body.split('\u{1f}').map(|item| decode_value(&unescape(item))).collect::<Option<Vec<_>>>().map(Value::Array),
+        _ => None,
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t").replace('\u{1f}', "\\u")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('u') => out.push('\u{1f}'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
@@ -0,0 +1,183 @@
+/// Guided lessons for `synthesis learn`
+///
+/// Each lesson hands out a starter file and a task. Every time the file is saved it is
+/// parsed and run for a few headless frames; errors come back with their usual suggestions,
+/// and a script that runs but misses the task gets a hint for the first thing it's missing.
+use crate::errors::SynthesisError;
+use crate::runtime::{CapabilityPolicy, SandboxLimits, SandboxReport, SandboxedEngine};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Frames a lesson run gets; enough to tell a `loop { }` from a one-off script
+const LESSON_FRAMES: u64 = 3;
+
+const PROGRESS_FILE: &str = ".progress";
+
+/// Something a solution has to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    /// Call `Module.function()` at least once
+    Calls(&'static str, &'static str, &'static str),
+    /// Leave a variable with this name defined
+    Defines(&'static str, &'static str),
+    /// Run a `loop { }` so the sketch keeps drawing
+    Animates(&'static str),
+}
+
+impl Goal {
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Goal::Calls(_, _, hint) | Goal::Defines(_, hint) | Goal::Animates(hint) => hint,
+        }
+    }
+
+    pub fn is_met(&self, report: &SandboxReport) -> bool {
+        match self {
+            Goal::Calls(module, function, _) => report.calls.iter().any(|c| c.module == *module && c.function == *function),
+            Goal::Defines(name, _) => report.variables.contains_key(*name),
+            Goal::Animates(_) => report.frames_run > 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lesson {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub task: &'static str,
+    pub starter: &'static str,
+    pub goals: &'static [Goal],
+}
+
+#[derive(Debug, Clone)]
+pub enum LessonCheck {
+    Passed,
+    /// The script didn't parse or failed while running
+    Error(SynthesisError),
+    /// It runs, but doesn't do what the task asks yet
+    Unmet(&'static str),
+}
+
+const LESSONS: &[Lesson] = &[
+    Lesson {
+        id: "first_shape",
+        title: "Your first shape",
+        task: "Clear the screen, then draw a circle with Graphics.circle(x, y, radius).",
+        starter: "// Lesson 1: Your first shape\n// Draw a circle in the middle of the screen.\n\nGraphics.clear(0)\n\n// Graphics.circle(x, y, radius) goes here\n",
+        goals: &[
+            Goal::Calls("Graphics", "circle", "Add a line like Graphics.circle(160, 90, 40) under the clear"),
+        ],
+    },
+    Lesson {
+        id: "variables",
+        title: "Naming things",
+        task: "Store the circle's radius in a variable called size and draw the circle with it.",
+        starter: "// Lesson 2: Naming things\n// Give the radius a name so you can change it in one place.\n\nGraphics.clear(0)\nGraphics.circle(160, 90, 40)\n",
+        goals: &[
+            Goal::Defines("size", "Create the variable first: size = 40"),
+            Goal::Calls("Graphics", "circle", "Draw the circle with your variable: Graphics.circle(160, 90, size)"),
+        ],
+    },
+    Lesson {
+        id: "animation",
+        title: "Making it move",
+        task: "Put the drawing inside loop { } and make size grow a little every frame.",
+        starter: "// Lesson 3: Making it move\n// Code inside loop { } runs once per frame.\n\nsize = 10\n\nGraphics.clear(0)\nGraphics.circle(160, 90, size)\n",
+        goals: &[
+            Goal::Animates("Wrap the clear and the circle in loop { ... } so they run every frame"),
+            Goal::Calls("Graphics", "circle", "Keep drawing the circle inside the loop"),
+        ],
+    },
+    Lesson {
+        id: "orbit",
+        title: "Going in circles",
+        task: "Move the circle around the centre using Math.sin and Math.cos of an angle that grows each frame.",
+        starter: "// Lesson 4: Going in circles\n// x = 160 + Math.cos(angle) * 60 traces the horizontal part of a circle.\n\nangle = 0.0\n\nloop {\n    Graphics.clear(0)\n    Graphics.circle(160, 90, 10)\n}\n",
+        goals: &[
+            Goal::Calls("Math", "cos", "Work out x with Math.cos(angle), like x = 160 + Math.cos(angle) * 60"),
+            Goal::Calls("Math", "sin", "Work out y with Math.sin(angle), like y = 90 + Math.sin(angle) * 60"),
+            Goal::Animates("Keep everything inside loop { } and add angle = angle + 0.05"),
+        ],
+    },
+    Lesson {
+        id: "controls",
+        title: "Hands on",
+        task: "Add a GUI.slider so the radius can be changed while the sketch runs.",
+        starter: "// Lesson 5: Hands on\n// GUI.slider(\"Label\", min, max, start) returns the slider's current value.\n\nloop {\n    Graphics.clear(0)\n    Graphics.circle(160, 90, 30)\n}\n",
+        goals: &[
+            Goal::Calls("GUI", "slider", "Inside the loop: size = GUI.slider(\"Size\", 5, 80, 30)"),
+            Goal::Calls("Graphics", "circle", "Use the slider's value as the radius"),
+        ],
+    },
+    Lesson {
+        id: "listening",
+        title: "Listening",
+        task: "Open the microphone with Audio.mic_input() and flash the screen when Audio.beat_detect() hears a beat.",
+        starter: "// Lesson 6: Listening\n// if condition { ... } runs its block only when the condition is true.\n\nloop {\n    Graphics.clear(0)\n}\n",
+        goals: &[
+            Goal::Calls("Audio", "mic_input", "Before the loop: audio = Audio.mic_input()"),
+            Goal::Calls("Audio", "beat_detect", "Inside the loop: if Audio.beat_detect(audio) { Graphics.flash(16777215, 0.1) }"),
+            Goal::Animates("Keep the loop so it keeps listening"),
+        ],
+    },
+];
+
+pub fn lessons() -> &'static [Lesson] {
+    LESSONS
+}
+
+impl Lesson {
+    /// Run a solution and see how far along it is
+    pub fn check(&self, source: &str) -> LessonCheck {
+        // Lessons use the mic and GUI, so devices stay allowed; the budget still stops runaway loops
+        let report = SandboxedEngine::new(SandboxLimits { max_frames: LESSON_FRAMES, ..SandboxLimits::default() })
+            .with_capabilities(CapabilityPolicy::default())
+            .run(source);
+
+        if let Some(error) = report.error {
+            return LessonCheck::Error(error);
+        }
+        match self.goals.iter().find(|goal| !goal.is_met(&report)) {
+            Some(goal) => LessonCheck::Unmet(goal.hint()),
+            None => LessonCheck::Passed,
+        }
+    }
+
+    /// Where the learner's copy of this lesson lives
+    pub fn file_in(&self, dir: &Path) -> PathBuf {
+        let number = LESSONS.iter().position(|l| l.id == self.id).unwrap_or(0) + 1;
+        dir.join(format!("{:02}_{}.syn", number, self.id))
+    }
+}
+
+/// Completed lessons, kept next to the lesson files
+#[derive(Debug, Clone, Default)]
+pub struct LessonProgress {
+    path: PathBuf,
+    completed: BTreeSet<String>,
+}
+
+impl LessonProgress {
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(PROGRESS_FILE);
+        let completed = std::fs::read_to_string(&path)
+            .map(|text| text.lines().map(|line| line.trim().to_string()).filter(|l| !l.is_empty()).collect())
+            .unwrap_or_default();
+        Self { path, completed }
+    }
+
+    pub fn is_complete(&self, lesson: &Lesson) -> bool {
+        self.completed.contains(lesson.id)
+    }
+
+    /// The first lesson not yet passed, or `None` once they're all done
+    pub fn next_lesson(&self) -> Option<usize> {
+        LESSONS.iter().position(|lesson| !self.is_complete(lesson))
+    }
+
+    pub fn complete(&mut self, lesson: &Lesson) -> std::io::Result<()> {
+        self.completed.insert(lesson.id.to_string());
+        let text: String = self.completed.iter().map(|id| format!("{}\n", id)).collect();
+        std::fs::write(&self.path, text)
+    }
+}
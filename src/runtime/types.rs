@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fmt;
+use crate::runtime::events::EventStream;
 use crate::runtime::units::UnitValue;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,8 +12,17 @@ pub enum Value {
     Stream(Stream),
     Function(Function),
     Object(HashMap<String, Value>),
+    /// An instance of a script's `struct` or `class`; unlike an object it only has the fields its definition lists
+    Struct { name: String, fields: HashMap<String, Value> },
     Array(Vec<Value>),
     UnitValue(UnitValue),
+    /// `0..10` counts 0 to 9, `0..=10` includes the 10
+    Range { start: i64, end: i64, inclusive: bool },
+    /// Timestamped notes, triggers and messages, unlike a stream's continuous samples
+    Events(EventStream),
+    /// `#ff00aa`: red, green, blue and alpha, each 0 to 255. Anything that takes a colour as a
+    /// number like `0xFF00AA` takes it as one
+    Color([u8; 4]),
     Null,
 }
 
@@ -33,6 +43,16 @@ impl fmt::Display for Value {
                 }
                 write!(f, "}}")
             }
+            Value::Struct { name, fields } => {
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_by(|a, b| a.0.cmp(b.0));
+                write!(f, "{} {{ ", name)?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, " }}")
+            }
             Value::Array(arr) => {
                 write!(f, "[")?;
                 for (i, value) in arr.iter().enumerate() {
@@ -42,6 +62,10 @@ impl fmt::Display for Value {
                 write!(f, "]")
             }
             Value::UnitValue(unit_val) => write!(f, "{}{}", unit_val.value, unit_val.unit.to_string()),
+            Value::Range { start, end, inclusive } => write!(f, "{}{}{}", start, if *inclusive { "..=" } else { ".." }, end),
+            Value::Events(events) => write!(f, "Events<{}>", events.len()),
+            Value::Color([r, g, b, 255]) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            Value::Color([r, g, b, a]) => write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
             Value::Null => write!(f, "null"),
         }
     }
@@ -81,8 +105,12 @@ impl Value {
             Value::Stream(_) => "stream",
             Value::Function(_) => "function",
             Value::Object(_) => "object",
+            Value::Struct { .. } => "struct",
             Value::Array(_) => "array",
             Value::UnitValue(_) => "unit_value",
+            Value::Range { .. } => "range",
+            Value::Events(_) => "events",
+            Value::Color(_) => "color",
             Value::Null => "null",
         }
     }
@@ -104,6 +132,9 @@ impl Value {
             Value::Integer(n) => Some(*n as f64),
             Value::Float(f) => Some(*f),
             Value::UnitValue(unit_val) => Some(unit_val.to_base_value()),
+            // Graphics functions take colours as 0xRRGGBB; a translucent one also says how
+            // transparent it is in the byte above, so opaque colours read as plain 0xRRGGBB
+            Value::Color([r, g, b, a]) => Some(((255 - *a as u32) << 24 | (*r as u32) << 16 | (*g as u32) << 8 | *b as u32) as f64),
             _ => None,
         }
     }
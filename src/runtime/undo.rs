@@ -0,0 +1,135 @@
+/// Undo/redo for live parameter changes
+///
+/// Every control or public-variable change made through the control API lands here, so a
+/// tweak made in rehearsal can be taken back with Ctrl+Z or `Undo.last()`. Quick successive
+/// changes to the same parameter (dragging a slider) collapse into one step.
+use crate::runtime::Value;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Oldest steps are dropped past this
+pub const MAX_UNDO_STEPS: usize = 200;
+/// Changes to one parameter closer together than this are one gesture
+pub const UNDO_COALESCE_WINDOW: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamTarget {
+    /// A GUI control by label; `Null` values mean "no override, follow the local widget"
+    Control(String),
+    /// A variable listed in `config { remote: [...] }`
+    Variable(String),
+}
+
+impl ParamTarget {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Control(name) | Self::Variable(name) => name,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Control(_) => "control",
+            Self::Variable(_) => "variable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamChange {
+    pub target: ParamTarget,
+    pub before: Value,
+    pub after: Value,
+    pub time: Instant,
+}
+
+impl ParamChange {
+    pub fn to_value(&self) -> Value {
+        let mut fields = std::collections::HashMap::new();
+        fields.insert("name".to_string(), Value::String(self.target.name().to_string()));
+        fields.insert("kind".to_string(), Value::String(self.target.kind().to_string()));
+        fields.insert("before".to_string(), self.before.clone());
+        fields.insert("after".to_string(), self.after.clone());
+        Value::Object(fields)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    undo: Vec<ParamChange>,
+    redo: Vec<ParamChange>,
+}
+
+impl UndoHistory {
+    pub fn record(&mut self, target: ParamTarget, before: Value, after: Value) {
+        if before == after {
+            return;
+        }
+        let now = Instant::now();
+        self.redo.clear();
+        if let Some(last) = self.undo.last_mut() {
+            if last.target == target && now.duration_since(last.time) < UNDO_COALESCE_WINDOW {
+                last.after = after;
+                last.time = now;
+                if last.before == last.after {
+                    self.undo.pop();
+                }
+                return;
+            }
+        }
+        self.undo.push(ParamChange { target, before, after, time: now });
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Take back the latest change, or the latest change to one parameter when `name` is given
+    pub fn undo(&mut self, name: Option<&str>) -> Option<ParamChange> {
+        let index = match name {
+            Some(name) => self.undo.iter().rposition(|change| change.target.name() == name)?,
+            None => self.undo.len().checked_sub(1)?,
+        };
+        let change = self.undo.remove(index);
+        self.redo.push(change.clone());
+        Some(change)
+    }
+
+    pub fn redo(&mut self) -> Option<ParamChange> {
+        let change = self.redo.pop()?;
+        self.undo.push(change.clone());
+        Some(change)
+    }
+
+    /// Undoable steps, oldest first
+    pub fn entries(&self) -> &[ParamChange] {
+        &self.undo
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+static UNDO_HISTORY: OnceLock<Mutex<UndoHistory>> = OnceLock::new();
+
+pub fn undo_history() -> &'static Mutex<UndoHistory> {
+    UNDO_HISTORY.get_or_init(|| Mutex::new(UndoHistory::default()))
+}
+
+/// Undo and apply: the parameter goes back to its `before` value. Returns what was undone.
+pub fn undo_last(name: Option<&str>) -> Option<ParamChange> {
+    let change = undo_history().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).undo(name)?;
+    crate::runtime::remote_control::restore_param(&change.target, change.before.clone());
+    Some(change)
+}
+
+pub fn redo_last() -> Option<ParamChange> {
+    let change = undo_history().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).redo()?;
+    crate::runtime::remote_control::restore_param(&change.target, change.after.clone());
+    Some(change)
+}
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnitValue {
@@ -24,6 +23,12 @@ pub enum Unit {
     Hertz,
     Kilohertz,
     
+    // Musical lengths, resolved against the transport tempo: a note is a whole note,
+    // so 1/8.note is an eighth; a beat is a quarter note
+    Note,
+    Beat,
+    Bar,
+    
     // Dimensionless
     Scalar,
 }
@@ -31,18 +36,26 @@ pub enum Unit {
 impl Unit {
     pub fn from_string(unit_str: &str) -> Option<Unit> {
         match unit_str {
-            "s" => Some(Unit::Second),
+            "s" | "second" | "seconds" => Some(Unit::Second),
             "ms" => Some(Unit::Millisecond),
             "px" => Some(Unit::Pixel),
             "%" | "percent" => Some(Unit::Percent),
             "degrees" => Some(Unit::Degree),
             "radians" => Some(Unit::Radian),
-            "Hz" => Some(Unit::Hertz),
-            "kHz" => Some(Unit::Kilohertz),
+            "Hz" | "hz" => Some(Unit::Hertz),
+            "kHz" | "khz" => Some(Unit::Kilohertz),
+            "note" | "notes" => Some(Unit::Note),
+            "beat" | "beats" => Some(Unit::Beat),
+            "bar" | "bars" => Some(Unit::Bar),
             _ => None,
         }
     }
     
+    /// Musical lengths only become a time once there's a tempo
+    pub fn is_tempo_relative(&self) -> bool {
+        matches!(self, Unit::Note | Unit::Beat | Unit::Bar)
+    }
+    
     pub fn to_string(&self) -> &'static str {
         match self {
             Unit::Second => "s",
@@ -53,6 +66,9 @@ impl Unit {
             Unit::Radian => "radians",
             Unit::Hertz => "Hz",
             Unit::Kilohertz => "kHz",
+            Unit::Note => "note",
+            Unit::Beat => "beat",
+            Unit::Bar => "bar",
             Unit::Scalar => "",
         }
     }
@@ -69,6 +85,9 @@ impl Unit {
             // Frequency units are compatible
             (Hertz, Kilohertz) | (Kilohertz, Hertz) => true,
             
+            // Notes and beats are compatible; bars depend on the time signature
+            (Note, Beat) | (Beat, Note) => true,
+            
             // Same units are always compatible
             (a, b) if a == b => true,
             
@@ -97,6 +116,10 @@ impl Unit {
             (Hertz, Kilohertz) => Some(0.001),
             (Kilohertz, Hertz) => Some(1000.0),
             
+            // Musical conversions
+            (Note, Beat) => Some(4.0),
+            (Beat, Note) => Some(0.25),
+            
             // To/from scalar
             (Scalar, _) | (_, Scalar) => Some(1.0),
             
@@ -115,11 +138,7 @@ impl UnitValue {
     }
     
     pub fn convert_to(&self, target_unit: &Unit) -> Option<UnitValue> {
-        if let Some(factor) = self.unit.conversion_factor(target_unit) {
-            Some(UnitValue::new(self.value * factor, target_unit.clone()))
-        } else {
-            None
-        }
+        self.unit.conversion_factor(target_unit).map(|factor| UnitValue::new(self.value * factor, target_unit.clone()))
     }
     
     pub fn add(&self, other: &UnitValue) -> Option<UnitValue> {
@@ -158,6 +177,19 @@ impl UnitValue {
         }
     }
     
+    /// Length in seconds at `bpm`, counting quarter-note beats; None for units that aren't time
+    pub fn to_seconds(&self, bpm: f64, beats_per_bar: f64) -> Option<f64> {
+        let beat = 60.0 / bpm;
+        match &self.unit {
+            Unit::Second | Unit::Scalar => Some(self.value),
+            Unit::Millisecond => Some(self.value * 0.001),
+            Unit::Note => Some(self.value * 4.0 * beat),
+            Unit::Beat => Some(self.value * beat),
+            Unit::Bar => Some(self.value * beats_per_bar * beat),
+            _ => None,
+        }
+    }
+    
     // Convert to base unit value (for calculations)
     pub fn to_base_value(&self) -> f64 {
         match &self.unit {
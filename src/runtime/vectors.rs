@@ -0,0 +1,365 @@
+/// Vectors and matrices for positions, velocities and transforms
+///
+/// In scripts they're plain structs, so fields read and assign like any other: `Vec2 { x, y }`,
+/// `Vec3 { x, y, z }`, and `Mat3`/`Mat4` holding `m`, a list of 9 or 16 numbers in column-major
+/// order, the way GLSL and the GPU lay them out. A Mat3 is a 2D transform in homogeneous
+/// coordinates and a Mat4 a 3D one, so `matrix * point` moves points as well as turning them.
+use crate::parser::ast::{BinaryOperator, Expression, Field, Literal, StructDef, TypeAnnotation};
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Linear {
+    Vec2([f64; 2]),
+    Vec3([f64; 3]),
+    Mat3([f64; 9]),
+    Mat4([f64; 16]),
+}
+
+const VECTOR_FIELDS: [&str; 3] = ["x", "y", "z"];
+/// How close two components have to be for `==`
+const EPSILON: f64 = 1e-9;
+
+impl Linear {
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let Value::Struct { name, fields } = value else {
+            return None;
+        };
+        let component = |field: &str| fields.get(field).and_then(|v| v.as_number());
+        let entries = |count: usize| match fields.get("m") {
+            Some(Value::Array(items)) if items.len() == count => items.iter().map(|item| item.as_number()).collect::<Option<Vec<f64>>>(),
+            _ => None,
+        };
+        match name.as_str() {
+            "Vec2" if fields.len() == 2 => Some(Linear::Vec2([component("x")?, component("y")?])),
+            "Vec3" if fields.len() == 3 => Some(Linear::Vec3([component("x")?, component("y")?, component("z")?])),
+            "Mat3" => Some(Linear::Mat3(entries(9)?.try_into().ok()?)),
+            "Mat4" => Some(Linear::Mat4(entries(16)?.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    pub fn to_value(self) -> Value {
+        let name = self.name().to_string();
+        let fields = match self {
+            Linear::Vec2(_) | Linear::Vec3(_) => VECTOR_FIELDS.iter()
+                .zip(self.components())
+                .map(|(field, value)| (field.to_string(), Value::Float(*value)))
+                .collect(),
+            Linear::Mat3(_) | Linear::Mat4(_) => {
+                let entries = self.components().iter().map(|value| Value::Float(*value)).collect();
+                HashMap::from([("m".to_string(), Value::Array(entries))])
+            }
+        };
+        Value::Struct { name, fields }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Linear::Vec2(_) => "Vec2",
+            Linear::Vec3(_) => "Vec3",
+            Linear::Mat3(_) => "Mat3",
+            Linear::Mat4(_) => "Mat4",
+        }
+    }
+
+    pub fn components(&self) -> &[f64] {
+        match self {
+            Linear::Vec2(v) => v,
+            Linear::Vec3(v) => v,
+            Linear::Mat3(m) => m,
+            Linear::Mat4(m) => m,
+        }
+    }
+
+    fn components_mut(&mut self) -> &mut [f64] {
+        match self {
+            Linear::Vec2(v) => v,
+            Linear::Vec3(v) => v,
+            Linear::Mat3(m) => m,
+            Linear::Mat4(m) => m,
+        }
+    }
+
+    pub fn map(mut self, f: impl Fn(f64) -> f64) -> Self {
+        for value in self.components_mut() {
+            *value = f(*value);
+        }
+        self
+    }
+
+    /// Component by component, when both are the same kind
+    pub fn zip(mut self, other: Self, f: impl Fn(f64, f64) -> f64) -> Option<Self> {
+        if std::mem::discriminant(&self) != std::mem::discriminant(&other) {
+            return None;
+        }
+        for (value, other) in self.components_mut().iter_mut().zip(other.components()) {
+            *value = f(*value, *other);
+        }
+        Some(self)
+    }
+
+    pub fn is_vector(&self) -> bool {
+        matches!(self, Linear::Vec2(_) | Linear::Vec3(_))
+    }
+
+    /// Side length of a matrix
+    fn order(&self) -> Option<usize> {
+        match self {
+            Linear::Mat3(_) => Some(3),
+            Linear::Mat4(_) => Some(4),
+            _ => None,
+        }
+    }
+}
+
+fn matrix(order: usize, entries: Vec<f64>) -> Linear {
+    match order {
+        3 => Linear::Mat3(entries.try_into().unwrap_or(IDENTITY3)),
+        _ => Linear::Mat4(entries.try_into().unwrap_or(IDENTITY4)),
+    }
+}
+
+pub const IDENTITY3: [f64; 9] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+pub const IDENTITY4: [f64; 16] = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+/// Entry at `row`, `column` of a column-major matrix
+fn at(m: &[f64], order: usize, row: usize, column: usize) -> f64 {
+    m[column * order + row]
+}
+
+fn matrix_product(a: &[f64], b: &[f64], order: usize) -> Vec<f64> {
+    let mut product = vec![0.0; order * order];
+    for column in 0..order {
+        for row in 0..order {
+            product[column * order + row] = (0..order).map(|k| at(a, order, row, k) * at(b, order, k, column)).sum();
+        }
+    }
+    product
+}
+
+/// `matrix * vector`; a vector one shorter than the matrix is a point, so it gets moved by the
+/// translation and divided back out of homogeneous coordinates
+pub fn transform(m: &[f64], order: usize, v: &[f64]) -> Option<Vec<f64>> {
+    let point = v.len() + 1 == order;
+    if v.len() != order && !point {
+        return None;
+    }
+    let input: Vec<f64> = v.iter().copied().chain(point.then_some(1.0)).collect();
+    let mut output: Vec<f64> = (0..order).map(|row| (0..order).map(|k| at(m, order, row, k) * input[k]).sum()).collect();
+    if point {
+        let w = output.pop().unwrap_or(1.0);
+        if w.abs() > EPSILON && (w - 1.0).abs() > EPSILON {
+            output.iter_mut().for_each(|value| *value /= w);
+        }
+    }
+    Some(output)
+}
+
+fn vector(components: Vec<f64>) -> Option<Linear> {
+    match components[..] {
+        [x, y] => Some(Linear::Vec2([x, y])),
+        [x, y, z] => Some(Linear::Vec3([x, y, z])),
+        _ => None,
+    }
+}
+
+pub fn transpose(m: Linear) -> Option<Linear> {
+    let order = m.order()?;
+    let entries = m.components();
+    Some(matrix(order, (0..order * order).map(|i| at(entries, order, i % order, i / order)).collect()))
+}
+
+/// The inverse by Gauss-Jordan elimination, or None when the matrix flattens space and can't be undone
+pub fn inverse(m: Linear) -> Option<Linear> {
+    let order = m.order()?;
+    let mut a: Vec<Vec<f64>> = (0..order).map(|row| (0..order).map(|column| at(m.components(), order, row, column)).collect()).collect();
+    let mut inverse: Vec<Vec<f64>> = (0..order).map(|row| (0..order).map(|column| if row == column { 1.0 } else { 0.0 }).collect()).collect();
+    for column in 0..order {
+        let pivot = (column..order).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+        if a[pivot][column].abs() < EPSILON {
+            return None;
+        }
+        a.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let scale = a[column][column];
+        for k in 0..order {
+            a[column][k] /= scale;
+            inverse[column][k] /= scale;
+        }
+        for row in (0..order).filter(|&row| row != column) {
+            let factor = a[row][column];
+            for k in 0..order {
+                a[row][k] -= factor * a[column][k];
+                inverse[row][k] -= factor * inverse[column][k];
+            }
+        }
+    }
+    Some(matrix(order, (0..order * order).map(|i| inverse[i % order][i / order]).collect()))
+}
+
+pub fn dot(a: Linear, b: Linear) -> Option<f64> {
+    if !a.is_vector() {
+        return None;
+    }
+    let product = a.zip(b, |x, y| x * y)?;
+    Some(product.components().iter().sum())
+}
+
+/// The Vec3 at right angles to both; for Vec2s, the z of that, which is positive when `b` is
+/// counter-clockwise from `a`
+pub fn cross(a: Linear, b: Linear) -> Option<Value> {
+    match (a, b) {
+        (Linear::Vec2([ax, ay]), Linear::Vec2([bx, by])) => Some(Value::Float(ax * by - ay * bx)),
+        (Linear::Vec3([ax, ay, az]), Linear::Vec3([bx, by, bz])) => {
+            Some(Linear::Vec3([ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx]).to_value())
+        }
+        _ => None,
+    }
+}
+
+pub fn length(v: Linear) -> Option<f64> {
+    dot(v, v).map(f64::sqrt)
+}
+
+/// The same direction with length 1; the zero vector stays zero rather than turning into NaN
+pub fn normalized(v: Linear) -> Option<Linear> {
+    let length = length(v)?;
+    Some(if length < EPSILON { v } else { v.map(|x| x / length) })
+}
+
+/// A 2D turn counter-clockwise by `angle` radians, as a Mat3
+pub fn rotation_2d(angle: f64) -> Linear {
+    let (sin, cos) = angle.sin_cos();
+    Linear::Mat3([cos, sin, 0.0, -sin, cos, 0.0, 0.0, 0.0, 1.0])
+}
+
+/// A 3D turn by `angle` radians around `axis`, as a Mat4
+pub fn rotation_3d(angle: f64, axis: [f64; 3]) -> Option<Linear> {
+    let Linear::Vec3([x, y, z]) = normalized(Linear::Vec3(axis))? else {
+        return None;
+    };
+    if x == 0.0 && y == 0.0 && z == 0.0 {
+        return None;
+    }
+    let (sin, cos) = angle.sin_cos();
+    let t = 1.0 - cos;
+    Some(Linear::Mat4([
+        t * x * x + cos, t * x * y + sin * z, t * x * z - sin * y, 0.0,
+        t * x * y - sin * z, t * y * y + cos, t * y * z + sin * x, 0.0,
+        t * x * z + sin * y, t * y * z - sin * x, t * z * z + cos, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ]))
+}
+
+/// A Mat3 that moves 2D points by `offset`, or a Mat4 for 3D ones
+pub fn translation(offset: &[f64]) -> Option<Linear> {
+    let order = offset.len() + 1;
+    if !(3..=4).contains(&order) {
+        return None;
+    }
+    let mut entries = if order == 3 { IDENTITY3.to_vec() } else { IDENTITY4.to_vec() };
+    for (row, value) in offset.iter().enumerate() {
+        entries[(order - 1) * order + row] = *value;
+    }
+    Some(matrix(order, entries))
+}
+
+/// A Mat3 that stretches 2D points by `factors`, or a Mat4 for 3D ones
+pub fn scaling(factors: &[f64]) -> Option<Linear> {
+    let order = factors.len() + 1;
+    if !(3..=4).contains(&order) {
+        return None;
+    }
+    let mut entries = if order == 3 { IDENTITY3.to_vec() } else { IDENTITY4.to_vec() };
+    for (index, value) in factors.iter().enumerate() {
+        entries[index * order + index] = *value;
+    }
+    Some(matrix(order, entries))
+}
+
+/// `+`, `-`, `*`, `/`, `==` and `!=` on vectors and matrices. Vectors add and subtract their own
+/// kind, scale by numbers and multiply component by component; matrices multiply each other and
+/// transform vectors. None when neither side is a vector or matrix
+pub fn binary_op(left: &Value, op: &BinaryOperator, right: &Value) -> Option<crate::Result<Value>> {
+    let (a, b) = (Linear::from_value(left), Linear::from_value(right));
+    if a.is_none() && b.is_none() {
+        return None;
+    }
+    let result = match (op, a, b) {
+        (BinaryOperator::Add, Some(a), Some(b)) => a.zip(b, |x, y| x + y).map(Linear::to_value),
+        (BinaryOperator::Subtract, Some(a), Some(b)) => a.zip(b, |x, y| x - y).map(Linear::to_value),
+        (BinaryOperator::Multiply, Some(a), None) => right.as_number().map(|s| a.map(|x| x * s).to_value()),
+        (BinaryOperator::Multiply, None, Some(b)) => left.as_number().map(|s| b.map(|x| x * s).to_value()),
+        (BinaryOperator::Multiply, Some(a), Some(b)) if a.is_vector() => a.zip(b, |x, y| x * y).map(Linear::to_value),
+        (BinaryOperator::Multiply, Some(a), Some(b)) => match (a.order(), b.order()) {
+            (Some(order), Some(other)) if order == other => Some(matrix(order, matrix_product(a.components(), b.components(), order)).to_value()),
+            (Some(order), None) => transform(a.components(), order, b.components()).and_then(vector).map(Linear::to_value),
+            _ => None,
+        },
+        (BinaryOperator::Divide, Some(a), None) => match right.as_number() {
+            Some(0.0) => return Some(Err(crate::errors::synthesis_error(
+                crate::errors::ErrorKind::InvalidExpression,
+                format!("📐 Can't divide a {} by zero", a.name())
+            )
+            .with_suggestion("Check the length first, or use Math.normalize() for a direction"))),
+            Some(s) => Some(a.map(|x| x / s).to_value()),
+            None => None,
+        },
+        (BinaryOperator::Equal | BinaryOperator::NotEqual, a, b) => {
+            let same = match (a, b) {
+                (Some(a), Some(b)) => a.zip(b, |x, y| x - y).is_some_and(|difference| difference.components().iter().all(|d| d.abs() < EPSILON)),
+                _ => false,
+            };
+            Some(Value::Boolean(same == (*op == BinaryOperator::Equal)))
+        }
+        _ => None,
+    };
+    let describe = |value: &Value, linear: Option<Linear>| linear.map(|l| l.name().to_string()).unwrap_or_else(|| value.type_name().to_string());
+    Some(result.ok_or_else(|| crate::errors::synthesis_error(
+        crate::errors::ErrorKind::TypeMismatch,
+        format!("📐 Can't use {} on {} and {}", symbol(op), describe(left, a), describe(right, b))
+    )
+    .with_suggestion("Vectors add to vectors of the same size and scale by numbers: a + b, v * 2")
+    .with_suggestion("Matrices multiply matrices of the same size, or transform vectors: m * v")))
+}
+
+fn symbol(op: &BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::LessThan | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThan | BinaryOperator::GreaterThanOrEqual => "a comparison",
+        _ => "that operator",
+    }
+}
+
+/// `Vec2` and `Vec3` as struct definitions with zero defaults, so `Vec3 { y: 1 }` builds one
+/// when the script hasn't defined its own
+pub fn builtin_struct(name: &str) -> Option<Arc<StructDef>> {
+    let size = match name {
+        "Vec2" => 2,
+        "Vec3" => 3,
+        _ => return None,
+    };
+    let fields = VECTOR_FIELDS[..size].iter().map(|field| Field {
+        name: field.to_string(),
+        type_annotation: TypeAnnotation::Simple("Float".to_string()),
+        default_value: Some(Expression::Literal(Literal::Float(0.0))),
+    }).collect();
+    Some(Arc::new(StructDef { name: name.to_string(), fields, methods: Vec::new() }))
+}
+
+/// Vectors among `args` as their separate components, so `Graphics.circle(position, 20)` reads
+/// like `Graphics.circle(position.x, position.y, 20)`
+pub fn spread_vectors(args: Vec<Value>) -> Vec<Value> {
+    args.into_iter()
+        .flat_map(|arg| match Linear::from_value(&arg) {
+            Some(v) if v.is_vector() => v.components().iter().map(|value| Value::Float(*value)).collect(),
+            _ => vec![arg],
+        })
+        .collect()
+}
@@ -0,0 +1,304 @@
+/// Community extensions compiled to WebAssembly, run in a sandbox
+///
+/// Native plugins are fast but can do anything the process can, so they're only for code you'd
+/// compile yourself. A `.wasm` extension can only see what Synthesis hands it: the numbers it's
+/// called with, a block of samples to read and write, and a few math functions. It can't open
+/// files, sockets or devices, every call has a fuel budget so a stuck loop can't hang the show,
+/// and its memory is capped. That makes a shared effect safe to install from a stranger.
+///
+/// Each exported function becomes `Name.function(...)`, where `Name` is the file's name with a
+/// capital letter (`bitcrush.wasm` gives `Bitcrush`). Numbers go in as parameters, and a stream
+/// passes its newest sample. When the first argument is a list, its samples are the block: the
+/// extension reads them with `input(i)` and writes results with `output(i, value)`, and the
+/// call returns the written samples as a list. Anything it imports has to come from the
+/// `synthesis` host module, so modules built for WASI's filesystem and clock are turned away.
+use crate::runtime::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// The module extensions import the host API from
+pub const HOST_MODULE: &str = "synthesis";
+/// Everything the host API offers; any other import keeps an extension from loading
+pub const HOST_FUNCTIONS: &[&str] = &[
+    "input_len", "input", "output",
+    "sin", "cos", "tan", "exp", "log", "pow", "atan2", "random",
+];
+/// Instructions one call may run before it's stopped, roughly a few milliseconds' worth
+pub const DEFAULT_FUEL: u64 = 10_000_000;
+/// Linear memory an extension may grow to
+pub const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// The samples one call reads and writes
+#[derive(Debug, Default)]
+pub struct Block {
+    pub input: Vec<f32>,
+    pub output: Vec<f32>,
+}
+
+fn extension_error(message: String) -> crate::errors::SynthesisError {
+    crate::errors::synthesis_error(crate::errors::ErrorKind::UnknownModule, message)
+        .with_docs("https://synthesis-lang.org/docs/extensions")
+}
+
+/// The block and the numbers a call passes; streams have already become their newest sample
+fn call_arguments(extension: &str, function: &str, args: &[Value]) -> crate::Result<(Block, Vec<f64>)> {
+    let mut block = Block::default();
+    let mut args = args;
+    if let Some(Value::Array(samples)) = args.first() {
+        block.input = samples.iter().map(|sample| sample.as_number().unwrap_or(0.0) as f32).collect();
+        args = &args[1..];
+    }
+    let numbers = args.iter().map(|arg| match arg {
+        Value::Boolean(flag) => Ok(if *flag { 1.0 } else { 0.0 }),
+        other => other.as_number().ok_or_else(|| crate::errors::synthesis_error(
+            crate::errors::ErrorKind::TypeMismatch,
+            format!("🧩 {}.{}() takes numbers, and a list of samples first, not {}", extension, function, other.type_name())
+        )
+        .with_suggestion("Extensions take their arguments in order, without names")),
+    }).collect::<crate::Result<Vec<f64>>>()?;
+    Ok((block, numbers))
+}
+
+#[cfg(feature = "wasmi")]
+mod backend {
+    use super::{extension_error, Block, DEFAULT_FUEL, HOST_FUNCTIONS, HOST_MODULE, MAX_MEMORY_BYTES};
+    use crate::runtime::Value;
+    use wasmi::{Caller, Config, Engine, Extern, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Val, ValType};
+
+    impl Block {
+        fn read(&self, index: i32) -> f32 {
+            usize::try_from(index).ok().and_then(|index| self.input.get(index)).copied().unwrap_or(0.0)
+        }
+
+        /// Writes past the input's length are dropped, so an extension can't grow the output forever
+        fn write(&mut self, index: i32, value: f32) {
+            let Ok(index) = usize::try_from(index) else { return };
+            if index >= self.input.len() {
+                return;
+            }
+            if self.output.len() <= index {
+                self.output.resize(index + 1, 0.0);
+            }
+            self.output[index] = value;
+        }
+    }
+
+    /// Refuse any import the host API doesn't provide, naming it
+    fn check_import(extension: &str, module: &str, name: &str) -> crate::Result<()> {
+        if module == HOST_MODULE && HOST_FUNCTIONS.contains(&name) {
+            return Ok(());
+        }
+        Err(extension_error(format!("🧩 The extension {} asks for {}.{}, which extensions can't have", extension, module, name))
+            .with_suggestion(format!("Extensions may only import from \"{}\": {}", HOST_MODULE, HOST_FUNCTIONS.join(", ")))
+            .with_suggestion("Build it without WASI, for the wasm32-unknown-unknown target"))
+    }
+
+    struct HostState {
+        block: Block,
+        limits: StoreLimits,
+    }
+
+    pub struct Sandbox {
+        store: Store<HostState>,
+        instance: Instance,
+    }
+
+    fn wasm_error(extension: &str, error: impl std::fmt::Display) -> crate::errors::SynthesisError {
+        extension_error(format!("🧩 The extension {} failed: {}", extension, error))
+    }
+
+    impl Sandbox {
+        pub fn new(extension: &str, bytes: &[u8]) -> crate::Result<Self> {
+            let mut config = Config::default();
+            config.consume_fuel(true);
+            let engine = Engine::new(&config);
+            let module = Module::new(&engine, bytes).map_err(|error| wasm_error(extension, error))?;
+            for import in module.imports() {
+                check_import(extension, import.module(), import.name())?;
+            }
+
+            let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).instances(1).build();
+            let mut store = Store::new(&engine, HostState { block: Block::default(), limits });
+            store.limiter(|state| &mut state.limits);
+            store.set_fuel(DEFAULT_FUEL).map_err(|error| wasm_error(extension, error))?;
+
+            let mut linker = <Linker<HostState>>::new(&engine);
+            let host = |error| wasm_error(extension, error);
+            linker.func_wrap(HOST_MODULE, "input_len", |caller: Caller<'_, HostState>| caller.data().block.input.len() as i32).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "input", |caller: Caller<'_, HostState>, index: i32| caller.data().block.read(index)).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "output", |mut caller: Caller<'_, HostState>, index: i32, value: f32| {
+                caller.data_mut().block.write(index, value)
+            }).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "sin", f64::sin).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "cos", f64::cos).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "tan", f64::tan).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "exp", f64::exp).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "log", f64::ln).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "pow", f64::powf).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "atan2", f64::atan2).map_err(host)?;
+            linker.func_wrap(HOST_MODULE, "random", rand::random::<f64>).map_err(host)?;
+
+            let instance = linker.instantiate(&mut store, &module)
+                .and_then(|pre| pre.start(&mut store))
+                .map_err(|error| wasm_error(extension, error))?;
+            Ok(Self { store, instance })
+        }
+
+        /// Exported functions that take and return plain numbers
+        pub fn functions(&self) -> Vec<String> {
+            self.instance.exports(&self.store)
+                .filter_map(|export| {
+                    let name = export.name().to_string();
+                    let Extern::Func(func) = export.into_extern() else { return None };
+                    let ty = func.ty(&self.store);
+                    let numeric = |ty: &ValType| matches!(ty, ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64);
+                    (ty.params().iter().all(numeric) && ty.results().iter().all(numeric) && ty.results().len() <= 1).then_some(name)
+                })
+                .collect()
+        }
+
+        pub fn call(&mut self, extension: &str, function: &str, block: Block, numbers: &[f64]) -> crate::Result<(Option<Value>, Block)> {
+            let func = self.instance.get_func(&self.store, function).ok_or_else(|| crate::errors::synthesis_error(
+                crate::errors::ErrorKind::UnknownFunction,
+                format!("🧩 The extension {} has no function {}()", extension, function)
+            ))?;
+            let ty = func.ty(&self.store);
+            if numbers.len() != ty.params().len() {
+                return Err(crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::TypeMismatch,
+                    format!("🧩 {}.{}() takes {} number(s), not {}", extension, function, ty.params().len(), numbers.len())
+                ));
+            }
+            let params: Vec<Val> = ty.params().iter().zip(numbers).map(|(ty, number)| match ty {
+                ValType::I32 => Val::I32(*number as i32),
+                ValType::I64 => Val::I64(*number as i64),
+                ValType::F32 => Val::F32((*number as f32).into()),
+                _ => Val::F64((*number).into()),
+            }).collect();
+            let mut results: Vec<Val> = ty.results().iter().map(|ty| Val::default(*ty)).collect();
+
+            self.store.data_mut().block = block;
+            self.store.set_fuel(DEFAULT_FUEL).map_err(|error| wasm_error(extension, error))?;
+            let outcome = func.call(&mut self.store, &params, &mut results);
+            let block = std::mem::take(&mut self.store.data_mut().block);
+            outcome.map_err(|error| match self.store.get_fuel() {
+                Ok(0) => crate::errors::synthesis_error(
+                    crate::errors::ErrorKind::PerformanceConstraintViolation,
+                    format!("🧩 {}.{}() ran too long and was stopped", extension, function)
+                )
+                .with_suggestion("The extension may be stuck in a loop; give it a smaller block or ask its author"),
+                _ => wasm_error(extension, error),
+            })?;
+
+            let result = results.first().map(|result| match result {
+                Val::I32(n) => Value::Integer(*n as i64),
+                Val::I64(n) => Value::Integer(*n),
+                Val::F32(f) => Value::Float(f32::from(*f) as f64),
+                Val::F64(f) => Value::Float(f64::from(*f)),
+                _ => Value::Null,
+            });
+            Ok((result, block))
+        }
+    }
+}
+
+#[cfg(not(feature = "wasmi"))]
+mod backend {
+    use super::{extension_error, Block};
+    use crate::runtime::Value;
+
+    pub struct Sandbox;
+
+    impl Sandbox {
+        pub fn new(extension: &str, _bytes: &[u8]) -> crate::Result<Self> {
+            Err(extension_error(format!("🧩 This build of Synthesis can't run WebAssembly extensions like {}", extension))
+                .with_suggestion("Rebuild with --features wasmi"))
+        }
+
+        pub fn functions(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub fn call(&mut self, _extension: &str, _function: &str, block: Block, _numbers: &[f64]) -> crate::Result<(Option<Value>, Block)> {
+            Ok((None, block))
+        }
+    }
+}
+
+use backend::Sandbox;
+
+pub struct WasmExtension {
+    pub name: String,
+    pub functions: Vec<String>,
+    sandbox: Sandbox,
+}
+
+/// The extensions loaded so far, by the module name scripts use
+#[derive(Default)]
+pub struct WasmExtensions {
+    extensions: HashMap<String, WasmExtension>,
+}
+
+impl WasmExtensions {
+    /// Compile and sandbox an extension; returns the functions it offers
+    pub fn load(&mut self, name: &str, bytes: &[u8]) -> crate::Result<Vec<String>> {
+        if self.extensions.contains_key(name) {
+            return Err(extension_error(format!("🧩 An extension called {} is already loaded", name))
+                .with_suggestion("Rename one of the .wasm files"));
+        }
+        let sandbox = Sandbox::new(name, bytes)?;
+        let functions = sandbox.functions();
+        self.extensions.insert(name.to_string(), WasmExtension { name: name.to_string(), functions: functions.clone(), sandbox });
+        Ok(functions)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.extensions.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&WasmExtension> {
+        self.extensions.get(name)
+    }
+
+    pub fn unload(&mut self, name: &str) -> bool {
+        self.extensions.remove(name).is_some()
+    }
+
+    /// `Name.function(...)`: the written samples if it wrote any, otherwise what it returned
+    pub fn call(&mut self, name: &str, function: &str, args: &[Value]) -> crate::Result<Value> {
+        let Some(extension) = self.extensions.get_mut(name) else {
+            return Err(extension_error(format!("🧩 There's no extension called {}", name)));
+        };
+        let (block, numbers) = call_arguments(name, function, args)?;
+        let (result, block) = extension.sandbox.call(name, function, block, &numbers)?;
+        if !block.output.is_empty() {
+            return Ok(Value::Array(block.output.into_iter().map(|sample| Value::Float(sample as f64)).collect()));
+        }
+        Ok(result.unwrap_or(Value::Null))
+    }
+}
+
+static EXTENSIONS: OnceLock<Mutex<WasmExtensions>> = OnceLock::new();
+
+pub fn wasm_extensions() -> &'static Mutex<WasmExtensions> {
+    EXTENSIONS.get_or_init(|| Mutex::new(WasmExtensions::default()))
+}
+
+/// The module name a file gives: `bitcrush.wasm` is `Bitcrush`
+pub fn extension_name(path: &Path) -> String {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut chars = stem.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => stem,
+    }
+}
+
+/// Load a `.wasm` extension for every interpreter; returns its module name and functions
+pub fn load_extension(path: &Path) -> crate::Result<(String, Vec<String>)> {
+    let bytes = std::fs::read(path).map_err(|error| extension_error(format!("🧩 Couldn't read the extension {}: {}", path.display(), error)))?;
+    let name = extension_name(path);
+    let functions = wasm_extensions().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).load(&name, &bytes)?;
+    Ok((name, functions))
+}
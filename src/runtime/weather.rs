@@ -0,0 +1,134 @@
+/// Live weather from OpenWeatherMap for `Environment.weather()`
+///
+/// Reports are fetched on a background thread at most every ten minutes per place, since the
+/// weather changes slowly and the free API allows about a thousand calls a day; the frame loop
+/// only ever reads the last report.
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const WEATHER_HOST: &str = "api.openweathermap.org";
+/// Where the key comes from when the script doesn't pass one
+pub const WEATHER_KEY_VARIABLE: &str = "OPENWEATHER_API_KEY";
+pub const WEATHER_REFRESH: Duration = Duration::from_secs(10 * 60);
+/// How long to wait before trying again after a failed fetch
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherReport {
+    /// Degrees Celsius
+    pub temperature: f64,
+    pub feels_like: f64,
+    /// Percent
+    pub humidity: f64,
+    /// Hectopascals
+    pub pressure: f64,
+    /// Metres per second
+    pub wind_speed: f64,
+    /// Degrees clockwise from north, where the wind blows from
+    pub wind_direction: f64,
+    /// Percent of the sky covered
+    pub clouds: f64,
+    /// Millimetres over the last hour
+    pub rain: f64,
+    /// Short description like "Rain" or "Clear"
+    pub condition: String,
+}
+
+impl WeatherReport {
+    /// Read an OpenWeatherMap current-weather response (metric units)
+    pub fn from_openweather(json: &serde_json::Value) -> Option<Self> {
+        let number = |section: &str, field: &str| json.get(section).and_then(|s| s.get(field)).and_then(|v| v.as_f64());
+        Some(Self {
+            temperature: number("main", "temp")?,
+            feels_like: number("main", "feels_like").unwrap_or_default(),
+            humidity: number("main", "humidity").unwrap_or_default(),
+            pressure: number("main", "pressure").unwrap_or_default(),
+            wind_speed: number("wind", "speed").unwrap_or_default(),
+            wind_direction: number("wind", "deg").unwrap_or_default(),
+            clouds: number("clouds", "all").unwrap_or_default(),
+            rain: number("rain", "1h").unwrap_or_default(),
+            condition: json.get("weather")
+                .and_then(|w| w.get(0))
+                .and_then(|w| w.get("main"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown")
+                .to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Place {
+    report: Option<(WeatherReport, Instant)>,
+    /// When the last fetch started, so a slow or failing API isn't asked every frame
+    requested: Option<Instant>,
+    /// Why the last fetch failed; failures are retried sooner than the usual refresh
+    error: Option<String>,
+}
+
+// Places by rounded "lat,lon", written by fetch threads
+static WEATHER: OnceLock<Mutex<HashMap<String, Place>>> = OnceLock::new();
+
+fn places() -> &'static Mutex<HashMap<String, Place>> {
+    WEATHER.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The latest report for a place with its age, starting a fetch if there's none or it's stale
+pub fn current_weather(latitude: f64, longitude: f64, key: &str) -> Option<(WeatherReport, Duration)> {
+    // About a kilometre apart is the same weather
+    let place = format!("{:.2},{:.2}", latitude, longitude);
+    let mut known = places().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let entry = known.entry(place.clone()).or_default();
+    let fresh = entry.report.as_ref().is_some_and(|(_, at)| at.elapsed() < WEATHER_REFRESH);
+    let waiting = entry.requested.is_some_and(|at| at.elapsed() < if entry.error.is_some() { RETRY_AFTER } else { WEATHER_REFRESH });
+    if !fresh && !waiting {
+        entry.requested = Some(Instant::now());
+        let key = key.to_string();
+        let spawned = std::thread::Builder::new()
+            .name("synthesis-weather".to_string())
+            .spawn(move || {
+                let result = fetch_weather(latitude, longitude, &key);
+                let mut known = places().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let entry = known.entry(place).or_default();
+                match result {
+                    Ok(report) => {
+                        entry.report = Some((report, Instant::now()));
+                        entry.error = None;
+                    }
+                    Err(error) => {
+                        tracing::warn!(target: "environment", %error, "🌦️ weather fetch failed");
+                        entry.error = Some(error);
+                    }
+                }
+            });
+        if let Err(error) = spawned {
+            entry.error = Some(error.to_string());
+        }
+    }
+    entry.report.as_ref().map(|(report, at)| (report.clone(), at.elapsed()))
+}
+
+fn fetch_weather(latitude: f64, longitude: f64, key: &str) -> Result<WeatherReport, String> {
+    let mut stream = TcpStream::connect((WEATHER_HOST, 80)).map_err(|error| format!("couldn't reach {}: {}", WEATHER_HOST, error))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10))).map_err(|error| error.to_string())?;
+    write!(
+        stream,
+        "GET /data/2.5/weather?lat={}&lon={}&units=metric&appid={} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        latitude, longitude, key, WEATHER_HOST
+    )
+    .map_err(|error| error.to_string())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|error| error.to_string())?;
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or("the weather service sent an empty reply")?;
+    let status = head.split_whitespace().nth(1).unwrap_or("");
+    let json: serde_json::Value = serde_json::from_str(body).map_err(|error| format!("unreadable weather reply: {}", error))?;
+    if status != "200" {
+        let message = json.get("message").and_then(|v| v.as_str()).unwrap_or("no reason given");
+        return Err(format!("the weather service said {}: {}", status, message));
+    }
+    WeatherReport::from_openweather(&json).ok_or_else(|| "the weather reply had no temperature".to_string())
+}
@@ -0,0 +1,255 @@
+/// Script-defined web endpoints: `Web.serve(8080, { level: "level" })` publishes script
+/// values as JSON, so a piece can run its own audience-facing page without external glue.
+///
+/// ```text
+/// level: "level"                                 GET /level returns the value of `level`
+/// scene: ["hue", "speed"]                        GET /scene returns {"hue": ..., "speed": ...}
+/// "/api/vote": { get: "votes", post: "choice" }  POST sets `choice` from a JSON body
+/// ```
+///
+/// A route that sets several variables takes a JSON object of them; one that sets a single
+/// variable takes the bare value or `{"value": ...}`. Unlike the remote control API these
+/// endpoints are open to anyone who can reach the port, so only listed variables are exposed.
+use crate::runtime::remote_control::{json_to_value, percent_decode, value_to_json};
+use crate::runtime::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const MAX_BODY: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebRoute {
+    /// Variables a GET returns: one gives its bare value, several an object of them
+    pub get: Vec<String>,
+    /// Variables a POST may set
+    pub post: Vec<String>,
+}
+
+impl WebRoute {
+    /// A variable name or list of names is read-only; `{ get: ..., post: ... }` says which is which
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Object(fields) => {
+                let route = Self {
+                    get: fields.get("get").map(names).unwrap_or_default(),
+                    post: fields.get("post").map(names).unwrap_or_default(),
+                };
+                (!route.get.is_empty() || !route.post.is_empty()).then_some(route)
+            }
+            Value::String(_) | Value::Array(_) => {
+                let get = names(value);
+                (!get.is_empty()).then_some(Self { get, post: Vec::new() })
+            }
+            _ => None,
+        }
+    }
+
+    fn respond_get(&self, values: &BTreeMap<String, Value>) -> serde_json::Value {
+        let value = |name: &String| values.get(name).map(value_to_json).unwrap_or(serde_json::Value::Null);
+        match self.get.as_slice() {
+            [name] => value(name),
+            names => serde_json::Value::Object(names.iter().map(|name| (name.clone(), value(name))).collect()),
+        }
+    }
+
+    /// The variable writes a POST body asks for, checked against what the route allows
+    fn writes(&self, body: &serde_json::Value) -> Result<Vec<(String, Value)>, String> {
+        match self.post.as_slice() {
+            [name] => Ok(vec![(name.clone(), json_to_value(body.get("value").unwrap_or(body)))]),
+            allowed => {
+                let fields = body.as_object().ok_or_else(|| format!("Expected an object with any of {:?}", allowed))?;
+                fields.iter()
+                    .map(|(name, value)| if allowed.contains(name) {
+                        Ok((name.clone(), json_to_value(value)))
+                    } else {
+                        Err(format!("'{}' can't be set here; this route sets {:?}", name, allowed))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn names(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(name) => vec![name.clone()],
+        Value::Array(items) => items.iter()
+            .filter_map(|item| match item {
+                Value::String(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct WebSite {
+    routes: BTreeMap<String, WebRoute>,
+    /// HTML served at `/` when no route claims it
+    page: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct WebState {
+    /// By port
+    sites: BTreeMap<u16, WebSite>,
+    /// Latest values of every variable a route reads
+    values: BTreeMap<String, Value>,
+    pending_writes: Vec<(String, Value)>,
+}
+
+// Shared between the script's frame loop and the HTTP threads
+static WEB_STATE: OnceLock<Mutex<WebState>> = OnceLock::new();
+
+fn web_state() -> &'static Mutex<WebState> {
+    WEB_STATE.get_or_init(|| Mutex::new(WebState::default()))
+}
+
+/// Serve `routes` on `port`, binding it the first time; serving the same port again (as a
+/// reloaded script does) swaps in the new routes. Returns the address actually bound, since
+/// port 0 picks a free one
+pub fn serve_web_routes(port: u16, routes: BTreeMap<String, WebRoute>, page: Option<String>) -> std::io::Result<SocketAddr> {
+    let mut state = web_state().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let address = if port != 0 && state.sites.contains_key(&port) {
+        SocketAddr::from(([0, 0, 0, 0], port))
+    } else {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let address = listener.local_addr()?;
+        let bound = address.port();
+        std::thread::Builder::new()
+            .name(format!("synthesis-web-{}", bound))
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let _ = std::thread::Builder::new()
+                        .name("synthesis-web-client".to_string())
+                        .spawn(move || {
+                            if let Err(error) = handle_connection(stream, bound) {
+                                tracing::debug!(target: "web", %error, "web connection closed");
+                            }
+                        });
+                }
+            })?;
+        tracing::info!(target: "web", %address, routes = routes.len(), "🌐 serving script routes");
+        address
+    };
+    state.sites.insert(address.port(), WebSite { routes, page });
+    Ok(address)
+}
+
+/// Update what GET routes return; called by the interpreter every frame
+pub fn publish_web_values(variables: &HashMap<String, Value>) {
+    let mut state = web_state().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if state.sites.is_empty() {
+        return;
+    }
+    let wanted: Vec<String> = state.sites.values()
+        .flat_map(|site| site.routes.values())
+        .flat_map(|route| route.get.iter().cloned())
+        .collect();
+    state.values = wanted.into_iter()
+        .map(|name| {
+            let value = variables.get(&name).cloned().unwrap_or(Value::Null);
+            (name, value)
+        })
+        .collect();
+}
+
+/// Variables POSTed since the last frame, applied by the interpreter
+pub fn take_web_writes() -> Vec<(String, Value)> {
+    std::mem::take(&mut web_state().lock().unwrap_or_else(|poisoned| poisoned.into_inner()).pending_writes)
+}
+
+fn handle_connection(stream: TcpStream, port: u16) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut request_line = request_line.split_whitespace();
+    let method = request_line.next().unwrap_or("").to_string();
+    let target = request_line.next().unwrap_or("/");
+    let path = percent_decode(target.split_once('?').map_or(target, |(path, _)| path));
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().unwrap_or(0);
+            }
+        }
+    }
+    let mut stream = &stream;
+    if length > MAX_BODY {
+        return respond(&mut stream, "413 Payload Too Large", &serde_json::json!({"error": "body too large"}));
+    }
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body)?;
+
+    if method == "OPTIONS" {
+        return write_response(&mut stream, "204 No Content", "application/json", "");
+    }
+    match route(port, &method, &path, &body) {
+        Response::Json(status, body) => respond(&mut stream, status, &body),
+        Response::Page(page) => write_response(&mut stream, "200 OK", "text/html; charset=utf-8", &page),
+    }
+}
+
+enum Response {
+    Json(&'static str, serde_json::Value),
+    Page(String),
+}
+
+fn route(port: u16, method: &str, path: &str, body: &[u8]) -> Response {
+    let mut state = web_state().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(site) = state.sites.get(&port) else {
+        return Response::Json("503 Service Unavailable", serde_json::json!({"error": "nothing is served here any more"}));
+    };
+    let path = format!("/{}", path.trim_matches('/'));
+    let Some(route) = site.routes.get(&path).cloned() else {
+        if let (Some(page), "GET", "/") = (&site.page, method, path.as_str()) {
+            return Response::Page(page.clone());
+        }
+        let routes: Vec<&String> = site.routes.keys().collect();
+        return Response::Json("404 Not Found", serde_json::json!({"error": "unknown route", "routes": routes}));
+    };
+
+    match method {
+        "GET" if !route.get.is_empty() => Response::Json("200 OK", route.respond_get(&state.values)),
+        "POST" | "PUT" if !route.post.is_empty() => {
+            let json = match serde_json::from_slice::<serde_json::Value>(body) {
+                Ok(json) => json,
+                Err(error) => return Response::Json("400 Bad Request", serde_json::json!({"error": format!("body must be JSON: {}", error)})),
+            };
+            match route.writes(&json) {
+                Ok(writes) => {
+                    let set: serde_json::Map<_, _> = writes.iter().map(|(name, value)| (name.clone(), value_to_json(value))).collect();
+                    state.pending_writes.extend(writes);
+                    Response::Json("200 OK", serde_json::json!({"ok": true, "set": set}))
+                }
+                Err(error) => Response::Json("400 Bad Request", serde_json::json!({"ok": false, "error": error})),
+            }
+        }
+        _ => Response::Json("405 Method Not Allowed", serde_json::json!({"error": format!("{} isn't allowed on {}", method, path)})),
+    }
+}
+
+fn respond(stream: &mut &TcpStream, status: &str, body: &serde_json::Value) -> std::io::Result<()> {
+    write_response(stream, status, "application/json", &body.to_string())
+}
+
+fn write_response(stream: &mut &TcpStream, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: Content-Type\r\n\
+         Access-Control-Allow-Methods: GET, PUT, POST, OPTIONS\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    )
+}